@@ -38,6 +38,8 @@ class BcsDeserializer : public BinaryDeserializer<BcsDeserializer> {
   public:
     BcsDeserializer(std::vector<uint8_t> bytes)
         : Parent(std::move(bytes), BCS_MAX_CONTAINER_DEPTH) {}
+    BcsDeserializer(byte_span bytes)
+        : Parent(bytes, BCS_MAX_CONTAINER_DEPTH) {}
 
     size_t deserialize_len();
     uint32_t deserialize_variant_index();
@@ -73,7 +75,7 @@ inline void BcsSerializer::sort_last_entries(std::vector<size_t> offsets) {
     offsets.push_back(bytes_.size());
 
     std::vector<std::vector<uint8_t>> slices;
-    for (auto i = 1; i < offsets.size(); i++) {
+    for (size_t i = 1; i < offsets.size(); i++) {
         auto start = bytes_.cbegin() + offsets[i - 1];
         auto end = bytes_.cbegin() + offsets[i];
         slices.emplace_back(start, end);
@@ -127,10 +129,10 @@ inline uint32_t BcsDeserializer::deserialize_variant_index() {
 
 inline void BcsDeserializer::check_that_key_slices_are_increasing(
     std::tuple<size_t, size_t> key1, std::tuple<size_t, size_t> key2) {
-    if (!std::lexicographical_compare(bytes_.cbegin() + std::get<0>(key1),
-                                      bytes_.cbegin() + std::get<1>(key1),
-                                      bytes_.cbegin() + std::get<0>(key2),
-                                      bytes_.cbegin() + std::get<1>(key2))) {
+    if (!std::lexicographical_compare(input_.begin() + std::get<0>(key1),
+                                      input_.begin() + std::get<1>(key1),
+                                      input_.begin() + std::get<0>(key2),
+                                      input_.begin() + std::get<1>(key2))) {
         throw serde::deserialization_error(
             "Error while decoding map: keys are not serialized in the "
             "expected order");
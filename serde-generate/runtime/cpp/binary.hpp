@@ -11,6 +11,16 @@
 
 namespace serde {
 
+// Appends an unsigned integer in little-endian order, regardless of the byte
+// order of the host platform.
+template <typename T>
+inline void write_le(std::vector<uint8_t> &bytes, T value) {
+    static_assert(std::is_unsigned<T>::value, "Expecting an unsigned integer");
+    for (size_t i = 0; i < sizeof(T); i++) {
+        bytes.push_back((uint8_t)(value >> (8 * i)));
+    }
+}
+
 template <class S>
 class BinarySerializer {
   protected:
@@ -55,13 +65,23 @@ class BinaryDeserializer {
     size_t container_depth_budget_;
 
   protected:
+    // Owned input, if any.
     std::vector<uint8_t> bytes_;
+    // Bytes being read: either `bytes_` or borrowed memory (e.g. a memory-mapped file).
+    byte_span input_;
     uint8_t read_byte();
+    template <typename T>
+    T read_le();
 
   public:
     BinaryDeserializer(std::vector<uint8_t> bytes, size_t max_container_depth)
         : pos_(0), container_depth_budget_(max_container_depth),
-          bytes_(std::move(bytes)) {}
+          bytes_(std::move(bytes)), input_(bytes_) {}
+
+    // Read borrowed bytes without copying them. The bytes must outlive the deserializer.
+    BinaryDeserializer(byte_span input, size_t max_container_depth)
+        : pos_(0), container_depth_budget_(max_container_depth), bytes_(),
+          input_(input) {}
 
     std::string deserialize_str();
 
@@ -128,28 +148,17 @@ void BinarySerializer<S>::serialize_u8(uint8_t value) {
 
 template <class S>
 void BinarySerializer<S>::serialize_u16(uint16_t value) {
-    bytes_.push_back((uint8_t)value);
-    bytes_.push_back((uint8_t)(value >> 8));
+    write_le(bytes_, value);
 }
 
 template <class S>
 void BinarySerializer<S>::serialize_u32(uint32_t value) {
-    bytes_.push_back((uint8_t)value);
-    bytes_.push_back((uint8_t)(value >> 8));
-    bytes_.push_back((uint8_t)(value >> 16));
-    bytes_.push_back((uint8_t)(value >> 24));
+    write_le(bytes_, value);
 }
 
 template <class S>
 void BinarySerializer<S>::serialize_u64(uint64_t value) {
-    bytes_.push_back((uint8_t)value);
-    bytes_.push_back((uint8_t)(value >> 8));
-    bytes_.push_back((uint8_t)(value >> 16));
-    bytes_.push_back((uint8_t)(value >> 24));
-    bytes_.push_back((uint8_t)(value >> 32));
-    bytes_.push_back((uint8_t)(value >> 40));
-    bytes_.push_back((uint8_t)(value >> 48));
-    bytes_.push_back((uint8_t)(value >> 56));
+    write_le(bytes_, value);
 }
 
 template <class S>
@@ -209,10 +218,23 @@ void BinarySerializer<S>::decrease_container_depth() {
 
 template <class D>
 uint8_t BinaryDeserializer<D>::read_byte() {
-    if (pos_ >= bytes_.size()) {
+    if (pos_ >= input_.size()) {
         throw serde::deserialization_error("Input is not large enough");
     }
-    return bytes_.at(pos_++);
+    return input_[pos_++];
+}
+
+// Reads an unsigned integer in little-endian order, regardless of the byte
+// order of the host platform.
+template <class D>
+template <typename T>
+T BinaryDeserializer<D>::read_le() {
+    static_assert(std::is_unsigned<T>::value, "Expecting an unsigned integer");
+    T val = 0;
+    for (size_t i = 0; i < sizeof(T); i++) {
+        val |= (T)((T)read_byte() << (8 * i));
+    }
+    return val;
 }
 
 inline bool is_valid_utf8(const std::string &input) {
@@ -298,34 +320,17 @@ uint8_t BinaryDeserializer<D>::deserialize_u8() {
 
 template <class D>
 uint16_t BinaryDeserializer<D>::deserialize_u16() {
-    uint16_t val = 0;
-    val |= (uint16_t)read_byte();
-    val |= (uint16_t)read_byte() << 8;
-    return val;
+    return read_le<uint16_t>();
 }
 
 template <class D>
 uint32_t BinaryDeserializer<D>::deserialize_u32() {
-    uint32_t val = 0;
-    val |= (uint32_t)read_byte();
-    val |= (uint32_t)read_byte() << 8;
-    val |= (uint32_t)read_byte() << 16;
-    val |= (uint32_t)read_byte() << 24;
-    return val;
+    return read_le<uint32_t>();
 }
 
 template <class D>
 uint64_t BinaryDeserializer<D>::deserialize_u64() {
-    uint64_t val = 0;
-    val |= (uint64_t)read_byte();
-    val |= (uint64_t)read_byte() << 8;
-    val |= (uint64_t)read_byte() << 16;
-    val |= (uint64_t)read_byte() << 24;
-    val |= (uint64_t)read_byte() << 32;
-    val |= (uint64_t)read_byte() << 40;
-    val |= (uint64_t)read_byte() << 48;
-    val |= (uint64_t)read_byte() << 56;
-    return val;
+    return read_le<uint64_t>();
 }
 
 template <class D>
@@ -4,6 +4,7 @@
 #pragma once
 
 #include <cstdint>
+#include <cstring>
 
 #include "binary.hpp"
 #include "serde.hpp"
@@ -33,6 +34,7 @@ class BincodeDeserializer : public BinaryDeserializer<BincodeDeserializer> {
   public:
     BincodeDeserializer(std::vector<uint8_t> bytes)
         : Parent(std::move(bytes), SIZE_MAX) {}
+    BincodeDeserializer(byte_span bytes) : Parent(bytes, SIZE_MAX) {}
 
     float deserialize_f32();
     double deserialize_f64();
@@ -48,12 +50,18 @@ static_assert(std::numeric_limits<double>::is_iec559);
 static_assert(sizeof(float) == sizeof(uint32_t));
 static_assert(sizeof(double) == sizeof(uint64_t));
 
+// Floats are copied bit-for-bit into integers of the same size, then written in
+// little-endian order like any other integer.
 inline void BincodeSerializer::serialize_f32(float value) {
-    Parent::serialize_u32(*reinterpret_cast<uint32_t *>(&value));
+    uint32_t bits;
+    std::memcpy(&bits, &value, sizeof(bits));
+    Parent::serialize_u32(bits);
 }
 
 inline void BincodeSerializer::serialize_f64(double value) {
-    Parent::serialize_u64(*reinterpret_cast<uint64_t *>(&value));
+    uint64_t bits;
+    std::memcpy(&bits, &value, sizeof(bits));
+    Parent::serialize_u64(bits);
 }
 
 inline void BincodeSerializer::serialize_len(size_t value) {
@@ -68,17 +76,22 @@ inline void BincodeSerializer::serialize_variant_index(uint32_t value) {
 }
 
 inline float BincodeDeserializer::deserialize_f32() {
-    auto value = Parent::deserialize_u32();
-    return *reinterpret_cast<float *>(&value);
+    auto bits = Parent::deserialize_u32();
+    float value;
+    std::memcpy(&value, &bits, sizeof(value));
+    return value;
 }
 
 inline double BincodeDeserializer::deserialize_f64() {
-    auto value = Parent::deserialize_u64();
-    return *reinterpret_cast<double *>(&value);
+    auto bits = Parent::deserialize_u64();
+    double value;
+    std::memcpy(&value, &bits, sizeof(value));
+    return value;
 }
 
 inline size_t BincodeDeserializer::deserialize_len() {
-    auto value = (size_t)Parent::deserialize_u64();
+    // Check the length before narrowing it to `size_t` (32 bits on some targets).
+    auto value = Parent::deserialize_u64();
     if (value > BINCODE_MAX_LENGTH) {
         throw serde::deserialization_error("Length is too large");
     }
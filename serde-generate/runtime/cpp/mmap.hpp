@@ -0,0 +1,71 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#pragma once
+
+#include <cerrno>
+#include <system_error>
+
+#include <fcntl.h>
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include "serde.hpp"
+
+namespace serde {
+
+// Read-only memory mapping of a file (POSIX only). The span returned by
+// `bytes()` is valid as long as the `MappedFile` object is alive.
+class MappedFile {
+    void *data_;
+    size_t size_;
+
+  public:
+    explicit MappedFile(const std::string &path);
+    ~MappedFile();
+
+    MappedFile(const MappedFile &) = delete;
+    MappedFile &operator=(const MappedFile &) = delete;
+
+    byte_span bytes() const {
+        return byte_span(static_cast<const uint8_t *>(data_), size_);
+    }
+};
+
+inline MappedFile::MappedFile(const std::string &path)
+    : data_(nullptr), size_(0) {
+    int fd = ::open(path.c_str(), O_RDONLY);
+    if (fd < 0) {
+        throw std::system_error(errno, std::generic_category(),
+                                "Cannot open " + path);
+    }
+    struct stat info;
+    if (::fstat(fd, &info) < 0) {
+        int error = errno;
+        ::close(fd);
+        throw std::system_error(error, std::generic_category(),
+                                "Cannot read the size of " + path);
+    }
+    size_ = (size_t)info.st_size;
+    // Empty files cannot be mapped.
+    if (size_ > 0) {
+        data_ = ::mmap(nullptr, size_, PROT_READ, MAP_PRIVATE, fd, 0);
+        if (data_ == MAP_FAILED) {
+            int error = errno;
+            ::close(fd);
+            throw std::system_error(error, std::generic_category(),
+                                    "Cannot map " + path);
+        }
+    }
+    // The mapping remains valid after closing the file descriptor.
+    ::close(fd);
+}
+
+inline MappedFile::~MappedFile() {
+    if (data_ != nullptr) {
+        ::munmap(data_, size_);
+    }
+}
+
+} // end of namespace serde
@@ -4,14 +4,18 @@
 #pragma once
 
 #include <array>
+#include <climits>
 #include <cstdint>
 #include <functional>
+#include <limits>
 #include <map>
 #include <memory>
 #include <optional>
+#include <stdexcept>
 #include <string>
 #include <tuple>
 #include <type_traits>
+#include <utility>
 #include <variant>
 #include <vector>
 
@@ -33,6 +37,91 @@ class deserialization_error : public std::invalid_argument {
         : std::invalid_argument(what_arg) {}
 };
 
+// Read-only view of a contiguous sequence of bytes, in the spirit of C++20's
+// `std::span`. The bytes must outlive the view.
+class byte_span {
+    const uint8_t *data_;
+    size_t size_;
+
+  public:
+    byte_span(const uint8_t *data, size_t size) : data_(data), size_(size) {}
+    byte_span(const std::vector<uint8_t> &bytes)
+        : data_(bytes.data()), size_(bytes.size()) {}
+
+    const uint8_t *data() const { return data_; }
+    size_t size() const { return size_; }
+    const uint8_t *begin() const { return data_; }
+    const uint8_t *end() const { return data_ + size_; }
+    uint8_t operator[](size_t index) const { return data_[index]; }
+};
+
+// Hooks transforming serialized bytes (e.g. compression or encryption) in
+// generated code using envelope hooks. `unwrap` must reverse `wrap`.
+class envelope {
+  public:
+    using hook = std::function<std::vector<uint8_t>(std::vector<uint8_t>)>;
+
+    static void set_hooks(hook wrap, hook unwrap) {
+        hooks() = {std::move(wrap), std::move(unwrap)};
+    }
+
+    static std::vector<uint8_t> wrap(std::vector<uint8_t> bytes) {
+        auto &wrap = hooks().first;
+        return wrap ? wrap(std::move(bytes)) : bytes;
+    }
+
+    static std::vector<uint8_t> unwrap(std::vector<uint8_t> bytes) {
+        auto &unwrap = hooks().second;
+        return unwrap ? unwrap(std::move(bytes)) : bytes;
+    }
+
+  private:
+    static std::pair<hook, hook> &hooks() {
+        static std::pair<hook, hook> value;
+        return value;
+    }
+};
+
+// CRC-32 checksums (as in zlib) appended in little-endian order to the output
+// of checked serialization methods.
+class checksum {
+  public:
+    static uint32_t crc32(const uint8_t *data, size_t size) {
+        uint32_t crc = 0xFFFFFFFF;
+        for (size_t i = 0; i < size; i++) {
+            crc ^= data[i];
+            for (int k = 0; k < 8; k++) {
+                crc = (crc >> 1) ^ (0xEDB88320 & (0 - (crc & 1)));
+            }
+        }
+        return ~crc;
+    }
+
+    static std::vector<uint8_t> append(std::vector<uint8_t> bytes) {
+        uint32_t crc = crc32(bytes.data(), bytes.size());
+        for (int i = 0; i < 4; i++) {
+            bytes.push_back(static_cast<uint8_t>(crc >> (8 * i)));
+        }
+        return bytes;
+    }
+
+    static std::vector<uint8_t> verify(std::vector<uint8_t> bytes) {
+        if (bytes.size() < 4) {
+            throw deserialization_error("Missing checksum");
+        }
+        size_t size = bytes.size() - 4;
+        uint32_t expected = 0;
+        for (int i = 0; i < 4; i++) {
+            expected |= static_cast<uint32_t>(bytes[size + i]) << (8 * i);
+        }
+        if (crc32(bytes.data(), size) != expected) {
+            throw deserialization_error("Checksum mismatch");
+        }
+        bytes.resize(size);
+        return bytes;
+    }
+};
+
 // Basic implementation for 128-bit unsigned integers.
 struct uint128_t {
     uint64_t high;
@@ -107,20 +196,45 @@ bool operator==(const value_ptr<T> &lhs, const value_ptr<T> &rhs) {
     return *lhs == *rhs;
 }
 
+// Calls to the free functions `serialize(const T &, Serializer &)` and
+// `deserialize(T &, Deserializer &)`, found by argument-dependent lookup in the
+// namespace of T or in the namespace `serde` of the (de)serializer.
+namespace adl {
+
+template <typename T, typename Serializer>
+void call_serialize(const T &value, Serializer &serializer) {
+    serialize(value, serializer);
+}
+
+template <typename T, typename Deserializer>
+T call_deserialize(Deserializer &deserializer) {
+    T value;
+    deserialize(value, deserializer);
+    return value;
+}
+
+} // end of namespace adl
+
 // Trait to enable serialization of values of type T.
-// This is similar to the `serde::Serialize` trait in Rust.
+// This is similar to the `serde::Serialize` trait in Rust. Unless the trait is
+// specialized for T, values are serialized by a free function (see above).
 template <typename T>
 struct Serializable {
     template <typename Serializer>
-    static void serialize(const T &value, Serializer &serializer);
+    static void serialize(const T &value, Serializer &serializer) {
+        adl::call_serialize(value, serializer);
+    }
 };
 
 // Trait to enable deserialization of values of type T.
-// This is similar to the `serde::Deserialize` trait in Rust.
+// This is similar to the `serde::Deserialize` trait in Rust. Unless the trait is
+// specialized for T, values are deserialized by a free function (see above).
 template <typename T>
 struct Deserializable {
     template <typename Deserializer>
-    static T deserialize(Deserializer &deserializer);
+    static T deserialize(Deserializer &deserializer) {
+        return adl::call_deserialize<T>(deserializer);
+    }
 };
 
 // --- Implementation of Serializable for base types ---
@@ -0,0 +1,74 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#pragma once
+
+#include <cstdint>
+#include <fstream>
+#include <sstream>
+#include <stdexcept>
+#include <string>
+#include <vector>
+
+namespace serde {
+
+// The encoding of a value of a named type, as written by `serdegen test-vectors`:
+// one vector per line, made of four fields separated by tabs (type name, encoding,
+// hexadecimal bytes, and JSON value). Empty lines and lines starting with `#` are
+// ignored.
+struct TestVector {
+    std::string type_name;
+    std::string encoding;
+    std::vector<uint8_t> bytes;
+    std::string json;
+};
+
+inline std::vector<TestVector> parse_test_vectors(const std::string &content) {
+    std::vector<TestVector> vectors;
+    std::istringstream input(content);
+    std::string line;
+    for (size_t index = 1; std::getline(input, line); index++) {
+        if (!line.empty() && line.back() == '\r') {
+            line.pop_back();
+        }
+        if (line.find_first_not_of(" \t") == std::string::npos || line[0] == '#') {
+            continue;
+        }
+        auto invalid = std::invalid_argument("Invalid test vector at line " +
+                                             std::to_string(index));
+        std::string fields[4];
+        size_t start = 0;
+        for (size_t i = 0; i < 3; i++) {
+            size_t end = line.find('\t', start);
+            if (end == std::string::npos) {
+                throw invalid;
+            }
+            fields[i] = line.substr(start, end - start);
+            start = end + 1;
+        }
+        fields[3] = line.substr(start);
+        const std::string &hex = fields[2];
+        if (hex.size() % 2 != 0 ||
+            hex.find_first_not_of("0123456789abcdefABCDEF") != std::string::npos) {
+            throw invalid;
+        }
+        std::vector<uint8_t> bytes;
+        for (size_t i = 0; i < hex.size(); i += 2) {
+            bytes.push_back((uint8_t)std::stoi(hex.substr(i, 2), nullptr, 16));
+        }
+        vectors.push_back({fields[0], fields[1], std::move(bytes), fields[3]});
+    }
+    return vectors;
+}
+
+inline std::vector<TestVector> load_test_vectors(const std::string &path) {
+    std::ifstream file(path);
+    if (!file) {
+        throw std::runtime_error("Cannot open " + path);
+    }
+    std::ostringstream content;
+    content << file.rdbuf();
+    return parse_test_vectors(content.str());
+}
+
+} // end of namespace serde
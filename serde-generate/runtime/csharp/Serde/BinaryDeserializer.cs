@@ -8,6 +8,11 @@ using System.Text;
 
 namespace Serde
 {
+    /// <summary>
+    /// Deserializers hold the state of a single decoding, hence must not be shared between
+    /// threads. The runtime itself holds no shared mutable state, so that several deserializers
+    /// may be used concurrently.
+    /// </summary>
     public abstract class BinaryDeserializer : IDeserializer, IDisposable
     {
         protected readonly ArraySegment<byte> input;
@@ -19,7 +24,11 @@ namespace Serde
 
         public BinaryDeserializer(ArraySegment<byte> _input, long maxContainerDepth)
         {
-            input = _input;
+            // Decode a copy of the input, so that callers may reuse their buffers (e.g. from a
+            // pool) while values are decoded in other threads.
+            var copy = new byte[_input.Count];
+            Array.Copy(_input.Array, _input.Offset, copy, 0, _input.Count);
+            input = new ArraySegment<byte>(copy);
             reader = new BinaryReader(new MemoryStream(input.Array, input.Offset, input.Count));
             containerDepthBudget = maxContainerDepth;
         }
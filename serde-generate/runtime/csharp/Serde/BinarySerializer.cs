@@ -9,6 +9,11 @@ using System.Text;
 
 namespace Serde
 {
+    /// <summary>
+    /// Serializers hold the state of a single encoding, hence must not be shared between
+    /// threads. The runtime itself holds no shared mutable state, so that several serializers
+    /// may be used concurrently.
+    /// </summary>
     public abstract class BinarySerializer : ISerializer, IDisposable
     {
         protected readonly MemoryStream buffer;
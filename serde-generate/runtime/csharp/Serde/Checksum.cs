@@ -0,0 +1,58 @@
+using System;
+
+namespace Serde
+{
+    /// <summary>
+    /// CRC-32 checksums (as in zlib) appended in little-endian order to the output of checked
+    /// serialization methods.
+    /// </summary>
+    public static class Checksum
+    {
+        public static uint Crc32(byte[] bytes, int size)
+        {
+            uint crc = 0xFFFFFFFF;
+            for (int i = 0; i < size; i++)
+            {
+                crc ^= bytes[i];
+                for (int k = 0; k < 8; k++)
+                {
+                    crc = (crc >> 1) ^ (0xEDB88320 & (0 - (crc & 1)));
+                }
+            }
+            return ~crc;
+        }
+
+        public static byte[] Append(byte[] bytes)
+        {
+            uint crc = Crc32(bytes, bytes.Length);
+            byte[] result = new byte[bytes.Length + 4];
+            Array.Copy(bytes, result, bytes.Length);
+            for (int i = 0; i < 4; i++)
+            {
+                result[bytes.Length + i] = (byte)(crc >> (8 * i));
+            }
+            return result;
+        }
+
+        public static byte[] Verify(byte[] bytes)
+        {
+            if (bytes.Length < 4)
+            {
+                throw new DeserializationException("Missing checksum");
+            }
+            int size = bytes.Length - 4;
+            uint expected = 0;
+            for (int i = 0; i < 4; i++)
+            {
+                expected |= (uint)bytes[size + i] << (8 * i);
+            }
+            if (Crc32(bytes, size) != expected)
+            {
+                throw new DeserializationException("Checksum mismatch");
+            }
+            byte[] payload = new byte[size];
+            Array.Copy(bytes, payload, size);
+            return payload;
+        }
+    }
+}
@@ -0,0 +1,46 @@
+using System;
+using System.Threading;
+
+namespace Serde
+{
+    /// <summary>
+    /// Hooks transforming serialized bytes (e.g. compression or encryption) in generated code using
+    /// envelope hooks. The `unwrap` hook must reverse the `wrap` hook.
+    ///
+    /// Both hooks are held by a single immutable `Hooks` object, which is replaced atomically: a
+    /// thread calling `SetHooks` never lets concurrent callers observe the `wrap` hook of one pair
+    /// together with the `unwrap` hook of another. Hooks may be called concurrently from several
+    /// threads, hence must be thread-safe.
+    /// </summary>
+    public static class Envelope
+    {
+        /// <summary>Immutable pair of envelope hooks.</summary>
+        public sealed class Hooks
+        {
+            public static readonly Hooks Identity = new Hooks(bytes => bytes, bytes => bytes);
+
+            public Func<byte[], byte[]> Wrap { get; }
+            public Func<byte[], byte[]> Unwrap { get; }
+
+            public Hooks(Func<byte[], byte[]> wrap, Func<byte[], byte[]> unwrap)
+            {
+                Wrap = wrap ?? throw new ArgumentNullException(nameof(wrap));
+                Unwrap = unwrap ?? throw new ArgumentNullException(nameof(unwrap));
+            }
+        }
+
+        private static Hooks hooks = Hooks.Identity;
+
+        public static void SetHooks(Hooks value) =>
+            Volatile.Write(ref hooks, value ?? throw new ArgumentNullException(nameof(value)));
+
+        public static void SetHooks(Func<byte[], byte[]> wrap, Func<byte[], byte[]> unwrap) =>
+            SetHooks(new Hooks(wrap, unwrap));
+
+        public static Hooks GetHooks() => Volatile.Read(ref hooks);
+
+        public static byte[] Wrap(byte[] bytes) => GetHooks().Wrap(bytes);
+
+        public static byte[] Unwrap(byte[] bytes) => GetHooks().Unwrap(bytes);
+    }
+}
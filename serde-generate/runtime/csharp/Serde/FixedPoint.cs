@@ -0,0 +1,39 @@
+using System;
+using System.Numerics;
+
+namespace Serde
+{
+    /// <summary>
+    /// Fixed-point decimal numbers, encoded as the signed integer holding the value multiplied
+    /// by 10^scale.
+    /// </summary>
+    public static class FixedPoint
+    {
+        public static BigInteger ToMantissa(decimal value, int scale)
+        {
+            decimal scaled = value;
+            for (int i = 0; i < scale; i++)
+            {
+                scaled *= 10;
+            }
+            if (decimal.Truncate(scaled) != scaled)
+            {
+                throw new SerializationException("Too many fractional digits in " + value);
+            }
+            return new BigInteger(scaled);
+        }
+
+        public static decimal FromMantissa(BigInteger mantissa, int scale)
+        {
+            BigInteger magnitude = BigInteger.Abs(mantissa);
+            if (scale > 28 || magnitude >= (BigInteger.One << 96))
+            {
+                throw new DeserializationException("Fixed-point value out of the range of decimal");
+            }
+            int lo = (int)(uint)(magnitude & uint.MaxValue);
+            int mid = (int)(uint)((magnitude >> 32) & uint.MaxValue);
+            int hi = (int)(uint)(magnitude >> 64);
+            return new decimal(lo, mid, hi, mantissa.Sign < 0, (byte)scale);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+using System;
+using System.Collections.Generic;
+using System.IO;
+
+namespace Serde
+{
+    /// <summary>
+    /// The encoding of a value of a named type, as written by `serdegen test-vectors`: one vector
+    /// per line, made of four fields separated by tabs (type name, encoding, hexadecimal bytes, and
+    /// JSON value). Empty lines and lines starting with `#` are ignored.
+    /// </summary>
+    public sealed class TestVector
+    {
+        public string TypeName { get; }
+        public string Encoding { get; }
+        public byte[] Bytes { get; }
+        public string Json { get; }
+
+        public TestVector(string typeName, string encoding, byte[] bytes, string json)
+        {
+            TypeName = typeName;
+            Encoding = encoding;
+            Bytes = bytes;
+            Json = json;
+        }
+
+        public static List<TestVector> Parse(string content)
+        {
+            var vectors = new List<TestVector>();
+            string[] lines = content.Split('\n');
+            for (int index = 0; index < lines.Length; index++)
+            {
+                string line = lines[index].TrimEnd('\r');
+                if (line.Trim().Length == 0 || line.StartsWith("#"))
+                {
+                    continue;
+                }
+                string[] fields = line.Split(new[] { '\t' }, 4);
+                if (fields.Length != 4 || fields[2].Length % 2 != 0)
+                {
+                    throw new FormatException($"Invalid test vector at line {index + 1}");
+                }
+                byte[] bytes = new byte[fields[2].Length / 2];
+                for (int i = 0; i < bytes.Length; i++)
+                {
+                    bytes[i] = Convert.ToByte(fields[2].Substring(2 * i, 2), 16);
+                }
+                vectors.Add(new TestVector(fields[0], fields[1], bytes, fields[3]));
+            }
+            return vectors;
+        }
+
+        public static List<TestVector> Load(string path) => Parse(File.ReadAllText(path));
+    }
+}
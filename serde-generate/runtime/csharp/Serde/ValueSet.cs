@@ -0,0 +1,57 @@
+using System;
+using System.Collections;
+using System.Collections.Generic;
+
+namespace Serde
+{
+    /// <summary>
+    /// Immutable wrapper class around <see cref="HashSet<T>"/>. Implements value semantics for
+    /// <see cref="object.Equals(object)"/> and <see cref="object.GetHashCode"/>.
+    /// </summary>
+    public class ValueSet<T> : IEquatable<ValueSet<T>>, IReadOnlyCollection<T>
+    where T: IEquatable<T>
+    {
+        private readonly HashSet<T> set;
+        private int? hashCode;
+
+        public int Count => set.Count;
+
+        public ValueSet(HashSet<T> set) {
+            this.set = set ?? throw new ArgumentNullException(nameof(set));
+            hashCode = null;
+        }
+
+        public bool Contains(T item) => set.Contains(item);
+
+        IEnumerator<T> IEnumerable<T>.GetEnumerator() => set.GetEnumerator();
+
+        public IEnumerator GetEnumerator() => ((IEnumerable)set).GetEnumerator();
+
+        public override bool Equals(object obj) => obj is ValueSet<T> other && Equals(other);
+
+        public bool Equals(ValueSet<T> other) {
+            if (other == null) return false;
+            return set.SetEquals(other.set);
+        }
+
+        public static bool operator ==(ValueSet<T> left, ValueSet<T> right) => Equals(left, right);
+
+        public static bool operator !=(ValueSet<T> left, ValueSet<T> right) => !Equals(left, right);
+
+        public override int GetHashCode()
+        {
+            unchecked
+            {
+                if (hashCode.HasValue) return hashCode.Value;
+                // The hash of a set does not depend on the order of its elements.
+                int code = 45053;
+                foreach (var item in set)
+                {
+                    code += item.GetHashCode();
+                }
+                hashCode = code;
+                return code;
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+part of bcs_test;
+
+/// Minimal concrete serializer so that these tests do not depend on the
+/// encodings selected at generation time.
+class _FlutterTestSerializer extends BinarySerializer {
+  void serialize_len(int len) => serialize_u32(len);
+
+  void serialize_variant_index(int index) => serialize_u32(index);
+}
+
+class _FlutterTestDeserializer extends BinaryDeserializer {
+  _FlutterTestDeserializer(Uint8List input) : super(input);
+
+  int deserialize_len() => deserialize_u32();
+
+  int deserialize_variant_index() => deserialize_u32();
+}
+
+/// Widget-free tests meant to run with `flutter test` (including `--platform chrome`).
+void runFlutterTests() {
+  TestWidgetsFlutterBinding.ensureInitialized();
+
+  test('u64 values round-trip', () {
+    var serializer = new _FlutterTestSerializer();
+    serializer.serialize_u64(0x1234567890);
+    var bytes = serializer.get_bytes();
+    expect(bytes, Uint8List.fromList([0x90, 0x78, 0x56, 0x34, 0x12, 0, 0, 0]));
+
+    var deserializer = new _FlutterTestDeserializer(bytes);
+    expect(deserializer.deserialize_u64(), 0x1234567890);
+  });
+
+  test('Int128 values round-trip through BigInt', () {
+    var value = BigInt.parse('18446744073709551621');
+    expect(Int128.fromBigInt(value).toBigInt(), value);
+    expect(Int128.fromJson(jsonDecode(jsonEncode(Int128.fromBigInt(value)))),
+        Int128.fromBigInt(value));
+  });
+}
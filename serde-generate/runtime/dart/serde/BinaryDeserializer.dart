@@ -33,7 +33,7 @@ abstract class BinaryDeserializer {
 
   int deserialize_u16() {
     var result = this.input.getUint16(offset, Endian.little);
-    this.offset += 1;
+    this.offset += 2;
     return result;
   }
 
@@ -44,11 +44,55 @@ abstract class BinaryDeserializer {
   }
 
   int deserialize_u64() {
-    var result = this.input.getUint64(offset, Endian.little);
+    var result = int64.readUint64(this.input, offset);
+    this.offset += 8;
+    return result;
+  }
+
+  int deserialize_i8() {
+    var result = this.input.getInt8(offset);
+    this.offset += 1;
+    return result;
+  }
+
+  int deserialize_i16() {
+    var result = this.input.getInt16(offset, Endian.little);
+    this.offset += 2;
+    return result;
+  }
+
+  int deserialize_i32() {
+    var result = this.input.getInt32(offset, Endian.little);
+    this.offset += 4;
+    return result;
+  }
+
+  int deserialize_i64() {
+    var result = int64.readInt64(this.input, offset);
     this.offset += 8;
     return result;
   }
 
+  /// Deserialize `size` bytes in little-endian order as an unsigned integer.
+  BigInt deserialize_bigint(int size) {
+    var result = BigInt.zero;
+    for (var i = size - 1; i >= 0; i--) {
+      result = (result << 8) | BigInt.from(this.input.getUint8(offset + i));
+    }
+    this.offset += size;
+    return result;
+  }
+
+  /// Same as `deserialize_u64`, for values that are not exact `int` values on the web.
+  BigInt deserialize_u64_bigint() {
+    return deserialize_bigint(8);
+  }
+
+  /// Same as `deserialize_i64`, for values that are not exact `int` values on the web.
+  BigInt deserialize_i64_bigint() {
+    return deserialize_bigint(8).toSigned(64);
+  }
+
   Bytes deserialize_bytes() {
     return new Bytes(deserialize_uint8list());
   }
@@ -84,9 +128,11 @@ abstract class BinaryDeserializer {
   int deserialize_len();
 
   Int128 deserialize_u128() {
-    var low = this.deserialize_u64();
-    var high = this.deserialize_u64();
-    return Int128(high,low);
+    return Int128.fromBigInt(deserialize_bigint(16));
+  }
+
+  Int128 deserialize_i128() {
+    return Int128.fromBigInt(deserialize_bigint(16).toSigned(128));
   }
 
   int getUint8() {
@@ -49,10 +49,30 @@ abstract class BinarySerializer {
 
   void serialize_u64(int val) {
     var bdata = new ByteData(8);
-    bdata.setUint64(0, val, Endian.little);
+    int64.writeUint64(bdata, 0, val);
     this.output.addAll(bdata.buffer.asUint8List());
   }
 
+  /// Serialize the `size` low bytes of `value` in little-endian order. Negative values are
+  /// written in two's complement.
+  void serialize_bigint(BigInt value, int size) {
+    var mask = BigInt.from(0xFF);
+    for (var i = 0; i < size; i++) {
+      this.output.add((value & mask).toInt());
+      value = value >> 8;
+    }
+  }
+
+  /// Same as `serialize_u64`, for values that are not exact `int` values on the web.
+  void serialize_u64_bigint(BigInt value) {
+    serialize_bigint(value, 8);
+  }
+
+  /// Same as `serialize_i64`, for values that are not exact `int` values on the web.
+  void serialize_i64_bigint(BigInt value) {
+    serialize_bigint(value, 8);
+  }
+
   void serialize_i8(int value) {
     serialize_u8(value);
   }
@@ -84,8 +104,11 @@ abstract class BinarySerializer {
   void serialize_len(int len);
 
   void serialize_u128(Int128 value) {
-    serialize_u64(value.low);
-    serialize_u64(value.high);
+    serialize_bigint(value.toBigInt(), 16);
+  }
+
+  void serialize_i128(Int128 value) {
+    serialize_bigint(value.toBigInt(), 16);
   }
 
   int get_buffer_offset() {
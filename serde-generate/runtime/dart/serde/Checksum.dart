@@ -0,0 +1,40 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+part of serde;
+
+/// CRC-32 checksums (as in zlib) appended in little-endian order to the output of checked
+/// serialization methods.
+class Checksum {
+  static int crc32(Uint8List bytes) {
+    var crc = 0xFFFFFFFF;
+    for (var byte in bytes) {
+      crc ^= byte;
+      for (var k = 0; k < 8; k++) {
+        crc = (crc >> 1) ^ (0xEDB88320 & -(crc & 1));
+      }
+    }
+    return (crc ^ 0xFFFFFFFF) & 0xFFFFFFFF;
+  }
+
+  static Uint8List append(Uint8List bytes) {
+    var result = Uint8List(bytes.length + 4);
+    result.setAll(0, bytes);
+    result.buffer
+        .asByteData()
+        .setUint32(bytes.length, crc32(bytes), Endian.little);
+    return result;
+  }
+
+  static Uint8List verify(Uint8List bytes) {
+    if (bytes.length < 4) {
+      throw Exception("Missing checksum");
+    }
+    var payload = Uint8List.sublistView(bytes, 0, bytes.length - 4);
+    var expected = ByteData.sublistView(bytes, bytes.length - 4)
+        .getUint32(0, Endian.little);
+    if (crc32(payload) != expected) {
+      throw Exception("Checksum mismatch");
+    }
+    return payload;
+  }
+}
@@ -0,0 +1,21 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+part of serde;
+
+typedef Uint8List EnvelopeHook(Uint8List bytes);
+
+/// Hooks transforming serialized bytes (e.g. compression or encryption) in generated code using
+/// envelope hooks. The `unwrap` hook must reverse the `wrap` hook.
+class Envelope {
+  static EnvelopeHook _wrap = (bytes) => bytes;
+  static EnvelopeHook _unwrap = (bytes) => bytes;
+
+  static void setHooks(EnvelopeHook wrap, EnvelopeHook unwrap) {
+    _wrap = wrap;
+    _unwrap = unwrap;
+  }
+
+  static Uint8List wrap(Uint8List bytes) => _wrap(bytes);
+
+  static Uint8List unwrap(Uint8List bytes) => _unwrap(bytes);
+}
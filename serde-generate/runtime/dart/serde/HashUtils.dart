@@ -31,6 +31,10 @@ bool isListsEqual<T>(List<T> list1, List<T> list2) {
   return true;
 }
 
+bool isSetsEqual<T>(Set<T> set1, Set<T> set2) {
+  return set1.length == set2.length && set1.containsAll(set2);
+}
+
 bool isUint8ListsEqual<T>(Uint8List list1, Uint8List list2) {
   if (list1.length != list2.length) {
     return false;
@@ -43,4 +47,12 @@ bool isUint8ListsEqual<T>(Uint8List list1, Uint8List list2) {
   }
 
   return true;
-}
\ No newline at end of file
+}
+
+int bytesHashCode(Uint8List bytes) {
+  int hash = 0;
+  for (int i = 0; i < bytes.length; i++) {
+    hash = $jc(hash, bytes[i]);
+  }
+  return $jf(hash);
+}
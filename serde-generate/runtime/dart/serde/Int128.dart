@@ -1,51 +1,44 @@
 part of serde;
 
+/// 128-bit integers, stored as `BigInt` values so that they are exact on all platforms,
+/// including the web.
 class Int128 {
-  int high;
-  int low;
+  final BigInt value;
 
-  Int128(int high, int low) {
-    this.high = high;
-    this.low = low;
-  }
+  Int128(int high, int low)
+      : value = (BigInt.from(high) << 64) + BigInt.from(low).toUnsigned(64);
+
+  Int128._(this.value);
 
-  Int128 fromBigInt(BigInt num) {
-    high = (num >> 64).toInt();
-    low = (num & BigInt.from(0xFFFFFFFFFFFFFFFF)).toInt();
-    return Int128(high, low);
+  static Int128 fromBigInt(BigInt num) {
+    return Int128._(num);
   }
 
   BigInt toBigInt() {
-    var result = BigInt.from(high);
-    result << 64;
-    result = result + BigInt.from(low);
-    return result;
+    return value;
   }
 
+  /// High 64 bits, as a signed integer. Only exact on the web for small values.
+  int get high => (value >> 64).toInt();
+
+  /// Low 64 bits, as a signed integer. Only exact on the web for small values.
+  int get low => value.toSigned(64).toInt();
+
   @override
   bool operator ==(covariant Int128 other) {
     if (other == null) return false;
-    if (this.high == other.high && this.low == other.low) {
-      return true;
-    } else {
-      return false;
-    }
+    return this.value == other.value;
   }
 
   @override
-  int get hashCode => $jf($jc(this.high.hashCode, this.low));
+  int get hashCode => value.hashCode;
 
   @override
   String toString() {
-    return "$high$low";
-  }
-
-  Int128.fromJson(String json) {
-    final num = BigInt.parse(json);
-    high = (num >> 64).toInt();
-    low = (num & BigInt.from(0xFFFFFFFFFFFFFFFF)).toInt();
+    return value.toString();
   }
 
-  String toJson() => this.toBigInt().toString();
+  Int128.fromJson(String json) : value = BigInt.parse(json);
 
+  String toJson() => value.toString();
 }
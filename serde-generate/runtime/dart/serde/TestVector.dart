@@ -0,0 +1,40 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+part of serde;
+
+/// The encoding of a value of a named type, as written by `serdegen test-vectors`: one vector
+/// per line, made of four fields separated by tabs (type name, encoding, hexadecimal bytes, and
+/// JSON value). Empty lines and lines starting with `#` are ignored.
+class TestVector {
+  TestVector(this.typeName, this.encoding, this.bytes, this.json);
+
+  final String typeName;
+  final String encoding;
+  final Uint8List bytes;
+  final String json;
+
+  static List<TestVector> parse(String content) {
+    final vectors = <TestVector>[];
+    final lines = content.split(RegExp(r'\r?\n'));
+    for (var index = 0; index < lines.length; index++) {
+      final line = lines[index];
+      if (line.trim().isEmpty || line.startsWith('#')) {
+        continue;
+      }
+      final fields = <String>[];
+      var start = 0;
+      for (var i = 0; i < 3; i++) {
+        final end = line.indexOf('\t', start);
+        if (end < 0) {
+          throw FormatException('Invalid test vector at line ${index + 1}');
+        }
+        fields.add(line.substring(start, end));
+        start = end + 1;
+      }
+      fields.add(line.substring(start));
+      final bytes = Uint8List.fromList(HEX.decode(fields[2]));
+      vectors.add(TestVector(fields[0], fields[1], bytes, fields[3]));
+    }
+    return vectors;
+  }
+}
@@ -0,0 +1,16 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+// 64-bit integers on platforms with 64-bit `int` values (the Dart VM, Flutter, and wasm).
+// See `int64_web.dart` for JavaScript.
+import 'dart:typed_data';
+
+/// Whether `int` values are JavaScript numbers, i.e. only exact up to 2^53.
+const bool isWeb = false;
+
+int readUint64(ByteData data, int offset) => data.getUint64(offset, Endian.little);
+
+int readInt64(ByteData data, int offset) => data.getInt64(offset, Endian.little);
+
+void writeUint64(ByteData data, int offset, int value) =>
+    data.setUint64(offset, value, Endian.little);
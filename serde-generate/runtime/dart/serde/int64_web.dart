@@ -0,0 +1,38 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+// 64-bit integers when compiling to JavaScript, where `ByteData.getUint64` and friends are not
+// supported and `int` values are only exact up to 2^53. Values that cannot be represented
+// exactly are rejected: use the `BigInt` variants of the (de)serialization methods instead.
+import 'dart:typed_data';
+
+const bool isWeb = true;
+
+/// Upper bound of the high 32 bits of an exact JavaScript integer.
+const int _maxSafeHigh = 0x1FFFFF;
+
+int readUint64(ByteData data, int offset) {
+  var low = data.getUint32(offset, Endian.little);
+  var high = data.getUint32(offset + 4, Endian.little);
+  if (high > _maxSafeHigh) {
+    throw Exception("64-bit integer cannot be represented exactly: use BigInt instead");
+  }
+  return high * 0x100000000 + low;
+}
+
+int readInt64(ByteData data, int offset) {
+  var low = data.getUint32(offset, Endian.little);
+  var high = data.getInt32(offset + 4, Endian.little);
+  if (high > _maxSafeHigh || high < -_maxSafeHigh - 1) {
+    throw Exception("64-bit integer cannot be represented exactly: use BigInt instead");
+  }
+  return high * 0x100000000 + low;
+}
+
+void writeUint64(ByteData data, int offset, int value) {
+  // Negative values are written in two's complement, as on the VM.
+  var low = value % 0x100000000;
+  var high = ((value - low) ~/ 0x100000000) % 0x100000000;
+  data.setUint32(offset, low, Endian.little);
+  data.setUint32(offset + 4, high, Endian.little);
+}
@@ -2,11 +2,16 @@ library serde;
 
 import 'dart:typed_data';
 import 'package:hex/hex.dart';
+// JavaScript does not support 64-bit integers natively (e.g. `ByteData.getUint64`).
+import 'int64_native.dart' if (dart.library.html) 'int64_web.dart' as int64;
 
 part 'BinaryDeserializer.dart';
 part 'BinarySerializer.dart';
 part 'Bytes.dart';
+part 'Checksum.dart';
+part 'Envelope.dart';
 part 'HashUtils.dart';
 part 'Int128.dart';
 part 'Slice.dart';
+part 'TestVector.dart';
 part 'Unit.dart';
@@ -40,4 +40,23 @@ void runBcsTests() {
           0
         ]));
   });
+
+  test('64-bit and 128-bit integers work', () {
+    BcsSerializer serializer = new BcsSerializer();
+    serializer.serialize_u64_bigint(BigInt.parse('18446744073709551615'));
+    serializer.serialize_i64_bigint(BigInt.from(-2));
+    serializer.serialize_u64(4294967296);
+    serializer.serialize_i128(Int128.fromBigInt(BigInt.from(-1)));
+    var bytes = serializer.get_bytes();
+    expect(bytes.sublist(0, 16), Uint8List.fromList([
+      0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, //
+      0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    ]));
+
+    BcsDeserializer deserializer = new BcsDeserializer(bytes);
+    expect(deserializer.deserialize_u64_bigint(), BigInt.parse('18446744073709551615'));
+    expect(deserializer.deserialize_i64_bigint(), BigInt.from(-2));
+    expect(deserializer.deserialize_u64(), 4294967296);
+    expect(deserializer.deserialize_i128(), Int128.fromBigInt(BigInt.from(-1)));
+  });
 }
@@ -23,7 +23,7 @@ void runDijetsTests() {
     ]);
     expect(accountAddress.bcsSerialize(), expect_result);
 
-    AccountAddress address = AccountAddress.bcsDeserialize(expect_result);
+    AccountAddress address = AccountAddressBcs.bcsDeserialize(expect_result);
     expect(address, accountAddress);
 
     expect(AccountAddress.fromJson(jsonDecode(jsonEncode(accountAddress))),
@@ -42,7 +42,7 @@ void runDijetsTests() {
         [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 3, 49, 50, 51]);
     expect(result, expect_result);
 
-    AccessPath path = AccessPath.bcsDeserialize(expect_result);
+    AccessPath path = AccessPathBcs.bcsDeserialize(expect_result);
     expect(accessPath1, path);
 
     print(jsonEncode(accessPath1));
@@ -56,7 +56,7 @@ void runDijetsTests() {
     var expect_result = Uint8List.fromList([0, 1]);
     expect(result, expect_result);
 
-    var u8args_de = TransactionArgument.bcsDeserialize(expect_result);
+    var u8args_de = TransactionArgumentBcs.bcsDeserialize(expect_result);
     expect(u8args_de, u8ags);
 
     print(jsonEncode(u8args_de));
@@ -78,7 +78,7 @@ void runDijetsTests() {
 
     expect(result, expect_result);
 
-    var payload = TransactionPayload.bcsDeserialize(result);
+    var payload = TransactionPayloadBcs.bcsDeserialize(result);
     expect(payload, t_script);
 
     print(jsonEncode(payload));
@@ -0,0 +1,31 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package serde
+
+import (
+	"encoding/binary"
+	"errors"
+	"hash/crc32"
+)
+
+// AppendChecksum returns a copy of `bytes` followed by its CRC-32 (IEEE) checksum in
+// little-endian order.
+func AppendChecksum(bytes []byte) []byte {
+	result := make([]byte, len(bytes)+4)
+	copy(result, bytes)
+	binary.LittleEndian.PutUint32(result[len(bytes):], crc32.ChecksumIEEE(bytes))
+	return result
+}
+
+// VerifyChecksum checks and removes the CRC-32 checksum at the end of `bytes`.
+func VerifyChecksum(bytes []byte) ([]byte, error) {
+	if len(bytes) < 4 {
+		return nil, errors.New("Missing checksum")
+	}
+	size := len(bytes) - 4
+	if binary.LittleEndian.Uint32(bytes[size:]) != crc32.ChecksumIEEE(bytes[:size]) {
+		return nil, errors.New("Checksum mismatch")
+	}
+	return bytes[:size], nil
+}
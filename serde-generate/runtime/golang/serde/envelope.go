@@ -0,0 +1,30 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package serde
+
+// EnvelopeHook transforms serialized bytes (e.g. compression or encryption).
+type EnvelopeHook func([]byte) ([]byte, error)
+
+var envelopeWrap, envelopeUnwrap EnvelopeHook
+
+// SetEnvelopeHooks registers the hooks used by generated code with envelope hooks.
+// `unwrap` must reverse `wrap`. Nil hooks leave the bytes unchanged.
+func SetEnvelopeHooks(wrap, unwrap EnvelopeHook) {
+	envelopeWrap = wrap
+	envelopeUnwrap = unwrap
+}
+
+func WrapEnvelope(bytes []byte) ([]byte, error) {
+	if envelopeWrap == nil {
+		return bytes, nil
+	}
+	return envelopeWrap(bytes)
+}
+
+func UnwrapEnvelope(bytes []byte) ([]byte, error) {
+	if envelopeUnwrap == nil {
+		return bytes, nil
+	}
+	return envelopeUnwrap(bytes)
+}
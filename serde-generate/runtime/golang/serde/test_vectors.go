@@ -0,0 +1,50 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package serde
+
+import (
+	"encoding/hex"
+	"fmt"
+	"io/ioutil"
+	"strings"
+)
+
+// TestVector is the encoding of a value of a named type, as written by `serdegen test-vectors`.
+type TestVector struct {
+	TypeName string
+	Encoding string
+	Bytes    []byte
+	Json     string
+}
+
+// ParseTestVectors parses test vectors given as lines of tab-separated fields: type name,
+// encoding, hexadecimal bytes, and JSON value. Empty lines and lines starting with `#` are ignored.
+func ParseTestVectors(content string) ([]TestVector, error) {
+	var vectors []TestVector
+	for index, line := range strings.Split(content, "\n") {
+		line = strings.TrimSuffix(line, "\r")
+		if strings.TrimSpace(line) == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+		fields := strings.SplitN(line, "\t", 4)
+		if len(fields) != 4 {
+			return nil, fmt.Errorf("Invalid test vector at line %d", index+1)
+		}
+		bytes, err := hex.DecodeString(fields[2])
+		if err != nil {
+			return nil, fmt.Errorf("Invalid test vector at line %d: %w", index+1, err)
+		}
+		vectors = append(vectors, TestVector{fields[0], fields[1], bytes, fields[3]})
+	}
+	return vectors, nil
+}
+
+// LoadTestVectors reads a file of test vectors.
+func LoadTestVectors(path string) ([]TestVector, error) {
+	content, err := ioutil.ReadFile(path)
+	if err != nil {
+		return nil, err
+	}
+	return ParseTestVectors(string(content))
+}
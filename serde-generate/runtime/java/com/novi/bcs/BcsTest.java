@@ -76,6 +76,26 @@ public class BcsTest {
         assert Arrays.equals(serializer.get_bytes(), new byte[]{-1, /**/ 0, /**/ 0, /**/ 0, 0, /**/ 0, 1, 0,  /**/ 1, /**/ 2, 0, 0, 0});
     }
 
+    static void test_deserializer_copies_its_input() throws Exception {
+        byte[] input = new byte[]{3, 1, 2, 3};
+        BcsDeserializer deserializer = new BcsDeserializer(input);
+        input[1] = 7;
+        assert Arrays.equals(deserializer.deserialize_bytes().content(), new byte[]{1, 2, 3});
+    }
+
+    static void test_envelope_hooks_are_replaced_together() throws Exception {
+        com.novi.serde.Envelope.Hooks hooks = com.novi.serde.Envelope.getHooks();
+        try {
+            com.novi.serde.Envelope.setHooks(bytes -> new byte[]{1}, bytes -> new byte[]{2});
+            com.novi.serde.Envelope.Hooks current = com.novi.serde.Envelope.getHooks();
+            assert Arrays.equals(current.wrap.apply(new byte[0]), new byte[]{1});
+            assert Arrays.equals(current.unwrap.apply(new byte[0]), new byte[]{2});
+        } finally {
+            com.novi.serde.Envelope.setHooks(hooks);
+        }
+        assert com.novi.serde.Envelope.getHooks() == hooks;
+    }
+
     public static void main(String[] args) throws Exception {
         for (Method method : BcsTest.class.getDeclaredMethods()) {
             if (method.getName().startsWith("test_")) {
@@ -10,12 +10,19 @@ import java.nio.charset.StandardCharsets;
 import java.nio.charset.CharacterCodingException;
 import java.math.BigInteger;
 
+/**
+ * Deserializers hold the state of a single decoding, hence must not be shared between threads.
+ * The runtime itself holds no shared mutable state, so that several deserializers may be used
+ * concurrently.
+ */
 public abstract class BinaryDeserializer implements Deserializer {
     protected ByteBuffer input;
     private long containerDepthBudget;
 
     public BinaryDeserializer(byte[] input, long maxContainerDepth) {
-        this.input = ByteBuffer.wrap(input);
+        // Decode a copy of the input, so that callers may reuse their buffers (e.g. from a pool)
+        // while values are decoded in other threads.
+        this.input = ByteBuffer.wrap(input.clone());
         this.input.order(ByteOrder.LITTLE_ENDIAN);
         containerDepthBudget = maxContainerDepth;
     }
@@ -5,6 +5,11 @@ package com.novi.serde;
 
 import java.math.BigInteger;
 
+/**
+ * Serializers hold the state of a single encoding, hence must not be shared between threads.
+ * The runtime itself holds no shared mutable state, so that several serializers may be used
+ * concurrently. `get_bytes` returns a copy of the output.
+ */
 public abstract class BinarySerializer implements Serializer {
     protected MyByteArrayOutputStream output;
     private long containerDepthBudget;
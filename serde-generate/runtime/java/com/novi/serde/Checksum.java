@@ -0,0 +1,45 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.serde;
+
+import java.util.Arrays;
+import java.util.zip.CRC32;
+
+/**
+ * CRC-32 checksums (as in zlib) appended in little-endian order to the output of checked
+ * serialization methods.
+ */
+public final class Checksum {
+    private Checksum() {}
+
+    public static byte[] append(byte[] bytes) {
+        long crc = crc32(bytes, bytes.length);
+        byte[] result = Arrays.copyOf(bytes, bytes.length + 4);
+        for (int i = 0; i < 4; i++) {
+            result[bytes.length + i] = (byte) (crc >>> (8 * i));
+        }
+        return result;
+    }
+
+    public static byte[] verify(byte[] bytes) throws DeserializationError {
+        if (bytes.length < 4) {
+            throw new DeserializationError("Missing checksum");
+        }
+        int size = bytes.length - 4;
+        long expected = 0;
+        for (int i = 0; i < 4; i++) {
+            expected |= (long) (bytes[size + i] & 0xFF) << (8 * i);
+        }
+        if (crc32(bytes, size) != expected) {
+            throw new DeserializationError("Checksum mismatch");
+        }
+        return Arrays.copyOf(bytes, size);
+    }
+
+    private static long crc32(byte[] bytes, int size) {
+        CRC32 crc = new CRC32();
+        crc.update(bytes, 0, size);
+        return crc.getValue();
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.serde;
+
+import java.util.function.UnaryOperator;
+
+/**
+ * Hooks transforming serialized bytes (e.g. compression or encryption) in generated code using
+ * envelope hooks. The `unwrap` hook must reverse the `wrap` hook.
+ *
+ * Both hooks are held by a single immutable `Hooks` object, which is replaced atomically: a
+ * thread calling `setHooks` never lets concurrent callers observe the `wrap` hook of one pair
+ * together with the `unwrap` hook of another. Hooks may be called concurrently from several
+ * threads, hence must be thread-safe.
+ */
+public final class Envelope {
+    /** Immutable pair of envelope hooks. */
+    public static final class Hooks {
+        public static final Hooks IDENTITY = new Hooks(UnaryOperator.identity(), UnaryOperator.identity());
+
+        public final UnaryOperator<byte[]> wrap;
+        public final UnaryOperator<byte[]> unwrap;
+
+        public Hooks(UnaryOperator<byte[]> wrap, UnaryOperator<byte[]> unwrap) {
+            this.wrap = java.util.Objects.requireNonNull(wrap);
+            this.unwrap = java.util.Objects.requireNonNull(unwrap);
+        }
+    }
+
+    private static volatile Hooks hooks = Hooks.IDENTITY;
+
+    private Envelope() {}
+
+    public static void setHooks(Hooks hooks) {
+        Envelope.hooks = java.util.Objects.requireNonNull(hooks);
+    }
+
+    public static void setHooks(UnaryOperator<byte[]> wrap, UnaryOperator<byte[]> unwrap) {
+        setHooks(new Hooks(wrap, unwrap));
+    }
+
+    public static Hooks getHooks() {
+        return hooks;
+    }
+
+    public static byte[] wrap(byte[] bytes) {
+        return hooks.wrap.apply(bytes);
+    }
+
+    public static byte[] unwrap(byte[] bytes) {
+        return hooks.unwrap.apply(bytes);
+    }
+}
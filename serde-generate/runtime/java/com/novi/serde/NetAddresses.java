@@ -0,0 +1,54 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.serde;
+
+import java.net.InetAddress;
+import java.net.InetSocketAddress;
+import java.net.UnknownHostException;
+
+/**
+ * Conversions between the network addresses of `java.net` and the wire format of the Rust types
+ * `std::net::IpAddr` and `std::net::SocketAddr`: a variant index (0 for IPv4, 1 for IPv6)
+ * followed by the octets of the address, then the port number for socket addresses.
+ */
+public final class NetAddresses {
+    private NetAddresses() {}
+
+    public static void serialize_ip_addr(InetAddress value, Serializer serializer) throws SerializationError {
+        byte[] octets = value.getAddress();
+        serializer.serialize_variant_index(octets.length == 4 ? 0 : 1);
+        for (byte octet : octets) {
+            serializer.serialize_u8(octet);
+        }
+    }
+
+    public static InetAddress deserialize_ip_addr(Deserializer deserializer) throws DeserializationError {
+        int index = deserializer.deserialize_variant_index();
+        byte[] octets;
+        switch (index) {
+            case 0: octets = new byte[4]; break;
+            case 1: octets = new byte[16]; break;
+            default: throw new DeserializationError("Unknown variant index for IpAddr: " + index);
+        }
+        for (int i = 0; i < octets.length; i++) {
+            octets[i] = deserializer.deserialize_u8();
+        }
+        try {
+            return InetAddress.getByAddress(octets);
+        } catch (UnknownHostException e) {
+            throw new DeserializationError("Invalid IP address");
+        }
+    }
+
+    public static void serialize_socket_addr(InetSocketAddress value, Serializer serializer) throws SerializationError {
+        serialize_ip_addr(value.getAddress(), serializer);
+        serializer.serialize_u16((short) value.getPort());
+    }
+
+    public static InetSocketAddress deserialize_socket_addr(Deserializer deserializer) throws DeserializationError {
+        InetAddress address = deserialize_ip_addr(deserializer);
+        short port = deserializer.deserialize_u16();
+        return new InetSocketAddress(address, Short.toUnsignedInt(port));
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.serde;
+
+import java.io.IOException;
+import java.nio.charset.StandardCharsets;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * The encoding of a value of a named type, as written by `serdegen test-vectors`: one vector per
+ * line, made of four fields separated by tabs (type name, encoding, hexadecimal bytes, and JSON
+ * value). Empty lines and lines starting with `#` are ignored.
+ */
+public final class TestVector {
+    public final String typeName;
+    public final String encoding;
+    public final byte[] bytes;
+    public final String json;
+
+    public TestVector(String typeName, String encoding, byte[] bytes, String json) {
+        this.typeName = typeName;
+        this.encoding = encoding;
+        this.bytes = bytes;
+        this.json = json;
+    }
+
+    public static List<TestVector> parse(String content) {
+        List<TestVector> vectors = new ArrayList<>();
+        String[] lines = content.split("\r?\n");
+        for (int index = 0; index < lines.length; index++) {
+            String line = lines[index];
+            if (line.trim().isEmpty() || line.startsWith("#")) {
+                continue;
+            }
+            String[] fields = line.split("\t", 4);
+            if (fields.length != 4 || fields[2].length() % 2 != 0) {
+                throw new IllegalArgumentException("Invalid test vector at line " + (index + 1));
+            }
+            byte[] bytes = new byte[fields[2].length() / 2];
+            for (int i = 0; i < bytes.length; i++) {
+                bytes[i] = (byte) Integer.parseInt(fields[2].substring(2 * i, 2 * i + 2), 16);
+            }
+            vectors.add(new TestVector(fields[0], fields[1], bytes, fields[3]));
+        }
+        return vectors;
+    }
+
+    public static List<TestVector> load(Path path) throws IOException {
+        return parse(new String(Files.readAllBytes(path), StandardCharsets.UTF_8));
+    }
+}
@@ -9,12 +9,17 @@ Note: This internal module is currently only meant to share code between the BCS
 
 import dataclasses
 import collections
+import datetime
 import io
+import ipaddress
 import typing
 from typing import get_type_hints
 
 import serde_types as st
 
+# Origin of `datetime.datetime` values, which are serialized as durations since the UNIX epoch.
+UNIX_EPOCH = datetime.datetime(1970, 1, 1, tzinfo=datetime.timezone.utc)
+
 
 @dataclasses.dataclass
 class BinarySerializer:
@@ -47,6 +52,10 @@ class BinarySerializer:
             st.char: self.serialize_char,
             str: self.serialize_str,
             bytes: self.serialize_bytes,
+            datetime.timedelta: self.serialize_duration,
+            datetime.datetime: self.serialize_system_time,
+            st.ip_addr: self.serialize_ip_addr,
+            st.socket_addr: self.serialize_socket_addr,
         }
 
     def serialize_bytes(self, value: bytes):
@@ -101,6 +110,31 @@ class BinarySerializer:
     def serialize_char(self, value: st.char):
         raise NotImplementedError
 
+    def serialize_duration(self, value: datetime.timedelta):
+        if value < datetime.timedelta(0):
+            raise st.SerializationError("Negative duration", value)
+        self.serialize_u64(st.uint64(value.days * 86400 + value.seconds))
+        self.serialize_u32(st.uint32(value.microseconds * 1000))
+
+    def serialize_system_time(self, value: datetime.datetime):
+        if value.tzinfo is None:
+            value = value.replace(tzinfo=datetime.timezone.utc)
+        self.serialize_duration(value - UNIX_EPOCH)
+
+    def serialize_ip_addr(self, value: st.ip_addr):
+        if isinstance(value, ipaddress.IPv4Address):
+            self.serialize_variant_index(0)
+        elif isinstance(value, ipaddress.IPv6Address):
+            self.serialize_variant_index(1)
+        else:
+            raise st.SerializationError("Wrong Value for the type", value, st.ip_addr)
+        self.output.write(value.packed)
+
+    def serialize_socket_addr(self, value: st.socket_addr):
+        address, port = value
+        self.serialize_ip_addr(address)
+        self.serialize_u16(st.uint16(port))
+
     def get_buffer_offset(self) -> int:
         return len(self.output.getbuffer())
 
@@ -131,6 +165,10 @@ class BinarySerializer:
         if obj_type in self.primitive_type_serializer:
             self.primitive_type_serializer[obj_type](obj)
 
+        elif hasattr(obj_type, "SCALE"):  # Fixed-point decimal
+            mantissa = st.decimal_to_mantissa(obj, obj_type.SCALE)
+            self.serialize_any(obj_type.MANTISSA(mantissa), obj_type.MANTISSA)
+
         elif hasattr(obj_type, "__origin__"):  # Generic type
             types = getattr(obj_type, "__args__")
 
@@ -141,6 +179,16 @@ class BinarySerializer:
                 for item in obj:
                     self.serialize_any(item, item_type)
 
+            elif getattr(obj_type, "__origin__") == frozenset:  # Set
+                assert len(types) == 1
+                item_type = types[0]
+                self.serialize_len(len(obj))
+                offsets = []
+                for item in obj:
+                    offsets.append(self.get_buffer_offset())
+                    self.serialize_any(item, item_type)
+                self.sort_map_entries(offsets)
+
             elif getattr(obj_type, "__origin__") == tuple:  # Tuple
                 for i in range(len(obj)):
                     self.serialize_any(obj[i], types[i])
@@ -153,7 +201,10 @@ class BinarySerializer:
                     self.output.write(b"\x01")
                     self.serialize_any(obj, types[0])
 
-            elif getattr(obj_type, "__origin__") == dict:  # Map
+            elif getattr(obj_type, "__origin__") in (
+                dict,
+                collections.abc.Mapping,
+            ):  # Map
                 assert len(types) == 2
                 self.serialize_len(len(obj))
                 offsets = []
@@ -174,7 +225,10 @@ class BinarySerializer:
                     raise st.SerializationError(
                         "Wrong Value for the type", obj, obj_type
                     )
-                self.serialize_variant_index(obj.__class__.INDEX)
+                if hasattr(obj_type, "TAG"):  # Internally tagged enum
+                    self.serialize_str(obj.__class__.NAME)
+                else:
+                    self.serialize_variant_index(obj.__class__.INDEX)
                 # Proceed to variant
                 obj_type = obj_type.VARIANTS[obj.__class__.INDEX]
                 if not dataclasses.is_dataclass(obj_type):
@@ -226,6 +280,10 @@ class BinaryDeserializer:
             st.char: self.deserialize_char,
             str: self.deserialize_str,
             bytes: self.deserialize_bytes,
+            datetime.timedelta: self.deserialize_duration,
+            datetime.datetime: self.deserialize_system_time,
+            st.ip_addr: self.deserialize_ip_addr,
+            st.socket_addr: self.deserialize_socket_addr,
         }
 
     def read(self, length: int) -> bytes:
@@ -269,6 +327,28 @@ class BinaryDeserializer:
     def deserialize_u64(self) -> st.uint64:
         return st.uint64(int.from_bytes(self.read(8), byteorder="little", signed=False))
 
+    def deserialize_duration(self) -> datetime.timedelta:
+        seconds = self.deserialize_u64()
+        nanos = self.deserialize_u32()
+        if nanos >= 1000000000:
+            raise st.DeserializationError("Invalid number of nanoseconds", nanos)
+        return datetime.timedelta(seconds=int(seconds), microseconds=int(nanos) // 1000)
+
+    def deserialize_system_time(self) -> datetime.datetime:
+        return UNIX_EPOCH + self.deserialize_duration()
+
+    def deserialize_ip_addr(self) -> st.ip_addr:
+        index = self.deserialize_variant_index()
+        if index == 0:
+            return ipaddress.IPv4Address(self.read(4))
+        if index == 1:
+            return ipaddress.IPv6Address(self.read(16))
+        raise st.DeserializationError("Unexpected variant index", index)
+
+    def deserialize_socket_addr(self) -> st.socket_addr:
+        address = self.deserialize_ip_addr()
+        return (address, self.deserialize_u16())
+
     def deserialize_u128(self) -> st.uint128:
         return st.uint128(
             int.from_bytes(self.read(16), byteorder="little", signed=False)
@@ -332,6 +412,10 @@ class BinaryDeserializer:
         if obj_type in self.primitive_type_deserializer:
             return self.primitive_type_deserializer[obj_type]()
 
+        elif hasattr(obj_type, "SCALE"):  # Fixed-point decimal
+            mantissa = self.deserialize_any(obj_type.MANTISSA)
+            return st.mantissa_to_decimal(int(mantissa), obj_type.SCALE)
+
         elif hasattr(obj_type, "__origin__"):  # Generic type
             types = getattr(obj_type, "__args__")
             if getattr(obj_type, "__origin__") == collections.abc.Sequence:  # Sequence
@@ -345,6 +429,30 @@ class BinaryDeserializer:
 
                 return result
 
+            elif getattr(obj_type, "__origin__") == frozenset:  # Set
+                assert len(types) == 1
+                item_type = types[0]
+                length = self.deserialize_len()
+                result = set()
+                previous_item_slice = None
+                for i in range(0, length):
+                    item_start = self.get_buffer_offset()
+                    item = self.deserialize_any(item_type)
+                    item_end = self.get_buffer_offset()
+
+                    item_slice = (item_start, item_end)
+                    if previous_item_slice is not None:
+                        self.check_that_key_slices_are_increasing(
+                            previous_item_slice, item_slice
+                        )
+                    previous_item_slice = item_slice
+
+                    result.add(item)
+
+                if len(result) != length:
+                    raise st.DeserializationError("Duplicate elements in set")
+                return frozenset(result)
+
             elif getattr(obj_type, "__origin__") == tuple:  # Tuple
                 result = []
                 for i in range(len(types)):
@@ -362,7 +470,10 @@ class BinaryDeserializer:
                 else:
                     raise st.DeserializationError("Wrong tag for Option value")
 
-            elif getattr(obj_type, "__origin__") == dict:  # Map
+            elif getattr(obj_type, "__origin__") in (
+                dict,
+                collections.abc.Mapping,
+            ):  # Map
                 assert len(types) == 2
                 length = self.deserialize_len()
                 result = dict()
@@ -403,6 +514,12 @@ class BinaryDeserializer:
 
             # handle variant
             elif hasattr(obj_type, "VARIANTS"):
+                if hasattr(obj_type, "TAG"):  # Internally tagged enum
+                    name = self.deserialize_str()
+                    for new_type in obj_type.VARIANTS:
+                        if new_type.NAME == name:
+                            return self.deserialize_any(new_type)
+                    raise st.DeserializationError("Unexpected variant name", name)
                 variant_index = self.deserialize_variant_index()
                 if variant_index not in range(len(obj_type.VARIANTS)):
                     raise st.DeserializationError(
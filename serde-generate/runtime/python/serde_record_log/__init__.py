@@ -0,0 +1,127 @@
+# Copyright (c) Facebook, Inc. and its affiliates
+# SPDX-License-Identifier: MIT OR Apache-2.0
+
+"""
+Record logs hold a stream of serialized values of a single root type.
+
+Layout (all integers are little-endian):
+* header: magic b"SRLG", format version (u8), encoding (u8: 0 = BCS, 1 = Bincode),
+  schema fingerprint (u64);
+* records: length (u32) followed by the serialized value;
+* optional index: the marker 0xFFFFFFFF (u32), the number of records (u64), the offset of
+  each record (u64), then a footer made of the offset of the marker (u64) and b"SRIX".
+"""
+
+import struct
+import typing
+
+import serde_types as st
+
+MAGIC = b"SRLG"
+INDEX_MAGIC = b"SRIX"
+VERSION = 1
+BCS = 0
+BINCODE = 1
+
+_INDEX_MARKER = 0xFFFFFFFF
+_HEADER = struct.Struct("<4sBBQ")
+_LENGTH = struct.Struct("<I")
+_INDEX_HEADER = struct.Struct("<IQ")
+_FOOTER = struct.Struct("<Q4s")
+
+T = typing.TypeVar("T")
+
+
+class RecordLogWriter(typing.Generic[T]):
+    """Write values of type `T` into a binary file object."""
+
+    def __init__(
+        self,
+        output: typing.BinaryIO,
+        fingerprint: int,
+        encoding: int,
+        serialize: typing.Callable[[T], bytes],
+    ):
+        self.output = output
+        self.serialize = serialize
+        self.position = _HEADER.size
+        self.offsets = []  # type: typing.List[int]
+        output.write(_HEADER.pack(MAGIC, VERSION, encoding, fingerprint))
+
+    def write(self, value: T) -> None:
+        payload = self.serialize(value)
+        if len(payload) >= _INDEX_MARKER:
+            raise st.SerializationError("Record is too large")
+        self.output.write(_LENGTH.pack(len(payload)))
+        self.output.write(payload)
+        self.offsets.append(self.position)
+        self.position += _LENGTH.size + len(payload)
+
+    def finish(self, index: bool = True) -> None:
+        """Write the index of the records (if requested)."""
+        if index:
+            self.output.write(_INDEX_HEADER.pack(_INDEX_MARKER, len(self.offsets)))
+            self.output.write(struct.pack("<%dQ" % len(self.offsets), *self.offsets))
+            self.output.write(_FOOTER.pack(self.position, INDEX_MAGIC))
+        self.output.flush()
+
+
+class RecordLogReader(typing.Generic[T]):
+    """Read values of type `T` from the bytes of a record log (e.g. a memory-mapped file)."""
+
+    def __init__(
+        self,
+        content: bytes,
+        fingerprint: int,
+        encoding: int,
+        deserialize: typing.Callable[[bytes], T],
+    ):
+        if len(content) < _HEADER.size:
+            raise st.DeserializationError("Not a record log")
+        magic, version, actual_encoding, actual_fingerprint = _HEADER.unpack_from(content)
+        if magic != MAGIC:
+            raise st.DeserializationError("Not a record log")
+        if version != VERSION:
+            raise st.DeserializationError("Unsupported record log version")
+        if actual_encoding != encoding:
+            raise st.DeserializationError("Unexpected encoding of records")
+        if actual_fingerprint != fingerprint:
+            raise st.DeserializationError("Schema fingerprint does not match")
+        self.content = content
+        self.deserialize = deserialize
+
+    def __iter__(self) -> typing.Iterator[T]:
+        offset = _HEADER.size
+        while offset < len(self.content):
+            length = self._read_length(offset)
+            if length == _INDEX_MARKER:
+                return
+            yield self.record_at(offset)
+            offset += _LENGTH.size + length
+
+    def record_at(self, offset: int) -> T:
+        """Decode the record at the given offset (see `index`)."""
+        length = self._read_length(offset)
+        start = offset + _LENGTH.size
+        if length == _INDEX_MARKER or start + length > len(self.content):
+            raise st.DeserializationError("Invalid record offset")
+        return self.deserialize(self.content[start : start + length])
+
+    def index(self) -> typing.Optional[typing.List[int]]:
+        """Offsets of the records, if the log has an index."""
+        size = len(self.content)
+        if size < _HEADER.size + _FOOTER.size or self.content[-4:] != INDEX_MAGIC:
+            return None
+        marker, _ = _FOOTER.unpack_from(self.content, size - _FOOTER.size)
+        if marker + _INDEX_HEADER.size > size:
+            return None
+        tag, count = _INDEX_HEADER.unpack_from(self.content, marker)
+        start = marker + _INDEX_HEADER.size
+        if tag != _INDEX_MARKER or start + 8 * count + _FOOTER.size != size:
+            return None
+        return list(struct.unpack_from("<%dQ" % count, self.content, start))
+
+    def _read_length(self, offset: int) -> int:
+        if offset < 0 or offset + _LENGTH.size > len(self.content):
+            raise st.DeserializationError("Record is truncated")
+        return _LENGTH.unpack_from(self.content, offset)[0]
@@ -1,9 +1,12 @@
 # Copyright (c) Facebook, Inc. and its affiliates
 # SPDX-License-Identifier: MIT OR Apache-2.0
 
+import decimal
+import ipaddress
 import numpy as np
 from dataclasses import dataclass
 import typing
+import zlib
 
 
 class SerializationError(ValueError):
@@ -72,3 +75,115 @@ uint64 = np.uint64
 
 float32 = np.float32
 float64 = np.float64
+
+# Network addresses, as defined by `std::net::IpAddr` and `std::net::SocketAddr` in Rust.
+ip_addr = typing.Union[ipaddress.IPv4Address, ipaddress.IPv6Address]
+socket_addr = typing.Tuple[ip_addr, uint16]
+
+
+_fixed_point_types = {}  # type: typing.Dict[typing.Tuple[typing.Any, int], typing.Any]
+
+
+def fixed_point(mantissa: typing.Any, scale: int) -> typing.Any:
+    """Type of the `decimal.Decimal` values with at most `scale` fractional digits, encoded as
+    the signed integer `mantissa` holding the value multiplied by `10 ** scale`."""
+    key = (mantissa, scale)
+    if key not in _fixed_point_types:
+        _fixed_point_types[key] = type(
+            "fixed_point", (decimal.Decimal,), {"MANTISSA": mantissa, "SCALE": scale}
+        )
+    return _fixed_point_types[key]
+
+
+def decimal_to_mantissa(value: decimal.Decimal, scale: int) -> int:
+    """Exact integer value of `value * 10 ** scale`."""
+    sign, digits, exponent = decimal.Decimal(value).as_tuple()
+    if not isinstance(exponent, int):
+        raise SerializationError("Not a finite decimal number", value)
+    number = 0
+    for digit in digits:
+        number = number * 10 + digit
+    shift = exponent + scale
+    if shift >= 0:
+        number *= 10**shift
+    else:
+        number, remainder = divmod(number, 10**-shift)
+        if remainder != 0:
+            raise SerializationError("Too many fractional digits", value, scale)
+    return -number if sign else number
+
+
+def mantissa_to_decimal(mantissa: int, scale: int) -> decimal.Decimal:
+    """Exact decimal value of `mantissa * 10 ** -scale`."""
+    digits = tuple(int(digit) for digit in str(abs(mantissa)))
+    return decimal.Decimal((1 if mantissa < 0 else 0, digits, -scale))
+
+
+EnvelopeHook = typing.Callable[[bytes], bytes]
+
+
+def _identity(content: bytes) -> bytes:
+    return content
+
+
+_envelope_hooks = (_identity, _identity)  # type: typing.Tuple[EnvelopeHook, EnvelopeHook]
+
+
+def set_envelope_hooks(wrap: EnvelopeHook, unwrap: EnvelopeHook) -> None:
+    """Register the functions transforming serialized bytes (e.g. compression or encryption)
+    in generated code using envelope hooks. `unwrap` must reverse `wrap`."""
+    global _envelope_hooks
+    _envelope_hooks = (wrap, unwrap)
+
+
+def wrap_envelope(content: bytes) -> bytes:
+    return _envelope_hooks[0](content)
+
+
+def unwrap_envelope(content: bytes) -> bytes:
+    return _envelope_hooks[1](content)
+
+
+def append_checksum(content: bytes) -> bytes:
+    """Append the CRC-32 checksum of `content` (as in zlib) in little-endian order."""
+    return content + (zlib.crc32(content) & 0xFFFFFFFF).to_bytes(4, "little")
+
+
+def verify_checksum(content: bytes) -> bytes:
+    """Check and remove the CRC-32 checksum at the end of `content`."""
+    if len(content) < 4:
+        raise DeserializationError("Missing checksum")
+    payload, checksum = content[:-4], content[-4:]
+    if (zlib.crc32(payload) & 0xFFFFFFFF).to_bytes(4, "little") != checksum:
+        raise DeserializationError("Checksum mismatch")
+    return payload
+
+
+@dataclass(frozen=True)
+class TestVector:
+    """The encoding of a value of a named type, as written by `serdegen test-vectors`."""
+
+    type_name: str
+    encoding: str
+    bytes: bytes
+    json: str
+
+
+def parse_test_vectors(content: str) -> typing.List[TestVector]:
+    """Parse test vectors, given as lines of tab-separated fields: type name, encoding,
+    hexadecimal bytes, and JSON value. Empty lines and lines starting with `#` are ignored."""
+    vectors = []
+    for index, line in enumerate(content.splitlines()):
+        if not line.strip() or line.startswith("#"):
+            continue
+        fields = line.split("\t", 3)
+        if len(fields) != 4:
+            raise ValueError("Invalid test vector at line {}".format(index + 1))
+        vectors.append(TestVector(fields[0], fields[1], bytes.fromhex(fields[2]), fields[3]))
+    return vectors
+
+
+def load_test_vectors(path: str) -> typing.List[TestVector]:
+    """Read a file of test vectors."""
+    with open(path, encoding="utf-8") as f:
+        return parse_test_vectors(f.read())
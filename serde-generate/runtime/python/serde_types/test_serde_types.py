@@ -1,3 +1,4 @@
+import decimal
 import unittest
 import serde_types as st
 
@@ -28,3 +29,10 @@ class SerdeTypesTestCase(unittest.TestCase):
         self.assertEqual(str(st.char("a")), "a")
         with self.assertRaises(ValueError):
             st.char("ab")
+
+    def test_fixed_point(self):
+        self.assertIs(st.fixed_point(st.int64, 2), st.fixed_point(st.int64, 2))
+        self.assertEqual(st.decimal_to_mantissa(decimal.Decimal("-123.4"), 2), -12340)
+        self.assertEqual(st.mantissa_to_decimal(-12340, 2), decimal.Decimal("-123.40"))
+        with self.assertRaises(st.SerializationError):
+            st.decimal_to_mantissa(decimal.Decimal("0.001"), 2)
@@ -0,0 +1,208 @@
+// Record logs hold a stream of serialized values of a single root type.
+//
+// Layout (all integers are little-endian):
+// * header: magic `SRLG`, format version (u8), encoding (u8: 0 = BCS, 1 = Bincode),
+//   schema fingerprint (u64);
+// * records: length (u32) followed by the serialized value;
+// * optional index: the marker 0xFFFFFFFF (u32), the number of records (u64), the offset
+//   of each record (u64), then a footer made of the offset of the marker (u64) and `SRIX`.
+
+use std::io::{Error, ErrorKind, Result, Write};
+
+pub const MAGIC: &[u8; 4] = b"SRLG";
+pub const INDEX_MAGIC: &[u8; 4] = b"SRIX";
+pub const VERSION: u8 = 1;
+const INDEX_MARKER: u32 = 0xFFFF_FFFF;
+const HEADER_SIZE: usize = 14;
+const FOOTER_SIZE: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Bcs = 0,
+    Bincode = 1,
+}
+
+pub fn invalid_data<E>(error: E) -> Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let mut buffer = [0u8; 4];
+    buffer.copy_from_slice(bytes.get(offset..offset.checked_add(4)?)?);
+    Some(u32::from_le_bytes(buffer))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(bytes.get(offset..offset.checked_add(8)?)?);
+    Some(u64::from_le_bytes(buffer))
+}
+
+/// Write values of type `T` into a record log.
+pub struct RecordLogWriter<W, T> {
+    writer: W,
+    serialize: fn(&T) -> Result<Vec<u8>>,
+    position: u64,
+    offsets: Vec<u64>,
+}
+
+impl<W: Write, T> RecordLogWriter<W, T> {
+    /// Write the header of a new record log.
+    pub fn new(
+        mut writer: W,
+        fingerprint: u64,
+        encoding: Encoding,
+        serialize: fn(&T) -> Result<Vec<u8>>,
+    ) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, encoding as u8])?;
+        writer.write_all(&fingerprint.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            serialize,
+            position: HEADER_SIZE as u64,
+            offsets: Vec::new(),
+        })
+    }
+
+    /// Append a record.
+    pub fn write(&mut self, value: &T) -> Result<()> {
+        let payload = (self.serialize)(value)?;
+        if payload.len() >= INDEX_MARKER as usize {
+            return Err(invalid_data("Record is too large"));
+        }
+        self.writer
+            .write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&payload)?;
+        self.offsets.push(self.position);
+        self.position += 4 + payload.len() as u64;
+        Ok(())
+    }
+
+    /// Write the index of the records (if requested), then return the underlying writer.
+    pub fn finish(mut self, index: bool) -> Result<W> {
+        if index {
+            self.writer.write_all(&INDEX_MARKER.to_le_bytes())?;
+            self.writer
+                .write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+            for offset in &self.offsets {
+                self.writer.write_all(&offset.to_le_bytes())?;
+            }
+            self.writer.write_all(&self.position.to_le_bytes())?;
+            self.writer.write_all(INDEX_MAGIC)?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Read values of type `T` from the bytes of a record log (e.g. a memory-mapped file).
+pub struct RecordLogReader<'a, T> {
+    bytes: &'a [u8],
+    deserialize: fn(&[u8]) -> Result<T>,
+}
+
+impl<'a, T> RecordLogReader<'a, T> {
+    /// Check the header of a record log.
+    pub fn new(
+        bytes: &'a [u8],
+        fingerprint: u64,
+        encoding: Encoding,
+        deserialize: fn(&[u8]) -> Result<T>,
+    ) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+            return Err(invalid_data("Not a record log"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid_data("Unsupported record log version"));
+        }
+        if bytes[5] != encoding as u8 {
+            return Err(invalid_data("Unexpected encoding of records"));
+        }
+        if read_u64(bytes, 6) != Some(fingerprint) {
+            return Err(invalid_data("Schema fingerprint does not match"));
+        }
+        Ok(Self { bytes, deserialize })
+    }
+
+    /// Iterate over the records in order.
+    pub fn records(&self) -> Records<'a, T> {
+        Records {
+            bytes: self.bytes,
+            deserialize: self.deserialize,
+            offset: HEADER_SIZE,
+        }
+    }
+
+    /// Decode the record at the given offset (see `index`).
+    pub fn record_at(&self, offset: u64) -> Result<T> {
+        let offset = offset as usize;
+        let len = match read_u32(self.bytes, offset) {
+            Some(len) if len != INDEX_MARKER => len as usize,
+            _ => return Err(invalid_data("Invalid record offset")),
+        };
+        match self.bytes.get(offset + 4..offset + 4 + len) {
+            Some(payload) => (self.deserialize)(payload),
+            None => Err(invalid_data("Record is truncated")),
+        }
+    }
+
+    /// Offsets of the records, if the log has an index.
+    pub fn index(&self) -> Option<Vec<u64>> {
+        let len = self.bytes.len();
+        if len < HEADER_SIZE + FOOTER_SIZE || &self.bytes[len - 4..] != INDEX_MAGIC {
+            return None;
+        }
+        let marker = read_u64(self.bytes, len - FOOTER_SIZE)? as usize;
+        if read_u32(self.bytes, marker)? != INDEX_MARKER {
+            return None;
+        }
+        let count = read_u64(self.bytes, marker + 4)? as usize;
+        let start = marker + 12;
+        if count.checked_mul(8)? != len.checked_sub(start + FOOTER_SIZE)? {
+            return None;
+        }
+        (0..count)
+            .map(|i| read_u64(self.bytes, start + 8 * i))
+            .collect()
+    }
+}
+
+/// Iterator over the records of a log.
+pub struct Records<'a, T> {
+    bytes: &'a [u8],
+    deserialize: fn(&[u8]) -> Result<T>,
+    offset: usize,
+}
+
+impl<'a, T> Iterator for Records<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytes.len() {
+            return None;
+        }
+        let result = match read_u32(self.bytes, self.offset) {
+            Some(INDEX_MARKER) => return None,
+            Some(len) => match self
+                .bytes
+                .get(self.offset + 4..self.offset + 4 + len as usize)
+            {
+                Some(payload) => {
+                    self.offset += 4 + len as usize;
+                    (self.deserialize)(payload)
+                }
+                None => Err(invalid_data("Record is truncated")),
+            },
+            None => Err(invalid_data("Record is truncated")),
+        };
+        if result.is_err() {
+            // Stop after the first error.
+            self.offset = self.bytes.len();
+        }
+        Some(result)
+    }
+}
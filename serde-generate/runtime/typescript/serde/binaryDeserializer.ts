@@ -1,11 +1,14 @@
 import { Deserializer } from './deserializer';
-import util from 'util';
 
+/**
+ * Deserializers hold the state of a single decoding and work on a copy of their input. The
+ * runtime holds no other mutable state, so that decodings may be interleaved (e.g. in async
+ * tasks) or run in workers.
+ */
 export abstract class BinaryDeserializer implements Deserializer {
   private static readonly BIG_32 = BigInt(32);
   private static readonly BIG_64 = BigInt(64);
-  private static readonly textDecoder =
-      typeof window === 'undefined' ? new util.TextDecoder() : new TextDecoder();
+  private static readonly textDecoder = new TextDecoder();
   public buffer: ArrayBuffer;
   public offset: number;
 
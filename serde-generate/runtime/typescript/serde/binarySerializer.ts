@@ -1,6 +1,10 @@
 import { Serializer } from './serializer';
-import util from 'util';
 
+/**
+ * Serializers hold the state of a single encoding, and `getBytes` returns a copy of their
+ * output. The runtime holds no other mutable state, so that encodings may be interleaved
+ * (e.g. in async tasks) or run in workers.
+ */
 export abstract class BinarySerializer implements Serializer {
     private static readonly BIG_32 = BigInt(32);
     private static readonly BIG_64 = BigInt(64);
@@ -11,8 +15,7 @@ export abstract class BinarySerializer implements Serializer {
     private static readonly BIG_32Fs = BigInt('4294967295');
     private static readonly BIG_64Fs = BigInt('18446744073709551615');
 
-    private static readonly textEncoder =
-        typeof window === 'undefined' ? new util.TextEncoder() : new TextEncoder();
+    private static readonly textEncoder = new TextEncoder();
 
     private buffer: ArrayBuffer;
     private offset: number;
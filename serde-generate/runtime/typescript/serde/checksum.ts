@@ -0,0 +1,34 @@
+/**
+ * CRC-32 checksums (as in zlib) appended in little-endian order to the output of checked
+ * serialization functions.
+ */
+
+export function crc32(bytes: Uint8Array): number {
+  let crc = 0xffffffff;
+  for (const byte of bytes) {
+    crc ^= byte;
+    for (let k = 0; k < 8; k++) {
+      crc = (crc >>> 1) ^ (0xedb88320 & -(crc & 1));
+    }
+  }
+  return (crc ^ 0xffffffff) >>> 0;
+}
+
+export function appendChecksum(bytes: Uint8Array): Uint8Array {
+  const result = new Uint8Array(bytes.length + 4);
+  result.set(bytes);
+  new DataView(result.buffer).setUint32(bytes.length, crc32(bytes), true);
+  return result;
+}
+
+export function verifyChecksum(bytes: Uint8Array): Uint8Array {
+  if (bytes.length < 4) {
+    throw new Error("Missing checksum");
+  }
+  const payload = bytes.subarray(0, bytes.length - 4);
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  if (view.getUint32(payload.length, true) !== crc32(payload)) {
+    throw new Error("Checksum mismatch");
+  }
+  return payload;
+}
@@ -0,0 +1,34 @@
+/**
+ * Hooks transforming serialized bytes (e.g. compression or encryption) in generated code using
+ * envelope hooks. The `unwrap` hook must reverse the `wrap` hook.
+ *
+ * Both hooks are held by a single frozen `EnvelopeHooks` object, which is replaced as a whole
+ * by `setEnvelopeHooks`.
+ */
+
+export type EnvelopeHook = (bytes: Uint8Array) => Uint8Array;
+
+export interface EnvelopeHooks {
+  readonly wrap: EnvelopeHook;
+  readonly unwrap: EnvelopeHook;
+}
+
+const identity: EnvelopeHook = (bytes) => bytes;
+
+let hooks: EnvelopeHooks = Object.freeze({ wrap: identity, unwrap: identity });
+
+export function setEnvelopeHooks(wrap: EnvelopeHook, unwrap: EnvelopeHook): void {
+  hooks = Object.freeze({ wrap, unwrap });
+}
+
+export function getEnvelopeHooks(): EnvelopeHooks {
+  return hooks;
+}
+
+export function wrapEnvelope(bytes: Uint8Array): Uint8Array {
+  return hooks.wrap(bytes);
+}
+
+export function unwrapEnvelope(bytes: Uint8Array): Uint8Array {
+  return hooks.unwrap(bytes);
+}
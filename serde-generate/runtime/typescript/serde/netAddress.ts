@@ -0,0 +1,127 @@
+/**
+ * Network addresses of Rust (`std::net::IpAddr` and `std::net::SocketAddr`), represented by
+ * their usual text format, e.g. `127.0.0.1`, `::1`, `127.0.0.1:8080` or `[::1]:8080`.
+ */
+
+import { Serializer } from './serializer';
+import { Deserializer } from './deserializer';
+
+function parseIpv4(value: string): number[] | null {
+  const parts = value.split('.');
+  if (parts.length !== 4 || !parts.every((part) => /^\d{1,3}$/.test(part))) {
+    return null;
+  }
+  const octets = parts.map((part) => Number(part));
+  return octets.every((octet) => octet <= 255) ? octets : null;
+}
+
+function parseIpv6(value: string): number[] | null {
+  let text = value;
+  let suffix: number[] = [];
+  // An IPv4 address may end the address, e.g. `::ffff:127.0.0.1`.
+  if (text.includes('.')) {
+    const colon = text.lastIndexOf(':');
+    const ipv4 = parseIpv4(text.slice(colon + 1));
+    if (ipv4 === null) {
+      return null;
+    }
+    suffix = ipv4;
+    text = text.slice(0, colon + 1);
+    if (!text.endsWith('::')) {
+      text = text.slice(0, -1);
+    }
+  }
+  const count = 8 - suffix.length / 2;
+  const halves = text.split('::');
+  if (halves.length > 2) {
+    return null;
+  }
+  const split = (part: string) => (part === '' ? [] : part.split(':'));
+  let groups = split(halves[0]);
+  if (halves.length === 2) {
+    const tail = split(halves[1]);
+    if (groups.length + tail.length >= count) {
+      return null;
+    }
+    groups = [...groups, ...Array(count - groups.length - tail.length).fill('0'), ...tail];
+  }
+  if (groups.length !== count || !groups.every((group) => /^[0-9a-fA-F]{1,4}$/.test(group))) {
+    return null;
+  }
+  const octets: number[] = [];
+  groups.forEach((group) => {
+    const word = parseInt(group, 16);
+    octets.push(word >> 8, word & 0xff);
+  });
+  return [...octets, ...suffix];
+}
+
+function formatIpv6(octets: number[]): string {
+  const words = Array.from({ length: 8 }, (_, i) => (octets[2 * i] << 8) | octets[2 * i + 1]);
+  // Compress the longest run of at least two zero words, as recommended by RFC 5952.
+  let start = -1;
+  let length = 0;
+  for (let i = 0; i < 8; ) {
+    let j = i;
+    while (j < 8 && words[j] === 0) {
+      j++;
+    }
+    if (j - i > length && j - i >= 2) {
+      start = i;
+      length = j - i;
+    }
+    i = j === i ? i + 1 : j;
+  }
+  const hex = (ws: number[]) => ws.map((w) => w.toString(16)).join(':');
+  if (start < 0) {
+    return hex(words);
+  }
+  return hex(words.slice(0, start)) + '::' + hex(words.slice(start + length));
+}
+
+export function serializeIpAddr(value: string, serializer: Serializer): void {
+  const ipv4 = parseIpv4(value);
+  if (ipv4 !== null) {
+    serializer.serializeVariantIndex(0);
+    ipv4.forEach((octet) => serializer.serializeU8(octet));
+    return;
+  }
+  const ipv6 = parseIpv6(value);
+  if (ipv6 === null) {
+    throw new Error('Invalid IP address: ' + value);
+  }
+  serializer.serializeVariantIndex(1);
+  ipv6.forEach((octet) => serializer.serializeU8(octet));
+}
+
+export function deserializeIpAddr(deserializer: Deserializer): string {
+  const index = deserializer.deserializeVariantIndex();
+  switch (index) {
+    case 0:
+      return Array.from({ length: 4 }, () => deserializer.deserializeU8()).join('.');
+    case 1:
+      return formatIpv6(Array.from({ length: 16 }, () => deserializer.deserializeU8()));
+    default:
+      throw new Error('Unknown variant index for IpAddr: ' + index);
+  }
+}
+
+export function serializeSocketAddr(value: string, serializer: Serializer): void {
+  const match = /^(?:\[([^\]]+)\]|([^:]+)):(\d{1,5})$/.exec(value);
+  const port = match === null ? NaN : Number(match[3]);
+  if (match === null || port > 0xffff) {
+    throw new Error('Invalid socket address: ' + value);
+  }
+  const ip = match[1] !== undefined ? match[1] : match[2];
+  if (match[1] !== undefined ? parseIpv6(ip) === null : parseIpv4(ip) === null) {
+    throw new Error('Invalid socket address: ' + value);
+  }
+  serializeIpAddr(ip, serializer);
+  serializer.serializeU16(port);
+}
+
+export function deserializeSocketAddr(deserializer: Deserializer): string {
+  const ip = deserializeIpAddr(deserializer);
+  const port = deserializer.deserializeU16();
+  return ip.includes(':') ? `[${ip}]:${port}` : `${ip}:${port}`;
+}
@@ -0,0 +1,155 @@
+/**
+ * Record logs hold a stream of serialized values of a single root type.
+ *
+ * Layout (all integers are little-endian):
+ * - header: magic "SRLG", format version (u8), encoding (u8: 0 = BCS, 1 = Bincode),
+ *   schema fingerprint (u64);
+ * - records: length (u32) followed by the serialized value;
+ * - optional index: the marker 0xFFFFFFFF (u32), the number of records (u64), the offset of
+ *   each record (u64), then a footer made of the offset of the marker (u64) and "SRIX".
+ */
+
+export enum RecordLogEncoding {
+  Bcs = 0,
+  Bincode = 1,
+}
+
+const MAGIC = [0x53, 0x52, 0x4c, 0x47]; // "SRLG"
+const INDEX_MAGIC = [0x53, 0x52, 0x49, 0x58]; // "SRIX"
+const VERSION = 1;
+const INDEX_MARKER = 0xffffffff;
+const HEADER_SIZE = 14;
+const FOOTER_SIZE = 12;
+
+/** Write values of type `T`. The content of the log is returned by `finish`. */
+export class RecordLogWriter<T> {
+  private chunks: Uint8Array[] = [];
+  private position = HEADER_SIZE;
+  private offsets: number[] = [];
+
+  constructor(
+    fingerprint: bigint,
+    encoding: RecordLogEncoding,
+    private serialize: (value: T) => Uint8Array
+  ) {
+    const header = new Uint8Array(HEADER_SIZE);
+    const view = new DataView(header.buffer);
+    header.set(MAGIC, 0);
+    view.setUint8(4, VERSION);
+    view.setUint8(5, encoding);
+    view.setBigUint64(6, fingerprint, true);
+    this.chunks.push(header);
+  }
+
+  public write(value: T): void {
+    const payload = this.serialize(value);
+    if (payload.length >= INDEX_MARKER) {
+      throw new Error("Record is too large");
+    }
+    const length = new Uint8Array(4);
+    new DataView(length.buffer).setUint32(0, payload.length, true);
+    this.chunks.push(length, payload);
+    this.offsets.push(this.position);
+    this.position += 4 + payload.length;
+  }
+
+  /** Append the index of the records (if requested) and return the content of the log. */
+  public finish(index = true): Uint8Array {
+    if (index) {
+      const trailer = new Uint8Array(12 + 8 * this.offsets.length + FOOTER_SIZE);
+      const view = new DataView(trailer.buffer);
+      view.setUint32(0, INDEX_MARKER, true);
+      view.setBigUint64(4, BigInt(this.offsets.length), true);
+      this.offsets.forEach((offset, i) => view.setBigUint64(12 + 8 * i, BigInt(offset), true));
+      view.setBigUint64(trailer.length - FOOTER_SIZE, BigInt(this.position), true);
+      trailer.set(INDEX_MAGIC, trailer.length - 4);
+      this.chunks.push(trailer);
+    }
+    const result = new Uint8Array(this.chunks.reduce((size, chunk) => size + chunk.length, 0));
+    let offset = 0;
+    for (const chunk of this.chunks) {
+      result.set(chunk, offset);
+      offset += chunk.length;
+    }
+    return result;
+  }
+}
+
+/** Read values of type `T` from the content of a record log. */
+export class RecordLogReader<T> implements Iterable<T> {
+  private view: DataView;
+
+  constructor(
+    private bytes: Uint8Array,
+    fingerprint: bigint,
+    encoding: RecordLogEncoding,
+    private deserialize: (record: Uint8Array) => T
+  ) {
+    this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+    if (bytes.length < HEADER_SIZE || MAGIC.some((byte, i) => bytes[i] !== byte)) {
+      throw new Error("Not a record log");
+    }
+    if (bytes[4] !== VERSION) {
+      throw new Error("Unsupported record log version");
+    }
+    if (bytes[5] !== encoding) {
+      throw new Error("Unexpected encoding of records");
+    }
+    if (this.view.getBigUint64(6, true) !== fingerprint) {
+      throw new Error("Schema fingerprint does not match");
+    }
+  }
+
+  public *[Symbol.iterator](): Iterator<T> {
+    let offset = HEADER_SIZE;
+    while (offset < this.bytes.length) {
+      const length = this.readLength(offset);
+      if (length === INDEX_MARKER) {
+        return;
+      }
+      yield this.recordAt(offset);
+      offset += 4 + length;
+    }
+  }
+
+  /** Decode the record at the given offset (see `index`). */
+  public recordAt(offset: number): T {
+    const length = this.readLength(offset);
+    const start = offset + 4;
+    if (length === INDEX_MARKER || start + length > this.bytes.length) {
+      throw new Error("Invalid record offset");
+    }
+    return this.deserialize(this.bytes.subarray(start, start + length));
+  }
+
+  /** Offsets of the records, if the log has an index. */
+  public index(): number[] | undefined {
+    const size = this.bytes.length;
+    if (
+      size < HEADER_SIZE + FOOTER_SIZE ||
+      INDEX_MAGIC.some((byte, i) => this.bytes[size - 4 + i] !== byte)
+    ) {
+      return undefined;
+    }
+    const marker = Number(this.view.getBigUint64(size - FOOTER_SIZE, true));
+    if (marker + 12 > size || this.view.getUint32(marker, true) !== INDEX_MARKER) {
+      return undefined;
+    }
+    const count = Number(this.view.getBigUint64(marker + 4, true));
+    if (marker + 12 + 8 * count + FOOTER_SIZE !== size) {
+      return undefined;
+    }
+    const offsets = [];
+    for (let i = 0; i < count; i++) {
+      offsets.push(Number(this.view.getBigUint64(marker + 12 + 8 * i, true)));
+    }
+    return offsets;
+  }
+
+  private readLength(offset: number): number {
+    if (offset < 0 || offset + 4 > this.bytes.length) {
+      throw new Error("Record is truncated");
+    }
+    return this.view.getUint32(offset, true);
+  }
+}
@@ -0,0 +1,46 @@
+/**
+ * Test vectors written by `serdegen test-vectors`: one vector per line, made of four fields
+ * separated by tabs (type name, encoding, hexadecimal bytes, and JSON value). Empty lines and
+ * lines starting with `#` are ignored.
+ */
+
+export interface TestVector {
+  typeName: string;
+  encoding: string;
+  bytes: Uint8Array;
+  json: string;
+}
+
+export function parseTestVectors(content: string): TestVector[] {
+  const vectors: TestVector[] = [];
+  content.split(/\r?\n/).forEach((line, index) => {
+    if (line.trim() === "" || line.startsWith("#")) {
+      return;
+    }
+    const fields = splitFields(line);
+    if (fields === null || !/^([0-9a-fA-F]{2})*$/.test(fields[2])) {
+      throw new Error("Invalid test vector at line " + (index + 1));
+    }
+    const bytes = new Uint8Array(fields[2].length / 2);
+    for (let i = 0; i < bytes.length; i++) {
+      bytes[i] = parseInt(fields[2].substring(2 * i, 2 * i + 2), 16);
+    }
+    vectors.push({ typeName: fields[0], encoding: fields[1], bytes, json: fields[3] });
+  });
+  return vectors;
+}
+
+function splitFields(line: string): string[] | null {
+  const fields: string[] = [];
+  let start = 0;
+  for (let i = 0; i < 3; i++) {
+    const end = line.indexOf("\t", start);
+    if (end < 0) {
+      return null;
+    }
+    fields.push(line.substring(start, end));
+    start = end + 1;
+  }
+  fields.push(line.substring(start));
+  return fields;
+}
@@ -0,0 +1,115 @@
+/**
+ * Utilities to decode large payloads off the main thread.
+ *
+ * In a worker script:
+ *
+ *     import { BcsDecoders } from '../my_module';
+ *     import { serveDecodeRequests } from '../serde/workerPool';
+ *     serveDecodeRequests(self, BcsDecoders);
+ *
+ * In the main thread:
+ *
+ *     const pool = new DecodeWorkerPool(() => new Worker('decoder.js'), 4);
+ *     const value = await pool.decode('MyType', buffer);
+ *
+ * Input buffers are transferred (not copied) to the workers, hence are no longer usable
+ * by the caller. Decoded values are sent back using structured cloning: they are received
+ * as plain data objects (class prototypes are not preserved).
+ */
+
+export type Decoder = (buffer: ArrayBuffer) => unknown;
+
+export interface DecodeRequest {
+  id: number;
+  typeName: string;
+  buffer: ArrayBuffer;
+}
+
+export interface DecodeResponse {
+  id: number;
+  value?: unknown;
+  error?: string;
+}
+
+/** Subset of the `Worker` API used by `DecodeWorkerPool`. */
+export interface WorkerLike {
+  onmessage: ((event: MessageEvent) => void) | null;
+  postMessage(message: DecodeRequest, transfer: Transferable[]): void;
+  terminate(): void;
+}
+
+/** Subset of the worker global scope used by `serveDecodeRequests`. */
+export interface WorkerScopeLike {
+  onmessage: ((event: MessageEvent) => void) | null;
+  postMessage(message: DecodeResponse): void;
+}
+
+interface PendingRequest {
+  resolve: (value: unknown) => void;
+  reject: (reason: Error) => void;
+}
+
+export class DecodeWorkerPool {
+  private readonly workers: WorkerLike[] = [];
+  private readonly pending = new Map<number, PendingRequest>();
+  private nextId = 0;
+  private nextWorker = 0;
+
+  constructor(createWorker: () => WorkerLike, size: number) {
+    if (size <= 0) {
+      throw new Error('The size of a worker pool must be positive');
+    }
+    for (let i = 0; i < size; i++) {
+      const worker = createWorker();
+      worker.onmessage = (event: MessageEvent) => this.onResponse(event.data as DecodeResponse);
+      this.workers.push(worker);
+    }
+  }
+
+  public decode<T>(typeName: string, buffer: ArrayBuffer): Promise<T> {
+    const id = this.nextId++;
+    const worker = this.workers[this.nextWorker];
+    this.nextWorker = (this.nextWorker + 1) % this.workers.length;
+    return new Promise<T>((resolve, reject) => {
+      this.pending.set(id, { resolve: resolve as (value: unknown) => void, reject });
+      worker.postMessage({ id, typeName, buffer }, [buffer]);
+    });
+  }
+
+  public terminate(): void {
+    this.workers.forEach((worker) => worker.terminate());
+    this.pending.forEach((request) => request.reject(new Error('Worker pool was terminated')));
+    this.pending.clear();
+  }
+
+  private onResponse(response: DecodeResponse): void {
+    const request = this.pending.get(response.id);
+    if (!request) {
+      return;
+    }
+    this.pending.delete(response.id);
+    if (response.error !== undefined) {
+      request.reject(new Error(response.error));
+    } else {
+      request.resolve(response.value);
+    }
+  }
+}
+
+export function serveDecodeRequests(
+    scope: WorkerScopeLike,
+    decoders: { [typeName: string]: Decoder }
+): void {
+  scope.onmessage = (event: MessageEvent) => {
+    const request = event.data as DecodeRequest;
+    try {
+      const decoder = decoders[request.typeName];
+      if (!decoder) {
+        throw new Error('Unknown type name: ' + request.typeName);
+      }
+      scope.postMessage({ id: request.id, value: decoder(request.buffer) });
+    } catch (e) {
+      scope.postMessage({ id: request.id, error: String(e) });
+    }
+  };
+}
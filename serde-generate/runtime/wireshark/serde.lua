@@ -0,0 +1,193 @@
+-- Copyright (c) Facebook, Inc. and its affiliates
+-- SPDX-License-Identifier: MIT OR Apache-2.0
+
+-- Parsers shared by generated dissectors. Every parser has the signature
+-- `parse(buffer, offset, tree, name)`: it adds the value found at `offset` to the
+-- protocol tree under the label `name`, then returns the offset of the next value.
+-- Requires `proto`, `read_len`, and `read_variant_index` to be defined beforehand.
+
+local function leaf(size, show)
+    return function(buffer, offset, tree, name)
+        local range = buffer(offset, size)
+        tree:add(proto, range, name .. ": " .. show(range))
+        return offset + size
+    end
+end
+
+local function show_uint(range) return tostring(range:le_uint()) end
+local function show_int(range) return tostring(range:le_int()) end
+local function show_uint64(range) return tostring(range:le_uint64()) end
+local function show_int64(range) return tostring(range:le_int64()) end
+local function show_float(range) return tostring(range:le_float()) end
+-- 128-bit integers are shown as little-endian bytes.
+local function show_hex(range) return "0x" .. range:bytes():tohex() .. " (little-endian)" end
+
+local parse_bool = leaf(1, function(range) return tostring(range:uint() ~= 0) end)
+local parse_u8 = leaf(1, show_uint)
+local parse_u16 = leaf(2, show_uint)
+local parse_u32 = leaf(4, show_uint)
+local parse_u64 = leaf(8, show_uint64)
+local parse_u128 = leaf(16, show_hex)
+local parse_i8 = leaf(1, show_int)
+local parse_i16 = leaf(2, show_int)
+local parse_i32 = leaf(4, show_int)
+local parse_i64 = leaf(8, show_int64)
+local parse_i128 = leaf(16, show_hex)
+local parse_f32 = leaf(4, show_float)
+local parse_f64 = leaf(8, show_float)
+
+local function parse_unit(buffer, offset, tree, name)
+    tree:add(proto, name .. ": ()")
+    return offset
+end
+
+local function parse_char(buffer, offset, tree, name)
+    local first = buffer(offset, 1):uint()
+    local size = 4
+    if first < 0x80 then size = 1 elseif first < 0xe0 then size = 2 elseif first < 0xf0 then size = 3 end
+    local range = buffer(offset, size)
+    tree:add(proto, range, name .. ": " .. range:string(ENC_UTF_8))
+    return offset + size
+end
+
+local function parse_str(buffer, offset, tree, name)
+    local len, start = read_len(buffer, offset)
+    local value = ""
+    if len > 0 then value = buffer(start, len):string(ENC_UTF_8) end
+    tree:add(proto, buffer(offset, start - offset + len), name .. ": \"" .. value .. "\"")
+    return start + len
+end
+
+local function parse_bytes(buffer, offset, tree, name)
+    local len, start = read_len(buffer, offset)
+    local value = ""
+    if len > 0 then value = buffer(start, len):bytes():tohex() end
+    tree:add(proto, buffer(offset, start - offset + len), name .. ": 0x" .. value)
+    return start + len
+end
+
+-- Add a subtree labelled `name` and let `parse_children` fill it from `offset`.
+local function group(buffer, offset, tree, name, parse_children)
+    local item
+    if offset < buffer:len() then
+        item = tree:add(proto, buffer(offset), name)
+    else
+        item = tree:add(proto, name)
+    end
+    local next_offset = parse_children(item, offset)
+    if offset < buffer:len() then
+        item:set_len(next_offset - offset)
+    end
+    return next_offset
+end
+
+local function parse_option(inner)
+    return function(buffer, offset, tree, name)
+        if buffer(offset, 1):uint() == 0 then
+            tree:add(proto, buffer(offset, 1), name .. ": None")
+            return offset + 1
+        end
+        return inner(buffer, offset + 1, tree, name)
+    end
+end
+
+local function parse_seq(inner)
+    return function(buffer, offset, tree, name)
+        return group(buffer, offset, tree, name, function(item, offset)
+            local len
+            len, offset = read_len(buffer, offset)
+            for i = 0, len - 1 do
+                offset = inner(buffer, offset, item, "[" .. i .. "]")
+            end
+            return offset
+        end)
+    end
+end
+
+local function parse_map(key, value)
+    return function(buffer, offset, tree, name)
+        return group(buffer, offset, tree, name, function(item, offset)
+            local len
+            len, offset = read_len(buffer, offset)
+            for i = 0, len - 1 do
+                offset = key(buffer, offset, item, "key[" .. i .. "]")
+                offset = value(buffer, offset, item, "value[" .. i .. "]")
+            end
+            return offset
+        end)
+    end
+end
+
+local function parse_array(inner, size)
+    return function(buffer, offset, tree, name)
+        return group(buffer, offset, tree, name, function(item, offset)
+            for i = 0, size - 1 do
+                offset = inner(buffer, offset, item, "[" .. i .. "]")
+            end
+            return offset
+        end)
+    end
+end
+
+local function parse_tuple(parsers)
+    return function(buffer, offset, tree, name)
+        return group(buffer, offset, tree, name, function(item, offset)
+            for i, parse in ipairs(parsers) do
+                offset = parse(buffer, offset, item, tostring(i - 1))
+            end
+            return offset
+        end)
+    end
+end
+
+-- `fields` is a list of pairs `{name, parser}`.
+local function parse_struct(fields)
+    return function(buffer, offset, tree, name)
+        return group(buffer, offset, tree, name, function(item, offset)
+            for _, field in ipairs(fields) do
+                offset = field[2](buffer, offset, item, field[1])
+            end
+            return offset
+        end)
+    end
+end
+
+-- `variants` maps variant indices to pairs `{name, parser}`.
+local function parse_enum(variants)
+    return function(buffer, offset, tree, name)
+        local index, start = read_variant_index(buffer, offset)
+        local variant = variants[index]
+        if variant == nil then
+            error("unknown variant index " .. index .. " in " .. name)
+        end
+        return group(buffer, offset, tree, name .. ": " .. variant[1], function(item, _)
+            return variant[2](buffer, start, item, variant[1])
+        end)
+    end
+end
+
+-- Internally tagged enums start with the variant name: `variants` maps variant names to parsers.
+local function parse_tagged_enum(variants)
+    return function(buffer, offset, tree, name)
+        local len, start = read_len(buffer, offset)
+        local variant = ""
+        if len > 0 then variant = buffer(start, len):string(ENC_UTF_8) end
+        local parse = variants[variant]
+        if parse == nil then
+            error("unknown variant " .. variant .. " in " .. name)
+        end
+        return group(buffer, offset, tree, name .. ": " .. variant, function(item, _)
+            return parse(buffer, start + len, item, variant)
+        end)
+    end
+end
+
+-- Parsers of containers, indexed by name.
+local types = {}
+
+-- Refer to a container parser that may not be defined yet.
+local function named(type_name)
+    return function(buffer, offset, tree, name)
+        return types[type_name](buffer, offset, tree, name)
+    end
+end
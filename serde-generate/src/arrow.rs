@@ -0,0 +1,312 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    analyzer, common,
+    indent::{IndentConfig, IndentedWriter},
+    CodeGeneratorConfig, Encoding,
+};
+use heck::SnakeCase;
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
+
+/// Main configuration object for the generation of Apache Arrow schemas.
+/// The generated Rust code uses the `arrow` crate. Each container `Foo` is given a
+/// function `foo_data_type()`; structs also have `foo_fields()` and `foo_schema()`.
+/// * Optional values are nullable, sequences are lists, and enums are dense unions
+///   indexed by variant.
+/// * 128-bit integers are stored as 16-byte little-endian binary values.
+/// * Recursive containers cannot be described by a finite Arrow type and are rejected.
+pub struct CodeGenerator<'a> {
+    /// Language-independent configuration.
+    config: &'a CodeGeneratorConfig,
+}
+
+/// Shared state for the generation of Arrow schemas.
+struct ArrowEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    /// Generator.
+    generator: &'a CodeGenerator<'a>,
+}
+
+impl<'a> CodeGenerator<'a> {
+    /// Create an Arrow schema generator for the given config.
+    pub fn new(config: &'a CodeGeneratorConfig) -> Self {
+        Self { config }
+    }
+
+    /// Write Rust functions returning the Arrow types of the containers.
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        let registry = &*common::without_skipped_fields(registry)?;
+        common::check_formats("Arrow", registry)?;
+        if let Some(name) = Self::find_recursive_container(registry) {
+            return Err(crate::Error::UnsupportedFormat {
+                language: "Arrow",
+                format: format!("{:?}", Format::TypeName(name.clone())),
+                container: name,
+                field: None,
+            });
+        }
+        let mut emitter = ArrowEmitter {
+            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            generator: self,
+        };
+        emitter.output_preamble()?;
+        for (name, format) in registry {
+            common::check_cancellation(self.config)?;
+            emitter.output_container(name, format)?;
+        }
+        Ok(())
+    }
+
+    fn find_recursive_container(registry: &Registry) -> Option<String> {
+        let dependencies = analyzer::get_dependency_map(registry).ok()?;
+        for start in dependencies.keys() {
+            // Look for a path from `start` back to itself.
+            let mut visited = BTreeSet::new();
+            let mut stack: Vec<&str> = dependencies[start].iter().cloned().collect();
+            while let Some(node) = stack.pop() {
+                if node == *start {
+                    return Some(node.to_string());
+                }
+                if visited.insert(node) {
+                    if let Some(next) = dependencies.get(node) {
+                        stack.extend(next.iter().cloned());
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a, T> ArrowEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_preamble(&mut self) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"//! Arrow schemas for the Serde formats of `{}`.
+#![allow(dead_code)]
+
+use arrow::datatypes::{{DataType, Field, Fields, Schema, UnionFields, UnionMode}};
+use std::sync::Arc;"#,
+            self.generator.config.module_name
+        )
+    }
+
+    fn output_comment(&mut self, name: &str) -> Result<()> {
+        let mut path: Vec<_> = self
+            .generator
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect();
+        path.push(name.to_string());
+        if let Some(doc) = self.generator.config.comment(&path) {
+            let text = textwrap::indent(&doc, "/// ").replace("\n\n", "\n///\n");
+            write!(self.out, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    fn quote_data_type(format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(x) => format!("{}_data_type()", x.to_snake_case()),
+            Unit => "DataType::Null".into(),
+            Bool => "DataType::Boolean".into(),
+            I8 => "DataType::Int8".into(),
+            I16 => "DataType::Int16".into(),
+            I32 => "DataType::Int32".into(),
+            I64 => "DataType::Int64".into(),
+            U8 => "DataType::UInt8".into(),
+            U16 => "DataType::UInt16".into(),
+            U32 => "DataType::UInt32".into(),
+            U64 => "DataType::UInt64".into(),
+            I128 | U128 => "DataType::FixedSizeBinary(16)".into(),
+            F32 => "DataType::Float32".into(),
+            F64 => "DataType::Float64".into(),
+            Char | Str => "DataType::Utf8".into(),
+            Bytes => "DataType::Binary".into(),
+            Option(format) => Self::quote_data_type(format),
+            Seq(format) | Set(format) => format!("DataType::List(Arc::new({}))", Self::quote_field("item", format)),
+            Map { key, value } => format!(
+                "DataType::Map(Arc::new(Field::new(\"entries\", DataType::Struct(Fields::from(vec![{}, {}])), false)), false)",
+                Self::quote_field("key", key),
+                Self::quote_field("value", value),
+            ),
+            Tuple(formats) => format!("DataType::Struct({})", Self::quote_tuple_fields(formats)),
+            TupleArray { content, size } => format!(
+                "DataType::FixedSizeList(Arc::new({}), {})",
+                Self::quote_field("item", content),
+                size
+            ),
+            Custom { layout, .. } => Self::quote_data_type(layout),
+            Decimal { mantissa, .. } => Self::quote_data_type(mantissa),
+            Variable(_) | Skipped(_) => unreachable!("formats are checked by common::check_formats"),
+        }
+    }
+
+    fn quote_field(name: &str, format: &Format) -> String {
+        let nullable = matches!(format, Format::Option(_) | Format::Unit);
+        format!(
+            "Field::new({:?}, {}, {})",
+            name,
+            Self::quote_data_type(format),
+            nullable
+        )
+    }
+
+    fn quote_tuple_fields(formats: &[Format]) -> String {
+        format!(
+            "Fields::from(vec![{}])",
+            formats
+                .iter()
+                .enumerate()
+                .map(|(i, format)| Self::quote_field(&i.to_string(), format))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn quote_struct_fields(fields: &[Named<Format>]) -> String {
+        format!(
+            "Fields::from(vec![{}])",
+            fields
+                .iter()
+                .map(|field| Self::quote_field(&field.name, &field.value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn quote_variant_data_type(format: &VariantFormat) -> String {
+        use VariantFormat::*;
+        match format {
+            Unit => "DataType::Null".into(),
+            NewType(format) => Self::quote_data_type(format),
+            Tuple(formats) => format!("DataType::Struct({})", Self::quote_tuple_fields(formats)),
+            Struct(fields) => format!("DataType::Struct({})", Self::quote_struct_fields(fields)),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
+        }
+    }
+
+    fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        let prefix = name.to_snake_case();
+        let data_type = match format {
+            UnitStruct => "DataType::Null".to_string(),
+            NewTypeStruct(format) => Self::quote_data_type(format),
+            TupleStruct(formats) => {
+                format!("DataType::Struct({})", Self::quote_tuple_fields(formats))
+            }
+            Struct(fields) => {
+                writeln!(self.out)?;
+                self.output_comment(name)?;
+                writeln!(
+                    self.out,
+                    "pub fn {}_fields() -> Fields {{\n    {}\n}}",
+                    prefix,
+                    Self::quote_struct_fields(fields)
+                )?;
+                writeln!(self.out)?;
+                self.output_comment(name)?;
+                writeln!(
+                    self.out,
+                    "pub fn {0}_schema() -> Schema {{\n    Schema::new({0}_fields())\n}}",
+                    prefix
+                )?;
+                format!("DataType::Struct({}_fields())", prefix)
+            }
+            Enum(variants) | InternallyTaggedEnum { variants, .. } => self.quote_union(variants),
+        };
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(
+            self.out,
+            "pub fn {}_data_type() -> DataType {{\n    {}\n}}",
+            prefix, data_type
+        )
+    }
+
+    fn quote_union(&self, variants: &BTreeMap<u32, Named<VariantFormat>>) -> String {
+        let (type_ids, fields): (Vec<_>, Vec<_>) = variants
+            .iter()
+            .map(|(index, variant)| {
+                let nullable = matches!(variant.value, VariantFormat::Unit);
+                (
+                    index.to_string(),
+                    format!(
+                        "Field::new({:?}, {}, {})",
+                        variant.name,
+                        Self::quote_variant_data_type(&variant.value),
+                        nullable
+                    ),
+                )
+            })
+            .unzip();
+        format!(
+            "DataType::Union(UnionFields::new(vec![{}], vec![{}]), UnionMode::Dense)",
+            type_ids.join(", "),
+            fields.join(", ")
+        )
+    }
+}
+
+/// Installer for Arrow schemas.
+#[derive(Clone)]
+pub struct Installer {
+    install_dir: PathBuf,
+}
+
+impl Installer {
+    pub fn new(install_dir: PathBuf) -> Self {
+        Installer { install_dir }
+    }
+}
+
+impl crate::SourceInstaller for Installer {
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self::new(install_dir.to_path_buf()))
+    }
+
+    fn install_module(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &Registry,
+    ) -> std::result::Result<(), Self::Error> {
+        std::fs::create_dir_all(&self.install_dir)?;
+        let path = self.install_dir.join(format!(
+            "{}_arrow.rs",
+            config.module_name.replace('.', "_").to_snake_case()
+        ));
+        let mut file = common::create_source_file(config, path)?;
+        CodeGenerator::new(config).output(&mut file, registry)?;
+        Ok(())
+    }
+
+    // Arrow schemas only depend on the `arrow` crate.
+
+    fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn install_encoding_runtime(
+        &self,
+        _encoding: Encoding,
+    ) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
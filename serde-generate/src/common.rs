@@ -1,8 +1,22 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_reflection::Format;
+use crate::{
+    analyzer,
+    transform::{RegistryTransform, RemoveSkippedFields, SeqU8AsBytes},
+    CodeGeneratorConfig, ContainerOrder, Error, FileProgress, Result,
+};
+use serde_reflection::{
+    ContainerFormat, Format, FormatHolder, FormatInterner, Named, Registry, VariantFormat,
+};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
+/// Default name mangler, e.g. `vector_option_u64` for `Vec<Option<u64>>`.
 pub(crate) fn mangle_type(format: &Format) -> String {
     use Format::*;
     match format {
@@ -27,6 +41,7 @@ pub(crate) fn mangle_type(format: &Format) -> String {
 
         Option(format) => format!("option_{}", mangle_type(format)),
         Seq(format) => format!("vector_{}", mangle_type(format)),
+        Set(format) => format!("set_{}", mangle_type(format)),
         Map { key, value } => format!("map_{}_to_{}", mangle_type(key), mangle_type(value)),
         Tuple(formats) => format!(
             "tuple{}_{}",
@@ -38,6 +53,849 @@ pub(crate) fn mangle_type(format: &Format) -> String {
                 .join("_")
         ),
         TupleArray { content, size } => format!("array{}_{}_array", size, mangle_type(content)),
-        Variable(_) => panic!("unexpected value"),
+        Custom { name, .. } => format!("custom_{}", name),
+        Decimal { mantissa, scale } => format!("decimal{}_{}", scale, mangle_type(mantissa)),
+        Skipped(format) => format!("skipped_{}", mangle_type(format)),
+        Variable(variable) => match variable.borrow().as_ref() {
+            Some(format) => mangle_type(format),
+            None => "unknown".into(),
+        },
     }
 }
+
+/// Name of the (de)serialization helpers of `format`, as computed by the name mangler of the
+/// config. Names longer than the configured maximum are truncated and completed with a hash of
+/// the format.
+pub(crate) fn helper_name(config: &CodeGeneratorConfig, format: &Format) -> String {
+    let name = (config.name_mangler)(format);
+    match config.max_helper_name_length {
+        Some(max_length) if name.len() > max_length => {
+            let hash = format!("{:016x}", fnv1a(&format!("{:?}", format)));
+            let prefix = name
+                .chars()
+                .take(max_length.saturating_sub(hash.len() + 1))
+                .collect::<String>();
+            format!("{}_{}", prefix, hash)
+        }
+        _ => name,
+    }
+}
+
+/// Whether the (de)serialization of `format` should be inlined at call sites rather than
+/// delegated to a helper, i.e. `config.inline_simple_helpers` is set and `format` is an option
+/// or a sequence of primitive values or named containers.
+pub(crate) fn inlines_helper(config: &CodeGeneratorConfig, format: &Format) -> bool {
+    use Format::*;
+    fn is_simple(format: &Format) -> bool {
+        match format {
+            Custom { layout, .. } => is_simple(layout),
+            Decimal { mantissa, .. } => is_simple(mantissa),
+            Option(_)
+            | Seq(_)
+            | Set(_)
+            | Map { .. }
+            | Tuple(_)
+            | TupleArray { .. }
+            | Variable(_) => false,
+            _ => true,
+        }
+    }
+    config.inline_simple_helpers
+        && match format {
+            Option(format) | Seq(format) => is_simple(format),
+            _ => false,
+        }
+}
+
+/// Split the variants of an enum into consecutive chunks of at most `config.max_switch_cases`
+/// variants, each dispatched by its own method. Return a single chunk if the enum is small
+/// enough or there is no limit.
+pub(crate) fn variant_chunks<'a, T>(
+    config: &CodeGeneratorConfig,
+    variants: &'a BTreeMap<u32, T>,
+) -> Vec<Vec<(&'a u32, &'a T)>> {
+    let variants = variants.iter().collect::<Vec<_>>();
+    match config.max_switch_cases {
+        Some(max_cases) if variants.len() > max_cases => variants
+            .chunks(std::cmp::max(max_cases, 1))
+            .map(|chunk| chunk.to_vec())
+            .collect(),
+        _ => vec![variants],
+    }
+}
+
+/// Line written by emitters between the containers of a source file that may be split into
+/// parts (see `CodeGeneratorConfig::with_max_file_lines`). The form feed is otherwise unused in
+/// generated code.
+pub(crate) const PART_BOUNDARY: &str = "\x0c";
+
+/// Split generated code at the boundaries written by emitters.
+pub(crate) fn split_chunks(content: &str) -> Vec<String> {
+    let mut chunks = vec![String::new()];
+    for line in content.lines() {
+        if line.trim_matches(&[' ', '\t'][..]) == PART_BOUNDARY {
+            chunks.push(String::new());
+        } else {
+            let chunk = chunks.last_mut().unwrap();
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+    }
+    chunks
+}
+
+/// Group consecutive chunks of generated code into parts of at most `max_lines` lines, without
+/// splitting chunks.
+pub(crate) fn group_chunks(chunks: &[String], max_lines: usize) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut lines = 0;
+    for chunk in chunks {
+        let chunk_lines = chunk.lines().count();
+        match parts.last_mut() {
+            Some(part) if lines + chunk_lines <= max_lines => {
+                part.push_str(chunk);
+                lines += chunk_lines;
+            }
+            _ => {
+                parts.push(chunk.clone());
+                lines = chunk_lines;
+            }
+        }
+    }
+    parts
+}
+
+/// Fail if the code generation for `config` was cancelled.
+pub(crate) fn check_cancellation(config: &CodeGeneratorConfig) -> Result<()> {
+    match &config.cancellation_token {
+        Some(token) if token.is_cancelled() => Err(Error::Cancelled),
+        _ => Ok(()),
+    }
+}
+
+/// Source file of a module written by an installer. Its generation is reported to the progress
+/// reporter of the config, if any, once the file is dropped.
+pub(crate) struct SourceFile<'a> {
+    file: std::fs::File,
+    path: PathBuf,
+    config: &'a CodeGeneratorConfig,
+    bytes: u64,
+    start: Instant,
+}
+
+/// Create the source file `path` of the module of `config`.
+pub(crate) fn create_source_file<P: AsRef<Path>>(
+    config: &CodeGeneratorConfig,
+    path: P,
+) -> std::io::Result<SourceFile<'_>> {
+    Ok(SourceFile {
+        file: std::fs::File::create(path.as_ref())?,
+        path: path.as_ref().to_path_buf(),
+        config,
+        bytes: 0,
+        start: Instant::now(),
+    })
+}
+
+impl Write for SourceFile<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for SourceFile<'_> {
+    fn drop(&mut self) {
+        if let Some(reporter) = self.config.progress_reporter {
+            reporter(&FileProgress {
+                module_name: self.config.module_name.clone(),
+                path: Some(self.path.clone()),
+                bytes: self.bytes,
+                elapsed: self.start.elapsed(),
+            });
+        }
+    }
+}
+
+/// Formats of the registry that need (de)serialization helpers, indexed by helper name,
+/// optionally including the formats in `config.root_formats`.
+/// Fails if two different formats are given the same name, including formats that only differ
+/// by custom or decimal formats, since they may be represented by different types.
+/// Repeated formats are interned, so that each distinct format is only named and cloned once.
+pub(crate) fn helper_formats<F>(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+    with_root_formats: bool,
+    needs_helper: F,
+) -> Result<BTreeMap<String, Arc<Format>>>
+where
+    F: Fn(&Format) -> bool,
+{
+    let mut interner = FormatInterner::new();
+    interner.intern_registry(registry)?;
+    if with_root_formats {
+        for format in config.root_formats.values() {
+            interner.intern(format)?;
+        }
+    }
+    let mut subtypes: BTreeMap<String, Arc<Format>> = BTreeMap::new();
+    let mut collision = None;
+    for format in interner.formats().filter(|f| needs_helper(f)) {
+        let name = helper_name(config, format);
+        if let Some(other) = subtypes.get(&name) {
+            collision = Some((name.clone(), (**other).clone(), (**format).clone()));
+        }
+        subtypes.insert(name, format.clone());
+    }
+    if let Some((name, first, second)) = collision {
+        return Err(Error::InvalidConfig(format!(
+            "Helper name {} is used by both {:?} and {:?}",
+            name, first, second
+        )));
+    }
+    Ok(subtypes)
+}
+
+/// Time types of the standard library, traced as custom formats with
+/// `TracerConfig::time_formats`, which code generators may map to native types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TimeFormat {
+    Duration,
+    SystemTime,
+}
+
+impl TimeFormat {
+    /// The time type of the custom format `name`, if it is `Duration` or `SystemTime` with the
+    /// layout `(u64, u32)`.
+    pub(crate) fn new(name: &str, layout: &Format) -> Option<Self> {
+        if *layout != Format::Tuple(vec![Format::U64, Format::U32]) {
+            return None;
+        }
+        match name {
+            "Duration" => Some(TimeFormat::Duration),
+            "SystemTime" => Some(TimeFormat::SystemTime),
+            _ => None,
+        }
+    }
+
+    /// The time type of `format`, if any.
+    pub(crate) fn of(format: &Format) -> Option<Self> {
+        match format {
+            Format::Custom { name, layout } => Self::new(name, layout),
+            _ => None,
+        }
+    }
+}
+
+/// Network address types of the standard library, i.e. the containers `IpAddr` and `SocketAddr`
+/// as traced from `std::net`, which code generators may map to native types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AddressFormat {
+    IpAddr,
+    SocketAddr,
+}
+
+impl AddressFormat {
+    /// The address type of the container `name`, if its format is the one traced from
+    /// `std::net`: an enum with the variants `V4` and `V6` holding the octets of the IP address,
+    /// followed by a port number in the case of socket addresses.
+    pub(crate) fn new(name: &str, format: &ContainerFormat) -> Option<Self> {
+        let octets = |size| Format::TupleArray {
+            content: Box::new(Format::U8),
+            size,
+        };
+        let (address, v4, v6) = match name {
+            "IpAddr" => (AddressFormat::IpAddr, octets(4), octets(16)),
+            "SocketAddr" => (
+                AddressFormat::SocketAddr,
+                Format::Tuple(vec![octets(4), Format::U16]),
+                Format::Tuple(vec![octets(16), Format::U16]),
+            ),
+            _ => return None,
+        };
+        let variant = |name: &str, format| {
+            Named::new(name.to_string(), VariantFormat::NewType(Box::new(format)))
+        };
+        let expected = vec![(0, variant("V4", v4)), (1, variant("V6", v6))];
+        if *format == ContainerFormat::Enum(expected.into_iter().collect()) {
+            Some(address)
+        } else {
+            None
+        }
+    }
+
+    /// Suffix of the names of the (de)serialization functions of the runtimes, e.g.
+    /// `serialize_ip_addr`.
+    pub(crate) fn helper_suffix(self) -> &'static str {
+        match self {
+            AddressFormat::IpAddr => "ip_addr",
+            AddressFormat::SocketAddr => "socket_addr",
+        }
+    }
+}
+
+/// Containers of the registry recognized as network address types.
+pub(crate) fn address_formats(registry: &Registry) -> BTreeMap<String, AddressFormat> {
+    registry
+        .iter()
+        .filter_map(|(name, format)| Some((name.clone(), AddressFormat::new(name, format)?)))
+        .collect()
+}
+
+/// How a variant is identified on the wire: by its index, or by its name serialized as a
+/// string in the case of internally tagged enums, where `tag` is the name of the tag field.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum VariantTag<'a> {
+    Index(u32),
+    Name { tag: &'a str, name: &'a str },
+}
+
+impl<'a> VariantTag<'a> {
+    pub(crate) fn new(tag: Option<&'a str>, index: u32, name: &'a str) -> Self {
+        match tag {
+            Some(tag) => VariantTag::Name { tag, name },
+            None => VariantTag::Index(index),
+        }
+    }
+}
+
+/// Kind of the values of a field, as reported by field descriptors, together with whether
+/// the field is optional.
+pub(crate) fn field_kind(format: &Format) -> (&'static str, bool) {
+    use Format::*;
+    let kind = match format {
+        Option(format) => return (field_kind(format).0, true),
+        Custom { layout, .. } => return field_kind(layout),
+        Decimal { mantissa, .. } => return field_kind(mantissa),
+        TypeName(_) => "container",
+        Unit => "unit",
+        Bool => "bool",
+        I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128 => "integer",
+        F32 | F64 => "float",
+        Char => "char",
+        Str => "string",
+        Bytes => "bytes",
+        Seq(_) | Set(_) | TupleArray { .. } => "sequence",
+        Map { .. } => "map",
+        Tuple(_) => "tuple",
+        Variable(_) | Skipped(_) => unreachable!("formats are checked by check_formats"),
+    };
+    (kind, false)
+}
+
+/// Sets of flags declared for the fields of the definition `path`, together with the
+/// value of each flag. Only `u8`, `u16`, and `u32` fields are eligible. Flags that do not
+/// fit in the field are ignored.
+pub(crate) fn flag_sets<'a>(
+    config: &'a CodeGeneratorConfig,
+    path: &[String],
+    fields: &'a [Named<Format>],
+) -> Vec<(&'a Named<Format>, BTreeMap<&'a str, u32>)> {
+    let mut result = Vec::new();
+    for field in fields {
+        let width = match field.value {
+            Format::U8 => 8,
+            Format::U16 => 16,
+            Format::U32 => 32,
+            _ => continue,
+        };
+        let mut field_path = path.to_vec();
+        field_path.push(field.name.clone());
+        if let Some(flags) = config.flags.get(&field_path) {
+            let values = flags
+                .iter()
+                .filter(|(_, bit)| **bit < width)
+                .map(|(name, bit)| (name.as_str(), 1u32 << bit))
+                .collect();
+            result.push((field, values));
+        }
+    }
+    result
+}
+
+/// Constraint on the values of an integer field, checked by the constructors of generated
+/// classes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct IntegerConstraint {
+    /// Whether the field is a nonzero integer, e.g. `NonZeroU64`.
+    pub(crate) nonzero: bool,
+    /// Inclusive bounds declared with `CodeGeneratorConfig::with_integer_bounds`.
+    pub(crate) min: Option<i128>,
+    pub(crate) max: Option<i128>,
+}
+
+impl IntegerConstraint {
+    /// Comparisons `(operator, bound)` satisfied by valid values, e.g. `(">=", 1)`.
+    pub(crate) fn comparisons(&self) -> Vec<(&'static str, i128)> {
+        let mut result = Vec::new();
+        if self.nonzero {
+            result.push(("!=", 0));
+        }
+        if let Some(min) = self.min {
+            result.push((">=", min));
+        }
+        if let Some(max) = self.max {
+            result.push(("<=", max));
+        }
+        result
+    }
+
+    /// A valid value, as close to zero as possible, used by test fixtures.
+    pub(crate) fn sample(&self) -> i128 {
+        let mut value = 0;
+        if let Some(min) = self.min {
+            value = std::cmp::max(value, min);
+        }
+        if let Some(max) = self.max {
+            value = std::cmp::min(value, max);
+        }
+        if self.nonzero && value == 0 {
+            value = if self.max == Some(0) { -1 } else { 1 };
+        }
+        value
+    }
+
+    /// Description of valid values in error messages, e.g. `nonzero` or `at least 1`.
+    pub(crate) fn description(&self) -> String {
+        let mut parts = Vec::new();
+        if self.nonzero {
+            parts.push("nonzero".to_string());
+        }
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => parts.push(format!("between {} and {}", min, max)),
+            (Some(min), None) => parts.push(format!("at least {}", min)),
+            (None, Some(max)) => parts.push(format!("at most {}", max)),
+            (None, None) => (),
+        }
+        parts.join(" and ")
+    }
+}
+
+/// The integer format of a nonzero integer, i.e. a custom format such as `NonZeroU64` traced
+/// from `std::num::NonZeroU64`.
+pub(crate) fn nonzero_integer(format: &Format) -> Option<&Format> {
+    use Format::*;
+    match format {
+        Custom { name, layout } if name == &format!("NonZero{:?}", layout) => match **layout {
+            I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128 => Some(layout),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Integer fields of the definition `path` with a constraint on their values, together with
+/// their integer format. Fields are constrained when they hold a nonzero integer or when
+/// bounds were declared for them.
+pub(crate) fn integer_constraints<'a>(
+    config: &CodeGeneratorConfig,
+    path: &[String],
+    fields: &'a [Named<Format>],
+) -> Vec<(&'a Named<Format>, &'a Format, IntegerConstraint)> {
+    use Format::*;
+    let mut result = Vec::new();
+    for field in fields {
+        let mut constraint = IntegerConstraint::default();
+        let mut format = &field.value;
+        loop {
+            if let Some(integer) = nonzero_integer(format) {
+                constraint.nonzero = true;
+                format = integer;
+            } else if let Custom { layout, .. } = format {
+                format = layout;
+            } else {
+                break;
+            }
+        }
+        if !matches!(
+            format,
+            I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128
+        ) {
+            continue;
+        }
+        let mut field_path = path.to_vec();
+        field_path.push(field.name.clone());
+        if let Some((min, max)) = config.integer_bounds.get(&field_path) {
+            constraint.min = *min;
+            constraint.max = *max;
+        }
+        if constraint != IntegerConstraint::default() {
+            result.push((field, format, constraint));
+        }
+    }
+    result
+}
+
+/// Names and layouts of the custom formats (e.g. type aliases) used in the registry. Custom
+/// formats appear after the ones used in their layout.
+pub(crate) fn custom_formats(registry: &Registry) -> Vec<(String, Format)> {
+    let mut names = BTreeSet::new();
+    let mut result = Vec::new();
+    for format in registry.values() {
+        format
+            .visit(&mut |f| {
+                if let Format::Custom { name, layout } = f {
+                    if names.insert(name.clone()) {
+                        result.push((name.clone(), layout.as_ref().clone()));
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+    }
+    result
+}
+
+/// Whether the registry uses fixed-point decimal formats.
+pub(crate) fn has_decimal_formats(registry: &Registry) -> bool {
+    let mut found = false;
+    for format in registry.values() {
+        format
+            .visit(&mut |f| {
+                found |= matches!(f, Format::Decimal { .. });
+                Ok(())
+            })
+            .unwrap();
+    }
+    found
+}
+
+/// Whether the registry records fields skipped by Serde.
+fn has_skipped_fields(registry: &Registry) -> bool {
+    let is_skipped = |fields: &[Named<Format>]| {
+        fields
+            .iter()
+            .any(|field| matches!(field.value, Format::Skipped(_)))
+    };
+    registry.values().any(|format| match format {
+        ContainerFormat::Struct(fields) => is_skipped(fields),
+        ContainerFormat::Enum(variants)
+        | ContainerFormat::InternallyTaggedEnum { variants, .. } => {
+            variants.values().any(|variant| match &variant.value {
+                VariantFormat::Struct(fields) => is_skipped(fields),
+                _ => false,
+            })
+        }
+        _ => false,
+    })
+}
+
+/// The registry without the fields skipped by Serde, which are absent from the wire.
+pub(crate) fn without_skipped_fields(registry: &Registry) -> crate::Result<Cow<'_, Registry>> {
+    if !has_skipped_fields(registry) {
+        return Ok(Cow::Borrowed(registry));
+    }
+    Ok(Cow::Owned(RemoveSkippedFields.apply(registry.clone())?))
+}
+
+/// The registry to generate code for, without skipped fields, and where sequences of bytes are
+/// replaced by `Bytes` if requested by the configuration.
+pub(crate) fn prepare_registry<'a>(
+    config: &CodeGeneratorConfig,
+    registry: &'a Registry,
+) -> crate::Result<Cow<'a, Registry>> {
+    let registry = without_skipped_fields(registry)?;
+    if !config.seq_u8_as_bytes {
+        return Ok(registry);
+    }
+    Ok(Cow::Owned(SeqU8AsBytes.apply(registry.into_owned())?))
+}
+
+/// Whether `format` is the name of a struct of `registry`.
+fn is_struct(registry: &Registry, format: &Format) -> bool {
+    match format {
+        Format::TypeName(name) => matches!(registry.get(name), Some(ContainerFormat::Struct(_))),
+        _ => false,
+    }
+}
+
+/// Check that code can be generated in `language` for the formats of `registry`, that is,
+/// formats are fully traced, skipped formats only mark (removed) fields, the mantissas of
+/// decimal numbers are signed integers, and the variants of internally tagged enums are units,
+/// structs, or newtypes of structs.
+/// Generators call this before emitting any code, so that the formats rejected here are
+/// reported as `Error::UnsupportedFormat` and never reach the emitters.
+pub(crate) fn check_formats(language: &'static str, registry: &Registry) -> crate::Result<()> {
+    let check = |container: &str, field: Option<&str>, format: &Format| {
+        let supported = format.visit(&mut |format| match format {
+            Format::Skipped(_) => Err(serde_reflection::Error::NotSupported("skipped formats")),
+            Format::Decimal { mantissa, .. } => match mantissa.as_ref() {
+                Format::I8 | Format::I16 | Format::I32 | Format::I64 | Format::I128 => Ok(()),
+                _ => Err(serde_reflection::Error::NotSupported("decimal mantissas")),
+            },
+            _ => Ok(()),
+        });
+        match supported {
+            Ok(()) => Ok(()),
+            Err(_) => Err(crate::Error::UnsupportedFormat {
+                language,
+                container: container.to_string(),
+                field: field.map(String::from),
+                format: format!("{:?}", format),
+            }),
+        }
+    };
+    let check_fields = |container: &str, prefix: &str, fields: &[Named<Format>]| {
+        fields.iter().try_for_each(|field| {
+            let name = format!("{}{}", prefix, field.name);
+            check(container, Some(&name), &field.value)
+        })
+    };
+    for (name, format) in registry {
+        match format {
+            ContainerFormat::UnitStruct => (),
+            ContainerFormat::NewTypeStruct(format) => check(name, None, format)?,
+            ContainerFormat::TupleStruct(formats) => formats
+                .iter()
+                .try_for_each(|format| check(name, None, format))?,
+            ContainerFormat::Struct(fields) => check_fields(name, "", fields)?,
+            ContainerFormat::Enum(variants)
+            | ContainerFormat::InternallyTaggedEnum { variants, .. } => {
+                let is_tagged = matches!(format, ContainerFormat::InternallyTaggedEnum { .. });
+                for variant in variants.values() {
+                    let unsupported = || crate::Error::UnsupportedFormat {
+                        language,
+                        container: name.clone(),
+                        field: Some(variant.name.clone()),
+                        format: format!("{:?}", variant.value),
+                    };
+                    match &variant.value {
+                        VariantFormat::Unit => (),
+                        VariantFormat::NewType(format)
+                            if is_tagged && !is_struct(registry, format) =>
+                        {
+                            return Err(unsupported())
+                        }
+                        VariantFormat::NewType(format) => check(name, Some(&variant.name), format)?,
+                        VariantFormat::Tuple(_) if is_tagged => return Err(unsupported()),
+                        VariantFormat::Tuple(formats) => formats
+                            .iter()
+                            .try_for_each(|format| check(name, Some(&variant.name), format))?,
+                        VariantFormat::Struct(fields) => {
+                            check_fields(name, &format!("{}.", variant.name), fields)?
+                        }
+                        VariantFormat::Variable(_) => return Err(unsupported()),
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Options of `CodeGeneratorConfig` implemented by some code generators only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ConfigOption {
+    /// See `CodeGeneratorConfig::with_json_integers_as_strings`.
+    JsonIntegersAsStrings,
+    /// See `CodeGeneratorConfig::with_immutable_collections`.
+    ImmutableCollections,
+    /// See `CodeGeneratorConfig::with_canonical_equality`.
+    CanonicalEquality,
+    /// See `CodeGeneratorConfig::with_root_formats`.
+    RootFormats,
+    /// See `CodeGeneratorConfig::with_type_aliases`.
+    TypeAliases,
+    /// See `CodeGeneratorConfig::with_default_values`.
+    DefaultValues,
+    /// See `CodeGeneratorConfig::with_fixtures`.
+    Fixtures,
+    /// See `CodeGeneratorConfig::with_hand_written_types`.
+    HandWrittenTypes,
+}
+
+impl ConfigOption {
+    const ALL: &'static [ConfigOption] = &[
+        ConfigOption::JsonIntegersAsStrings,
+        ConfigOption::ImmutableCollections,
+        ConfigOption::CanonicalEquality,
+        ConfigOption::RootFormats,
+        ConfigOption::TypeAliases,
+        ConfigOption::DefaultValues,
+        ConfigOption::Fixtures,
+        ConfigOption::HandWrittenTypes,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ConfigOption::JsonIntegersAsStrings => "json_integers_as_strings",
+            ConfigOption::ImmutableCollections => "immutable_collections",
+            ConfigOption::CanonicalEquality => "canonical_equality",
+            ConfigOption::RootFormats => "root_formats",
+            ConfigOption::TypeAliases => "type_aliases",
+            ConfigOption::DefaultValues => "default_values",
+            ConfigOption::Fixtures => "fixtures",
+            ConfigOption::HandWrittenTypes => "hand_written_types",
+        }
+    }
+
+    fn is_set(self, config: &CodeGeneratorConfig) -> bool {
+        match self {
+            ConfigOption::JsonIntegersAsStrings => config.json_integers_as_strings,
+            ConfigOption::ImmutableCollections => config.immutable_collections,
+            ConfigOption::CanonicalEquality => config.canonical_equality,
+            ConfigOption::RootFormats => !config.root_formats.is_empty(),
+            ConfigOption::TypeAliases => config.type_aliases,
+            ConfigOption::DefaultValues => !config.default_values.is_empty(),
+            ConfigOption::Fixtures => config.fixtures,
+            ConfigOption::HandWrittenTypes => !config.hand_written_types.is_empty(),
+        }
+    }
+}
+
+/// Check that the options set in `config` are `supported` by the code generator of
+/// `language`, so that unsupported options are reported as `Error::InvalidConfig` instead of
+/// being ignored.
+pub(crate) fn check_options(
+    language: &'static str,
+    config: &CodeGeneratorConfig,
+    supported: &[ConfigOption],
+) -> crate::Result<()> {
+    for option in ConfigOption::ALL {
+        if option.is_set(config) && !supported.contains(option) {
+            return Err(Error::InvalidConfig(format!(
+                "{} does not support the option {}",
+                language,
+                option.name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Containers of the registry in the order selected by the configuration.
+pub(crate) fn ordered_containers<'a>(
+    config: &CodeGeneratorConfig,
+    registry: &'a Registry,
+) -> Result<Vec<(&'a str, &'a ContainerFormat)>> {
+    let mut names = registry.keys().map(String::as_str).collect::<Vec<_>>();
+    match config.container_order {
+        ContainerOrder::Name => (),
+        ContainerOrder::Namespace => {
+            names.sort_by_key(|name| (namespace(name), *name));
+        }
+        ContainerOrder::Dependencies => {
+            let dependencies = analyzer::get_dependency_map(registry)?;
+            names = analyzer::best_effort_topological_sort(&dependencies);
+        }
+    }
+    Ok(names
+        .into_iter()
+        .filter_map(|name| registry.get_key_value(name))
+        .map(|(name, format)| (name.as_str(), format))
+        .collect())
+}
+
+/// Namespace of a container name, e.g. `foo` for `foo.Bar` or `foo::Bar`.
+fn namespace(name: &str) -> &str {
+    let index = match (name.rfind("::"), name.rfind('.')) {
+        (Some(i), Some(j)) => i.max(j),
+        (Some(i), None) | (None, Some(i)) => i,
+        (None, None) => 0,
+    };
+    &name[..index]
+}
+
+/// Fingerprint of the schema of the root type `name`, i.e. a 64-bit FNV-1a hash of the
+/// (YAML) definitions of all the containers reachable from `name`.
+pub(crate) fn schema_fingerprint(registry: &Registry, name: &str) -> Result<u64> {
+    if !registry.contains_key(name) {
+        return Err(Error::InvalidConfig(format!("Unknown root type: {}", name)));
+    }
+    let dependencies = analyzer::get_dependency_map(registry)?;
+    let mut reachable = BTreeSet::new();
+    let mut queue = vec![name];
+    while let Some(name) = queue.pop() {
+        if reachable.insert(name) {
+            queue.extend(dependencies.get(name).into_iter().flatten());
+        }
+    }
+    let schema: BTreeMap<_, _> = registry
+        .iter()
+        .filter(|(name, _)| reachable.contains(name.as_str()))
+        .map(|(name, format)| (name, without_source_names(format)))
+        .collect();
+    let content = serde_yaml::to_string(&schema).map_err(yaml_error)?;
+    Ok(fnv1a(&content))
+}
+
+/// Copy of `format` without the source names of fields and variants, which do not affect the
+/// wire format.
+fn without_source_names(format: &ContainerFormat) -> ContainerFormat {
+    let clear_fields = |fields: &mut Vec<Named<Format>>| {
+        for field in fields {
+            field.source_name = None;
+        }
+    };
+    let mut format = format.clone();
+    match &mut format {
+        ContainerFormat::Struct(fields) => clear_fields(fields),
+        ContainerFormat::Enum(variants)
+        | ContainerFormat::InternallyTaggedEnum { variants, .. } => {
+            for variant in variants.values_mut() {
+                variant.source_name = None;
+                if let VariantFormat::Struct(fields) = &mut variant.value {
+                    clear_fields(fields);
+                }
+            }
+        }
+        ContainerFormat::UnitStruct
+        | ContainerFormat::NewTypeStruct(_)
+        | ContainerFormat::TupleStruct(_) => (),
+    }
+    format
+}
+
+/// 64-bit FNV-1a hash.
+pub(crate) fn fnv1a(content: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in content.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// The registry encoded in YAML, compressed with zlib, then encoded in base64 and split into
+/// lines of at most 76 characters, so that it can be embedded as a string constant.
+pub(crate) fn compressed_schema(registry: &Registry) -> Result<Vec<String>> {
+    use flate2::{write::ZlibEncoder, Compression};
+    let content = serde_yaml::to_string(registry).map_err(yaml_error)?;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(content.as_bytes())?;
+    let encoded = base64(&encoder.finish()?);
+    Ok(encoded
+        .as_bytes()
+        .chunks(76)
+        .map(|chunk| String::from_utf8(chunk.to_vec()).unwrap())
+        .collect())
+}
+
+/// Registries that cannot be encoded in YAML, e.g. because of unresolved variables.
+fn yaml_error(error: serde_yaml::Error) -> Error {
+    Error::Registry(serde_reflection::Error::Custom(error.to_string()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | u32::from(*byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,406 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{CodeGeneratorConfig, Encoding};
+use serde_reflection::{
+    ContainerChange, ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat,
+};
+use std::{collections::BTreeMap, fmt};
+
+/// Whether values encoded with the old formats of a registry are decoded identically by
+/// readers of the new formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Old values are still decoded, e.g. after renaming a field or appending a variant.
+    Compatible,
+    /// Old values fail to decode, or decode to different values.
+    Breaking,
+    /// Old values fail to decode because a definition marked as deprecated was removed, see
+    /// `check_compatibility_with_config`.
+    DeprecatedRemoval,
+}
+
+/// A change between two registries, as reported by `serde_reflection::diff`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaChange {
+    /// The container is present in the new registry only.
+    ContainerAdded,
+    /// The container is present in the old registry only.
+    ContainerRemoved,
+    /// The container is present in both registries with different formats.
+    ContainerChanged(ContainerChange),
+}
+
+/// A change of a container, classified for a given encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassifiedChange {
+    /// Name of the container.
+    pub container: String,
+    /// Change of the container.
+    pub change: SchemaChange,
+    /// Whether the change preserves the decoding of old values.
+    pub compatibility: Compatibility,
+}
+
+/// Classification of all the changes between two registries for a given encoding, as
+/// computed by `check_compatibility`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// Encoding for which changes were classified.
+    pub encoding: Encoding,
+    /// Changes between the registries, in the order of `serde_reflection::diff`.
+    pub changes: Vec<ClassifiedChange>,
+}
+
+impl CompatibilityReport {
+    /// Breaking changes.
+    pub fn violations(&self) -> Vec<&ClassifiedChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.compatibility == Compatibility::Breaking)
+            .collect()
+    }
+
+    /// Whether all changes are compatible.
+    pub fn is_compatible(&self) -> bool {
+        self.violations().is_empty()
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::ContainerAdded => write!(f, "container was added"),
+            SchemaChange::ContainerRemoved => write!(f, "container was removed"),
+            SchemaChange::ContainerChanged(change) => write!(f, "{}", change),
+        }
+    }
+}
+
+impl fmt::Display for ClassifiedChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.container, self.change)
+    }
+}
+
+/// Lists the violations, one per line.
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for violation in self.violations() {
+            writeln!(f, "{} (breaking for {})", violation, self.encoding.name())?;
+        }
+        Ok(())
+    }
+}
+
+/// Classify the changes from the registry `old` to the registry `new` for `encoding`, e.g. to
+/// reject breaking schema updates in CI.
+///
+/// A change is compatible if values encoded with `old` are decoded identically with `new`.
+/// Binary encodings identify fields by position and variants by index, so that renaming
+/// fields and variants is compatible, as well as changes preserving the wire layout, e.g.
+/// replacing `Vec<u8>` by `Bytes`, a newtype struct by its content, or skipping a new field.
+/// Appending a variant is compatible as long as readers of the old formats tolerate unknown
+/// variant indices, whereas removing a field or a variant is breaking.
+///
+/// Fails with `Error::Registry` if a registry contains formats that were not fully traced.
+pub fn check_compatibility(
+    old: &Registry,
+    new: &Registry,
+    encoding: Encoding,
+) -> crate::Result<CompatibilityReport> {
+    check(old, new, encoding, &|_| false)
+}
+
+/// Same as `check_compatibility`, except that removing a container, a field, or a variant
+/// marked as deprecated in `config` (see `CodeGeneratorConfig::with_deprecations`) is classified
+/// as `Compatibility::DeprecatedRemoval` instead of `Compatibility::Breaking`, and is therefore
+/// not a violation. Definitions are qualified by the module name of `config`.
+pub fn check_compatibility_with_config(
+    old: &Registry,
+    new: &Registry,
+    encoding: Encoding,
+    config: &CodeGeneratorConfig,
+) -> crate::Result<CompatibilityReport> {
+    let namespace = config.module_name.split('.').map(String::from);
+    let is_deprecated = |names: &[&str]| {
+        let path = namespace
+            .clone()
+            .chain(names.iter().map(|name| name.to_string()))
+            .collect::<Vec<_>>();
+        config.deprecations.contains_key(&path)
+    };
+    check(old, new, encoding, &is_deprecated)
+}
+
+fn check(
+    old: &Registry,
+    new: &Registry,
+    encoding: Encoding,
+    is_deprecated: &dyn Fn(&[&str]) -> bool,
+) -> crate::Result<CompatibilityReport> {
+    check_traced(old)?;
+    check_traced(new)?;
+    let diff = serde_reflection::diff(old, new);
+    let mut changes = Vec::new();
+    for name in diff.added_containers {
+        changes.push(ClassifiedChange {
+            container: name,
+            change: SchemaChange::ContainerAdded,
+            compatibility: Compatibility::Compatible,
+        });
+    }
+    for name in diff.removed_containers {
+        let compatibility = if is_deprecated(&[&name]) {
+            Compatibility::DeprecatedRemoval
+        } else {
+            Compatibility::Breaking
+        };
+        changes.push(ClassifiedChange {
+            container: name,
+            change: SchemaChange::ContainerRemoved,
+            compatibility,
+        });
+    }
+    for (name, container_changes) in diff.changed_containers {
+        let old_format = &old[&name];
+        let new_format = &new[&name];
+        for change in container_changes {
+            let mut compatibility = classify(encoding, old_format, new_format, &change);
+            if compatibility == Compatibility::Breaking
+                && removes_deprecated(&name, &change, is_deprecated)
+            {
+                compatibility = Compatibility::DeprecatedRemoval;
+            }
+            changes.push(ClassifiedChange {
+                container: name.clone(),
+                change: SchemaChange::ContainerChanged(change),
+                compatibility,
+            });
+        }
+    }
+    Ok(CompatibilityReport { encoding, changes })
+}
+
+/// Check that the formats of `registry` do not contain variables, i.e. unknown formats or
+/// formats only resolved during tracing, which have no wire layout to compare.
+fn check_traced(registry: &Registry) -> crate::Result<()> {
+    for (name, format) in registry {
+        format
+            .visit(&mut |_| Ok(()))
+            .map_err(|_| serde_reflection::Error::UnknownFormatInContainer(name.clone()))?;
+    }
+    Ok(())
+}
+
+fn classify(
+    encoding: Encoding,
+    old: &ContainerFormat,
+    new: &ContainerFormat,
+    change: &ContainerChange,
+) -> Compatibility {
+    use ContainerChange::*;
+    let compatible = match change {
+        ContainerRetyped { .. } => match (container_layout(old), container_layout(new)) {
+            (Some(old), Some(new)) => is_compatible(encoding, &old, &new),
+            _ => false,
+        },
+        TagChanged { .. } | VariantRemoved { .. } => false,
+        // Fields are compared by position, hence the changes of a list of fields are
+        // compatible when the whole list is.
+        FieldAdded { variant, .. }
+        | FieldRemoved { variant, .. }
+        | FieldRetyped { variant, .. }
+        | FieldsReordered { variant, .. } => match variant {
+            None => match (container_layout(old), container_layout(new)) {
+                (Some(old), Some(new)) => is_compatible(encoding, &old, &new),
+                _ => false,
+            },
+            Some(name) => match matching_variants(old, new, name) {
+                Some((old, new)) => {
+                    is_compatible(encoding, &variant_layout(old), &variant_layout(new))
+                }
+                None => false,
+            },
+        },
+        VariantAdded { .. } | VariantRenamed { .. } => true,
+        VariantRetyped { old, new, .. } => {
+            is_compatible(encoding, &variant_layout(old), &variant_layout(new))
+        }
+    };
+    if compatible {
+        Compatibility::Compatible
+    } else {
+        Compatibility::Breaking
+    }
+}
+
+/// Whether `change` removes a field or a variant of the container `container` that is marked as
+/// deprecated.
+fn removes_deprecated(
+    container: &str,
+    change: &ContainerChange,
+    is_deprecated: &dyn Fn(&[&str]) -> bool,
+) -> bool {
+    match change {
+        ContainerChange::FieldRemoved {
+            variant: None,
+            name,
+            ..
+        } => is_deprecated(&[container, name]),
+        ContainerChange::FieldRemoved {
+            variant: Some(variant),
+            name,
+            ..
+        } => is_deprecated(&[container, variant, name]),
+        ContainerChange::VariantRemoved { name, .. } => is_deprecated(&[container, name]),
+        _ => false,
+    }
+}
+
+/// The old and new formats of the variant named `name` in the new container.
+fn matching_variants<'a>(
+    old: &'a ContainerFormat,
+    new: &'a ContainerFormat,
+    name: &str,
+) -> Option<(&'a VariantFormat, &'a VariantFormat)> {
+    match (old, new) {
+        (ContainerFormat::Enum(old), ContainerFormat::Enum(new)) => {
+            let (index, new_variant) = new.iter().find(|(_, variant)| variant.name == name)?;
+            Some((&old.get(index)?.value, &new_variant.value))
+        }
+        (
+            ContainerFormat::InternallyTaggedEnum { variants: old, .. },
+            ContainerFormat::InternallyTaggedEnum { variants: new, .. },
+        ) => {
+            let find = |variants: &'a BTreeMap<u32, Named<VariantFormat>>| {
+                variants
+                    .values()
+                    .find(|variant| variant.name == name)
+                    .map(|variant| &variant.value)
+            };
+            Some((find(old)?, find(new)?))
+        }
+        _ => None,
+    }
+}
+
+/// Wire layout of a struct as a single format, e.g. `(u64, String)` for `struct { id: u64,
+/// name: String }`. Enums have no such layout.
+fn container_layout(format: &ContainerFormat) -> Option<Format> {
+    use ContainerFormat::*;
+    match format {
+        UnitStruct => Some(Format::Unit),
+        NewTypeStruct(format) => Some(format.as_ref().clone()),
+        TupleStruct(formats) => Some(Format::Tuple(formats.clone())),
+        Struct(fields) => Some(Format::Tuple(
+            fields.iter().map(|field| field.value.clone()).collect(),
+        )),
+        Enum(_) | InternallyTaggedEnum { .. } => None,
+    }
+}
+
+/// Wire layout of the content of a variant, following its index.
+fn variant_layout(format: &VariantFormat) -> Format {
+    use VariantFormat::*;
+    match format {
+        Variable(_) => unreachable!("registries are checked by check_traced"),
+        Unit => Format::Unit,
+        NewType(format) => format.as_ref().clone(),
+        Tuple(formats) => Format::Tuple(formats.clone()),
+        Struct(fields) => Format::Tuple(fields.iter().map(|field| field.value.clone()).collect()),
+    }
+}
+
+/// Rewrite `format` into a canonical format with the same binary layout: custom formats are
+/// replaced by their layout, bytes and sets by sequences, arrays by tuples, and tuples are
+/// flattened without the components absent from the wire (e.g. skipped fields).
+fn normalize(format: &Format) -> Format {
+    use Format::*;
+    match format {
+        Custom { layout, .. } => normalize(layout),
+        Bytes => Seq(Box::new(U8)),
+        Set(format) | Seq(format) => Seq(Box::new(normalize(format))),
+        Option(format) => Option(Box::new(normalize(format))),
+        Map { key, value } => Map {
+            key: Box::new(normalize(key)),
+            value: Box::new(normalize(value)),
+        },
+        Skipped(_) => Unit,
+        Tuple(_) | TupleArray { .. } => {
+            let mut components = Vec::new();
+            flatten(format, &mut components);
+            match components.len() {
+                0 => Unit,
+                1 => components.pop().unwrap(),
+                _ => Tuple(components),
+            }
+        }
+        _ => format.clone(),
+    }
+}
+
+fn flatten(format: &Format, components: &mut Vec<Format>) {
+    match format {
+        Format::Tuple(formats) => {
+            for format in formats {
+                flatten(format, components);
+            }
+        }
+        Format::TupleArray { content, size } => {
+            for _ in 0..*size {
+                flatten(content, components);
+            }
+        }
+        _ => match normalize(format) {
+            Format::Unit => (),
+            Format::Tuple(formats) => components.extend(formats),
+            format => components.push(format),
+        },
+    }
+}
+
+/// Whether values encoded with the format `old` are decoded identically with the format `new`.
+fn is_compatible(encoding: Encoding, old: &Format, new: &Format) -> bool {
+    compatible_layouts(encoding, &normalize(old), &normalize(new))
+}
+
+fn compatible_layouts(encoding: Encoding, old: &Format, new: &Format) -> bool {
+    use Format::*;
+    match (old, new) {
+        (Option(old), Option(new)) | (Seq(old), Seq(new)) => compatible_layouts(encoding, old, new),
+        (
+            Map {
+                key: old_key,
+                value: old_value,
+            },
+            Map {
+                key: new_key,
+                value: new_value,
+            },
+        ) => {
+            compatible_layouts(encoding, old_key, new_key)
+                && compatible_layouts(encoding, old_value, new_value)
+        }
+        (Tuple(old), Tuple(new)) => {
+            old.len() == new.len()
+                && old
+                    .iter()
+                    .zip(new)
+                    .all(|(old, new)| compatible_layouts(encoding, old, new))
+        }
+        // Maps are encoded as sequences of pairs. Sorted maps are valid sequences, but BCS
+        // readers reject maps whose keys are not in canonical order.
+        (Map { key, value }, Seq(pair)) => {
+            compatible_layouts(encoding, &pair_layout(key, value), pair)
+        }
+        (Seq(pair), Map { key, value }) if encoding == Encoding::Bincode => {
+            compatible_layouts(encoding, pair, &pair_layout(key, value))
+        }
+        _ => old == new,
+    }
+}
+
+fn pair_layout(key: &Format, value: &Format) -> Format {
+    normalize(&Format::Tuple(vec![key.clone(), value.clone()]))
+}
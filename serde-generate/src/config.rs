@@ -1,7 +1,11 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::collections::{BTreeMap, BTreeSet};
+use crate::install::{OverwritePolicy, PlannedFile};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
 
 /// Code generation options meant to be supported by all languages.
 #[derive(Clone, Debug)]
@@ -13,6 +17,40 @@ pub struct CodeGeneratorConfig {
     pub(crate) comments: DocComments,
     pub(crate) custom_code: CustomCode,
     pub(crate) c_style_enums: bool,
+    pub(crate) flags: FlagSets,
+    pub(crate) semantic_tags: SemanticTags,
+    pub(crate) integer_bounds: IntegerBounds,
+    pub(crate) default_values: DefaultValues,
+    pub(crate) deprecations: Deprecations,
+    pub(crate) variant_names: bool,
+    pub(crate) field_descriptors: bool,
+    pub(crate) fixtures: bool,
+    pub(crate) file_decoding: bool,
+    pub(crate) record_log_types: BTreeSet<String>,
+    pub(crate) envelope_hooks: bool,
+    pub(crate) checksums: bool,
+    pub(crate) thread_safety_notes: bool,
+    pub(crate) root_formats: RootFormats,
+    pub(crate) type_aliases: bool,
+    pub(crate) json_integers_as_strings: bool,
+    pub(crate) json_integer_policy: JsonIntegerPolicy,
+    pub(crate) embedded_schema: bool,
+    pub(crate) name_mangler: NameMangler,
+    pub(crate) max_helper_name_length: Option<usize>,
+    pub(crate) inline_simple_helpers: bool,
+    pub(crate) variant_naming: VariantNaming,
+    pub(crate) nested_variant_classes: bool,
+    pub(crate) immutable_collections: bool,
+    pub(crate) canonical_equality: bool,
+    pub(crate) container_order: ContainerOrder,
+    pub(crate) output_layout: OutputLayout,
+    pub(crate) seq_u8_as_bytes: bool,
+    pub(crate) max_switch_cases: Option<usize>,
+    pub(crate) max_file_lines: Option<usize>,
+    pub(crate) hand_written_types: HandWrittenTypes,
+    pub(crate) accessor_convention: AccessorConvention,
+    pub(crate) progress_reporter: Option<ProgressReporter>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
 }
 
 #[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq)]
@@ -35,6 +73,160 @@ pub type CustomCode = std::collections::BTreeMap<
     /* custom code */ String,
 >;
 
+/// Track integer fields holding sets of flags (use with `u8`, `u16`, and `u32` fields only).
+pub type FlagSets = std::collections::BTreeMap<
+    /* qualified field name */ Vec<String>,
+    /* bit position of each named flag */ BTreeMap<String, u32>,
+>;
+
+/// Track semantic tags (e.g. units of measure such as "microseconds") attached to particular fields.
+pub type SemanticTags =
+    std::collections::BTreeMap</* qualified field name */ Vec<String>, /* tag */ String>;
+
+/// Track inclusive bounds on the values of particular integer fields.
+pub type IntegerBounds = std::collections::BTreeMap<
+    /* qualified field name */ Vec<String>,
+    /* minimum and maximum, if any */ (Option<i128>, Option<i128>),
+>;
+
+/// Track fields with a Serde default (e.g. `#[serde(default)]`), together with their default
+/// values when they are known.
+pub type DefaultValues = std::collections::BTreeMap<
+    /* qualified field name */ Vec<String>,
+    /* default value, if known */ Option<serde_reflection::Value>,
+>;
+
+/// Track definitions marked as deprecated, together with an explanation.
+pub type Deprecations =
+    std::collections::BTreeMap</* qualified name */ Vec<String>, /* message */ String>;
+
+/// Track named root formats that are not containers (e.g. `Vec<Transaction>`).
+pub type RootFormats =
+    std::collections::BTreeMap</* name */ String, /* format */ serde_reflection::Format>;
+
+/// Track existing user types standing for particular containers (e.g. `bank::Account` for
+/// `Account`), for which only (de)serialization code is generated.
+pub type HandWrittenTypes =
+    std::collections::BTreeMap</* container name */ String, /* qualified type */ String>;
+
+/// Compute the base name of the (de)serialization helpers of a format (e.g. `vector_u8` for
+/// `Vec<u8>`).
+pub type NameMangler = fn(&serde_reflection::Format) -> String;
+
+/// Function called by installers after writing each source file of a module, e.g. to show
+/// the progress of long generations in CI logs.
+pub type ProgressReporter = fn(&FileProgress);
+
+/// Source file reported to a `ProgressReporter`.
+#[derive(Clone, Debug)]
+pub struct FileProgress {
+    /// Name of the module.
+    pub module_name: String,
+    /// Path of the file, or `None` for code written to another output.
+    pub path: Option<std::path::PathBuf>,
+    /// Number of bytes written.
+    pub bytes: u64,
+    /// Time spent writing the file, usually including the generation of its content.
+    pub elapsed: std::time::Duration,
+}
+
+/// How to name the classes holding the payload of enum variants, in languages where they are
+/// top-level definitions (Dart, Go, Python, and TypeScript). Other languages nest them in the
+/// class of the enum, e.g. `Shape.Circle` in Java.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VariantNaming {
+    /// Scheme of each language: `<Base><Variant>Item` in Dart, `<Base>__<Variant>` in Go and
+    /// Python, and `<Base>Variant<Variant>` in TypeScript.
+    Default,
+    /// `<Base>__<Variant>`, standing for the nested class `<Base>.<Variant>`.
+    Nested,
+    /// `<Base><prefix><Variant>`, e.g. `ShapeVariantCircle` for the prefix `Variant`.
+    Prefix(String),
+    /// `<Base><Variant><suffix>`, e.g. `ShapeCircleItem` for the suffix `Item`.
+    Suffix(String),
+}
+
+/// Shared flag aborting code generation, e.g. when an editor or a watch mode must restart it
+/// after a change of the registry. Code generators check it between containers and fail with
+/// `Error::Cancelled` once it is cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that is not cancelled yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort the code generations using this token, or any clone of it.
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// In which order to emit container definitions and the files or parts declaring them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerOrder {
+    /// Sort containers by name.
+    Name,
+    /// Group containers by namespace, i.e. the prefix of their name before the last `.` or
+    /// `::`, then sort them by name.
+    Namespace,
+    /// Emit the dependencies of a container before the container itself, whenever possible.
+    Dependencies,
+}
+
+/// How generated JSON decoders handle integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonIntegerPolicy {
+    /// Use parsed values as they are, without checks.
+    Unchecked,
+    /// Reject values that are not integers or that are out of the range of their format.
+    Strict,
+    /// Same as `Strict`, but also accept floating-point numbers without a fractional part
+    /// (e.g. `3.0`) and decimal strings (e.g. `"3"`).
+    Lenient,
+}
+
+/// How to lay out the source files of generated modules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputLayout {
+    /// The usual layout of each language, e.g. a Dart library made of parts, or a Python package.
+    Default,
+    /// A single source file per module, e.g. to embed generated code in scripts or notebooks.
+    SingleFile,
+}
+
+/// How generated code reads and assigns the fields of hand-written types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessorConvention {
+    /// Public fields named after the fields of the container, e.g. `obj.balance`.
+    Fields,
+    /// Getters named after the fields and setters prefixed by `set_`, e.g. `obj.balance()`
+    /// and `obj.set_balance(value)`.
+    Methods,
+    /// JavaBeans-style getters and setters, e.g. `obj.getBalance()` and
+    /// `obj.setBalance(value)`.
+    JavaBeans,
+}
+
+/// Where generated packages find the Serde runtimes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum RuntimeSource {
+    /// Copy the sources of the runtimes into the installation directory.
+    #[default]
+    Vendored,
+    /// Depend on a published package providing the runtimes, declared in the manifest of
+    /// generated packages, so that runtime updates are made in one place. Installing runtimes
+    /// then writes nothing.
+    Package { name: String, version: String },
+}
+
 /// How to copy generated source code and available runtimes for a given language.
 pub trait SourceInstaller {
     type Error;
@@ -49,11 +241,95 @@ pub trait SourceInstaller {
     /// Install the serde runtime.
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error>;
 
-    /// Install the bincode runtime.
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error>;
+    /// Install the runtime of the given encoding, e.g. the Libra Canonical Serialization (BCS)
+    /// runtime for `Encoding::Bcs`.
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error>;
+
+    /// Install the runtimes of the encodings enabled in `config`, and only those.
+    fn install_encoding_runtimes(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        for encoding in &config.encodings {
+            self.install_encoding_runtime(*encoding)?;
+        }
+        Ok(())
+    }
+
+    /// Install the unit tests of the serde runtime and of the runtimes of the encodings enabled
+    /// in `config`, if the language ships any, so that users may check the installed runtimes
+    /// with their own toolchain.
+    fn install_runtime_tests(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        let _ = config;
+        Ok(())
+    }
+
+    /// Install the types of the shared corpus of test vectors as the module of `config`,
+    /// together with the vectors of the encodings enabled in `config` and a runner checking
+    /// that each of them decodes and encodes back to the same bytes (see
+    /// `test_vectors::corpus`), if supported by the language.
+    fn install_corpus_runner(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        let _ = config;
+        Ok(())
+    }
 
-    /// Install the Libra Canonical Serialization (BCS) runtime.
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error>;
+    /// Directory where the installer writes its files, if any.
+    fn install_dir(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Same installer writing its files into `install_dir` instead, if supported. Used to
+    /// stage installations in `plan` and `install`.
+    fn relocated(&self, install_dir: &Path) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let _ = install_dir;
+        None
+    }
+
+    /// Run the installation `steps` (e.g. `|installer| installer.install_module(&config, &registry)`)
+    /// in a staging directory and report the files that `install` would write or remove, without
+    /// touching the installation directory.
+    fn plan<F>(&self, steps: F) -> std::result::Result<Vec<PlannedFile>, Self::Error>
+    where
+        Self: Sized,
+        Self::Error: From<std::io::Error>,
+        F: FnOnce(&Self) -> std::result::Result<(), Self::Error>,
+    {
+        crate::install::plan(self, steps)
+    }
+
+    /// Run the installation `steps` in a staging directory, then copy the new and changed files
+    /// into the installation directory according to `policy`. Files listed in the manifest of a
+    /// previous installation but no longer generated are removed, and the manifest is updated.
+    fn install<F>(
+        &self,
+        steps: F,
+        policy: OverwritePolicy,
+    ) -> std::result::Result<Vec<PlannedFile>, Self::Error>
+    where
+        Self: Sized,
+        Self::Error: From<std::io::Error>,
+        F: FnOnce(&Self) -> std::result::Result<(), Self::Error>,
+    {
+        crate::install::install(self, steps, policy)
+    }
+
+    /// Remove the files listed in the manifest of a previous `install`, then the manifest itself.
+    /// Returns the paths of the removed files, relative to the installation directory.
+    fn uninstall(&self) -> std::result::Result<Vec<PathBuf>, Self::Error>
+    where
+        Self::Error: From<std::io::Error>,
+    {
+        crate::install::uninstall(self)
+    }
 }
 
 impl CodeGeneratorConfig {
@@ -67,6 +343,40 @@ impl CodeGeneratorConfig {
             comments: BTreeMap::new(),
             custom_code: BTreeMap::new(),
             c_style_enums: false,
+            flags: BTreeMap::new(),
+            semantic_tags: BTreeMap::new(),
+            integer_bounds: BTreeMap::new(),
+            default_values: BTreeMap::new(),
+            deprecations: BTreeMap::new(),
+            variant_names: false,
+            field_descriptors: false,
+            fixtures: false,
+            file_decoding: false,
+            record_log_types: BTreeSet::new(),
+            envelope_hooks: false,
+            checksums: false,
+            thread_safety_notes: false,
+            root_formats: BTreeMap::new(),
+            type_aliases: false,
+            json_integers_as_strings: false,
+            json_integer_policy: JsonIntegerPolicy::Strict,
+            embedded_schema: false,
+            name_mangler: crate::common::mangle_type,
+            max_helper_name_length: None,
+            inline_simple_helpers: false,
+            variant_naming: VariantNaming::Default,
+            nested_variant_classes: false,
+            immutable_collections: false,
+            canonical_equality: false,
+            container_order: ContainerOrder::Name,
+            output_layout: OutputLayout::Default,
+            seq_u8_as_bytes: false,
+            max_switch_cases: None,
+            max_file_lines: None,
+            hand_written_types: BTreeMap::new(),
+            accessor_convention: AccessorConvention::Fields,
+            progress_reporter: None,
+            cancellation_token: None,
         }
     }
 
@@ -117,6 +427,377 @@ impl CodeGeneratorConfig {
         self.c_style_enums = c_style_enums;
         self
     }
+
+    /// Integer fields holding sets of flags. Helper constants and methods are generated
+    /// for each field while the wire format remains the underlying integer.
+    pub fn with_flags(mut self, flags: FlagSets) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Semantic tags attached to particular fields. Tags are added to the documentation
+    /// of the fields.
+    pub fn with_semantic_tags(mut self, semantic_tags: SemanticTags) -> Self {
+        self.semantic_tags = semantic_tags;
+        self
+    }
+
+    /// Inclusive bounds on the values of particular integer fields. Bounds are added to the
+    /// documentation of the fields and checked by the constructors of generated classes in
+    /// Java, Python, and TypeScript, as for nonzero integers (e.g. `NonZeroU64`).
+    pub fn with_integer_bounds(mut self, integer_bounds: IntegerBounds) -> Self {
+        self.integer_bounds = integer_bounds;
+        self
+    }
+
+    /// Fields with a Serde default, e.g. as computed by `Defaulted::qualified_default_values`
+    /// in `serde-reflection`. Fields with a known default value become optional parameters of
+    /// constructors and may be missing from parsed JSON objects (TypeScript only, other code
+    /// generators reject non-empty default values with `Error::InvalidConfig`).
+    pub fn with_default_values(mut self, default_values: DefaultValues) -> Self {
+        self.default_values = default_values;
+        self
+    }
+
+    /// Containers, fields, and variants marked as deprecated. Generated code uses the
+    /// deprecation mechanism of each language (e.g. `@Deprecated` in Java, or `@deprecated`
+    /// from `typing_extensions` in Python, where deprecated fields are only commented).
+    /// `compatibility::check_compatibility_with_config` accepts the removal of deprecated
+    /// definitions.
+    pub fn with_deprecations(mut self, deprecations: Deprecations) -> Self {
+        self.deprecations = deprecations;
+        self
+    }
+
+    /// Whether to generate a table of variant names for each enum, together with functions
+    /// converting variant indices to names and back.
+    pub fn with_variant_names(mut self, variant_names: bool) -> Self {
+        self.variant_names = variant_names;
+        self
+    }
+
+    /// Whether to generate a static `fields()` descriptor for each struct, listing the name,
+    /// the kind of values, and the optionality of each field (Dart, Python, and TypeScript only).
+    pub fn with_field_descriptors(mut self, field_descriptors: bool) -> Self {
+        self.field_descriptors = field_descriptors;
+        self
+    }
+
+    /// Whether to install test fixtures, i.e. a class `FooFixtures` for each container `Foo`
+    /// with a static method `sample()` building a valid instance. Samples use the first variant
+    /// of enums, empty sequences and maps, absent options, and integers as close to zero as
+    /// their constraints allow. Struct fields may be overridden. Fixtures are installed as a
+    /// separate module, e.g. `fixtures.ts`, meant to be imported by tests only (Python and
+    /// TypeScript only, other code generators reject the option with `Error::InvalidConfig`).
+    pub fn with_fixtures(mut self, fixtures: bool) -> Self {
+        self.fixtures = fixtures;
+        self
+    }
+
+    /// Whether to generate functions decoding values from memory-mapped files, for each
+    /// encoding (C++ and Rust only).
+    pub fn with_file_decoding(mut self, file_decoding: bool) -> Self {
+        self.file_decoding = file_decoding;
+        self
+    }
+
+    /// Root types stored in record logs. For each of them, a schema fingerprint and typed
+    /// functions writing and reading record logs are generated (Python, Rust, and TypeScript only).
+    pub fn with_record_log_types<I>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.record_log_types = types.into_iter().collect();
+        self
+    }
+
+    /// Whether encoding-specific entry points (e.g. `bcsSerialize`) should pass serialized bytes
+    /// through the envelope hooks registered in the serde runtime (e.g. to compress or encrypt
+    /// them), and reverse them before deserializing (all languages with such entry points,
+    /// i.e. not Rust).
+    pub fn with_envelope_hooks(mut self, envelope_hooks: bool) -> Self {
+        self.envelope_hooks = envelope_hooks;
+        self
+    }
+
+    /// Whether to generate checked variants of encoding-specific entry points (e.g.
+    /// `bcsSerializeChecked`) appending a CRC-32 checksum to the serialized bytes and verifying
+    /// it before deserializing (all languages with such entry points, i.e. not Rust).
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Whether to document the thread safety of generated classes, i.e. which instances may be
+    /// shared between threads and the reentrancy of (de)serialization (Java, C#, and
+    /// TypeScript only).
+    pub fn with_thread_safety_notes(mut self, thread_safety_notes: bool) -> Self {
+        self.thread_safety_notes = thread_safety_notes;
+        self
+    }
+
+    /// Named root formats that are not containers (e.g. `TransactionList` for
+    /// `Vec<Transaction>`). For each of them, top-level functions serializing and deserializing
+    /// values with the selected encodings are generated (C++, Go, Java, Python, and TypeScript
+    /// only, other code generators reject non-empty root formats with `Error::InvalidConfig`).
+    pub fn with_root_formats(mut self, root_formats: RootFormats) -> Self {
+        self.root_formats = root_formats;
+        self
+    }
+
+    /// Whether to emit a native alias for each custom format (e.g. `BlockHeight` traced with
+    /// `TracerConfig::type_alias`) and use its name in type signatures instead of its layout
+    /// (C++, Go, Python, and TypeScript only, other code generators reject the option with
+    /// `Error::InvalidConfig`).
+    pub fn with_type_aliases(mut self, type_aliases: bool) -> Self {
+        self.type_aliases = type_aliases;
+        self
+    }
+
+    /// Whether 64-bit and 128-bit integers are written as decimal strings in JSON rather than
+    /// numbers, so that they survive double-precision parsers (Dart and TypeScript only, other
+    /// code generators reject the option with `Error::InvalidConfig`).
+    pub fn with_json_integers_as_strings(mut self, json_integers_as_strings: bool) -> Self {
+        self.json_integers_as_strings = json_integers_as_strings;
+        self
+    }
+
+    /// How JSON decoders check integers, so that malformed inputs fail early with an error
+    /// naming the field. This applies to the JSON conversions of Dart (unless generated by
+    /// `json_serializable`) and to the zod schemas of TypeScript, where 64-bit and 128-bit
+    /// integers are always checked (Dart and TypeScript only).
+    pub fn with_json_integer_policy(mut self, json_integer_policy: JsonIntegerPolicy) -> Self {
+        self.json_integer_policy = json_integer_policy;
+        self
+    }
+
+    /// Whether to embed the registry as a compressed constant in the generated code, together
+    /// with an accessor returning its YAML encoding, e.g. `schema()` in Python (Go, Java, and
+    /// Python only).
+    pub fn with_embedded_schema(mut self, embedded_schema: bool) -> Self {
+        self.embedded_schema = embedded_schema;
+        self
+    }
+
+    /// Function naming the (de)serialization helpers of formats such as `Vec<Option<u64>>`.
+    /// Different formats must be given different names, including formats that only differ by
+    /// custom or decimal formats (e.g. `Option<Uuid>` and `Option<[u8; 16]>`).
+    pub fn with_name_mangler(mut self, name_mangler: NameMangler) -> Self {
+        self.name_mangler = name_mangler;
+        self
+    }
+
+    /// Function called by installers after writing each source file of the module, with its
+    /// size and generation time. Runtime files copied by installers are not reported.
+    pub fn with_progress_reporter(mut self, progress_reporter: Option<ProgressReporter>) -> Self {
+        self.progress_reporter = progress_reporter;
+        self
+    }
+
+    /// Token checked by code generators between containers to abort generation early.
+    pub fn with_cancellation_token(mut self, token: Option<CancellationToken>) -> Self {
+        self.cancellation_token = token;
+        self
+    }
+
+    /// Maximal length of helper names. Longer names are truncated and completed with a hash
+    /// of the format.
+    pub fn with_max_helper_name_length(mut self, max_helper_name_length: Option<usize>) -> Self {
+        self.max_helper_name_length = max_helper_name_length;
+        self
+    }
+
+    /// Whether to inline the (de)serialization of simple formats such as `Option<u64>` and
+    /// `Vec<u8>` at call sites instead of generating helpers for them (TypeScript only).
+    pub fn with_inline_simple_helpers(mut self, inline_simple_helpers: bool) -> Self {
+        self.inline_simple_helpers = inline_simple_helpers;
+        self
+    }
+
+    /// Naming scheme of the classes holding the payload of enum variants (Dart, Go, Python, and
+    /// TypeScript only).
+    pub fn with_variant_naming(mut self, variant_naming: VariantNaming) -> Self {
+        self.variant_naming = variant_naming;
+        self
+    }
+
+    /// Whether to emit the classes holding the payload of enum variants as nested classes of
+    /// the enum, e.g. `Shape.Circle`, rather than top-level classes named after the variant
+    /// naming scheme (TypeScript only, while C#, C++, and Java always nest them).
+    pub fn with_nested_variant_classes(mut self, nested_variant_classes: bool) -> Self {
+        self.nested_variant_classes = nested_variant_classes;
+        self
+    }
+
+    /// Whether sequences and maps held by generated classes should be immutable, both when
+    /// passed to constructors and when decoded, e.g. `java.util.Collections.unmodifiableList`
+    /// in Java, `List.unmodifiable` in Dart, `tuple` and `types.MappingProxyType` in Python, and
+    /// `ReadonlyArray` in TypeScript (Dart, Java, Python, and TypeScript only, other code
+    /// generators reject the option with `Error::InvalidConfig`).
+    pub fn with_immutable_collections(mut self, immutable_collections: bool) -> Self {
+        self.immutable_collections = immutable_collections;
+        self
+    }
+
+    /// Whether generated equality and hashing should compare the canonical (BCS) serialization
+    /// of values, computed once and cached, rather than their fields. This requires the BCS
+    /// runtime and has no effect unless serialization is enabled (Dart and Java only, other code
+    /// generators reject the option with `Error::InvalidConfig`).
+    pub fn with_canonical_equality(mut self, canonical_equality: bool) -> Self {
+        self.canonical_equality = canonical_equality;
+        self
+    }
+
+    /// In which order to emit containers in single-file outputs and library files (Dart, Go,
+    /// Python, and TypeScript only, while C++ and Rust always emit dependencies first).
+    pub fn with_container_order(mut self, container_order: ContainerOrder) -> Self {
+        self.container_order = container_order;
+        self
+    }
+
+    /// How to lay out the source files of generated modules (C++, Dart, and Python only, while
+    /// Go, Rust, and TypeScript modules always consist of a single source file).
+    pub fn with_output_layout(mut self, output_layout: OutputLayout) -> Self {
+        self.output_layout = output_layout;
+        self
+    }
+
+    /// Whether to treat sequences of bytes `Seq(U8)` as `Bytes`, so that they are mapped to the
+    /// byte arrays of the target language, e.g. `Uint8List` in Dart, `byte[]` in Java, and
+    /// `bytes` in Python. The wire layout is the same (C++, C#, Dart, Go, Java, Python, Rust, and
+    /// TypeScript only).
+    pub fn with_seq_u8_as_bytes(mut self, seq_u8_as_bytes: bool) -> Self {
+        self.seq_u8_as_bytes = seq_u8_as_bytes;
+        self
+    }
+
+    /// Maximal number of cases in the switch dispatching the deserialization of enum variants.
+    /// Larger enums spread the dispatch over several methods, which keeps methods within the
+    /// bytecode limits of some runtimes and readable by coverage tools (C#, Dart, Go, Java, and
+    /// TypeScript only, while other languages do not generate such switches).
+    pub fn with_max_switch_cases(mut self, max_switch_cases: Option<usize>) -> Self {
+        self.max_switch_cases = max_switch_cases;
+        self
+    }
+
+    /// Maximal number of lines of the source files written by installers. Longer files are split
+    /// between containers into numbered parts, e.g. `<module>_part1.hpp` included by the C++
+    /// header, Dart part files, or TypeScript modules re-exported by `index.ts`. Containers are
+    /// never split, so that parts holding a large container may exceed the limit (C++, Dart
+    /// libraries with `OutputLayout::SingleFile`, and TypeScript only).
+    pub fn with_max_file_lines(mut self, max_file_lines: Option<usize>) -> Self {
+        self.max_file_lines = max_file_lines;
+        self
+    }
+
+    /// Existing user types standing for containers of the registry. No definitions are
+    /// generated for these containers, only (de)serialization code and entry points that read
+    /// and fill in values of the user types, according to the accessor convention. User types
+    /// must be default-constructible, and enums must hold their variants like generated enums
+    /// (C++ only, other code generators reject non-empty hand-written types with
+    /// `Error::InvalidConfig`).
+    pub fn with_hand_written_types(mut self, hand_written_types: HandWrittenTypes) -> Self {
+        self.hand_written_types = hand_written_types;
+        self
+    }
+
+    /// How (de)serialization code reads and assigns the fields of hand-written types
+    /// (C++ only).
+    pub fn with_accessor_convention(mut self, accessor_convention: AccessorConvention) -> Self {
+        self.accessor_convention = accessor_convention;
+        self
+    }
+
+    /// Name of the class holding the payload of the variant `variant` of the enum `base`, where
+    /// `default` is the naming scheme of the target language.
+    pub(crate) fn variant_class_name(
+        &self,
+        base: &str,
+        variant: &str,
+        default: &VariantNaming,
+    ) -> String {
+        let naming = match &self.variant_naming {
+            VariantNaming::Default => default,
+            naming => naming,
+        };
+        match naming {
+            VariantNaming::Default | VariantNaming::Nested => format!("{}__{}", base, variant),
+            VariantNaming::Prefix(prefix) => format!("{}{}{}", base, prefix, variant),
+            VariantNaming::Suffix(suffix) => format!("{}{}{}", base, variant, suffix),
+        }
+    }
+
+    /// Documentation attached to the entity `path`, including its semantic tag and integer
+    /// bounds (if any).
+    pub(crate) fn comment(&self, path: &[String]) -> Option<String> {
+        let mut notes = String::new();
+        if let Some(tag) = self.semantic_tags.get(path) {
+            notes.push_str(&format!("Unit: {}\n", tag));
+        }
+        if let Some((min, max)) = self.integer_bounds.get(path) {
+            let min = min.map(|v| v.to_string()).unwrap_or_default();
+            let max = max.map(|v| format!("={}", v)).unwrap_or_default();
+            notes.push_str(&format!("Range: {}..{}\n", min, max));
+        }
+        match (self.comments.get(path), notes.is_empty()) {
+            (None, true) => None,
+            (Some(comment), true) => Some(comment.clone()),
+            (None, false) => Some(notes),
+            (Some(comment), false) => Some(format!("{}\n{}", comment, notes)),
+        }
+    }
+
+    /// Same as `comment` with an additional paragraph `{prefix}{message}` if the
+    /// entity `path` is deprecated.
+    pub(crate) fn comment_with_deprecation(&self, path: &[String], prefix: &str) -> Option<String> {
+        self.with_deprecation_notice(self.comment(path), path, prefix)
+    }
+
+    /// The comment `comment` followed by the paragraph `{prefix}{message}` if the entity
+    /// `path` is deprecated.
+    pub(crate) fn with_deprecation_notice(
+        &self,
+        comment: Option<String>,
+        path: &[String],
+        prefix: &str,
+    ) -> Option<String> {
+        match self.deprecations.get(path) {
+            None => comment,
+            Some(message) => {
+                let notice = format!("{}{}\n", prefix, message.trim());
+                match comment {
+                    None => Some(notice),
+                    Some(comment) => Some(format!("{}\n{}", comment, notice)),
+                }
+            }
+        }
+    }
+
+    /// The comment `comment` of a container followed by the paragraph `note` on thread
+    /// safety, if requested by `with_thread_safety_notes`.
+    pub(crate) fn with_thread_safety_note(
+        &self,
+        comment: Option<String>,
+        note: &str,
+    ) -> Option<String> {
+        if !self.thread_safety_notes {
+            return comment;
+        }
+        match comment {
+            None => Some(format!("{}\n", note)),
+            Some(comment) => Some(format!("{}\n{}\n", comment, note)),
+        }
+    }
+
+    /// Encoding used by generators describing a single wire format: BCS, unless Bincode is
+    /// the only encoding selected.
+    pub(crate) fn single_encoding(&self) -> Encoding {
+        if self.encodings.contains(&Encoding::Bincode) && !self.encodings.contains(&Encoding::Bcs) {
+            Encoding::Bincode
+        } else {
+            Encoding::Bcs
+        }
+    }
 }
 
 impl Encoding {
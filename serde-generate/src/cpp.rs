@@ -3,22 +3,29 @@
 
 use crate::{
     analyzer,
+    common::{self, ConfigOption},
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    AccessorConvention, CodeGeneratorConfig, Encoding, OutputLayout,
 };
-use heck::CamelCase;
-use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use heck::{CamelCase, ShoutySnakeCase};
+use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{Result, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Main configuration object for code-generation in C++.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
     config: &'a CodeGeneratorConfig,
     /// Mapping from external type names to suitably qualified names (e.g. "MyClass" -> "name::MyClass").
-    /// Derived from `config.external_definitions`.
+    /// Derived from `config.external_definitions` and `config.hand_written_types`.
     external_qualified_names: HashMap<String, String>,
+    /// Whether to amalgamate the runtime headers into the generated header.
+    single_header: bool,
+    /// Whether to mark the places where the installer may split the header into parts.
+    part_boundaries: bool,
+    /// Whether to generate (de)serialization code as free functions rather than members.
+    free_functions: bool,
 }
 
 /// Shared state for the code generation of a C++ source file.
@@ -38,9 +45,6 @@ struct CppEmitter<'a, T> {
 impl<'a> CodeGenerator<'a> {
     /// Create a C++ code generator for the given config.
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("C++ does not support generating c-style enums");
-        }
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -48,17 +52,74 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}::{}", namespace, name));
             }
         }
+        for (name, qualified_name) in &config.hand_written_types {
+            external_qualified_names.insert(name.to_string(), qualified_name.to_string());
+        }
         Self {
             config,
             external_qualified_names,
+            single_header: config.output_layout == OutputLayout::SingleFile,
+            part_boundaries: false,
+            free_functions: false,
         }
     }
 
-    pub fn output(
-        &self,
-        out: &mut dyn Write,
-        registry: &Registry,
-    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// Whether to emit a self-contained header, including the definitions of the runtime
+    /// headers (`serde.hpp`, the selected encodings, etc.) instead of `#include` directives.
+    /// This is the default when the configuration selects `OutputLayout::SingleFile`.
+    pub fn with_single_header(mut self, single_header: bool) -> Self {
+        self.single_header = single_header;
+        self
+    }
+
+    pub(crate) fn with_part_boundaries(mut self, part_boundaries: bool) -> Self {
+        self.part_boundaries = part_boundaries;
+        self
+    }
+
+    /// Whether to generate (de)serialization code as free functions `serde::serialize(const T &,
+    /// Serializer &)` and `serde::deserialize(T &, Deserializer &)`, found by argument-dependent
+    /// lookup, rather than specializations of `serde::Serializable` and `serde::Deserializable`.
+    /// Generated structs then only declare their comparison operator as a member, and
+    /// encoding-specific entry points become functions of the module, e.g. `bcsSerializeFoo`
+    /// and `bcsDeserializeFoo`. Containers listed in the external definitions of the
+    /// configuration are not defined: their (de)serialization code is generated for the
+    /// hand-written types of the external namespace.
+    pub fn with_free_functions(mut self, free_functions: bool) -> Self {
+        self.free_functions = free_functions;
+        self
+    }
+
+    /// Whether the container `name` is written by hand, i.e. only its (de)serialization code
+    /// is generated.
+    fn is_hand_written(&self, name: &str) -> bool {
+        self.config.hand_written_types.contains_key(name)
+            || (self.free_functions && self.external_qualified_names.contains_key(name))
+    }
+
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        if self.config.c_style_enums {
+            return Err(crate::Error::InvalidConfig(
+                "C++ does not support generating c-style enums".to_string(),
+            ));
+        }
+        if self.free_functions && (self.config.checksums || self.config.file_decoding) {
+            return Err(crate::Error::InvalidConfig(
+                "C++ free functions do not support checksums and file decoding".to_string(),
+            ));
+        }
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("C++", registry)?;
+        common::check_options(
+            "C++",
+            self.config,
+            &[
+                ConfigOption::RootFormats,
+                ConfigOption::TypeAliases,
+                ConfigOption::HandWrittenTypes,
+            ],
+        )?;
         let current_namespace = self
             .config
             .module_name
@@ -76,10 +137,29 @@ impl<'a> CodeGenerator<'a> {
         emitter.output_preamble()?;
         emitter.output_open_namespace()?;
 
+        if self.config.type_aliases {
+            for (name, layout) in common::custom_formats(registry) {
+                emitter.output_type_alias(registry, &name, &layout)?;
+            }
+        }
+
+        for name in registry.keys() {
+            if self.is_hand_written(name) {
+                emitter.known_names.insert(name);
+                emitter.known_sizes.insert(name);
+            }
+        }
+
         let dependencies = analyzer::get_dependency_map(registry)?;
-        let entries = analyzer::best_effort_topological_sort(&dependencies);
+        let entries = analyzer::best_effort_topological_sort(&dependencies)
+            .into_iter()
+            .filter(|name| !self.is_hand_written(name));
 
-        for name in entries {
+        for (index, name) in entries.enumerate() {
+            common::check_cancellation(self.config)?;
+            if index > 0 {
+                emitter.output_part_boundary(/* in_namespace */ true)?;
+            }
             for dependency in &dependencies[name] {
                 if !emitter.known_names.contains(dependency) {
                     emitter.output_container_forward_definition(*dependency)?;
@@ -95,8 +175,22 @@ impl<'a> CodeGenerator<'a> {
         emitter.output_close_namespace()?;
         writeln!(emitter.out)?;
         for (name, format) in registry {
+            emitter.output_part_boundary(/* in_namespace */ false)?;
             emitter.output_container_traits(&name, format)?;
         }
+        if emitter.has_root_formats() {
+            emitter.output_part_boundary(/* in_namespace */ false)?;
+            emitter.output_open_namespace()?;
+            for name in registry.keys() {
+                if self.free_functions || self.is_hand_written(name) {
+                    emitter.output_root_format(name, &Format::TypeName(name.clone()))?;
+                }
+            }
+            for (name, format) in &self.config.root_formats {
+                emitter.output_root_format(name, format)?;
+            }
+            emitter.output_close_namespace()?;
+        }
         Ok(())
     }
 }
@@ -106,20 +200,51 @@ where
     T: std::io::Write,
 {
     fn output_preamble(&mut self) -> Result<()> {
-        writeln!(
-            self.out,
-            r#"#pragma once
-
-#include "serde.hpp""#
-        )?;
-        if self.generator.config.serialization {
-            for encoding in &self.generator.config.encodings {
-                writeln!(self.out, "#include \"{}.hpp\"", encoding.name())?;
+        let config = self.generator.config;
+        let mut headers = vec!["serde"];
+        if config.serialization {
+            if self.generator.single_header && !config.encodings.is_empty() {
+                // Included by the headers of the encodings otherwise.
+                headers.push("binary");
             }
+            headers.extend(config.encodings.iter().map(|encoding| encoding.name()));
+            if config.file_decoding {
+                headers.push("mmap");
+            }
+        }
+        writeln!(self.out, "#pragma once\n")?;
+        for header in headers {
+            if self.generator.single_header {
+                self.output_runtime_header(header)?;
+            } else {
+                writeln!(self.out, "#include \"{}.hpp\"", header)?;
+            }
+        }
+        if config.serialization {
+            writeln!(
+                self.out,
+                r#"
+// Binary encodings are defined over 8-bit bytes and lengths of up to 2^31 - 1.
+static_assert(CHAR_BIT == 8, "Serde runtimes require 8-bit bytes");
+static_assert(sizeof(size_t) >= sizeof(uint32_t), "Serde runtimes require a size_t of at least 32 bits");"#
+            )?;
         }
         Ok(())
     }
 
+    /// Copy the content of a runtime header, except for `#pragma once` and local includes.
+    fn output_runtime_header(&mut self, name: &str) -> Result<()> {
+        writeln!(self.out, "// Amalgamated from {}.hpp", name)?;
+        for line in runtime_header(name).lines() {
+            let directive = line.trim();
+            if directive == "#pragma once" || directive.starts_with("#include \"") {
+                continue;
+            }
+            writeln!(self.out, "{}", line)?;
+        }
+        writeln!(self.out)
+    }
+
     fn output_open_namespace(&mut self) -> Result<()> {
         writeln!(
             self.out,
@@ -140,6 +265,22 @@ where
         Ok(())
     }
 
+    /// Mark a place where the header may be split into parts. Inside the namespace of the module,
+    /// the namespace is closed before the boundary and reopened after it.
+    fn output_part_boundary(&mut self, in_namespace: bool) -> Result<()> {
+        if !self.generator.part_boundaries {
+            return Ok(());
+        }
+        if in_namespace {
+            self.output_close_namespace()?;
+        }
+        writeln!(self.out, "{}", common::PART_BOUNDARY)?;
+        if in_namespace {
+            self.output_open_namespace()?;
+        }
+        Ok(())
+    }
+
     fn enter_class(&mut self, name: &str) {
         self.out.indent();
         self.current_namespace.push(name.to_string());
@@ -153,13 +294,27 @@ where
     fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
         let mut path = self.current_namespace.clone();
         path.push(name.to_string());
-        if let Some(doc) = self.generator.config.comments.get(&path) {
-            let text = textwrap::indent(doc, "/// ").replace("\n\n", "\n///\n");
+        if let Some(doc) = self
+            .generator
+            .config
+            .comment_with_deprecation(&path, "@deprecated ")
+        {
+            let text = textwrap::indent(&doc, "/// ").replace("\n\n", "\n///\n");
             write!(self.out, "{}", text)?;
         }
         Ok(())
     }
 
+    /// The attribute `[[deprecated]]` followed by a space, if the entity `name` is deprecated.
+    fn quote_deprecation(&self, name: &str) -> String {
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        match self.generator.config.deprecations.get(&path) {
+            Some(message) => format!("[[deprecated({:?})]] ", message.trim()),
+            None => String::new(),
+        }
+    }
+
     fn output_custom_code(&mut self) -> std::io::Result<()> {
         if let Some(code) = self
             .generator
@@ -172,13 +327,21 @@ where
         Ok(())
     }
 
-    /// Compute a fully qualified reference to the container type `name`.
+    /// Compute a fully qualified reference to the container type `name`, or to one of its
+    /// variants (e.g. "MyEnum::MyVariant").
     fn quote_qualified_name(&self, name: &str) -> String {
-        self.generator
+        let mut path = name.splitn(2, "::");
+        let container = path.next().unwrap_or(name);
+        let qualified_container = self
+            .generator
             .external_qualified_names
-            .get(name)
+            .get(container)
             .cloned()
-            .unwrap_or_else(|| format!("{}::{}", self.generator.config.module_name, name))
+            .unwrap_or_else(|| format!("{}::{}", self.generator.config.module_name, container));
+        match path.next() {
+            Some(variant) => format!("{}::{}", qualified_container, variant),
+            None => qualified_container,
+        }
     }
 
     fn quote_type(&self, format: &Format, require_known_size: bool) -> String {
@@ -216,7 +379,7 @@ where
                 "std::optional<{}>",
                 self.quote_type(format, require_known_size)
             ),
-            Seq(format) => format!("std::vector<{}>", self.quote_type(format, false)),
+            Seq(format) | Set(format) => format!("std::vector<{}>", self.quote_type(format, false)),
             Map { key, value } => format!(
                 "std::map<{}, {}>",
                 self.quote_type(key, false),
@@ -231,8 +394,18 @@ where
                 self.quote_type(content, require_known_size),
                 *size
             ),
+            Custom { name, layout } => {
+                if self.generator.config.type_aliases {
+                    self.quote_qualified_name(name)
+                } else {
+                    self.quote_type(layout, require_known_size)
+                }
+            }
+            Decimal { mantissa, .. } => self.quote_type(mantissa, require_known_size),
 
-            Variable(_) => panic!("unexpected value"),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
         }
     }
 
@@ -251,13 +424,19 @@ where
     ) -> Result<()> {
         writeln!(self.out)?;
         self.output_comment(name)?;
-        writeln!(self.out, "struct {} {{", name)?;
+        writeln!(
+            self.out,
+            "struct {}{} {{",
+            self.quote_deprecation(name),
+            name
+        )?;
         self.enter_class(name);
         for field in fields {
             self.output_comment(&field.name)?;
             writeln!(
                 self.out,
-                "{} {};",
+                "{}{} {};",
+                self.quote_deprecation(&field.name),
                 self.quote_type(&field.value, true),
                 field.name
             )?;
@@ -265,26 +444,48 @@ where
         if !fields.is_empty() {
             writeln!(self.out)?;
         }
+        self.output_flags(fields)?;
         self.output_class_method_declarations(name)?;
         self.output_custom_code()?;
         self.leave_class();
         writeln!(self.out, "}};")
     }
 
+    fn output_flags(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        let flag_sets = common::flag_sets(self.generator.config, &self.current_namespace, fields);
+        for (field, flags) in flag_sets {
+            let field_type = self.quote_type(&field.value, true);
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "static constexpr {} {}_{} = {:#x};",
+                    field_type,
+                    field.name.to_shouty_snake_case(),
+                    flag.to_shouty_snake_case(),
+                    value
+                )?;
+            }
+            writeln!(
+                self.out,
+                r#"
+bool has_{0}({1} mask) const {{ return ({0} & mask) == mask; }}
+void set_{0}({1} mask) {{ {0} |= mask; }}
+void clear_{0}({1} mask) {{ {0} &= static_cast<{1}>(~mask); }}
+"#,
+                field.name, field_type
+            )?;
+        }
+        Ok(())
+    }
+
     fn output_variant(&mut self, name: &str, variant: &VariantFormat) -> Result<()> {
         use VariantFormat::*;
         let fields = match variant {
             Unit => Vec::new(),
-            NewType(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            Tuple(formats) => vec![Named {
-                name: "value".to_string(),
-                value: Format::Tuple(formats.clone()),
-            }],
+            NewType(format) => vec![Named::new("value", format.as_ref().clone())],
+            Tuple(formats) => vec![Named::new("value", Format::Tuple(formats.clone()))],
             Struct(fields) => fields.clone(),
-            Variable(_) => panic!("incorrect value"),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         };
         self.output_struct_or_variant_container(name, &fields)
     }
@@ -293,6 +494,37 @@ where
         writeln!(self.out, "\nstruct {};", name)
     }
 
+    fn output_type_alias(
+        &mut self,
+        registry: &'a Registry,
+        name: &str,
+        layout: &Format,
+    ) -> Result<()> {
+        let mut dependencies = Vec::new();
+        layout
+            .visit(&mut |f| {
+                if let Format::TypeName(x) = f {
+                    if let Some((key, _)) = registry.get_key_value(x) {
+                        dependencies.push(key.as_str());
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+        for dependency in dependencies {
+            if !self.known_names.contains(dependency) {
+                self.output_container_forward_definition(dependency)?;
+                self.known_names.insert(dependency);
+            }
+        }
+        writeln!(
+            self.out,
+            "\nusing {} = {};",
+            name,
+            self.quote_type(layout, true)
+        )
+    }
+
     fn output_enum_container(
         &mut self,
         name: &str,
@@ -300,7 +532,12 @@ where
     ) -> Result<()> {
         writeln!(self.out)?;
         self.output_comment(name)?;
-        writeln!(self.out, "struct {} {{", name)?;
+        writeln!(
+            self.out,
+            "struct {}{} {{",
+            self.quote_deprecation(name),
+            name
+        )?;
         self.enter_class(name);
         for (expected_index, (index, variant)) in variants.iter().enumerate() {
             assert_eq!(*index, expected_index as u32);
@@ -316,19 +553,56 @@ where
                 .join(", "),
         )?;
         writeln!(self.out)?;
+        if self.generator.config.variant_names {
+            self.output_variant_names(variants)?;
+        }
         self.output_class_method_declarations(name)?;
         self.output_custom_code()?;
         self.leave_class();
         writeln!(self.out, "}};")
     }
 
+    fn output_variant_names(
+        &mut self,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"static constexpr const char *variant_names[] = {{{0}}};
+
+static const char *variant_name(size_t index) {{
+    return index < {1} ? variant_names[index] : nullptr;
+}}
+
+static std::optional<size_t> variant_index(const std::string &name) {{
+    for (size_t index = 0; index < {1}; index++) {{
+        if (name == variant_names[index]) {{
+            return index;
+        }}
+    }}
+    return std::nullopt;
+}}
+
+const char *variant_name() const {{
+    return variant_names[value.index()];
+}}
+"#,
+            variants
+                .values()
+                .map(|variant| format!("{:?}", variant.name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            variants.len()
+        )
+    }
+
     fn output_class_method_declarations(&mut self, name: &str) -> Result<()> {
         writeln!(
             self.out,
             "friend bool operator==(const {}&, const {}&);",
             name, name
         )?;
-        if self.generator.config.serialization {
+        if self.generator.config.serialization && !self.generator.free_functions {
             for encoding in &self.generator.config.encodings {
                 writeln!(
                     self.out,
@@ -341,6 +615,22 @@ where
                     name,
                     encoding.name()
                 )?;
+                if self.generator.config.checksums {
+                    writeln!(
+                        self.out,
+                        "std::vector<uint8_t> {0}SerializeChecked() const;\nstatic {1} {0}DeserializeChecked(std::vector<uint8_t>);",
+                        encoding.name(),
+                        name
+                    )?;
+                }
+                if self.generator.config.file_decoding {
+                    writeln!(
+                        self.out,
+                        "static {} {}DeserializeFile(const std::string &path);",
+                        name,
+                        encoding.name()
+                    )?;
+                }
             }
         }
         Ok(())
@@ -350,16 +640,10 @@ where
         use ContainerFormat::*;
         let fields = match format {
             UnitStruct => Vec::new(),
-            NewTypeStruct(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            TupleStruct(formats) => vec![Named {
-                name: "value".to_string(),
-                value: Format::Tuple(formats.clone()),
-            }],
+            NewTypeStruct(format) => vec![Named::new("value", format.as_ref().clone())],
+            TupleStruct(formats) => vec![Named::new("value", Format::Tuple(formats.clone()))],
             Struct(fields) => fields.clone(),
-            Enum(variants) => {
+            Enum(variants) | InternallyTaggedEnum { variants, .. } => {
                 self.output_enum_container(name, variants)?;
                 return Ok(());
             }
@@ -397,12 +681,17 @@ where
 inline std::vector<uint8_t> {}::{}Serialize() const {{
     auto serializer = serde::{}Serializer();
     serde::Serializable<{}>::serialize(*this, serializer);
-    return std::move(serializer).bytes();
+    return {};
 }}"#,
             name,
             encoding.name(),
             encoding.name().to_camel_case(),
-            name
+            name,
+            if self.generator.config.envelope_hooks {
+                "serde::envelope::wrap(std::move(serializer).bytes())"
+            } else {
+                "std::move(serializer).bytes()"
+            },
         )
     }
 
@@ -414,7 +703,7 @@ inline std::vector<uint8_t> {}::{}Serialize() const {{
         writeln!(
             self.out,
             r#"
-inline {} {}::{}Deserialize(std::vector<uint8_t> input) {{
+inline {} {}::{}Deserialize(std::vector<uint8_t> input) {{{}
     auto deserializer = serde::{}Deserializer(input);
     auto value = serde::Deserializable<{}>::deserialize(deserializer);
     if (deserializer.get_buffer_offset() < input.size()) {{
@@ -425,25 +714,80 @@ inline {} {}::{}Deserialize(std::vector<uint8_t> input) {{
             name,
             name,
             encoding.name(),
+            if self.generator.config.envelope_hooks {
+                "\n    input = serde::envelope::unwrap(std::move(input));"
+            } else {
+                ""
+            },
             encoding.name().to_camel_case(),
             name,
         )
     }
 
-    fn output_struct_serializable(
+    fn output_struct_checked_methods_for_encoding(
         &mut self,
         name: &str,
-        fields: &[&str],
-        is_container: bool,
+        encoding: Encoding,
     ) -> Result<()> {
         writeln!(
             self.out,
             r#"
-template <>
-template <typename Serializer>
-void serde::Serializable<{0}>::serialize(const {0} &obj, Serializer &serializer) {{"#,
+inline std::vector<uint8_t> {0}::{1}SerializeChecked() const {{
+    return serde::checksum::append({1}Serialize());
+}}
+
+inline {0} {0}::{1}DeserializeChecked(std::vector<uint8_t> input) {{
+    return {1}Deserialize(serde::checksum::verify(std::move(input)));
+}}"#,
             name,
-        )?;
+            encoding.name(),
+        )
+    }
+
+    fn output_struct_deserialize_file_for_encoding(
+        &mut self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<()> {
+        if self.generator.config.envelope_hooks {
+            // Envelope hooks operate on owned bytes.
+            return writeln!(
+                self.out,
+                r#"
+inline {0} {0}::{1}DeserializeFile(const std::string &path) {{
+    serde::MappedFile file(path);
+    return {0}::{1}Deserialize(std::vector<uint8_t>(file.bytes().begin(), file.bytes().end()));
+}}"#,
+                name,
+                encoding.name(),
+            );
+        }
+        writeln!(
+            self.out,
+            r#"
+inline {0} {0}::{1}DeserializeFile(const std::string &path) {{
+    serde::MappedFile file(path);
+    auto deserializer = serde::{2}Deserializer(file.bytes());
+    auto value = serde::Deserializable<{0}>::deserialize(deserializer);
+    if (deserializer.get_buffer_offset() < file.bytes().size()) {{
+        throw serde::deserialization_error("Some input bytes were not read");
+    }}
+    return value;
+}}"#,
+            name,
+            encoding.name(),
+            encoding.name().to_camel_case(),
+        )
+    }
+
+    fn output_struct_serializable(
+        &mut self,
+        name: &str,
+        fields: &[&str],
+        is_container: bool,
+        accessors: AccessorConvention,
+    ) -> Result<()> {
+        writeln!(self.out, "\n{}", self.quote_serialize_header(name))?;
         self.out.indent();
         if is_container {
             writeln!(self.out, "serializer.increase_container_depth();")?;
@@ -451,8 +795,9 @@ void serde::Serializable<{0}>::serialize(const {0} &obj, Serializer &serializer)
         for field in fields {
             writeln!(
                 self.out,
-                "serde::Serializable<decltype(obj.{0})>::serialize(obj.{0}, serializer);",
-                field,
+                "serde::Serializable<{}>::serialize({}, serializer);",
+                quote_field_type(accessors, field),
+                quote_field_read(accessors, field),
             )?;
         }
         if is_container {
@@ -467,54 +812,262 @@ void serde::Serializable<{0}>::serialize(const {0} &obj, Serializer &serializer)
         name: &str,
         fields: &[&str],
         is_container: bool,
+        accessors: AccessorConvention,
     ) -> Result<()> {
-        writeln!(
-            self.out,
-            r#"
-template <>
-template <typename Deserializer>
-{0} serde::Deserializable<{0}>::deserialize(Deserializer &deserializer) {{"#,
-            name,
-        )?;
+        writeln!(self.out, "\n{}", self.quote_deserialize_header(name))?;
         self.out.indent();
         if is_container {
             writeln!(self.out, "deserializer.increase_container_depth();")?;
         }
-        writeln!(self.out, "{} obj;", name)?;
+        if !self.generator.free_functions {
+            writeln!(self.out, "{} obj;", name)?;
+        }
         for field in fields {
-            writeln!(
-                self.out,
-                "obj.{0} = serde::Deserializable<decltype(obj.{0})>::deserialize(deserializer);",
-                field,
-            )?;
+            let value = format!(
+                "serde::Deserializable<{}>::deserialize(deserializer)",
+                quote_field_type(accessors, field),
+            );
+            writeln!(self.out, "{};", quote_field_write(accessors, field, &value))?;
         }
         if is_container {
             writeln!(self.out, "deserializer.decrease_container_depth();")?;
         }
-        writeln!(self.out, "return obj;")?;
+        if !self.generator.free_functions {
+            writeln!(self.out, "return obj;")?;
+        }
         self.out.unindent();
         writeln!(self.out, "}}")
     }
 
+    /// First line(s) of the definition of the serialization of the type `name`, whose body
+    /// refers to the value as `obj`.
+    fn quote_serialize_header(&self, name: &str) -> String {
+        if self.generator.free_functions {
+            return format!(
+                "template <typename Serializer>\nvoid serialize(const {} &obj, Serializer &serializer) {{",
+                name
+            );
+        }
+        format!(
+            "template <>\ntemplate <typename Serializer>\nvoid serde::Serializable<{0}>::serialize(const {0} &obj, Serializer &serializer) {{",
+            name
+        )
+    }
+
+    /// First line(s) of the definition of the deserialization of the type `name`. Free
+    /// functions fill in `obj`, while trait methods must declare and return it.
+    fn quote_deserialize_header(&self, name: &str) -> String {
+        if self.generator.free_functions {
+            return format!(
+                "template <typename Deserializer>\nvoid deserialize({} &obj, Deserializer &deserializer) {{",
+                name
+            );
+        }
+        format!(
+            "template <>\ntemplate <typename Deserializer>\n{0} serde::Deserializable<{0}>::deserialize(Deserializer &deserializer) {{",
+            name
+        )
+    }
+
+    /// Free functions are declared in the namespace `serde`, where argument-dependent lookup
+    /// finds them through the type of the (de)serializer.
+    fn output_open_free_functions(&mut self) -> Result<()> {
+        if self.generator.free_functions {
+            writeln!(self.out, "\nnamespace serde {{")?;
+            self.out.indent();
+        }
+        Ok(())
+    }
+
+    fn output_close_free_functions(&mut self) -> Result<()> {
+        if self.generator.free_functions {
+            self.out.unindent();
+            writeln!(self.out, "\n}} // end of namespace serde")?;
+        }
+        Ok(())
+    }
+
     fn output_struct_traits(
         &mut self,
         name: &str,
         fields: &[&str],
         is_container: bool,
+        methods: bool,
+    ) -> Result<()> {
+        if methods {
+            self.output_struct_methods(name, fields)?;
+        }
+        let accessors = self.field_accessors(methods);
+        let namespaced_name = self.quote_qualified_name(name);
+        if self.generator.config.serialization {
+            self.output_open_free_functions()?;
+            self.output_struct_serializable(&namespaced_name, fields, is_container, accessors)?;
+            self.output_struct_deserializable(&namespaced_name, fields, is_container, accessors)?;
+            self.output_close_free_functions()?;
+        }
+        Ok(())
+    }
+
+    /// Same as `output_struct_traits` for an internally tagged enum: the name of the variant is
+    /// serialized as a string instead of the variant index.
+    fn output_tagged_enum_traits(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+        methods: bool,
     ) -> Result<()> {
+        if methods {
+            self.output_struct_methods(name, &["value"])?;
+        }
+        if !self.generator.config.serialization {
+            return Ok(());
+        }
+        let accessors = self.field_accessors(methods);
+        let value = quote_field_read(accessors, "value");
+        let namespaced_name = self.quote_qualified_name(name);
+        self.output_open_free_functions()?;
+        writeln!(
+            self.out,
+            r#"
+{}
+    serializer.increase_container_depth();
+    switch ({}.index()) {{"#,
+            self.quote_serialize_header(&namespaced_name),
+            value,
+        )?;
+        for (index, variant) in variants.values().enumerate() {
+            writeln!(
+                self.out,
+                "    case {}: serializer.serialize_str({:?}); break;",
+                index, variant.name
+            )?;
+        }
+        writeln!(
+            self.out,
+            r#"    }}
+    std::visit([&](const auto &content) {{
+        serde::Serializable<std::decay_t<decltype(content)>>::serialize(content, serializer);
+    }}, {});
+    serializer.decrease_container_depth();
+}}
+
+{}
+    deserializer.increase_container_depth();
+    auto tag = deserializer.deserialize_str();{}"#,
+            value,
+            self.quote_deserialize_header(&namespaced_name),
+            if self.generator.free_functions {
+                String::new()
+            } else {
+                format!("\n    {} obj;", namespaced_name)
+            },
+        )?;
+        for (index, variant) in variants.values().enumerate() {
+            let content = format!(
+                "serde::Deserializable<{}::{}>::deserialize(deserializer)",
+                namespaced_name, variant.name,
+            );
+            writeln!(
+                self.out,
+                "    {}if (tag == {:?}) {{
+        {};
+    }}",
+                if index > 0 { "else " } else { "" },
+                variant.name,
+                quote_field_write(accessors, "value", &content),
+            )?;
+        }
+        writeln!(
+            self.out,
+            r#"    else {{
+        throw serde::deserialization_error("Unknown variant name for enum");
+    }}
+    deserializer.decrease_container_depth();{}
+}}"#,
+            if self.generator.free_functions {
+                ""
+            } else {
+                "\n    return obj;"
+            },
+        )?;
+        self.output_close_free_functions()
+    }
+
+    /// How the (de)serialization code of a container reads and assigns fields: generated
+    /// types, which come with their own methods, expose public fields, while hand-written types
+    /// follow the configured convention.
+    fn field_accessors(&self, methods: bool) -> AccessorConvention {
+        if methods {
+            AccessorConvention::Fields
+        } else {
+            self.generator.config.accessor_convention
+        }
+    }
+
+    fn output_struct_methods(&mut self, name: &str, fields: &[&str]) -> Result<()> {
         self.output_open_namespace()?;
         self.output_struct_equality_test(name, fields)?;
-        if self.generator.config.serialization {
+        if self.generator.config.serialization && !self.generator.free_functions {
             for encoding in &self.generator.config.encodings {
-                self.output_struct_serialize_for_encoding(&name, *encoding)?;
-                self.output_struct_deserialize_for_encoding(&name, *encoding)?;
+                self.output_struct_serialize_for_encoding(name, *encoding)?;
+                self.output_struct_deserialize_for_encoding(name, *encoding)?;
+                if self.generator.config.checksums {
+                    self.output_struct_checked_methods_for_encoding(name, *encoding)?;
+                }
+                if self.generator.config.file_decoding {
+                    self.output_struct_deserialize_file_for_encoding(name, *encoding)?;
+                }
             }
         }
-        self.output_close_namespace()?;
-        let namespaced_name = self.quote_qualified_name(name);
-        if self.generator.config.serialization {
-            self.output_struct_serializable(&namespaced_name, fields, is_container)?;
-            self.output_struct_deserializable(&namespaced_name, fields, is_container)?;
+        self.output_close_namespace()
+    }
+
+    /// Whether to generate top-level entry points, i.e. for root formats, for hand-written
+    /// types, or, with free functions, for containers.
+    fn has_root_formats(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization
+            && !config.encodings.is_empty()
+            && (!config.root_formats.is_empty()
+                || !config.hand_written_types.is_empty()
+                || self.generator.free_functions)
+    }
+
+    fn output_root_format(&mut self, name: &str, format: &Format) -> Result<()> {
+        let value_type = self.quote_type(format, false);
+        for encoding in &self.generator.config.encodings {
+            writeln!(
+                self.out,
+                r#"
+inline std::vector<uint8_t> {0}Serialize{1}(const {2} &value) {{
+    auto serializer = serde::{3}Serializer();
+    serde::Serializable<{2}>::serialize(value, serializer);
+    return {4};
+}}
+
+inline {2} {0}Deserialize{1}(std::vector<uint8_t> input) {{{5}
+    auto deserializer = serde::{3}Deserializer(input);
+    auto value = serde::Deserializable<{2}>::deserialize(deserializer);
+    if (deserializer.get_buffer_offset() < input.size()) {{
+        throw serde::deserialization_error("Some input bytes were not read");
+    }}
+    return value;
+}}"#,
+                encoding.name(),
+                name,
+                value_type,
+                encoding.name().to_camel_case(),
+                if self.generator.config.envelope_hooks {
+                    "serde::envelope::wrap(std::move(serializer).bytes())"
+                } else {
+                    "std::move(serializer).bytes()"
+                },
+                if self.generator.config.envelope_hooks {
+                    "\n    input = serde::envelope::unwrap(std::move(input));"
+                } else {
+                    ""
+                },
+            )?;
         }
         Ok(())
     }
@@ -529,16 +1082,18 @@ template <typename Deserializer>
                 .iter()
                 .map(|field| field.name.as_str())
                 .collect::<Vec<_>>(),
-            Variable(_) => panic!("incorrect value"),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         }
     }
 
     fn output_container_traits(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
+        // Hand-written types come with their own equality.
+        let methods = !self.generator.is_hand_written(name);
         match format {
-            UnitStruct => self.output_struct_traits(name, &[], true),
-            NewTypeStruct(_format) => self.output_struct_traits(name, &["value"], true),
-            TupleStruct(_formats) => self.output_struct_traits(name, &["value"], true),
+            UnitStruct => self.output_struct_traits(name, &[], true, methods),
+            NewTypeStruct(_format) => self.output_struct_traits(name, &["value"], true, methods),
+            TupleStruct(_formats) => self.output_struct_traits(name, &["value"], true, methods),
             Struct(fields) => self.output_struct_traits(
                 name,
                 &fields
@@ -546,14 +1101,20 @@ template <typename Deserializer>
                     .map(|field| field.name.as_str())
                     .collect::<Vec<_>>(),
                 true,
+                methods,
             ),
-            Enum(variants) => {
-                self.output_struct_traits(name, &["value"], true)?;
+            Enum(variants) | InternallyTaggedEnum { variants, .. } => {
+                if let InternallyTaggedEnum { .. } = format {
+                    self.output_tagged_enum_traits(name, variants, methods)?;
+                } else {
+                    self.output_struct_traits(name, &["value"], true, methods)?;
+                }
                 for variant in variants.values() {
                     self.output_struct_traits(
                         &format!("{}::{}", name, variant.name),
                         &Self::get_variant_fields(&variant.value),
                         false,
+                        methods,
                     )?;
                 }
                 Ok(())
@@ -562,14 +1123,75 @@ template <typename Deserializer>
     }
 }
 
+/// Expression reading the field `field` of `obj`.
+fn quote_field_read(accessors: AccessorConvention, field: &str) -> String {
+    match accessors {
+        AccessorConvention::Fields => format!("obj.{}", field),
+        AccessorConvention::Methods => format!("obj.{}()", field),
+        AccessorConvention::JavaBeans => format!("obj.get{}()", field.to_camel_case()),
+    }
+}
+
+/// Statement (without semicolon) assigning `value` to the field `field` of `obj`.
+fn quote_field_write(accessors: AccessorConvention, field: &str, value: &str) -> String {
+    match accessors {
+        AccessorConvention::Fields => format!("obj.{} = {}", field, value),
+        AccessorConvention::Methods => format!("obj.set_{}({})", field, value),
+        AccessorConvention::JavaBeans => format!("obj.set{}({})", field.to_camel_case(), value),
+    }
+}
+
+/// Type of the field `field` of `obj`. Getters may return references.
+fn quote_field_type(accessors: AccessorConvention, field: &str) -> String {
+    match accessors {
+        AccessorConvention::Fields => format!("decltype(obj.{})", field),
+        _ => format!(
+            "std::decay_t<decltype({})>",
+            quote_field_read(accessors, field)
+        ),
+    }
+}
+
 /// Installer for generated source files in C++.
+fn runtime_header(name: &str) -> &'static str {
+    match name {
+        "serde" => include_str!("../runtime/cpp/serde.hpp"),
+        "binary" => include_str!("../runtime/cpp/binary.hpp"),
+        "bincode" => include_str!("../runtime/cpp/bincode.hpp"),
+        "bcs" => include_str!("../runtime/cpp/bcs.hpp"),
+        "mmap" => include_str!("../runtime/cpp/mmap.hpp"),
+        _ => panic!("unknown runtime header {}", name),
+    }
+}
+
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
+    single_header: bool,
+    free_functions: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            single_header: false,
+            free_functions: false,
+        }
+    }
+
+    /// Whether to install a single self-contained header for the module, in which case
+    /// runtimes are not installed separately.
+    pub fn with_single_header(mut self, single_header: bool) -> Self {
+        self.single_header = single_header;
+        self
+    }
+
+    /// Generate (de)serialization code as free functions, and only the codecs of the containers
+    /// listed in the external definitions (see `CodeGenerator::with_free_functions`).
+    pub fn with_free_functions(mut self, free_functions: bool) -> Self {
+        self.free_functions = free_functions;
+        self
     }
 
     fn create_header_file(&self, name: &str) -> Result<std::fs::File> {
@@ -577,38 +1199,95 @@ impl Installer {
         std::fs::create_dir_all(dir_path)?;
         std::fs::File::create(dir_path.join(name.to_string() + ".hpp"))
     }
+
+    fn create_module_header_file<'a>(
+        &self,
+        config: &'a CodeGeneratorConfig,
+        name: &str,
+    ) -> Result<common::SourceFile<'a>> {
+        let dir_path = &self.install_dir;
+        std::fs::create_dir_all(dir_path)?;
+        common::create_source_file(config, dir_path.join(name.to_string() + ".hpp"))
+    }
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
 
     fn install_module(
         &self,
         config: &crate::CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
-        let mut file = self.create_header_file(&config.module_name)?;
-        let generator = CodeGenerator::new(&config);
-        generator.output(&mut file, &registry)
+        let mut file = self.create_module_header_file(config, &config.module_name)?;
+        let generator = CodeGenerator::new(config)
+            .with_single_header(
+                self.single_header || config.output_layout == OutputLayout::SingleFile,
+            )
+            .with_part_boundaries(config.max_file_lines.is_some())
+            .with_free_functions(self.free_functions);
+        let max_lines = match config.max_file_lines {
+            None => return generator.output(&mut file, registry),
+            Some(max_lines) => max_lines,
+        };
+        // The main header includes the other parts at the end.
+        let mut content = Vec::new();
+        generator.output(&mut content, registry)?;
+        let content = String::from_utf8(content).expect("generated code should be UTF-8");
+        let parts = common::group_chunks(&common::split_chunks(&content), max_lines);
+        for (index, part) in parts.iter().enumerate() {
+            if index == 0 {
+                write!(file, "{}", part)?;
+                continue;
+            }
+            let name = format!("{}_part{}", config.module_name, index);
+            writeln!(file, "#include \"{}.hpp\"", name)?;
+            let mut part_file = self.create_module_header_file(config, &name)?;
+            write!(
+                part_file,
+                "// Part {} of {}.hpp\n#pragma once\n{}",
+                index, config.module_name, part
+            )?;
+        }
+        Ok(())
     }
 
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        if self.single_header {
+            return Ok(());
+        }
         let mut file = self.create_header_file("serde")?;
         write!(file, "{}", include_str!("../runtime/cpp/serde.hpp"))?;
         let mut file = self.create_header_file("binary")?;
         write!(file, "{}", include_str!("../runtime/cpp/binary.hpp"))?;
+        let mut file = self.create_header_file("mmap")?;
+        write!(file, "{}", include_str!("../runtime/cpp/mmap.hpp"))?;
+        let mut file = self.create_header_file("test_vectors")?;
+        write!(file, "{}", include_str!("../runtime/cpp/test_vectors.hpp"))?;
         Ok(())
     }
 
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
-        let mut file = self.create_header_file("bincode")?;
-        write!(file, "{}", include_str!("../runtime/cpp/bincode.hpp"))?;
-        Ok(())
-    }
-
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        let mut file = self.create_header_file("bcs")?;
-        write!(file, "{}", include_str!("../runtime/cpp/bcs.hpp"))?;
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
+        if self.single_header {
+            return Ok(());
+        }
+        let source = match encoding {
+            Encoding::Bincode => include_str!("../runtime/cpp/bincode.hpp"),
+            Encoding::Bcs => include_str!("../runtime/cpp/bcs.hpp"),
+        };
+        let mut file = self.create_header_file(encoding.name())?;
+        write!(file, "{}", source)?;
         Ok(())
     }
 }
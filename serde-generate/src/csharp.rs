@@ -2,17 +2,17 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    common,
+    common::{self, VariantTag},
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    CodeGeneratorConfig, Encoding, RuntimeSource,
 };
 use heck::CamelCase;
 use include_dir::include_dir as include_directory;
-use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::{
     collections::{BTreeMap, HashMap},
     io::{Result, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 /// Main configuration object for code-generation in C#.
@@ -24,6 +24,12 @@ pub struct CodeGenerator<'a> {
     external_qualified_names: HashMap<String, String>,
 }
 
+/// Note on thread safety added to the documentation of generated classes.
+const THREAD_SAFETY_NOTE: &str =
+    "Thread safety: instances may be shared between threads as long as they are not
+modified. Serialization and deserialization methods are reentrant: each call uses its own
+serializer or deserializer.";
+
 /// Shared state for the code generation of a C# source file.
 struct CSharpEmitter<'a, T> {
     /// Writer.
@@ -65,7 +71,11 @@ impl<'a> CodeGenerator<'a> {
         &self,
         install_dir: std::path::PathBuf,
         registry: &Registry,
-    ) -> Result<std::path::PathBuf> {
+    ) -> crate::Result<std::path::PathBuf> {
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("C#", registry)?;
+        common::check_options("C#", self.config, &[])?;
         let current_namespace = self
             .config
             .module_name
@@ -93,6 +103,7 @@ impl<'a> CodeGenerator<'a> {
         }
 
         for (name, format) in registry {
+            common::check_cancellation(self.config)?;
             self.write_container_class(
                 &dir_path,
                 current_namespace.clone(),
@@ -115,7 +126,8 @@ impl<'a> CodeGenerator<'a> {
         name: &str,
         format: &ContainerFormat,
     ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join(name.to_string() + ".cs"))?;
+        let mut file =
+            common::create_source_file(self.config, dir_path.join(name.to_string() + ".cs"))?;
         let mut emitter = CSharpEmitter {
             out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
             generator: self,
@@ -138,8 +150,8 @@ impl<'a> CodeGenerator<'a> {
         current_namespace: Vec<String>,
         cstyle_enum_names: Vec<String>,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join("TraitHelpers.cs"))?;
+    ) -> crate::Result<()> {
+        let mut file = common::create_source_file(self.config, dir_path.join("TraitHelpers.cs"))?;
         let mut emitter = CSharpEmitter {
             out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
             generator: self,
@@ -225,12 +237,24 @@ using System.Numerics;"
     }
 
     fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
+        self.output_comment_with_note(name, None)
+    }
+
+    /// Same as `output_comment` followed by the paragraph `note` on thread safety, if requested.
+    fn output_comment_with_note(&mut self, name: &str, note: Option<&str>) -> std::io::Result<()> {
         let mut path = self.current_namespace.clone();
         path.push(name.to_string());
-        if let Some(doc) = self.generator.config.comments.get(&path) {
-            let text = textwrap::indent(doc, "/// ").replace("\n\n", "\n///\n");
+        let mut doc = self.generator.config.comment(&path);
+        if let Some(note) = note {
+            doc = self.generator.config.with_thread_safety_note(doc, note);
+        }
+        if let Some(doc) = doc {
+            let text = textwrap::indent(&doc, "/// ").replace("\n\n", "\n///\n");
             write!(self.out, "{}", text)?;
         }
+        if let Some(message) = self.generator.config.deprecations.get(&path) {
+            writeln!(self.out, "[Obsolete({:?})]", message.trim())?;
+        }
         Ok(())
     }
 
@@ -250,8 +274,11 @@ using System.Numerics;"
         use Format::*;
         match format {
             TypeName(name) => !self.cstyle_enum_names.contains(name),
-            Str | Seq(_) | Map { .. } | TupleArray { .. } => true,
-            Variable(_) => panic!("unexpected value"),
+            Str | Seq(_) | Set(_) | Map { .. } | TupleArray { .. } => true,
+            Custom { layout, .. } => self.is_nullable(layout),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
             _ => false,
         }
     }
@@ -280,6 +307,7 @@ using System.Numerics;"
 
             Option(format) => format!("Serde.Option<{}>", self.quote_type(format)),
             Seq(format) => format!("Serde.ValueArray<{}>", self.quote_type(format)),
+            Set(format) => format!("Serde.ValueSet<{}>", self.quote_type(format)),
             Map { key, value } => format!(
                 "Serde.ValueDictionary<{}, {}>",
                 self.quote_type(key),
@@ -290,7 +318,11 @@ using System.Numerics;"
                 content,
                 size: _size,
             } => format!("Serde.ValueArray<{}>", self.quote_type(content),),
-            Variable(_) => panic!("unexpected value"),
+            Custom { layout, .. } => self.quote_type(layout),
+            Decimal { .. } => "decimal".into(),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
         }
     }
 
@@ -326,18 +358,9 @@ using System.Numerics;"
             .join(", ")
     }
 
-    fn output_trait_helpers(&mut self, registry: &Registry) -> Result<()> {
-        let mut subtypes = BTreeMap::new();
-        for format in registry.values() {
-            format
-                .visit(&mut |f| {
-                    if Self::needs_helper(f) {
-                        subtypes.insert(common::mangle_type(f), f.clone());
-                    }
-                    Ok(())
-                })
-                .unwrap();
-        }
+    fn output_trait_helpers(&mut self, registry: &Registry) -> crate::Result<()> {
+        let subtypes =
+            common::helper_formats(self.generator.config, registry, false, Self::needs_helper)?;
         writeln!(self.out, "static class TraitHelpers {{")?;
         let reserved_names = &[];
         self.enter_class("TraitHelpers", reserved_names);
@@ -346,14 +369,15 @@ using System.Numerics;"
             self.output_deserialization_helper(mangled_name, subtype)?;
         }
         self.leave_class(reserved_names);
-        writeln!(self.out, "}}\n")
+        writeln!(self.out, "}}\n")?;
+        Ok(())
     }
 
     fn needs_helper(format: &Format) -> bool {
         use Format::*;
         matches!(
             format,
-            Option(_) | Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. }
+            Option(_) | Seq(_) | Set(_) | Map { .. } | Tuple(_) | TupleArray { .. }
         )
     }
 
@@ -378,10 +402,22 @@ using System.Numerics;"
             Char => format!("serializer.serialize_char({});", value),
             Str => format!("serializer.serialize_str({});", value),
             Bytes => format!("serializer.serialize_bytes({});", value),
+            Custom { layout, .. } => self.quote_serialize_value(value, layout),
+            Decimal { mantissa, scale } => {
+                let mantissa_value = format!("Serde.FixedPoint.ToMantissa({}, {})", value, scale);
+                match **mantissa {
+                    I128 => self.quote_serialize_value(&mantissa_value, mantissa),
+                    // The conversion fails with an `OverflowException` if the value does not fit.
+                    _ => self.quote_serialize_value(
+                        &format!("({}){}", self.quote_type(mantissa), mantissa_value),
+                        mantissa,
+                    ),
+                }
+            }
             _ => format!(
                 "{}.serialize_{}({}, serializer);",
                 self.quote_qualified_name("TraitHelpers"),
-                common::mangle_type(format),
+                common::helper_name(self.generator.config, format),
                 value
             ),
         }
@@ -421,10 +457,16 @@ using System.Numerics;"
             Char => "deserializer.deserialize_char()".to_string(),
             Str => "deserializer.deserialize_str()".to_string(),
             Bytes => "deserializer.deserialize_bytes()".to_string(),
+            Custom { layout, .. } => self.quote_deserialize(layout),
+            Decimal { mantissa, scale } => format!(
+                "Serde.FixedPoint.FromMantissa({}, {})",
+                self.quote_deserialize(mantissa),
+                scale
+            ),
             _ => format!(
                 "{}.deserialize_{}(deserializer)",
                 self.quote_qualified_name("TraitHelpers"),
-                common::mangle_type(format),
+                common::helper_name(self.generator.config, format),
             ),
         }
     }
@@ -468,6 +510,23 @@ foreach (var item in value) {{
                 )?;
             }
 
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+serializer.serialize_len(value.Count);
+int[] offsets = new int[value.Count];
+int count = 0;
+foreach (var item in value) {{
+    offsets[count++] = serializer.get_buffer_offset();
+    {}
+}}
+serializer.sort_map_entries(offsets);
+"#,
+                    self.quote_serialize_value("item", format)
+                )?;
+            }
+
             Map { key, value } => {
                 write!(
                     self.out,
@@ -546,6 +605,36 @@ if (!tag) {{
                 )?;
             }
 
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+long length = deserializer.deserialize_len();
+var obj = new HashSet<{0}>();
+int previous_item_start = 0;
+int previous_item_end = 0;
+for (long i = 0; i < length; i++) {{
+    int item_start = deserializer.get_buffer_offset();
+    var item = {1};
+    int item_end = deserializer.get_buffer_offset();
+    if (i > 0) {{
+        deserializer.check_that_key_slices_are_increasing(
+            new Serde.Range(previous_item_start, previous_item_end),
+            new Serde.Range(item_start, item_end));
+    }}
+    previous_item_start = item_start;
+    previous_item_end = item_end;
+    if (!obj.Add(item)) {{
+        throw new Serde.DeserializationException("Duplicate elements in set");
+    }}
+}}
+return new Serde.ValueSet<{0}>(obj);
+"#,
+                    self.quote_type(format),
+                    self.quote_deserialize(format)
+                )?;
+            }
+
             Seq(format) => {
                 write!(
                     self.out,
@@ -633,38 +722,34 @@ return new Serde.ValueArray<{0}>(obj);
     fn output_variant(
         &mut self,
         base: &str,
-        index: u32,
+        tag: VariantTag,
         name: &str,
         variant: &VariantFormat,
     ) -> Result<()> {
         use VariantFormat::*;
         let fields = match variant {
             Unit => Vec::new(),
-            NewType(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
+            NewType(format) => vec![Named::new("value", format.as_ref().clone())],
             Tuple(formats) => formats
                 .iter()
                 .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
+                .map(|(i, f)| Named::new(format!("field{}", i), f.clone()))
                 .collect(),
             Struct(fields) => fields.clone(),
-            Variable(_) => panic!("incorrect value"),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         };
-        self.output_struct_or_variant_container(Some(base), Some(index), name, &fields)
+        self.output_struct_or_variant_container(Some(base), Some(tag), name, &fields)
     }
 
     fn output_variants(
         &mut self,
         base: &str,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
+        tag: Option<&str>,
     ) -> Result<()> {
         for (index, variant) in variants {
-            self.output_variant(base, *index, &variant.name, &variant.value)?;
+            let tag = VariantTag::new(tag, *index, &variant.name);
+            self.output_variant(base, tag, &variant.name, &variant.value)?;
         }
         Ok(())
     }
@@ -672,7 +757,7 @@ return new Serde.ValueArray<{0}>(obj);
     fn output_struct_or_variant_container(
         &mut self,
         variant_base: Option<&str>,
-        variant_index: Option<u32>,
+        variant_tag: Option<VariantTag>,
         name: &str,
         fields: &[Named<Format>],
     ) -> Result<()> {
@@ -687,7 +772,7 @@ return new Serde.ValueArray<{0}>(obj);
             )?;
             "override "
         } else {
-            self.output_comment(name)?;
+            self.output_comment_with_note(name, Some(THREAD_SAFETY_NOTE))?;
             writeln!(
                 self.out,
                 "public sealed class {0}: IEquatable<{0}> {{",
@@ -743,8 +828,14 @@ return new Serde.ValueArray<{0}>(obj);
             )?;
             self.out.indent();
             writeln!(self.out, "serializer.increase_container_depth();")?;
-            if let Some(index) = variant_index {
-                writeln!(self.out, "serializer.serialize_variant_index({});", index)?;
+            match variant_tag {
+                Some(VariantTag::Index(index)) => {
+                    writeln!(self.out, "serializer.serialize_variant_index({});", index)?
+                }
+                Some(VariantTag::Name { name, .. }) => {
+                    writeln!(self.out, "serializer.serialize_str({:?});", name)?
+                }
+                None => (),
             }
             for field in fields {
                 writeln!(
@@ -757,7 +848,7 @@ return new Serde.ValueArray<{0}>(obj);
             self.out.unindent();
             writeln!(self.out, "}}")?;
 
-            if variant_index.is_none() {
+            if variant_tag.is_none() {
                 for encoding in &self.generator.config.encodings {
                     self.output_class_serialize_for_encoding(*encoding)?;
                 }
@@ -765,7 +856,7 @@ return new Serde.ValueArray<{0}>(obj);
         }
         // Deserialize (struct) or Load (variant)
         if self.generator.config.serialization {
-            if variant_index.is_none() {
+            if variant_tag.is_none() {
                 writeln!(
                     self.out,
                     "\npublic static {}{} Deserialize(Serde.IDeserializer deserializer) {{",
@@ -795,7 +886,7 @@ return new Serde.ValueArray<{0}>(obj);
             self.out.unindent();
             writeln!(self.out, "}}")?;
 
-            if variant_index.is_none() {
+            if variant_tag.is_none() {
                 for encoding in &self.generator.config.encodings {
                     self.output_class_deserialize_for_encoding(name, *encoding)?;
                 }
@@ -850,6 +941,8 @@ return new Serde.ValueArray<{0}>(obj);
         writeln!(self.out, "}}")?;
         self.out.unindent();
         writeln!(self.out, "}}")?;
+        // Flags
+        self.output_flags(fields)?;
         // Custom code
         self.output_custom_code()?;
         // End of class
@@ -857,13 +950,69 @@ return new Serde.ValueArray<{0}>(obj);
         writeln!(self.out, "}}")
     }
 
+    fn output_flags(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        let flag_sets = common::flag_sets(self.generator.config, &self.current_namespace, fields);
+        for (field, flags) in flag_sets {
+            let field_type = self.quote_type(&field.value);
+            writeln!(self.out)?;
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "public const {} {}{} = {:#x};",
+                    field_type,
+                    field.name.to_camel_case(),
+                    flag.to_camel_case(),
+                    value
+                )?;
+            }
+            writeln!(
+                self.out,
+                r#"
+public bool Has{0}({1} mask) => ({2} & mask) == mask;
+
+public void Set{0}({1} mask) {{ {2} |= mask; }}
+
+public void Clear{0}({1} mask) {{ {2} &= ({1}) ~mask; }}"#,
+                field.name.to_camel_case(),
+                field_type,
+                field.name
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Open a switch on the variant `key` and output the cases loading the given variants. The
+    /// caller outputs the default case and closes the switch.
+    fn output_variant_switch(
+        &mut self,
+        key: &str,
+        tag: Option<&str>,
+        variants: &[(&u32, &Named<VariantFormat>)],
+    ) -> Result<()> {
+        writeln!(self.out, "switch ({}) {{", key)?;
+        self.out.indent();
+        for (index, variant) in variants {
+            writeln!(
+                self.out,
+                "case {}: return {}.Load(deserializer);",
+                match tag {
+                    Some(_) => format!("{:?}", variant.name),
+                    None => index.to_string(),
+                },
+                variant.name,
+            )?;
+        }
+        Ok(())
+    }
+
     fn output_enum_container(
         &mut self,
         name: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
         writeln!(self.out)?;
-        self.output_comment(name)?;
+        self.output_comment_with_note(name, Some(THREAD_SAFETY_NOTE))?;
         writeln!(
             self.out,
             "public abstract class {0}: IEquatable<{0}> {{",
@@ -885,35 +1034,70 @@ return new Serde.ValueArray<{0}>(obj);
                 name
             )?;
             self.out.indent();
-            writeln!(
-                self.out,
-                r#"
-int index = deserializer.deserialize_variant_index();
-switch (index) {{"#,
-            )?;
-            self.out.indent();
-            for (index, variant) in variants {
+            let (key_type, key, kind) = match tag {
+                Some(_) => ("string", "tag", "name"),
+                None => ("int", "index", "index"),
+            };
+            if tag.is_some() {
+                writeln!(self.out, "\nstring tag = deserializer.deserialize_str();")?;
+            } else {
                 writeln!(
                     self.out,
-                    "case {}: return {}.Load(deserializer);",
-                    index, variant.name,
+                    "\nint index = deserializer.deserialize_variant_index();"
+                )?;
+            }
+            let chunks = common::variant_chunks(self.generator.config, variants);
+            if chunks.len() == 1 {
+                self.output_variant_switch(key, tag, &chunks[0])?;
+                writeln!(
+                    self.out,
+                    r#"default: throw new Serde.DeserializationException("Unknown variant {} for {}: " + {});"#,
+                    kind, name, key,
+                )?;
+                self.out.unindent();
+                writeln!(self.out, "}}")?;
+            } else {
+                writeln!(self.out, "{} value;", name)?;
+                for index in 0..chunks.len() {
+                    writeln!(
+                        self.out,
+                        "if ((value = DeserializeVariants{}({}, deserializer)) != null) return value;",
+                        index, key,
+                    )?;
+                }
+                writeln!(
+                    self.out,
+                    r#"throw new Serde.DeserializationException("Unknown variant {} for {}: " + {});"#,
+                    kind, name, key,
                 )?;
             }
-            writeln!(
-                self.out,
-                r#"default: throw new Serde.DeserializationException("Unknown variant index for {}: " + index);"#,
-                name,
-            )?;
-            self.out.unindent();
-            writeln!(self.out, "}}")?;
             self.out.unindent();
             writeln!(self.out, "}}")?;
+            if chunks.len() > 1 {
+                for (index, chunk) in chunks.iter().enumerate() {
+                    write!(
+                        self.out,
+                        "\nprivate static {} DeserializeVariants{}({} {}, Serde.IDeserializer deserializer) {{\n",
+                        name, index, key_type, key,
+                    )?;
+                    self.out.indent();
+                    self.output_variant_switch(key, tag, chunk)?;
+                    writeln!(self.out, "default: return null;")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                }
+            }
 
             for encoding in &self.generator.config.encodings {
                 self.output_class_serialize_for_encoding(*encoding)?;
                 self.output_class_deserialize_for_encoding(name, *encoding)?;
             }
         }
+        if self.generator.config.variant_names {
+            self.output_variant_names(variants)?;
+        }
 
         writeln!(self.out, "public override int GetHashCode() {{")?;
         self.out.indent();
@@ -956,7 +1140,7 @@ switch (index) {{"#,
         self.out.unindent();
         writeln!(self.out, "}}\n")?;
 
-        self.output_variants(name, variants)?;
+        self.output_variants(name, variants, tag)?;
         self.leave_class(&reserved_names);
         writeln!(self.out, "}}\n")
     }
@@ -1024,7 +1208,65 @@ public static byte[] {0}Serialize(this {1} value)  {{
         Ok(())
     }
 
+    fn output_variant_names(
+        &mut self,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+public static readonly System.Collections.ObjectModel.ReadOnlyCollection<string> VariantNames = Array.AsReadOnly(new string[] {{ {} }});
+
+public static string VariantName(int index) => index >= 0 && index < VariantNames.Count ? VariantNames[index] : null;
+
+public static int VariantIndex(string name) => VariantNames.IndexOf(name);
+"#,
+            variants
+                .values()
+                .map(|v| format!("{:?}", v.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
     fn output_class_serialize_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
+        self.output_class_serialize_bytes_for_encoding(encoding)?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+public byte[] {0}SerializeChecked() => Serde.Checksum.Append({0}Serialize());"#,
+                encoding.name().to_camel_case()
+            )?;
+        }
+        Ok(())
+    }
+
+    fn output_class_serialize_bytes_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
+        if self.generator.config.envelope_hooks {
+            // The size of the output is only known after wrapping the serialized bytes.
+            return writeln!(
+                self.out,
+                r#"
+public int {0}Serialize(byte[] outputBuffer) => {0}Serialize(new ArraySegment<byte>(outputBuffer));
+
+public int {0}Serialize(ArraySegment<byte> outputBuffer) {{
+    byte[] bytes = {0}Serialize();
+    if (bytes.Length > outputBuffer.Count) {{
+         throw new Serde.SerializationException("Output buffer is too small");
+    }}
+    Array.Copy(bytes, 0, outputBuffer.Array, outputBuffer.Offset, bytes.Length);
+    return bytes.Length;
+}}
+
+public byte[] {0}Serialize()  {{
+    Serde.ISerializer serializer = new {0}.{0}Serializer();
+    Serialize(serializer);
+    return Serde.Envelope.Wrap(serializer.get_bytes());
+}}"#,
+                encoding.name().to_camel_case()
+            );
+        }
         writeln!(
             self.out,
             r#"
@@ -1058,7 +1300,7 @@ public static {0} {1}Deserialize(byte[] input) => {1}Deserialize(new ArraySegmen
 public static {0} {1}Deserialize(ArraySegment<byte> input) {{
     if (input == null) {{
          throw new Serde.DeserializationException("Cannot deserialize null array");
-    }}
+    }}{2}
     Serde.IDeserializer deserializer = new {1}.{1}Deserializer(input);
     {0} value = Deserialize(deserializer);
     if (deserializer.get_buffer_offset() < input.Count) {{
@@ -1067,25 +1309,39 @@ public static {0} {1}Deserialize(ArraySegment<byte> input) {{
     return value;
 }}"#,
             name,
-            encoding.name().to_camel_case()
-        )
+            encoding.name().to_camel_case(),
+            if self.generator.config.envelope_hooks {
+                "\n    input = new ArraySegment<byte>(Serde.Envelope.Unwrap(input.ToArray()));"
+            } else {
+                ""
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+public static {0} {1}DeserializeChecked(byte[] input) {{
+    if (input == null) {{
+         throw new Serde.DeserializationException("Cannot deserialize null array");
+    }}
+    return {1}Deserialize(Serde.Checksum.Verify(input));
+}}"#,
+                name,
+                encoding.name().to_camel_case()
+            )?;
+        }
+        Ok(())
     }
 
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
         let fields = match format {
             UnitStruct => Vec::new(),
-            NewTypeStruct(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
+            NewTypeStruct(format) => vec![Named::new("value", format.as_ref().clone())],
             TupleStruct(formats) => formats
                 .iter()
                 .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
+                .map(|(i, f)| Named::new(format!("field{}", i), f.clone()))
                 .collect::<Vec<_>>(),
             Struct(fields) => fields.clone(),
             Enum(variants) => {
@@ -1096,30 +1352,42 @@ public static {0} {1}Deserialize(ArraySegment<byte> input) {{
                 {
                     self.output_cstyle_enum(name, variants)?;
                 } else {
-                    self.output_enum_container(name, variants)?;
+                    self.output_enum_container(name, None, variants)?;
                 }
                 return Ok(());
             }
+            InternallyTaggedEnum { tag, variants } => {
+                self.output_enum_container(name, Some(tag), variants)?;
+                return Ok(());
+            }
         };
         self.output_struct_or_variant_container(None, None, name, &fields)
     }
 }
 
 /// Installer for generated source files in C#.
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
+    runtime_source: RuntimeSource,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            runtime_source: RuntimeSource::Vendored,
+        }
     }
 
-    fn install_runtime(
-        &self,
-        source_dir: include_dir::Dir,
-        path: &str,
-    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// Reference the vendored runtime projects `Serde`, `Bincode`, and `Bcs` (the default) or
+    /// a published NuGet package providing the same namespaces.
+    pub fn with_runtime_source(mut self, runtime_source: RuntimeSource) -> Self {
+        self.runtime_source = runtime_source;
+        self
+    }
+
+    fn install_runtime(&self, source_dir: include_dir::Dir, path: &str) -> crate::Result<()> {
         let dir_path = self.install_dir.join(path);
         std::fs::create_dir_all(&dir_path)?;
         for entry in source_dir.files() {
@@ -1131,7 +1399,18 @@ impl Installer {
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
 
     fn install_module(
         &self,
@@ -1143,21 +1422,34 @@ impl crate::SourceInstaller for Installer {
         let dir_path = generator.write_source_files(self.install_dir.clone(), registry)?;
 
         let back_path: String = std::iter::repeat("..\\".to_string())
-            .take(dir_path.strip_prefix(&self.install_dir)?.iter().count())
-            .collect();
-        let mut deps = vec!["Serde".to_string()];
-        for encoding in &config.encodings {
-            deps.push(encoding.name().to_camel_case());
-        }
-        let deps: String = deps
-            .iter()
-            .map(|d| {
-                format!(
-                    "      <ProjectReference Include=\"{1}{0}\\{0}.csproj\" />\n",
-                    d, back_path
-                )
-            })
+            .take(
+                dir_path
+                    .strip_prefix(&self.install_dir)
+                    .expect("source files should be written in the install directory")
+                    .iter()
+                    .count(),
+            )
             .collect();
+        let deps: String = match &self.runtime_source {
+            RuntimeSource::Vendored => {
+                let mut deps = vec!["Serde".to_string()];
+                for encoding in &config.encodings {
+                    deps.push(encoding.name().to_camel_case());
+                }
+                deps.iter()
+                    .map(|d| {
+                        format!(
+                            "      <ProjectReference Include=\"{1}{0}\\{0}.csproj\" />\n",
+                            d, back_path
+                        )
+                    })
+                    .collect()
+            }
+            RuntimeSource::Package { name, version } => format!(
+                "      <PackageReference Include=\"{}\" Version=\"{}\" />\n",
+                name, version
+            ),
+        };
 
         let mut proj = std::fs::File::create(dir_path.join(name + ".csproj"))?;
         write!(
@@ -1183,14 +1475,48 @@ impl crate::SourceInstaller for Installer {
     }
 
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        if self.runtime_source != RuntimeSource::Vendored {
+            return Ok(());
+        }
         self.install_runtime(include_directory!("runtime/csharp/Serde"), "Serde")
     }
 
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.install_runtime(include_directory!("runtime/csharp/Bincode"), "Bincode")
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
+        if self.runtime_source != RuntimeSource::Vendored {
+            return Ok(());
+        }
+        match encoding {
+            Encoding::Bincode => {
+                self.install_runtime(include_directory!("runtime/csharp/Bincode"), "Bincode")
+            }
+            Encoding::Bcs => self.install_runtime(include_directory!("runtime/csharp/Bcs"), "Bcs"),
+        }
     }
 
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.install_runtime(include_directory!("runtime/csharp/Bcs"), "Bcs")
+    fn install_runtime_tests(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        // The runtime tests exercise the vendored BCS runtime.
+        if self.runtime_source != RuntimeSource::Vendored
+            || !config.encodings.contains(&Encoding::Bcs)
+        {
+            return Ok(());
+        }
+        self.install_runtime(
+            include_directory!("runtime/csharp/Serde.Tests"),
+            "Serde.Tests",
+        )?;
+        if !config.encodings.contains(&Encoding::Bincode) {
+            let project = include_str!("../runtime/csharp/Serde.Tests/Serde.Tests.csproj").replace(
+                "    <ProjectReference Include=\"..\\Bincode\\Bincode.csproj\" />\n",
+                "",
+            );
+            std::fs::write(
+                self.install_dir.join("Serde.Tests/Serde.Tests.csproj"),
+                project,
+            )?;
+        }
+        Ok(())
     }
 }
@@ -1,20 +1,33 @@
 use crate::indent::{IndentConfig, IndentedWriter};
-use crate::{common, CodeGeneratorConfig, Encoding};
-use heck::CamelCase;
+use crate::{
+    common::{self, ConfigOption, VariantTag},
+    CodeGeneratorConfig, Encoding, JsonIntegerPolicy, OutputLayout, RuntimeSource, VariantNaming,
+};
+use heck::{CamelCase, MixedCase};
 use include_dir::include_dir as include_directory;
-use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::borrow::Borrow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::{
     collections::HashMap,
     io::{Result, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 /// Main configuration object for code-generation in Dart.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
     config: &'a CodeGeneratorConfig,
+    /// Whether to produce a Flutter package rather than a pure Dart package.
+    flutter: bool,
+    /// Whether JSON conversions are generated by `json_serializable` rather than written out.
+    json_serializable: bool,
+    /// Whether (de)serialization methods are generated as extension methods.
+    codec_extensions: bool,
+    /// Where to import the Serde runtimes from.
+    runtime_source: RuntimeSource,
+    /// Whether 64-bit integers are represented by `BigInt` values, which are exact on the web.
+    web_support: bool,
 }
 
 /// Shared state for the code generation of a Dart source file.
@@ -37,11 +50,90 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}.{}", namespace, name));
             }
         }
-        Self { config }
+        Self {
+            config,
+            flutter: false,
+            json_serializable: false,
+            codec_extensions: false,
+            runtime_source: RuntimeSource::Vendored,
+            web_support: false,
+        }
+    }
+
+    /// Whether to produce a Flutter package (i.e. depending on the Flutter SDK and testing
+    /// with `flutter_test`) rather than a pure Dart package.
+    pub fn with_flutter(mut self, flutter: bool) -> Self {
+        self.flutter = flutter;
+        self
+    }
+
+    /// Whether to annotate classes with `@JsonSerializable()` and let `build_runner` generate
+    /// the JSON conversions in the part file `<module>.g.dart`.
+    pub fn with_json_serializable(mut self, json_serializable: bool) -> Self {
+        self.json_serializable = json_serializable;
+        self
+    }
+
+    /// Whether to generate (de)serialization methods as extension methods (e.g. `FooSerde`
+    /// on `Foo`) rather than members of otherwise plain data classes.
+    pub fn with_codec_extensions(mut self, codec_extensions: bool) -> Self {
+        self.codec_extensions = codec_extensions;
+        self
+    }
+
+    /// Whether to import the runtimes from the vendored libraries `lib/serde`, `lib/bincode`,
+    /// and `lib/bcs` of the generated package, or from the same libraries of a published
+    /// package declared in `pubspec.yaml`.
+    pub fn with_runtime_source(mut self, runtime_source: RuntimeSource) -> Self {
+        self.runtime_source = runtime_source;
+        self
+    }
+
+    /// Whether generated code must be exact when compiled to JavaScript (e.g. for Flutter Web),
+    /// where `int` values are only exact up to 2^53: 64-bit integers are then represented by
+    /// `BigInt` values. The runtimes themselves work on all platforms.
+    pub fn with_web_support(mut self, web_support: bool) -> Self {
+        self.web_support = web_support;
+        self
+    }
+
+    /// Import path of the runtime library `runtime` (e.g. `serde`) in generated libraries.
+    fn runtime_import(&self, runtime: &str) -> String {
+        match &self.runtime_source {
+            RuntimeSource::Vendored => format!("../{0}/{0}.dart", runtime),
+            RuntimeSource::Package { name, .. } => {
+                format!("package:{0}/{1}/{1}.dart", name, runtime)
+            }
+        }
+    }
+
+    /// Import path of the runtime library `runtime` in tests.
+    fn runtime_test_import(&self, runtime: &str) -> String {
+        let package = match &self.runtime_source {
+            RuntimeSource::Vendored => &self.config.module_name,
+            RuntimeSource::Package { name, .. } => name,
+        };
+        format!("package:{0}/{1}/{1}.dart", package, runtime)
     }
 
     /// Output class definitions for `registry`.
-    pub fn output(&self, install_dir: std::path::PathBuf, registry: &Registry) -> Result<()> {
+    pub fn output(
+        &self,
+        install_dir: std::path::PathBuf,
+        registry: &Registry,
+    ) -> crate::Result<()> {
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("Dart", registry)?;
+        common::check_options(
+            "Dart",
+            self.config,
+            &[
+                ConfigOption::JsonIntegersAsStrings,
+                ConfigOption::ImmutableCollections,
+                ConfigOption::CanonicalEquality,
+            ],
+        )?;
         let current_namespace = self
             .config
             .module_name
@@ -57,11 +149,26 @@ impl<'a> CodeGenerator<'a> {
             dir_path = dir_path.join(part);
         }
         std::fs::create_dir_all(&dir_path)?;
+        if self.config.output_layout == OutputLayout::SingleFile {
+            self.write_library(&dir_path, current_namespace, registry)?;
+            return Ok(());
+        }
 
         for (name, format) in registry {
+            common::check_cancellation(self.config)?;
             self.write_container_class(&dir_path, current_namespace.clone(), name, format)?;
         }
         self.write_helper_class(&dir_path, current_namespace.clone(), registry)?;
+        if self.config.serialization {
+            for encoding in &self.config.encodings {
+                self.write_encoding_library(
+                    &dir_path,
+                    current_namespace.clone(),
+                    registry,
+                    *encoding,
+                )?;
+            }
+        }
         self.write_library(&dir_path, current_namespace, registry)?;
         Ok(())
     }
@@ -69,9 +176,47 @@ impl<'a> CodeGenerator<'a> {
     fn write_package(&self, install_dir: &std::path::PathBuf) -> Result<()> {
         let mut file = std::fs::File::create(install_dir.join("pubspec.yaml"))?;
         let mut out = IndentedWriter::new(&mut file, IndentConfig::Space(2));
+        let (mut dependencies, json_dev_dependencies) = if self.json_serializable {
+            (
+                "\n  json_annotation: '3.0.1'".to_string(),
+                "\n  build_runner: '^1.10.0'",
+            )
+        } else {
+            (String::new(), "")
+        };
+        if let RuntimeSource::Package { name, version } = &self.runtime_source {
+            dependencies += &format!("\n  {}: '{}'", name, version);
+        }
+        if self.flutter {
+            writeln!(
+                &mut out,
+                r#"name: {0}
+
+environment:
+  sdk: '>=2.10.0 <3.0.0'
+  flutter: '>=1.20.0'
+
+dependencies:
+  flutter:
+    sdk: flutter
+  optional: '5.0.0'
+  tuple: '1.0.3'
+  json_serializable: '3.4.1'
+  hex: ^0.1.2{1}
+dev_dependencies:
+  flutter_test:
+    sdk: flutter
+  pedantic: '^1.0.0'{2}
+
+flutter:
+"#,
+                self.config.module_name, dependencies, json_dev_dependencies,
+            )?;
+            return Ok(());
+        }
         writeln!(
             &mut out,
-            r#"name: {}
+            r#"name: {0}
             
 environment:
   sdk: '>=2.10.0 <3.0.0'
@@ -80,14 +225,14 @@ dependencies:
   optional: '5.0.0'
   tuple: '1.0.3'  
   json_serializable: '3.4.1'
-  hex: ^0.1.2
+  hex: ^0.1.2{1}
 dev_dependencies:
   mockito: '>=4.0.0 <5.0.0'
   test: '>=0.12.0 <2.0.0'
   pedantic: '^1.0.0'
-  test_coverage: '^0.4.0'
+  test_coverage: '^0.4.0'{2}
             "#,
-            self.config.module_name
+            self.config.module_name, dependencies, json_dev_dependencies,
         )?;
         Ok(())
     }
@@ -96,44 +241,60 @@ dev_dependencies:
         let test_dir_path = install_dir.join("test");
         std::fs::create_dir_all(&test_dir_path)?;
 
-        let mut file = std::fs::File::create(test_dir_path.join("all_test.dart"))?;
+        let mut file =
+            common::create_source_file(self.config, test_dir_path.join("all_test.dart"))?;
         let mut out = IndentedWriter::new(&mut file, IndentConfig::Space(2));
         writeln!(
             &mut out,
             r#"library bcs_test;
 
-import 'package:test/test.dart';
+import 'package:{1}/{1}.dart';
 import 'dart:typed_data';
 import 'dart:convert';
 import 'package:{0}/{0}/{0}.dart';
-import 'package:{0}/serde/serde.dart';"#,
-            self.config.module_name
+import '{2}';"#,
+            self.config.module_name,
+            if self.flutter { "flutter_test" } else { "test" },
+            self.runtime_test_import("serde"),
         )?;
 
         for encoding in &self.config.encodings {
             writeln!(
                 &mut out,
-                "import 'package:{0}/{1}/{1}.dart';",
-                self.config.module_name,
-                encoding.name()
+                "import '{}';",
+                self.runtime_test_import(encoding.name())
             )?;
+            if self.config.output_layout == OutputLayout::Default {
+                writeln!(
+                    &mut out,
+                    "import 'package:{0}/{0}/{0}_{1}.dart';",
+                    self.config.module_name,
+                    encoding.name()
+                )?;
+            }
         }
 
-        writeln!(
-            &mut out,
-            r#"part 'src/serde_test.dart';
-part 'src/dijets_test.dart';"#
-        )?;
+        // The Dijets tests exercise the BCS extensions of the generated classes.
+        let bcs = self.config.encodings.contains(&Encoding::Bcs);
+        writeln!(&mut out, "part 'src/serde_test.dart';")?;
+        if bcs {
+            writeln!(&mut out, "part 'src/dijets_test.dart';")?;
+        }
         for encoding in &self.config.encodings {
             writeln!(&mut out, "part 'src/{}_test.dart';", encoding.name())?;
         }
+        if self.flutter {
+            writeln!(&mut out, "part 'src/flutter_test.dart';")?;
+        }
 
         writeln!(
             &mut out,
             r#"void main() {{
-  group('Serde', runSerdeTests);
-  group('dijets', runDijetsTests);"#,
+  group('Serde', runSerdeTests);"#,
         )?;
+        if bcs {
+            writeln!(&mut out, "  group('dijets', runDijetsTests);")?;
+        }
         for encoding in &self.config.encodings {
             writeln!(
                 &mut out,
@@ -141,6 +302,9 @@ part 'src/dijets_test.dart';"#
                 encoding.name().to_camel_case()
             )?;
         }
+        if self.flutter {
+            writeln!(&mut out, "  group('Flutter', runFlutterTests);")?;
+        }
 
         writeln!(&mut out, "}}")?;
         Ok(())
@@ -151,11 +315,13 @@ part 'src/dijets_test.dart';"#
         install_dir: &std::path::PathBuf,
         current_namespace: Vec<String>,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file =
-            std::fs::File::create(install_dir.join(self.config.module_name.clone() + ".dart"))?;
+    ) -> crate::Result<()> {
+        // With a single file, the library may be split into part files between containers.
+        let single_file = self.config.output_layout == OutputLayout::SingleFile;
+        let max_lines = self.config.max_file_lines.filter(|_| single_file);
+        let mut content = Vec::new();
         let mut emitter = DartEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(2)),
+            out: IndentedWriter::new(&mut content, IndentConfig::Space(2)),
             generator: self,
             current_namespace,
         };
@@ -168,15 +334,26 @@ import 'dart:typed_data';
 import 'package:optional/optional.dart';
 import 'package:tuple/tuple.dart';
 import 'package:hex/hex.dart';
-import '../serde/serde.dart';"#,
+import '{}';"#,
             self.config.module_name,
+            self.runtime_import("serde"),
         )?;
 
-        for encoding in &self.config.encodings {
+        // With a single file, the library includes the encoding extensions.
+        let mut runtimes = BTreeSet::new();
+        if self.config.serialization {
+            if single_file {
+                runtimes.extend(self.config.encodings.iter().map(|encoding| encoding.name()));
+            }
+            if self.config.canonical_equality {
+                runtimes.insert("bcs");
+            }
+        }
+        for runtime in runtimes {
             writeln!(
                 &mut emitter.out,
-                "import '../{0}/{0}.dart';",
-                encoding.name()
+                "import '{}';",
+                self.runtime_import(runtime)
             )?;
         }
 
@@ -186,11 +363,78 @@ import '../serde/serde.dart';"#,
             }
         }
 
-        writeln!(&mut emitter.out, "\npart 'TraitHelpers.dart';")?;
-        for name in registry.keys() {
-            writeln!(&mut emitter.out, "part '{}.dart';", name)?;
+        if self.json_serializable {
+            writeln!(
+                &mut emitter.out,
+                "import 'package:json_annotation/json_annotation.dart';"
+            )?;
+        }
+
+        let containers = common::ordered_containers(self.config, registry)?;
+        if !single_file {
+            writeln!(&mut emitter.out, "\npart 'TraitHelpers.dart';")?;
+            for (name, _) in &containers {
+                writeln!(&mut emitter.out, "part '{}.dart';", name)?;
+            }
+        }
+        if self.json_serializable {
+            writeln!(
+                &mut emitter.out,
+                "part '{}.g.dart';",
+                self.config.module_name
+            )?;
+        }
+
+        if single_file {
+            let boundary = max_lines.is_some();
+            emitter.output_part_boundary(boundary)?;
+            emitter.output_trait_helpers(registry)?;
+            for (name, format) in &containers {
+                common::check_cancellation(self.config)?;
+                emitter.output_part_boundary(boundary)?;
+                emitter.output_container(name, format)?;
+            }
+            if self.config.serialization {
+                for encoding in &self.config.encodings {
+                    for (name, _) in &containers {
+                        emitter.output_part_boundary(boundary)?;
+                        emitter.output_encoding_extension(name, *encoding)?;
+                    }
+                }
+            }
         }
 
+        let module_name = &self.config.module_name;
+        let mut file = common::create_source_file(
+            self.config,
+            install_dir.join(module_name.clone() + ".dart"),
+        )?;
+        let max_lines = match max_lines {
+            None => {
+                file.write_all(&content)?;
+                return Ok(());
+            }
+            Some(max_lines) => max_lines,
+        };
+        // The first chunk holds the library directive and the imports of the library.
+        let content = String::from_utf8(content).expect("generated code should be UTF-8");
+        let mut chunks = common::split_chunks(&content);
+        let header = chunks.remove(0);
+        let parts = common::group_chunks(&chunks, max_lines);
+        write!(file, "{}", header)?;
+        for index in 1..parts.len() {
+            writeln!(file, "part '{}_part{}.dart';", module_name, index)?;
+        }
+        if let Some(part) = parts.first() {
+            write!(file, "{}", part)?;
+        }
+        for (index, part) in parts.iter().enumerate().skip(1) {
+            let mut file = common::create_source_file(
+                self.config,
+                install_dir.join(format!("{}_part{}.dart", module_name, index)),
+            )?;
+            write!(file, "part of {}_types;\n{}", module_name, part)?;
+        }
         Ok(())
     }
 
@@ -201,7 +445,8 @@ import '../serde/serde.dart';"#,
         name: &str,
         format: &ContainerFormat,
     ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join(name.to_string() + ".dart"))?;
+        let mut file =
+            common::create_source_file(self.config, dir_path.join(name.to_string() + ".dart"))?;
         let mut emitter = DartEmitter {
             out: IndentedWriter::new(&mut file, IndentConfig::Space(2)),
             generator: self,
@@ -212,13 +457,56 @@ import '../serde/serde.dart';"#,
         emitter.output_container(name, format)
     }
 
+    /// Write the library `<module>_<encoding>.dart` extending the classes of the types library
+    /// with the methods specific to `encoding`, so that applications only import (and compile)
+    /// the encodings that they use.
+    fn write_encoding_library(
+        &self,
+        dir_path: &std::path::Path,
+        current_namespace: Vec<String>,
+        registry: &Registry,
+        encoding: Encoding,
+    ) -> crate::Result<()> {
+        let mut file = common::create_source_file(
+            self.config,
+            dir_path.join(format!(
+                "{}_{}.dart",
+                self.config.module_name,
+                encoding.name()
+            )),
+        )?;
+        let mut emitter = DartEmitter {
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(2)),
+            generator: self,
+            current_namespace,
+        };
+
+        writeln!(
+            &mut emitter.out,
+            r#"library {0}_{1};
+
+import 'dart:typed_data';
+import '{2}';
+import '{3}';
+import '{0}.dart';"#,
+            self.config.module_name,
+            encoding.name(),
+            self.runtime_import("serde"),
+            self.runtime_import(encoding.name()),
+        )?;
+        for (name, _) in common::ordered_containers(self.config, registry)? {
+            emitter.output_encoding_extension(name, encoding)?;
+        }
+        Ok(())
+    }
+
     fn write_helper_class(
         &self,
         dir_path: &std::path::Path,
         current_namespace: Vec<String>,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join("TraitHelpers.dart"))?;
+    ) -> crate::Result<()> {
+        let mut file = common::create_source_file(self.config, dir_path.join("TraitHelpers.dart"))?;
         let mut emitter = DartEmitter {
             out: IndentedWriter::new(&mut file, IndentConfig::Space(2)),
             generator: self,
@@ -226,7 +514,8 @@ import '../serde/serde.dart';"#,
         };
 
         emitter.output_preamble()?;
-        emitter.output_trait_helpers(registry)
+        emitter.output_trait_helpers(registry)?;
+        Ok(())
     }
 }
 
@@ -244,14 +533,42 @@ where
         Ok(())
     }
 
+    fn output_part_boundary(&mut self, enabled: bool) -> Result<()> {
+        if enabled {
+            writeln!(self.out, "{}", common::PART_BOUNDARY)?;
+        }
+        Ok(())
+    }
+
     fn quote_qualified_name(&self, name: &str) -> String {
         name.to_string()
     }
 
+    /// Class or extension holding the static `deserialize` (or `load`) method of `name`.
+    fn quote_codec_class(&self, name: &str) -> String {
+        if self.generator.codec_extensions {
+            format!("{}Serde", name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    fn quote_variant_class(&self, base: &str, variant: &str) -> String {
+        let default = VariantNaming::Suffix("Item".to_string());
+        self.generator
+            .config
+            .variant_class_name(base, variant, &default)
+    }
+
     fn to_json(&self, format: &Named<Format>) -> String {
         use Format::*;
         match &format.value {
             TypeName(_) => format!("\"{0}\" : {0}.toJson() ", format.name),
+            I64 | U64 if self.converts_json_64_bit_integers() => format!(
+                "\"{0}\" : {1} ",
+                format.name,
+                self.quote_to_json(&format.value, &format.name)
+            ),
             Unit | Bool | I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128 | F32 | F64 => {
                 format!("\"{0}\" : {0} ", format.name)
             }
@@ -267,17 +584,51 @@ where
                     format!("'{0}' : {0}", format.name)
                 }
             }
+            Set(t) => {
+                if let TypeName(_) = t.borrow() {
+                    format!("'{0}' : {0}.map((f) => f.toJson()).toList()", format.name)
+                } else {
+                    format!("'{0}' : {0}.toList()", format.name)
+                }
+            }
             Tuple(_) => format!("\"{0}\" : {0} ", format.name),
             TupleArray {
                 content: _,
                 size: _,
             } => format!("\"{0}\" : {0} ", format.name),
+            Custom { .. } if common::TimeFormat::of(&format.value).is_some() => format!(
+                "\"{0}\" : {1} ",
+                format.name,
+                self.quote_to_json(&format.value, &format.name)
+            ),
+            Custom { layout, .. } => {
+                self.to_json(&Named::new(format.name.clone(), layout.as_ref().clone()))
+            }
+            Decimal { mantissa, .. } => {
+                self.to_json(&Named::new(format.name.clone(), mantissa.as_ref().clone()))
+            }
+
+            Skipped(_) => unreachable!("formats are checked by common::check_formats"),
         }
     }
 
     fn from_json(&self, format: &Named<Format>) -> String {
         use Format::*;
         match &format.value {
+            I64 | U64 if self.converts_json_64_bit_integers() => format!(
+                "{0} = {1}",
+                format.name,
+                self.quote_from_json(&format.value, &format!("json['{}']", format.name))
+            ),
+            I8 | I16 | I32 | I64 | U8 | U16 | U32 | U64 if self.checks_json_integers() => format!(
+                "{0} = {1}",
+                format.name,
+                self.quote_integer_from_json(
+                    &format.value,
+                    &format!("json['{}']", format.name),
+                    Some(&format.name)
+                )
+            ),
             Unit | Bool | I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128 | F32 | F64
             | Char | Str => format!("{0} = json['{0}']", format.name),
             Bytes | Variable(_) | Map { key: _, value: _ } => {
@@ -295,15 +646,282 @@ where
                     format!("{0} = json['{0}']", format.name,)
                 }
             }
+            Set(t) => {
+                if let TypeName(name) = t.borrow() {
+                    format!(
+                        "{0} = Set<{1}>.from(json['{0}'].map((f) => {1}.fromJson(f)))",
+                        format.name, name
+                    )
+                } else {
+                    format!(
+                        "{0} = Set<{1}>.from(json['{0}'])",
+                        format.name,
+                        self.quote_type(t)
+                    )
+                }
+            }
             Tuple(_) => format!("{0} = {0}", format.name),
             TupleArray { content, size: _ } => format!(
                 "{0} = List<{1}>.from(json['{0}'])",
                 format.name,
                 self.quote_type(content)
             ),
+            Custom { .. } if common::TimeFormat::of(&format.value).is_some() => format!(
+                "{0} = {1}",
+                format.name,
+                self.quote_from_json(&format.value, &format!("json['{}']", format.name))
+            ),
+            Custom { layout, .. } => {
+                self.from_json(&Named::new(format.name.clone(), layout.as_ref().clone()))
+            }
+            Decimal { mantissa, .. } => {
+                self.from_json(&Named::new(format.name.clone(), mantissa.as_ref().clone()))
+            }
+
+            Skipped(_) => unreachable!("formats are checked by common::check_formats"),
+        }
+    }
+
+    /// Whether a field needs explicit conversions in `json_serializable` mode.
+    fn needs_json_key(&self, format: &Format) -> bool {
+        match format {
+            // `json_serializable` converts `Duration` and `DateTime` natively.
+            Format::Custom { .. } if common::TimeFormat::of(format).is_some() => false,
+            Format::Custom { layout, .. } => self.needs_json_key(layout),
+            Format::Decimal { mantissa, .. } => self.needs_json_key(mantissa),
+            Format::Unit => true,
+            Format::I64 | Format::U64 => self.converts_json_64_bit_integers(),
+            _ => Self::needs_helper(format),
+        }
+    }
+
+    fn quote_json_key(&self, format: &Format) -> String {
+        let name = match format {
+            Format::Custom { layout, .. } => return self.quote_json_key(layout),
+            Format::Decimal { mantissa, .. } => return self.quote_json_key(mantissa),
+            Format::Unit => "unit".to_string(),
+            _ => common::helper_name(self.generator.config, format),
+        };
+        format!(
+            "@JsonKey(fromJson: TraitHelpers.from_json_{0}, toJson: TraitHelpers.to_json_{0})",
+            name
+        )
+    }
+
+    /// Whether JSON conversions of 64-bit integers go through the helpers `from_json_i64`,
+    /// `to_json_i64`, etc.
+    fn converts_json_64_bit_integers(&self) -> bool {
+        self.generator.config.json_integers_as_strings || self.generator.web_support
+    }
+
+    /// Whether JSON conversions check integers, following `JsonIntegerPolicy`.
+    fn checks_json_integers(&self) -> bool {
+        self.generator.config.json_integer_policy != JsonIntegerPolicy::Unchecked
+    }
+
+    /// Check the JSON integer `value` of the given format, e.g. the field `name`.
+    fn quote_integer_from_json(&self, format: &Format, value: &str, name: Option<&str>) -> String {
+        use Format::*;
+        let (min, max) = match format {
+            I8 => (i8::MIN.to_string(), i8::MAX.to_string()),
+            I16 => (i16::MIN.to_string(), i16::MAX.to_string()),
+            I32 => (i32::MIN.to_string(), i32::MAX.to_string()),
+            U8 => ("0".to_string(), u8::MAX.to_string()),
+            U16 => ("0".to_string(), u16::MAX.to_string()),
+            U32 => ("0".to_string(), u32::MAX.to_string()),
+            // 64-bit integers are only checked to be integers.
+            _ => ("null".to_string(), "null".to_string()),
+        };
+        match name {
+            Some(name) => format!(
+                "TraitHelpers.from_json_integer({}, {}, {}, '{}')",
+                value, min, max, name
+            ),
+            None => format!(
+                "TraitHelpers.from_json_integer({}, {}, {})",
+                value, min, max
+            ),
+        }
+    }
+
+    fn quote_from_json(&self, format: &Format, value: &str) -> String {
+        use Format::*;
+        match format {
+            TypeName(name) => format!("{}.fromJson({})", self.quote_qualified_name(name), value),
+            Unit => format!("TraitHelpers.from_json_unit({})", value),
+            F32 | F64 => format!("({} as num).toDouble()", value),
+            I128 | U128 => format!("Int128.fromJson({})", value),
+            I64 | U64 if self.converts_json_64_bit_integers() => format!(
+                "TraitHelpers.from_json_{}({})",
+                common::helper_name(self.generator.config, format),
+                value
+            ),
+            I8 | I16 | I32 | I64 | U8 | U16 | U32 | U64 if self.checks_json_integers() => {
+                self.quote_integer_from_json(format, value, None)
+            }
+            Bytes => format!("Bytes.fromJson({})", value),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => format!(
+                    "Duration(seconds: {0}['secs'] as int, microseconds: ({0}['nanos'] as int) ~/ 1000)",
+                    value
+                ),
+                Some(common::TimeFormat::SystemTime) => format!(
+                    "DateTime.fromMicrosecondsSinceEpoch(({0}['secs_since_epoch'] as int) * 1000000 + ({0}['nanos_since_epoch'] as int) ~/ 1000, isUtc: true)",
+                    value
+                ),
+                None => self.quote_from_json(layout, value),
+            },
+            Decimal { mantissa, .. } => self.quote_from_json(mantissa, value),
+            Option(_) | Seq(_) | Set(_) | Map { .. } | Tuple(_) | TupleArray { .. } => format!(
+                "TraitHelpers.from_json_{}({})",
+                common::helper_name(self.generator.config, format),
+                value
+            ),
+            _ => format!("{} as {}", value, self.quote_type(format)),
+        }
+    }
+
+    fn quote_to_json(&self, format: &Format, value: &str) -> String {
+        use Format::*;
+        match format {
+            TypeName(_) | I128 | U128 | Bytes => format!("{}.toJson()", value),
+            Unit => "null".to_string(),
+            I64 | U64 if self.converts_json_64_bit_integers() => format!(
+                "TraitHelpers.to_json_{}({})",
+                common::helper_name(self.generator.config, format),
+                value
+            ),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => format!(
+                    "{{'secs': {0}.inMicroseconds ~/ 1000000, 'nanos': {0}.inMicroseconds.remainder(1000000) * 1000}}",
+                    value
+                ),
+                Some(common::TimeFormat::SystemTime) => format!(
+                    "{{'secs_since_epoch': {0}.microsecondsSinceEpoch ~/ 1000000, 'nanos_since_epoch': {0}.microsecondsSinceEpoch.remainder(1000000) * 1000}}",
+                    value
+                ),
+                None => self.quote_to_json(layout, value),
+            },
+            Decimal { mantissa, .. } => self.quote_to_json(mantissa, value),
+            Option(_) | Seq(_) | Set(_) | Map { .. } | Tuple(_) | TupleArray { .. } => format!(
+                "TraitHelpers.to_json_{}({})",
+                common::helper_name(self.generator.config, format),
+                value
+            ),
+            _ => value.to_string(),
+        }
+    }
+
+    /// JSON objects only have string keys.
+    fn quote_key_from_json(&self, format: &Format, value: &str) -> String {
+        use Format::*;
+        match format {
+            Str => value.to_string(),
+            Bool => format!("{} == 'true'", value),
+            I64 | U64 if self.generator.web_support => format!("BigInt.parse({})", value),
+            I8 | I16 | I32 | I64 | U8 | U16 | U32 | U64 | Char => format!("int.parse({})", value),
+            Custom { layout, .. } => self.quote_key_from_json(layout, value),
+            Decimal { mantissa, .. } => self.quote_key_from_json(mantissa, value),
+            _ => self.quote_from_json(format, value),
+        }
+    }
+
+    fn quote_key_to_json(&self, format: &Format, value: &str) -> String {
+        use Format::*;
+        match format {
+            Str => value.to_string(),
+            Bool | I8 | I16 | I32 | I64 | U8 | U16 | U32 | U64 | Char => {
+                format!("{}.toString()", value)
+            }
+            Custom { layout, .. } => self.quote_key_to_json(layout, value),
+            Decimal { mantissa, .. } => self.quote_key_to_json(mantissa, value),
+            _ => self.quote_to_json(format, value),
         }
     }
 
+    fn output_json_helpers(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+        let (from_json, to_json) = match format0 {
+            Option(format) => (
+                format!(
+                    "json == null ? Optional.empty() : Optional.of({})",
+                    self.quote_from_json(format, "json")
+                ),
+                format!(
+                    "value.isPresent ? {} : null",
+                    self.quote_to_json(format, "value.value")
+                ),
+            ),
+            Set(format) => (
+                format!(
+                    "Set<{}>.from((json as List).map((e) => {}))",
+                    self.quote_type(format),
+                    self.quote_from_json(format, "e")
+                ),
+                format!(
+                    "value.map((e) => {}).toList()",
+                    self.quote_to_json(format, "e")
+                ),
+            ),
+            Seq(format) | TupleArray {
+                content: format, ..
+            } => (
+                format!(
+                    "List<{}>.from((json as List).map((e) => {}))",
+                    self.quote_type(format),
+                    self.quote_from_json(format, "e")
+                ),
+                format!(
+                    "value.map((e) => {}).toList()",
+                    self.quote_to_json(format, "e")
+                ),
+            ),
+            Map { key, value } => (
+                format!(
+                    "{}.fromEntries((json as Map<String, dynamic>).entries.map((e) => MapEntry({}, {})))",
+                    self.quote_type(format0),
+                    self.quote_key_from_json(key, "e.key"),
+                    self.quote_from_json(value, "e.value")
+                ),
+                format!(
+                    "value.map((k, v) => MapEntry({}, {}))",
+                    self.quote_key_to_json(key, "k"),
+                    self.quote_to_json(value, "v")
+                ),
+            ),
+            Tuple(formats) => (
+                format!(
+                    "{}({})",
+                    self.quote_type(format0),
+                    formats
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| self.quote_from_json(f, &format!("json[{}]", i)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                format!(
+                    "[{}]",
+                    formats
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| self.quote_to_json(f, &format!("value.item{}", i + 1)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+            _ => panic!("unexpected case"),
+        };
+        writeln!(
+            self.out,
+            "static {0} from_json_{1}(dynamic json) => {2};\n\nstatic dynamic to_json_{1}({0} value) => {3};\n",
+            self.quote_type(format0),
+            name,
+            from_json,
+            to_json
+        )
+    }
+
     fn quote_type(&self, format: &Format) -> String {
         use Format::*;
         match format {
@@ -313,11 +931,13 @@ where
             I8 => "int".into(),
             I16 => "int".into(),
             I32 => "int".into(),
+            I64 if self.generator.web_support => "BigInt".into(),
             I64 => "int".into(),
             I128 => "Int128".into(),
             U8 => "int".into(),
             U16 => "int".into(),
             U32 => "int".into(),
+            U64 if self.generator.web_support => "BigInt".into(),
             U64 => "int".into(),
             U128 => "Int128".into(),
             F32 => "float".into(),
@@ -328,12 +948,37 @@ where
 
             Option(format) => format!("Optional<{}>", self.quote_type(format)),
             Seq(format) => format!("List<{}>", self.quote_type(format)),
+            Set(format) => format!("Set<{}>", self.quote_type(format)),
             Map { key, value } => {
                 format!("Map<{}, {}>", self.quote_type(key), self.quote_type(value))
             }
             Tuple(formats) => format!("Tuple{}<{}>", formats.len(), self.quote_types(formats)),
             TupleArray { content, size: _ } => format!("List<{}>", self.quote_type(content)),
-            Variable(_) => panic!("unexpected value"),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => "Duration".into(),
+                Some(common::TimeFormat::SystemTime) => "DateTime".into(),
+                None => self.quote_type(layout),
+            },
+            Decimal { mantissa, .. } => self.quote_type(mantissa),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
+        }
+    }
+
+    /// Wrap `value` into an unmodifiable collection if `format` is a sequence or a map and
+    /// immutable collections were requested.
+    fn quote_immutable(&self, value: &str, format: &Format) -> String {
+        use Format::*;
+        if !self.generator.config.immutable_collections {
+            return value.to_string();
+        }
+        match format {
+            Seq(_) | Set(_) | Map { .. } | TupleArray { .. } => {
+                format!("{}.unmodifiable({})", self.quote_type(format), value)
+            }
+            Custom { layout, .. } => self.quote_immutable(value, layout),
+            _ => value.to_string(),
         }
     }
 
@@ -354,11 +999,17 @@ where
             I8 => format!("serializer.serialize_i8({});", value),
             I16 => format!("serializer.serialize_i16({});", value),
             I32 => format!("serializer.serialize_i32({});", value),
+            I64 if self.generator.web_support => {
+                format!("serializer.serialize_i64_bigint({});", value)
+            }
             I64 => format!("serializer.serialize_i64({});", value),
             I128 => format!("serializer.serialize_i128({});", value),
             U8 => format!("serializer.serialize_u8({});", value),
             U16 => format!("serializer.serialize_u16({});", value),
             U32 => format!("serializer.serialize_u32({});", value),
+            U64 if self.generator.web_support => {
+                format!("serializer.serialize_u64_bigint({});", value)
+            }
             U64 => format!("serializer.serialize_u64({});", value),
             U128 => format!("serializer.serialize_u128({});", value),
             F32 => format!("serializer.serialize_f32({});", value),
@@ -366,10 +1017,22 @@ where
             Char => format!("serializer.serialize_char({});", value),
             Str => format!("serializer.serialize_str({});", value),
             Bytes => format!("serializer.serialize_bytes({});", value),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => format!(
+                    "serializer.serialize_u64({0}.inMicroseconds ~/ 1000000); serializer.serialize_u32({0}.inMicroseconds.remainder(1000000) * 1000);",
+                    value
+                ),
+                Some(common::TimeFormat::SystemTime) => format!(
+                    "serializer.serialize_u64({0}.microsecondsSinceEpoch ~/ 1000000); serializer.serialize_u32({0}.microsecondsSinceEpoch.remainder(1000000) * 1000);",
+                    value
+                ),
+                None => self.quote_serialize_value(value, layout),
+            },
+            Decimal { mantissa, .. } => self.quote_serialize_value(value, mantissa),
             _ => format!(
                 "{}.serialize_{}({}, serializer);",
                 self.quote_qualified_name("TraitHelpers"),
-                common::mangle_type(format),
+                common::helper_name(self.generator.config, format),
                 value
             ),
         }
@@ -380,18 +1043,20 @@ where
         match format {
             TypeName(name) => format!(
                 "{}.deserialize(deserializer)",
-                self.quote_qualified_name(name)
+                self.quote_codec_class(&self.quote_qualified_name(name))
             ),
             Unit => "deserializer.deserialize_unit()".to_string(),
             Bool => "deserializer.deserialize_bool()".to_string(),
             I8 => "deserializer.deserialize_i8()".to_string(),
             I16 => "deserializer.deserialize_i16()".to_string(),
             I32 => "deserializer.deserialize_i32()".to_string(),
+            I64 if self.generator.web_support => "deserializer.deserialize_i64_bigint()".to_string(),
             I64 => "deserializer.deserialize_i64()".to_string(),
             I128 => "deserializer.deserialize_i128()".to_string(),
             U8 => "deserializer.deserialize_u8()".to_string(),
             U16 => "deserializer.deserialize_u16()".to_string(),
             U32 => "deserializer.deserialize_u32()".to_string(),
+            U64 if self.generator.web_support => "deserializer.deserialize_u64_bigint()".to_string(),
             U64 => "deserializer.deserialize_u64()".to_string(),
             U128 => "deserializer.deserialize_u128()".to_string(),
             F32 => "deserializer.deserialize_f32()".to_string(),
@@ -399,14 +1064,39 @@ where
             Char => "deserializer.deserialize_char()".to_string(),
             Str => "deserializer.deserialize_str()".to_string(),
             Bytes => "deserializer.deserialize_bytes()".to_string(),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => "Duration(seconds: deserializer.deserialize_u64(), microseconds: deserializer.deserialize_u32() ~/ 1000)".to_string(),
+                Some(common::TimeFormat::SystemTime) => "DateTime.fromMicrosecondsSinceEpoch(deserializer.deserialize_u64() * 1000000 + deserializer.deserialize_u32() ~/ 1000, isUtc: true)".to_string(),
+                None => self.quote_deserialize(layout),
+            },
+            Decimal { mantissa, .. } => self.quote_deserialize(mantissa),
             _ => format!(
                 "{}.deserialize_{}(deserializer)",
                 self.quote_qualified_name("TraitHelpers"),
-                common::mangle_type(format),
+                common::helper_name(self.generator.config, format),
             ),
         }
     }
 
+    fn output_comment(&mut self, name: &str) -> Result<()> {
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        if let Some(doc) = self.generator.config.comment(&path) {
+            let text = textwrap::indent(&doc, "/// ").replace("\n\n", "\n///\n");
+            write!(self.out, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    fn output_deprecation(&mut self, name: &str) -> Result<()> {
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        if let Some(message) = self.generator.config.deprecations.get(&path) {
+            writeln!(self.out, "@Deprecated({:?})", message.trim())?;
+        }
+        Ok(())
+    }
+
     fn enter_class(&mut self, name: &str) {
         self.out.indent();
         self.current_namespace.push(name.to_string());
@@ -417,33 +1107,99 @@ where
         self.current_namespace.pop();
     }
 
-    fn output_trait_helpers(&mut self, registry: &Registry) -> Result<()> {
-        let mut subtypes = BTreeMap::new();
-        for format in registry.values() {
-            format
-                .visit(&mut |f| {
-                    if Self::needs_helper(f) {
-                        subtypes.insert(common::mangle_type(f), f.clone());
-                    }
-                    Ok(())
-                })
-                .unwrap();
-        }
+    fn output_trait_helpers(&mut self, registry: &Registry) -> crate::Result<()> {
+        let subtypes =
+            common::helper_formats(self.generator.config, registry, false, Self::needs_helper)?;
         writeln!(self.out, "class TraitHelpers {{")?;
         self.enter_class("TraitHelpers");
         for (mangled_name, subtype) in &subtypes {
             self.output_serialization_helper(mangled_name, subtype)?;
             self.output_deserialization_helper(mangled_name, subtype)?;
         }
-        self.leave_class();
-        writeln!(self.out, "}}\n")
-    }
-
+        if self.generator.json_serializable {
+            writeln!(
+                self.out,
+                "static Unit from_json_unit(dynamic json) => Unit();\n\nstatic dynamic to_json_unit(Unit value) => null;\n"
+            )?;
+            for (mangled_name, subtype) in &subtypes {
+                self.output_json_helpers(mangled_name, subtype)?;
+            }
+        }
+        if self.generator.web_support {
+            // Integers beyond the range of `int` are written as strings.
+            let to_json = if self.generator.config.json_integers_as_strings {
+                "value.toString()"
+            } else {
+                "value.isValidInt ? value.toInt() : value.toString()"
+            };
+            writeln!(
+                self.out,
+                r#"static BigInt from_json_i64(dynamic json) => json is String ? BigInt.parse(json) : BigInt.from(json as int);
+
+static dynamic to_json_i64(BigInt value) => {0};
+
+static BigInt from_json_u64(dynamic json) => json is String ? BigInt.parse(json) : BigInt.from(json as int);
+
+static dynamic to_json_u64(BigInt value) => {0};
+"#,
+                to_json
+            )?;
+        } else if self.generator.config.json_integers_as_strings {
+            writeln!(
+                self.out,
+                r#"static int from_json_i64(dynamic json) => int.parse(json as String);
+
+static dynamic to_json_i64(int value) => value.toString();
+
+static int from_json_u64(dynamic json) => BigInt.parse(json as String).toSigned(64).toInt();
+
+static dynamic to_json_u64(int value) => BigInt.from(value).toUnsigned(64).toString();
+"#
+            )?;
+        }
+        if self.checks_json_integers() {
+            self.output_json_integer_helper()?;
+        }
+        self.leave_class();
+        writeln!(self.out, "}}\n")?;
+        Ok(())
+    }
+
+    /// Helper checking JSON integers against optional bounds, following `JsonIntegerPolicy`.
+    fn output_json_integer_helper(&mut self) -> Result<()> {
+        writeln!(
+            self.out,
+            "static int from_json_integer(dynamic json, int min, int max, [String name]) {{"
+        )?;
+        self.out.indent();
+        writeln!(self.out, "var value = json is int ? json : null;")?;
+        if self.generator.config.json_integer_policy == JsonIntegerPolicy::Lenient {
+            writeln!(
+                self.out,
+                r#"if (json is double && json.isFinite && json == json.truncateToDouble()) {{
+  value = json.toInt();
+}} else if (json is String) {{
+  value = int.tryParse(json);
+}}"#
+            )?;
+        }
+        writeln!(
+            self.out,
+            r#"if (value == null || (min != null && value < min) || (max != null && value > max)) {{
+  final range = min != null ? ' between $min and $max' : '';
+  throw FormatException('${{name ?? 'value'}}: expected an integer$range', json);
+}}
+return value;"#
+        )?;
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
     fn needs_helper(format: &Format) -> bool {
         use Format::*;
         matches!(
             format,
-            Option(_) | Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. }
+            Option(_) | Seq(_) | Set(_) | Map { .. } | Tuple(_) | TupleArray { .. }
         )
     }
 
@@ -487,6 +1243,24 @@ for ({} item in value) {{
                 )?;
             }
 
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+serializer.serialize_len(value.length);
+Int32List offsets = new Int32List(value.length);
+int count = 0;
+for ({} item in value) {{
+    offsets[count++] = serializer.get_buffer_offset();
+    {}
+}}
+serializer.sort_map_entries(offsets);
+"#,
+                    self.quote_type(format),
+                    self.quote_serialize_value("item", format)
+                )?;
+            }
+
             Map { key, value } => {
                 write!(
                     self.out,
@@ -563,6 +1337,37 @@ if (!tag) {{
                 )?;
             }
 
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+int length = deserializer.deserialize_len();
+Set<{0}> obj = <{0}>{{}};
+int previous_item_start = 0;
+int previous_item_end = 0;
+for (int i = 0; i < length; i++) {{
+    int item_start = deserializer.get_buffer_offset();
+    {0} item = {1};
+    int item_end = deserializer.get_buffer_offset();
+    if (i > 0) {{
+        deserializer.check_that_key_slices_are_increasing(
+            new Slice(previous_item_start, previous_item_end),
+            new Slice(item_start, item_end));
+    }}
+    previous_item_start = item_start;
+    previous_item_end = item_end;
+    if (!obj.add(item)) {{
+        throw new Exception("Duplicate elements in set");
+    }}
+}}
+return {2};
+"#,
+                    self.quote_type(format),
+                    self.quote_deserialize(format),
+                    self.quote_immutable("obj", format0),
+                )?;
+            }
+
             Seq(format) => {
                 write!(
                     self.out,
@@ -572,10 +1377,11 @@ List<{0}> obj = new List<{0}>(length);
 for (int i = 0; i < length; i++) {{
     obj[i]={1};
 }}
-return obj;
+return {2};
 "#,
                     self.quote_type(format),
-                    self.quote_deserialize(format)
+                    self.quote_deserialize(format),
+                    self.quote_immutable("obj", format0),
                 )?;
             }
 
@@ -601,12 +1407,13 @@ for (int i = 0; i < length; i++) {{
     {1} value = {3};
     obj.put(key, value);
 }}
-return obj;
+return {4};
 "#,
                     self.quote_type(key),
                     self.quote_type(value),
                     self.quote_deserialize(key),
                     self.quote_deserialize(value),
+                    self.quote_immutable("obj", format0),
                 )?;
             }
 
@@ -634,11 +1441,12 @@ List<{0}> obj = new List<{0}>.filled({1}, 0);
 for (int i = 0; i < {1}; i++) {{
     obj[i] = {2};
 }}
-return obj;
+return {3};
 "#,
                     self.quote_type(content),
                     size,
-                    self.quote_deserialize(content)
+                    self.quote_deserialize(content),
+                    self.quote_immutable("obj", format0),
                 )?;
             }
 
@@ -655,32 +1463,62 @@ return obj;
             UnitStruct => Vec::new(),
             NewTypeStruct(format) => {
                 redefine = true;
-                vec![Named {
-                    name: "value".to_string(),
-                    value: format.as_ref().clone(),
-                }]
+                vec![Named::new("value", format.as_ref().clone())]
             }
             TupleStruct(formats) => formats
                 .iter()
                 .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
+                .map(|(i, f)| Named::new(format!("field{}", i), f.clone()))
                 .collect::<Vec<_>>(),
             Struct(fields) => fields.clone(),
             Enum(variants) => {
-                self.output_enum_container(name, variants)?;
-                return Ok(());
+                return self.output_enum_container(name, None, variants);
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                return self.output_enum_container(name, Some(tag), variants);
             }
         };
         self.output_struct_or_variant_container(None, None, name, &fields, redefine, name)
     }
 
+    /// Extension of the class `name` with the methods specific to `encoding`. Extension
+    /// methods also apply to the variants of enums.
+    fn output_encoding_extension(&mut self, name: &str, encoding: Encoding) -> Result<()> {
+        writeln!(
+            self.out,
+            "\nextension {}{} on {} {{",
+            name,
+            encoding.name().to_camel_case(),
+            name
+        )?;
+        self.out.indent();
+        self.output_class_serialize_for_encoding(encoding)?;
+        self.output_class_deserialize_for_encoding(name, encoding)?;
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        self.output_top_level_codec_functions(name, encoding)
+    }
+
+    /// Top-level functions (unlike static methods in older SDKs) can be passed to
+    /// Flutter's `compute` in order to (de)serialize large payloads in a separate isolate.
+    fn output_top_level_codec_functions(&mut self, name: &str, encoding: Encoding) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+{0} {1}{2}Decode(Uint8List input) => {0}{2}.{3}Deserialize(input);
+
+Uint8List {1}{2}Encode({0} value) => value.{3}Serialize();"#,
+            name,
+            name.to_mixed_case(),
+            encoding.name().to_camel_case(),
+            encoding.name(),
+        )
+    }
+
     fn output_struct_or_variant_container(
         &mut self,
         variant_base: Option<&str>,
-        variant_index: Option<u32>,
+        variant_tag: Option<VariantTag>,
         name: &str,
         fields: &[Named<Format>],
         redefine: bool,
@@ -688,15 +1526,27 @@ return obj;
     ) -> Result<()> {
         // Beginning of class
         writeln!(self.out)?;
+        self.output_comment(actual_name)?;
+        self.output_deprecation(actual_name)?;
+        let json_serializable = self.generator.json_serializable && !redefine;
+        if json_serializable {
+            writeln!(self.out, "@JsonSerializable(explicitToJson: true)")?;
+        }
         if let Some(base) = variant_base {
             writeln!(self.out, "class {} extends {} {{", name, base)?;
         } else {
             writeln!(self.out, "class {} {{", name)?;
         }
-        self.enter_class(name);
+        // Variant classes are top-level in Dart but their fields are documented under the
+        // name of the variant.
+        self.enter_class(actual_name);
         // Fields
         for field in fields {
-            //self.output_comment(&field.name)?;
+            self.output_comment(&field.name)?;
+            self.output_deprecation(&field.name)?;
+            if json_serializable && self.needs_json_key(&field.value) {
+                writeln!(self.out, "{}", self.quote_json_key(&field.value))?;
+            }
             writeln!(
                 self.out,
                 "{} {};",
@@ -723,79 +1573,103 @@ return obj;
             writeln!(self.out, "assert ({} != null);", &field.name)?;
         }
         for field in fields {
-            writeln!(self.out, "this.{} = {};", &field.name, &field.name)?;
+            writeln!(
+                self.out,
+                "this.{} = {};",
+                &field.name,
+                self.quote_immutable(&field.name, &field.value)
+            )?;
         }
         self.out.unindent();
         writeln!(self.out, "}}")?;
 
-        // Serialize
-        if self.generator.config.serialization {
-            writeln!(self.out, "\nvoid serialize(BinarySerializer serializer){{",)?;
-            self.out.indent();
-            if let Some(index) = variant_index {
-                writeln!(self.out, "serializer.serialize_variant_index({});", index)?;
-            }
-            for field in fields {
-                writeln!(
-                    self.out,
-                    "{}",
-                    self.quote_serialize_value(&field.name, &field.value)
-                )?;
-            }
-            self.out.unindent();
-            writeln!(self.out, "}}")?;
+        if self.generator.config.field_descriptors {
+            self.output_field_descriptors(fields)?;
+        }
 
-            if variant_index.is_none() {
-                for encoding in &self.generator.config.encodings {
-                    self.output_class_serialize_for_encoding(*encoding)?;
-                }
-            }
+        if self.generator.config.serialization && !self.generator.codec_extensions {
+            self.output_serialization_methods(name, variant_tag, fields)?;
         }
-        // Deserialize (struct) or Load (variant)
-        if self.generator.config.serialization {
-            if variant_index.is_none() {
-                writeln!(
-                    self.out,
-                    "\nstatic {} deserialize(BinaryDeserializer deserializer){{",
-                    name,
-                )?;
+        // Equality and hashing
+        let fields_num = fields.len();
+        if self.generator.config.serialization && self.generator.config.canonical_equality {
+            self.output_canonical_equality(name)?;
+        } else {
+            self.output_fieldwise_equality(name, fields)?;
+        }
+
+        if json_serializable {
+            self.output_json_serializable_methods(name, variant_tag, actual_name)?;
+        } else {
+            if fields_num > 0 {
+                if variant_tag.is_none() {
+                    writeln!(self.out, "\n{0}.fromJson(dynamic json) :", name)?;
+                } else {
+                    //enum
+                    writeln!(self.out, "\n{}.loadJson(dynamic json) :", name,)?;
+                }
+                self.out.indent();
+                if redefine {
+                    writeln!(self.out, "{} = json ;", &fields[0].name,)?;
+                } else {
+                    for (index, field) in fields.iter().enumerate() {
+                        if index == fields_num - 1 {
+                            writeln!(self.out, "{} ;", self.from_json(field))?;
+                        } else {
+                            writeln!(self.out, "{} ,", self.from_json(field))?;
+                        }
+                    }
+                }
+                self.out.unindent();
+            } else if variant_tag.is_none() {
+                writeln!(self.out, "\n{0}.fromJson(dynamic json);", name)?;
             } else {
-                writeln!(
-                    self.out,
-                    "\nstatic {} load(BinaryDeserializer deserializer){{",
-                    name,
-                )?;
+                writeln!(self.out, "\n{0}.loadJson(dynamic json);", name)?; //enum
             }
-            self.out.indent();
 
-            for field in fields {
-                writeln!(
-                    self.out,
-                    "var {} = {};",
-                    field.name,
-                    self.quote_deserialize(&field.value)
-                )?;
-            }
-            writeln!(
-                self.out,
-                "return new {}({});",
-                name,
-                fields
-                    .iter()
-                    .map(|f| f.name.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )?;
+            if !redefine {
+                writeln!(self.out, "\ndynamic toJson() => {{")?;
 
-            self.out.unindent();
-            writeln!(self.out, "}}")?;
+                self.out.indent();
 
-            if variant_index.is_none() {
-                for encoding in &self.generator.config.encodings {
-                    self.output_class_deserialize_for_encoding(name, *encoding)?;
+                for field in fields.iter() {
+                    writeln!(self.out, "{},", self.to_json(field))?;
                 }
+                match variant_tag {
+                    Some(VariantTag::Index(index)) => {
+                        writeln!(self.out, "\"type\" : {},", index)?;
+                        writeln!(self.out, "\"type_name\" : \"{}\"", actual_name)?;
+                    }
+                    Some(VariantTag::Name { tag, name }) => {
+                        writeln!(self.out, "{:?} : {:?}", tag, name)?;
+                    }
+                    None => (),
+                }
+                self.out.unindent();
+                writeln!(self.out, "}};")?;
+            } else if fields_num > 0 {
+                writeln!(self.out, "\ndynamic toJson() => {};", &fields[0].name)?;
             }
         }
+
+        self.output_flags(fields)?;
+
+        self.out.unindent();
+        // End of class
+        self.leave_class();
+        writeln!(self.out, "}}")?;
+
+        if self.generator.config.serialization && self.generator.codec_extensions {
+            writeln!(self.out, "\nextension {0}Serde on {0} {{", name)?;
+            self.out.indent();
+            self.output_serialization_methods(name, variant_tag, fields)?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+        }
+        Ok(())
+    }
+
+    fn output_fieldwise_equality(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
         // Equality
         write!(self.out, "\n@override")?;
         write!(self.out, "\nbool operator ==(covariant {0} other) {{", name)?;
@@ -806,13 +1680,20 @@ return obj;
 if (other == null) return false;"#,
         )?;
         let fields_num = fields.len();
-
         if fields_num > 0 {
             write!(self.out, "\nif (")?;
 
             for (index, field) in fields.iter().enumerate() {
-                let stmt = match &field.value {
-                    Format::Seq(_) => format!(" isListsEqual(this.{0} , other.{0}) ", &field.name),
+                let value = match &field.value {
+                    Format::Custom { layout, .. } => layout.as_ref(),
+                    Format::Decimal { mantissa, .. } => mantissa.as_ref(),
+                    value => value,
+                };
+                let stmt = match value {
+                    Format::Seq(_) => {
+                        format!(" isListsEqual(this.{0} , other.{0}) ", &field.name)
+                    }
+                    Format::Set(_) => format!(" isSetsEqual(this.{0} , other.{0}) ", &field.name),
                     Format::TupleArray {
                         content: _,
                         size: _,
@@ -849,55 +1730,184 @@ if (other == null) return false;"#,
         writeln!(self.out, "return value;")?;
         self.out.unindent();
         writeln!(self.out, "}}")?;
+        Ok(())
+    }
 
-        if fields_num > 0 {
-            if variant_index.is_none() {
-                writeln!(self.out, "\n{0}.fromJson(dynamic json) :", name)?;
+    /// Compare and hash values through their BCS serialization, which is cached on first use.
+    fn output_canonical_equality(&mut self, name: &str) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+Uint8List _canonicalBytes;
+
+Uint8List get canonicalBytes {{
+  if (_canonicalBytes == null) {{
+    var serializer = new BcsSerializer();
+    {1}.serialize(serializer);
+    _canonicalBytes = serializer.get_bytes();
+  }}
+  return _canonicalBytes;
+}}
+
+@override
+bool operator ==(covariant {0} other) {{
+  if (other == null) return false;
+  return isUint8ListsEqual(canonicalBytes, other.canonicalBytes);
+}}
+
+@override
+int get hashCode => bytesHashCode(canonicalBytes);"#,
+            name,
+            if self.generator.codec_extensions {
+                format!("{}(this)", self.quote_codec_class(name))
             } else {
-                //enum
-                writeln!(self.out, "\n{}.loadJson(dynamic json) :", name,)?;
+                "this".to_string()
+            },
+        )
+    }
+
+    /// Methods (de)serializing the fields of a struct or a variant, either in the class
+    /// itself or in its codec extension.
+    fn output_serialization_methods(
+        &mut self,
+        name: &str,
+        variant_tag: Option<VariantTag>,
+        fields: &[Named<Format>],
+    ) -> Result<()> {
+        // Serialize
+        writeln!(self.out, "\nvoid serialize(BinarySerializer serializer){{",)?;
+        self.out.indent();
+        match variant_tag {
+            Some(VariantTag::Index(index)) => {
+                writeln!(self.out, "serializer.serialize_variant_index({});", index)?
             }
-            self.out.indent();
-            if redefine {
-                writeln!(self.out, "{} = json ;", &fields[0].name,)?;
-            } else {
-                for (index, field) in fields.iter().enumerate() {
-                    if index == fields_num - 1 {
-                        writeln!(self.out, "{} ;", self.from_json(field))?;
-                    } else {
-                        writeln!(self.out, "{} ,", self.from_json(field))?;
-                    }
-                }
+            Some(VariantTag::Name { name, .. }) => {
+                writeln!(self.out, "serializer.serialize_str({:?});", name)?
             }
-            self.out.unindent();
-        } else if variant_index.is_none() {
-            writeln!(self.out, "\n{0}.fromJson(dynamic json);", name)?;
+            None => (),
+        }
+        for field in fields {
+            writeln!(
+                self.out,
+                "{}",
+                self.quote_serialize_value(&field.name, &field.value)
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        // Deserialize (struct) or Load (variant)
+        if variant_tag.is_none() {
+            writeln!(
+                self.out,
+                "\nstatic {} deserialize(BinaryDeserializer deserializer){{",
+                name,
+            )?;
         } else {
-            writeln!(self.out, "\n{0}.loadJson(dynamic json);", name)?; //enum
+            writeln!(
+                self.out,
+                "\nstatic {} load(BinaryDeserializer deserializer){{",
+                name,
+            )?;
         }
+        self.out.indent();
 
-        if !redefine {
-            writeln!(self.out, "\ndynamic toJson() => {{")?;
+        for field in fields {
+            writeln!(
+                self.out,
+                "var {} = {};",
+                field.name,
+                self.quote_deserialize(&field.value)
+            )?;
+        }
+        writeln!(
+            self.out,
+            "return new {}({});",
+            name,
+            fields
+                .iter()
+                .map(|f| f.name.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        )?;
 
-            self.out.indent();
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        Ok(())
+    }
 
-            for (_, field) in fields.iter().enumerate() {
-                writeln!(self.out, "{},", self.to_json(field))?;
-            }
-            if let Some(index) = variant_index {
-                writeln!(self.out, "\"type\" : {},", index)?;
-                writeln!(self.out, "\"type_name\" : \"{}\"", actual_name)?;
-            }
-            self.out.unindent();
-            writeln!(self.out, "}};")?;
-        } else if fields_num > 0 {
-            writeln!(self.out, "\ndynamic toJson() => {};", &fields[0].name)?;
+    fn output_json_serializable_methods(
+        &mut self,
+        name: &str,
+        variant_tag: Option<VariantTag>,
+        actual_name: &str,
+    ) -> Result<()> {
+        match variant_tag {
+            None => writeln!(
+                self.out,
+                r#"
+factory {0}.fromJson(Map<String, dynamic> json) => _${0}FromJson(json);
+
+Map<String, dynamic> toJson() => _${0}ToJson(this);"#,
+                name
+            ),
+            // Variants also record their index and name.
+            Some(VariantTag::Index(index)) => writeln!(
+                self.out,
+                r#"
+factory {0}.loadJson(Map<String, dynamic> json) => _${0}FromJson(json);
+
+dynamic toJson() => <String, dynamic>{{
+  ..._${0}ToJson(this),
+  "type" : {1},
+  "type_name" : "{2}"
+}};"#,
+                name, index, actual_name
+            ),
+            // Variants of internally tagged enums record their name under the tag.
+            Some(VariantTag::Name { tag, name: variant }) => writeln!(
+                self.out,
+                r#"
+factory {0}.loadJson(Map<String, dynamic> json) => _${0}FromJson(json);
+
+dynamic toJson() => <String, dynamic>{{
+  ..._${0}ToJson(this),
+  {1:?} : {2:?}
+}};"#,
+                name, tag, variant
+            ),
         }
+    }
 
-        self.out.unindent();
-        // End of class
-        self.leave_class();
-        writeln!(self.out, "}}")
+    fn output_flags(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        let flag_sets = common::flag_sets(self.generator.config, &self.current_namespace, fields);
+        for (field, flags) in flag_sets {
+            writeln!(self.out)?;
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "static const int {}{} = {:#x};",
+                    field.name.to_mixed_case(),
+                    flag.to_camel_case(),
+                    value
+                )?;
+            }
+            writeln!(
+                self.out,
+                r#"
+bool has{0}(int mask) => ({1} & mask) == mask;
+
+void set{0}(int mask) {{
+  {1} |= mask;
+}}
+
+void clear{0}(int mask) {{
+  {1} &= ~mask;
+}}"#,
+                field.name.to_camel_case(),
+                field.name
+            )?;
+        }
+        Ok(())
     }
 
     fn output_class_serialize_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
@@ -906,12 +1916,26 @@ if (other == null) return false;"#,
             r#"
 Uint8List {0}Serialize() {{
     var serializer = new {1}Serializer();
-    serialize(serializer);
-    return serializer.get_bytes();
+    this.serialize(serializer);
+    return {2};
 }}"#,
             encoding.name(),
             encoding.name().to_camel_case(),
-        )
+            if self.generator.config.envelope_hooks {
+                "Envelope.wrap(serializer.get_bytes())"
+            } else {
+                "serializer.get_bytes()"
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+Uint8List {0}SerializeChecked() => Checksum.append({0}Serialize());"#,
+                encoding.name(),
+            )?;
+        }
+        Ok(())
     }
 
     fn output_class_deserialize_for_encoding(
@@ -922,9 +1946,9 @@ Uint8List {0}Serialize() {{
         writeln!(
             self.out,
             r#"
-static {0} {1}Deserialize(Uint8List input)  {{
+static {0} {1}Deserialize(Uint8List input)  {{{3}
    var deserializer = new {2}Deserializer(input);
-    {0} value = deserialize(deserializer);
+    {0} value = {4}.deserialize(deserializer);
     if (deserializer.get_buffer_offset() < input.length) {{
          throw new Exception("Some input bytes were not read");
     }}
@@ -932,107 +1956,305 @@ static {0} {1}Deserialize(Uint8List input)  {{
 }}"#,
             name,
             encoding.name(),
-            encoding.name().to_camel_case()
-        )
+            encoding.name().to_camel_case(),
+            if self.generator.config.envelope_hooks {
+                "\n    input = Envelope.unwrap(input);"
+            } else {
+                ""
+            },
+            self.quote_codec_class(name),
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+static {0} {1}DeserializeChecked(Uint8List input) => {1}Deserialize(Checksum.verify(input));"#,
+                name,
+                encoding.name(),
+            )?;
+        }
+        Ok(())
     }
 
     fn output_enum_container(
         &mut self,
         name: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
         writeln!(self.out)?;
-        //self.output_comment(name)?;
+        self.output_comment(name)?;
+        self.output_deprecation(name)?;
         writeln!(self.out, "abstract class {} {{", name)?;
         self.enter_class(name);
         writeln!(self.out, "{}();", name)?;
 
         if self.generator.config.serialization {
-            writeln!(self.out, "\nvoid serialize(BinarySerializer serializer);")?;
-            write!(
-                self.out,
-                "\nstatic {} deserialize(BinaryDeserializer deserializer) {{",
-                name
-            )?;
-            self.out.indent();
+            if !self.generator.codec_extensions {
+                self.output_enum_serialization_methods(name, tag, variants)?;
+            }
+
+            let key_type = if tag.is_some() { "String" } else { "int" };
             writeln!(
                 self.out,
                 r#"
-int index = deserializer.deserialize_variant_index();
-switch (index) {{"#,
+{}(dynamic json){{
+  final type = json[{:?}] as {};"#,
+                // `json_serializable` only recognizes constructors.
+                if self.generator.json_serializable {
+                    format!("factory {}.fromJson", name)
+                } else {
+                    format!("static {} fromJson", name)
+                },
+                tag.unwrap_or("type"),
+                key_type,
             )?;
             self.out.indent();
-            for (index, variant) in variants {
+            let chunks = common::variant_chunks(self.generator.config, variants);
+            if chunks.len() == 1 {
+                self.output_variant_switch(name, "type", tag, &chunks[0], "loadJson(json)", false)?;
                 writeln!(
                     self.out,
-                    "case {}: return {}{}Item.load(deserializer);",
-                    index, name, variant.name,
+                    "default: throw new Exception(\"Unknown type for {}: \" + type.toString());",
+                    name,
+                )?;
+                self.out.unindent();
+                writeln!(self.out, "}}")?;
+            } else {
+                writeln!(self.out, "{} value;", name)?;
+                for index in 0..chunks.len() {
+                    writeln!(
+                        self.out,
+                        "if ((value = _fromJsonVariants{}(type, json)) != null) return value;",
+                        index,
+                    )?;
+                }
+                writeln!(
+                    self.out,
+                    "throw new Exception(\"Unknown type for {}: \" + type.toString());",
+                    name,
                 )?;
             }
-            writeln!(
-                self.out,
-                "default: throw new Exception(\"Unknown variant index for {}: \" + index.toString());",
-                name,
-            )?;
-            self.out.unindent();
-            writeln!(self.out, "}}")?;
             self.out.unindent();
             writeln!(self.out, "}}")?;
-
-            for encoding in &self.generator.config.encodings {
-                self.output_class_serialize_for_encoding(*encoding)?;
-                self.output_class_deserialize_for_encoding(name, *encoding)?;
+            if chunks.len() > 1 {
+                for (index, chunk) in chunks.iter().enumerate() {
+                    write!(
+                        self.out,
+                        "\nstatic {} _fromJsonVariants{}({} type, dynamic json) {{\n",
+                        name, index, key_type,
+                    )?;
+                    self.out.indent();
+                    self.output_variant_switch(name, "type", tag, chunk, "loadJson(json)", false)?;
+                    writeln!(self.out, "default: return null;")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                }
             }
 
-            writeln!(
-                self.out,
-                r#"
-static {} fromJson(dynamic json){{
-  final type = json['type'] as int;
-  switch (type) {{"#,
-                name,
-            )?;
+            writeln!(self.out, "\ndynamic toJson();",)?;
+        }
+        if self.generator.config.variant_names {
+            self.output_variant_names(variants)?;
+        }
+        self.out.unindent();
+        self.out.unindent();
+
+        writeln!(self.out, "}}\n")?;
+
+        if self.generator.config.serialization && self.generator.codec_extensions {
+            writeln!(self.out, "extension {0}Serde on {0} {{", name)?;
             self.out.indent();
+            self.output_enum_serialization_methods(name, tag, variants)?;
+            self.out.unindent();
+            writeln!(self.out, "}}\n")?;
+        }
+
+        self.output_variants(name, tag, variants)?;
+        self.leave_class();
+        Ok(())
+    }
+
+    /// Methods (de)serializing an enum, either in the class itself or in its codec extension.
+    /// Since extension methods are resolved statically, the codec extension of an enum
+    /// dispatches serialization to the extension of each variant.
+    fn output_enum_serialization_methods(
+        &mut self,
+        name: &str,
+        tag: Option<&str>,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        if self.generator.codec_extensions {
+            writeln!(self.out, "\nvoid serialize(BinarySerializer serializer) {{")?;
             self.out.indent();
-            for (index, variant) in variants {
+            writeln!(self.out, "final value = this;")?;
+            for variant in variants.values() {
+                let class = self.quote_variant_class(name, &variant.name);
                 writeln!(
                     self.out,
-                    "case {}: return {}{}Item.loadJson(json);",
-                    index, name, variant.name,
+                    "if (value is {0}) return {0}Serde(value).serialize(serializer);",
+                    class
                 )?;
             }
             writeln!(
                 self.out,
-                "default: throw new Exception(\"Unknown type for {}: \" + type.toString());",
-                name,
+                "throw new Exception(\"Unknown variant of {}: \" + value.runtimeType.toString());",
+                name
             )?;
             self.out.unindent();
             writeln!(self.out, "}}")?;
+        } else {
+            writeln!(self.out, "\nvoid serialize(BinarySerializer serializer);")?;
+        }
+        write!(
+            self.out,
+            "\nstatic {} deserialize(BinaryDeserializer deserializer) {{",
+            name
+        )?;
+        self.out.indent();
+        let (key_type, key) = match tag {
+            Some(_) => ("String", "tag"),
+            None => ("int", "index"),
+        };
+        if tag.is_some() {
+            writeln!(self.out, "\nString tag = deserializer.deserialize_str();")?;
+        } else {
+            writeln!(
+                self.out,
+                "\nint index = deserializer.deserialize_variant_index();"
+            )?;
+        }
+        let unknown = match tag {
+            Some(_) => format!("\"Unknown variant name for {}: \" + tag", name),
+            None => format!(
+                "\"Unknown variant index for {}: \" + index.toString()",
+                name
+            ),
+        };
+        let chunks = common::variant_chunks(self.generator.config, variants);
+        if chunks.len() == 1 {
+            self.output_variant_switch(name, key, tag, &chunks[0], "load(deserializer)", true)?;
+            writeln!(self.out, "default: throw new Exception({});", unknown)?;
             self.out.unindent();
             writeln!(self.out, "}}")?;
-
-            writeln!(self.out, "\ndynamic toJson();",)?;
+        } else {
+            writeln!(self.out, "{} value;", name)?;
+            for index in 0..chunks.len() {
+                writeln!(
+                    self.out,
+                    "if ((value = _deserializeVariants{}({}, deserializer)) != null) return value;",
+                    index, key,
+                )?;
+            }
+            writeln!(self.out, "throw new Exception({});", unknown)?;
         }
         self.out.unindent();
+        writeln!(self.out, "}}")?;
+        if chunks.len() > 1 {
+            for (index, chunk) in chunks.iter().enumerate() {
+                write!(
+                    self.out,
+                    "\nstatic {} _deserializeVariants{}({} {}, BinaryDeserializer deserializer) {{\n",
+                    name, index, key_type, key,
+                )?;
+                self.out.indent();
+                self.output_variant_switch(name, key, tag, chunk, "load(deserializer)", true)?;
+                writeln!(self.out, "default: return null;")?;
+                self.out.unindent();
+                writeln!(self.out, "}}")?;
+                self.out.unindent();
+                writeln!(self.out, "}}")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Open a switch on the variant `key` and output the cases returning `<class>.<call>` for the
+    /// given variants, where `codec` selects the codec classes of the variants. The caller
+    /// outputs the default case and closes the switch.
+    fn output_variant_switch(
+        &mut self,
+        name: &str,
+        key: &str,
+        tag: Option<&str>,
+        variants: &[(&u32, &Named<VariantFormat>)],
+        call: &str,
+        codec: bool,
+    ) -> Result<()> {
+        writeln!(self.out, "switch ({}) {{", key)?;
+        self.out.indent();
+        for (index, variant) in variants {
+            let class = self.quote_variant_class(name, &variant.name);
+            writeln!(
+                self.out,
+                "case {}: return {}.{};",
+                match tag {
+                    Some(_) => format!("{:?}", variant.name),
+                    None => index.to_string(),
+                },
+                if codec {
+                    self.quote_codec_class(&class)
+                } else {
+                    class
+                },
+                call,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn output_field_descriptors(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        writeln!(
+            self.out,
+            "\nstatic List<Map<String, dynamic>> fields() => ["
+        )?;
+        self.out.indent();
+        for field in fields {
+            let (kind, optional) = common::field_kind(&field.value);
+            writeln!(
+                self.out,
+                "{{'name': '{}', 'kind': '{}', 'optional': {}}},",
+                field.name, kind, optional
+            )?;
+        }
         self.out.unindent();
+        writeln!(self.out, "];")
+    }
 
-        writeln!(self.out, "}}\n")?;
+    fn output_variant_names(
+        &mut self,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+static const List<String> variantNames = [{}];
 
-        self.output_variants(name, variants)?;
-        self.leave_class();
-        Ok(())
+static String variantName(int index) =>
+    index >= 0 && index < variantNames.length ? variantNames[index] : null;
+
+static int variantIndex(String name) => variantNames.indexOf(name);"#,
+            variants
+                .values()
+                .map(|v| format!("'{}'", v.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
     }
 
     fn output_variants(
         &mut self,
         base: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
         for (index, variant) in variants {
             self.output_variant(
                 base,
-                *index,
-                &format!("{}{}Item", base, &variant.name),
+                VariantTag::new(tag, *index, &variant.name),
+                &self.quote_variant_class(base, &variant.name),
                 &variant.value,
                 &variant.name,
             )?;
@@ -1043,7 +2265,7 @@ static {} fromJson(dynamic json){{
     fn output_variant(
         &mut self,
         base: &str,
-        index: u32,
+        tag: VariantTag,
         name: &str,
         variant: &VariantFormat,
         actual_name: &str,
@@ -1051,24 +2273,18 @@ static {} fromJson(dynamic json){{
         use VariantFormat::*;
         let fields = match variant {
             Unit => Vec::new(),
-            NewType(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
+            NewType(format) => vec![Named::new("value", format.as_ref().clone())],
             Tuple(formats) => formats
                 .iter()
                 .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
+                .map(|(i, f)| Named::new(format!("field{}", i), f.clone()))
                 .collect(),
             Struct(fields) => fields.clone(),
-            Variable(_) => panic!("incorrect value"),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         };
         self.output_struct_or_variant_container(
             Some(base),
-            Some(index),
+            Some(tag),
             name,
             &fields,
             false,
@@ -1077,21 +2293,62 @@ static {} fromJson(dynamic json){{
     }
 }
 
-/// Installer for generated source files in Go.
+/// Installer for generated source files in Dart.
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
+    flutter: bool,
+    json_serializable: bool,
+    codec_extensions: bool,
+    runtime_source: RuntimeSource,
+    web_support: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            flutter: false,
+            json_serializable: false,
+            codec_extensions: false,
+            runtime_source: RuntimeSource::Vendored,
+            web_support: false,
+        }
     }
 
-    fn install_runtime(
-        &self,
-        source_dir: include_dir::Dir,
-        path: &str,
-    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// Install a Flutter package instead of a pure Dart package.
+    pub fn with_flutter(mut self, flutter: bool) -> Self {
+        self.flutter = flutter;
+        self
+    }
+
+    /// Generate JSON conversions with `json_serializable` (run `build_runner` after installation).
+    pub fn with_json_serializable(mut self, json_serializable: bool) -> Self {
+        self.json_serializable = json_serializable;
+        self
+    }
+
+    /// Generate (de)serialization methods as extension methods on plain data classes.
+    pub fn with_codec_extensions(mut self, codec_extensions: bool) -> Self {
+        self.codec_extensions = codec_extensions;
+        self
+    }
+
+    /// Vendor the runtimes in `lib/` (the default) or depend on a published package providing
+    /// the libraries `serde`, `bincode`, and `bcs`.
+    pub fn with_runtime_source(mut self, runtime_source: RuntimeSource) -> Self {
+        self.runtime_source = runtime_source;
+        self
+    }
+
+    /// Represent 64-bit integers by `BigInt` values in generated code, so that they are exact
+    /// when compiling to JavaScript (e.g. for Flutter Web).
+    pub fn with_web_support(mut self, web_support: bool) -> Self {
+        self.web_support = web_support;
+        self
+    }
+
+    fn install_runtime(&self, source_dir: include_dir::Dir, path: &str) -> crate::Result<()> {
         let dir_path = self.install_dir.join(path);
         std::fs::create_dir_all(&dir_path)?;
         for entry in source_dir.files() {
@@ -1103,29 +2360,54 @@ impl Installer {
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
 
     fn install_module(
         &self,
         config: &CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
-        let generator = CodeGenerator::new(config);
+        let generator = CodeGenerator::new(config)
+            .with_flutter(self.flutter)
+            .with_json_serializable(self.json_serializable)
+            .with_codec_extensions(self.codec_extensions)
+            .with_runtime_source(self.runtime_source.clone())
+            .with_web_support(self.web_support);
         generator.output(self.install_dir.clone(), registry)?;
         generator.output_test(&self.install_dir)?;
         self.install_runtime(include_directory!("runtime/dart/test"), "test/src")?;
+        if self.flutter {
+            self.install_runtime(include_directory!("runtime/dart/flutter_test"), "test/src")?;
+        }
         Ok(())
     }
 
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        if self.runtime_source != RuntimeSource::Vendored {
+            return Ok(());
+        }
         self.install_runtime(include_directory!("runtime/dart/serde"), "lib/serde")
     }
 
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.install_runtime(include_directory!("runtime/dart/bincode"), "lib/bincode")
-    }
-
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.install_runtime(include_directory!("runtime/dart/bcs"), "lib/bcs")
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
+        if self.runtime_source != RuntimeSource::Vendored {
+            return Ok(());
+        }
+        let source_dir = match encoding {
+            Encoding::Bincode => include_directory!("runtime/dart/bincode"),
+            Encoding::Bcs => include_directory!("runtime/dart/bcs"),
+        };
+        self.install_runtime(source_dir, &format!("lib/{}", encoding.name()))
     }
 }
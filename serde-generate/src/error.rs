@@ -0,0 +1,34 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use thiserror::Error;
+
+/// Result type used by the code generators and source installers of this crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error type used by the code generators and source installers of this crate.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{language} does not support the format {format} in {}", location(.container, .field))]
+    UnsupportedFormat {
+        language: &'static str,
+        container: String,
+        field: Option<String>,
+        format: String,
+    },
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid registry: {0}")]
+    Registry(#[from] serde_reflection::Error),
+    #[error("Code generation was cancelled")]
+    Cancelled,
+}
+
+fn location(container: &str, field: &Option<String>) -> String {
+    match field {
+        Some(field) => format!("field {} of {}", field, container),
+        None => format!("container {}", container),
+    }
+}
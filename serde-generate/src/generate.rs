@@ -7,13 +7,21 @@
 //! cargo run --bin serdegen -- --help
 //! '''
 
+use serde::{Deserialize, Serialize};
 use serde_generate::{
-    cpp, csharp, dart, golang, java, python3, rust, typescript, CodeGeneratorConfig, Encoding,
-    SourceInstaller,
+    arrow, cpp, csharp, dart, golang, java, kaitai, lock, python3, rust, sql,
+    test_vectors::TestVector, transcode, transform, typescript, wireshark, AccessorConvention,
+    CodeGeneratorConfig, ContainerOrder, Encoding, ExternalDefinitions, FileProgress,
+    JsonIntegerPolicy, OutputLayout, ProgressReporter, RootFormats, RuntimeSource, SourceInstaller,
+    VariantNaming,
 };
-use serde_reflection::Registry;
+use serde_reflection::{Format, Registry};
+use std::io::{BufRead, Read, Write};
 use std::path::PathBuf;
-use structopt::{clap::arg_enum, StructOpt};
+use structopt::{
+    clap::{arg_enum, Shell},
+    StructOpt,
+};
 
 arg_enum! {
 #[derive(Debug, StructOpt)]
@@ -26,6 +34,10 @@ enum Language {
     Dart,
     TypeScript,
     CSharp,
+    Wireshark,
+    Kaitai,
+    Sql,
+    Arrow,
 }
 }
 
@@ -38,6 +50,276 @@ enum Runtime {
 }
 }
 
+arg_enum! {
+#[derive(Debug, StructOpt, Clone, Copy)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum ModuleSystem {
+    CommonJs,
+    Esm,
+    Deno,
+}
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum WireEncoding {
+    Bincode,
+    Bcs,
+}
+}
+
+impl From<&WireEncoding> for Encoding {
+    fn from(encoding: &WireEncoding) -> Self {
+        match encoding {
+            WireEncoding::Bincode => Encoding::Bincode,
+            WireEncoding::Bcs => Encoding::Bcs,
+        }
+    }
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum Framing {
+    U32Le,
+    U32Be,
+    Uleb128,
+}
+}
+
+impl From<&Framing> for transcode::Framing {
+    fn from(framing: &Framing) -> Self {
+        match framing {
+            Framing::U32Le => transcode::Framing::U32Le,
+            Framing::U32Be => transcode::Framing::U32Be,
+            Framing::Uleb128 => transcode::Framing::Uleb128,
+        }
+    }
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum SqlDialect {
+    Postgres,
+    ClickHouse,
+}
+}
+
+impl From<&SqlDialect> for sql::Dialect {
+    fn from(dialect: &SqlDialect) -> Self {
+        match dialect {
+            SqlDialect::Postgres => sql::Dialect::Postgres,
+            SqlDialect::ClickHouse => sql::Dialect::ClickHouse,
+        }
+    }
+}
+
+impl From<&ModuleSystem> for typescript::ModuleSystem {
+    fn from(module_system: &ModuleSystem) -> Self {
+        match module_system {
+            ModuleSystem::CommonJs => typescript::ModuleSystem::CommonJs,
+            ModuleSystem::Esm => typescript::ModuleSystem::EsModule,
+            ModuleSystem::Deno => typescript::ModuleSystem::Deno,
+        }
+    }
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum VariantNamingScheme {
+    Default,
+    Nested,
+    Prefix,
+    Suffix,
+}
+}
+
+fn variant_naming(scheme: &VariantNamingScheme, affix: Option<String>) -> VariantNaming {
+    match scheme {
+        VariantNamingScheme::Default => VariantNaming::Default,
+        VariantNamingScheme::Nested => VariantNaming::Nested,
+        VariantNamingScheme::Prefix => {
+            VariantNaming::Prefix(affix.unwrap_or_else(|| "Variant".to_string()))
+        }
+        VariantNamingScheme::Suffix => {
+            VariantNaming::Suffix(affix.unwrap_or_else(|| "Item".to_string()))
+        }
+    }
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum ContainerOrderOption {
+    Name,
+    Namespace,
+    Dependencies,
+}
+}
+
+impl From<&ContainerOrderOption> for ContainerOrder {
+    fn from(order: &ContainerOrderOption) -> Self {
+        match order {
+            ContainerOrderOption::Name => ContainerOrder::Name,
+            ContainerOrderOption::Namespace => ContainerOrder::Namespace,
+            ContainerOrderOption::Dependencies => ContainerOrder::Dependencies,
+        }
+    }
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum JsonIntegerPolicyOption {
+    Unchecked,
+    Strict,
+    Lenient,
+}
+}
+
+impl From<&JsonIntegerPolicyOption> for JsonIntegerPolicy {
+    fn from(policy: &JsonIntegerPolicyOption) -> Self {
+        match policy {
+            JsonIntegerPolicyOption::Unchecked => JsonIntegerPolicy::Unchecked,
+            JsonIntegerPolicyOption::Strict => JsonIntegerPolicy::Strict,
+            JsonIntegerPolicyOption::Lenient => JsonIntegerPolicy::Lenient,
+        }
+    }
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum AccessorConventionOption {
+    Fields,
+    Methods,
+    JavaBeans,
+}
+}
+
+impl From<&AccessorConventionOption> for AccessorConvention {
+    fn from(convention: &AccessorConventionOption) -> Self {
+        match convention {
+            AccessorConventionOption::Fields => AccessorConvention::Fields,
+            AccessorConventionOption::Methods => AccessorConvention::Methods,
+            AccessorConventionOption::JavaBeans => AccessorConvention::JavaBeans,
+        }
+    }
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt)]
+enum OutputLayoutOption {
+    Default,
+    SingleFile,
+}
+}
+
+impl From<&OutputLayoutOption> for OutputLayout {
+    fn from(layout: &OutputLayoutOption) -> Self {
+        match layout {
+            OutputLayoutOption::Default => OutputLayout::Default,
+            OutputLayoutOption::SingleFile => OutputLayout::SingleFile,
+        }
+    }
+}
+
+/// A registry transformation given on the command line, e.g. `rename:Old=New`,
+/// `replace:BYTES=BOOL`, `inline-newtypes`, `seq-u8-as-bytes`, or `strip-namespaces`.
+#[derive(Debug)]
+enum TransformSpec {
+    Rename(String, String),
+    Replace(Format, Format),
+    InlineNewTypes,
+    SeqU8AsBytes,
+    StripNamespaces,
+    ExternalType(String, String),
+}
+
+impl std::str::FromStr for TransformSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, argument) = match s.find(':') {
+            Some(index) => (&s[..index], Some(&s[index + 1..])),
+            None => (s, None),
+        };
+        let parse_format = |format: &str| {
+            serde_yaml::from_str::<Format>(format)
+                .map_err(|e| format!("Invalid format {}: {}", format, e))
+        };
+        match kind {
+            "rename" => {
+                let (from, to) = split_transform_argument(kind, argument)?;
+                Ok(TransformSpec::Rename(from.to_string(), to.to_string()))
+            }
+            "replace" => {
+                let (from, to) = split_transform_argument(kind, argument)?;
+                Ok(TransformSpec::Replace(
+                    parse_format(from)?,
+                    parse_format(to)?,
+                ))
+            }
+            "inline-newtypes" => Ok(TransformSpec::InlineNewTypes),
+            "seq-u8-as-bytes" => Ok(TransformSpec::SeqU8AsBytes),
+            "strip-namespaces" => Ok(TransformSpec::StripNamespaces),
+            "external-type" => {
+                let (name, module) = split_transform_argument(kind, argument)?;
+                Ok(TransformSpec::ExternalType(
+                    name.to_string(),
+                    module.to_string(),
+                ))
+            }
+            _ => Err(format!("Unknown transformation {}", kind)),
+        }
+    }
+}
+
+fn parse_hand_written_type(argument: &str) -> Result<(String, String), String> {
+    let index = argument
+        .find('=')
+        .ok_or_else(|| "Expected `<Name>=<type>`".to_string())?;
+    Ok((
+        argument[..index].to_string(),
+        argument[index + 1..].to_string(),
+    ))
+}
+
+fn split_transform_argument<'a>(
+    kind: &str,
+    argument: Option<&'a str>,
+) -> Result<(&'a str, &'a str), String> {
+    argument
+        .and_then(|argument| {
+            let index = argument.find('=')?;
+            Some((&argument[..index], &argument[index + 1..]))
+        })
+        .ok_or_else(|| format!("Expected `{}:<from>=<to>`", kind))
+}
+
+impl TransformSpec {
+    fn transform(&self) -> Box<dyn transform::RegistryTransform> {
+        match self {
+            TransformSpec::Rename(from, to) => Box::new(transform::RenameContainers(
+                std::iter::once((from.clone(), to.clone())).collect(),
+            )),
+            TransformSpec::Replace(from, to) => Box::new(transform::ReplaceFormat {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            TransformSpec::InlineNewTypes => Box::new(transform::InlineNewTypes),
+            TransformSpec::SeqU8AsBytes => Box::new(transform::SeqU8AsBytes),
+            TransformSpec::StripNamespaces => Box::new(transform::StripNamespaces),
+            TransformSpec::ExternalType(name, _) => Box::new(transform::ExternalTypes(
+                std::iter::once(name.clone()).collect(),
+            )),
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "Serde code generator",
@@ -48,6 +330,14 @@ struct Options {
     #[structopt(parse(from_os_str))]
     input: Option<PathBuf>,
 
+    /// Transformations applied in order to the formats before code generation:
+    /// `rename:<Old>=<New>`, `replace:<format>=<format>` (YAML-encoded formats),
+    /// `inline-newtypes`, `seq-u8-as-bytes`, `strip-namespaces`, or
+    /// `external-type:<Name>=<module>` (custom formats named `<Name>` become references to a
+    /// definition provided by `<module>`).
+    #[structopt(long = "transform", number_of_values = 1)]
+    transforms: Vec<TransformSpec>,
+
     /// Language for code generation.
     #[structopt(long, possible_values = &Language::variants(), case_insensitive = true, default_value = "Python3")]
     language: Language,
@@ -61,6 +351,17 @@ struct Options {
     #[structopt(long, possible_values = &Runtime::variants(), case_insensitive = true)]
     with_runtimes: Vec<Runtime>,
 
+    /// Also install the unit tests of the runtimes given by `--with-runtimes`, if the language
+    /// ships any (Python, Java, and C#).
+    #[structopt(long)]
+    with_runtime_tests: bool,
+
+    /// Also install the types and test vectors of the shared corpus (module `serde_corpus`)
+    /// with a runner checking the runtimes given by `--with-runtimes` against them (Python and
+    /// Java).
+    #[structopt(long)]
+    with_corpus_runner: bool,
+
     /// Module name for the Serde formats installed in the `target_source_dir`.
     /// Rust crates may contain a version number separated with a colon, e.g. "test:1.2.0".
     /// (By default, the installer will use version "0.1.0".)
@@ -71,10 +372,704 @@ struct Options {
     #[structopt(long)]
     serde_package_name: Option<String>,
 
+    /// Depend on a published package providing the Serde runtimes, given as `name:version`
+    /// (e.g. "serde_runtimes:^0.1.0"), instead of installing their sources (Dart and C# only).
+    #[structopt(long)]
+    runtime_package: Option<String>,
+
+    /// Generate Pydantic dataclasses with validators (Python only).
+    #[structopt(long)]
+    pydantic: bool,
+
+    /// Embed the Serde runtimes in the generated module, which is then installed as a single
+    /// file importable without any package (Python only).
+    #[structopt(long)]
+    embedded_runtime: bool,
+
     /// Translate enums without variant data (c-style enums) into their equivalent in the target language,
     /// if the target language and the generator code support them.
     #[structopt(long)]
     use_c_style_enums: bool,
+
+    /// Generate a table of variant names for each enum, with functions converting
+    /// variant indices to names and back.
+    #[structopt(long)]
+    variant_names: bool,
+
+    /// Generate a static `fields()` descriptor for each struct, listing the name, the kind of
+    /// values, and the optionality of each field (Dart, Python, and TypeScript only).
+    #[structopt(long)]
+    field_descriptors: bool,
+
+    /// Install test fixtures, i.e. a class `FooFixtures` with a static method `sample()`
+    /// building a valid instance of each container `Foo`, in a separate module (Python and
+    /// TypeScript only).
+    #[structopt(long)]
+    fixtures: bool,
+
+    /// Generate functions decoding values from memory-mapped files, for each encoding
+    /// (C++ and Rust only).
+    #[structopt(long)]
+    file_decoding: bool,
+
+    /// Root types stored in record logs. Generate their schema fingerprint and typed
+    /// functions writing and reading record logs (Python, Rust, and TypeScript only).
+    #[structopt(long)]
+    record_log_types: Vec<String>,
+
+    /// Pass the bytes of encoding-specific entry points (e.g. `bcsSerialize`) through the
+    /// envelope hooks registered in the serde runtime, e.g. to compress or encrypt them
+    /// (all languages except Rust).
+    #[structopt(long)]
+    envelope_hooks: bool,
+
+    /// Generate checked variants of encoding-specific entry points (e.g. `bcsSerializeChecked`)
+    /// appending and verifying a CRC-32 checksum (all languages except Rust).
+    #[structopt(long)]
+    checksums: bool,
+
+    /// Document the thread safety of generated classes (Java, C#, and TypeScript only).
+    #[structopt(long)]
+    thread_safety_notes: bool,
+
+    /// Path to a YAML file mapping names to root formats that are not containers, e.g.
+    /// `TransactionList: {SEQ: {TYPENAME: Transaction}}`. Generate top-level functions
+    /// serializing and deserializing such values (C++, Go, Java, Python, and TypeScript only).
+    #[structopt(long, parse(from_os_str))]
+    root_formats: Option<PathBuf>,
+
+    /// Emit native aliases for custom formats, such as type aliases declared during tracing,
+    /// and use their names in type signatures (C++, Go, Python, and TypeScript only).
+    #[structopt(long)]
+    type_aliases: bool,
+
+    /// Write 64-bit and 128-bit integers as decimal strings in JSON rather than numbers
+    /// (Dart and TypeScript only).
+    #[structopt(long)]
+    json_integers_as_strings: bool,
+
+    /// How JSON decoders check integers: not at all (Unchecked), by rejecting non-integers and
+    /// out-of-range values (Strict), or by also accepting integral floats and decimal strings
+    /// (Lenient) (Dart and TypeScript only).
+    #[structopt(long, possible_values = &JsonIntegerPolicyOption::variants(), case_insensitive = true, default_value = "Strict")]
+    json_integer_policy: JsonIntegerPolicyOption,
+
+    /// Embed the Serde formats as a compressed constant in the generated code, with an accessor
+    /// returning them in YAML (Go, Java, and Python only).
+    #[structopt(long)]
+    embed_schema: bool,
+
+    /// Maximal length of the names of (de)serialization helpers, such as `serialize_vector_u8`.
+    /// Longer names are truncated and completed with a hash of the format.
+    #[structopt(long)]
+    max_helper_name_length: Option<usize>,
+
+    /// Inline the (de)serialization of simple formats such as `Option<u64>` and `Vec<u8>`
+    /// instead of generating helpers for them (TypeScript only).
+    #[structopt(long)]
+    inline_simple_helpers: bool,
+
+    /// Maximal number of cases in the switch dispatching the deserialization of enum variants.
+    /// Larger enums spread the dispatch over several methods (C#, Dart, Go, Java, and
+    /// TypeScript only).
+    #[structopt(long)]
+    max_switch_cases: Option<usize>,
+
+    /// Maximal number of lines of generated source files. Longer files are split between
+    /// containers into numbered parts (C++, Dart with `--output-layout SingleFile`, and
+    /// TypeScript only).
+    #[structopt(long)]
+    max_file_lines: Option<usize>,
+
+    /// Naming scheme of the classes holding the payload of enum variants: `<Base>__<Variant>`
+    /// (Nested), `<Base><affix><Variant>` (Prefix), or `<Base><Variant><affix>` (Suffix)
+    /// (Dart, Go, Python, and TypeScript only).
+    #[structopt(long, possible_values = &VariantNamingScheme::variants(), case_insensitive = true, default_value = "Default")]
+    variant_naming: VariantNamingScheme,
+
+    /// Affix of the Prefix and Suffix variant naming schemes (by default, "Variant" and "Item"
+    /// respectively).
+    #[structopt(long)]
+    variant_affix: Option<String>,
+
+    /// Emit the classes holding the payload of enum variants as nested classes of the enum,
+    /// e.g. `Shape.Circle` (TypeScript only).
+    #[structopt(long)]
+    nested_variant_classes: bool,
+
+    /// Expose sequences and maps of generated classes as read-only collections
+    /// (Dart, Java, Python, and TypeScript only).
+    #[structopt(long)]
+    immutable_collections: bool,
+
+    /// Compare and hash generated values through their cached BCS serialization rather than
+    /// field by field (Dart and Java only).
+    #[structopt(long)]
+    canonical_equality: bool,
+
+    /// In which order to emit containers: by name, grouped by namespace, or dependencies first
+    /// (Dart, Go, Python, and TypeScript only).
+    #[structopt(long, possible_values = &ContainerOrderOption::variants(), case_insensitive = true, default_value = "Name")]
+    container_order: ContainerOrderOption,
+
+    /// Map sequences of bytes to the byte arrays of the target language, e.g. `Uint8List` in
+    /// Dart (C++, C#, Dart, Go, Java, Python, Rust, and TypeScript only).
+    #[structopt(long)]
+    seq_u8_as_bytes: bool,
+
+    /// Install a Flutter package rather than a pure Dart package (Dart only).
+    #[structopt(long)]
+    flutter: bool,
+
+    /// Emit a single self-contained header amalgamating the runtime headers (C++ only).
+    #[structopt(long)]
+    single_header: bool,
+
+    /// Generate (de)serialization code as free functions `serde::serialize` and
+    /// `serde::deserialize` rather than members, and only the codecs of the containers provided
+    /// by external modules, e.g. with `--transform external-type:<Name>=<module>` (C++ only).
+    #[structopt(long)]
+    free_functions: bool,
+
+    /// Existing type standing for a container, as `<Name>=<type>` (e.g.
+    /// `Account=bank::Account`). Only the (de)serialization code of the container is generated,
+    /// for the given type (C++ only).
+    #[structopt(long = "hand-written-type", number_of_values = 1, parse(try_from_str = parse_hand_written_type))]
+    hand_written_types: Vec<(String, String)>,
+
+    /// How (de)serialization code reads and assigns the fields of hand-written types: public
+    /// fields, `x()` and `set_x(value)`, or `getX()` and `setX(value)` (C++ only).
+    #[structopt(long, possible_values = &AccessorConventionOption::variants(), case_insensitive = true, default_value = "Fields")]
+    accessor_convention: AccessorConventionOption,
+
+    /// How to lay out the source files of installed modules, e.g. a single Dart library
+    /// without parts (C++, Dart, and Python only).
+    #[structopt(long, possible_values = &OutputLayoutOption::variants(), case_insensitive = true, default_value = "Default")]
+    output_layout: OutputLayoutOption,
+
+    /// Generate JSON conversions with `json_serializable` (Dart only).
+    #[structopt(long)]
+    json_serializable: bool,
+
+    /// Generate (de)serialization methods as extension methods on plain data classes (Dart only).
+    #[structopt(long)]
+    codec_extensions: bool,
+
+    /// Represent 64-bit integers by `BigInt` values, which are exact when compiling to
+    /// JavaScript, e.g. for Flutter Web (Dart only).
+    #[structopt(long)]
+    web_support: bool,
+
+    /// Add Jackson annotations to the generated classes (Java only).
+    #[structopt(long)]
+    jackson: bool,
+
+    /// Module system targeted by the generated code and runtimes (TypeScript only).
+    #[structopt(long, possible_values = &ModuleSystem::variants(), case_insensitive = true, default_value = "CommonJs")]
+    module_system: ModuleSystem,
+
+    /// Generate zod schemas validating JSON inputs (TypeScript only).
+    #[structopt(long)]
+    zod: bool,
+
+    /// Container name of the messages carried by the protocol (Wireshark and Kaitai only).
+    #[structopt(long)]
+    root_type: Option<String>,
+
+    /// Length prefix delimiting the messages (Wireshark only).
+    #[structopt(long, possible_values = &Framing::variants(), case_insensitive = true, default_value = "U32Le")]
+    framing: Framing,
+
+    /// TCP port of the protocol (Wireshark only).
+    #[structopt(long)]
+    port: Option<u16>,
+
+    /// Dialect of the generated statements (SQL only).
+    #[structopt(long, possible_values = &SqlDialect::variants(), case_insensitive = true, default_value = "Postgres")]
+    sql_dialect: SqlDialect,
+
+    /// Format of the diagnostics printed on the standard error: human-readable lines or one
+    /// JSON object per line.
+    #[structopt(long, possible_values = &MessageFormat::variants(), case_insensitive = true, default_value = "Human")]
+    message_format: MessageFormat,
+
+    /// Print a note on the standard error for each generated source file, with its size and
+    /// generation time.
+    #[structopt(long)]
+    progress: bool,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Record the wire layout of the Serde formats in a lockfile, or check the formats against
+    /// an existing lockfile and fail on incompatible changes.
+    Lock {
+        /// Path to the YAML-encoded Serde formats.
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+
+        /// Path to the lockfile.
+        #[structopt(long, parse(from_os_str), default_value = "serde-formats.lock")]
+        lockfile: PathBuf,
+
+        /// Overwrite the lockfile even if the wire layout has changed.
+        #[structopt(long)]
+        update: bool,
+    },
+
+    /// Interactively encode JSON values into hexadecimal bytes and decode them back, following
+    /// the Serde formats.
+    Repl {
+        /// Path to the YAML-encoded Serde formats.
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+
+        /// Encodings to use (default: all of them).
+        #[structopt(long, possible_values = &WireEncoding::variants(), case_insensitive = true)]
+        encodings: Vec<WireEncoding>,
+    },
+
+    /// Forward TCP connections to another service while logging the framed messages exchanged
+    /// in both directions as JSON lines.
+    Proxy {
+        /// Path to the YAML-encoded Serde formats.
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+
+        /// Address where to accept connections.
+        #[structopt(long, default_value = "127.0.0.1:8000")]
+        listen: String,
+
+        /// Address of the service receiving the forwarded connections.
+        #[structopt(long)]
+        upstream: String,
+
+        /// Container name of the messages sent to the upstream service.
+        #[structopt(long)]
+        request_type: String,
+
+        /// Container name of the messages sent back by the upstream service.
+        #[structopt(long)]
+        response_type: String,
+
+        /// Encoding of the messages.
+        #[structopt(long, possible_values = &WireEncoding::variants(), case_insensitive = true, default_value = "Bcs")]
+        encoding: WireEncoding,
+
+        /// Length prefix delimiting the messages.
+        #[structopt(long, possible_values = &Framing::variants(), case_insensitive = true, default_value = "U32Le")]
+        framing: Framing,
+    },
+
+    /// Decode the same bytes with an old and a new version of the Serde formats, and report where
+    /// the two interpretations diverge.
+    DecodeDiff {
+        /// Path to the old YAML-encoded Serde formats.
+        #[structopt(long, parse(from_os_str))]
+        old: PathBuf,
+
+        /// Path to the new YAML-encoded Serde formats.
+        #[structopt(long, parse(from_os_str))]
+        new: PathBuf,
+
+        /// Container name of the encoded value.
+        #[structopt(long = "type")]
+        type_name: String,
+
+        /// Encoded value in hexadecimal.
+        #[structopt(long)]
+        hex: String,
+
+        /// Encoding of the value.
+        #[structopt(long, possible_values = &WireEncoding::variants(), case_insensitive = true, default_value = "Bcs")]
+        encoding: WireEncoding,
+    },
+
+    /// Print test vectors for the values listed in a YAML file, one line per value and encoding:
+    /// type name, encoding, hexadecimal bytes, and JSON value, separated by tabs.
+    TestVectors {
+        /// Path to the YAML-encoded Serde formats.
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+
+        /// Path to a YAML list of entries such as `{type: Foo, value: {x: 1}}`.
+        #[structopt(long, parse(from_os_str))]
+        values: PathBuf,
+
+        /// Encodings to use (default: all of them).
+        #[structopt(long, possible_values = &WireEncoding::variants(), case_insensitive = true)]
+        encodings: Vec<WireEncoding>,
+    },
+
+    /// Print a completion script for the given shell.
+    Completions {
+        /// Target shell.
+        #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+        shell: Shell,
+    },
+
+    /// Describe the supported languages, encodings, and language-specific options, e.g. for
+    /// build tools wrapping the code generator.
+    Describe {
+        /// Print JSON instead of YAML.
+        #[structopt(long)]
+        json: bool,
+    },
+}
+
+/// Options that only apply to some languages, with the languages in question.
+const LANGUAGE_OPTIONS: &[(&str, &[&str])] = &[
+    ("--serde-package-name", &["Python3", "Go"]),
+    ("--runtime-package", &["Dart", "CSharp"]),
+    ("--with-runtime-tests", &["Python3", "Java", "CSharp"]),
+    ("--with-corpus-runner", &["Python3", "Java"]),
+    ("--pydantic", &["Python3"]),
+    ("--embedded-runtime", &["Python3"]),
+    ("--field-descriptors", &["Python3", "Dart", "TypeScript"]),
+    ("--fixtures", &["Python3", "TypeScript"]),
+    ("--file-decoding", &["Cpp", "Rust"]),
+    ("--record-log-types", &["Python3", "Rust", "TypeScript"]),
+    ("--envelope-hooks", CODE_LANGUAGES_EXCEPT_RUST),
+    ("--checksums", CODE_LANGUAGES_EXCEPT_RUST),
+    ("--thread-safety-notes", &["Java", "CSharp", "TypeScript"]),
+    (
+        "--root-formats",
+        &["Python3", "Cpp", "Java", "Go", "TypeScript"],
+    ),
+    ("--type-aliases", &["Python3", "Cpp", "Go", "TypeScript"]),
+    ("--json-integers-as-strings", &["Dart", "TypeScript"]),
+    ("--embed-schema", &["Python3", "Java", "Go"]),
+    ("--inline-simple-helpers", &["TypeScript"]),
+    (
+        "--max-switch-cases",
+        &["CSharp", "Dart", "Go", "Java", "TypeScript"],
+    ),
+    ("--max-file-lines", &["Cpp", "Dart", "TypeScript"]),
+    ("--variant-naming", &["Python3", "Go", "Dart", "TypeScript"]),
+    ("--variant-affix", &["Python3", "Go", "Dart", "TypeScript"]),
+    ("--nested-variant-classes", &["TypeScript"]),
+    (
+        "--immutable-collections",
+        &["Python3", "Java", "Dart", "TypeScript"],
+    ),
+    ("--canonical-equality", &["Java", "Dart"]),
+    (
+        "--container-order",
+        &["Python3", "Go", "Dart", "TypeScript"],
+    ),
+    (
+        "--seq-u8-as-bytes",
+        &[
+            "Python3",
+            "Cpp",
+            "Rust",
+            "Java",
+            "Go",
+            "Dart",
+            "TypeScript",
+            "CSharp",
+        ],
+    ),
+    ("--flutter", &["Dart"]),
+    ("--single-header", &["Cpp"]),
+    ("--free-functions", &["Cpp"]),
+    ("--hand-written-type", &["Cpp"]),
+    ("--accessor-convention", &["Cpp"]),
+    ("--output-layout", &["Python3", "Cpp", "Dart"]),
+    ("--json-serializable", &["Dart"]),
+    ("--codec-extensions", &["Dart"]),
+    ("--web-support", &["Dart"]),
+    ("--jackson", &["Java"]),
+    ("--module-system", &["TypeScript"]),
+    ("--zod", &["TypeScript"]),
+    ("--root-type", &["Wireshark", "Kaitai"]),
+    ("--framing", &["Wireshark"]),
+    ("--port", &["Wireshark"]),
+    ("--sql-dialect", &["Sql"]),
+];
+
+const CODE_LANGUAGES_EXCEPT_RUST: &[&str] = &[
+    "Python3",
+    "Cpp",
+    "Java",
+    "Go",
+    "Dart",
+    "TypeScript",
+    "CSharp",
+];
+
+#[derive(Serialize)]
+struct Capabilities {
+    languages: Vec<String>,
+    encodings: Vec<String>,
+    runtimes: Vec<String>,
+    options: Vec<OptionDescription>,
+}
+
+#[derive(Serialize)]
+struct OptionDescription {
+    name: String,
+    languages: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<String>>,
+}
+
+fn run_describe(json: bool) {
+    let strings = |values: &[&str]| values.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let options = LANGUAGE_OPTIONS
+        .iter()
+        .map(|(name, languages)| {
+            let values = match *name {
+                "--variant-naming" => Some(strings(&VariantNamingScheme::variants())),
+                "--container-order" => Some(strings(&ContainerOrderOption::variants())),
+                "--accessor-convention" => Some(strings(&AccessorConventionOption::variants())),
+                "--output-layout" => Some(strings(&OutputLayoutOption::variants())),
+                "--module-system" => Some(strings(&ModuleSystem::variants())),
+                "--framing" => Some(strings(&Framing::variants())),
+                "--sql-dialect" => Some(strings(&SqlDialect::variants())),
+                _ => None,
+            };
+            OptionDescription {
+                name: name.to_string(),
+                languages: strings(languages),
+                values,
+            }
+        })
+        .collect();
+    let capabilities = Capabilities {
+        languages: strings(&Language::variants()),
+        encodings: strings(&WireEncoding::variants()),
+        runtimes: strings(&Runtime::variants()),
+        options,
+    };
+    if json {
+        let value = serde_yaml::to_value(&capabilities).unwrap();
+        println!("{}", transcode::to_json(&value));
+    } else {
+        print!("{}", serde_yaml::to_string(&capabilities).unwrap());
+    }
+}
+
+/// A problem reported on the standard error. Errors make the tool exit with status 1 (usage
+/// errors exit with status 2), whereas warnings do not change the exit status.
+#[derive(Serialize)]
+struct Diagnostic {
+    severity: &'static str,
+    /// Stable identifier of the kind of problem, e.g. `invalid-formats`.
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+    /// Location within the formats, starting with the container name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+}
+
+impl Diagnostic {
+    fn error(code: &'static str, message: String) -> Self {
+        Self {
+            severity: "error",
+            code,
+            message,
+            file: None,
+            line: None,
+            column: None,
+            path: None,
+        }
+    }
+
+    fn warning(code: &'static str, message: String) -> Self {
+        Self {
+            severity: "warning",
+            ..Self::error(code, message)
+        }
+    }
+
+    fn note(code: &'static str, message: String) -> Self {
+        Self {
+            severity: "note",
+            ..Self::error(code, message)
+        }
+    }
+
+    fn with_file(mut self, file: &std::path::Path) -> Self {
+        self.file = Some(file.to_path_buf());
+        self
+    }
+
+    fn with_path(mut self, path: String) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Diagnostic for a YAML file that could not be parsed, with the location of the error.
+    fn invalid_yaml(code: &'static str, file: &std::path::Path, error: serde_yaml::Error) -> Self {
+        let mut diagnostic = Self::error(code, error.to_string()).with_file(file);
+        if let Some(location) = error.location() {
+            diagnostic.line = Some(location.line());
+            diagnostic.column = Some(location.column());
+        }
+        diagnostic
+    }
+
+    fn emit(&self, format: MessageFormat) {
+        match format {
+            MessageFormat::Human => {
+                let mut location = String::new();
+                if let Some(file) = &self.file {
+                    location += &format!("{}:", file.display());
+                    if let (Some(line), Some(column)) = (self.line, self.column) {
+                        location += &format!("{}:{}:", line, column);
+                    }
+                    location += " ";
+                }
+                if let Some(path) = &self.path {
+                    location += &format!("{}: ", path);
+                }
+                eprintln!(
+                    "{}[{}]: {}{}",
+                    self.severity, self.code, location, self.message
+                );
+            }
+            MessageFormat::Json => {
+                let value = serde_yaml::to_value(self).unwrap();
+                eprintln!("{}", transcode::to_json(&value));
+            }
+        }
+    }
+
+    /// Emit the diagnostic, which should be an error, then exit.
+    fn fail(&self, format: MessageFormat) -> ! {
+        self.emit(format);
+        std::process::exit(1);
+    }
+}
+
+/// Note reporting a generated source file (see `--progress`).
+fn progress_note(progress: &FileProgress) -> Diagnostic {
+    let message = format!(
+        "generated {} bytes of module `{}` in {} ms",
+        progress.bytes,
+        progress.module_name,
+        progress.elapsed.as_millis()
+    );
+    let note = Diagnostic::note("generated-file", message);
+    match &progress.path {
+        Some(path) => note.with_file(path),
+        None => note,
+    }
+}
+
+fn report_progress(progress: &FileProgress) {
+    progress_note(progress).emit(MessageFormat::Human);
+}
+
+fn report_progress_as_json(progress: &FileProgress) {
+    progress_note(progress).emit(MessageFormat::Json);
+}
+
+/// Writer counting the bytes of the code printed on the standard output.
+struct CountingWriter<W> {
+    inner: W,
+    bytes: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn read_file(path: &std::path::Path, message_format: MessageFormat) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|error| {
+        Diagnostic::error("unreadable-file", error.to_string())
+            .with_file(path)
+            .fail(message_format)
+    })
+}
+
+fn read_registry(path: &std::path::Path, message_format: MessageFormat) -> Registry {
+    let content = read_file(path, message_format);
+    serde_yaml::from_str::<Registry>(content.as_str()).unwrap_or_else(|error| {
+        Diagnostic::invalid_yaml("invalid-formats", path, error).fail(message_format)
+    })
+}
+
+fn read_root_formats(path: &std::path::Path, message_format: MessageFormat) -> RootFormats {
+    let content = read_file(path, message_format);
+    serde_yaml::from_str::<RootFormats>(content.as_str()).unwrap_or_else(|error| {
+        Diagnostic::invalid_yaml("invalid-root-formats", path, error).fail(message_format)
+    })
+}
+
+fn run_lock(
+    input: &std::path::Path,
+    lockfile: &std::path::Path,
+    update: bool,
+    message_format: MessageFormat,
+) {
+    let registry = read_registry(input, message_format);
+    if lockfile.exists() {
+        let locked = read_registry(lockfile, message_format);
+        let changes = lock::check_wire_layout(&locked, &registry);
+        for change in &changes {
+            let diagnostic = if update {
+                Diagnostic::warning("wire-layout-change", change.description.clone())
+            } else {
+                Diagnostic::error("wire-layout-change", change.description.clone())
+            };
+            diagnostic
+                .with_file(input)
+                .with_path(change.path.join("."))
+                .emit(message_format);
+        }
+        if !changes.is_empty() && !update {
+            Diagnostic::error(
+                "locked-formats-changed",
+                "Wire layout differs from the lockfile (use `--update` to accept the changes)"
+                    .to_string(),
+            )
+            .with_file(lockfile)
+            .fail(message_format);
+        }
+    }
+    // Locked containers are unchanged at this point, so the new lockfile only records additions.
+    std::fs::write(lockfile, serde_yaml::to_string(&registry).unwrap()).unwrap_or_else(|error| {
+        Diagnostic::error("unwritable-file", error.to_string())
+            .with_file(lockfile)
+            .fail(message_format)
+    });
+}
+
+/// Modules providing the types declared with `--transform external-type`.
+fn external_definitions(transforms: &[TransformSpec]) -> ExternalDefinitions {
+    let mut definitions = ExternalDefinitions::new();
+    for transform in transforms {
+        if let TransformSpec::ExternalType(name, module) = transform {
+            definitions
+                .entry(module.clone())
+                .or_default()
+                .push(name.clone());
+        }
+    }
+    definitions
+}
+
+fn generation_failed(error: impl std::fmt::Display, message_format: MessageFormat) -> ! {
+    Diagnostic::error("generation-failed", error.to_string()).fail(message_format)
 }
 
 fn get_codegen_config<'a, I>(name: String, runtimes: I, c_style_enums: bool) -> CodeGeneratorConfig
@@ -98,86 +1093,664 @@ where
         .with_c_style_enums(c_style_enums)
 }
 
+const REPL_HELP: &str = r#"Commands:
+  encode <Type> <JSON value>    Print the encoded bytes of a value in hexadecimal
+  decode <Type> <hex bytes>     Print the decoded value of some bytes as JSON
+  types                         List the containers in the Serde formats
+  help                          Print this message
+  quit                          Exit"#;
+
+fn run_repl(input: &std::path::Path, encodings: &[WireEncoding], message_format: MessageFormat) {
+    let registry = read_registry(input, message_format);
+    let encodings: Vec<Encoding> = if encodings.is_empty() {
+        vec![Encoding::Bincode, Encoding::Bcs]
+    } else {
+        encodings.iter().map(Encoding::from).collect()
+    };
+    println!("{}", REPL_HELP);
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        std::io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(line) => line.expect("failed to read standard input"),
+            None => break,
+        };
+        let mut words = line.trim().splitn(3, char::is_whitespace);
+        match (words.next(), words.next(), words.next()) {
+            (Some(""), None, None) => (),
+            (Some("quit"), None, None) | (Some("exit"), None, None) => break,
+            (Some("help"), None, None) => println!("{}", REPL_HELP),
+            (Some("types"), None, None) => {
+                for name in registry.keys() {
+                    println!("{}", name);
+                }
+            }
+            (Some("encode"), Some(name), Some(input)) => {
+                let value = match serde_yaml::from_str::<serde_yaml::Value>(input) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        println!("Invalid value: {}", error);
+                        continue;
+                    }
+                };
+                let format = Format::TypeName(name.to_string());
+                for encoding in &encodings {
+                    match transcode::encode(&registry, &format, &value, *encoding) {
+                        Ok(bytes) => println!("{}: {}", encoding.name(), to_hex(&bytes)),
+                        Err(error) => println!("{}: {}", encoding.name(), error),
+                    }
+                }
+            }
+            (Some("decode"), Some(name), Some(input)) => {
+                let bytes = match from_hex(input) {
+                    Some(bytes) => bytes,
+                    None => {
+                        println!("Invalid hexadecimal bytes");
+                        continue;
+                    }
+                };
+                let format = Format::TypeName(name.to_string());
+                for encoding in &encodings {
+                    match transcode::decode(&registry, &format, &bytes, *encoding) {
+                        Ok(value) => {
+                            println!("{}: {}", encoding.name(), transcode::to_json(&value))
+                        }
+                        Err(error) => println!("{}: {}", encoding.name(), error),
+                    }
+                }
+            }
+            _ => println!("Unknown command (type `help` for a list of commands)"),
+        }
+    }
+}
+
+fn run_decode_diff(
+    old: &std::path::Path,
+    new: &std::path::Path,
+    type_name: &str,
+    hex: &str,
+    encoding: Encoding,
+    message_format: MessageFormat,
+) {
+    let bytes = from_hex(hex).unwrap_or_else(|| {
+        Diagnostic::error("invalid-hex", "Invalid hexadecimal bytes".to_string())
+            .fail(message_format)
+    });
+    let format = Format::TypeName(type_name.to_string());
+    let old_value = transcode::decode(
+        &read_registry(old, message_format),
+        &format,
+        &bytes,
+        encoding,
+    );
+    let new_value = transcode::decode(
+        &read_registry(new, message_format),
+        &format,
+        &bytes,
+        encoding,
+    );
+    for (label, value) in &[("old", &old_value), ("new", &new_value)] {
+        match value {
+            Ok(value) => println!("{}: {}", label, transcode::to_json(value)),
+            Err(error) => println!("{}: error: {}", label, error),
+        }
+    }
+    let differences = match (&old_value, &new_value) {
+        (Ok(old_value), Ok(new_value)) => transcode::diff(old_value, new_value),
+        (Err(_), Err(_)) => std::process::exit(1),
+        _ => vec!["$: decoding succeeds with only one of the schemas".to_string()],
+    };
+    if differences.is_empty() {
+        println!("No divergence");
+    } else {
+        for difference in &differences {
+            println!("{}", difference);
+        }
+        std::process::exit(1);
+    }
+}
+
+#[derive(Deserialize)]
+struct TestVectorEntry {
+    #[serde(rename = "type")]
+    type_name: String,
+    value: serde_yaml::Value,
+}
+
+fn run_test_vectors(
+    input: &std::path::Path,
+    values: &std::path::Path,
+    encodings: &[WireEncoding],
+    message_format: MessageFormat,
+) {
+    let registry = read_registry(input, message_format);
+    let encodings: Vec<Encoding> = if encodings.is_empty() {
+        vec![Encoding::Bincode, Encoding::Bcs]
+    } else {
+        encodings.iter().map(Encoding::from).collect()
+    };
+    let content = read_file(values, message_format);
+    let entries =
+        serde_yaml::from_str::<Vec<TestVectorEntry>>(content.as_str()).unwrap_or_else(|error| {
+            Diagnostic::invalid_yaml("invalid-values", values, error).fail(message_format)
+        });
+    for entry in entries {
+        let vectors = TestVector::generate(&registry, &entry.type_name, &entry.value, &encodings)
+            .unwrap_or_else(|error| {
+                Diagnostic::error(
+                    "invalid-value",
+                    format!(
+                        "Failed to encode a value of type {}: {}",
+                        entry.type_name, error
+                    ),
+                )
+                .with_file(values)
+                .fail(message_format)
+            });
+        for vector in vectors {
+            println!("{}", vector);
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim();
+    let digits: Vec<u8> = input
+        .strip_prefix("0x")
+        .unwrap_or(input)
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn run_proxy(
+    input: &std::path::Path,
+    listen: &str,
+    upstream: &str,
+    formats: (Format, Format),
+    encoding: Encoding,
+    framing: transcode::Framing,
+    message_format: MessageFormat,
+) {
+    let registry = std::sync::Arc::new(read_registry(input, message_format));
+    let listener = std::net::TcpListener::bind(listen).expect("failed to bind listening address");
+    for client in listener.incoming() {
+        let connect = || -> std::io::Result<_> {
+            let client = client?;
+            let server = std::net::TcpStream::connect(upstream)?;
+            Ok((client.try_clone()?, client, server.try_clone()?, server))
+        };
+        let (client_reader, client_writer, server_reader, server_writer) = match connect() {
+            Ok(streams) => streams,
+            Err(error) => {
+                eprintln!("Failed to open connection: {}", error);
+                continue;
+            }
+        };
+        let relays = vec![
+            ("request", formats.0.clone(), client_reader, server_writer),
+            ("response", formats.1.clone(), server_reader, client_writer),
+        ];
+        for (direction, format, reader, writer) in relays {
+            let registry = registry.clone();
+            spawn_connection_thread(move || {
+                relay(
+                    &registry, direction, &format, encoding, framing, reader, writer,
+                )
+            });
+        }
+    }
+}
+
+/// Stack size of connection threads. Decoding recurses once per nested container, up to
+/// `bcs::MAX_CONTAINER_DEPTH` times, which may not fit in the default stack of spawned threads.
+const CONNECTION_STACK_SIZE: usize = 64 << 20;
+
+fn spawn_connection_thread<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    if let Err(error) = std::thread::Builder::new()
+        .stack_size(CONNECTION_STACK_SIZE)
+        .spawn(f)
+    {
+        eprintln!("Failed to start connection thread: {}", error);
+    }
+}
+
+/// Copy bytes from `reader` to `writer` while printing each complete message.
+fn relay(
+    registry: &Registry,
+    direction: &str,
+    format: &Format,
+    encoding: Encoding,
+    framing: transcode::Framing,
+    mut reader: std::net::TcpStream,
+    mut writer: std::net::TcpStream,
+) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        if writer.write_all(&chunk[..n]).is_err() {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        let messages = match framing.split_messages(&mut buffer) {
+            Ok(messages) => messages,
+            Err(error) => {
+                // Keep forwarding bytes but stop trying to decode this stream.
+                eprintln!("Invalid {} framing: {}", direction, error);
+                std::io::copy(&mut reader, &mut writer).ok();
+                break;
+            }
+        };
+        for message in messages {
+            let entry = match transcode::decode(registry, format, &message, encoding) {
+                Ok(value) => format!("\"value\": {}", transcode::to_json(&value)),
+                Err(error) => format!(
+                    "\"error\": {:?}, \"bytes\": \"{}\"",
+                    error.to_string(),
+                    to_hex(&message)
+                ),
+            };
+            println!("{{\"direction\": \"{}\", {}}}", direction, entry);
+        }
+    }
+    writer.shutdown(std::net::Shutdown::Write).ok();
+}
+
 fn main() {
-    let options = Options::from_args();
+    let options = Options::from_iter_safe(std::env::args_os()).unwrap_or_else(|error| {
+        if error.use_stderr() {
+            eprintln!("{}", error.message);
+            std::process::exit(2);
+        }
+        error.exit()
+    });
+    let message_format = options.message_format;
+    if let Some(Command::Completions { shell }) = &options.command {
+        Options::clap().gen_completions_to("serdegen", *shell, &mut std::io::stdout());
+        return;
+    }
+    if let Some(Command::Describe { json }) = &options.command {
+        run_describe(*json);
+        return;
+    }
+    if let Some(Command::Lock {
+        input,
+        lockfile,
+        update,
+    }) = &options.command
+    {
+        run_lock(input, lockfile, *update, message_format);
+        return;
+    }
+    if let Some(Command::Repl { input, encodings }) = &options.command {
+        run_repl(input, encodings, message_format);
+        return;
+    }
+    if let Some(Command::DecodeDiff {
+        old,
+        new,
+        type_name,
+        hex,
+        encoding,
+    }) = &options.command
+    {
+        run_decode_diff(old, new, type_name, hex, encoding.into(), message_format);
+        return;
+    }
+    if let Some(Command::TestVectors {
+        input,
+        values,
+        encodings,
+    }) = &options.command
+    {
+        run_test_vectors(input, values, encodings, message_format);
+        return;
+    }
+    if let Some(Command::Proxy {
+        input,
+        listen,
+        upstream,
+        request_type,
+        response_type,
+        encoding,
+        framing,
+    }) = &options.command
+    {
+        let formats = (
+            Format::TypeName(request_type.clone()),
+            Format::TypeName(response_type.clone()),
+        );
+        run_proxy(
+            input,
+            listen,
+            upstream,
+            formats,
+            encoding.into(),
+            framing.into(),
+            message_format,
+        );
+        return;
+    }
     let serde_package_name_opt = options.serde_package_name.clone();
+    let runtime_source = match &options.runtime_package {
+        None => RuntimeSource::Vendored,
+        Some(_) if !matches!(options.language, Language::Dart | Language::CSharp) => {
+            Diagnostic::error(
+                "unsupported-runtime-package",
+                format!(
+                    "Runtime packages are only supported in Dart and C#, not in {:?}",
+                    options.language
+                ),
+            )
+            .fail(message_format)
+        }
+        Some(spec) => match spec.split_once(':') {
+            Some((name, version)) if !name.is_empty() && !version.is_empty() => {
+                RuntimeSource::Package {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                }
+            }
+            _ => Diagnostic::error(
+                "invalid-runtime-package",
+                format!(
+                    "Expected a runtime package `name:version`, found `{}`",
+                    spec
+                ),
+            )
+            .fail(message_format),
+        },
+    };
     let named_registry_opt = match &options.input {
         None => None,
         Some(input) => {
             let name = options.module_name.clone().unwrap_or_else(|| {
                 input
                     .file_stem()
-                    .expect("failed to deduce module name from input path")
+                    .unwrap_or_else(|| {
+                        Diagnostic::error(
+                            "invalid-module-name",
+                            "Failed to deduce the module name from the input path".to_string(),
+                        )
+                        .with_file(input)
+                        .fail(message_format)
+                    })
                     .to_string_lossy()
                     .into_owned()
             });
-            let content = std::fs::read_to_string(input).expect("input file must be readable");
-            let registry = serde_yaml::from_str::<Registry>(content.as_str()).unwrap();
+            let transforms = options
+                .transforms
+                .iter()
+                .map(TransformSpec::transform)
+                .collect::<Vec<_>>();
+            let registry = transform::apply_all(read_registry(input, message_format), &transforms)
+                .unwrap_or_else(|error| {
+                    Diagnostic::error("transform-failed", error.to_string())
+                        .with_file(input)
+                        .fail(message_format)
+                });
             Some((registry, name))
         }
     };
     let runtimes: std::collections::BTreeSet<_> = options.with_runtimes.into_iter().collect();
+    let root_formats = options
+        .root_formats
+        .as_deref()
+        .map(|path| read_root_formats(path, message_format))
+        .unwrap_or_default();
+
+    // In C++, runtimes are then amalgamated into the module header.
+    let single_header =
+        options.single_header || matches!(options.output_layout, OutputLayoutOption::SingleFile);
+    let progress_reporter = match (options.progress, message_format) {
+        (false, _) => None,
+        (true, MessageFormat::Human) => Some(report_progress as ProgressReporter),
+        (true, MessageFormat::Json) => Some(report_progress_as_json as ProgressReporter),
+    };
 
     match options.target_source_dir {
         None => {
             if let Some((registry, name)) = named_registry_opt {
-                let config = get_codegen_config(name, &runtimes, options.use_c_style_enums);
+                let config = get_codegen_config(name, &runtimes, options.use_c_style_enums)
+                    .with_variant_names(options.variant_names)
+                    .with_field_descriptors(options.field_descriptors)
+                    .with_fixtures(options.fixtures)
+                    .with_file_decoding(options.file_decoding)
+                    .with_record_log_types(options.record_log_types)
+                    .with_envelope_hooks(options.envelope_hooks)
+                    .with_checksums(options.checksums)
+                    .with_thread_safety_notes(options.thread_safety_notes)
+                    .with_root_formats(root_formats)
+                    .with_type_aliases(options.type_aliases)
+                    .with_json_integers_as_strings(options.json_integers_as_strings)
+                    .with_json_integer_policy((&options.json_integer_policy).into())
+                    .with_embedded_schema(options.embed_schema)
+                    .with_max_helper_name_length(options.max_helper_name_length)
+                    .with_inline_simple_helpers(options.inline_simple_helpers)
+                    .with_max_switch_cases(options.max_switch_cases)
+                    .with_max_file_lines(options.max_file_lines)
+                    .with_variant_naming(variant_naming(
+                        &options.variant_naming,
+                        options.variant_affix.clone(),
+                    ))
+                    .with_nested_variant_classes(options.nested_variant_classes)
+                    .with_immutable_collections(options.immutable_collections)
+                    .with_canonical_equality(options.canonical_equality)
+                    .with_container_order((&options.container_order).into())
+                    .with_seq_u8_as_bytes(options.seq_u8_as_bytes)
+                    .with_output_layout((&options.output_layout).into())
+                    .with_progress_reporter(progress_reporter)
+                    .with_external_definitions(external_definitions(&options.transforms))
+                    .with_hand_written_types(options.hand_written_types.iter().cloned().collect())
+                    .with_accessor_convention((&options.accessor_convention).into());
 
                 let stdout = std::io::stdout();
-                let mut out = stdout.lock();
+                let mut out = CountingWriter {
+                    inner: stdout.lock(),
+                    bytes: 0,
+                };
+                let start = std::time::Instant::now();
                 match options.language {
                     Language::Python3 => python3::CodeGenerator::new(&config)
                         .with_serde_package_name(serde_package_name_opt)
+                        .with_pydantic(options.pydantic)
+                        .with_embedded_runtime(options.embedded_runtime)
                         .output(&mut out, &registry)
-                        .unwrap(),
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
                     Language::Rust => rust::CodeGenerator::new(&config)
                         .output(&mut out, &registry)
-                        .unwrap(),
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
                     Language::Cpp => cpp::CodeGenerator::new(&config)
+                        .with_single_header(single_header)
+                        .with_free_functions(options.free_functions)
                         .output(&mut out, &registry)
-                        .unwrap(),
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
                     Language::Go => golang::CodeGenerator::new(&config)
                         .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::Java => panic!("Code generation in Java requires `--install-dir`"),
-                    Language::Dart => panic!("Code generation in Dart requires `--install-dir`"),
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                    Language::Java => Diagnostic::error(
+                        "missing-target-dir",
+                        "Code generation in Java requires `--target-source-dir`".to_string(),
+                    )
+                    .fail(message_format),
+                    Language::Dart => Diagnostic::error(
+                        "missing-target-dir",
+                        "Code generation in Dart requires `--target-source-dir`".to_string(),
+                    )
+                    .fail(message_format),
                     Language::TypeScript => typescript::CodeGenerator::new(&config)
+                        .with_module_system((&options.module_system).into())
+                        .with_zod(options.zod)
                         .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::CSharp => {
-                        panic!("Code generation in C# requires `--target-source-dir`")
-                    }
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                    Language::CSharp => Diagnostic::error(
+                        "missing-target-dir",
+                        "Code generation in C# requires `--target-source-dir`".to_string(),
+                    )
+                    .fail(message_format),
+                    Language::Wireshark => wireshark::CodeGenerator::new(&config)
+                        .with_root_type(options.root_type.clone())
+                        .with_framing((&options.framing).into())
+                        .with_port(options.port)
+                        .output(&mut out, &registry)
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                    Language::Kaitai => kaitai::CodeGenerator::new(&config)
+                        .with_root_type(options.root_type.clone())
+                        .output(&mut out, &registry)
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                    Language::Sql => sql::CodeGenerator::new(&config)
+                        .with_dialect((&options.sql_dialect).into())
+                        .output(&mut out, &registry)
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                    Language::Arrow => arrow::CodeGenerator::new(&config)
+                        .output(&mut out, &registry)
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                }
+                if let Some(reporter) = progress_reporter {
+                    reporter(&FileProgress {
+                        module_name: config.module_name().to_string(),
+                        path: None,
+                        bytes: out.bytes,
+                        elapsed: start.elapsed(),
+                    });
                 }
             }
         }
 
         Some(install_dir) => {
-            let installer: Box<dyn SourceInstaller<Error = Box<dyn std::error::Error>>> =
-                match options.language {
-                    Language::Python3 => {
-                        Box::new(python3::Installer::new(install_dir, serde_package_name_opt))
-                    }
-                    Language::Rust => Box::new(rust::Installer::new(install_dir)),
-                    Language::Cpp => Box::new(cpp::Installer::new(install_dir)),
-                    Language::Java => Box::new(java::Installer::new(install_dir)),
-                    Language::Go => {
-                        Box::new(golang::Installer::new(install_dir, serde_package_name_opt))
-                    }
-                    Language::Dart => Box::new(dart::Installer::new(install_dir)),
-                    Language::TypeScript => Box::new(typescript::Installer::new(install_dir)),
-                    Language::CSharp => Box::new(csharp::Installer::new(install_dir)),
-                };
+            let installer: Box<dyn SourceInstaller<Error = serde_generate::Error>> = match options
+                .language
+            {
+                Language::Python3 => Box::new(
+                    python3::Installer::new(install_dir, serde_package_name_opt)
+                        .with_pydantic(options.pydantic)
+                        .with_embedded_runtime(options.embedded_runtime),
+                ),
+                Language::Rust => Box::new(rust::Installer::new(install_dir)),
+                Language::Cpp => Box::new(
+                    cpp::Installer::new(install_dir)
+                        .with_single_header(single_header)
+                        .with_free_functions(options.free_functions),
+                ),
+                Language::Java => {
+                    Box::new(java::Installer::new(install_dir).with_jackson(options.jackson))
+                }
+                Language::Go => {
+                    Box::new(golang::Installer::new(install_dir, serde_package_name_opt))
+                }
+                Language::Dart => Box::new(
+                    dart::Installer::new(install_dir)
+                        .with_flutter(options.flutter)
+                        .with_json_serializable(options.json_serializable)
+                        .with_codec_extensions(options.codec_extensions)
+                        .with_runtime_source(runtime_source.clone())
+                        .with_web_support(options.web_support),
+                ),
+                Language::TypeScript => Box::new(
+                    typescript::Installer::new(install_dir)
+                        .with_module_system((&options.module_system).into())
+                        .with_zod(options.zod),
+                ),
+                Language::CSharp => Box::new(
+                    csharp::Installer::new(install_dir).with_runtime_source(runtime_source.clone()),
+                ),
+                Language::Wireshark => Box::new(
+                    wireshark::Installer::new(install_dir)
+                        .with_root_type(options.root_type.clone())
+                        .with_framing((&options.framing).into())
+                        .with_port(options.port),
+                ),
+                Language::Kaitai => Box::new(
+                    kaitai::Installer::new(install_dir).with_root_type(options.root_type.clone()),
+                ),
+                Language::Sql => Box::new(
+                    sql::Installer::new(install_dir).with_dialect((&options.sql_dialect).into()),
+                ),
+                Language::Arrow => Box::new(arrow::Installer::new(install_dir)),
+            };
 
             if let Some((registry, name)) = named_registry_opt {
-                let config = get_codegen_config(name, &runtimes, options.use_c_style_enums);
-                installer.install_module(&config, &registry).unwrap();
+                let config = get_codegen_config(name, &runtimes, options.use_c_style_enums)
+                    .with_variant_names(options.variant_names)
+                    .with_field_descriptors(options.field_descriptors)
+                    .with_fixtures(options.fixtures)
+                    .with_file_decoding(options.file_decoding)
+                    .with_record_log_types(options.record_log_types)
+                    .with_envelope_hooks(options.envelope_hooks)
+                    .with_checksums(options.checksums)
+                    .with_thread_safety_notes(options.thread_safety_notes)
+                    .with_root_formats(root_formats)
+                    .with_type_aliases(options.type_aliases)
+                    .with_json_integers_as_strings(options.json_integers_as_strings)
+                    .with_json_integer_policy((&options.json_integer_policy).into())
+                    .with_embedded_schema(options.embed_schema)
+                    .with_max_helper_name_length(options.max_helper_name_length)
+                    .with_inline_simple_helpers(options.inline_simple_helpers)
+                    .with_max_switch_cases(options.max_switch_cases)
+                    .with_max_file_lines(options.max_file_lines)
+                    .with_variant_naming(variant_naming(
+                        &options.variant_naming,
+                        options.variant_affix.clone(),
+                    ))
+                    .with_nested_variant_classes(options.nested_variant_classes)
+                    .with_immutable_collections(options.immutable_collections)
+                    .with_canonical_equality(options.canonical_equality)
+                    .with_container_order((&options.container_order).into())
+                    .with_seq_u8_as_bytes(options.seq_u8_as_bytes)
+                    .with_output_layout((&options.output_layout).into())
+                    .with_progress_reporter(progress_reporter)
+                    .with_external_definitions(external_definitions(&options.transforms))
+                    .with_hand_written_types(options.hand_written_types.iter().cloned().collect())
+                    .with_accessor_convention((&options.accessor_convention).into());
+                installer
+                    .install_module(&config, &registry)
+                    .unwrap_or_else(|error| generation_failed(error, message_format));
             }
 
+            if options.with_runtime_tests {
+                let config = get_codegen_config(String::new(), &runtimes, false);
+                installer
+                    .install_runtime_tests(&config)
+                    .unwrap_or_else(|error| generation_failed(error, message_format));
+            }
+            if options.with_corpus_runner {
+                let config = get_codegen_config("serde_corpus".to_string(), &runtimes, false);
+                installer
+                    .install_corpus_runner(&config)
+                    .unwrap_or_else(|error| generation_failed(error, message_format));
+            }
             for runtime in runtimes {
                 match runtime {
-                    Runtime::Serde => installer.install_serde_runtime().unwrap(),
-                    Runtime::Bincode => installer.install_bincode_runtime().unwrap(),
-                    Runtime::Bcs => installer.install_bcs_runtime().unwrap(),
+                    Runtime::Serde => installer
+                        .install_serde_runtime()
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                    Runtime::Bincode => installer
+                        .install_encoding_runtime(Encoding::Bincode)
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
+                    Runtime::Bcs => installer
+                        .install_encoding_runtime(Encoding::Bcs)
+                        .unwrap_or_else(|error| generation_failed(error, message_format)),
                 }
             }
         }
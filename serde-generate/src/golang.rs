@@ -2,16 +2,16 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    common,
+    common::{self, ConfigOption, VariantTag},
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    CodeGeneratorConfig, Encoding, VariantNaming,
 };
 use heck::CamelCase;
 use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
 use std::{
     collections::{BTreeMap, HashMap},
     io::{Result, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 /// Main configuration object for code-generation in Go.
@@ -39,9 +39,6 @@ struct GoEmitter<'a, T> {
 impl<'a> CodeGenerator<'a> {
     /// Create a Go code generator for the given config.
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Go does not support generating c-style enums");
-        }
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             let package_name = {
@@ -73,7 +70,20 @@ impl<'a> CodeGenerator<'a> {
     }
 
     /// Output class definitions for `registry`.
-    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        if self.config.c_style_enums {
+            return Err(crate::Error::InvalidConfig(
+                "Go does not support generating c-style enums".to_string(),
+            ));
+        }
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("Go", registry)?;
+        common::check_options(
+            "Go",
+            self.config,
+            &[ConfigOption::RootFormats, ConfigOption::TypeAliases],
+        )?;
         let current_namespace = self
             .config
             .module_name
@@ -90,14 +100,28 @@ impl<'a> CodeGenerator<'a> {
 
         emitter.output_preamble(registry)?;
 
-        for (name, format) in registry {
+        if self.config.type_aliases {
+            emitter.output_type_aliases(registry)?;
+        }
+
+        for (name, format) in common::ordered_containers(self.config, registry)? {
+            common::check_cancellation(self.config)?;
             emitter.output_container(name, format)?;
         }
 
         if self.config.serialization {
+            for (name, format) in &self.config.root_formats {
+                for encoding in &self.config.encodings {
+                    emitter.output_root_format_for_encoding(name, format, *encoding)?;
+                }
+            }
             emitter.output_trait_helpers(registry)?;
         }
 
+        if self.config.embedded_schema && !registry.is_empty() {
+            emitter.output_embedded_schema(registry)?;
+        }
+
         Ok(())
     }
 }
@@ -118,6 +142,12 @@ where
         }
         writeln!(self.out, "import (")?;
         self.out.indent();
+        if self.generator.config.embedded_schema {
+            writeln!(
+                self.out,
+                "\"bytes\"\n\"compress/zlib\"\n\"encoding/base64\"\n\"io/ioutil\""
+            )?;
+        }
         if self.generator.config.serialization
             && (Self::has_enum(registry) || !self.generator.config.encodings.is_empty())
         {
@@ -144,6 +174,40 @@ where
         Ok(())
     }
 
+    fn output_embedded_schema(&mut self, registry: &Registry) -> crate::Result<()> {
+        let lines = common::compressed_schema(registry)?;
+        writeln!(
+            self.out,
+            "var compressedSchema = {}",
+            lines
+                .iter()
+                .map(|line| format!("\"{}\"", line))
+                .collect::<Vec<_>>()
+                .join(" +\n\t")
+        )?;
+        writeln!(
+            self.out,
+            r#"
+// Schema returns the Serde formats of this package, encoded in YAML.
+func Schema() string {{
+	compressed, err := base64.StdEncoding.DecodeString(compressedSchema)
+	if err != nil {{
+		panic(err)
+	}}
+	reader, err := zlib.NewReader(bytes.NewReader(compressed))
+	if err != nil {{
+		panic(err)
+	}}
+	content, err := ioutil.ReadAll(reader)
+	if err != nil {{
+		panic(err)
+	}}
+	return string(content)
+}}"#
+        )?;
+        Ok(())
+    }
+
     fn has_int128(registry: &Registry) -> bool {
         for format in registry.values() {
             if format
@@ -164,7 +228,8 @@ where
 
     fn has_enum(registry: &Registry) -> bool {
         for format in registry.values() {
-            if let ContainerFormat::Enum(_) = format {
+            if let ContainerFormat::Enum(_) | ContainerFormat::InternallyTaggedEnum { .. } = format
+            {
                 return true;
             }
         }
@@ -180,11 +245,22 @@ where
             .unwrap_or_else(|| name.to_string())
     }
 
+    fn quote_variant_class(&self, base: &str, variant: &str) -> String {
+        let default = VariantNaming::Nested;
+        self.generator
+            .config
+            .variant_class_name(base, variant, &default)
+    }
+
     fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
         let mut path = self.current_namespace.clone();
         path.push(name.to_string());
-        if let Some(doc) = self.generator.config.comments.get(&path) {
-            let text = textwrap::indent(doc, "// ").replace("\n\n", "\n//\n");
+        if let Some(doc) = self
+            .generator
+            .config
+            .comment_with_deprecation(&path, "Deprecated: ")
+        {
+            let text = textwrap::indent(&doc, "// ").replace("\n\n", "\n//\n");
             write!(self.out, "{}", text)?;
         }
         Ok(())
@@ -222,7 +298,7 @@ where
             Bytes => "[]byte".into(),
 
             Option(format) => format!("*{}", self.quote_type(format)),
-            Seq(format) => format!("[]{}", self.quote_type(format)),
+            Seq(format) | Set(format) => format!("[]{}", self.quote_type(format)),
             Map { key, value } => {
                 format!("map[{}]{}", self.quote_type(key), self.quote_type(value))
             }
@@ -236,11 +312,28 @@ where
                     .join("; ")
             ),
             TupleArray { content, size } => format!("[{}]{}", size, self.quote_type(content)),
+            Custom { name, layout } => {
+                if self.generator.config.type_aliases {
+                    name.to_string()
+                } else {
+                    self.quote_type(layout)
+                }
+            }
+            Decimal { mantissa, .. } => self.quote_type(mantissa),
 
-            Variable(_) => panic!("unexpected value"),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
         }
     }
 
+    fn output_type_aliases(&mut self, registry: &Registry) -> Result<()> {
+        for (name, layout) in common::custom_formats(registry) {
+            writeln!(self.out, "type {} = {}", name, self.quote_type(&layout))?;
+        }
+        Ok(())
+    }
+
     fn enter_class(&mut self, name: &str) {
         self.out.indent();
         self.current_namespace.push(name.to_string());
@@ -251,18 +344,9 @@ where
         self.current_namespace.pop();
     }
 
-    fn output_trait_helpers(&mut self, registry: &Registry) -> Result<()> {
-        let mut subtypes = BTreeMap::new();
-        for format in registry.values() {
-            format
-                .visit(&mut |f| {
-                    if Self::needs_helper(f) {
-                        subtypes.insert(common::mangle_type(f), f.clone());
-                    }
-                    Ok(())
-                })
-                .unwrap();
-        }
+    fn output_trait_helpers(&mut self, registry: &Registry) -> crate::Result<()> {
+        let subtypes =
+            common::helper_formats(self.generator.config, registry, true, Self::needs_helper)?;
         for (mangled_name, subtype) in &subtypes {
             self.output_serialization_helper(mangled_name, subtype)?;
             self.output_deserialization_helper(mangled_name, subtype)?;
@@ -274,7 +358,7 @@ where
         use Format::*;
         matches!(
             format,
-            Option(_) | Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. }
+            Option(_) | Seq(_) | Set(_) | Map { .. } | Tuple(_) | TupleArray { .. }
         )
     }
 
@@ -299,9 +383,11 @@ where
             Char => format!("serializer.SerializeChar({})", value),
             Str => format!("serializer.SerializeStr({})", value),
             Bytes => format!("serializer.SerializeBytes({})", value),
+            Custom { layout, .. } => return self.quote_serialize_value(value, layout),
+            Decimal { mantissa, .. } => return self.quote_serialize_value(value, mantissa),
             _ => format!(
                 "serialize_{}({}, serializer)",
-                common::mangle_type(format),
+                common::helper_name(self.generator.config, format),
                 value
             ),
         };
@@ -332,7 +418,12 @@ where
             Char => "deserializer.DeserializeChar()".to_string(),
             Str => "deserializer.DeserializeStr()".to_string(),
             Bytes => "deserializer.DeserializeBytes()".to_string(),
-            _ => format!("deserialize_{}(deserializer)", common::mangle_type(format)),
+            Custom { layout, .. } => return self.quote_deserialize(layout, dest, fail),
+            Decimal { mantissa, .. } => return self.quote_deserialize(mantissa, dest, fail),
+            _ => format!(
+                "deserialize_{}(deserializer)",
+                common::helper_name(self.generator.config, format)
+            ),
         };
         format!(
             "if val, err := {}; err == nil {{ {} = val }} else {{ return {}, err }}",
@@ -366,7 +457,7 @@ if value != nil {{
                 )?;
             }
 
-            Seq(format) => {
+            Seq(format) | Set(format) => {
                 write!(
                     self.out,
                     r#"
@@ -456,7 +547,7 @@ if tag {{
                 )?;
             }
 
-            Seq(format) => {
+            Seq(format) | Set(format) => {
                 write!(
                     self.out,
                     r#"
@@ -546,7 +637,7 @@ return obj, nil
     fn output_variant(
         &mut self,
         base: &str,
-        index: u32,
+        tag: VariantTag,
         name: &str,
         variant: &VariantFormat,
     ) -> Result<()> {
@@ -560,15 +651,14 @@ return obj, nil
                 //
                 // Similarly, option types are compiled as pointers but `type Foo *Bar` would prevent `Foo` from being a
                 // valid pointer receiver.
-                Format::TypeName(_) | Format::Option(_) => vec![Named {
-                    name: "Value".to_string(),
-                    value: format.as_ref().clone(),
-                }],
+                Format::TypeName(_) | Format::Option(_) => {
+                    vec![Named::new("Value", format.as_ref().clone())]
+                }
                 // Other cases are fine.
                 _ => {
                     self.output_struct_or_variant_new_type_container(
                         Some(base),
-                        Some(index),
+                        Some(tag),
                         name,
                         format,
                     )?;
@@ -578,33 +668,24 @@ return obj, nil
             Tuple(formats) => formats
                 .iter()
                 .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("Field{}", i),
-                    value: f.clone(),
-                })
+                .map(|(i, f)| Named::new(format!("Field{}", i), f.clone()))
                 .collect(),
-            Struct(fields) => fields
-                .iter()
-                .map(|f| Named {
-                    name: f.name.to_camel_case(),
-                    value: f.value.clone(),
-                })
-                .collect(),
-            Variable(_) => panic!("incorrect value"),
+            Struct(fields) => fields.clone(),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         };
-        self.output_struct_or_variant_container(Some(base), Some(index), name, &fields)
+        self.output_struct_or_variant_container(Some(base), Some(tag), name, &fields)
     }
 
     fn output_struct_or_variant_container(
         &mut self,
         variant_base: Option<&str>,
-        variant_index: Option<u32>,
+        variant_tag: Option<VariantTag>,
         name: &str,
         fields: &[Named<Format>],
     ) -> Result<()> {
         let full_name = match variant_base {
             None => name.to_string(),
-            Some(base) => format!("{}__{}", base, name),
+            Some(base) => self.quote_variant_class(base, name),
         };
         // Struct
         writeln!(self.out)?;
@@ -612,8 +693,14 @@ return obj, nil
         writeln!(self.out, "type {} struct {{", full_name)?;
         self.enter_class(name);
         for field in fields {
+            // Comments and deprecations of fields refer to their Serde names.
             self.output_comment(&field.name)?;
-            writeln!(self.out, "{} {}", field.name, self.quote_type(&field.value))?;
+            writeln!(
+                self.out,
+                "{} {}",
+                field.name.to_camel_case(),
+                self.quote_type(&field.value)
+            )?;
         }
         self.leave_class();
         writeln!(self.out, "}}")?;
@@ -623,6 +710,12 @@ return obj, nil
             writeln!(self.out, "\nfunc (*{}) is{}() {{}}", full_name, base)?;
         }
 
+        self.output_flags(name, &full_name, fields)?;
+        let fields = fields
+            .iter()
+            .map(|f| Named::new(f.name.to_camel_case(), f.value.clone()))
+            .collect::<Vec<_>>();
+
         // Serialize
         if self.generator.config.serialization {
             writeln!(
@@ -635,10 +728,16 @@ return obj, nil
                 self.out,
                 "if err := serializer.IncreaseContainerDepth(); err != nil {{ return err }}"
             )?;
-            if let Some(index) = variant_index {
-                writeln!(self.out, "serializer.SerializeVariantIndex({})", index)?;
+            match variant_tag {
+                Some(VariantTag::Index(index)) => {
+                    writeln!(self.out, "serializer.SerializeVariantIndex({})", index)?
+                }
+                Some(VariantTag::Name { name, .. }) => {
+                    writeln!(self.out, "serializer.SerializeStr({:?})", name)?
+                }
+                None => (),
             }
-            for field in fields {
+            for field in &fields {
                 writeln!(
                     self.out,
                     "{}",
@@ -672,7 +771,7 @@ return obj, nil
                 self.out,
                 "if err := deserializer.IncreaseContainerDepth(); err != nil {{ return obj, err }}"
             )?;
-            for field in fields {
+            for field in &fields {
                 writeln!(
                     self.out,
                     "{}",
@@ -695,18 +794,66 @@ return obj, nil
         Ok(())
     }
 
+    fn output_flags(
+        &mut self,
+        name: &str,
+        full_name: &str,
+        fields: &[Named<Format>],
+    ) -> Result<()> {
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        for (field, flags) in common::flag_sets(self.generator.config, &path, fields) {
+            let field_type = self.quote_type(&field.value);
+            writeln!(self.out, "\nconst (")?;
+            self.out.indent();
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "{}{}{} {} = {:#x}",
+                    full_name,
+                    field.name.to_camel_case(),
+                    flag.to_camel_case(),
+                    field_type,
+                    value
+                )?;
+            }
+            self.out.unindent();
+            writeln!(
+                self.out,
+                r#")
+
+func (obj *{0}) Has{1}(mask {2}) bool {{
+	return obj.{3}&mask == mask
+}}
+
+func (obj *{0}) Set{1}(mask {2}) {{
+	obj.{3} |= mask
+}}
+
+func (obj *{0}) Clear{1}(mask {2}) {{
+	obj.{3} &^= mask
+}}"#,
+                full_name,
+                field.name.to_camel_case(),
+                field_type,
+                field.name
+            )?;
+        }
+        Ok(())
+    }
+
     // Same as output_struct_or_variant_container but we map the container with a single anonymous field
     // to a new type in Go.
     fn output_struct_or_variant_new_type_container(
         &mut self,
         variant_base: Option<&str>,
-        variant_index: Option<u32>,
+        variant_tag: Option<VariantTag>,
         name: &str,
         format: &Format,
     ) -> Result<()> {
         let full_name = match variant_base {
             None => name.to_string(),
-            Some(base) => format!("{}__{}", base, name),
+            Some(base) => self.quote_variant_class(base, name),
         };
         // Struct
         writeln!(self.out)?;
@@ -730,8 +877,14 @@ return obj, nil
                 self.out,
                 "if err := serializer.IncreaseContainerDepth(); err != nil {{ return err }}"
             )?;
-            if let Some(index) = variant_index {
-                writeln!(self.out, "serializer.SerializeVariantIndex({})", index)?;
+            match variant_tag {
+                Some(VariantTag::Index(index)) => {
+                    writeln!(self.out, "serializer.SerializeVariantIndex({})", index)?
+                }
+                Some(VariantTag::Name { name, .. }) => {
+                    writeln!(self.out, "serializer.SerializeStr({:?})", name)?
+                }
+                None => (),
             }
             writeln!(
                 self.out,
@@ -800,12 +953,31 @@ func (obj *{0}) {2}Serialize() ([]byte, error) {{
 	}}
 	serializer := {1}.NewSerializer();
 	if err := obj.Serialize(serializer); err != nil {{ return nil, err }}
-	return serializer.GetBytes(), nil
+	return {3}
 }}"#,
             name,
             encoding.name(),
-            encoding.name().to_camel_case()
-        )
+            encoding.name().to_camel_case(),
+            if self.generator.config.envelope_hooks {
+                "serde.WrapEnvelope(serializer.GetBytes())"
+            } else {
+                "serializer.GetBytes(), nil"
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+func (obj *{0}) {1}SerializeChecked() ([]byte, error) {{
+	bytes, err := obj.{1}Serialize()
+	if err != nil {{ return nil, err }}
+	return serde.AppendChecksum(bytes), nil
+}}"#,
+                name,
+                encoding.name().to_camel_case()
+            )?;
+        }
+        Ok(())
     }
 
     fn output_struct_deserialize_for_encoding(
@@ -820,7 +992,7 @@ func {2}Deserialize{0}(input []byte) ({0}, error) {{
 	if input == nil {{
 		var obj {0}
 		return obj, fmt.Errorf("Cannot deserialize null array")
-	}}
+	}}{3}
 	deserializer := {1}.NewDeserializer(input);
 	obj, err := Deserialize{0}(deserializer)
 	if err == nil && deserializer.GetBufferOffset() < uint64(len(input)) {{
@@ -831,14 +1003,128 @@ func {2}Deserialize{0}(input []byte) ({0}, error) {{
             name,
             encoding.name(),
             encoding.name().to_camel_case(),
+            if self.generator.config.envelope_hooks {
+                format!(
+                    "\n\tinput, err := serde.UnwrapEnvelope(input)\n\tif err != nil {{\n\t\tvar obj {}\n\t\treturn obj, err\n\t}}",
+                    name
+                )
+            } else {
+                String::new()
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+func {1}DeserializeChecked{0}(input []byte) ({0}, error) {{
+	payload, err := serde.VerifyChecksum(input)
+	if err != nil {{
+		var obj {0}
+		return obj, err
+	}}
+	return {1}Deserialize{0}(payload)
+}}"#,
+                name,
+                encoding.name().to_camel_case(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn output_root_format_for_encoding(
+        &mut self,
+        name: &str,
+        format: &Format,
+        encoding: Encoding,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"func {1}Serialize{0}(value {2}) ([]byte, error) {{
+	serializer := {3}.NewSerializer();
+	{4}
+	return {5}
+}}
+
+func {1}Deserialize{0}(input []byte) ({2}, error) {{
+	var obj {2}
+	if input == nil {{
+		return obj, fmt.Errorf("Cannot deserialize null array")
+	}}{6}
+	deserializer := {3}.NewDeserializer(input);
+	{7}
+	if deserializer.GetBufferOffset() < uint64(len(input)) {{
+		return obj, fmt.Errorf("Some input bytes were not read")
+	}}
+	return obj, nil
+}}
+"#,
+            name,
+            encoding.name().to_camel_case(),
+            self.quote_type(format),
+            encoding.name(),
+            self.quote_serialize_value("value", format)
+                .replace("return err", "return nil, err"),
+            if self.generator.config.envelope_hooks {
+                "serde.WrapEnvelope(serializer.GetBytes())"
+            } else {
+                "serializer.GetBytes(), nil"
+            },
+            if self.generator.config.envelope_hooks {
+                "\n\tinput, err := serde.UnwrapEnvelope(input)\n\tif err != nil {\n\t\treturn obj, err\n\t}"
+            } else {
+                ""
+            },
+            self.quote_deserialize(format, "obj", "obj"),
         )
     }
 
+    /// Open a switch on the variant `key` and output the cases loading the given variants. The
+    /// caller outputs the default case and closes the switch.
+    fn output_variant_switch(
+        &mut self,
+        name: &str,
+        key: &str,
+        tag: Option<&str>,
+        serde_names: &BTreeMap<u32, &str>,
+        variants: &[(&u32, &Named<VariantFormat>)],
+    ) -> Result<()> {
+        writeln!(self.out, "switch {} {{", key)?;
+        for (index, variant) in variants {
+            writeln!(
+                self.out,
+                r#"case {}:
+	if val, err := load_{}(deserializer); err == nil {{
+		return &val, nil
+	}} else {{
+		return nil, err
+	}}
+"#,
+                match tag {
+                    Some(_) => format!("{:?}", serde_names[index]),
+                    None => index.to_string(),
+                },
+                self.quote_variant_class(name, &variant.name)
+            )?;
+        }
+        Ok(())
+    }
+
     fn output_enum_container(
         &mut self,
         name: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
+        // Variants are declared under their camel-cased names, while internally tagged enums
+        // identify variants by their Serde names.
+        let serde_names: BTreeMap<u32, &str> = variants
+            .iter()
+            .map(|(i, v)| (*i, v.name.as_str()))
+            .collect();
+        let variants: BTreeMap<_, _> = variants
+            .iter()
+            .map(|(i, f)| (*i, Named::new(f.name.to_camel_case(), f.value.clone())))
+            .collect();
         writeln!(self.out)?;
         self.output_comment(name)?;
         writeln!(self.out, "type {} interface {{", name)?;
@@ -853,6 +1139,13 @@ func {2}Deserialize{0}(input []byte) ({0}, error) {{
                     "{}Serialize() ([]byte, error)",
                     encoding.name().to_camel_case()
                 )?;
+                if self.generator.config.checksums {
+                    writeln!(
+                        self.out,
+                        "{}SerializeChecked() ([]byte, error)",
+                        encoding.name().to_camel_case()
+                    )?;
+                }
             }
         }
         self.out.unindent();
@@ -865,44 +1158,81 @@ func {2}Deserialize{0}(input []byte) ({0}, error) {{
                 name
             )?;
             self.out.indent();
-            writeln!(
-                self.out,
-                r#"
-index, err := deserializer.DeserializeVariantIndex()
+            let (key_type, key, kind, verb) = match tag {
+                Some(_) => ("string", "tag", "name", "%s"),
+                None => ("uint32", "index", "index", "%d"),
+            };
+            if tag.is_some() {
+                writeln!(
+                    self.out,
+                    r#"
+tag, err := deserializer.DeserializeStr()
 if err != nil {{ return nil, err }}
-
-switch index {{"#,
-            )?;
-            for (index, variant) in variants {
+"#,
+                )?;
+            } else {
                 writeln!(
                     self.out,
-                    r#"case {}:
-	if val, err := load_{}__{}(deserializer); err == nil {{
-		return &val, nil
-	}} else {{
-		return nil, err
-	}}
+                    r#"
+index, err := deserializer.DeserializeVariantIndex()
+if err != nil {{ return nil, err }}
 "#,
-                    index, name, variant.name
                 )?;
             }
-            writeln!(
-                self.out,
-                "default:
-	return nil, fmt.Errorf(\"Unknown variant index for {}: %d\", index)",
-                name,
-            )?;
-            writeln!(self.out, "}}")?;
+            let chunks = common::variant_chunks(self.generator.config, &variants);
+            if chunks.len() == 1 {
+                self.output_variant_switch(name, key, tag, &serde_names, &chunks[0])?;
+                writeln!(
+                    self.out,
+                    "default:
+	return nil, fmt.Errorf(\"Unknown variant {} for {}: {}\", {})",
+                    kind, name, verb, key,
+                )?;
+                writeln!(self.out, "}}")?;
+            } else {
+                for index in 0..chunks.len() {
+                    writeln!(
+                        self.out,
+                        "if val, err := deserialize{}Variants{}({}, deserializer); val != nil || err != nil {{
+	return val, err
+}}",
+                        name, index, key,
+                    )?;
+                }
+                writeln!(
+                    self.out,
+                    "return nil, fmt.Errorf(\"Unknown variant {} for {}: {}\", {})",
+                    kind, name, verb, key,
+                )?;
+            }
             self.out.unindent();
             writeln!(self.out, "}}")?;
+            if chunks.len() > 1 {
+                for (index, chunk) in chunks.iter().enumerate() {
+                    writeln!(
+                        self.out,
+                        "\nfunc deserialize{0}Variants{1}({2} {3}, deserializer serde.Deserializer) ({0}, error) {{",
+                        name, index, key, key_type,
+                    )?;
+                    self.out.indent();
+                    self.output_variant_switch(name, key, tag, &serde_names, chunk)?;
+                    writeln!(self.out, "default:\n\treturn nil, nil\n}}")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                }
+            }
 
             for encoding in &self.generator.config.encodings {
                 self.output_struct_deserialize_for_encoding(name, *encoding)?;
             }
         }
+        if self.generator.config.variant_names {
+            self.output_variant_names(name, &variants)?;
+        }
 
-        for (index, variant) in variants {
-            self.output_variant(name, *index, &variant.name, &variant.value)?;
+        for (index, variant) in &variants {
+            let variant_tag = VariantTag::new(tag, *index, serde_names[index]);
+            self.output_variant(name, variant_tag, &variant.name, &variant.value)?;
         }
         self.current_namespace.pop();
         // Custom code
@@ -910,16 +1240,50 @@ switch index {{"#,
         Ok(())
     }
 
+    fn output_variant_names(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+// {0}VariantNames lists the names of the variants of {0}, indexed by variant index.
+var {0}VariantNames = []string{{{1}}}
+
+func {0}VariantName(index uint32) (string, bool) {{
+	if int(index) < len({0}VariantNames) {{
+		return {0}VariantNames[index], true
+	}}
+	return "", false
+}}
+
+func {0}VariantIndex(name string) (uint32, bool) {{
+	for index, value := range {0}VariantNames {{
+		if value == name {{
+			return uint32(index), true
+		}}
+	}}
+	return 0, false
+}}"#,
+            name,
+            variants
+                .values()
+                .map(|v| format!("{:?}", v.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
         let fields = match format {
             UnitStruct => Vec::new(),
             NewTypeStruct(format) => match format.as_ref() {
                 // See comment in `output_variant`.
-                Format::TypeName(_) | Format::Option(_) => vec![Named {
-                    name: "Value".to_string(),
-                    value: format.as_ref().clone(),
-                }],
+                Format::TypeName(_) | Format::Option(_) => {
+                    vec![Named::new("Value", format.as_ref().clone())]
+                }
                 _ => {
                     self.output_struct_or_variant_new_type_container(None, None, name, format)?;
                     return Ok(());
@@ -928,32 +1292,15 @@ switch index {{"#,
             TupleStruct(formats) => formats
                 .iter()
                 .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("Field{}", i),
-                    value: f.clone(),
-                })
-                .collect(),
-            Struct(fields) => fields
-                .iter()
-                .map(|f| Named {
-                    name: f.name.to_camel_case(),
-                    value: f.value.clone(),
-                })
+                .map(|(i, f)| Named::new(format!("Field{}", i), f.clone()))
                 .collect(),
+            Struct(fields) => fields.clone(),
             Enum(variants) => {
-                let variants = variants
-                    .iter()
-                    .map(|(i, f)| {
-                        (
-                            *i,
-                            Named {
-                                name: f.name.to_camel_case(),
-                                value: f.value.clone(),
-                            },
-                        )
-                    })
-                    .collect();
-                self.output_enum_container(name, &variants)?;
+                self.output_enum_container(name, None, variants)?;
+                return Ok(());
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                self.output_enum_container(name, Some(tag), variants)?;
                 return Ok(());
             }
         };
@@ -962,6 +1309,7 @@ switch index {{"#,
 }
 
 /// Installer for generated source files in Go.
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
     serde_module_path: Option<String>,
@@ -988,7 +1336,18 @@ impl Installer {
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
 
     fn install_module(
         &self,
@@ -998,7 +1357,7 @@ impl crate::SourceInstaller for Installer {
         let dir_path = self.install_dir.join(&config.module_name);
         std::fs::create_dir_all(&dir_path)?;
         let source_path = dir_path.join("lib.go");
-        let mut file = std::fs::File::create(source_path)?;
+        let mut file = common::create_source_file(config, source_path)?;
 
         let mut generator = CodeGenerator::new(config);
         if let Some(path) = &self.serde_module_path {
@@ -1013,13 +1372,8 @@ impl crate::SourceInstaller for Installer {
         Ok(())
     }
 
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.runtime_installation_message("bincode");
-        Ok(())
-    }
-
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.runtime_installation_message("bcs");
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
+        self.runtime_installation_message(encoding.name());
         Ok(())
     }
 }
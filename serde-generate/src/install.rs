@@ -0,0 +1,272 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::SourceInstaller;
+use std::{
+    collections::BTreeSet,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Name of the manifest listing the files written by `SourceInstaller::install`, relative to
+/// the installation directory.
+pub const MANIFEST_FILE_NAME: &str = ".serdegen-manifest";
+
+/// What to do with existing files that an installation would change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// Replace existing files.
+    Overwrite,
+    /// Leave existing files untouched.
+    KeepExisting,
+    /// Fail before writing anything.
+    Fail,
+}
+
+/// Change made to a file of the installation directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileAction {
+    /// The file does not exist yet.
+    Create,
+    /// The file exists with a different content.
+    Update,
+    /// The file exists with the same content. It is not written again, so that incremental
+    /// build systems do not see it as modified.
+    Unchanged,
+    /// The file exists with a different content but the overwrite policy keeps it.
+    Keep,
+    /// The file was written by a previous installation and is no longer generated.
+    Remove,
+}
+
+/// A file of the installation directory, as reported by `SourceInstaller::plan` and
+/// `SourceInstaller::install`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedFile {
+    /// Path of the file, relative to the installation directory.
+    pub path: PathBuf,
+    /// Change made to the file.
+    pub action: FileAction,
+}
+
+/// Read the files listed by the manifest of the installation directory `install_dir`, if any.
+pub fn read_manifest(install_dir: &Path) -> Result<BTreeSet<PathBuf>> {
+    match std::fs::read_to_string(install_dir.join(MANIFEST_FILE_NAME)) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect()),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(BTreeSet::new()),
+        Err(error) => Err(error),
+    }
+}
+
+fn write_manifest(install_dir: &Path, files: &BTreeSet<PathBuf>) -> Result<()> {
+    let mut content = String::new();
+    for file in files {
+        content += &manifest_entry(file);
+        content.push('\n');
+    }
+    std::fs::write(install_dir.join(MANIFEST_FILE_NAME), content)
+}
+
+/// Paths are recorded with forward slashes so that manifests can be shared across platforms.
+fn manifest_entry(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A temporary directory where installations are staged, removed when dropped.
+struct Staging(PathBuf);
+
+impl Staging {
+    fn new() -> Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "serdegen-staging-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path)?;
+        Ok(Staging(path))
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).unwrap_or(());
+    }
+}
+
+fn unsupported() -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        "this installer does not support staged installations",
+    )
+}
+
+/// Run `steps` with a copy of `installer` writing into a staging directory, then compare the
+/// staged files with the installation directory.
+fn stage<I, F>(installer: &I, steps: F) -> std::result::Result<Stage, I::Error>
+where
+    I: SourceInstaller,
+    I::Error: From<Error>,
+    F: FnOnce(&I) -> std::result::Result<(), I::Error>,
+{
+    let install_dir = installer.install_dir().ok_or_else(unsupported)?;
+    let staging = Staging::new()?;
+    let staged = installer.relocated(&staging.0).ok_or_else(unsupported)?;
+    steps(&staged)?;
+
+    let mut files = Vec::new();
+    let mut generated = BTreeSet::new();
+    for path in list_files(&staging.0)? {
+        let content = std::fs::read(staging.0.join(&path))?;
+        let action = match std::fs::read(install_dir.join(&path)) {
+            Ok(existing) if existing == content => FileAction::Unchanged,
+            Ok(_) => FileAction::Update,
+            Err(error) if error.kind() == ErrorKind::NotFound => FileAction::Create,
+            Err(error) => return Err(error.into()),
+        };
+        generated.insert(path.clone());
+        files.push(PlannedFile { path, action });
+    }
+    for path in read_manifest(install_dir)? {
+        if !generated.contains(&path) && install_dir.join(&path).is_file() {
+            files.push(PlannedFile {
+                path,
+                action: FileAction::Remove,
+            });
+        }
+    }
+    Ok(Stage {
+        install_dir: install_dir.to_path_buf(),
+        staging,
+        files,
+    })
+}
+
+struct Stage {
+    install_dir: PathBuf,
+    staging: Staging,
+    files: Vec<PlannedFile>,
+}
+
+/// Relative paths of the files in `dir`, in a deterministic order.
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative_dir) = pending.pop() {
+        let mut entries = std::fs::read_dir(dir.join(&relative_dir))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            let relative_path = relative_dir.join(entry.file_name().expect("entries have names"));
+            if entry.is_dir() {
+                pending.push(relative_path);
+            } else {
+                files.push(relative_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Remove `path` from `install_dir`, then its parent directories if they are left empty.
+fn remove_file(install_dir: &Path, path: &Path) -> Result<()> {
+    match std::fs::remove_file(install_dir.join(path)) {
+        Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+        _ => (),
+    }
+    for parent in path.ancestors().skip(1) {
+        if parent.as_os_str().is_empty() || std::fs::remove_dir(install_dir.join(parent)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn plan<I, F>(installer: &I, steps: F) -> std::result::Result<Vec<PlannedFile>, I::Error>
+where
+    I: SourceInstaller,
+    I::Error: From<Error>,
+    F: FnOnce(&I) -> std::result::Result<(), I::Error>,
+{
+    Ok(stage(installer, steps)?.files)
+}
+
+pub(crate) fn install<I, F>(
+    installer: &I,
+    steps: F,
+    policy: OverwritePolicy,
+) -> std::result::Result<Vec<PlannedFile>, I::Error>
+where
+    I: SourceInstaller,
+    I::Error: From<Error>,
+    F: FnOnce(&I) -> std::result::Result<(), I::Error>,
+{
+    let Stage {
+        install_dir,
+        staging,
+        mut files,
+    } = stage(installer, steps)?;
+    if policy == OverwritePolicy::Fail {
+        if let Some(file) = files.iter().find(|file| file.action == FileAction::Update) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!(
+                    "installation would overwrite {}",
+                    install_dir.join(&file.path).display()
+                ),
+            )
+            .into());
+        }
+    }
+    let mut manifest = BTreeSet::new();
+    for file in &mut files {
+        match file.action {
+            FileAction::Update if policy == OverwritePolicy::KeepExisting => {
+                file.action = FileAction::Keep;
+            }
+            FileAction::Create | FileAction::Update => {
+                let target = install_dir.join(&file.path);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::copy(staging.0.join(&file.path), target)?;
+            }
+            FileAction::Remove => {
+                remove_file(&install_dir, &file.path)?;
+                continue;
+            }
+            FileAction::Unchanged => (),
+            // Files kept by the policy are not listed in the manifest, so that they are never
+            // removed by `uninstall`.
+            FileAction::Keep => continue,
+        }
+        manifest.insert(file.path.clone());
+    }
+    std::fs::create_dir_all(&install_dir)?;
+    write_manifest(&install_dir, &manifest)?;
+    Ok(files)
+}
+
+pub(crate) fn uninstall<I>(installer: &I) -> std::result::Result<Vec<PathBuf>, I::Error>
+where
+    I: SourceInstaller + ?Sized,
+    I::Error: From<Error>,
+{
+    let install_dir = installer.install_dir().ok_or_else(unsupported)?;
+    let files = read_manifest(install_dir)?;
+    for path in &files {
+        remove_file(install_dir, path)?;
+    }
+    remove_file(install_dir, Path::new(MANIFEST_FILE_NAME))?;
+    Ok(files.into_iter().collect())
+}
@@ -2,17 +2,17 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    common,
+    common::{self, AddressFormat, ConfigOption, VariantTag},
     indent::{IndentConfig, IndentedWriter},
     CodeGeneratorConfig, Encoding,
 };
-use heck::CamelCase;
+use heck::{CamelCase, ShoutySnakeCase};
 use include_dir::include_dir as include_directory;
-use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::{
     collections::{BTreeMap, HashMap},
     io::{Result, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 /// Main configuration object for code-generation in Java.
@@ -22,6 +22,25 @@ pub struct CodeGenerator<'a> {
     /// Mapping from external type names to fully-qualified class names (e.g. "MyClass" -> "com.my_org.my_package.MyClass").
     /// Derived from `config.external_definitions`.
     external_qualified_names: HashMap<String, String>,
+    /// Whether to add Jackson annotations matching the JSON representation of Serde.
+    jackson: bool,
+}
+
+/// Note on thread safety added to the documentation of generated classes.
+const THREAD_SAFETY_NOTE: &str =
+    "Thread safety: instances are immutable and may be shared between threads, as long as
+the collections they hold are not modified. Serialization and deserialization methods are
+reentrant: each call uses its own serializer or deserializer.";
+
+/// JSON representation of a struct or a variant in Serde.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum JsonShape {
+    /// An object with one entry per field.
+    Object,
+    /// The value of the single field.
+    Value,
+    /// An array of field values.
+    Array,
 }
 
 /// Shared state for the code generation of a Java source file.
@@ -37,14 +56,13 @@ struct JavaEmitter<'a, T> {
     /// * We count multiplicities to allow inplace backtracking.
     /// * Names in the registry are assumed to never clash.
     current_reserved_names: HashMap<String, usize>,
+    /// Containers mapped to the network addresses of `java.net`, indexed by name.
+    addresses: &'a BTreeMap<String, AddressFormat>,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a Java code generator for the given config.
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Java does not support generating c-style enums");
-        }
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -55,9 +73,18 @@ impl<'a> CodeGenerator<'a> {
         Self {
             config,
             external_qualified_names,
+            jackson: false,
         }
     }
 
+    /// Whether to add Jackson annotations so that generated classes can also be (de)serialized
+    /// in JSON. Optional values require the module `jackson-datatype-jdk8`.
+    /// Unlike Serde, unit variants are written as empty objects (e.g. `{"A": {}}`).
+    pub fn with_jackson(mut self, jackson: bool) -> Self {
+        self.jackson = jackson;
+        self
+    }
+
     /// Output class definitions for ` registry` in separate source files.
     /// Source files will be created in a subdirectory of `install_dir` corresponding to the given
     /// package name (if any, otherwise `install_dir` it self).
@@ -65,7 +92,24 @@ impl<'a> CodeGenerator<'a> {
         &self,
         install_dir: std::path::PathBuf,
         registry: &Registry,
-    ) -> Result<()> {
+    ) -> crate::Result<()> {
+        if self.config.c_style_enums {
+            return Err(crate::Error::InvalidConfig(
+                "Java does not support generating c-style enums".to_string(),
+            ));
+        }
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("Java", registry)?;
+        common::check_options(
+            "Java",
+            self.config,
+            &[
+                ConfigOption::ImmutableCollections,
+                ConfigOption::CanonicalEquality,
+                ConfigOption::RootFormats,
+            ],
+        )?;
         let current_namespace = self
             .config
             .module_name
@@ -79,28 +123,99 @@ impl<'a> CodeGenerator<'a> {
         }
         std::fs::create_dir_all(&dir_path)?;
 
+        let addresses = common::address_formats(registry);
         for (name, format) in registry {
-            self.write_container_class(&dir_path, current_namespace.clone(), name, format)?;
+            common::check_cancellation(self.config)?;
+            // Network addresses use `java.net` classes instead.
+            if !addresses.contains_key(name) {
+                self.write_container_class(
+                    &dir_path,
+                    current_namespace.clone(),
+                    &addresses,
+                    name,
+                    format,
+                )?;
+            }
+        }
+        if self.config.serialization && !self.config.encodings.is_empty() {
+            for (name, format) in &self.config.root_formats {
+                self.write_root_format_class(
+                    &dir_path,
+                    current_namespace.clone(),
+                    &addresses,
+                    name,
+                    format,
+                )?;
+            }
+        }
+        if self.config.embedded_schema {
+            self.write_schema_class(&dir_path, current_namespace.clone(), &addresses, registry)?;
         }
         if self.config.serialization {
-            self.write_helper_class(&dir_path, current_namespace, registry)?;
+            self.write_helper_class(&dir_path, current_namespace, &addresses, registry)?;
         }
         Ok(())
     }
 
+    fn write_schema_class(
+        &self,
+        dir_path: &std::path::Path,
+        current_namespace: Vec<String>,
+        addresses: &BTreeMap<String, AddressFormat>,
+        registry: &Registry,
+    ) -> crate::Result<()> {
+        let mut file = common::create_source_file(self.config, dir_path.join("Schema.java"))?;
+        let mut emitter = JavaEmitter {
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
+            generator: self,
+            current_namespace,
+            current_reserved_names: HashMap::new(),
+            addresses,
+        };
+
+        emitter.output_preamble()?;
+        emitter.output_embedded_schema(registry)?;
+        Ok(())
+    }
+
+    fn write_root_format_class(
+        &self,
+        dir_path: &std::path::Path,
+        current_namespace: Vec<String>,
+        addresses: &BTreeMap<String, AddressFormat>,
+        name: &str,
+        format: &Format,
+    ) -> Result<()> {
+        let mut file =
+            common::create_source_file(self.config, dir_path.join(name.to_string() + ".java"))?;
+        let mut emitter = JavaEmitter {
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
+            generator: self,
+            current_namespace,
+            current_reserved_names: HashMap::new(),
+            addresses,
+        };
+
+        emitter.output_preamble()?;
+        emitter.output_root_format(name, format)
+    }
+
     fn write_container_class(
         &self,
         dir_path: &std::path::Path,
         current_namespace: Vec<String>,
+        addresses: &BTreeMap<String, AddressFormat>,
         name: &str,
         format: &ContainerFormat,
     ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join(name.to_string() + ".java"))?;
+        let mut file =
+            common::create_source_file(self.config, dir_path.join(name.to_string() + ".java"))?;
         let mut emitter = JavaEmitter {
             out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
             generator: self,
             current_namespace,
             current_reserved_names: HashMap::new(),
+            addresses,
         };
 
         emitter.output_preamble()?;
@@ -111,18 +226,21 @@ impl<'a> CodeGenerator<'a> {
         &self,
         dir_path: &std::path::Path,
         current_namespace: Vec<String>,
+        addresses: &BTreeMap<String, AddressFormat>,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join("TraitHelpers.java"))?;
+    ) -> crate::Result<()> {
+        let mut file = common::create_source_file(self.config, dir_path.join("TraitHelpers.java"))?;
         let mut emitter = JavaEmitter {
             out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
             generator: self,
             current_namespace,
             current_reserved_names: HashMap::new(),
+            addresses,
         };
 
         emitter.output_preamble()?;
-        emitter.output_trait_helpers(registry)
+        emitter.output_trait_helpers(registry)?;
+        Ok(())
     }
 }
 
@@ -166,12 +284,27 @@ where
     }
 
     fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
+        self.output_comment_with_note(name, None)
+    }
+
+    /// Same as `output_comment` followed by the paragraph `note` on thread safety, if requested.
+    fn output_comment_with_note(&mut self, name: &str, note: Option<&str>) -> std::io::Result<()> {
         let mut path = self.current_namespace.clone();
         path.push(name.to_string());
-        if let Some(doc) = self.generator.config.comments.get(&path) {
-            let text = textwrap::indent(doc, " * ").replace("\n\n", "\n *\n");
+        let mut doc = self
+            .generator
+            .config
+            .comment_with_deprecation(&path, "@deprecated ");
+        if let Some(note) = note {
+            doc = self.generator.config.with_thread_safety_note(doc, note);
+        }
+        if let Some(doc) = doc {
+            let text = textwrap::indent(&doc, " * ").replace("\n\n", "\n *\n");
             writeln!(self.out, "/**\n{} */", text)?;
         }
+        if self.generator.config.deprecations.contains_key(&path) {
+            writeln!(self.out, "@Deprecated")?;
+        }
         Ok(())
     }
 
@@ -187,10 +320,18 @@ where
         Ok(())
     }
 
+    fn quote_jackson(annotation: &str) -> String {
+        format!("@com.fasterxml.jackson.annotation.{}", annotation)
+    }
+
     fn quote_type(&self, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(x) => self.quote_qualified_name(x),
+            TypeName(x) => match self.addresses.get(x) {
+                Some(AddressFormat::IpAddr) => "java.net.InetAddress".into(),
+                Some(AddressFormat::SocketAddr) => "java.net.InetSocketAddress".into(),
+                None => self.quote_qualified_name(x),
+            },
             Unit => "com.novi.serde.Unit".into(),
             Bool => "Boolean".into(),
             I8 => "Byte".into(),
@@ -211,6 +352,7 @@ where
 
             Option(format) => format!("java.util.Optional<{}>", self.quote_type(format)),
             Seq(format) => format!("java.util.List<{}>", self.quote_type(format)),
+            Set(format) => format!("java.util.Set<{}>", self.quote_type(format)),
             Map { key, value } => format!(
                 "java.util.Map<{}, {}>",
                 self.quote_type(key),
@@ -226,7 +368,15 @@ where
                 size,
                 self.quote_type(content)
             ),
-            Variable(_) => panic!("unexpected value"),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => "java.time.Duration".into(),
+                Some(common::TimeFormat::SystemTime) => "java.time.Instant".into(),
+                None => self.quote_type(layout),
+            },
+            Decimal { .. } => "java.math.BigDecimal".into(),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
         }
     }
 
@@ -262,18 +412,53 @@ where
             .join(", ")
     }
 
-    fn output_trait_helpers(&mut self, registry: &Registry) -> Result<()> {
-        let mut subtypes = BTreeMap::new();
-        for format in registry.values() {
-            format
-                .visit(&mut |f| {
-                    if Self::needs_helper(f) {
-                        subtypes.insert(common::mangle_type(f), f.clone());
-                    }
-                    Ok(())
-                })
-                .unwrap();
+    /// Compare the integer `value` of the given format with `bound`, e.g. `value >= 1`.
+    /// Unsigned integers are compared as such.
+    fn quote_integer_comparison(
+        value: &str,
+        format: &Format,
+        operator: &str,
+        bound: i128,
+    ) -> String {
+        use Format::*;
+        match format {
+            I64 => format!("{} {} {}L", value, operator, bound),
+            U8 => format!("Byte.toUnsignedInt({}) {} {}", value, operator, bound),
+            U16 => format!("Short.toUnsignedInt({}) {} {}", value, operator, bound),
+            U32 => format!("Integer.toUnsignedLong({}) {} {}L", value, operator, bound),
+            U64 => format!(
+                "Long.compareUnsigned({}, {}L) {} 0",
+                value, bound as u64 as i64, operator
+            ),
+            I128 | U128 => format!(
+                "{}.compareTo(new java.math.BigInteger(\"{}\")) {} 0",
+                value, bound, operator
+            ),
+            _ => format!("{} {} {}", value, operator, bound),
+        }
+    }
+
+    /// Wrap `value` into an unmodifiable view if `format` is a sequence or a map and
+    /// immutable collections were requested.
+    fn quote_immutable(&self, value: &str, format: &Format) -> String {
+        use Format::*;
+        if !self.generator.config.immutable_collections {
+            return value.to_string();
+        }
+        match format {
+            Seq(_) | TupleArray { .. } => {
+                format!("java.util.Collections.unmodifiableList({})", value)
+            }
+            Set(_) => format!("java.util.Collections.unmodifiableSet({})", value),
+            Map { .. } => format!("java.util.Collections.unmodifiableMap({})", value),
+            Custom { layout, .. } => self.quote_immutable(value, layout),
+            _ => value.to_string(),
         }
+    }
+
+    fn output_trait_helpers(&mut self, registry: &Registry) -> crate::Result<()> {
+        let subtypes =
+            common::helper_formats(self.generator.config, registry, true, Self::needs_helper)?;
         writeln!(self.out, "final class TraitHelpers {{")?;
         let reserved_names = &[];
         self.enter_class("TraitHelpers", reserved_names);
@@ -282,21 +467,29 @@ where
             self.output_deserialization_helper(mangled_name, subtype)?;
         }
         self.leave_class(reserved_names);
-        writeln!(self.out, "}}\n")
+        writeln!(self.out, "}}\n")?;
+        Ok(())
     }
 
     fn needs_helper(format: &Format) -> bool {
         use Format::*;
         matches!(
             format,
-            Option(_) | Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. }
+            Option(_) | Seq(_) | Set(_) | Map { .. } | Tuple(_) | TupleArray { .. }
         )
     }
 
     fn quote_serialize_value(&self, value: &str, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(_) => format!("{}.serialize(serializer);", value),
+            TypeName(name) => match self.addresses.get(name) {
+                Some(address) => format!(
+                    "com.novi.serde.NetAddresses.serialize_{}({}, serializer);",
+                    address.helper_suffix(),
+                    value
+                ),
+                None => format!("{}.serialize(serializer);", value),
+            },
             Unit => format!("serializer.serialize_unit({});", value),
             Bool => format!("serializer.serialize_bool({});", value),
             I8 => format!("serializer.serialize_i8({});", value),
@@ -314,10 +507,33 @@ where
             Char => format!("serializer.serialize_char({});", value),
             Str => format!("serializer.serialize_str({});", value),
             Bytes => format!("serializer.serialize_bytes({});", value),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => format!(
+                    "serializer.serialize_u64({0}.getSeconds()); serializer.serialize_u32({0}.getNano());",
+                    value
+                ),
+                Some(common::TimeFormat::SystemTime) => format!(
+                    "serializer.serialize_u64({0}.getEpochSecond()); serializer.serialize_u32({0}.getNano());",
+                    value
+                ),
+                None => self.quote_serialize_value(value, layout),
+            },
+            Decimal { mantissa, scale } => {
+                // Fails with an `ArithmeticException` if the value does not fit the mantissa.
+                let unscaled = format!("{}.setScale({}).unscaledValue()", value, scale);
+                let mantissa_value = match **mantissa {
+                    I8 => format!("{}.byteValueExact()", unscaled),
+                    I16 => format!("{}.shortValueExact()", unscaled),
+                    I32 => format!("{}.intValueExact()", unscaled),
+                    I64 => format!("{}.longValueExact()", unscaled),
+                    _ => unscaled,
+                };
+                self.quote_serialize_value(&mantissa_value, mantissa)
+            }
             _ => format!(
                 "{}.serialize_{}({}, serializer);",
                 self.quote_qualified_name("TraitHelpers"),
-                common::mangle_type(format),
+                common::helper_name(self.generator.config, format),
                 value
             ),
         }
@@ -326,10 +542,16 @@ where
     fn quote_deserialize(&self, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(name) => format!(
-                "{}.deserialize(deserializer)",
-                self.quote_qualified_name(name)
-            ),
+            TypeName(name) => match self.addresses.get(name) {
+                Some(address) => format!(
+                    "com.novi.serde.NetAddresses.deserialize_{}(deserializer)",
+                    address.helper_suffix()
+                ),
+                None => format!(
+                    "{}.deserialize(deserializer)",
+                    self.quote_qualified_name(name)
+                ),
+            },
             Unit => "deserializer.deserialize_unit()".to_string(),
             Bool => "deserializer.deserialize_bool()".to_string(),
             I8 => "deserializer.deserialize_i8()".to_string(),
@@ -347,10 +569,27 @@ where
             Char => "deserializer.deserialize_char()".to_string(),
             Str => "deserializer.deserialize_str()".to_string(),
             Bytes => "deserializer.deserialize_bytes()".to_string(),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => "java.time.Duration.ofSeconds(deserializer.deserialize_u64(), deserializer.deserialize_u32())".to_string(),
+                Some(common::TimeFormat::SystemTime) => "java.time.Instant.ofEpochSecond(deserializer.deserialize_u64(), deserializer.deserialize_u32())".to_string(),
+                None => self.quote_deserialize(layout),
+            },
+            Decimal { mantissa, scale } => match **mantissa {
+                I128 => format!(
+                    "new java.math.BigDecimal({}, {})",
+                    self.quote_deserialize(mantissa),
+                    scale
+                ),
+                _ => format!(
+                    "new java.math.BigDecimal(java.math.BigInteger.valueOf({}), {})",
+                    self.quote_deserialize(mantissa),
+                    scale
+                ),
+            },
             _ => format!(
                 "{}.deserialize_{}(deserializer)",
                 self.quote_qualified_name("TraitHelpers"),
-                common::mangle_type(format),
+                common::helper_name(self.generator.config, format),
             ),
         }
     }
@@ -395,6 +634,24 @@ for ({} item : value) {{
                 )?;
             }
 
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+serializer.serialize_len(value.size());
+int[] offsets = new int[value.size()];
+int count = 0;
+for ({} item : value) {{
+    offsets[count++] = serializer.get_buffer_offset();
+    {}
+}}
+serializer.sort_map_entries(offsets);
+"#,
+                    self.quote_type(format),
+                    self.quote_serialize_value("item", format)
+                )?;
+            }
+
             Map { key, value } => {
                 write!(
                     self.out,
@@ -482,10 +739,42 @@ java.util.List<{0}> obj = new java.util.ArrayList<{0}>((int) length);
 for (long i = 0; i < length; i++) {{
     obj.add({1});
 }}
-return obj;
+return {2};
+"#,
+                    self.quote_type(format),
+                    self.quote_deserialize(format),
+                    self.quote_immutable("obj", format0),
+                )?;
+            }
+
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+long length = deserializer.deserialize_len();
+java.util.Set<{0}> obj = new java.util.LinkedHashSet<{0}>();
+int previous_item_start = 0;
+int previous_item_end = 0;
+for (long i = 0; i < length; i++) {{
+    int item_start = deserializer.get_buffer_offset();
+    {0} item = {1};
+    int item_end = deserializer.get_buffer_offset();
+    if (i > 0) {{
+        deserializer.check_that_key_slices_are_increasing(
+            new com.novi.serde.Slice(previous_item_start, previous_item_end),
+            new com.novi.serde.Slice(item_start, item_end));
+    }}
+    previous_item_start = item_start;
+    previous_item_end = item_end;
+    if (!obj.add(item)) {{
+        throw new com.novi.serde.DeserializationError("Duplicate elements in set");
+    }}
+}}
+return {2};
 "#,
                     self.quote_type(format),
-                    self.quote_deserialize(format)
+                    self.quote_deserialize(format),
+                    self.quote_immutable("obj", format0),
                 )?;
             }
 
@@ -511,12 +800,13 @@ for (long i = 0; i < length; i++) {{
     {1} value = {3};
     obj.put(key, value);
 }}
-return obj;
+return {4};
 "#,
                     self.quote_type(key),
                     self.quote_type(value),
                     self.quote_deserialize(key),
                     self.quote_deserialize(value),
+                    self.quote_immutable("obj", format0),
                 )?;
             }
 
@@ -544,11 +834,12 @@ java.util.List<{0}> obj = new java.util.ArrayList<{0}>({1});
 for (long i = 0; i < {1}; i++) {{
     obj.add({2});
 }}
-return obj;
+return {3};
 "#,
                     self.quote_type(content),
                     size,
-                    self.quote_deserialize(content)
+                    self.quote_deserialize(content),
+                    self.quote_immutable("obj", format0),
                 )?;
             }
 
@@ -561,38 +852,40 @@ return obj;
     fn output_variant(
         &mut self,
         base: &str,
-        index: u32,
+        tag: VariantTag,
         name: &str,
         variant: &VariantFormat,
     ) -> Result<()> {
         use VariantFormat::*;
-        let fields = match variant {
-            Unit => Vec::new(),
-            NewType(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            Tuple(formats) => formats
-                .iter()
-                .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
-                .collect(),
-            Struct(fields) => fields.clone(),
-            Variable(_) => panic!("incorrect value"),
+        let (fields, shape) = match variant {
+            Unit => (Vec::new(), JsonShape::Object),
+            NewType(format) => (
+                vec![Named::new("value", format.as_ref().clone())],
+                JsonShape::Value,
+            ),
+            Tuple(formats) => (
+                formats
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Named::new(format!("field{}", i), f.clone()))
+                    .collect(),
+                JsonShape::Array,
+            ),
+            Struct(fields) => (fields.clone(), JsonShape::Object),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         };
-        self.output_struct_or_variant_container(Some(base), Some(index), name, &fields)
+        self.output_struct_or_variant_container(Some(base), Some(tag), name, &fields, shape)
     }
 
     fn output_variants(
         &mut self,
         base: &str,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
+        tag: Option<&str>,
     ) -> Result<()> {
         for (index, variant) in variants {
-            self.output_variant(base, *index, &variant.name, &variant.value)?;
+            let tag = VariantTag::new(tag, *index, &variant.name);
+            self.output_variant(base, tag, &variant.name, &variant.value)?;
         }
         Ok(())
     }
@@ -600,21 +893,24 @@ return obj;
     fn output_struct_or_variant_container(
         &mut self,
         variant_base: Option<&str>,
-        variant_index: Option<u32>,
+        variant_tag: Option<VariantTag>,
         name: &str,
         fields: &[Named<Format>],
+        shape: JsonShape,
     ) -> Result<()> {
         // Beginning of class
         writeln!(self.out)?;
         if let Some(base) = variant_base {
             self.output_comment(name)?;
+            self.output_jackson_shape(shape, fields)?;
             writeln!(
                 self.out,
                 "public static final class {} extends {} {{",
                 name, base
             )?;
         } else {
-            self.output_comment(name)?;
+            self.output_comment_with_note(name, Some(THREAD_SAFETY_NOTE))?;
+            self.output_jackson_shape(shape, fields)?;
             writeln!(self.out, "public final class {} {{", name)?;
         }
         let reserved_names = &["Builder"];
@@ -622,6 +918,17 @@ return obj;
         // Fields
         for field in fields {
             self.output_comment(&field.name)?;
+            if self.generator.jackson {
+                match shape {
+                    JsonShape::Value => writeln!(self.out, "{}", Self::quote_jackson("JsonValue"))?,
+                    _ => writeln!(
+                        self.out,
+                        "{}({:?})",
+                        Self::quote_jackson("JsonProperty"),
+                        field.name
+                    )?,
+                }
+            }
             writeln!(
                 self.out,
                 "public final {} {};",
@@ -633,13 +940,36 @@ return obj;
             writeln!(self.out)?;
         }
         // Constructor.
+        let jackson_properties = self.generator.jackson && shape != JsonShape::Value;
+        if self.generator.jackson && !fields.is_empty() {
+            match shape {
+                JsonShape::Value => writeln!(
+                    self.out,
+                    "{}(mode = com.fasterxml.jackson.annotation.JsonCreator.Mode.DELEGATING)",
+                    Self::quote_jackson("JsonCreator")
+                )?,
+                _ => writeln!(self.out, "{}", Self::quote_jackson("JsonCreator"))?,
+            }
+        }
         writeln!(
             self.out,
             "public {}({}) {{",
             name,
             fields
                 .iter()
-                .map(|f| format!("{} {}", self.quote_type(&f.value), &f.name))
+                .map(|f| {
+                    let parameter = format!("{} {}", self.quote_type(&f.value), &f.name);
+                    if jackson_properties {
+                        format!(
+                            "{}({:?}) {}",
+                            Self::quote_jackson("JsonProperty"),
+                            f.name,
+                            parameter
+                        )
+                    } else {
+                        parameter
+                    }
+                })
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
@@ -651,8 +981,32 @@ return obj;
                 &field.name
             )?;
         }
+        let constraints =
+            common::integer_constraints(self.generator.config, &self.current_namespace, fields);
+        for (field, format, constraint) in constraints {
+            let condition = constraint
+                .comparisons()
+                .iter()
+                .map(|(operator, bound)| {
+                    Self::quote_integer_comparison(&field.name, format, operator, *bound)
+                })
+                .collect::<Vec<_>>()
+                .join(" && ");
+            writeln!(
+                self.out,
+                "if (!({})) {{ throw new IllegalArgumentException(\"{} must be {}\"); }}",
+                condition,
+                field.name,
+                constraint.description()
+            )?;
+        }
         for field in fields {
-            writeln!(self.out, "this.{} = {};", &field.name, &field.name)?;
+            writeln!(
+                self.out,
+                "this.{} = {};",
+                &field.name,
+                self.quote_immutable(&field.name, &field.value)
+            )?;
         }
         self.out.unindent();
         writeln!(self.out, "}}")?;
@@ -664,8 +1018,14 @@ return obj;
             )?;
             self.out.indent();
             writeln!(self.out, "serializer.increase_container_depth();")?;
-            if let Some(index) = variant_index {
-                writeln!(self.out, "serializer.serialize_variant_index({});", index)?;
+            match variant_tag {
+                Some(VariantTag::Index(index)) => {
+                    writeln!(self.out, "serializer.serialize_variant_index({});", index)?
+                }
+                Some(VariantTag::Name { name, .. }) => {
+                    writeln!(self.out, "serializer.serialize_str({:?});", name)?
+                }
+                None => (),
             }
             for field in fields {
                 writeln!(
@@ -678,7 +1038,7 @@ return obj;
             self.out.unindent();
             writeln!(self.out, "}}")?;
 
-            if variant_index.is_none() {
+            if variant_tag.is_none() {
                 for encoding in &self.generator.config.encodings {
                     self.output_class_serialize_for_encoding(*encoding)?;
                 }
@@ -686,7 +1046,7 @@ return obj;
         }
         // Deserialize (struct) or Load (variant)
         if self.generator.config.serialization {
-            if variant_index.is_none() {
+            if variant_tag.is_none() {
                 writeln!(
                     self.out,
                     "\npublic static {} deserialize(com.novi.serde.Deserializer deserializer) throws com.novi.serde.DeserializationError {{",
@@ -715,12 +1075,30 @@ return obj;
             self.out.unindent();
             writeln!(self.out, "}}")?;
 
-            if variant_index.is_none() {
+            if variant_tag.is_none() {
                 for encoding in &self.generator.config.encodings {
                     self.output_class_deserialize_for_encoding(name, *encoding)?;
                 }
             }
         }
+        // Equality and hashing
+        if self.generator.config.serialization && self.generator.config.canonical_equality {
+            self.output_canonical_equality(name)?;
+        } else {
+            self.output_fieldwise_equality(name, fields)?;
+        }
+        // Flags
+        self.output_flags(name, fields)?;
+        // Builder
+        self.output_struct_or_variant_container_builder(name, fields)?;
+        // Custom code
+        self.output_custom_code()?;
+        // End of class
+        self.leave_class(reserved_names);
+        writeln!(self.out, "}}")
+    }
+
+    fn output_fieldwise_equality(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
         // Equality
         write!(self.out, "\npublic boolean equals(Object obj) {{")?;
         self.out.indent();
@@ -756,16 +1134,165 @@ if (getClass() != obj.getClass()) return false;
         }
         writeln!(self.out, "return value;")?;
         self.out.unindent();
-        writeln!(self.out, "}}")?;
-        // Builder
-        self.output_struct_or_variant_container_builder(name, fields)?;
-        // Custom code
-        self.output_custom_code()?;
-        // End of class
-        self.leave_class(reserved_names);
         writeln!(self.out, "}}")
     }
 
+    /// Compare and hash values through their BCS serialization, which is cached on first use.
+    fn output_canonical_equality(&mut self, name: &str) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+private transient byte[] canonicalBytes;
+
+private byte[] canonicalBytes() {{
+    if (canonicalBytes == null) {{
+        try {{
+            com.novi.serde.Serializer serializer = new com.novi.bcs.BcsSerializer();
+            serialize(serializer);
+            canonicalBytes = serializer.get_bytes();
+        }} catch (com.novi.serde.SerializationError e) {{
+            throw new IllegalStateException(e);
+        }}
+    }}
+    return canonicalBytes;
+}}
+
+public boolean equals(Object obj) {{
+    if (this == obj) return true;
+    if (obj == null) return false;
+    if (getClass() != obj.getClass()) return false;
+    {0} other = ({0}) obj;
+    return java.util.Arrays.equals(canonicalBytes(), other.canonicalBytes());
+}}
+
+public int hashCode() {{
+    return java.util.Arrays.hashCode(canonicalBytes());
+}}"#,
+            name,
+        )
+    }
+
+    fn output_jackson_shape(&mut self, shape: JsonShape, fields: &[Named<Format>]) -> Result<()> {
+        if !self.generator.jackson || shape != JsonShape::Array {
+            return Ok(());
+        }
+        writeln!(
+            self.out,
+            "{}(shape = com.fasterxml.jackson.annotation.JsonFormat.Shape.ARRAY)",
+            Self::quote_jackson("JsonFormat")
+        )?;
+        writeln!(
+            self.out,
+            "{}({{{}}})",
+            Self::quote_jackson("JsonPropertyOrder"),
+            fields
+                .iter()
+                .map(|f| format!("{:?}", f.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    /// Enums are externally tagged: `{"Variant": ...}`.
+    fn output_jackson_subtypes(
+        &mut self,
+        name: &str,
+        tag: Option<&str>,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        if !self.generator.jackson {
+            return Ok(());
+        }
+        match tag {
+            Some(tag) => writeln!(
+                self.out,
+                "{}(use = com.fasterxml.jackson.annotation.JsonTypeInfo.Id.NAME, include = com.fasterxml.jackson.annotation.JsonTypeInfo.As.PROPERTY, property = {:?})",
+                Self::quote_jackson("JsonTypeInfo"),
+                tag
+            )?,
+            None => writeln!(
+                self.out,
+                "{}(use = com.fasterxml.jackson.annotation.JsonTypeInfo.Id.NAME, include = com.fasterxml.jackson.annotation.JsonTypeInfo.As.WRAPPER_OBJECT)",
+                Self::quote_jackson("JsonTypeInfo")
+            )?,
+        }
+        writeln!(self.out, "{}({{", Self::quote_jackson("JsonSubTypes"))?;
+        self.out.indent();
+        let types = variants
+            .values()
+            .map(|variant| {
+                format!(
+                    "{}(value = {}.{}.class, name = {:?})",
+                    Self::quote_jackson("JsonSubTypes.Type"),
+                    name,
+                    variant.name,
+                    variant.name
+                )
+            })
+            .collect::<Vec<_>>();
+        writeln!(self.out, "{}", types.join(",\n"))?;
+        self.out.unindent();
+        writeln!(self.out, "}})")
+    }
+
+    fn output_flags(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let flag_sets = common::flag_sets(self.generator.config, &self.current_namespace, fields);
+        for (field, flags) in flag_sets {
+            let field_type = match field.value {
+                Format::U8 => "byte",
+                Format::U16 => "short",
+                _ => "int",
+            };
+            writeln!(self.out)?;
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "public static final {0} {1}_{2} = ({0}) {3:#x};",
+                    field_type,
+                    field.name.to_shouty_snake_case(),
+                    flag.to_shouty_snake_case(),
+                    value
+                )?;
+            }
+            // Instances are immutable: `set*` and `clear*` return updated copies.
+            let quote_arguments = |expr: &str| {
+                fields
+                    .iter()
+                    .map(|f| {
+                        if f.name == field.name {
+                            format!("({}) ({})", field_type, expr)
+                        } else {
+                            f.name.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            writeln!(
+                self.out,
+                r#"
+public boolean has{0}({1} mask) {{
+    return ({2} & mask) == mask;
+}}
+
+public {3} set{0}({1} mask) {{
+    return new {3}({4});
+}}
+
+public {3} clear{0}({1} mask) {{
+    return new {3}({5});
+}}"#,
+                field.name.to_camel_case(),
+                field_type,
+                field.name,
+                name,
+                quote_arguments(&format!("{} | mask", field.name)),
+                quote_arguments(&format!("{} & ~mask", field.name)),
+            )?;
+        }
+        Ok(())
+    }
+
     fn output_struct_or_variant_container_builder(
         &mut self,
         name: &str,
@@ -809,13 +1336,39 @@ if (getClass() != obj.getClass()) return false;
         writeln!(self.out, "}}")
     }
 
+    /// Open a switch on the variant `key` and output the cases loading the given variants. The
+    /// caller outputs the default case and closes the switch.
+    fn output_variant_switch(
+        &mut self,
+        key: &str,
+        tag: Option<&str>,
+        variants: &[(&u32, &Named<VariantFormat>)],
+    ) -> Result<()> {
+        writeln!(self.out, "switch ({}) {{", key)?;
+        self.out.indent();
+        for (index, variant) in variants {
+            writeln!(
+                self.out,
+                "case {}: return {}.load(deserializer);",
+                match tag {
+                    Some(_) => format!("{:?}", variant.name),
+                    None => index.to_string(),
+                },
+                variant.name,
+            )?;
+        }
+        Ok(())
+    }
+
     fn output_enum_container(
         &mut self,
         name: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
         writeln!(self.out)?;
-        self.output_comment(name)?;
+        self.output_comment_with_note(name, Some(THREAD_SAFETY_NOTE))?;
+        self.output_jackson_subtypes(name, tag, variants)?;
         writeln!(self.out, "public abstract class {} {{", name)?;
         let reserved_names = variants
             .values()
@@ -833,41 +1386,100 @@ if (getClass() != obj.getClass()) return false;
                 name
             )?;
             self.out.indent();
-            writeln!(
-                self.out,
-                r#"
-int index = deserializer.deserialize_variant_index();
-switch (index) {{"#,
-            )?;
-            self.out.indent();
-            for (index, variant) in variants {
+            let (key_type, key, kind) = match tag {
+                Some(_) => ("String", "tag", "name"),
+                None => ("int", "index", "index"),
+            };
+            if tag.is_some() {
+                writeln!(self.out, "\nString tag = deserializer.deserialize_str();")?;
+            } else {
                 writeln!(
                     self.out,
-                    "case {}: return {}.load(deserializer);",
-                    index, variant.name,
+                    "\nint index = deserializer.deserialize_variant_index();"
+                )?;
+            }
+            let chunks = common::variant_chunks(self.generator.config, variants);
+            if chunks.len() == 1 {
+                self.output_variant_switch(key, tag, &chunks[0])?;
+                writeln!(
+                    self.out,
+                    "default: throw new com.novi.serde.DeserializationError(\"Unknown variant {} for {}: \" + {});",
+                    kind, name, key,
+                )?;
+                self.out.unindent();
+                writeln!(self.out, "}}")?;
+            } else {
+                writeln!(self.out, "{} value;", name)?;
+                for index in 0..chunks.len() {
+                    writeln!(
+                        self.out,
+                        "if ((value = deserializeVariants{}({}, deserializer)) != null) return value;",
+                        index, key,
+                    )?;
+                }
+                writeln!(
+                    self.out,
+                    "throw new com.novi.serde.DeserializationError(\"Unknown variant {} for {}: \" + {});",
+                    kind, name, key,
                 )?;
             }
-            writeln!(
-                self.out,
-                "default: throw new com.novi.serde.DeserializationError(\"Unknown variant index for {}: \" + index);",
-                name,
-            )?;
-            self.out.unindent();
-            writeln!(self.out, "}}")?;
             self.out.unindent();
             writeln!(self.out, "}}")?;
+            if chunks.len() > 1 {
+                for (index, chunk) in chunks.iter().enumerate() {
+                    write!(
+                        self.out,
+                        "\nprivate static {} deserializeVariants{}({} {}, com.novi.serde.Deserializer deserializer) throws com.novi.serde.DeserializationError {{\n",
+                        name, index, key_type, key,
+                    )?;
+                    self.out.indent();
+                    self.output_variant_switch(key, tag, chunk)?;
+                    writeln!(self.out, "default: return null;")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                }
+            }
 
             for encoding in &self.generator.config.encodings {
                 self.output_class_serialize_for_encoding(*encoding)?;
                 self.output_class_deserialize_for_encoding(name, *encoding)?;
             }
         }
+        if self.generator.config.variant_names {
+            self.output_variant_names(variants)?;
+        }
 
-        self.output_variants(name, variants)?;
+        self.output_variants(name, variants, tag)?;
         self.leave_class(&reserved_names);
         writeln!(self.out, "}}\n")
     }
 
+    fn output_variant_names(
+        &mut self,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+public static final java.util.List<String> VARIANT_NAMES = java.util.Collections.unmodifiableList(java.util.Arrays.asList({}));
+
+public static String variantName(int index) {{
+    return index >= 0 && index < VARIANT_NAMES.size() ? VARIANT_NAMES.get(index) : null;
+}}
+
+public static int variantIndex(String name) {{
+    return VARIANT_NAMES.indexOf(name);
+}}"#,
+            variants
+                .values()
+                .map(|v| format!("{:?}", v.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
     fn output_class_serialize_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
         writeln!(
             self.out,
@@ -875,11 +1487,27 @@ switch (index) {{"#,
 public byte[] {0}Serialize() throws com.novi.serde.SerializationError {{
     com.novi.serde.Serializer serializer = new com.novi.{0}.{1}Serializer();
     serialize(serializer);
-    return serializer.get_bytes();
+    return {2};
 }}"#,
             encoding.name(),
-            encoding.name().to_camel_case()
-        )
+            encoding.name().to_camel_case(),
+            if self.generator.config.envelope_hooks {
+                "com.novi.serde.Envelope.wrap(serializer.get_bytes())"
+            } else {
+                "serializer.get_bytes()"
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+public byte[] {0}SerializeChecked() throws com.novi.serde.SerializationError {{
+    return com.novi.serde.Checksum.append({0}Serialize());
+}}"#,
+                encoding.name(),
+            )?;
+        }
+        Ok(())
     }
 
     fn output_class_deserialize_for_encoding(
@@ -893,7 +1521,7 @@ public byte[] {0}Serialize() throws com.novi.serde.SerializationError {{
 public static {0} {1}Deserialize(byte[] input) throws com.novi.serde.DeserializationError {{
     if (input == null) {{
          throw new com.novi.serde.DeserializationError("Cannot deserialize null array");
-    }}
+    }}{3}
     com.novi.serde.Deserializer deserializer = new com.novi.{1}.{2}Deserializer(input);
     {0} value = deserialize(deserializer);
     if (deserializer.get_buffer_offset() < input.length) {{
@@ -903,54 +1531,195 @@ public static {0} {1}Deserialize(byte[] input) throws com.novi.serde.Deserializa
 }}"#,
             name,
             encoding.name(),
-            encoding.name().to_camel_case()
-        )
+            encoding.name().to_camel_case(),
+            if self.generator.config.envelope_hooks {
+                "\n    input = com.novi.serde.Envelope.unwrap(input);"
+            } else {
+                ""
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+public static {0} {1}DeserializeChecked(byte[] input) throws com.novi.serde.DeserializationError {{
+    if (input == null) {{
+         throw new com.novi.serde.DeserializationError("Cannot deserialize null array");
+    }}
+    return {1}Deserialize(com.novi.serde.Checksum.verify(input));
+}}"#,
+                name,
+                encoding.name(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn output_embedded_schema(&mut self, registry: &Registry) -> crate::Result<()> {
+        let lines = common::compressed_schema(registry)?;
+        writeln!(self.out, "public final class Schema {{")?;
+        self.out.indent();
+        writeln!(self.out, "private Schema() {{}}\n")?;
+        // Literals are joined at runtime to stay below the size limit of class-file constants.
+        writeln!(
+            self.out,
+            "private static final String COMPRESSED = String.join(\"\","
+        )?;
+        self.out.indent();
+        writeln!(
+            self.out,
+            "{});",
+            lines
+                .iter()
+                .map(|line| format!("\"{}\"", line))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        )?;
+        self.out.unindent();
+        writeln!(
+            self.out,
+            r#"
+/** Return the Serde formats of this package, encoded in YAML. */
+public static String schema() {{
+    java.util.zip.Inflater inflater = new java.util.zip.Inflater();
+    inflater.setInput(java.util.Base64.getDecoder().decode(COMPRESSED));
+    java.io.ByteArrayOutputStream output = new java.io.ByteArrayOutputStream();
+    byte[] buffer = new byte[4096];
+    try {{
+        while (!inflater.finished()) {{
+            int count = inflater.inflate(buffer);
+            if (count == 0 && inflater.needsInput()) {{
+                throw new IllegalStateException("Truncated schema");
+            }}
+            output.write(buffer, 0, count);
+        }}
+    }} catch (java.util.zip.DataFormatException e) {{
+        throw new IllegalStateException(e);
+    }} finally {{
+        inflater.end();
+    }}
+    return new String(output.toByteArray(), java.nio.charset.StandardCharsets.UTF_8);
+}}"#
+        )?;
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        Ok(())
+    }
+
+    fn output_root_format(&mut self, name: &str, format: &Format) -> Result<()> {
+        self.output_comment(name)?;
+        writeln!(self.out, "public final class {} {{", name)?;
+        let reserved_names = &[];
+        self.enter_class(name, reserved_names);
+        writeln!(self.out, "private {}() {{}}", name)?;
+        let value_type = self.quote_type(format);
+        for encoding in &self.generator.config.encodings {
+            writeln!(
+                self.out,
+                r#"
+public static byte[] {0}Serialize({2} value) throws com.novi.serde.SerializationError {{
+    com.novi.serde.Serializer serializer = new com.novi.{0}.{1}Serializer();
+    {3}
+    return {4};
+}}
+
+public static {2} {0}Deserialize(byte[] input) throws com.novi.serde.DeserializationError {{
+    if (input == null) {{
+         throw new com.novi.serde.DeserializationError("Cannot deserialize null array");
+    }}{5}
+    com.novi.serde.Deserializer deserializer = new com.novi.{0}.{1}Deserializer(input);
+    {2} value = {6};
+    if (deserializer.get_buffer_offset() < input.length) {{
+         throw new com.novi.serde.DeserializationError("Some input bytes were not read");
+    }}
+    return value;
+}}"#,
+                encoding.name(),
+                encoding.name().to_camel_case(),
+                value_type,
+                self.quote_serialize_value("value", format),
+                if self.generator.config.envelope_hooks {
+                    "com.novi.serde.Envelope.wrap(serializer.get_bytes())"
+                } else {
+                    "serializer.get_bytes()"
+                },
+                if self.generator.config.envelope_hooks {
+                    "\n    input = com.novi.serde.Envelope.unwrap(input);"
+                } else {
+                    ""
+                },
+                self.quote_deserialize(format),
+            )?;
+        }
+        self.leave_class(reserved_names);
+        writeln!(self.out, "}}")
     }
 
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
-        let fields = match format {
-            UnitStruct => Vec::new(),
-            NewTypeStruct(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            TupleStruct(formats) => formats
-                .iter()
-                .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
-                .collect::<Vec<_>>(),
-            Struct(fields) => fields.clone(),
+        let (fields, shape) = match format {
+            UnitStruct => (Vec::new(), JsonShape::Object),
+            NewTypeStruct(format) => (
+                vec![Named::new("value", format.as_ref().clone())],
+                JsonShape::Value,
+            ),
+            TupleStruct(formats) => (
+                formats
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Named::new(format!("field{}", i), f.clone()))
+                    .collect::<Vec<_>>(),
+                JsonShape::Array,
+            ),
+            Struct(fields) => (fields.clone(), JsonShape::Object),
             Enum(variants) => {
-                self.output_enum_container(name, variants)?;
+                self.output_enum_container(name, None, variants)?;
+                return Ok(());
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                self.output_enum_container(name, Some(tag), variants)?;
                 return Ok(());
             }
         };
-        self.output_struct_or_variant_container(None, None, name, &fields)
+        self.output_struct_or_variant_container(None, None, name, &fields, shape)
     }
 }
 
 /// Installer for generated source files in Java.
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
+    jackson: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            jackson: false,
+        }
+    }
+
+    /// Whether to add Jackson annotations to the generated classes.
+    pub fn with_jackson(mut self, jackson: bool) -> Self {
+        self.jackson = jackson;
+        self
     }
 
+    /// Install the sources of a runtime, or only its unit tests (e.g. `BcsTest.java`) if
+    /// `tests` is true.
     fn install_runtime(
         &self,
         source_dir: include_dir::Dir,
         path: &str,
-    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        tests: bool,
+    ) -> crate::Result<()> {
         let dir_path = self.install_dir.join(path);
         std::fs::create_dir_all(&dir_path)?;
         for entry in source_dir.files() {
+            if entry.path().to_string_lossy().ends_with("Test.java") != tests {
+                continue;
+            }
             let mut file = std::fs::File::create(dir_path.join(entry.path()))?;
             file.write_all(entry.contents())?;
         }
@@ -959,14 +1728,25 @@ impl Installer {
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
 
     fn install_module(
         &self,
         config: &CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
-        let generator = CodeGenerator::new(config);
+        let generator = CodeGenerator::new(config).with_jackson(self.jackson);
         generator.write_source_files(self.install_dir.clone(), registry)?;
         Ok(())
     }
@@ -975,20 +1755,110 @@ impl crate::SourceInstaller for Installer {
         self.install_runtime(
             include_directory!("runtime/java/com/novi/serde"),
             "com/novi/serde",
+            false,
         )
     }
 
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
         self.install_runtime(
-            include_directory!("runtime/java/com/novi/bincode"),
-            "com/novi/bincode",
+            encoding_runtime(encoding),
+            &format!("com/novi/{}", encoding.name()),
+            false,
         )
     }
 
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.install_runtime(
-            include_directory!("runtime/java/com/novi/bcs"),
-            "com/novi/bcs",
-        )
+    fn install_runtime_tests(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        for encoding in &config.encodings {
+            self.install_runtime(
+                encoding_runtime(*encoding),
+                &format!("com/novi/{}", encoding.name()),
+                true,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn install_corpus_runner(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        let registry = crate::test_vectors::corpus_registry();
+        self.install_module(config, &registry)?;
+        let dir_path = config
+            .module_name
+            .split('.')
+            .fold(self.install_dir.clone(), |path, part| path.join(part));
+        let mut file = std::fs::File::create(dir_path.join("corpus.tsv"))?;
+        for vector in crate::test_vectors::corpus() {
+            if config.encodings.contains(&vector.encoding) {
+                writeln!(file, "{}", vector)?;
+            }
+        }
+        let mut cases = String::new();
+        for encoding in &config.encodings {
+            for name in registry.keys() {
+                cases += &format!(
+                    "            case \"{0}:{1}\": return {1}.{0}Deserialize(vector.bytes).{0}Serialize();\n",
+                    encoding.name(),
+                    name
+                );
+            }
+        }
+        let mut file = std::fs::File::create(dir_path.join("CorpusTest.java"))?;
+        write!(
+            file,
+            r#"package {0};
+
+import com.novi.serde.TestVector;
+import java.nio.file.Paths;
+import java.util.Arrays;
+
+/**
+ * Checks that the installed runtimes decode and encode back the shared corpus of test vectors.
+ * Usage: `java {0}.CorpusTest [path/to/corpus.tsv]`.
+ */
+public final class CorpusTest {{
+    static byte[] reencode(TestVector vector) throws Exception {{
+        switch (vector.encoding + ":" + vector.typeName) {{
+{1}            default: throw new IllegalArgumentException("Unknown type " + vector.typeName);
+        }}
+    }}
+
+    public static void main(String[] args) throws Exception {{
+        String path = args.length > 0 ? args[0] : "{2}/corpus.tsv";
+        int failures = 0;
+        for (TestVector vector : TestVector.load(Paths.get(path))) {{
+            byte[] bytes;
+            try {{
+                bytes = reencode(vector);
+            }} catch (Exception e) {{
+                bytes = null;
+            }}
+            if (!Arrays.equals(bytes, vector.bytes)) {{
+                System.err.println("Failed to round-trip " + vector.encoding + " vector of " + vector.typeName + ": " + vector.json);
+                failures++;
+            }}
+        }}
+        if (failures > 0) {{
+            System.exit(1);
+        }}
+    }}
+}}
+"#,
+            config.module_name,
+            cases,
+            config.module_name.replace('.', "/")
+        )?;
+        Ok(())
+    }
+}
+
+fn encoding_runtime(encoding: Encoding) -> include_dir::Dir<'static> {
+    match encoding {
+        Encoding::Bincode => include_directory!("runtime/java/com/novi/bincode"),
+        Encoding::Bcs => include_directory!("runtime/java/com/novi/bcs"),
     }
 }
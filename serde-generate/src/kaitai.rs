@@ -0,0 +1,521 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    common,
+    indent::{IndentConfig, IndentedWriter},
+    CodeGeneratorConfig, Encoding,
+};
+use heck::SnakeCase;
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use std::collections::BTreeMap;
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Main configuration object for the generation of Kaitai Struct definitions.
+pub struct CodeGenerator<'a> {
+    /// Language-independent configuration.
+    config: &'a CodeGeneratorConfig,
+    /// Encoding described by the definitions (by default, `config.single_encoding()`).
+    encoding: Option<Encoding>,
+    /// Container parsed at the top level of the file, if any.
+    root_type: Option<String>,
+}
+
+/// Shared state for the generation of a `.ksy` file.
+struct KaitaiEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    /// Generator.
+    generator: &'a CodeGenerator<'a>,
+    /// Encoding described by the definitions.
+    encoding: Encoding,
+}
+
+/// Attribute specification, e.g. `[("type", "u4")]`.
+type Spec = Vec<(&'static str, String)>;
+
+impl<'a> CodeGenerator<'a> {
+    /// Create a Kaitai Struct generator for the given config.
+    pub fn new(config: &'a CodeGeneratorConfig) -> Self {
+        Self {
+            config,
+            encoding: None,
+            root_type: None,
+        }
+    }
+
+    /// Encoding described by the definitions.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Container parsed at the top level of the file.
+    pub fn with_root_type(mut self, root_type: Option<String>) -> Self {
+        self.root_type = root_type;
+        self
+    }
+
+    /// Write Kaitai Struct definitions (in YAML) for the containers of the registry.
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        let registry = &*common::without_skipped_fields(registry)?;
+        common::check_formats("Kaitai", registry)?;
+        let mut emitter = KaitaiEmitter {
+            out: IndentedWriter::new(out, IndentConfig::Space(2)),
+            generator: self,
+            encoding: self
+                .encoding
+                .unwrap_or_else(|| self.config.single_encoding()),
+        };
+        emitter.output_preamble()?;
+        writeln!(emitter.out, "types:")?;
+        emitter.out.indent();
+        for (name, format) in registry {
+            common::check_cancellation(self.config)?;
+            emitter.output_container(name, format)?;
+        }
+        emitter.output_helpers(registry)?;
+        emitter.out.unindent();
+        Ok(())
+    }
+}
+
+impl<'a, T> KaitaiEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_preamble(&mut self) -> Result<()> {
+        let module_name = &self.generator.config.module_name;
+        writeln!(
+            self.out,
+            "meta:\n  id: {}_{}\n  title: Serde formats of {} ({} encoding)\n  endian: le",
+            module_name.replace('.', "_").to_snake_case(),
+            self.encoding.name(),
+            module_name,
+            self.encoding.name(),
+        )?;
+        if let Some(root_type) = &self.generator.root_type {
+            writeln!(self.out, "seq:")?;
+            self.out.indent();
+            self.output_attribute("value", Self::type_spec(&root_type.to_snake_case()))?;
+            self.out.unindent();
+        }
+        Ok(())
+    }
+
+    fn output_comment(&mut self, name: &str) -> Result<()> {
+        let mut path: Vec<_> = self
+            .generator
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect();
+        path.push(name.to_string());
+        if let Some(doc) = self.generator.config.comment(&path) {
+            writeln!(self.out, "doc: |")?;
+            write!(self.out, "{}", textwrap::indent(&doc, "  "))?;
+        }
+        Ok(())
+    }
+
+    fn type_spec(name: &str) -> Spec {
+        vec![("type", name.to_string())]
+    }
+
+    fn helper_name(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            Unit => "serde_unit".into(),
+            Char => "serde_char".into(),
+            Str => "serde_str".into(),
+            Bytes => "serde_bytes".into(),
+            _ => common::helper_name(self.generator.config, format).to_snake_case(),
+        }
+    }
+
+    fn needs_helper(format: &Format) -> bool {
+        use Format::*;
+        matches!(
+            format,
+            Unit | Char
+                | Str
+                | Bytes
+                | Option(_)
+                | Seq(_)
+                | Set(_)
+                | Map { .. }
+                | Tuple(_)
+                | TupleArray { .. }
+        )
+    }
+
+    fn quote_spec(&self, format: &Format) -> Spec {
+        use Format::*;
+        let kind = match format {
+            TypeName(x) => x.to_snake_case(),
+            Bool | U8 => "u1".into(),
+            U16 => "u2".into(),
+            U32 => "u4".into(),
+            U64 => "u8".into(),
+            I8 => "s1".into(),
+            I16 => "s2".into(),
+            I32 => "s4".into(),
+            I64 => "s8".into(),
+            I128 | U128 => return vec![("size", "16".into())],
+            F32 => "f4".into(),
+            F64 => "f8".into(),
+            Custom { layout, .. } => return self.quote_spec(layout),
+            Decimal { mantissa, .. } => return self.quote_spec(mantissa),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
+            _ => self.helper_name(format),
+        };
+        Self::type_spec(&kind)
+    }
+
+    fn output_attribute(&mut self, id: &str, spec: Spec) -> Result<()> {
+        writeln!(self.out, "- id: {}", id)?;
+        self.out.indent();
+        for (key, value) in spec {
+            writeln!(self.out, "{}: {}", key, value)?;
+        }
+        self.out.unindent();
+        Ok(())
+    }
+
+    fn output_seq(&mut self, attributes: Vec<(String, Spec)>) -> Result<()> {
+        if attributes.is_empty() {
+            return writeln!(self.out, "seq: []");
+        }
+        writeln!(self.out, "seq:")?;
+        self.out.indent();
+        for (id, spec) in attributes {
+            self.output_attribute(&id, spec)?;
+        }
+        self.out.unindent();
+        Ok(())
+    }
+
+    /// Attribute holding a length, and the expression of its value.
+    fn length(&self) -> ((String, Spec), &'static str) {
+        match self.encoding {
+            Encoding::Bincode => (("len".into(), Self::type_spec("u8")), "len"),
+            Encoding::Bcs => (("len".into(), Self::type_spec("uleb128")), "len.value"),
+        }
+    }
+
+    fn tuple_attributes(&self, formats: &[Format]) -> Vec<(String, Spec)> {
+        formats
+            .iter()
+            .enumerate()
+            .map(|(i, format)| (format!("field{}", i), self.quote_spec(format)))
+            .collect()
+    }
+
+    fn struct_attributes(&self, fields: &[Named<Format>]) -> Vec<(String, Spec)> {
+        fields
+            .iter()
+            .map(|field| (field.name.to_snake_case(), self.quote_spec(&field.value)))
+            .collect()
+    }
+
+    fn output_type(&mut self, name: &str, attributes: Vec<(String, Spec)>) -> Result<()> {
+        writeln!(self.out, "{}:", name)?;
+        self.out.indent();
+        self.output_seq(attributes)?;
+        self.out.unindent();
+        Ok(())
+    }
+
+    fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        let type_name = name.to_snake_case();
+        let attributes = match format {
+            UnitStruct => Vec::new(),
+            NewTypeStruct(format) => vec![("value".into(), self.quote_spec(format))],
+            TupleStruct(formats) => self.tuple_attributes(formats),
+            Struct(fields) => self.struct_attributes(fields),
+            Enum(variants) => return self.output_enum(name, &type_name, false, variants),
+            InternallyTaggedEnum { variants, .. } => {
+                return self.output_enum(name, &type_name, true, variants)
+            }
+        };
+        writeln!(self.out, "{}:", type_name)?;
+        self.out.indent();
+        self.output_comment(name)?;
+        self.output_seq(attributes)?;
+        self.out.unindent();
+        Ok(())
+    }
+
+    fn output_enum(
+        &mut self,
+        name: &str,
+        type_name: &str,
+        tagged: bool,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        // Internally tagged enums start with the name of the variant.
+        let (index_id, index_spec, index_value) = match (tagged, self.encoding) {
+            (true, _) => (
+                "variant_name",
+                Self::type_spec("serde_str"),
+                "variant_name.value",
+            ),
+            (false, Encoding::Bincode) => ("variant_index", Self::type_spec("u4"), "variant_index"),
+            (false, Encoding::Bcs) => (
+                "variant_index",
+                Self::type_spec("uleb128"),
+                "variant_index.value",
+            ),
+        };
+        writeln!(self.out, "{}:", type_name)?;
+        self.out.indent();
+        self.output_comment(name)?;
+        writeln!(self.out, "seq:")?;
+        self.out.indent();
+        self.output_attribute(index_id, index_spec)?;
+        writeln!(self.out, "- id: value")?;
+        self.out.indent();
+        writeln!(self.out, "type:")?;
+        self.out.indent();
+        writeln!(self.out, "switch-on: {}", index_value)?;
+        writeln!(self.out, "cases:")?;
+        self.out.indent();
+        for (index, variant) in variants {
+            if let VariantFormat::Unit = variant.value {
+                continue;
+            }
+            let case = if tagged {
+                format!("'{:?}'", variant.name)
+            } else {
+                index.to_string()
+            };
+            writeln!(
+                self.out,
+                "{}: {}__{}",
+                case,
+                type_name,
+                variant.name.to_snake_case()
+            )?;
+        }
+        for _ in 0..5 {
+            self.out.unindent();
+        }
+
+        for variant in variants.values() {
+            use VariantFormat::*;
+            let attributes = match &variant.value {
+                Unit => continue,
+                NewType(format) => vec![("value".into(), self.quote_spec(format))],
+                Tuple(formats) => self.tuple_attributes(formats),
+                Struct(fields) => self.struct_attributes(fields),
+                Variable(_) => unreachable!("formats are checked by common::check_formats"),
+            };
+            let variant_type = format!("{}__{}", type_name, variant.name.to_snake_case());
+            self.output_type(&variant_type, attributes)?;
+        }
+        Ok(())
+    }
+
+    fn output_helpers(&mut self, registry: &Registry) -> crate::Result<()> {
+        let mut subtypes = BTreeMap::new();
+        for format in
+            common::helper_formats(self.generator.config, registry, false, Self::needs_helper)?
+                .values()
+        {
+            subtypes.insert(self.helper_name(format), format.clone());
+        }
+        // Variant names of internally tagged enums are strings.
+        if registry
+            .values()
+            .any(|format| matches!(format, ContainerFormat::InternallyTaggedEnum { .. }))
+        {
+            subtypes.insert(self.helper_name(&Format::Str), Arc::new(Format::Str));
+        }
+        if let Encoding::Bcs = self.encoding {
+            self.output_uleb128()?;
+        }
+        for (name, format) in &subtypes {
+            self.output_helper(name, format)?;
+        }
+        Ok(())
+    }
+
+    fn output_uleb128(&mut self) -> Result<()> {
+        let value = (0..5)
+            .map(|i| {
+                if i == 0 {
+                    "(groups[0] & 0x7f)".to_string()
+                } else {
+                    format!(
+                        "(groups.size > {0} ? (groups[{0}] & 0x7f) << {1} : 0)",
+                        i,
+                        7 * i
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" | ");
+        writeln!(
+            self.out,
+            r#"uleb128:
+  doc: Unsigned integer in ULEB128 (at most 32 bits).
+  seq:
+    - id: groups
+      type: u1
+      repeat: until
+      repeat-until: _ < 0x80
+  instances:
+    value:
+      value: '{}'"#,
+            value
+        )
+    }
+
+    fn output_helper(&mut self, name: &str, format: &Format) -> Result<()> {
+        use Format::*;
+        let (len, len_value) = self.length();
+        let attributes = match format {
+            Unit => Vec::new(),
+            Char => vec![
+                ("first".into(), Self::type_spec("u1")),
+                (
+                    "rest".into(),
+                    vec![(
+                        "size",
+                        "'first < 0x80 ? 0 : first < 0xe0 ? 1 : first < 0xf0 ? 2 : 3'".into(),
+                    )],
+                ),
+            ],
+            Str => vec![
+                len,
+                (
+                    "value".into(),
+                    vec![
+                        ("type", "str".into()),
+                        ("size", len_value.into()),
+                        ("encoding", "UTF-8".into()),
+                    ],
+                ),
+            ],
+            Bytes => vec![len, ("value".into(), vec![("size", len_value.into())])],
+            Option(format) => {
+                let mut spec = self.quote_spec(format);
+                spec.push(("if", "tag == 1".into()));
+                vec![
+                    ("tag".into(), Self::type_spec("u1")),
+                    ("value".into(), spec),
+                ]
+            }
+            Seq(format) | Set(format) => {
+                let mut spec = self.quote_spec(format);
+                spec.push(("repeat", "expr".into()));
+                spec.push(("repeat-expr", len_value.into()));
+                vec![len, ("items".into(), spec)]
+            }
+            Map { key, value } => {
+                let entry = format!("{}_entry", name);
+                self.output_type(
+                    &entry,
+                    vec![
+                        ("key".into(), self.quote_spec(key)),
+                        ("value".into(), self.quote_spec(value)),
+                    ],
+                )?;
+                let mut spec = Self::type_spec(&entry);
+                spec.push(("repeat", "expr".into()));
+                spec.push(("repeat-expr", len_value.into()));
+                vec![len, ("entries".into(), spec)]
+            }
+            Tuple(formats) => self.tuple_attributes(formats),
+            TupleArray { content, size } => {
+                let mut spec = self.quote_spec(content);
+                spec.push(("repeat", "expr".into()));
+                spec.push(("repeat-expr", size.to_string()));
+                vec![("items".into(), spec)]
+            }
+            _ => panic!("unexpected helper"),
+        };
+        self.output_type(name, attributes)
+    }
+}
+
+/// Installer for Kaitai Struct definitions: one `.ksy` file per encoding of the config.
+#[derive(Clone)]
+pub struct Installer {
+    install_dir: PathBuf,
+    root_type: Option<String>,
+}
+
+impl Installer {
+    pub fn new(install_dir: PathBuf) -> Self {
+        Installer {
+            install_dir,
+            root_type: None,
+        }
+    }
+
+    /// Container parsed at the top level of the files.
+    pub fn with_root_type(mut self, root_type: Option<String>) -> Self {
+        self.root_type = root_type;
+        self
+    }
+}
+
+impl crate::SourceInstaller for Installer {
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
+
+    fn install_module(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &Registry,
+    ) -> std::result::Result<(), Self::Error> {
+        std::fs::create_dir_all(&self.install_dir)?;
+        let mut encodings: Vec<_> = config.encodings.iter().cloned().collect();
+        if encodings.is_empty() {
+            encodings.push(config.single_encoding());
+        }
+        for encoding in encodings {
+            let path = self.install_dir.join(format!(
+                "{}_{}.ksy",
+                config.module_name.replace('.', "_").to_snake_case(),
+                encoding.name()
+            ));
+            let mut file = common::create_source_file(config, path)?;
+            let generator = CodeGenerator::new(config)
+                .with_encoding(encoding)
+                .with_root_type(self.root_type.clone());
+            generator.output(&mut file, registry)?;
+        }
+        Ok(())
+    }
+
+    // Kaitai Struct definitions do not depend on a runtime provided by this crate.
+
+    fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn install_encoding_runtime(
+        &self,
+        _encoding: Encoding,
+    ) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
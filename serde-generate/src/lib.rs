@@ -19,6 +19,15 @@
 //!
 //! * TypeScript > 3.2 (make sure to enable `esnext.BigInt` and `dom` at tsconfig.json -> lib)
 //!
+//! In addition, `--language wireshark` generates a Lua dissector so that captures of framed messages
+//! can be inspected field by field in Wireshark (see `--root-type`, `--framing`, and `--port`).
+//! Likewise, `--language kaitai` describes the binary layout of each container in Kaitai Struct (`.ksy`)
+//! files, one per encoding.
+//! Finally, `--language sql` maps structs to `CREATE TABLE` statements for Postgres or ClickHouse
+//! (see `--sql-dialect`), using JSON columns for nested values.
+//! `--language arrow` generates Rust functions returning the Apache Arrow schema of each container
+//! (using the `arrow` crate), so that decoded messages can be written to Parquet.
+//!
 //! ## Supported Encodings
 //!
 //! Type definitions in a target language are meant to be used together with a runtime library that
@@ -43,7 +52,7 @@
 //!     b: (u32, u32),
 //! }
 //!
-//! # fn main() -> Result<(), std::io::Error> {
+//! # fn main() -> Result<(), serde_generate::Error> {
 //! // Obtain the Serde format of `Test`. (In practice, formats are more often read from a file.)
 //! let mut tracer = Tracer::new(TracerConfig::default());
 //! tracer.trace_type::<Test>(&Samples::new()).unwrap();
@@ -105,12 +114,99 @@
 //! cargo run -p serde-generate -- --language python3 --with-runtimes serde bincode --module-name test --target-source-dir "$DEST" test.yaml
 //! ```
 //!
+//! To adapt third-party formats to local conventions, `--transform` rewrites them before code generation, e.g.
+//! `--transform strip-namespaces --transform rename:Hash=Digest --transform inline-newtypes`. Formats may also be
+//! replaced with `--transform 'replace:BYTES={TUPLEARRAY: {CONTENT: U8, SIZE: 32}}'`. Library users may implement
+//! their own rewrites with the trait `transform::RegistryTransform`.
+//!
+//! Types of third-party crates traced with `TracerConfig::map_external_type`, e.g. `{CUSTOM: {NAME: Uuid, LAYOUT: BYTES}}`,
+//! may be replaced by native types: `--transform external-type:Uuid=uuid` turns them into references to `Uuid`, as
+//! provided by the external module `uuid` (see `CodeGeneratorConfig::with_external_definitions`).
+//!
+//! Fields using `serde_bytes` or `bytes::Bytes` are traced as `BYTES`, whereas plain `Vec<u8>` fields are traced as
+//! sequences of `U8`. `--transform seq-u8-as-bytes` converts the latter to `BYTES`, without changing the wire layout,
+//! so that generated code uses the native byte arrays of each language.
+//!
+//! Fields recorded as skipped by the tracer, e.g. `cache: {SKIPPED: BYTES}`, are absent from the wire. Code generators
+//! leave them out of the generated definitions, while the registry keeps documenting them.
+//!
 //! See the help message of the tool with `--help` for more options.
 //!
+//! To guard against accidental changes of the wire layout, `serdegen lock test.yaml --lockfile test.lock`
+//! records the formats in a lockfile, then fails on later runs if a locked container has changed.
+//!
+//! For finer-grained checks, `compatibility::check_compatibility(&old, &new, Encoding::Bcs)` classifies each change
+//! between two registries as compatible or breaking for an encoding, i.e. whether values encoded with the old formats are
+//! still decoded identically. E.g. renaming a field or appending a variant is compatible, whereas removing a field is not.
+//! The report lists the violations. Registries with formats that were not fully traced are rejected with `Error::Registry`.
+//! With `compatibility::check_compatibility_with_config`, removing a container, a field, or a variant marked as deprecated
+//! (see `CodeGeneratorConfig::with_deprecations`) is classified as a deprecated removal instead of a violation.
+//!
+//! For quick protocol debugging, `serdegen repl test.yaml` encodes JSON values into hexadecimal bytes
+//! and decodes them back.
+//!
+//! Similarly, `serdegen proxy test.yaml --upstream <address> --request-type <Type> --response-type <Type>`
+//! forwards TCP connections to a service and logs the length-prefixed messages in both directions as JSON.
+//!
+//! In CI pipelines, `--message-format json` prints diagnostics on the standard error as one JSON object per line,
+//! with a severity, a stable code (e.g. `invalid-formats`, `wire-layout-change`, or `generation-failed`), a message,
+//! and when available a file, a line and column, and a path within the formats. `serdegen` exits with status 1 when
+//! an error is reported, and with status 2 on invalid command-line arguments.
+//!
+//! For long generations, `--progress` adds a note (code `generated-file`) for each generated source file, with its
+//! size and generation time. Libraries may pass their own function to `CodeGeneratorConfig::with_progress_reporter`,
+//! which installers call after writing each source file of a module.
+//!
+//! Library users install the runtime of an encoding with `installer.install_encoding_runtime(encoding)`, or those of the
+//! encodings enabled in a configuration with `installer.install_encoding_runtimes(&config)`, so that unused runtimes are
+//! left out.
+//!
+//! With `--with-runtime-tests`, or `installer.install_runtime_tests(&config)` for library users, the Python, Java, and C#
+//! installers also install the unit tests of the selected runtimes (e.g. `bcs/test_bcs.py`, `com/novi/bcs/BcsTest.java`, or
+//! the `Serde.Tests` project), so that consumers can check the vendored runtimes with their own toolchain versions.
+//!
+//! To check the byte-level conformance of the installed runtimes, `--with-corpus-runner`, or
+//! `installer.install_corpus_runner(&config)`, makes the Python and Java installers generate the types of a shared corpus
+//! of BCS and bincode test vectors as the module `serde_corpus`, copy the vectors of the selected encodings, and add a
+//! runner (`test_serde_corpus.py` or `serde_corpus.CorpusTest`) checking that every vector decodes and encodes back to the
+//! same bytes. The corpus covers corner cases such as extreme integers, ULEB128 lengths, multi-byte UTF-8, and maps in
+//! canonical order. In Rust, see `test_vectors::corpus()`.
+//!
+//! Instead of vendoring runtime sources in every generated package, Dart and C# installers may depend on a published
+//! package providing the runtimes, e.g. `dart::Installer::new(dir).with_runtime_source(RuntimeSource::Package { name, version })`
+//! or `--runtime-package serde_runtimes:0.1.0` on the command line. The package is then declared in `pubspec.yaml` or in
+//! the `.csproj` file of generated modules, and installing runtimes writes nothing. The command line rejects
+//! `--runtime-package` for the other languages.
+//!
+//! Build systems that install generated sources incrementally may stage installations instead of writing files
+//! directly: `installer.plan(steps)` runs installation steps such as `|installer| installer.install_module(&config, &registry)`
+//! in a temporary directory and reports which files would be created, updated, left unchanged, or removed.
+//! `installer.install(steps, policy)` then writes the new and changed files according to an `install::OverwritePolicy`
+//! (overwrite, keep existing files, or fail), removes the files of the previous installation that are no longer generated,
+//! and records the installed files in a manifest `.serdegen-manifest`. `installer.uninstall()` removes them.
+//!
+//! To restart a generation promptly when the registry changes (e.g. in an editor), pass a `CancellationToken` to
+//! `CodeGeneratorConfig::with_cancellation_token`. Calling `token.cancel()` from another thread makes code generators
+//! and installers fail with `Error::Cancelled` before the next container.
+//!
+//! Code generators and installers report failures with `serde_generate::Error` rather than panicking:
+//! `UnsupportedFormat` names the language, container, and field of formats that cannot be generated (e.g. unresolved
+//! formats or nested `SKIPPED` formats), `InvalidConfig` reports options not supported by a language (e.g. C-style enums
+//! in C++) or conflicting names, `Registry` reports registries that cannot be generated, `Cancelled` reports cancelled
+//! generations, and `Io` wraps I/O errors.
+//!
+//! Shell completions are printed by `serdegen completions <shell>` (e.g. `bash`, `zsh`, or `fish`). Build tools
+//! wrapping the code generator may call `serdegen describe --json` to list the supported languages, encodings,
+//! runtimes, and language-specific options instead of hardcoding them.
+//!
 //! Note: Outside of this repository, you may install the tool with `cargo install serde-generate` then use `$HOME/.cargo/bin/serdegen`.
 
 /// Dependency analysis and topological sort for Serde formats.
 pub mod analyzer;
+/// Support for the generation of Apache Arrow schemas in Rust
+pub mod arrow;
+/// Classification of registry changes as compatible or breaking for each encoding.
+pub mod compatibility;
 /// Support for code-generation in C++
 pub mod cpp;
 /// Support for code-generation in C#
@@ -121,14 +217,36 @@ pub mod dart;
 pub mod golang;
 /// Support for code-generation in Go
 pub mod indent;
+/// Staged installation of generated sources, with dry runs and uninstallation.
+pub mod install;
 /// Support for code-generation in Java
 pub mod java;
+/// Support for the generation of Kaitai Struct definitions
+pub mod kaitai;
+/// Detection of wire-layout changes against a lockfile.
+pub mod lock;
+/// A language server for YAML-encoded registries.
+#[cfg(feature = "lsp")]
+pub mod lsp;
 /// Support for code-generation in Python 3
 pub mod python3;
 /// Support for code-generation in Rust
 pub mod rust;
+/// A schema registry service storing Serde formats by fingerprint, and its client.
+#[cfg(feature = "schema-registry")]
+pub mod schema_registry;
+/// Support for the generation of SQL tables
+pub mod sql;
+/// Portable test vectors to check the conformance of implementations.
+pub mod test_vectors;
+/// Dynamic encoding and decoding of values following Serde formats.
+pub mod transcode;
+/// Rewrites of registries, e.g. renaming containers, before code generation.
+pub mod transform;
 /// Support for code-generation in TypeScript/JavaScript
 pub mod typescript;
+/// Support for the generation of Wireshark dissectors in Lua
+pub mod wireshark;
 
 #[doc(hidden)]
 /// Utility functions to help testing code generators.
@@ -138,5 +256,8 @@ pub mod test_utils;
 mod common;
 /// Common configuration objects and traits used in public APIs.
 mod config;
+/// Error type of the code generators and source installers.
+mod error;
 
 pub use config::*;
+pub use error::{Error, Result};
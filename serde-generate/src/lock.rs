@@ -0,0 +1,251 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+
+/// A difference between the wire layout recorded in a lockfile and the current formats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WireChange {
+    /// Location of the change, starting with the container name.
+    pub path: Vec<String>,
+    /// Human-readable description of the change.
+    pub description: String,
+}
+
+impl std::fmt::Display for WireChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.join("."), self.description)
+    }
+}
+
+/// Compare the current formats against the layout recorded in a lockfile.
+/// * Locked containers must keep their exact kind, field order, formats, and variant indices.
+/// * Adding new containers, or new variants at unused indices, is not a wire change.
+pub fn check_wire_layout(locked: &Registry, current: &Registry) -> Vec<WireChange> {
+    let mut changes = Vec::new();
+    for (name, locked_format) in locked {
+        let path = vec![name.clone()];
+        match current.get(name) {
+            None => push(&mut changes, &path, "container was removed".to_string()),
+            Some(current_format) => {
+                check_container(&mut changes, &path, locked_format, current_format)
+            }
+        }
+    }
+    changes
+}
+
+fn push(changes: &mut Vec<WireChange>, path: &[String], description: String) {
+    changes.push(WireChange {
+        path: path.to_vec(),
+        description,
+    });
+}
+
+fn extend(path: &[String], name: &str) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(name.to_string());
+    path
+}
+
+fn container_kind(format: &ContainerFormat) -> &'static str {
+    use ContainerFormat::*;
+    match format {
+        UnitStruct => "unit struct",
+        NewTypeStruct(_) => "newtype struct",
+        TupleStruct(_) => "tuple struct",
+        Struct(_) => "struct",
+        Enum(_) => "enum",
+        InternallyTaggedEnum { .. } => "internally tagged enum",
+    }
+}
+
+fn variant_kind(format: &VariantFormat) -> &'static str {
+    use VariantFormat::*;
+    match format {
+        Variable(_) => "unknown variant",
+        Unit => "unit variant",
+        NewType(_) => "newtype variant",
+        Tuple(_) => "tuple variant",
+        Struct(_) => "struct variant",
+    }
+}
+
+fn check_container(
+    changes: &mut Vec<WireChange>,
+    path: &[String],
+    locked: &ContainerFormat,
+    current: &ContainerFormat,
+) {
+    use ContainerFormat::*;
+    match (locked, current) {
+        (UnitStruct, UnitStruct) => (),
+        (NewTypeStruct(locked), NewTypeStruct(current)) => {
+            check_format(changes, path, locked, current)
+        }
+        (TupleStruct(locked), TupleStruct(current)) => {
+            check_formats(changes, path, locked, current)
+        }
+        (Struct(locked), Struct(current)) => check_fields(changes, path, locked, current),
+        (Enum(locked), Enum(current)) => {
+            for (index, locked_variant) in locked {
+                match current.get(index) {
+                    None => push(
+                        changes,
+                        path,
+                        format!("variant #{} `{}` was removed", index, locked_variant.name),
+                    ),
+                    Some(current_variant) => {
+                        if locked_variant.name != current_variant.name {
+                            push(
+                                changes,
+                                path,
+                                format!(
+                                    "variant #{} was renamed from `{}` to `{}`",
+                                    index, locked_variant.name, current_variant.name
+                                ),
+                            );
+                        }
+                        check_variant(
+                            changes,
+                            &extend(path, &locked_variant.name),
+                            &locked_variant.value,
+                            &current_variant.value,
+                        );
+                    }
+                }
+            }
+        }
+        (
+            InternallyTaggedEnum {
+                variants: locked, ..
+            },
+            InternallyTaggedEnum {
+                variants: current, ..
+            },
+        ) => {
+            // Variants are identified by name on the wire, hence indices do not matter.
+            for locked_variant in locked.values() {
+                match current.values().find(|v| v.name == locked_variant.name) {
+                    None => push(
+                        changes,
+                        path,
+                        format!("variant `{}` was removed", locked_variant.name),
+                    ),
+                    Some(current_variant) => check_variant(
+                        changes,
+                        &extend(path, &locked_variant.name),
+                        &locked_variant.value,
+                        &current_variant.value,
+                    ),
+                }
+            }
+        }
+        _ => push(
+            changes,
+            path,
+            format!(
+                "container changed from {} to {}",
+                container_kind(locked),
+                container_kind(current)
+            ),
+        ),
+    }
+}
+
+fn check_variant(
+    changes: &mut Vec<WireChange>,
+    path: &[String],
+    locked: &VariantFormat,
+    current: &VariantFormat,
+) {
+    use VariantFormat::*;
+    match (locked, current) {
+        (Unit, Unit) => (),
+        (NewType(locked), NewType(current)) => check_format(changes, path, locked, current),
+        (Tuple(locked), Tuple(current)) => check_formats(changes, path, locked, current),
+        (Struct(locked), Struct(current)) => check_fields(changes, path, locked, current),
+        _ => push(
+            changes,
+            path,
+            format!(
+                "variant changed from {} to {}",
+                variant_kind(locked),
+                variant_kind(current)
+            ),
+        ),
+    }
+}
+
+fn check_format(changes: &mut Vec<WireChange>, path: &[String], locked: &Format, current: &Format) {
+    if locked != current {
+        push(
+            changes,
+            path,
+            format!("format changed from `{:?}` to `{:?}`", locked, current),
+        );
+    }
+}
+
+fn check_formats(
+    changes: &mut Vec<WireChange>,
+    path: &[String],
+    locked: &[Format],
+    current: &[Format],
+) {
+    if locked.len() != current.len() {
+        push(
+            changes,
+            path,
+            format!(
+                "number of components changed from {} to {}",
+                locked.len(),
+                current.len()
+            ),
+        );
+    }
+    for (index, (locked, current)) in locked.iter().zip(current).enumerate() {
+        check_format(changes, &extend(path, &index.to_string()), locked, current);
+    }
+}
+
+fn check_fields(
+    changes: &mut Vec<WireChange>,
+    path: &[String],
+    locked: &[Named<Format>],
+    current: &[Named<Format>],
+) {
+    for index in 0..std::cmp::max(locked.len(), current.len()) {
+        match (locked.get(index), current.get(index)) {
+            (Some(locked), Some(current)) => {
+                if locked.name != current.name {
+                    push(
+                        changes,
+                        path,
+                        format!(
+                            "field #{} changed from `{}` to `{}`",
+                            index, locked.name, current.name
+                        ),
+                    );
+                }
+                check_format(
+                    changes,
+                    &extend(path, &locked.name),
+                    &locked.value,
+                    &current.value,
+                );
+            }
+            (Some(locked), None) => push(
+                changes,
+                path,
+                format!("field #{} `{}` was removed", index, locked.name),
+            ),
+            (None, Some(current)) => push(
+                changes,
+                path,
+                format!("field #{} `{}` was added", index, current.name),
+            ),
+            (None, None) => unreachable!(),
+        }
+    }
+}
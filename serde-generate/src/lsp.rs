@@ -0,0 +1,419 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{common, transcode};
+use serde_reflection::{Format, FormatHolder, Registry};
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Error, ErrorKind, Result, Write};
+
+/// Language server for YAML-encoded registries, speaking a minimal subset of the Language
+/// Server Protocol over JSON-RPC:
+/// * diagnostics for YAML errors, references to undefined containers, and formats rejected by
+///   code generators,
+/// * hover showing the format of the container under the cursor,
+/// * go-to-definition of containers,
+/// * completion of container names.
+///
+/// Documents are synchronized in full.
+#[derive(Default)]
+pub struct LanguageServer {
+    documents: BTreeMap<String, String>,
+}
+
+impl LanguageServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle the messages read from `input` until the `exit` notification or the end of the
+    /// input, writing responses and notifications to `output`.
+    pub fn serve<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            let method = message["method"].as_str().unwrap_or_default().to_string();
+            if method == "exit" {
+                break;
+            }
+            for response in self.handle(&method, &message["params"], &message["id"]) {
+                write_message(&mut output, &response)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, method: &str, params: &Value, id: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let result = match method {
+            "initialize" => object(vec![(
+                "capabilities",
+                object(vec![
+                    ("textDocumentSync", Value::from(1)),
+                    ("hoverProvider", Value::from(true)),
+                    ("definitionProvider", Value::from(true)),
+                    ("completionProvider", object(Vec::new())),
+                ]),
+            )]),
+            "shutdown" => Value::Null,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didChange" => {
+                // Full synchronization: the last change holds the whole text.
+                if let Some(text) = params["contentChanges"]
+                    .as_sequence()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![notification(
+                    "textDocument/publishDiagnostics",
+                    object(vec![
+                        ("uri", Value::from(uri)),
+                        ("diagnostics", Value::Sequence(Vec::new())),
+                    ]),
+                )];
+            }
+            "textDocument/hover" => self.hover(&uri, &params["position"]),
+            "textDocument/definition" => self.definition(&uri, &params["position"]),
+            "textDocument/completion" => self.completion(&uri),
+            _ => {
+                if id.is_null() {
+                    // Unknown notifications are ignored.
+                    return Vec::new();
+                }
+                return vec![object(vec![
+                    ("jsonrpc", Value::from("2.0")),
+                    ("id", id.clone()),
+                    (
+                        "error",
+                        object(vec![
+                            ("code", Value::from(-32601)),
+                            ("message", Value::from(format!("Unknown method {}", method))),
+                        ]),
+                    ),
+                ])];
+            }
+        };
+        if id.is_null() {
+            return Vec::new();
+        }
+        vec![object(vec![
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id.clone()),
+            ("result", result),
+        ])]
+    }
+
+    fn text(&self, uri: &str) -> &str {
+        self.documents
+            .get(uri)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let diagnostics = diagnostics(self.text(uri))
+            .into_iter()
+            .map(|(line, column, message)| {
+                object(vec![
+                    ("range", range(line, column, column)),
+                    ("severity", Value::from(1)),
+                    ("source", Value::from("serde-reflection")),
+                    ("message", Value::from(message)),
+                ])
+            })
+            .collect();
+        notification(
+            "textDocument/publishDiagnostics",
+            object(vec![
+                ("uri", Value::from(uri)),
+                ("diagnostics", Value::Sequence(diagnostics)),
+            ]),
+        )
+    }
+
+    fn hover(&self, uri: &str, position: &Value) -> Value {
+        let text = self.text(uri);
+        let registry = match serde_yaml::from_str::<Registry>(text) {
+            Ok(registry) => registry,
+            Err(_) => return Value::Null,
+        };
+        let name = match word_at(text, position) {
+            Some(name) => name,
+            None => return Value::Null,
+        };
+        let format = match registry.get(&name) {
+            Some(format) => format,
+            None => return Value::Null,
+        };
+        let mut entry = BTreeMap::new();
+        entry.insert(name, format);
+        let content = serde_yaml::to_string(&entry).unwrap_or_default();
+        let content = content.trim_start_matches("---\n").trim_end();
+        object(vec![(
+            "contents",
+            object(vec![
+                ("kind", Value::from("markdown")),
+                ("value", Value::from(format!("```yaml\n{}\n```", content))),
+            ]),
+        )])
+    }
+
+    fn definition(&self, uri: &str, position: &Value) -> Value {
+        let text = self.text(uri);
+        let location = word_at(text, position).and_then(|name| find_definition(text, &name));
+        match location {
+            Some(line) => object(vec![
+                ("uri", Value::from(uri)),
+                ("range", range(line, 0, 0)),
+            ]),
+            None => Value::Null,
+        }
+    }
+
+    fn completion(&self, uri: &str) -> Value {
+        let items = container_names(self.text(uri))
+            .into_iter()
+            .map(|name| {
+                object(vec![
+                    ("label", Value::from(name)),
+                    // Completion item kind "Class".
+                    ("kind", Value::from(7)),
+                ])
+            })
+            .collect();
+        Value::Sequence(items)
+    }
+}
+
+/// Validate a YAML-encoded registry. Return the 0-based line and column of each problem,
+/// together with a message. Columns are counted in UTF-16 code units, as in LSP positions.
+pub fn diagnostics(text: &str) -> Vec<(usize, usize, String)> {
+    let registry = match serde_yaml::from_str::<Registry>(text) {
+        Ok(registry) => registry,
+        Err(error) => {
+            let (line, column) = error
+                .location()
+                .map(|location| {
+                    let line = location.line().saturating_sub(1);
+                    let column = location.column().saturating_sub(1);
+                    let prefix: String = text
+                        .lines()
+                        .nth(line)
+                        .unwrap_or_default()
+                        .chars()
+                        .take(column)
+                        .collect();
+                    (line, prefix.encode_utf16().count())
+                })
+                .unwrap_or((0, 0));
+            return vec![(line, column, error.to_string())];
+        }
+    };
+    let mut undefined = BTreeSet::new();
+    for format in registry.values() {
+        format
+            .visit(&mut |format| {
+                if let Format::TypeName(name) = format {
+                    if !registry.contains_key(name) {
+                        undefined.insert(name.clone());
+                    }
+                }
+                Ok(())
+            })
+            .ok();
+    }
+    let mut result: Vec<_> = undefined
+        .into_iter()
+        .map(|name| {
+            let (line, column) = find_reference(text, &name).unwrap_or((0, 0));
+            (line, column, format!("Undefined container {}", name))
+        })
+        .collect();
+    result.extend(validation_diagnostics(text, &registry));
+    result
+}
+
+/// Run the checks of code generators on the formats of `registry`, and report the first
+/// unsupported format of each container at the line of its definition.
+fn validation_diagnostics(text: &str, registry: &Registry) -> Vec<(usize, usize, String)> {
+    let mut registry = match common::without_skipped_fields(registry) {
+        Ok(registry) => registry.into_owned(),
+        Err(error) => return vec![(0, 0, error.to_string())],
+    };
+    let mut result = Vec::new();
+    loop {
+        match common::check_formats("Registry", &registry) {
+            Ok(()) => break,
+            Err(crate::Error::UnsupportedFormat {
+                container,
+                field,
+                format,
+                ..
+            }) => {
+                let line = find_definition(text, &container).unwrap_or(0);
+                let message = match field {
+                    Some(field) => format!(
+                        "Unsupported format {} in field {} of {}",
+                        format, field, container
+                    ),
+                    None => format!("Unsupported format {} in container {}", format, container),
+                };
+                result.push((line, 0, message));
+                registry.remove(&container);
+            }
+            Err(error) => {
+                result.push((0, 0, error.to_string()));
+                break;
+            }
+        }
+    }
+    result
+}
+
+/// Names of the containers defined at the top level of the document, even if it does not
+/// parse as a registry.
+fn container_names(text: &str) -> Vec<String> {
+    text.lines()
+        .filter(|line| !line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '-'))
+        .filter_map(|line| line.split(':').next())
+        .map(|name| name.trim().trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Line where the container `name` is defined.
+fn find_definition(text: &str, name: &str) -> Option<usize> {
+    text.lines().position(|line| {
+        line.strip_prefix(name)
+            .or_else(|| line.strip_prefix(&format!("\"{}\"", name)))
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    })
+}
+
+/// Line and column (in UTF-16 code units) of the first reference to the container `name`.
+fn find_reference(text: &str, name: &str) -> Option<(usize, usize)> {
+    text.lines().enumerate().find_map(|(index, line)| {
+        let start = line.find("TYPENAME")? + "TYPENAME".len();
+        let offset = line[start..].match_indices(name).find_map(|(offset, _)| {
+            let offset = start + offset;
+            let before = line[..offset].chars().next_back();
+            let after = line[offset + name.len()..].chars().next();
+            if !matches!(before, Some(c) if is_identifier(c))
+                && !matches!(after, Some(c) if is_identifier(c))
+            {
+                Some(offset)
+            } else {
+                None
+            }
+        })?;
+        Some((index, line[..offset].encode_utf16().count()))
+    })
+}
+
+/// Whether `c` may appear in container names, including qualified ones.
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == ':' || c == '.'
+}
+
+/// Container name (or any other identifier) at the given LSP position, whose character offset
+/// is counted in UTF-16 code units.
+fn word_at(text: &str, position: &Value) -> Option<String> {
+    let line = text.lines().nth(position["line"].as_u64()? as usize)?;
+    let character = position["character"].as_u64()? as usize;
+    let chars: Vec<char> = line.chars().collect();
+    let mut units = 0;
+    let column = chars
+        .iter()
+        .position(|c| {
+            units += c.len_utf16();
+            units > character
+        })
+        .unwrap_or(chars.len());
+    let is_word = |c: &&char| is_identifier(**c);
+    let start = column - chars[..column].iter().rev().take_while(is_word).count();
+    let end = column + chars[column..].iter().take_while(is_word).count();
+    let word: String = chars[start..end].iter().collect();
+    let word = word.trim_end_matches(':');
+    if word.is_empty() {
+        None
+    } else {
+        Some(word.to_string())
+    }
+}
+
+fn object(entries: Vec<(&str, Value)>) -> Value {
+    let mut mapping = Mapping::new();
+    for (key, value) in entries {
+        mapping.insert(Value::from(key), value);
+    }
+    Value::Mapping(mapping)
+}
+
+fn range(line: usize, start: usize, end: usize) -> Value {
+    let position = |character: usize| {
+        object(vec![
+            ("line", Value::from(line as u64)),
+            ("character", Value::from(character as u64)),
+        ])
+    };
+    object(vec![("start", position(start)), ("end", position(end))])
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    object(vec![
+        ("jsonrpc", Value::from("2.0")),
+        ("method", Value::from(method)),
+        ("params", params),
+    ])
+}
+
+/// Read a JSON-RPC message framed with a `Content-Length` header. Return `None` at the end of
+/// the input.
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.trim().is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        let mut parts = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length =
+                    Some(value.trim().parse().map_err(|_| {
+                        Error::new(ErrorKind::InvalidData, "Invalid content length")
+                    })?);
+            }
+        }
+    }
+    let mut body = vec![0; length.unwrap_or_default()];
+    reader.read_exact(&mut body)?;
+    // JSON documents are YAML documents.
+    serde_yaml::from_slice(&body)
+        .map(Some)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Write a JSON-RPC message framed with a `Content-Length` header.
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = transcode::to_json(message);
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
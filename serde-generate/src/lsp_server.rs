@@ -0,0 +1,18 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Language server for Serde formats
+//!
+//! '''bash
+//! cargo run --features lsp --bin serdegen-lsp
+//! '''
+
+use serde_generate::lsp::LanguageServer;
+
+fn main() {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    LanguageServer::new()
+        .serve(stdin.lock(), stdout.lock())
+        .expect("failed to communicate with the editor");
+}
@@ -2,13 +2,15 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
+    common::{self, AddressFormat, ConfigOption},
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    CodeGeneratorConfig, Encoding, OutputLayout, VariantNaming,
 };
+use heck::{ShoutySnakeCase, SnakeCase};
 use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::collections::{BTreeMap, HashMap};
 use std::io::{Result, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Main configuration object for code-generation in Python.
 pub struct CodeGenerator<'a> {
@@ -16,6 +18,10 @@ pub struct CodeGenerator<'a> {
     config: &'a CodeGeneratorConfig,
     /// Whether the module providing Serde definitions is located within package.
     serde_package_name: Option<String>,
+    /// Whether to generate Pydantic dataclasses with validators rather than plain dataclasses.
+    pydantic: bool,
+    /// Whether to embed the Serde runtimes in the generated module.
+    embedded_runtime: bool,
     /// Mapping from external type names to suitably qualified names (e.g. "MyClass" -> "my_module.MyClass").
     /// Assumes suitable imports (e.g. "from my_package import my_module").
     /// Derived from `config.external_definitions`.
@@ -30,14 +36,13 @@ struct PythonEmitter<'a, T> {
     generator: &'a CodeGenerator<'a>,
     /// Current namespace (e.g. vec!["my_package", "my_module", "MyClass"])
     current_namespace: Vec<String>,
+    /// Containers mapped to the network addresses of `ipaddress`, indexed by name.
+    addresses: BTreeMap<String, AddressFormat>,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a Python code generator for the given config.
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Python 3 does not support generating c-style enums");
-        }
         let mut external_qualified_names = HashMap::new();
         for (module_path, names) in &config.external_definitions {
             let module = {
@@ -55,6 +60,8 @@ impl<'a> CodeGenerator<'a> {
         Self {
             config,
             serde_package_name: None,
+            pydantic: false,
+            embedded_runtime: false,
             external_qualified_names,
         }
     }
@@ -65,8 +72,41 @@ impl<'a> CodeGenerator<'a> {
         self
     }
 
+    /// Whether to generate Pydantic (v2) dataclasses with validators rather than plain dataclasses.
+    /// Validated classes require Python 3.9 and remain compatible with the binary runtimes.
+    pub fn with_pydantic(mut self, pydantic: bool) -> Self {
+        self.pydantic = pydantic;
+        self
+    }
+
+    /// Whether to embed the Serde runtimes (`serde_types`, the selected encodings, etc.) in the
+    /// generated module, so that it can be imported or pasted into a notebook without installing
+    /// any package. The serde package name is then ignored.
+    pub fn with_embedded_runtime(mut self, embedded_runtime: bool) -> Self {
+        self.embedded_runtime = embedded_runtime;
+        self
+    }
+
     /// Write container definitions in Python.
-    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        if self.config.c_style_enums {
+            return Err(crate::Error::InvalidConfig(
+                "Python 3 does not support generating c-style enums".to_string(),
+            ));
+        }
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("Python 3", registry)?;
+        common::check_options(
+            "Python 3",
+            self.config,
+            &[
+                ConfigOption::ImmutableCollections,
+                ConfigOption::RootFormats,
+                ConfigOption::TypeAliases,
+                ConfigOption::Fixtures,
+            ],
+        )?;
         let current_namespace = self
             .config
             .module_name
@@ -77,10 +117,77 @@ impl<'a> CodeGenerator<'a> {
             out: IndentedWriter::new(out, IndentConfig::Space(4)),
             generator: self,
             current_namespace,
+            addresses: common::address_formats(registry),
         };
-        emitter.output_preamble()?;
-        for (name, format) in registry {
-            emitter.output_container(name, format)?;
+        emitter.output_preamble(registry)?;
+        if self.config.type_aliases {
+            emitter.output_type_aliases(registry)?;
+        }
+        for (name, format) in common::ordered_containers(self.config, registry)? {
+            common::check_cancellation(self.config)?;
+            // Network addresses are defined by the runtime.
+            if !emitter.addresses.contains_key(name) {
+                emitter.output_container(name, format)?;
+            }
+        }
+        if emitter.has_record_logs() {
+            for name in &self.config.record_log_types {
+                let fingerprint = common::schema_fingerprint(registry, name)?;
+                emitter.output_record_log(name, fingerprint)?;
+            }
+        }
+        if emitter.has_root_formats() {
+            for (name, format) in &self.config.root_formats {
+                emitter.output_root_format(name, format)?;
+            }
+        }
+        if self.config.embedded_schema {
+            emitter.output_embedded_schema(registry)?;
+        }
+        Ok(())
+    }
+
+    /// Output test fixtures, i.e. a class `FooFixtures` with a static method `sample()` for each
+    /// container `Foo`, as a separate module importing the generated module. Fixtures of
+    /// external definitions are imported from the `fixtures` submodule of their module.
+    pub fn output_fixtures(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        if self.config.c_style_enums {
+            return Err(crate::Error::InvalidConfig(
+                "Python 3 does not support generating c-style enums".to_string(),
+            ));
+        }
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("Python 3", registry)?;
+        common::check_options(
+            "Python 3",
+            self.config,
+            &[
+                ConfigOption::ImmutableCollections,
+                ConfigOption::RootFormats,
+                ConfigOption::TypeAliases,
+                ConfigOption::Fixtures,
+            ],
+        )?;
+        let current_namespace = self
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect();
+        let mut emitter = PythonEmitter {
+            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            generator: self,
+            current_namespace,
+            addresses: common::address_formats(registry),
+        };
+        let containers = common::ordered_containers(self.config, registry)?
+            .into_iter()
+            .filter(|(name, _)| !emitter.addresses.contains_key(*name))
+            .collect::<Vec<_>>();
+        emitter.output_fixtures_preamble(registry, &containers)?;
+        for (name, format) in containers {
+            emitter.output_fixture(name, format)?;
         }
         Ok(())
     }
@@ -100,28 +207,199 @@ where
         }
     }
 
-    fn output_preamble(&mut self) -> Result<()> {
+    fn output_preamble(&mut self, registry: &Registry) -> Result<()> {
         let from_serde_package = match &self.generator.serde_package_name {
-            None => "".to_string(),
-            Some(name) => format!("from {} ", name),
+            Some(name) if !self.generator.embedded_runtime => format!("from {} ", name),
+            _ => "".to_string(),
         };
-        writeln!(
-            self.out,
-            r#"# pyre-strict
-from dataclasses import dataclass
+        writeln!(self.out, "# pyre-strict")?;
+        if self.generator.embedded_runtime {
+            self.output_embedded_runtimes()?;
+        }
+        if self.generator.pydantic {
+            writeln!(
+                self.out,
+                r#"from dataclasses import replace
+import pydantic
+from pydantic.dataclasses import dataclass
 import typing
 {}import serde_types as st"#,
-            from_serde_package,
-        )?;
+                from_serde_package,
+            )?;
+        } else {
+            writeln!(
+                self.out,
+                r#"from dataclasses import dataclass, replace
+import typing
+{}import serde_types as st"#,
+                from_serde_package,
+            )?;
+        }
         for encoding in &self.generator.config.encodings {
             writeln!(self.out, "{}import {}", from_serde_package, encoding.name())?;
         }
+        if common::custom_formats(registry)
+            .iter()
+            .any(|(name, layout)| common::TimeFormat::new(name, layout).is_some())
+        {
+            writeln!(self.out, "import datetime")?;
+        }
+        if common::has_decimal_formats(registry) {
+            writeln!(self.out, "import decimal")?;
+        }
+        if self.has_record_logs() {
+            writeln!(self.out, "{}import serde_record_log", from_serde_package)?;
+        }
+        if self.generator.config.embedded_schema {
+            writeln!(self.out, "import base64\nimport zlib")?;
+        }
+        if !self.generator.config.deprecations.is_empty() {
+            writeln!(self.out, "from typing_extensions import deprecated")?;
+        }
+        if self.generator.config.immutable_collections {
+            writeln!(self.out, "import types")?;
+        }
         for module in self.generator.config.external_definitions.keys() {
             writeln!(self.out, "{}\n", self.quote_import(module))?;
         }
+        if self.generator.pydantic {
+            self.output_pydantic_validators()?;
+        }
+        Ok(())
+    }
+
+    /// Define the runtime modules from their sources, unless they are already imported, so that
+    /// the usual imports of the generated code find them.
+    fn output_embedded_runtimes(&mut self) -> Result<()> {
+        let mut runtimes = vec![(
+            "serde_types",
+            include_str!("../runtime/python/serde_types/__init__.py"),
+        )];
+        if !self.generator.config.encodings.is_empty() {
+            runtimes.push((
+                "serde_binary",
+                include_str!("../runtime/python/serde_binary/__init__.py"),
+            ));
+        }
+        for encoding in &self.generator.config.encodings {
+            runtimes.push(match encoding {
+                Encoding::Bincode => (
+                    "bincode",
+                    include_str!("../runtime/python/bincode/__init__.py"),
+                ),
+                Encoding::Bcs => ("bcs", include_str!("../runtime/python/bcs/__init__.py")),
+            });
+        }
+        if self.has_record_logs() {
+            runtimes.push((
+                "serde_record_log",
+                include_str!("../runtime/python/serde_record_log/__init__.py"),
+            ));
+        }
+        writeln!(
+            self.out,
+            r#"
+# Serde runtimes embedded in this module.
+import sys as _sys
+import types as _types
+
+
+def _embed_runtime(name: str, source: str) -> None:
+    if name in _sys.modules:
+        return
+    module = _types.ModuleType(name)
+    _sys.modules[name] = module
+    exec(source, module.__dict__)
+"#
+        )?;
+        for (name, source) in runtimes {
+            // Escape the source as a triple-quoted Python string.
+            let source = source.replace('\\', "\\\\").replace("\"\"\"", "\\\"\"\"");
+            writeln!(
+                self.out,
+                "_embed_runtime({:?}, \"\"\"{}\"\"\")\n",
+                name, source
+            )?;
+        }
         Ok(())
     }
 
+    fn output_pydantic_validators(&mut self) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+_CONFIG = pydantic.ConfigDict(arbitrary_types_allowed=True)
+
+
+def _integer(cls: typing.Any, low: int, high: int) -> typing.Any:
+    def validate(value: typing.Any) -> typing.Any:
+        number = int(value)
+        if not low <= number <= high:
+            raise ValueError(f"{{number}} is out of range for {{cls.__name__}}")
+        return cls(number)
+
+    return pydantic.BeforeValidator(validate)
+
+
+def _char(value: typing.Any) -> st.char:
+    return value if isinstance(value, st.char) else st.char(value)
+
+
+def _unit(value: typing.Any) -> None:
+    if value is not None:
+        raise ValueError("expected None")
+    return None
+"#
+        )
+    }
+
+    /// Type annotation of a primitive format with the corresponding Pydantic validator.
+    fn quote_validated_type(&self, format: &Format) -> Option<String> {
+        use Format::*;
+        let integer = |name: &str, low: i128, high: u128| {
+            format!(
+                "typing.Annotated[st.{0}, _integer(st.{0}, {1}, {2})]",
+                name, low, high
+            )
+        };
+        let validated = match format {
+            Unit => "typing.Annotated[st.unit, pydantic.PlainValidator(_unit)]".into(),
+            I8 => integer("int8", i8::MIN.into(), i8::MAX as u128),
+            I16 => integer("int16", i16::MIN.into(), i16::MAX as u128),
+            I32 => integer("int32", i32::MIN.into(), i32::MAX as u128),
+            I64 => integer("int64", i64::MIN.into(), i64::MAX as u128),
+            I128 => integer("int128", i128::MIN, i128::MAX as u128),
+            U8 => integer("uint8", 0, u8::MAX.into()),
+            U16 => integer("uint16", 0, u16::MAX.into()),
+            U32 => integer("uint32", 0, u32::MAX.into()),
+            U64 => integer("uint64", 0, u64::MAX.into()),
+            U128 => integer("uint128", 0, u128::MAX),
+            F32 => "typing.Annotated[st.float32, pydantic.BeforeValidator(st.float32)]".into(),
+            F64 => "typing.Annotated[st.float64, pydantic.BeforeValidator(st.float64)]".into(),
+            Char => "typing.Annotated[st.char, pydantic.BeforeValidator(_char)]".into(),
+            Decimal { mantissa, scale } => format!(
+                "typing.Annotated[{}, pydantic.PlainValidator(decimal.Decimal)]",
+                Self::quote_fixed_point(mantissa, *scale)
+            ),
+            _ => return None,
+        };
+        Some(validated)
+    }
+
+    /// Type annotation of the `decimal.Decimal` values of a fixed-point decimal format.
+    fn quote_fixed_point(mantissa: &Format, scale: u32) -> String {
+        use Format::*;
+        let mantissa = match mantissa {
+            I8 => "int8",
+            I16 => "int16",
+            I32 => "int32",
+            I64 => "int64",
+            I128 => "int128",
+            _ => unreachable!("formats are checked by common::check_formats"),
+        };
+        format!("st.fixed_point(st.{}, {})", mantissa, scale)
+    }
+
     /// Compute a reference to the registry type `name`.
     /// Use a qualified name in case of external definitions.
     fn quote_qualified_name(&self, name: &str) -> String {
@@ -135,10 +413,26 @@ import typing
             })
     }
 
+    fn quote_variant_class(&self, base: &str, variant: &str) -> String {
+        let default = VariantNaming::Nested;
+        self.generator
+            .config
+            .variant_class_name(base, variant, &default)
+    }
+
     fn quote_type(&self, format: &Format) -> String {
         use Format::*;
+        if self.generator.pydantic {
+            if let Some(validated) = self.quote_validated_type(format) {
+                return validated;
+            }
+        }
         match format {
-            TypeName(x) => self.quote_qualified_name(x),
+            TypeName(x) => match self.addresses.get(x) {
+                Some(AddressFormat::IpAddr) => "st.ip_addr".into(),
+                Some(AddressFormat::SocketAddr) => "st.socket_addr".into(),
+                None => self.quote_qualified_name(x),
+            },
             Unit => "st.unit".into(),
             Bool => "bool".into(),
             I8 => "st.int8".into(),
@@ -159,8 +453,14 @@ import typing
 
             Option(format) => format!("typing.Optional[{}]", self.quote_type(format)),
             Seq(format) => format!("typing.Sequence[{}]", self.quote_type(format)),
+            Set(format) => format!("typing.FrozenSet[{}]", self.quote_type(format)),
             Map { key, value } => format!(
-                "typing.Dict[{}, {}]",
+                "typing.{}[{}, {}]",
+                if self.generator.config.immutable_collections {
+                    "Mapping"
+                } else {
+                    "Dict"
+                },
                 self.quote_type(key),
                 self.quote_type(value)
             ),
@@ -169,11 +469,36 @@ import typing
                 "typing.Tuple[{}]",
                 self.quote_types(&vec![content.as_ref().clone(); *size])
             ), // Sadly, there are no fixed-size arrays in python.
+            Custom { name, layout } => {
+                if let Some(time) = common::TimeFormat::of(format) {
+                    match time {
+                        common::TimeFormat::Duration => "datetime.timedelta".into(),
+                        common::TimeFormat::SystemTime => "datetime.datetime".into(),
+                    }
+                } else if self.generator.config.type_aliases {
+                    name.to_string()
+                } else {
+                    self.quote_type(layout)
+                }
+            }
+            Decimal { mantissa, scale } => Self::quote_fixed_point(mantissa, *scale),
 
-            Variable(_) => panic!("unexpected value"),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
         }
     }
 
+    fn output_type_aliases(&mut self, registry: &Registry) -> Result<()> {
+        for (name, layout) in common::custom_formats(registry) {
+            if common::TimeFormat::new(&name, &layout).is_some() {
+                continue;
+            }
+            writeln!(self.out, "\n{} = {}", name, self.quote_type(&layout))?;
+        }
+        Ok(())
+    }
+
     fn quote_types(&self, formats: &[Format]) -> String {
         formats
             .iter()
@@ -185,12 +510,30 @@ import typing
     fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
         let mut path = self.current_namespace.clone();
         path.push(name.to_string());
-        if let Some(doc) = self.generator.config.comments.get(&path) {
+        if let Some(doc) = self
+            .generator
+            .config
+            .comment_with_deprecation(&path, "Deprecated: ")
+        {
             writeln!(self.out, "\"\"\"{}\"\"\"", doc)?;
         }
         Ok(())
     }
 
+    /// The decorator `@deprecated` followed by a newline, if the class `path` is deprecated.
+    fn quote_deprecation(&self, path: &[String]) -> Option<String> {
+        let message = self.generator.config.deprecations.get(path)?;
+        Some(format!("@deprecated({:?})\n", message.trim()))
+    }
+
+    fn quote_decorator(&self) -> &'static str {
+        if self.generator.pydantic {
+            "@dataclass(frozen=True, config=_CONFIG)"
+        } else {
+            "@dataclass(frozen=True)"
+        }
+    }
+
     fn output_custom_code(&mut self) -> std::io::Result<bool> {
         match self
             .generator
@@ -212,6 +555,11 @@ import typing
             return Ok(());
         }
         for field in fields {
+            let mut path = self.current_namespace.clone();
+            path.push(field.name.clone());
+            if let Some(message) = self.generator.config.deprecations.get(&path) {
+                writeln!(self.out, "# Deprecated: {}", message.trim())?;
+            }
             writeln!(
                 self.out,
                 "{}: {}",
@@ -222,41 +570,167 @@ import typing
         Ok(())
     }
 
+    fn output_field_descriptors(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        writeln!(
+            self.out,
+            "\n@staticmethod\ndef fields() -> typing.Sequence[typing.Mapping[str, typing.Any]]:"
+        )?;
+        self.out.indent();
+        writeln!(self.out, "return [")?;
+        self.out.indent();
+        for field in fields {
+            let (kind, optional) = common::field_kind(&field.value);
+            writeln!(
+                self.out,
+                "{{\"name\": {:?}, \"kind\": {:?}, \"optional\": {}}},",
+                field.name,
+                kind,
+                if optional { "True" } else { "False" }
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "]")?;
+        self.out.unindent();
+        Ok(())
+    }
+
+    /// Check the values of nonzero and bounded integer fields when instances are created.
+    /// Output `__post_init__`, checking integer constraints and making collections immutable
+    /// if requested.
+    fn output_post_init(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        let constraints =
+            common::integer_constraints(self.generator.config, &self.current_namespace, fields);
+        let conversions = fields
+            .iter()
+            .filter_map(|field| {
+                let value = self.quote_immutable(&format!("self.{}", field.name), &field.value)?;
+                Some((field.name.as_str(), value))
+            })
+            .collect::<Vec<_>>();
+        if constraints.is_empty() && conversions.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.out, "\ndef __post_init__(self) -> None:")?;
+        self.out.indent();
+        // Classes are frozen, hence `object.__setattr__`.
+        for (name, value) in conversions {
+            writeln!(self.out, "object.__setattr__(self, {:?}, {})", name, value)?;
+        }
+        for (field, _, constraint) in constraints {
+            let condition = constraint
+                .comparisons()
+                .iter()
+                .map(|(operator, bound)| format!("self.{} {} {}", field.name, operator, bound))
+                .collect::<Vec<_>>()
+                .join(" and ");
+            writeln!(
+                self.out,
+                "if not ({}):\n    raise ValueError(\"{} must be {}\")",
+                condition,
+                field.name,
+                constraint.description()
+            )?;
+        }
+        self.out.unindent();
+        Ok(())
+    }
+
+    /// An immutable copy of `value` if `format` is a sequence, a set, or a map and immutable
+    /// collections were requested.
+    fn quote_immutable(&self, value: &str, format: &Format) -> Option<String> {
+        use Format::*;
+        if !self.generator.config.immutable_collections {
+            return None;
+        }
+        match format {
+            Seq(_) => Some(format!("tuple({})", value)),
+            Set(_) => Some(format!("frozenset({})", value)),
+            Map { .. } => Some(format!("types.MappingProxyType(dict({}))", value)),
+            Custom { layout, .. } if common::TimeFormat::of(format).is_none() => {
+                self.quote_immutable(value, layout)
+            }
+            _ => None,
+        }
+    }
+
+    fn output_flags(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let flag_sets = common::flag_sets(self.generator.config, &self.current_namespace, fields);
+        for (field, flags) in flag_sets {
+            let field_type = self.quote_type(&field.value);
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "{}_{} = {}({:#x})",
+                    field.name.to_shouty_snake_case(),
+                    flag.to_shouty_snake_case(),
+                    field_type,
+                    value
+                )?;
+            }
+            // Instances are frozen: `set_*` and `clear_*` return updated copies.
+            writeln!(
+                self.out,
+                r#"
+def has_{0}(self, mask: {1}) -> bool:
+    return (self.{0} & mask) == mask
+
+def set_{0}(self, mask: {1}) -> "{2}":
+    return replace(self, {0}={1}(self.{0} | mask))
+
+def clear_{0}(self, mask: {1}) -> "{2}":
+    return replace(self, {0}={1}(self.{0} & ~mask))
+"#,
+                field.name, field_type, name
+            )?;
+        }
+        Ok(())
+    }
+
     fn output_variant(
         &mut self,
         base: &str,
         name: &str,
         index: u32,
         variant: &VariantFormat,
+        tagged: bool,
     ) -> Result<()> {
         use VariantFormat::*;
         let fields = match variant {
             Unit => Vec::new(),
-            NewType(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            Tuple(formats) => vec![Named {
-                name: "value".to_string(),
-                value: Format::Tuple(formats.clone()),
-            }],
+            NewType(format) => vec![Named::new("value", format.as_ref().clone())],
+            Tuple(formats) => vec![Named::new("value", Format::Tuple(formats.clone()))],
             Struct(fields) => fields.clone(),
-            Variable(_) => panic!("incorrect value"),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         };
 
         // Regarding comments, we pretend the namespace is `[module, base, name]`.
+        // Variants of deprecated enums are deprecated as well.
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
         writeln!(
             self.out,
-            "\n@dataclass(frozen=True)\nclass {0}__{1}({0}):",
-            base, name
+            "\n{}{}\nclass {}({}):",
+            self.quote_deprecation(&path)
+                .or_else(|| self.quote_deprecation(&self.current_namespace))
+                .unwrap_or_default(),
+            self.quote_decorator(),
+            self.quote_variant_class(base, name),
+            base
         )?;
         self.out.indent();
         self.output_comment(&name)?;
         if self.generator.config.serialization {
             writeln!(self.out, "INDEX = {}  # type: int", index)?;
+            if tagged {
+                writeln!(self.out, "NAME = {:?}  # type: str", name)?;
+            }
         }
         self.current_namespace.push(name.to_string());
         self.output_fields(&fields)?;
+        self.output_post_init(&fields)?;
+        if self.generator.config.field_descriptors {
+            self.output_field_descriptors(&fields)?;
+        }
         self.output_custom_code()?;
         self.current_namespace.pop();
         self.out.unindent();
@@ -266,6 +740,7 @@ import typing
     fn output_enum_container(
         &mut self,
         name: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
         writeln!(self.out, "\nclass {}:", name)?;
@@ -278,20 +753,33 @@ import typing
                 "VARIANTS = []  # type: typing.Sequence[typing.Type[{}]]",
                 name
             )?;
+            if let Some(tag) = tag {
+                // Variants are serialized by name, see `NAME` in variant classes.
+                writeln!(self.out, "TAG = {:?}  # type: str", tag)?;
+            }
             for encoding in &self.generator.config.encodings {
                 self.output_serialize_method_for_encoding(name, *encoding)?;
                 self.output_deserialize_method_for_encoding(name, *encoding)?;
             }
         }
+        if self.generator.config.variant_names {
+            if self.generator.config.serialization {
+                writeln!(self.out)?;
+            }
+            self.output_variant_names(variants)?;
+        }
         let wrote_custom_code = self.output_custom_code()?;
-        if !self.generator.config.serialization && !wrote_custom_code {
+        if !self.generator.config.serialization
+            && !self.generator.config.variant_names
+            && !wrote_custom_code
+        {
             writeln!(self.out, "pass")?;
         }
         writeln!(self.out)?;
         self.out.unindent();
 
         for (index, variant) in variants {
-            self.output_variant(name, &variant.name, *index, &variant.value)?;
+            self.output_variant(name, &variant.name, *index, &variant.value, tag.is_some())?;
         }
         self.current_namespace.pop();
 
@@ -302,7 +790,7 @@ import typing
                 name,
                 variants
                     .iter()
-                    .map(|(_, v)| format!("    {}__{},\n", name, v.name))
+                    .map(|(_, v)| format!("    {},\n", self.quote_variant_class(name, &v.name)))
                     .collect::<Vec<_>>()
                     .join("")
             )?;
@@ -310,19 +798,57 @@ import typing
         Ok(())
     }
 
+    fn output_variant_names(
+        &mut self,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"VARIANT_NAMES = [{}]  # type: typing.Sequence[str]
+
+@classmethod
+def variant_name(cls, index: int) -> typing.Optional[str]:
+    return cls.VARIANT_NAMES[index] if 0 <= index < len(cls.VARIANT_NAMES) else None
+
+@classmethod
+def variant_index(cls, name: str) -> typing.Optional[int]:
+    return cls.VARIANT_NAMES.index(name) if name in cls.VARIANT_NAMES else None"#,
+            variants
+                .values()
+                .map(|v| format!("{:?}", v.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
     fn output_serialize_method_for_encoding(
         &mut self,
         name: &str,
         encoding: Encoding,
     ) -> Result<()> {
+        let bytes = format!("{}.serialize(self, {})", encoding.name(), name);
         writeln!(
             self.out,
             r#"
 def {0}_serialize(self) -> bytes:
-    return {0}.serialize(self, {1})"#,
+    return {1}"#,
             encoding.name(),
-            name
-        )
+            if self.generator.config.envelope_hooks {
+                format!("st.wrap_envelope({})", bytes)
+            } else {
+                bytes
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+def {0}_serialize_checked(self) -> bytes:
+    return st.append_checksum(self.{0}_serialize())"#,
+                encoding.name(),
+            )?;
+        }
+        Ok(())
     }
 
     fn output_deserialize_method_for_encoding(
@@ -335,40 +861,68 @@ def {0}_serialize(self) -> bytes:
             r#"
 @staticmethod
 def {0}_deserialize(input: bytes) -> '{1}':
-    v, buffer = {0}.deserialize(input, {1})
+    v, buffer = {0}.deserialize({2}, {1})
     if buffer:
         raise st.DeserializationError("Some input bytes were not read");
     return v"#,
             encoding.name(),
-            name
-        )
+            name,
+            if self.generator.config.envelope_hooks {
+                "st.unwrap_envelope(input)"
+            } else {
+                "input"
+            },
+        )?;
+        if self.generator.config.checksums {
+            writeln!(
+                self.out,
+                r#"
+@staticmethod
+def {0}_deserialize_checked(input: bytes) -> '{1}':
+    return {1}.{0}_deserialize(st.verify_checksum(input))"#,
+                encoding.name(),
+                name
+            )?;
+        }
+        Ok(())
     }
 
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
         let fields = match format {
             UnitStruct => Vec::new(),
-            NewTypeStruct(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            TupleStruct(formats) => vec![Named {
-                name: "value".to_string(),
-                value: Format::Tuple(formats.clone()),
-            }],
+            NewTypeStruct(format) => vec![Named::new("value", format.as_ref().clone())],
+            TupleStruct(formats) => vec![Named::new("value", Format::Tuple(formats.clone()))],
             Struct(fields) => fields.clone(),
             Enum(variants) => {
                 // Enum case.
-                self.output_enum_container(name, variants)?;
+                self.output_enum_container(name, None, variants)?;
+                return Ok(());
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                self.output_enum_container(name, Some(tag), variants)?;
                 return Ok(());
             }
         };
         // Struct case.
-        writeln!(self.out, "\n@dataclass(frozen=True)\nclass {}:", name)?;
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        writeln!(
+            self.out,
+            "\n{}{}\nclass {}:",
+            self.quote_deprecation(&path).unwrap_or_default(),
+            self.quote_decorator(),
+            name
+        )?;
         self.out.indent();
         self.output_comment(name)?;
         self.current_namespace.push(name.to_string());
         self.output_fields(&fields)?;
+        self.output_post_init(&fields)?;
+        if self.generator.config.field_descriptors {
+            self.output_field_descriptors(&fields)?;
+        }
+        self.output_flags(name, &fields)?;
         for encoding in &self.generator.config.encodings {
             self.output_serialize_method_for_encoding(name, *encoding)?;
             self.output_deserialize_method_for_encoding(name, *encoding)?;
@@ -378,12 +932,347 @@ def {0}_deserialize(input: bytes) -> '{1}':
         self.out.unindent();
         writeln!(self.out)
     }
+
+    fn has_record_logs(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization && !config.encodings.is_empty() && !config.record_log_types.is_empty()
+    }
+
+    fn output_record_log(&mut self, name: &str, fingerprint: u64) -> Result<()> {
+        let encoding = self.generator.config.single_encoding();
+        writeln!(
+            self.out,
+            r#"
+{1}_SCHEMA_FINGERPRINT = {2:#018x}
+
+
+def {3}_log_writer(output: typing.BinaryIO) -> serde_record_log.RecordLogWriter[{0}]:
+    return serde_record_log.RecordLogWriter(
+        output, {1}_SCHEMA_FINGERPRINT, serde_record_log.{4}, {0}.{5}_serialize
+    )
+
+
+def {3}_log_reader(content: bytes) -> serde_record_log.RecordLogReader[{0}]:
+    return serde_record_log.RecordLogReader(
+        content, {1}_SCHEMA_FINGERPRINT, serde_record_log.{4}, {0}.{5}_deserialize
+    )
+"#,
+            name,
+            name.to_shouty_snake_case(),
+            fingerprint,
+            name.to_snake_case(),
+            encoding.name().to_uppercase(),
+            encoding.name(),
+        )
+    }
+
+    fn output_embedded_schema(&mut self, registry: &Registry) -> crate::Result<()> {
+        let lines = common::compressed_schema(registry)?;
+        writeln!(self.out, "\n_SCHEMA = (")?;
+        for line in lines {
+            writeln!(self.out, "    \"{}\"", line)?;
+        }
+        writeln!(
+            self.out,
+            r#")
+
+
+def schema() -> str:
+    """Return the Serde formats of this module, encoded in YAML."""
+    return zlib.decompress(base64.b64decode(_SCHEMA)).decode("utf-8")"#
+        )?;
+        Ok(())
+    }
+
+    fn has_root_formats(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization && !config.encodings.is_empty() && !config.root_formats.is_empty()
+    }
+
+    fn output_root_format(&mut self, name: &str, format: &Format) -> Result<()> {
+        let value_type = self.quote_type(format);
+        // All classes are defined at this point so the runtime type needs no forward references.
+        let runtime_type = value_type.replace('"', "");
+        for encoding in &self.generator.config.encodings {
+            let bytes = format!("{}.serialize(value, {})", encoding.name(), runtime_type);
+            writeln!(
+                self.out,
+                r#"
+def {0}_serialize_{1}(value: {2}) -> bytes:
+    return {3}
+
+
+def {0}_deserialize_{1}(input: bytes) -> {2}:
+    v, buffer = {0}.deserialize({4}, {5})
+    if buffer:
+        raise st.DeserializationError("Some input bytes were not read");
+    return v
+"#,
+                encoding.name(),
+                name.to_snake_case(),
+                value_type,
+                if self.generator.config.envelope_hooks {
+                    format!("st.wrap_envelope({})", bytes)
+                } else {
+                    bytes
+                },
+                if self.generator.config.envelope_hooks {
+                    "st.unwrap_envelope(input)"
+                } else {
+                    "input"
+                },
+                runtime_type,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> PythonEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_fixtures_preamble(
+        &mut self,
+        registry: &Registry,
+        containers: &[(&str, &ContainerFormat)],
+    ) -> Result<()> {
+        let from_serde_package = match &self.generator.serde_package_name {
+            Some(name) if !self.generator.embedded_runtime => format!("from {} ", name),
+            _ => "".to_string(),
+        };
+        let mut names = Vec::new();
+        for (name, format) in containers {
+            names.push(name.to_string());
+            if let ContainerFormat::Enum(variants)
+            | ContainerFormat::InternallyTaggedEnum { variants, .. } = format
+            {
+                if let Some(variant) = variants.values().next() {
+                    names.push(self.quote_variant_class(name, &variant.name));
+                }
+            }
+        }
+        writeln!(self.out, "# pyre-strict")?;
+        writeln!(
+            self.out,
+            "from {} import {}",
+            self.generator.config.module_name,
+            names.join(", ")
+        )?;
+        writeln!(
+            self.out,
+            "import typing\n{}import serde_types as st",
+            from_serde_package
+        )?;
+        if common::custom_formats(registry)
+            .iter()
+            .any(|(name, layout)| common::TimeFormat::new(name, layout).is_some())
+        {
+            writeln!(self.out, "import datetime")?;
+        }
+        if common::has_decimal_formats(registry) {
+            writeln!(self.out, "import decimal")?;
+        }
+        if !self.addresses.is_empty() {
+            writeln!(self.out, "import ipaddress")?;
+        }
+        for module_path in self.generator.config.external_definitions.keys() {
+            let module = module_path.rsplit('.').next().unwrap_or(module_path);
+            writeln!(
+                self.out,
+                "import {}.fixtures as {}_fixtures",
+                module_path, module
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Expression building a valid value of the given format, for test fixtures.
+    fn quote_sample(&self, format: &Format) -> String {
+        use Format::*;
+        if let Some(integer) = common::nonzero_integer(format) {
+            return Self::quote_integer_sample(integer, 1);
+        }
+        match format {
+            TypeName(x) => match self.addresses.get(x) {
+                Some(AddressFormat::IpAddr) => "ipaddress.IPv4Address(\"0.0.0.0\")".into(),
+                Some(AddressFormat::SocketAddr) => {
+                    "(ipaddress.IPv4Address(\"0.0.0.0\"), st.uint16(0))".into()
+                }
+                None => match self.generator.external_qualified_names.get(x) {
+                    Some(qualified_name) => format!(
+                        "{}Fixtures.sample()",
+                        qualified_name.replacen('.', "_fixtures.", 1)
+                    ),
+                    None => format!("{}Fixtures.sample()", x),
+                },
+            },
+            Unit | Option(_) => "None".into(),
+            Bool => "False".into(),
+            I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128 => {
+                Self::quote_integer_sample(format, 0)
+            }
+            F32 => "st.float32(0)".into(),
+            F64 => "st.float64(0)".into(),
+            Char => "st.char(\"a\")".into(),
+            Str => "\"\"".into(),
+            Bytes => "b\"\"".into(),
+            Seq(_) => "[]".into(),
+            Set(_) => "frozenset()".into(),
+            Map { .. } => "{}".into(),
+            Tuple(formats) => {
+                Self::quote_tuple(formats.iter().map(|f| self.quote_sample(f)).collect())
+            }
+            TupleArray { content, size } => {
+                Self::quote_tuple(vec![self.quote_sample(content); *size])
+            }
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => "datetime.timedelta()".into(),
+                Some(common::TimeFormat::SystemTime) => {
+                    "datetime.datetime.fromtimestamp(0, datetime.timezone.utc)".into()
+                }
+                None => self.quote_sample(layout),
+            },
+            Decimal { .. } => "decimal.Decimal(0)".into(),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
+        }
+    }
+
+    fn quote_tuple(values: Vec<String>) -> String {
+        match values.len() {
+            1 => format!("({},)", values[0]),
+            _ => format!("({})", values.join(", ")),
+        }
+    }
+
+    fn quote_integer_sample(format: &Format, value: i128) -> String {
+        use Format::*;
+        let name = match format {
+            I8 => "int8",
+            I16 => "int16",
+            I32 => "int32",
+            I64 => "int64",
+            I128 => "int128",
+            U8 => "uint8",
+            U16 => "uint16",
+            U32 => "uint32",
+            U64 => "uint64",
+            U128 => "uint128",
+            _ => panic!("unexpected integer"),
+        };
+        format!("st.{}({})", name, value)
+    }
+
+    /// Samples of the given fields of the definition `path`, following the constraints on
+    /// integer values, if any.
+    fn quote_field_samples<'b>(
+        &self,
+        path: &[String],
+        fields: &'b [Named<Format>],
+    ) -> Vec<(&'b str, String)> {
+        let constraints = common::integer_constraints(self.generator.config, path, fields);
+        fields
+            .iter()
+            .map(|field| {
+                let sample = match constraints.iter().find(|(f, _, _)| f.name == field.name) {
+                    Some((_, format, constraint)) => {
+                        Self::quote_integer_sample(format, constraint.sample())
+                    }
+                    None => self.quote_sample(&field.value),
+                };
+                (field.name.as_str(), sample)
+            })
+            .collect()
+    }
+
+    fn output_fixture(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        let fields = match format {
+            UnitStruct => Vec::new(),
+            NewTypeStruct(format) => vec![Named::new("value", format.as_ref().clone())],
+            TupleStruct(formats) => vec![Named::new("value", Format::Tuple(formats.clone()))],
+            Struct(fields) => fields.clone(),
+            Enum(variants) | InternallyTaggedEnum { variants, .. } => {
+                return self.output_enum_fixture(name, &path, variants);
+            }
+        };
+        writeln!(self.out, "\n\nclass {}Fixtures:", name)?;
+        self.out.indent();
+        writeln!(
+            self.out,
+            "@staticmethod\ndef sample(**overrides: typing.Any) -> {}:",
+            name
+        )?;
+        self.out.indent();
+        writeln!(
+            self.out,
+            "fields = {{{}}}  # type: typing.Dict[str, typing.Any]",
+            self.quote_field_samples(&path, &fields)
+                .into_iter()
+                .map(|(name, sample)| format!("{:?}: {}", name, sample))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        writeln!(self.out, "fields.update(overrides)")?;
+        writeln!(self.out, "return {}(**fields)", name)?;
+        self.out.unindent();
+        self.out.unindent();
+        Ok(())
+    }
+
+    fn output_enum_fixture(
+        &mut self,
+        name: &str,
+        path: &[String],
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(self.out, "\n\nclass {}Fixtures:", name)?;
+        self.out.indent();
+        writeln!(self.out, "@staticmethod\ndef sample() -> {}:", name)?;
+        self.out.indent();
+        // The first variant is a base case of recursive definitions.
+        match variants.values().next() {
+            None => writeln!(self.out, "raise ValueError(\"{} has no variants\")", name)?,
+            Some(variant) => {
+                use VariantFormat::*;
+                let fields = match &variant.value {
+                    Unit => Vec::new(),
+                    NewType(format) => vec![Named::new("value", format.as_ref().clone())],
+                    Tuple(formats) => vec![Named::new("value", Format::Tuple(formats.clone()))],
+                    Struct(fields) => fields.clone(),
+                    Variable(_) => unreachable!("formats are checked by common::check_formats"),
+                };
+                let mut path = path.to_vec();
+                path.push(variant.name.clone());
+                writeln!(
+                    self.out,
+                    "return {}({})",
+                    self.quote_variant_class(name, &variant.name),
+                    self.quote_field_samples(&path, &fields)
+                        .into_iter()
+                        .map(|(name, sample)| format!("{}={}", name, sample))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
+        }
+        self.out.unindent();
+        self.out.unindent();
+        Ok(())
+    }
 }
 
 /// Installer for generated source files in Python.
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
     serde_package_name: Option<String>,
+    pydantic: bool,
+    embedded_runtime: bool,
 }
 
 impl Installer {
@@ -391,13 +1280,32 @@ impl Installer {
         Installer {
             install_dir,
             serde_package_name,
+            pydantic: false,
+            embedded_runtime: false,
         }
     }
 
+    /// Whether to generate Pydantic (v2) dataclasses with validators.
+    pub fn with_pydantic(mut self, pydantic: bool) -> Self {
+        self.pydantic = pydantic;
+        self
+    }
+
+    /// Whether to install a single module `<module>.py` embedding the Serde runtimes, in which
+    /// case runtimes are not installed separately.
+    pub fn with_embedded_runtime(mut self, embedded_runtime: bool) -> Self {
+        self.embedded_runtime = embedded_runtime;
+        self
+    }
+
     fn create_module_init_file(&self, name: &str) -> Result<std::fs::File> {
+        self.create_module_file(name, "__init__.py")
+    }
+
+    fn create_module_file(&self, name: &str, file_name: &str) -> Result<std::fs::File> {
         let dir_path = self.install_dir.join(name);
         std::fs::create_dir_all(&dir_path)?;
-        std::fs::File::create(dir_path.join("__init__.py"))
+        std::fs::File::create(dir_path.join(file_name))
     }
 
     fn fix_serde_package(&self, content: &str) -> String {
@@ -417,21 +1325,58 @@ impl Installer {
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
 
     fn install_module(
         &self,
         config: &crate::CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
-        let mut file = self.create_module_init_file(&config.module_name)?;
-        let generator =
-            CodeGenerator::new(config).with_serde_package_name(self.serde_package_name.clone());
+        let path = if self.embedded_runtime || config.output_layout == OutputLayout::SingleFile {
+            std::fs::create_dir_all(&self.install_dir)?;
+            self.install_dir.join(config.module_name.clone() + ".py")
+        } else {
+            let dir_path = self.install_dir.join(&config.module_name);
+            std::fs::create_dir_all(&dir_path)?;
+            dir_path.join("__init__.py")
+        };
+        let mut file = common::create_source_file(config, path)?;
+        let generator = CodeGenerator::new(config)
+            .with_serde_package_name(self.serde_package_name.clone())
+            .with_pydantic(self.pydantic)
+            .with_embedded_runtime(self.embedded_runtime);
         generator.output(&mut file, registry)?;
+        if config.fixtures {
+            let path = if self.embedded_runtime || config.output_layout == OutputLayout::SingleFile
+            {
+                self.install_dir
+                    .join(config.module_name.clone() + "_fixtures.py")
+            } else {
+                self.install_dir
+                    .join(&config.module_name)
+                    .join("fixtures.py")
+            };
+            let mut file = common::create_source_file(config, path)?;
+            generator.output_fixtures(&mut file, registry)?;
+        }
         Ok(())
     }
 
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        if self.embedded_runtime {
+            return Ok(());
+        }
         let mut file = self.create_module_init_file("serde_types")?;
         write!(
             file,
@@ -444,26 +1389,98 @@ impl crate::SourceInstaller for Installer {
             "{}",
             self.fix_serde_package(include_str!("../runtime/python/serde_binary/__init__.py"))
         )?;
-        Ok(())
-    }
-
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
-        let mut file = self.create_module_init_file("bincode")?;
+        let mut file = self.create_module_init_file("serde_record_log")?;
         write!(
             file,
             "{}",
-            self.fix_serde_package(include_str!("../runtime/python/bincode/__init__.py"))
+            self.fix_serde_package(include_str!(
+                "../runtime/python/serde_record_log/__init__.py"
+            ))
         )?;
         Ok(())
     }
 
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        let mut file = self.create_module_init_file("bcs")?;
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
+        if self.embedded_runtime {
+            return Ok(());
+        }
+        let source = match encoding {
+            Encoding::Bincode => include_str!("../runtime/python/bincode/__init__.py"),
+            Encoding::Bcs => include_str!("../runtime/python/bcs/__init__.py"),
+        };
+        let mut file = self.create_module_init_file(encoding.name())?;
+        write!(file, "{}", self.fix_serde_package(source))?;
+        Ok(())
+    }
+
+    fn install_runtime_tests(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        if self.embedded_runtime {
+            return Ok(());
+        }
+        let mut file = self.create_module_file("serde_types", "test_serde_types.py")?;
         write!(
             file,
             "{}",
-            self.fix_serde_package(include_str!("../runtime/python/bcs/__init__.py"))
+            self.fix_serde_package(include_str!(
+                "../runtime/python/serde_types/test_serde_types.py"
+            ))
+        )?;
+        for encoding in &config.encodings {
+            let source = match encoding {
+                Encoding::Bincode => include_str!("../runtime/python/bincode/test_bincode.py"),
+                Encoding::Bcs => include_str!("../runtime/python/bcs/test_bcs.py"),
+            };
+            let mut file =
+                self.create_module_file(encoding.name(), &format!("test_{}.py", encoding.name()))?;
+            write!(file, "{}", self.fix_serde_package(source))?;
+        }
+        Ok(())
+    }
+
+    fn install_corpus_runner(
+        &self,
+        config: &CodeGeneratorConfig,
+    ) -> std::result::Result<(), Self::Error> {
+        if self.embedded_runtime {
+            return Ok(());
+        }
+        self.install_module(config, &crate::test_vectors::corpus_registry())?;
+        let mut file =
+            std::fs::File::create(self.install_dir.join(config.module_name.clone() + ".tsv"))?;
+        for vector in crate::test_vectors::corpus() {
+            if config.encodings.contains(&vector.encoding) {
+                writeln!(file, "{}", vector)?;
+            }
+        }
+        let mut file = std::fs::File::create(
+            self.install_dir
+                .join(format!("test_{}.py", config.module_name)),
         )?;
+        let source = format!(
+            r#"# Check that the installed runtimes decode and encode back the shared corpus of test vectors.
+
+import os
+import unittest
+
+import serde_types as st
+import {0} as corpus
+
+
+class CorpusTestCase(unittest.TestCase):
+    def test_corpus(self):
+        path = os.path.join(os.path.dirname(__file__), "{0}.tsv")
+        for vector in st.load_test_vectors(path):
+            with self.subTest(type_name=vector.type_name, encoding=vector.encoding, bytes=vector.bytes.hex()):
+                cls = getattr(corpus, vector.type_name)
+                value = getattr(cls, vector.encoding + "_deserialize")(vector.bytes)
+                self.assertEqual(getattr(value, vector.encoding + "_serialize")(), vector.bytes)
+"#,
+            config.module_name
+        );
+        write!(file, "{}", self.fix_serde_package(&source))?;
         Ok(())
     }
 }
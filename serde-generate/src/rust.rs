@@ -3,14 +3,16 @@
 
 use crate::{
     analyzer,
+    common::{self, AddressFormat},
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig,
+    CodeGeneratorConfig, Encoding,
 };
+use heck::{CamelCase, ShoutySnakeCase};
 use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::borrow::Cow;
 use std::collections::{BTreeMap, HashSet};
 use std::io::{Result, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Main configuration object for code-generation in Rust.
 pub struct CodeGenerator<'a> {
@@ -22,6 +24,8 @@ pub struct CodeGenerator<'a> {
     custom_derive_block: Option<String>,
     /// Whether definitions and fields should be marked as `pub`.
     track_visibility: bool,
+    /// Whether fields with a semantic tag should use a newtype named after the tag.
+    semantic_newtypes: bool,
 }
 
 /// Shared state for the code generation of a Rust source file.
@@ -34,6 +38,8 @@ struct RustEmitter<'a, T> {
     known_sizes: Cow<'a, HashSet<&'a str>>,
     /// Current namespace (e.g. vec!["my_package", "my_module", "MyClass"])
     current_namespace: Vec<String>,
+    /// Newtypes wrapping the fields with a semantic tag, indexed by name.
+    semantic_newtypes: &'a BTreeMap<String, Format>,
 }
 
 impl<'a> CodeGenerator<'a> {
@@ -47,6 +53,7 @@ impl<'a> CodeGenerator<'a> {
                 .collect(),
             custom_derive_block: None,
             track_visibility: true,
+            semantic_newtypes: false,
         }
     }
 
@@ -69,12 +76,62 @@ impl<'a> CodeGenerator<'a> {
         self
     }
 
+    /// Whether struct fields with a semantic tag (see `CodeGeneratorConfig::with_semantic_tags`)
+    /// should use a newtype named after the tag, e.g. `Microseconds(u64)`. Newtypes have the
+    /// same serialization format as the wrapped value. Tags used with different formats are
+    /// not wrapped.
+    pub fn with_semantic_newtypes(mut self, semantic_newtypes: bool) -> Self {
+        self.semantic_newtypes = semantic_newtypes;
+        self
+    }
+
+    fn get_semantic_newtypes(&self, registry: &Registry) -> BTreeMap<String, Format> {
+        if !self.semantic_newtypes {
+            return BTreeMap::new();
+        }
+        let mut newtypes = BTreeMap::new();
+        let module_path = self
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect::<Vec<_>>();
+        for (name, format) in registry {
+            let fields = match format {
+                ContainerFormat::Struct(fields) => fields,
+                _ => continue,
+            };
+            for field in fields {
+                let mut path = module_path.clone();
+                path.push(name.to_string());
+                path.push(field.name.to_string());
+                if let Some(tag) = self.config.semantic_tags.get(&path) {
+                    newtypes
+                        .entry(tag.to_camel_case())
+                        .or_insert_with(Vec::new)
+                        .push(field.value.clone());
+                }
+            }
+        }
+        newtypes
+            .into_iter()
+            .filter_map(|(name, mut formats)| {
+                formats.dedup();
+                if formats.len() == 1 && !registry.contains_key(&name) {
+                    formats.pop().map(|format| (name, format))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// Write container definitions in Rust.
-    pub fn output(
-        &self,
-        out: &mut dyn Write,
-        registry: &Registry,
-    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("Rust", registry)?;
+        common::check_options("Rust", self.config, &[])?;
         let external_names = self
             .config
             .external_definitions
@@ -97,19 +154,35 @@ impl<'a> CodeGenerator<'a> {
             .split('.')
             .map(String::from)
             .collect();
+        let semantic_newtypes = self.get_semantic_newtypes(registry);
         let mut emitter = RustEmitter {
             out: IndentedWriter::new(out, IndentConfig::Space(4)),
             generator: self,
             known_sizes: Cow::Owned(known_sizes),
             current_namespace,
+            semantic_newtypes: &semantic_newtypes,
         };
 
-        emitter.output_preamble()?;
+        let addresses = common::address_formats(registry);
+        emitter.output_preamble(&addresses)?;
+        for (name, format) in &semantic_newtypes {
+            emitter.output_semantic_newtype(name, format)?;
+        }
         for name in entries {
-            let format = &registry[name];
-            emitter.output_container(name, format)?;
+            common::check_cancellation(self.config)?;
+            // Network addresses are imported from `std::net`.
+            if !addresses.contains_key(name) {
+                let format = &registry[name];
+                emitter.output_container(name, format)?;
+            }
             emitter.known_sizes.to_mut().insert(name);
         }
+        if emitter.has_record_logs() {
+            for name in &self.config.record_log_types {
+                let fingerprint = common::schema_fingerprint(registry, name)?;
+                emitter.output_record_log(name, fingerprint)?;
+            }
+        }
         Ok(())
     }
 
@@ -117,7 +190,9 @@ impl<'a> CodeGenerator<'a> {
     pub fn quote_container_definitions(
         &self,
         registry: &Registry,
-    ) -> std::result::Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    ) -> crate::Result<BTreeMap<String, String>> {
+        common::check_formats("Rust", registry)?;
+        common::check_options("Rust", self.config, &[])?;
         let dependencies = analyzer::get_dependency_map(registry)?;
         let entries = analyzer::best_effort_topological_sort(&dependencies);
 
@@ -129,8 +204,28 @@ impl<'a> CodeGenerator<'a> {
             .split('.')
             .map(String::from)
             .collect::<Vec<_>>();
+        let semantic_newtypes = self.get_semantic_newtypes(registry);
+
+        for (name, format) in &semantic_newtypes {
+            let mut content = Vec::new();
+            {
+                let mut emitter = RustEmitter {
+                    out: IndentedWriter::new(&mut content, IndentConfig::Space(4)),
+                    generator: self,
+                    known_sizes: Cow::Borrowed(&known_sizes),
+                    current_namespace: current_namespace.clone(),
+                    semantic_newtypes: &semantic_newtypes,
+                };
+                emitter.output_semantic_newtype(name, format)?;
+            }
+            result.insert(
+                name.to_string(),
+                String::from_utf8_lossy(&content).trim().to_string() + "\n",
+            );
+        }
 
         for name in entries {
+            common::check_cancellation(self.config)?;
             let mut content = Vec::new();
             {
                 let mut emitter = RustEmitter {
@@ -138,6 +233,7 @@ impl<'a> CodeGenerator<'a> {
                     generator: self,
                     known_sizes: Cow::Borrowed(&known_sizes),
                     current_namespace: current_namespace.clone(),
+                    semantic_newtypes: &semantic_newtypes,
                 };
                 let format = &registry[name];
                 emitter.output_container(name, format)?;
@@ -156,13 +252,30 @@ impl<'a, T> RustEmitter<'a, T>
 where
     T: std::io::Write,
 {
+    /// Output the Serde attribute renaming a field or a variant whose Rust identifier is known
+    /// to differ from its Serde name. Return the identifier to use.
+    fn output_rename<'b, V>(&mut self, named: &'b Named<V>) -> std::io::Result<&'b str> {
+        match &named.source_name {
+            Some(source_name) if *source_name != named.name => {
+                if self.generator.config.serialization {
+                    writeln!(self.out, "#[serde(rename = {:?})]", named.name)?;
+                }
+                Ok(source_name)
+            }
+            _ => Ok(&named.name),
+        }
+    }
+
     fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
         let mut path = self.current_namespace.clone();
         path.push(name.to_string());
-        if let Some(doc) = self.generator.config.comments.get(&path) {
-            let text = textwrap::indent(doc, "/// ").replace("\n\n", "\n///\n");
+        if let Some(doc) = self.generator.config.comment(&path) {
+            let text = textwrap::indent(&doc, "/// ").replace("\n\n", "\n///\n");
             write!(self.out, "\n{}", text)?;
         }
+        if let Some(message) = self.generator.config.deprecations.get(&path) {
+            writeln!(self.out, "#[deprecated(note = {:?})]", message.trim())?;
+        }
         Ok(())
     }
 
@@ -175,7 +288,7 @@ where
         Ok(())
     }
 
-    fn output_preamble(&mut self) -> Result<()> {
+    fn output_preamble(&mut self, addresses: &BTreeMap<String, AddressFormat>) -> Result<()> {
         let external_names = self
             .generator
             .config
@@ -188,12 +301,19 @@ where
         if !external_names.contains("Map") {
             writeln!(self.out, "use std::collections::BTreeMap as Map;")?;
         }
+        if !external_names.contains("Set") {
+            writeln!(self.out, "use std::collections::BTreeSet as Set;")?;
+        }
         if self.generator.config.serialization {
             writeln!(self.out, "use serde::{{Serialize, Deserialize}};")?;
         }
         if self.generator.config.serialization && !external_names.contains("Bytes") {
             writeln!(self.out, "use serde_bytes::ByteBuf as Bytes;")?;
         }
+        if !addresses.is_empty() {
+            let names = addresses.keys().cloned().collect::<Vec<_>>();
+            writeln!(self.out, "use std::net::{{{}}};", names.join(", "))?;
+        }
         for (module, definitions) in &self.generator.config.external_definitions {
             // Skip the empty module name.
             if !module.is_empty() {
@@ -210,6 +330,35 @@ where
             // If we are not going to use Serde derive macros, use plain vectors.
             writeln!(self.out, "type Bytes = Vec<u8>;\n")?;
         }
+        if self.generator.config.serialization
+            && self.generator.config.file_decoding
+            && !self.generator.config.encodings.is_empty()
+        {
+            writeln!(
+                self.out,
+                r#"/// Memory-map a file for decoding. The file must not be modified while it is mapped.
+fn map_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<memmap2::Mmap> {{
+    let file = std::fs::File::open(path)?;
+    // Safety: callers must not modify the file while it is mapped.
+    unsafe {{ memmap2::Mmap::map(&file) }}
+}}
+"#
+            )?;
+        }
+        if self.has_record_logs() {
+            writeln!(
+                self.out,
+                "/// Reading and writing record logs.\npub mod record_log {{"
+            )?;
+            self.out.indent();
+            write!(
+                self.out,
+                "{}",
+                include_str!("../runtime/rust/record_log.rs")
+            )?;
+            self.out.unindent();
+            writeln!(self.out, "}}\n")?;
+        }
         Ok(())
     }
 
@@ -244,6 +393,7 @@ where
 
             Option(format) => format!("Option<{}>", Self::quote_type(format, known_sizes)),
             Seq(format) => format!("Vec<{}>", Self::quote_type(format, None)),
+            Set(format) => format!("Set<{}>", Self::quote_type(format, None)),
             Map { key, value } => format!(
                 "Map<{}, {}>",
                 Self::quote_type(key, None),
@@ -253,8 +403,15 @@ where
             TupleArray { content, size } => {
                 format!("[{}; {}]", Self::quote_type(content, known_sizes), *size)
             }
+            Custom { name, .. } if common::nonzero_integer(format).is_some() => {
+                format!("std::num::{}", name)
+            }
+            Custom { layout, .. } => Self::quote_type(layout, known_sizes),
+            Decimal { mantissa, .. } => Self::quote_type(mantissa, known_sizes),
 
-            Variable(_) => panic!("unexpected value"),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
         }
     }
 
@@ -275,36 +432,97 @@ where
         };
         for field in fields {
             self.output_comment(&field.name)?;
-            writeln!(
-                self.out,
-                "{}{}: {},",
-                prefix,
-                field.name,
-                Self::quote_type(&field.value, Some(&self.known_sizes)),
-            )?;
+            let field_name = self.output_rename(field)?;
+            let field_type = match self.quote_semantic_newtype(field) {
+                Some(name) => name,
+                None => Self::quote_type(&field.value, Some(&self.known_sizes)),
+            };
+            writeln!(self.out, "{}{}: {},", prefix, field_name, field_type)?;
         }
         Ok(())
     }
 
-    fn output_variant(&mut self, base: &str, name: &str, variant: &VariantFormat) -> Result<()> {
-        self.output_comment(name)?;
+    /// Name of the newtype to be used for the given field, if any.
+    fn quote_semantic_newtype(&self, field: &Named<Format>) -> Option<String> {
+        let mut path = self.current_namespace.clone();
+        path.push(field.name.clone());
+        let name = self
+            .generator
+            .config
+            .semantic_tags
+            .get(&path)?
+            .to_camel_case();
+        if self.semantic_newtypes.get(&name) == Some(&field.value) {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    fn output_semantic_newtype(&mut self, name: &str, format: &Format) -> Result<()> {
+        writeln!(
+            self.out,
+            "{}struct {}({}{});\n",
+            self.quote_definition_prefix(None),
+            name,
+            if self.generator.track_visibility {
+                "pub "
+            } else {
+                ""
+            },
+            Self::quote_type(format, Some(&self.known_sizes))
+        )
+    }
+
+    fn quote_definition_prefix(&self, tag: Option<&str>) -> String {
+        let mut derive_macros = self.generator.derive_macros.clone();
+        if self.generator.config.serialization {
+            derive_macros.push("Serialize".to_string());
+            derive_macros.push("Deserialize".to_string());
+        }
+        let mut prefix = String::new();
+        if !derive_macros.is_empty() {
+            prefix.push_str(&format!("#[derive({})]\n", derive_macros.join(", ")));
+        }
+        if let (Some(tag), true) = (tag, self.generator.config.serialization) {
+            prefix.push_str(&format!("#[serde(tag = {:?})]\n", tag));
+        }
+        if let Some(text) = &self.generator.custom_derive_block {
+            prefix.push_str(text);
+            prefix.push('\n');
+        }
+        if self.generator.track_visibility {
+            prefix.push_str("pub ");
+        }
+        prefix
+    }
+
+    /// Output a variant of the enum `base` where `name` is the Serde name of the variant and
+    /// `ident` its Rust identifier.
+    fn output_variant(
+        &mut self,
+        base: &str,
+        name: &str,
+        ident: &str,
+        variant: &VariantFormat,
+    ) -> Result<()> {
         use VariantFormat::*;
         match variant {
-            Unit => writeln!(self.out, "{},", name),
+            Unit => writeln!(self.out, "{},", ident),
             NewType(format) => writeln!(
                 self.out,
                 "{}({}),",
-                name,
+                ident,
                 Self::quote_type(format, Some(&self.known_sizes))
             ),
             Tuple(formats) => writeln!(
                 self.out,
                 "{}({}),",
-                name,
+                ident,
                 Self::quote_types(formats, Some(&self.known_sizes))
             ),
             Struct(fields) => {
-                writeln!(self.out, "{} {{", name)?;
+                writeln!(self.out, "{} {{", ident)?;
                 self.current_namespace.push(name.to_string());
                 self.out.indent();
                 self.output_fields(&[base, name], fields)?;
@@ -312,7 +530,7 @@ where
                 self.current_namespace.pop();
                 writeln!(self.out, "}},")
             }
-            Variable(_) => panic!("incorrect value"),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
         }
     }
 
@@ -323,29 +541,106 @@ where
     ) -> Result<()> {
         for (expected_index, (index, variant)) in variants.iter().enumerate() {
             assert_eq!(*index, expected_index as u32);
-            self.output_variant(base, &variant.name, &variant.value)?;
+            self.output_comment(&variant.name)?;
+            let name = self.output_rename(variant)?;
+            self.output_variant(base, &variant.name, name, &variant.value)?;
         }
         Ok(())
     }
 
-    fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
-        self.output_comment(name)?;
-        let mut derive_macros = self.generator.derive_macros.clone();
-        if self.generator.config.serialization {
-            derive_macros.push("Serialize".to_string());
-            derive_macros.push("Deserialize".to_string());
-        }
-        let mut prefix = String::new();
-        if !derive_macros.is_empty() {
-            prefix.push_str(&format!("#[derive({})]\n", derive_macros.join(", ")));
-        }
-        if let Some(text) = &self.generator.custom_derive_block {
-            prefix.push_str(text);
-            prefix.push('\n');
+    fn output_variant_names(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        let prefix = if self.generator.track_visibility {
+            "pub "
+        } else {
+            ""
+        };
+        writeln!(
+            self.out,
+            r#"impl {1} {{
+    /// Names of the variants, indexed by variant index.
+    {0}const VARIANT_NAMES: &'static [&'static str] = &[{2}];
+
+    {0}fn variant_name(index: u32) -> Option<&'static str> {{
+        Self::VARIANT_NAMES.get(index as usize).copied()
+    }}
+
+    {0}fn variant_index(name: &str) -> Option<u32> {{
+        Self::VARIANT_NAMES
+            .iter()
+            .position(|variant| *variant == name)
+            .map(|index| index as u32)
+    }}
+}}
+"#,
+            prefix,
+            name,
+            variants
+                .values()
+                .map(|variant| format!("{:?}", variant.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn output_flags(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let flag_sets = common::flag_sets(self.generator.config, &self.current_namespace, fields);
+        if flag_sets.is_empty() {
+            return Ok(());
         }
-        if self.generator.track_visibility {
-            prefix.push_str("pub ");
+        let prefix = if self.generator.track_visibility {
+            "pub "
+        } else {
+            ""
+        };
+        writeln!(self.out, "impl {} {{", name)?;
+        self.out.indent();
+        for (field, flags) in flag_sets {
+            let field_type = Self::quote_type(&field.value, None);
+            let field_name = field.source_name.as_ref().unwrap_or(&field.name);
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "{}const {}_{}: {} = {:#x};",
+                    prefix,
+                    field_name.to_shouty_snake_case(),
+                    flag.to_shouty_snake_case(),
+                    field_type,
+                    value
+                )?;
+            }
+            writeln!(
+                self.out,
+                r#"
+{0}fn has_{1}(&self, mask: {2}) -> bool {{
+    self.{1} & mask == mask
+}}
+
+{0}fn set_{1}(&mut self, mask: {2}) {{
+    self.{1} |= mask;
+}}
+
+{0}fn clear_{1}(&mut self, mask: {2}) {{
+    self.{1} &= !mask;
+}}
+"#,
+                prefix, field_name, field_type
+            )?;
         }
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
+    fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        self.output_comment(name)?;
+        let tag = match format {
+            ContainerFormat::InternallyTaggedEnum { tag, .. } => Some(tag.as_str()),
+            _ => None,
+        };
+        let prefix = self.quote_definition_prefix(tag);
 
         use ContainerFormat::*;
         match format {
@@ -375,10 +670,11 @@ where
                 self.out.indent();
                 self.output_fields(&[name], fields)?;
                 self.out.unindent();
-                self.current_namespace.pop();
                 writeln!(self.out, "}}\n")?;
+                self.output_flags(name, fields)?;
+                self.current_namespace.pop();
             }
-            Enum(variants) => {
+            Enum(variants) | InternallyTaggedEnum { variants, .. } => {
                 writeln!(self.out, "{}enum {} {{", prefix, name)?;
                 self.current_namespace.push(name.to_string());
                 self.out.indent();
@@ -386,13 +682,104 @@ where
                 self.out.unindent();
                 self.current_namespace.pop();
                 writeln!(self.out, "}}\n")?;
+                if self.generator.config.variant_names {
+                    self.output_variant_names(name, variants)?;
+                }
             }
         }
+        if self.generator.config.serialization && self.generator.config.file_decoding {
+            self.output_file_decoding(name)?;
+        }
         self.output_custom_code(name)
     }
+
+    fn output_file_decoding(&mut self, name: &str) -> Result<()> {
+        if self.generator.config.encodings.is_empty() {
+            return Ok(());
+        }
+        let prefix = if self.generator.track_visibility {
+            "pub "
+        } else {
+            ""
+        };
+        writeln!(self.out, "impl {} {{", name)?;
+        self.out.indent();
+        for (i, encoding) in self.generator.config.encodings.iter().enumerate() {
+            let (title, decode) = match encoding {
+                Encoding::Bcs => ("BCS", "bcs::from_bytes"),
+                Encoding::Bincode => ("Bincode", "bincode::deserialize"),
+            };
+            if i > 0 {
+                writeln!(self.out)?;
+            }
+            writeln!(
+                self.out,
+                r#"/// Decode a value from the {1} bytes of a memory-mapped file.
+{0}fn {2}_from_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {{
+    let map = map_file(path)?;
+    {3}(&map).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}}"#,
+                prefix,
+                title,
+                encoding.name(),
+                decode,
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
+    fn has_record_logs(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization && !config.encodings.is_empty() && !config.record_log_types.is_empty()
+    }
+
+    fn output_record_log(&mut self, name: &str, fingerprint: u64) -> Result<()> {
+        let (encoding, serialize, deserialize) = match self.generator.config.single_encoding() {
+            Encoding::Bcs => ("Bcs", "bcs::to_bytes", "bcs::from_bytes"),
+            Encoding::Bincode => ("Bincode", "bincode::serialize", "bincode::deserialize"),
+        };
+        let prefix = if self.generator.track_visibility {
+            "pub "
+        } else {
+            ""
+        };
+        writeln!(
+            self.out,
+            r#"impl {1} {{
+    /// Fingerprint of the schema of `{1}`, as stored in the header of record logs.
+    {0}const SCHEMA_FINGERPRINT: u64 = {2:#018x};
+
+    /// Start a record log of `{1}` values.
+    {0}fn log_writer<W: std::io::Write>(
+        writer: W,
+    ) -> std::io::Result<record_log::RecordLogWriter<W, Self>> {{
+        record_log::RecordLogWriter::new(
+            writer,
+            Self::SCHEMA_FINGERPRINT,
+            record_log::Encoding::{3},
+            |value| {4}(value).map_err(record_log::invalid_data),
+        )
+    }}
+
+    /// Read a record log of `{1}` values.
+    {0}fn log_reader(bytes: &[u8]) -> std::io::Result<record_log::RecordLogReader<'_, Self>> {{
+        record_log::RecordLogReader::new(
+            bytes,
+            Self::SCHEMA_FINGERPRINT,
+            record_log::Encoding::{3},
+            |bytes| {5}(bytes).map_err(record_log::invalid_data),
+        )
+    }}
+}}
+"#,
+            prefix, name, fingerprint, encoding, serialize, deserialize,
+        )
+    }
 }
 
 /// Installer for generated source files in Rust.
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
 }
@@ -408,7 +795,15 @@ impl Installer {
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self::new(install_dir.to_path_buf()))
+    }
 
     fn install_module(
         &self,
@@ -440,9 +835,22 @@ serde_bytes = "0.11"
 "#,
             name, version,
         )?;
+        if config.serialization && !config.encodings.is_empty() {
+            if config.file_decoding {
+                writeln!(cargo, "memmap2 = \"0.5\"")?;
+            }
+            if config.file_decoding || !config.record_log_types.is_empty() {
+                for encoding in &config.encodings {
+                    match encoding {
+                        Encoding::Bcs => writeln!(cargo, "bcs = \"0.1\"")?,
+                        Encoding::Bincode => writeln!(cargo, "bincode = \"1.3\"")?,
+                    }
+                }
+            }
+        }
         std::fs::create_dir(dir_path.join("src"))?;
         let source_path = dir_path.join("src/lib.rs");
-        let mut source = std::fs::File::create(&source_path)?;
+        let mut source = common::create_source_file(config, &source_path)?;
         generator.output(&mut source, &registry)
     }
 
@@ -451,13 +859,8 @@ serde_bytes = "0.11"
         Ok(())
     }
 
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
-        Self::runtime_installation_message("bincode");
-        Ok(())
-    }
-
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        Self::runtime_installation_message("bcs");
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
+        Self::runtime_installation_message(encoding.name());
         Ok(())
     }
 }
@@ -0,0 +1,315 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{transcode, Encoding};
+use serde_reflection::{fingerprint, Format, Registry};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Fingerprint of a registry, as computed by `serde_reflection::fingerprint`.
+pub type Fingerprint = [u8; 32];
+
+/// Default maximal size of the HTTP bodies read by servers and clients (16 MiB).
+pub const DEFAULT_MAX_BODY_SIZE: usize = 16 << 20;
+
+/// Default maximal number of connections handled concurrently by servers.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+
+/// Default timeout of the reads and writes of servers and clients.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximal size of the request line, status line, and each header of HTTP messages (8 KiB).
+const MAX_LINE_LENGTH: usize = 8 << 10;
+
+/// Maximal number of headers of HTTP messages.
+const MAX_HEADERS: usize = 100;
+
+/// In-memory schema registry service, speaking a minimal subset of HTTP/1.1.
+/// * `POST /schemas` with a YAML-encoded registry in the body publishes the registry and
+///   returns its fingerprint (64 hexadecimal digits), or 409 if a different registry was
+///   published with the same fingerprint (e.g. with other source names).
+/// * `GET /schemas/<fingerprint>` returns the YAML-encoded registry, or 404 if unknown.
+/// * Requests whose body exceeds the maximal size are rejected with 413.
+/// * Connections with overly long request lines or headers, or too many headers, are closed,
+///   and so are connections that stay idle longer than the timeout.
+#[derive(Clone)]
+pub struct SchemaRegistryServer {
+    schemas: Arc<Mutex<BTreeMap<Fingerprint, Registry>>>,
+    max_body_size: usize,
+    max_connections: usize,
+    timeout: Duration,
+}
+
+impl Default for SchemaRegistryServer {
+    fn default() -> Self {
+        Self {
+            schemas: Arc::default(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl SchemaRegistryServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximal size of the bodies of requests, in bytes.
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Maximal number of connections handled concurrently. Further connections wait until
+    /// one of them is closed.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Timeout of each read and write on connections.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Accept connections until the listener fails, handling each of them in a new thread, up
+    /// to the maximal number of concurrent connections.
+    pub fn serve(&self, listener: TcpListener) -> Result<()> {
+        let connections = Arc::new((Mutex::new(0), Condvar::new()));
+        for stream in listener.incoming() {
+            let stream = stream?;
+            {
+                let (count, closed) = &*connections;
+                let mut count = closed
+                    .wait_while(count.lock().unwrap(), |count| {
+                        *count >= self.max_connections
+                    })
+                    .unwrap();
+                *count += 1;
+            }
+            let server = self.clone();
+            let connections = connections.clone();
+            std::thread::spawn(move || {
+                server.handle(stream).ok();
+                let (count, closed) = &*connections;
+                *count.lock().unwrap() -= 1;
+                closed.notify_one();
+            });
+        }
+        Ok(())
+    }
+
+    fn handle(&self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let request_line = read_line(&mut reader)?;
+        let length = read_headers(&mut reader)?;
+        if length > self.max_body_size {
+            return write_message(&stream, "HTTP/1.1 413 Payload Too Large", "");
+        }
+        let body = read_body(&mut reader, length)?;
+        let mut words = request_line.split_whitespace();
+        let (status, response) = match (words.next(), words.next()) {
+            (Some("POST"), Some("/schemas")) => self.publish(&body),
+            (Some("GET"), Some(path)) if path.starts_with("/schemas/") => {
+                self.fetch(&path["/schemas/".len()..])
+            }
+            _ => ("404 Not Found", String::new()),
+        };
+        write_message(&stream, &format!("HTTP/1.1 {}", status), &response)
+    }
+
+    fn publish(&self, body: &str) -> (&'static str, String) {
+        let registry = match serde_yaml::from_str::<Registry>(body) {
+            Ok(registry) => registry,
+            Err(error) => return ("400 Bad Request", error.to_string()),
+        };
+        let fingerprint = fingerprint(&registry);
+        let mut schemas = self.schemas.lock().unwrap();
+        match schemas.get(&fingerprint) {
+            Some(existing) if existing != &registry => (
+                "409 Conflict",
+                "A different registry was published with the same fingerprint".to_string(),
+            ),
+            Some(_) => ("200 OK", to_hex(&fingerprint)),
+            None => {
+                schemas.insert(fingerprint, registry);
+                ("200 OK", to_hex(&fingerprint))
+            }
+        }
+    }
+
+    fn fetch(&self, fingerprint: &str) -> (&'static str, String) {
+        let schemas = self.schemas.lock().unwrap();
+        let content = from_hex(fingerprint)
+            .and_then(|fingerprint| schemas.get(&fingerprint))
+            .and_then(|registry| serde_yaml::to_string(registry).ok());
+        match content {
+            Some(content) => ("200 OK", content),
+            None => ("404 Not Found", String::new()),
+        }
+    }
+}
+
+/// Client of a schema registry service. Fetched registries are cached.
+pub struct SchemaRegistryClient {
+    address: String,
+    cache: Mutex<BTreeMap<Fingerprint, Arc<Registry>>>,
+}
+
+impl SchemaRegistryClient {
+    /// Create a client of the service at the given address (e.g. `127.0.0.1:8081`).
+    pub fn new(address: String) -> Self {
+        Self {
+            address,
+            cache: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Publish a registry and return its fingerprint.
+    pub fn publish(&self, registry: &Registry) -> Result<Fingerprint> {
+        let content = serde_yaml::to_string(registry)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+        let response = self.request("POST", "/schemas", &content)?;
+        let fingerprint = from_hex(response.trim())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid fingerprint"))?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(fingerprint, Arc::new(registry.clone()));
+        Ok(fingerprint)
+    }
+
+    /// Obtain the registry with the given fingerprint.
+    pub fn resolve(&self, fingerprint: Fingerprint) -> Result<Arc<Registry>> {
+        if let Some(registry) = self.cache.lock().unwrap().get(&fingerprint) {
+            return Ok(registry.clone());
+        }
+        let content = self.request("GET", &format!("/schemas/{}", to_hex(&fingerprint)), "")?;
+        let registry = serde_yaml::from_str::<Registry>(&content)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+        let registry = Arc::new(registry);
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(fingerprint, registry.clone());
+        Ok(registry)
+    }
+
+    /// Decode a value of the container `name` using the registry with the given fingerprint.
+    pub fn decode(
+        &self,
+        fingerprint: Fingerprint,
+        name: &str,
+        bytes: &[u8],
+        encoding: Encoding,
+    ) -> Result<serde_yaml::Value> {
+        let registry = self.resolve(fingerprint)?;
+        let format = Format::TypeName(name.to_string());
+        transcode::decode(&registry, &format, bytes, encoding)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn request(&self, method: &str, path: &str, body: &str) -> Result<String> {
+        let stream = TcpStream::connect(self.address.as_str())?;
+        stream.set_read_timeout(Some(DEFAULT_TIMEOUT))?;
+        stream.set_write_timeout(Some(DEFAULT_TIMEOUT))?;
+        write_message(&stream, &format!("{} {} HTTP/1.1", method, path), body)?;
+        let mut reader = BufReader::new(stream);
+        let status_line = read_line(&mut reader)?;
+        let length = read_headers(&mut reader)?;
+        if length > DEFAULT_MAX_BODY_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Schema registry response too large",
+            ));
+        }
+        let body = read_body(&mut reader, length)?;
+        match status_line.split_whitespace().nth(1) {
+            Some("200") => Ok(body),
+            Some("404") => Err(Error::new(ErrorKind::NotFound, "Unknown schema")),
+            Some("409") => Err(Error::new(ErrorKind::AlreadyExists, body)),
+            _ => Err(Error::other(format!(
+                "Schema registry error: {} {}",
+                status_line.trim(),
+                body
+            ))),
+        }
+    }
+}
+
+/// Write an HTTP message with the given first line and body.
+fn write_message(mut stream: &TcpStream, first_line: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        first_line,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Read a line of at most `MAX_LINE_LENGTH` bytes, or the rest of the input.
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_LINE_LENGTH as u64).read_line(&mut line)?;
+    if line.len() == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        return Err(Error::new(ErrorKind::InvalidData, "HTTP line too long"));
+    }
+    Ok(line)
+}
+
+/// Read the headers of an HTTP message and return the length of its body, as given by the
+/// `Content-Length` header.
+fn read_headers<R: BufRead>(reader: &mut R) -> Result<usize> {
+    let mut length = 0;
+    for _ in 0..=MAX_HEADERS {
+        let line = read_line(reader)?;
+        if line.trim().is_empty() {
+            return Ok(length);
+        }
+        let mut parts = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Error::new(ErrorKind::InvalidData, "Invalid content length"))?;
+            }
+        }
+    }
+    Err(Error::new(ErrorKind::InvalidData, "Too many HTTP headers"))
+}
+
+/// Read a body of `length` bytes.
+fn read_body<R: BufRead>(reader: &mut R, length: usize) -> Result<String> {
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+fn to_hex(fingerprint: &Fingerprint) -> String {
+    fingerprint
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn from_hex(hex: &str) -> Option<Fingerprint> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut fingerprint = [0; 32];
+    for (index, byte) in fingerprint.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * index..2 * index + 2], 16).ok()?;
+    }
+    Some(fingerprint)
+}
@@ -0,0 +1,277 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    common,
+    indent::{IndentConfig, IndentedWriter},
+    CodeGeneratorConfig, Encoding,
+};
+use heck::SnakeCase;
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
+
+/// SQL dialect of the generated statements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dialect {
+    #[default]
+    Postgres,
+    ClickHouse,
+}
+
+/// Main configuration object for the generation of SQL tables.
+/// * Structs are mapped to tables with one column per field.
+/// * Primitive fields use the closest SQL type, C-style enums are stored as the name of
+///   the variant, and other nested values are stored in JSON columns.
+/// * Enums and unit structs do not have tables.
+pub struct CodeGenerator<'a> {
+    /// Language-independent configuration.
+    config: &'a CodeGeneratorConfig,
+    /// SQL dialect.
+    dialect: Dialect,
+}
+
+/// Shared state for the generation of SQL statements.
+struct SqlEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    /// Generator.
+    generator: &'a CodeGenerator<'a>,
+    /// Registry of containers.
+    registry: &'a Registry,
+}
+
+impl<'a> CodeGenerator<'a> {
+    /// Create a SQL generator for the given config.
+    pub fn new(config: &'a CodeGeneratorConfig) -> Self {
+        Self {
+            config,
+            dialect: Dialect::default(),
+        }
+    }
+
+    /// SQL dialect of the generated statements.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Write `CREATE TABLE` statements for the structs of the registry.
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        let registry = &*common::without_skipped_fields(registry)?;
+        common::check_formats("SQL", registry)?;
+        let mut emitter = SqlEmitter {
+            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            generator: self,
+            registry,
+        };
+        writeln!(
+            emitter.out,
+            "-- Tables for the Serde formats of `{}`.",
+            self.config.module_name
+        )?;
+        for (name, format) in registry {
+            common::check_cancellation(self.config)?;
+            emitter.output_container(name, format)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> SqlEmitter<'a, T>
+where
+    T: Write,
+{
+    fn quote_identifier(&self, name: &str) -> String {
+        match self.generator.dialect {
+            Dialect::Postgres => format!("\"{}\"", name),
+            Dialect::ClickHouse => format!("`{}`", name),
+        }
+    }
+
+    fn is_c_style_enum(&self, name: &str) -> bool {
+        match self.registry.get(name) {
+            Some(ContainerFormat::Enum(variants)) => variants
+                .values()
+                .all(|variant| matches!(variant.value, VariantFormat::Unit)),
+            _ => false,
+        }
+    }
+
+    /// SQL type of a column holding values of the given (non-optional) format.
+    fn quote_column_type(&self, format: &Format) -> String {
+        use Dialect::*;
+        use Format::*;
+        let dialect = self.generator.dialect;
+        let json = match dialect {
+            Postgres => "JSONB",
+            ClickHouse => "String",
+        };
+        let sql_type = match (format, dialect) {
+            (TypeName(name), _) if self.is_c_style_enum(name) => match dialect {
+                Postgres => "TEXT",
+                ClickHouse => "LowCardinality(String)",
+            },
+            (Custom { layout, .. }, _) => return self.quote_column_type(layout),
+            (Bool, Postgres) => "BOOLEAN",
+            (Bool, ClickHouse) => "Bool",
+            (I8, Postgres) | (I16, Postgres) | (U8, Postgres) => "SMALLINT",
+            (I32, Postgres) | (U16, Postgres) => "INTEGER",
+            (I64, Postgres) | (U32, Postgres) => "BIGINT",
+            (U64, Postgres) => "NUMERIC(20, 0)",
+            (I128, Postgres) | (U128, Postgres) => "NUMERIC(39, 0)",
+            (F32, Postgres) => "REAL",
+            (F64, Postgres) => "DOUBLE PRECISION",
+            (Char, Postgres) | (Str, Postgres) => "TEXT",
+            (Bytes, Postgres) => "BYTEA",
+            (I8, ClickHouse) => "Int8",
+            (I16, ClickHouse) => "Int16",
+            (I32, ClickHouse) => "Int32",
+            (I64, ClickHouse) => "Int64",
+            (I128, ClickHouse) => "Int128",
+            (U8, ClickHouse) => "UInt8",
+            (U16, ClickHouse) => "UInt16",
+            (U32, ClickHouse) => "UInt32",
+            (U64, ClickHouse) => "UInt64",
+            (U128, ClickHouse) => "UInt128",
+            (F32, ClickHouse) => "Float32",
+            (F64, ClickHouse) => "Float64",
+            (Char, ClickHouse) | (Str, ClickHouse) | (Bytes, ClickHouse) => "String",
+            (Variable(_), _) => unreachable!("formats are checked by common::check_formats"),
+            _ => json,
+        };
+        sql_type.to_string()
+    }
+
+    fn quote_column(&self, name: &str, format: &Format) -> String {
+        let (format, nullable) = match format {
+            Format::Option(format) => (format.as_ref(), true),
+            _ => (format, false),
+        };
+        let column_type = self.quote_column_type(format);
+        let column_type = match (self.generator.dialect, nullable) {
+            (Dialect::Postgres, false) => format!("{} NOT NULL", column_type),
+            (Dialect::Postgres, true) => column_type,
+            (Dialect::ClickHouse, false) => column_type,
+            (Dialect::ClickHouse, true) if column_type.starts_with("LowCardinality") => {
+                "LowCardinality(Nullable(String))".to_string()
+            }
+            (Dialect::ClickHouse, true) => format!("Nullable({})", column_type),
+        };
+        format!("{} {}", self.quote_identifier(name), column_type)
+    }
+
+    fn output_comment(&mut self, name: &str) -> Result<()> {
+        let mut path: Vec<_> = self
+            .generator
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect();
+        path.push(name.to_string());
+        if let Some(doc) = self.generator.config.comment(&path) {
+            write!(self.out, "{}", textwrap::indent(&doc, "-- "))?;
+        }
+        Ok(())
+    }
+
+    fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        let columns: Vec<_> = match format {
+            UnitStruct | Enum(_) | InternallyTaggedEnum { .. } => return Ok(()),
+            NewTypeStruct(format) => vec![self.quote_column("value", format)],
+            TupleStruct(formats) => formats
+                .iter()
+                .enumerate()
+                .map(|(i, format)| self.quote_column(&format!("field{}", i), format))
+                .collect(),
+            Struct(fields) => fields
+                .iter()
+                .map(|Named { name, value, .. }| self.quote_column(&name.to_snake_case(), value))
+                .collect(),
+        };
+        if columns.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(
+            self.out,
+            "CREATE TABLE {} (",
+            self.quote_identifier(&name.to_snake_case())
+        )?;
+        self.out.indent();
+        writeln!(self.out, "{}", columns.join(",\n"))?;
+        self.out.unindent();
+        match self.generator.dialect {
+            Dialect::Postgres => writeln!(self.out, ");"),
+            Dialect::ClickHouse => writeln!(self.out, ")\nENGINE = MergeTree()\nORDER BY tuple();"),
+        }
+    }
+}
+
+/// Installer for SQL tables.
+#[derive(Clone)]
+pub struct Installer {
+    install_dir: PathBuf,
+    dialect: Dialect,
+}
+
+impl Installer {
+    pub fn new(install_dir: PathBuf) -> Self {
+        Installer {
+            install_dir,
+            dialect: Dialect::default(),
+        }
+    }
+
+    /// SQL dialect of the generated statements.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+}
+
+impl crate::SourceInstaller for Installer {
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
+
+    fn install_module(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &Registry,
+    ) -> std::result::Result<(), Self::Error> {
+        std::fs::create_dir_all(&self.install_dir)?;
+        let path = self
+            .install_dir
+            .join(format!("{}.sql", config.module_name.replace('.', "_")));
+        let mut file = common::create_source_file(config, path)?;
+        let generator = CodeGenerator::new(config).with_dialect(self.dialect);
+        generator.output(&mut file, registry)?;
+        Ok(())
+    }
+
+    // SQL tables do not depend on a runtime.
+
+    fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn install_encoding_runtime(
+        &self,
+        _encoding: Encoding,
+    ) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{transcode, Encoding};
+use serde_reflection::{Error, Format, Registry, Result};
+use std::fmt;
+
+/// The encoding of a value of a named type, as shared between implementations to check their
+/// conformance.
+///
+/// In a test-vector file, each vector takes one line made of four fields separated by tabs:
+/// the type name, the encoding (`bcs` or `bincode`), the encoded bytes in lowercase
+/// hexadecimal, and the value in JSON. Empty lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestVector {
+    pub type_name: String,
+    pub encoding: Encoding,
+    pub bytes: Vec<u8>,
+    /// The value in JSON, following the representation of `transcode::encode`.
+    pub json: String,
+}
+
+impl TestVector {
+    /// Encode a dynamic value of the container `type_name` with each of the given encodings.
+    pub fn generate(
+        registry: &Registry,
+        type_name: &str,
+        value: &serde_yaml::Value,
+        encodings: &[Encoding],
+    ) -> Result<Vec<TestVector>> {
+        let format = Format::TypeName(type_name.to_string());
+        let json = transcode::to_json(value);
+        let mut vectors = Vec::new();
+        for encoding in encodings {
+            vectors.push(TestVector {
+                type_name: type_name.to_string(),
+                encoding: *encoding,
+                bytes: transcode::encode(registry, &format, value, *encoding)?,
+                json: json.clone(),
+            });
+        }
+        Ok(vectors)
+    }
+
+    /// Check that the bytes of the vector decode into its value.
+    pub fn check(&self, registry: &Registry) -> Result<()> {
+        let format = Format::TypeName(self.type_name.clone());
+        let value = serde_yaml::from_str::<serde_yaml::Value>(&self.json)
+            .map_err(|error| Error::Custom(format!("Invalid JSON value: {}", error)))?;
+        let decoded = transcode::decode(registry, &format, &self.bytes, self.encoding)?;
+        if decoded != value {
+            return Err(Error::Custom(format!(
+                "Bytes decode into {} instead of {}",
+                transcode::to_json(&decoded),
+                self.json
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TestVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\t{}\t", self.type_name, self.encoding.name())?;
+        for byte in &self.bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, "\t{}", self.json)
+    }
+}
+
+/// Formats of the types of the shared corpus of test vectors, see `corpus`.
+pub fn corpus_registry() -> Registry {
+    serde_yaml::from_str(include_str!("../runtime/test_vectors/corpus.yaml"))
+        .expect("the corpus registry should be valid")
+}
+
+/// Shared corpus of BCS and bincode test vectors, covering extreme integers, ULEB128 lengths
+/// around 127 and 128, multi-byte UTF-8, every kind of variant, and maps in canonical order.
+///
+/// Installers copy the corpus next to generated runners (see
+/// `SourceInstaller::install_corpus_runner`), so that every runtime is checked against the
+/// same bytes.
+pub fn corpus() -> Vec<TestVector> {
+    parse(include_str!("../runtime/test_vectors/corpus.tsv"))
+        .expect("the corpus of test vectors should be valid")
+}
+
+/// Parse the content of a test-vector file.
+pub fn parse(content: &str) -> Result<Vec<TestVector>> {
+    let mut vectors = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let vector = parse_line(line)
+            .ok_or_else(|| Error::Custom(format!("Invalid test vector at line {}", index + 1)))?;
+        vectors.push(vector);
+    }
+    Ok(vectors)
+}
+
+fn parse_line(line: &str) -> Option<TestVector> {
+    let mut fields = line.splitn(4, '\t');
+    let type_name = fields.next()?.to_string();
+    let encoding = match fields.next()? {
+        "bcs" => Encoding::Bcs,
+        "bincode" => Encoding::Bincode,
+        _ => return None,
+    };
+    let hex = fields.next()?.as_bytes();
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = hex
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    let json = fields.next()?.to_string();
+    Some(TestVector {
+        type_name,
+        encoding,
+        bytes,
+        json,
+    })
+}
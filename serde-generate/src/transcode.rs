@@ -0,0 +1,852 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::Encoding;
+use serde_reflection::{ContainerFormat, Error, Format, Named, Registry, Result, VariantFormat};
+use serde_yaml::{Mapping, Number, Value};
+use std::convert::TryFrom;
+
+/// Encode a dynamic value (typically parsed from JSON or YAML) following the given format.
+/// * Structs are represented by mappings from field names to values, tuples by sequences,
+///   units by `null`.
+/// * Enum values are either the name of a unit variant, or a mapping with a single
+///   entry from the variant name to its content.
+/// * Values of internally tagged enums are mappings holding the variant name under the tag,
+///   together with the fields of the variant.
+/// * Optional values are `null` or their content, except when the content itself may be
+///   represented by `null` (e.g. nested options): present values are then wrapped in a
+///   sequence of one element.
+/// * 128-bit integers may be given as decimal strings.
+pub fn encode(
+    registry: &Registry,
+    format: &Format,
+    value: &Value,
+    encoding: Encoding,
+) -> Result<Vec<u8>> {
+    let mut encoder = Encoder {
+        registry,
+        encoding,
+        bytes: Vec::new(),
+    };
+    encoder.encode(format, value)?;
+    Ok(encoder.bytes)
+}
+
+/// Decode bytes following the given format, using the same value representation as `encode`.
+/// All the input must be consumed. Whatever the encoding, containers may be nested at most
+/// `bcs::MAX_CONTAINER_DEPTH` times so that recursive formats cannot exhaust the stack.
+pub fn decode(
+    registry: &Registry,
+    format: &Format,
+    bytes: &[u8],
+    encoding: Encoding,
+) -> Result<Value> {
+    let mut decoder = Decoder {
+        registry,
+        encoding,
+        input: bytes,
+        depth: 0,
+    };
+    let value = decoder.decode(format)?;
+    if !decoder.input.is_empty() {
+        return Err(Error::Custom(format!(
+            "{} trailing bytes after decoded value",
+            decoder.input.len()
+        )));
+    }
+    Ok(value)
+}
+
+/// Print a dynamic value as (single-line) JSON. Non-finite numbers are printed as `null`.
+pub fn to_json(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => serde_json::to_string(n).expect("numbers are valid JSON"),
+        Value::String(s) => json_string(s),
+        Value::Sequence(values) => format!(
+            "[{}]",
+            values.iter().map(to_json).collect::<Vec<_>>().join(", ")
+        ),
+        Value::Mapping(entries) => format!(
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Value::String(_) => to_json(key),
+                        _ => json_string(&to_json(key)),
+                    };
+                    format!("{}: {}", key, to_json(value))
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// The JSON string literal of `s`, e.g. with control characters escaped as `\u0001`.
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).expect("strings are valid JSON")
+}
+
+/// Compare two decoded values and describe where they diverge, one line per difference.
+/// Paths start with `$`, followed by field (or variant) names and sequence indices.
+pub fn diff(old: &Value, new: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff_at("$".to_string(), old, new, &mut differences);
+    differences
+}
+
+fn diff_at(path: String, old: &Value, new: &Value, differences: &mut Vec<String>) {
+    let child_path = |key: &Value| match key {
+        Value::String(name) => format!("{}.{}", path, name),
+        _ => format!("{}[{}]", path, to_json(key)),
+    };
+    match (old, new) {
+        (Value::Mapping(old_entries), Value::Mapping(new_entries)) => {
+            for (key, old_value) in old_entries.iter() {
+                match new_entries.get(key) {
+                    Some(new_value) => diff_at(child_path(key), old_value, new_value, differences),
+                    None => differences.push(format!(
+                        "{}: only in old schema: {}",
+                        child_path(key),
+                        to_json(old_value)
+                    )),
+                }
+            }
+            for (key, new_value) in new_entries.iter() {
+                if !old_entries.contains_key(key) {
+                    differences.push(format!(
+                        "{}: only in new schema: {}",
+                        child_path(key),
+                        to_json(new_value)
+                    ));
+                }
+            }
+        }
+        (Value::Sequence(old_values), Value::Sequence(new_values)) => {
+            if old_values.len() != new_values.len() {
+                differences.push(format!(
+                    "{}: {} elements vs {} elements",
+                    path,
+                    old_values.len(),
+                    new_values.len()
+                ));
+            }
+            for (index, (old_value, new_value)) in old_values.iter().zip(new_values).enumerate() {
+                diff_at(
+                    format!("{}[{}]", path, index),
+                    old_value,
+                    new_value,
+                    differences,
+                );
+            }
+        }
+        _ => {
+            if old != new {
+                differences.push(format!("{}: {} vs {}", path, to_json(old), to_json(new)));
+            }
+        }
+    }
+}
+
+fn error<T>(message: String) -> Result<T> {
+    Err(Error::Custom(message))
+}
+
+fn unexpected<T>(expected: &str, value: &Value) -> Result<T> {
+    error(format!("Expected {}, found {:?}", expected, value))
+}
+
+fn has_duplicates<T: Ord>(elements: &[T]) -> bool {
+    let mut seen = std::collections::BTreeSet::new();
+    !elements.iter().all(|element| seen.insert(element))
+}
+
+fn lookup<'a>(registry: &'a Registry, name: &str) -> Result<&'a ContainerFormat> {
+    match registry.get(name) {
+        Some(format) => Ok(format),
+        None => error(format!("Unknown container: {}", name)),
+    }
+}
+
+/// Whether values of the given format may be represented by `null`.
+fn is_nullable(registry: &Registry, format: &Format) -> bool {
+    match format {
+        Format::Unit | Format::Option(_) => true,
+        Format::Custom { layout, .. } => is_nullable(registry, layout),
+        Format::Decimal { mantissa, .. } => is_nullable(registry, mantissa),
+        Format::TypeName(name) => match registry.get(name) {
+            Some(ContainerFormat::UnitStruct) => true,
+            Some(ContainerFormat::NewTypeStruct(format)) => is_nullable(registry, format),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn tag_key(tag: &str) -> Value {
+    Value::String(tag.to_string())
+}
+
+/// Decompose the representation of an enum value into a variant name and its content (if any).
+fn variant_of(value: &Value) -> Result<(&str, Option<&Value>)> {
+    match value {
+        Value::String(name) => Ok((name, None)),
+        Value::Mapping(entries) if entries.len() == 1 => {
+            let (name, content) = entries.iter().next().unwrap();
+            match name.as_str() {
+                Some(name) => Ok((name, Some(content))),
+                None => unexpected("variant name", name),
+            }
+        }
+        _ => unexpected("enum variant", value),
+    }
+}
+
+macro_rules! write_integer {
+    ($self:ident, $value:expr, $ty:ty) => {
+        $self
+            .bytes
+            .extend(&Self::integer::<$ty>($value)?.to_le_bytes())
+    };
+}
+
+struct Encoder<'a> {
+    registry: &'a Registry,
+    encoding: Encoding,
+    bytes: Vec<u8>,
+}
+
+impl<'a> Encoder<'a> {
+    fn write_u32_as_uleb128(&mut self, mut value: u32) {
+        while value >= 0x80 {
+            self.bytes.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn write_len(&mut self, len: usize) -> Result<()> {
+        match self.encoding {
+            Encoding::Bincode => self.bytes.extend(&(len as u64).to_le_bytes()),
+            Encoding::Bcs => match u32::try_from(len) {
+                Ok(len) if len <= i32::MAX as u32 => self.write_u32_as_uleb128(len),
+                _ => return error(format!("Length {} is too large for BCS", len)),
+            },
+        }
+        Ok(())
+    }
+
+    fn write_variant_index(&mut self, index: u32) {
+        match self.encoding {
+            Encoding::Bincode => self.bytes.extend(&index.to_le_bytes()),
+            Encoding::Bcs => self.write_u32_as_uleb128(index),
+        }
+    }
+
+    fn integer<T>(value: &Value) -> Result<T>
+    where
+        T: TryFrom<i128> + std::str::FromStr,
+    {
+        let result = match value {
+            Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+                (Some(x), _) => T::try_from(x as i128).ok(),
+                (None, Some(x)) => T::try_from(x as i128).ok(),
+                _ => None,
+            },
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        };
+        match result {
+            Some(x) => Ok(x),
+            None => unexpected("integer in range", value),
+        }
+    }
+
+    fn float(&self, value: &Value) -> Result<f64> {
+        if let Encoding::Bcs = self.encoding {
+            return Err(Error::NotSupported("floats in BCS"));
+        }
+        match value.as_f64() {
+            Some(x) => Ok(x),
+            None => unexpected("number", value),
+        }
+    }
+
+    fn sequence(value: &Value, len: Option<usize>) -> Result<&Vec<Value>> {
+        match value.as_sequence() {
+            Some(values) if len.is_none() || len == Some(values.len()) => Ok(values),
+            _ => unexpected(
+                &match len {
+                    Some(len) => format!("sequence of length {}", len),
+                    None => "sequence".to_string(),
+                },
+                value,
+            ),
+        }
+    }
+
+    fn encode(&mut self, format: &Format, value: &Value) -> Result<()> {
+        use Format::*;
+        match format {
+            Variable(_) => return Err(Error::UnknownFormat),
+            TypeName(name) => {
+                let container = lookup(self.registry, name)?;
+                self.encode_container(container, value)?;
+            }
+            Unit => {
+                if !value.is_null() {
+                    return unexpected("null", value);
+                }
+            }
+            Bool => match value.as_bool() {
+                Some(x) => self.bytes.push(x as u8),
+                None => return unexpected("boolean", value),
+            },
+            I8 => write_integer!(self, value, i8),
+            I16 => write_integer!(self, value, i16),
+            I32 => write_integer!(self, value, i32),
+            I64 => write_integer!(self, value, i64),
+            I128 => write_integer!(self, value, i128),
+            U8 => write_integer!(self, value, u8),
+            U16 => write_integer!(self, value, u16),
+            U32 => write_integer!(self, value, u32),
+            U64 => write_integer!(self, value, u64),
+            U128 => write_integer!(self, value, u128),
+            F32 => {
+                let x = self.float(value)? as f32;
+                self.bytes.extend(&x.to_bits().to_le_bytes());
+            }
+            F64 => {
+                let x = self.float(value)?;
+                self.bytes.extend(&x.to_bits().to_le_bytes());
+            }
+            Char => {
+                if let Encoding::Bcs = self.encoding {
+                    return Err(Error::NotSupported("characters in BCS"));
+                }
+                let mut chars = value.as_str().unwrap_or_default().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => {
+                        let mut buffer = [0; 4];
+                        self.bytes.extend(c.encode_utf8(&mut buffer).as_bytes());
+                    }
+                    _ => return unexpected("single character", value),
+                }
+            }
+            Str => match value.as_str() {
+                Some(s) => {
+                    self.write_len(s.len())?;
+                    self.bytes.extend(s.as_bytes());
+                }
+                None => return unexpected("string", value),
+            },
+            Bytes => {
+                let values = Self::sequence(value, None)?;
+                self.write_len(values.len())?;
+                for value in values {
+                    self.bytes.push(Self::integer::<u8>(value)?);
+                }
+            }
+            Option(format) => {
+                if value.is_null() {
+                    self.bytes.push(0);
+                } else if is_nullable(self.registry, format) {
+                    match value.as_sequence().map(Vec::as_slice) {
+                        Some([content]) => {
+                            self.bytes.push(1);
+                            self.encode(format, content)?;
+                        }
+                        _ => return unexpected("null or a sequence of one element", value),
+                    }
+                } else {
+                    self.bytes.push(1);
+                    self.encode(format, value)?;
+                }
+            }
+            Seq(format) => {
+                let values = Self::sequence(value, None)?;
+                self.write_len(values.len())?;
+                for value in values {
+                    self.encode(format, value)?;
+                }
+            }
+            Set(format) => {
+                let values = Self::sequence(value, None)?;
+                let mut encoded = Vec::new();
+                for value in values {
+                    let start = self.bytes.len();
+                    self.encode(format, value)?;
+                    encoded.push(self.bytes.split_off(start));
+                }
+                if let Encoding::Bcs = self.encoding {
+                    // BCS requires set elements to be sorted by serialized values.
+                    encoded.sort();
+                }
+                if has_duplicates(&encoded) {
+                    return error("Duplicate elements in set".to_string());
+                }
+                self.write_len(encoded.len())?;
+                for element in encoded {
+                    self.bytes.extend(element);
+                }
+            }
+            Map { key, value: format } => {
+                let entries = match value.as_mapping() {
+                    Some(entries) => entries,
+                    None => return unexpected("mapping", value),
+                };
+                let mut encoded = Vec::new();
+                for (k, v) in entries.iter() {
+                    let start = self.bytes.len();
+                    self.encode(key, k)?;
+                    let key_bytes = self.bytes.split_off(start);
+                    self.encode(format, v)?;
+                    let value_bytes = self.bytes.split_off(start);
+                    encoded.push((key_bytes, value_bytes));
+                }
+                if let Encoding::Bcs = self.encoding {
+                    // BCS requires map entries to be sorted by serialized keys.
+                    encoded.sort();
+                }
+                self.write_len(encoded.len())?;
+                for (k, v) in encoded {
+                    self.bytes.extend(k);
+                    self.bytes.extend(v);
+                }
+            }
+            Tuple(formats) => self.encode_tuple(formats, value)?,
+            TupleArray { content, size } => {
+                let values = Self::sequence(value, Some(*size))?;
+                for value in values {
+                    self.encode(content, value)?;
+                }
+            }
+            Custom { layout, .. } => self.encode(layout, value)?,
+            Decimal { mantissa, .. } => self.encode(mantissa, value)?,
+            Skipped(_) => (),
+        }
+        Ok(())
+    }
+
+    fn encode_tuple(&mut self, formats: &[Format], value: &Value) -> Result<()> {
+        let values = Self::sequence(value, Some(formats.len()))?;
+        for (format, value) in formats.iter().zip(values) {
+            self.encode(format, value)?;
+        }
+        Ok(())
+    }
+
+    fn encode_struct(&mut self, fields: &[Named<Format>], value: &Value) -> Result<()> {
+        let entries = match value.as_mapping() {
+            Some(entries) => entries,
+            None => return unexpected("mapping", value),
+        };
+        // Skipped fields are absent from the wire.
+        for field in fields
+            .iter()
+            .filter(|field| !matches!(field.value, Format::Skipped(_)))
+        {
+            match entries.get(&Value::String(field.name.clone())) {
+                Some(value) => self.encode(&field.value, value)?,
+                None => return error(format!("Missing field: {}", field.name)),
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_container(&mut self, format: &ContainerFormat, value: &Value) -> Result<()> {
+        use ContainerFormat::*;
+        match format {
+            UnitStruct => self.encode(&Format::Unit, value),
+            NewTypeStruct(format) => self.encode(format, value),
+            TupleStruct(formats) => self.encode_tuple(formats, value),
+            Struct(fields) => self.encode_struct(fields, value),
+            Enum(variants) => {
+                let (name, content) = variant_of(value)?;
+                match variants.iter().find(|(_, variant)| variant.name == name) {
+                    Some((index, variant)) => {
+                        self.write_variant_index(*index);
+                        self.encode_variant(&variant.value, content)
+                    }
+                    None => error(format!("Unknown variant: {}", name)),
+                }
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                let name = match value.as_mapping().and_then(|e| e.get(&tag_key(tag))) {
+                    Some(Value::String(name)) => name,
+                    _ => return unexpected(&format!("mapping with a field `{}`", tag), value),
+                };
+                let variant = match variants.values().find(|variant| &variant.name == name) {
+                    Some(variant) => variant,
+                    None => return error(format!("Unknown variant: {}", name)),
+                };
+                self.encode(&Format::Str, &Value::String(name.clone()))?;
+                // The content of the variant is read from the same mapping as the tag.
+                match &variant.value {
+                    VariantFormat::Unit => Ok(()),
+                    VariantFormat::NewType(format) => self.encode(format, value),
+                    VariantFormat::Struct(fields) => self.encode_struct(fields, value),
+                    format => error(format!("Unsupported tagged variant: {:?}", format)),
+                }
+            }
+        }
+    }
+
+    fn encode_variant(&mut self, format: &VariantFormat, value: Option<&Value>) -> Result<()> {
+        use VariantFormat::*;
+        match (format, value) {
+            (Variable(_), _) => Err(Error::UnknownFormat),
+            (Unit, None) => Ok(()),
+            (Unit, Some(value)) => self.encode(&Format::Unit, value),
+            (_, None) => error("Missing variant content".to_string()),
+            (NewType(format), Some(value)) => self.encode(format, value),
+            (Tuple(formats), Some(value)) => self.encode_tuple(formats, value),
+            (Struct(fields), Some(value)) => self.encode_struct(fields, value),
+        }
+    }
+}
+
+struct Decoder<'a, 'b> {
+    registry: &'a Registry,
+    encoding: Encoding,
+    input: &'b [u8],
+    depth: usize,
+}
+
+macro_rules! read_integer {
+    ($self:ident, $ty:ty) => {{
+        let mut buffer = [0u8; std::mem::size_of::<$ty>()];
+        let bytes = $self.read_bytes(buffer.len())?;
+        buffer.copy_from_slice(bytes);
+        <$ty>::from_le_bytes(buffer)
+    }};
+}
+
+impl<'a, 'b> Decoder<'a, 'b> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'b [u8]> {
+        if self.input.len() < len {
+            return error("Unexpected end of input".to_string());
+        }
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(bytes)
+    }
+
+    fn read_u32_as_uleb128(&mut self) -> Result<u32> {
+        let mut value: u64 = 0;
+        for shift in (0..32).step_by(7) {
+            let byte = self.read_bytes(1)?[0];
+            let digit = (byte & 0x7f) as u64;
+            value |= digit << shift;
+            if byte & 0x80 == 0 {
+                if shift > 0 && digit == 0 {
+                    return error("Invalid ULEB128 number (non-canonical)".to_string());
+                }
+                return match u32::try_from(value) {
+                    Ok(value) => Ok(value),
+                    Err(_) => error("Invalid ULEB128 number (overflow)".to_string()),
+                };
+            }
+        }
+        error("Invalid ULEB128 number (too many bytes)".to_string())
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        let len = match self.encoding {
+            Encoding::Bincode => read_integer!(self, u64),
+            Encoding::Bcs => {
+                let len = self.read_u32_as_uleb128()?;
+                if len > i32::MAX as u32 {
+                    return error(format!("Length {} is too large for BCS", len));
+                }
+                len as u64
+            }
+        };
+        match usize::try_from(len) {
+            Ok(len) => Ok(len),
+            Err(_) => error(format!("Length {} is too large", len)),
+        }
+    }
+
+    fn read_variant_index(&mut self) -> Result<u32> {
+        match self.encoding {
+            Encoding::Bincode => Ok(read_integer!(self, u32)),
+            Encoding::Bcs => self.read_u32_as_uleb128(),
+        }
+    }
+
+    fn integer_value<T: std::fmt::Display>(value: T, small: Option<Number>) -> Value {
+        match small {
+            Some(n) => Value::Number(n),
+            None => Value::String(value.to_string()),
+        }
+    }
+
+    fn decode(&mut self, format: &Format) -> Result<Value> {
+        use Format::*;
+        let value = match format {
+            Variable(_) => return Err(Error::UnknownFormat),
+            TypeName(name) => {
+                let container = lookup(self.registry, name)?;
+                if self.depth >= bcs::MAX_CONTAINER_DEPTH {
+                    return error(format!(
+                        "Exceeded the maximum container depth {}",
+                        bcs::MAX_CONTAINER_DEPTH
+                    ));
+                }
+                self.depth += 1;
+                let value = self.decode_container(container);
+                self.depth -= 1;
+                value?
+            }
+            Unit => Value::Null,
+            Bool => match self.read_bytes(1)?[0] {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                b => return error(format!("Invalid boolean: {}", b)),
+            },
+            I8 => Value::Number((read_integer!(self, i8) as i64).into()),
+            I16 => Value::Number((read_integer!(self, i16) as i64).into()),
+            I32 => Value::Number((read_integer!(self, i32) as i64).into()),
+            I64 => Value::Number(read_integer!(self, i64).into()),
+            I128 => {
+                let x = read_integer!(self, i128);
+                Self::integer_value(x, i64::try_from(x).ok().map(Number::from))
+            }
+            U8 => Value::Number((read_integer!(self, u8) as u64).into()),
+            U16 => Value::Number((read_integer!(self, u16) as u64).into()),
+            U32 => Value::Number((read_integer!(self, u32) as u64).into()),
+            U64 => Value::Number(read_integer!(self, u64).into()),
+            U128 => {
+                let x = read_integer!(self, u128);
+                Self::integer_value(x, u64::try_from(x).ok().map(Number::from))
+            }
+            F32 | F64 if self.encoding == Encoding::Bcs => {
+                return Err(Error::NotSupported("floats in BCS"))
+            }
+            F32 => Value::Number((f32::from_bits(read_integer!(self, u32)) as f64).into()),
+            F64 => Value::Number(f64::from_bits(read_integer!(self, u64)).into()),
+            Char => {
+                if let Encoding::Bcs = self.encoding {
+                    return Err(Error::NotSupported("characters in BCS"));
+                }
+                let first = self.input.first().copied().unwrap_or_default();
+                let len = match first {
+                    0x00..=0x7f => 1,
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    _ => 4,
+                };
+                match std::str::from_utf8(self.read_bytes(len)?) {
+                    Ok(s) => Value::String(s.to_string()),
+                    Err(e) => return error(format!("Invalid character: {}", e)),
+                }
+            }
+            Str => {
+                let len = self.read_len()?;
+                match std::str::from_utf8(self.read_bytes(len)?) {
+                    Ok(s) => Value::String(s.to_string()),
+                    Err(e) => return error(format!("Invalid string: {}", e)),
+                }
+            }
+            Bytes => {
+                let len = self.read_len()?;
+                let bytes = self.read_bytes(len)?;
+                Value::Sequence(
+                    bytes
+                        .iter()
+                        .map(|b| Value::Number((*b as u64).into()))
+                        .collect(),
+                )
+            }
+            Option(format) => match self.read_bytes(1)?[0] {
+                0 => Value::Null,
+                1 if is_nullable(self.registry, format) => {
+                    Value::Sequence(vec![self.decode(format)?])
+                }
+                1 => self.decode(format)?,
+                b => return error(format!("Invalid option tag: {}", b)),
+            },
+            Seq(format) => {
+                let len = self.read_len()?;
+                let mut values = Vec::new();
+                for _ in 0..len {
+                    values.push(self.decode(format)?);
+                }
+                Value::Sequence(values)
+            }
+            Set(format) => {
+                let len = self.read_len()?;
+                let mut values = Vec::new();
+                let mut encoded = Vec::new();
+                for _ in 0..len {
+                    let start = self.input;
+                    values.push(self.decode(format)?);
+                    encoded.push(&start[..start.len() - self.input.len()]);
+                }
+                if let Encoding::Bcs = self.encoding {
+                    if encoded.windows(2).any(|pair| pair[0] >= pair[1]) {
+                        return error("Set elements are not strictly increasing".to_string());
+                    }
+                } else if has_duplicates(&encoded) {
+                    return error("Duplicate elements in set".to_string());
+                }
+                Value::Sequence(values)
+            }
+            Map { key, value } => {
+                let len = self.read_len()?;
+                let mut entries = Mapping::new();
+                for _ in 0..len {
+                    let k = self.decode(key)?;
+                    let v = self.decode(value)?;
+                    entries.insert(k, v);
+                }
+                Value::Mapping(entries)
+            }
+            Tuple(formats) => self.decode_tuple(formats)?,
+            TupleArray { content, size } => {
+                let mut values = Vec::new();
+                for _ in 0..*size {
+                    values.push(self.decode(content)?);
+                }
+                Value::Sequence(values)
+            }
+            Custom { layout, .. } => self.decode(layout)?,
+            Decimal { mantissa, .. } => self.decode(mantissa)?,
+            Skipped(_) => Value::Null,
+        };
+        Ok(value)
+    }
+
+    fn decode_tuple(&mut self, formats: &[Format]) -> Result<Value> {
+        let mut values = Vec::new();
+        for format in formats {
+            values.push(self.decode(format)?);
+        }
+        Ok(Value::Sequence(values))
+    }
+
+    fn decode_struct(&mut self, fields: &[Named<Format>]) -> Result<Value> {
+        let mut entries = Mapping::new();
+        for field in fields
+            .iter()
+            .filter(|field| !matches!(field.value, Format::Skipped(_)))
+        {
+            let value = self.decode(&field.value)?;
+            entries.insert(Value::String(field.name.clone()), value);
+        }
+        Ok(Value::Mapping(entries))
+    }
+
+    fn decode_container(&mut self, format: &ContainerFormat) -> Result<Value> {
+        use ContainerFormat::*;
+        match format {
+            UnitStruct => Ok(Value::Null),
+            NewTypeStruct(format) => self.decode(format),
+            TupleStruct(formats) => self.decode_tuple(formats),
+            Struct(fields) => self.decode_struct(fields),
+            Enum(variants) => {
+                let index = self.read_variant_index()?;
+                let variant = match variants.get(&index) {
+                    Some(variant) => variant,
+                    None => return error(format!("Unknown variant index: {}", index)),
+                };
+                let name = Value::String(variant.name.clone());
+                let content = match &variant.value {
+                    VariantFormat::Variable(_) => return Err(Error::UnknownFormat),
+                    VariantFormat::Unit => return Ok(name),
+                    VariantFormat::NewType(format) => self.decode(format)?,
+                    VariantFormat::Tuple(formats) => self.decode_tuple(formats)?,
+                    VariantFormat::Struct(fields) => self.decode_struct(fields)?,
+                };
+                let mut entries = Mapping::new();
+                entries.insert(name, content);
+                Ok(Value::Mapping(entries))
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                let name = self.decode(&Format::Str)?;
+                let variant = match variants
+                    .values()
+                    .find(|variant| name == variant.name.as_str())
+                {
+                    Some(variant) => variant,
+                    None => return error(format!("Unknown variant: {:?}", name)),
+                };
+                let content = match &variant.value {
+                    VariantFormat::Unit => Value::Mapping(Mapping::new()),
+                    VariantFormat::NewType(format) => self.decode(format)?,
+                    VariantFormat::Struct(fields) => self.decode_struct(fields)?,
+                    format => return error(format!("Unsupported tagged variant: {:?}", format)),
+                };
+                let mut entries = Mapping::new();
+                entries.insert(tag_key(tag), name);
+                match content {
+                    Value::Mapping(fields) => entries.extend(fields),
+                    content => return unexpected("mapping", &content),
+                }
+                Ok(Value::Mapping(entries))
+            }
+        }
+    }
+}
+
+/// How messages are delimited on a byte stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Each message is prefixed with its length as a 4-byte little-endian integer.
+    U32Le,
+    /// Each message is prefixed with its length as a 4-byte big-endian integer.
+    U32Be,
+    /// Each message is prefixed with its length in ULEB128, as BCS sequence lengths.
+    Uleb128,
+}
+
+impl Framing {
+    /// Parse the header of the next frame at the beginning of `buffer`. Return the size of the
+    /// header and the length of the message, or `None` if more bytes are needed.
+    pub fn parse_header(self, buffer: &[u8]) -> Result<Option<(usize, usize)>> {
+        match self {
+            Framing::U32Le | Framing::U32Be => {
+                if buffer.len() < 4 {
+                    return Ok(None);
+                }
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(&buffer[..4]);
+                let len = match self {
+                    Framing::U32Le => u32::from_le_bytes(bytes),
+                    _ => u32::from_be_bytes(bytes),
+                };
+                Ok(Some((4, len as usize)))
+            }
+            Framing::Uleb128 => {
+                let mut decoder = Decoder {
+                    registry: &Registry::new(),
+                    encoding: Encoding::Bcs,
+                    input: buffer,
+                    depth: 0,
+                };
+                match decoder.read_u32_as_uleb128() {
+                    Ok(len) => Ok(Some((buffer.len() - decoder.input.len(), len as usize))),
+                    Err(_) if buffer.len() < 5 && buffer.iter().all(|b| b & 0x80 != 0) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Remove all the complete messages at the beginning of `buffer`.
+    pub fn split_messages(self, buffer: &mut Vec<u8>) -> Result<Vec<Vec<u8>>> {
+        let mut messages = Vec::new();
+        while let Some((header, len)) = self.parse_header(buffer)? {
+            if buffer.len() < header + len {
+                break;
+            }
+            messages.push(buffer[header..header + len].to_vec());
+            buffer.drain(..header + len);
+        }
+        Ok(messages)
+    }
+}
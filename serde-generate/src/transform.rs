@@ -0,0 +1,264 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_reflection::{
+    ContainerFormat, Error, Format, FormatHolder, Named, Registry, Result, VariantFormat, VisitFlow,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A rewrite of a registry, e.g. to adapt third-party formats to local conventions before
+/// code generation.
+pub trait RegistryTransform {
+    /// Apply the transformation to `registry`.
+    fn apply(&self, registry: Registry) -> Result<Registry>;
+}
+
+/// Apply the given transformations in order.
+pub fn apply_all(
+    registry: Registry,
+    transforms: &[Box<dyn RegistryTransform>],
+) -> Result<Registry> {
+    transforms
+        .iter()
+        .try_fold(registry, |registry, transform| transform.apply(registry))
+}
+
+/// Rename containers, as well as all references to them.
+#[derive(Debug, Clone, Default)]
+pub struct RenameContainers(pub BTreeMap<String, String>);
+
+impl RegistryTransform for RenameContainers {
+    fn apply(&self, registry: Registry) -> Result<Registry> {
+        for name in self.0.keys() {
+            if !registry.contains_key(name) {
+                return Err(Error::Custom(format!("Unknown container {}", name)));
+            }
+        }
+        rename(registry, |name| {
+            self.0
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| name.to_string())
+        })
+    }
+}
+
+/// Remove the namespaces of container names, e.g. `foo::Bar` or `foo.Bar` become `Bar`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripNamespaces;
+
+impl RegistryTransform for StripNamespaces {
+    fn apply(&self, registry: Registry) -> Result<Registry> {
+        rename(registry, |name| {
+            let name = name.rsplit("::").next().unwrap_or(name);
+            name.rsplit('.').next().unwrap_or(name).to_string()
+        })
+    }
+}
+
+/// Replace every occurrence of a format, e.g. `Bytes` by `[u8; 32]`.
+#[derive(Debug, Clone)]
+pub struct ReplaceFormat {
+    pub from: Format,
+    pub to: Format,
+}
+
+impl RegistryTransform for ReplaceFormat {
+    fn apply(&self, mut registry: Registry) -> Result<Registry> {
+        for format in registry.values_mut() {
+            format.visit_mut_with(&mut |format| {
+                if *format == self.from {
+                    *format = self.to.clone();
+                    return Ok(VisitFlow::SkipChildren);
+                }
+                Ok(VisitFlow::Continue)
+            })?;
+        }
+        Ok(registry)
+    }
+}
+
+/// Replace sequences of bytes `Seq(U8)`, e.g. legacy formats traced from `Vec<u8>`, by `Bytes`,
+/// so that generated code uses the native byte arrays of each language. Both formats have the
+/// same wire layout in BCS and Bincode.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeqU8AsBytes;
+
+impl RegistryTransform for SeqU8AsBytes {
+    fn apply(&self, registry: Registry) -> Result<Registry> {
+        ReplaceFormat {
+            from: Format::Seq(Box::new(Format::U8)),
+            to: Format::Bytes,
+        }
+        .apply(registry)
+    }
+}
+
+/// Replace references to newtype structs by the format that they wrap, then remove them.
+/// Since newtype structs are transparent in binary encodings, this does not change the wire
+/// layout. Recursive newtype structs are kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InlineNewTypes;
+
+impl RegistryTransform for InlineNewTypes {
+    fn apply(&self, registry: Registry) -> Result<Registry> {
+        let mut newtypes = registry
+            .iter()
+            .filter_map(|(name, format)| match format {
+                ContainerFormat::NewTypeStruct(format) => Some((name.clone(), *format.clone())),
+                _ => None,
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut recursive = BTreeSet::new();
+        for name in newtypes.keys() {
+            if reaches(&newtypes, name, name, &mut BTreeSet::new())? {
+                recursive.insert(name.clone());
+            }
+        }
+        newtypes.retain(|name, _| !recursive.contains(name));
+
+        let mut inlined = BTreeMap::new();
+        for name in newtypes.keys() {
+            inlined.insert(name.clone(), inline_newtype(&newtypes, name)?);
+        }
+        let mut registry = registry
+            .into_iter()
+            .filter(|(name, _)| !inlined.contains_key(name))
+            .collect::<Registry>();
+        for format in registry.values_mut() {
+            format.visit_mut_with(&mut |format| replace_newtype(&inlined, format))?;
+        }
+        Ok(registry)
+    }
+}
+
+/// Remove the fields marked as skipped by the tracer, i.e. `Format::Skipped`. Skipped fields
+/// are absent from the wire so code generators do not serialize them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveSkippedFields;
+
+impl RegistryTransform for RemoveSkippedFields {
+    fn apply(&self, mut registry: Registry) -> Result<Registry> {
+        let retain = |fields: &mut Vec<Named<Format>>| {
+            fields.retain(|field| !matches!(field.value, Format::Skipped(_)))
+        };
+        for format in registry.values_mut() {
+            match format {
+                ContainerFormat::Struct(fields) => retain(fields),
+                ContainerFormat::Enum(variants)
+                | ContainerFormat::InternallyTaggedEnum { variants, .. } => {
+                    for variant in variants.values_mut() {
+                        if let VariantFormat::Struct(fields) = &mut variant.value {
+                            retain(fields);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(registry)
+    }
+}
+
+/// Replace the custom formats with the given names, e.g. `Uuid` as traced with
+/// `TracerConfig::map_external_type`, by references to types of the same name. These types are
+/// meant to be provided by the target language, see `CodeGeneratorConfig::with_external_definitions`.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalTypes(pub BTreeSet<String>);
+
+impl RegistryTransform for ExternalTypes {
+    fn apply(&self, mut registry: Registry) -> Result<Registry> {
+        for format in registry.values_mut() {
+            format.visit_mut_with(&mut |format| {
+                if let Format::Custom { name, .. } = format {
+                    if self.0.contains(name) {
+                        *format = Format::TypeName(name.clone());
+                        return Ok(VisitFlow::SkipChildren);
+                    }
+                }
+                Ok(VisitFlow::Continue)
+            })?;
+        }
+        Ok(registry)
+    }
+}
+
+/// Whether the newtype struct `target` is referenced by the newtype struct `current`, either
+/// directly or through other newtype structs.
+fn reaches(
+    newtypes: &BTreeMap<String, Format>,
+    current: &str,
+    target: &str,
+    seen: &mut BTreeSet<String>,
+) -> Result<bool> {
+    let mut names = Vec::new();
+    newtypes[current].visit(&mut |format| {
+        if let Format::TypeName(name) = format {
+            names.push(name.clone());
+        }
+        Ok(())
+    })?;
+    for name in names {
+        if name == target {
+            return Ok(true);
+        }
+        if newtypes.contains_key(&name)
+            && seen.insert(name.clone())
+            && reaches(newtypes, &name, target, seen)?
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Compute the format of the (non-recursive) newtype struct `name` once all the newtype
+/// structs that it references are inlined.
+fn inline_newtype(newtypes: &BTreeMap<String, Format>, name: &str) -> Result<Format> {
+    let mut format = newtypes[name].clone();
+    format.visit_mut_with(&mut |format| {
+        if let Format::TypeName(inner) = format {
+            if newtypes.contains_key(inner) {
+                *format = inline_newtype(newtypes, inner)?;
+                return Ok(VisitFlow::SkipChildren);
+            }
+        }
+        Ok(VisitFlow::Continue)
+    })?;
+    Ok(format)
+}
+
+fn replace_newtype(inlined: &BTreeMap<String, Format>, format: &mut Format) -> Result<VisitFlow> {
+    if let Format::TypeName(name) = format {
+        if let Some(inner) = inlined.get(name) {
+            *format = inner.clone();
+            return Ok(VisitFlow::SkipChildren);
+        }
+    }
+    Ok(VisitFlow::Continue)
+}
+
+/// Rename all containers with `f` and update the references to them.
+fn rename<F>(registry: Registry, f: F) -> Result<Registry>
+where
+    F: Fn(&str) -> String,
+{
+    let mut result = Registry::new();
+    for (name, mut format) in registry {
+        format.visit_mut_with(&mut |format| {
+            if let Format::TypeName(name) = format {
+                *name = f(name);
+            }
+            Ok(VisitFlow::Continue)
+        })?;
+        let new_name = f(&name);
+        if result.contains_key(&new_name) {
+            return Err(Error::Custom(format!(
+                "Renaming container {} would override container {}",
+                name, new_name
+            )));
+        }
+        result.insert(new_name, format);
+    }
+    Ok(result)
+}
@@ -3,19 +3,19 @@
 
 use include_dir::include_dir as include_directory;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     io::{Result, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, Value, VariantFormat};
 
 use crate::{
-    common,
+    common::{self, AddressFormat, ConfigOption, VariantTag},
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig,
+    CodeGeneratorConfig, Encoding, JsonIntegerPolicy, VariantNaming,
 };
-use heck::CamelCase;
+use heck::{CamelCase, MixedCase, ShoutySnakeCase};
 
 /// Main configuration object for code-generation in TypeScript.
 pub struct CodeGenerator<'a> {
@@ -26,6 +26,88 @@ pub struct CodeGenerator<'a> {
     external_qualified_names: HashMap<String, String>,
     /// vector of namespaces to import
     namespaces_to_import: Vec<String>,
+    /// How generated modules import each other and the runtime.
+    module_system: ModuleSystem,
+    /// Whether to generate zod schemas parsing the JSON representation of each container.
+    zod: bool,
+    /// Whether to mark the places where the installer may split the module into parts.
+    part_boundaries: bool,
+}
+
+/// Module system targeted by the generated TypeScript code. This only affects the paths used
+/// in `import` declarations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModuleSystem {
+    /// Extension-less imports, as expected by `tsc` when emitting CommonJS modules.
+    #[default]
+    CommonJs,
+    /// Imports ending with `.js`, as required by native ES modules (Node.js, browsers, Bun).
+    EsModule,
+    /// Imports ending with `.ts`, as required by Deno.
+    Deno,
+}
+
+impl ModuleSystem {
+    fn import_suffix(self) -> &'static str {
+        match self {
+            ModuleSystem::CommonJs => "",
+            ModuleSystem::EsModule => ".js",
+            ModuleSystem::Deno => ".ts",
+        }
+    }
+
+    /// Path to import the `index` file of a directory.
+    fn quote_directory_import(self, path: &str) -> String {
+        match self {
+            ModuleSystem::CommonJs => path.to_string(),
+            _ => format!("{}/index{}", path, self.import_suffix()),
+        }
+    }
+
+    /// Path to import a sibling module given by a relative path without extension.
+    fn quote_module_import(self, path: &str) -> String {
+        format!("{}{}", path, self.import_suffix())
+    }
+
+    /// Re-emit a runtime source file, whose relative imports (and re-exports) are written
+    /// without extension, with the module paths expected by the module system. Only the
+    /// single-line `import` and `export` declarations at the start of a line are concerned,
+    /// e.g. `import { Serializer } from './serializer';`, while comments are left unchanged.
+    fn quote_runtime_source(self, content: &str) -> String {
+        let mut result = String::new();
+        for line in content.lines() {
+            match Self::split_relative_import(line) {
+                Some((declaration, path, rest)) => {
+                    result.push_str(declaration);
+                    result.push_str(&self.quote_module_import(path));
+                    result.push_str(rest);
+                }
+                None => result.push_str(line),
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Split a declaration such as `import { A } from './a';` into the text before the module
+    /// path, the relative module path, and the text after it.
+    fn split_relative_import(line: &str) -> Option<(&str, &str, &str)> {
+        if !line.starts_with("import ") && !line.starts_with("export ") {
+            return None;
+        }
+        let start = line.rfind(" from ")? + " from ".len();
+        let quote = line[start..]
+            .chars()
+            .next()
+            .filter(|c| *c == '\'' || *c == '"')?;
+        let path_start = start + 1;
+        let path_end = path_start + line[path_start..].find(quote)?;
+        let path = &line[path_start..path_end];
+        if !path.starts_with("./") && !path.starts_with("../") {
+            return None;
+        }
+        Some((&line[..path_start], path, &line[path_end..]))
+    }
 }
 
 /// Shared state for the code generation of a TypeScript source file.
@@ -34,14 +116,15 @@ struct TypeScriptEmitter<'a, T> {
     out: IndentedWriter<T>,
     /// Generator.
     generator: &'a CodeGenerator<'a>,
+    /// Containers mapped to the text format of network addresses, indexed by name.
+    addresses: BTreeMap<String, AddressFormat>,
+    /// Enums without variant data, represented by unions of string literals.
+    unit_enums: BTreeSet<String>,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a TypeScript code generator for the given config.
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("TypeScript does not support generating c-style enums");
-        }
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -59,55 +142,310 @@ impl<'a> CodeGenerator<'a> {
                 .keys()
                 .map(|k| k.to_string())
                 .collect::<Vec<_>>(),
+            module_system: ModuleSystem::default(),
+            zod: false,
+            part_boundaries: false,
         }
     }
 
+    /// Module system targeted by the generated code.
+    pub fn with_module_system(mut self, module_system: ModuleSystem) -> Self {
+        self.module_system = module_system;
+        self
+    }
+
+    /// Whether to generate a zod schema `FooSchema` for each container `Foo`. Schemas validate
+    /// the JSON representation of Serde and produce instances of the generated classes.
+    pub fn with_zod(mut self, zod: bool) -> Self {
+        self.zod = zod;
+        self
+    }
+
+    pub(crate) fn with_part_boundaries(mut self, part_boundaries: bool) -> Self {
+        self.part_boundaries = part_boundaries;
+        self
+    }
+
     /// Output class definitions for `registry` in a single source file.
-    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("TypeScript", registry)?;
+        common::check_options(
+            "TypeScript",
+            self.config,
+            &[
+                ConfigOption::JsonIntegersAsStrings,
+                ConfigOption::ImmutableCollections,
+                ConfigOption::RootFormats,
+                ConfigOption::TypeAliases,
+                ConfigOption::DefaultValues,
+                ConfigOption::Fixtures,
+            ],
+        )?;
         let mut emitter = TypeScriptEmitter {
             out: IndentedWriter::new(out, IndentConfig::Space(2)),
             generator: self,
+            addresses: common::address_formats(registry),
+            unit_enums: Self::unit_enums(self.config, registry),
         };
 
+        if self.config.nested_variant_classes {
+            Self::check_nested_variant_names(registry)?;
+        }
+
         emitter.output_preamble()?;
+        emitter.output_part_boundary()?;
 
-        for (name, format) in registry {
+        if self.config.type_aliases {
+            emitter.output_type_aliases(registry)?;
+        }
+
+        // Network addresses are handled by the runtime.
+        let containers = common::ordered_containers(self.config, registry)?
+            .into_iter()
+            .filter(|(name, _)| !emitter.addresses.contains_key(*name))
+            .collect::<Vec<_>>();
+        for (name, format) in &containers {
+            common::check_cancellation(self.config)?;
+            emitter.output_part_boundary()?;
             emitter.output_container(name, format)?;
         }
+        emitter.output_part_boundary()?;
+
+        if self.zod {
+            for (name, format) in &containers {
+                emitter.output_zod_schema(name, format)?;
+            }
+        }
 
         if self.config.serialization {
             emitter.output_helpers(registry)?;
+            emitter.output_transferable_codecs(registry)?;
+        }
+
+        if emitter.has_record_logs() {
+            for name in &self.config.record_log_types {
+                let fingerprint = common::schema_fingerprint(registry, name)?;
+                emitter.output_record_log(name, fingerprint)?;
+            }
+        }
+
+        if emitter.has_root_formats() {
+            for (name, format) in &self.config.root_formats {
+                emitter.output_root_format(name, format)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Output test fixtures, i.e. a class `FooFixtures` with a static method `sample()` for each
+    /// container `Foo`, as a separate module importing the generated module from `./index`.
+    /// Fixtures of external definitions are imported from the `fixtures` module of their
+    /// namespace.
+    pub fn output_fixtures(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        let registry = common::prepare_registry(self.config, registry)?;
+        let registry = &*registry;
+        common::check_formats("TypeScript", registry)?;
+        common::check_options(
+            "TypeScript",
+            self.config,
+            &[
+                ConfigOption::JsonIntegersAsStrings,
+                ConfigOption::ImmutableCollections,
+                ConfigOption::RootFormats,
+                ConfigOption::TypeAliases,
+                ConfigOption::DefaultValues,
+                ConfigOption::Fixtures,
+            ],
+        )?;
+        let mut emitter = TypeScriptEmitter {
+            out: IndentedWriter::new(out, IndentConfig::Space(2)),
+            generator: self,
+            addresses: common::address_formats(registry),
+            unit_enums: Self::unit_enums(self.config, registry),
+        };
+        let containers = common::ordered_containers(self.config, registry)?
+            .into_iter()
+            .filter(|(name, _)| !emitter.addresses.contains_key(*name))
+            .collect::<Vec<_>>();
+        emitter.output_fixtures_preamble(&containers)?;
+        for (name, format) in &containers {
+            emitter.output_fixture(name, format)?;
+        }
+        Ok(())
+    }
+
+    /// With c-style enums, the (non-empty) enums whose variants are all units. They are
+    /// represented by unions of string literals instead of classes.
+    fn unit_enums(config: &CodeGeneratorConfig, registry: &Registry) -> BTreeSet<String> {
+        if !config.c_style_enums {
+            return BTreeSet::new();
         }
+        registry
+            .iter()
+            .filter_map(|(name, format)| match format {
+                ContainerFormat::Enum(variants)
+                    if !variants.is_empty()
+                        && variants.values().all(|v| v.value == VariantFormat::Unit) =>
+                {
+                    Some(name.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
 
+    /// Nested variant classes are declared in a namespace merged with the class of the enum.
+    /// Make sure that they do not shadow the names used by the generated code.
+    fn check_nested_variant_names(registry: &Registry) -> crate::Result<()> {
+        let mut names = RUNTIME_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<BTreeSet<_>>();
+        names.extend(registry.keys().cloned());
+        names.extend(
+            common::custom_formats(registry)
+                .into_iter()
+                .map(|(name, _)| name),
+        );
+        for (name, format) in registry {
+            if let ContainerFormat::Enum(variants)
+            | ContainerFormat::InternallyTaggedEnum { variants, .. } = format
+            {
+                for variant in variants.values() {
+                    if names.contains(&variant.name) {
+                        return Err(crate::Error::Registry(serde_reflection::Error::Custom(
+                            format!(
+                                "Nested variant class {}.{} would shadow the definition {}",
+                                name, variant.name, variant.name
+                            ),
+                        )));
+                    }
+                }
+            }
+        }
         Ok(())
     }
 }
 
+/// Note on thread safety added to the documentation of generated classes.
+const THREAD_SAFETY_NOTE: &str =
+    "Thread safety: each call to a serialization or deserialization method uses its own
+serializer or deserializer, so that values may be decoded in interleaved async tasks or in
+workers.";
+
+/// Names defined outside of the generated module and referred to by generated classes.
+const RUNTIME_NAMES: &[&str] = &[
+    "Serializer",
+    "Deserializer",
+    "Helpers",
+    "Optional",
+    "Seq",
+    "Tuple",
+    "ListTuple",
+    "Map",
+    "Set",
+    "Array",
+    "Error",
+    "unit",
+    "bool",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "int128",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "uint128",
+    "float32",
+    "float64",
+    "char",
+    "str",
+    "bytes",
+];
+
 impl<'a, T> TypeScriptEmitter<'a, T>
 where
     T: Write,
 {
     fn output_preamble(&mut self) -> Result<()> {
+        let module_system = self.generator.module_system;
         writeln!(
             self.out,
             r#"
-import {{ Serializer }} from '../serde/serializer';
-import {{ Deserializer }} from '../serde/deserializer';
-import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64, int128, uint8, uint16, uint32, uint64, uint128, float32, float64, char, str, bytes}} from '../serde/types';
-"#
+import {{ Serializer }} from '../serde/serializer{0}';
+import {{ Deserializer }} from '../serde/deserializer{0}';
+import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64, int128, uint8, uint16, uint32, uint64, uint128, float32, float64, char, str, bytes}} from '../serde/types{0}';
+"#,
+            module_system.import_suffix(),
         )?;
+        if self.generator.config.serialization {
+            for encoding in &self.generator.config.encodings {
+                writeln!(
+                    self.out,
+                    r#"import {{ {1}Serializer }} from '../{0}/{0}Serializer{2}';
+import {{ {1}Deserializer }} from '../{0}/{0}Deserializer{2}';"#,
+                    encoding.name(),
+                    encoding.name().to_camel_case(),
+                    module_system.import_suffix(),
+                )?;
+            }
+        }
+        if !self.addresses.is_empty() {
+            writeln!(
+                self.out,
+                "import {{ serializeIpAddr, deserializeIpAddr, serializeSocketAddr, deserializeSocketAddr }} from '../serde/netAddress{}';",
+                module_system.import_suffix(),
+            )?;
+        }
+        if self.has_envelope_hooks() {
+            writeln!(
+                self.out,
+                "import {{ unwrapEnvelope, wrapEnvelope }} from '../serde/envelope{}';",
+                module_system.import_suffix(),
+            )?;
+        }
+        if self.has_checksums() {
+            writeln!(
+                self.out,
+                "import {{ appendChecksum, verifyChecksum }} from '../serde/checksum{}';",
+                module_system.import_suffix(),
+            )?;
+        }
+        if self.has_record_logs() {
+            writeln!(
+                self.out,
+                "import {{ RecordLogEncoding, RecordLogReader, RecordLogWriter }} from '../serde/recordLog{}';",
+                module_system.import_suffix(),
+            )?;
+        }
+        if self.generator.zod {
+            writeln!(self.out, "import {{ z }} from 'zod';\n")?;
+        }
         for namespace in self.generator.namespaces_to_import.iter() {
             writeln!(
                 self.out,
-                "import * as {} from '../{}';\n",
+                "import * as {} from '{}';\n",
                 namespace.to_camel_case(),
-                namespace
+                module_system.quote_directory_import(&format!("../{}", namespace)),
             )?;
         }
 
         Ok(())
     }
 
+    /// Mark a place where the installer may split the module into parts.
+    fn output_part_boundary(&mut self) -> Result<()> {
+        if self.generator.part_boundaries {
+            writeln!(self.out, "{}", common::PART_BOUNDARY)?;
+        }
+        Ok(())
+    }
+
     fn quote_qualified_name(&self, name: &str) -> String {
         self.generator
             .external_qualified_names
@@ -116,11 +454,39 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
             .unwrap_or_else(|| name.to_string())
     }
 
-    fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
-        let mut path = Vec::new();
-        path.push(name.to_string());
-        if let Some(doc) = self.generator.config.comments.get(&path) {
-            let text = textwrap::indent(doc, " * ").replace("\n\n", "\n *\n");
+    fn quote_variant_class(&self, base: &str, variant: &str) -> String {
+        if self.generator.config.nested_variant_classes {
+            return format!("{}.{}", base, variant);
+        }
+        let default = VariantNaming::Prefix("Variant".to_string());
+        self.generator
+            .config
+            .variant_class_name(base, variant, &default)
+    }
+
+    /// Output the documentation of the entity `path`. Comments are looked up by the name of the
+    /// entity only, whereas deprecations use the qualified name.
+    fn output_comment(&mut self, path: &[String]) -> std::io::Result<()> {
+        self.output_comment_with_note(path, None)
+    }
+
+    /// Same as `output_comment` followed by the paragraph `note` on thread safety, if requested.
+    fn output_comment_with_note(
+        &mut self,
+        path: &[String],
+        note: Option<&str>,
+    ) -> std::io::Result<()> {
+        let name = path[path.len() - 1..].to_vec();
+        let comment = self.generator.config.comment(&name);
+        let mut doc = self
+            .generator
+            .config
+            .with_deprecation_notice(comment, path, "@deprecated ");
+        if let Some(note) = note {
+            doc = self.generator.config.with_thread_safety_note(doc, note);
+        }
+        if let Some(doc) = doc {
+            let text = textwrap::indent(&doc, " * ").replace("\n\n", "\n *\n");
             writeln!(self.out, "/**\n{} */", text)?;
         }
         Ok(())
@@ -129,6 +495,7 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
     fn quote_type(&self, format: &Format) -> String {
         use Format::*;
         match format {
+            TypeName(x) if self.addresses.contains_key(x) => "string".into(),
             TypeName(x) => self.quote_qualified_name(x),
             Unit => "unit".into(),
             Bool => "bool".into(),
@@ -149,17 +516,62 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
             Bytes => "bytes".into(),
 
             Option(format) => format!("Optional<{}>", self.quote_type(format)),
+            Seq(format) if self.generator.config.immutable_collections => {
+                format!("ReadonlyArray<{}>", self.quote_type(format))
+            }
             Seq(format) => format!("Seq<{}>", self.quote_type(format)),
+            Set(format) if self.generator.config.immutable_collections => {
+                format!("ReadonlySet<{}>", self.quote_type(format))
+            }
+            Set(format) => format!("Set<{}>", self.quote_type(format)),
+            Map { key, value } if self.generator.config.immutable_collections => format!(
+                "ReadonlyMap<{},{}>",
+                self.quote_type(key),
+                self.quote_type(value)
+            ),
             Map { key, value } => {
                 format!("Map<{},{}>", self.quote_type(key), self.quote_type(value))
             }
             Tuple(formats) => format!("Tuple<[{}]>", self.quote_types(formats, ", ")),
+            TupleArray { content, size: _ } if self.generator.config.immutable_collections => {
+                format!("ReadonlyArray<Tuple<[{}]>>", self.quote_type(content))
+            }
             TupleArray {
                 content,
                 size: _size,
             } => format!("ListTuple<[{}]>", self.quote_type(content),),
-            Variable(_) => panic!("unexpected value"),
+            Custom { name, layout } => {
+                if let Some(time) = common::TimeFormat::of(format) {
+                    match time {
+                        common::TimeFormat::Duration => "number".into(),
+                        common::TimeFormat::SystemTime => "Date".into(),
+                    }
+                } else if self.generator.config.type_aliases {
+                    name.to_string()
+                } else {
+                    self.quote_type(layout)
+                }
+            }
+            Decimal { mantissa, .. } => self.quote_type(mantissa),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
+        }
+    }
+
+    fn output_type_aliases(&mut self, registry: &Registry) -> Result<()> {
+        for (name, layout) in common::custom_formats(registry) {
+            if common::TimeFormat::new(&name, &layout).is_some() {
+                continue;
+            }
+            writeln!(
+                self.out,
+                "export type {} = {};",
+                name,
+                self.quote_type(&layout)
+            )?;
         }
+        writeln!(self.out)
     }
 
     fn quote_types(&self, formats: &[Format], sep: &str) -> String {
@@ -170,148 +582,820 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
             .join(sep)
     }
 
-    fn output_helpers(&mut self, registry: &Registry) -> Result<()> {
-        let mut subtypes = BTreeMap::new();
-        for format in registry.values() {
-            format
-                .visit(&mut |f| {
-                    if Self::needs_helper(f) {
-                        subtypes.insert(common::mangle_type(f), f.clone());
-                    }
-                    Ok(())
-                })
-                .unwrap();
-        }
-
-        writeln!(self.out, "export class Helpers {{")?;
-        self.out.indent();
-        for (mangled_name, subtype) in &subtypes {
-            self.output_serialization_helper(mangled_name, subtype)?;
-            self.output_deserialization_helper(mangled_name, subtype)?;
+    /// Compare the integer `value` of the given format with `bound`, e.g. `value >= 1`.
+    fn quote_integer_comparison(
+        value: &str,
+        format: &Format,
+        operator: &str,
+        bound: i128,
+    ) -> String {
+        use Format::*;
+        let operator = if operator == "!=" { "!==" } else { operator };
+        match format {
+            I64 | I128 | U64 | U128 => {
+                format!("({} as bigint) {} BigInt(\"{}\")", value, operator, bound)
+            }
+            _ => format!("{} {} {}", value, operator, bound),
         }
-        self.out.unindent();
-        writeln!(self.out, "}}")?;
-        writeln!(self.out)
     }
 
-    fn needs_helper(format: &Format) -> bool {
+    /// Literal of the default value `value` of the given format, if it can be written in
+    /// TypeScript, e.g. `BigInt("10")` for a `u64`.
+    fn quote_default_value(&self, format: &Format, value: &Value) -> Option<String> {
         use Format::*;
-        matches!(
-            format,
-            Option(_) | Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. }
-        )
+        let literal = match (format, value) {
+            (Bool, Value::Bool(x)) => x.to_string(),
+            (I8, Value::I8(x)) => x.to_string(),
+            (I16, Value::I16(x)) => x.to_string(),
+            (I32, Value::I32(x)) => x.to_string(),
+            (I64, Value::I64(x)) => format!("BigInt(\"{}\")", x),
+            (I128, Value::I128(x)) => format!("BigInt(\"{}\")", x),
+            (U8, Value::U8(x)) => x.to_string(),
+            (U16, Value::U16(x)) => x.to_string(),
+            (U32, Value::U32(x)) => x.to_string(),
+            (U64, Value::U64(x)) => format!("BigInt(\"{}\")", x),
+            (U128, Value::U128(x)) => format!("BigInt(\"{}\")", x),
+            (F32, Value::F32(x)) if x.is_finite() => format!("{:?}", x),
+            (F64, Value::F64(x)) if x.is_finite() => format!("{:?}", x),
+            (Char, Value::Char(x)) => format!("{:?}", x.to_string()),
+            (Str, Value::Str(x)) | (Str, Value::Number(x)) => format!("{:?}", x),
+            (Bytes, Value::Bytes(x)) => format!(
+                "new Uint8Array([{}])",
+                x.iter()
+                    .map(|b| b.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            (Option(_), Value::Option(None)) => "null".to_string(),
+            (Option(format), Value::Option(Some(value))) => {
+                self.quote_default_value(format, value)?
+            }
+            (Seq(_), Value::Seq(x)) if x.is_empty() => "[]".to_string(),
+            (Set(_), Value::Seq(x)) if x.is_empty() => "new Set()".to_string(),
+            (Map { .. }, Value::Seq(x)) if x.is_empty() => "new Map()".to_string(),
+            (Custom { .. }, _) if common::TimeFormat::of(format).is_some() => return None,
+            (Custom { layout, .. }, value) => self.quote_default_value(layout, value)?,
+            (Decimal { mantissa, .. }, value) => self.quote_default_value(mantissa, value)?,
+            _ => return None,
+        };
+        Some(literal)
     }
 
-    fn quote_serialize_value(&self, value: &str, format: &Format, use_this: bool) -> String {
-        use Format::*;
-        let this_str = if use_this { "this." } else { "" };
+    /// Literal of the default value of the field `name` of the definition `path`, if it is
+    /// known and can be written in TypeScript.
+    fn quote_field_default(&self, path: &[String], field: &Named<Format>) -> Option<String> {
+        let mut field_path = path.to_vec();
+        field_path.push(field.name.clone());
+        let value = self
+            .generator
+            .config
+            .default_values
+            .get(&field_path)?
+            .as_ref()?;
+        self.quote_default_value(&field.value, value)
+    }
 
-        match format {
-            TypeName(_) => format!("{}{}.serialize(serializer);", this_str, value),
-            Unit => format!("serializer.serializeUnit({}{});", this_str, value),
-            Bool => format!("serializer.serializeBool({}{});", this_str, value),
-            I8 => format!("serializer.serializeI8({}{});", this_str, value),
-            I16 => format!("serializer.serializeI16({}{});", this_str, value),
-            I32 => format!("serializer.serializeI32({}{});", this_str, value),
-            I64 => format!("serializer.serializeI64({}{});", this_str, value),
-            I128 => format!("serializer.serializeI128({}{});", this_str, value),
-            U8 => format!("serializer.serializeU8({}{});", this_str, value),
-            U16 => format!("serializer.serializeU16({}{});", this_str, value),
-            U32 => format!("serializer.serializeU32({}{});", this_str, value),
-            U64 => format!("serializer.serializeU64({}{});", this_str, value),
-            U128 => format!("serializer.serializeU128({}{});", this_str, value),
-            F32 => format!("serializer.serializeF32({}{});", this_str, value),
-            F64 => format!("serializer.serializeF64({}{});", this_str, value),
-            Char => format!("serializer.serializeChar({}{});", this_str, value),
-            Str => format!("serializer.serializeStr({}{});", this_str, value),
-            Bytes => format!("serializer.serializeBytes({}{});", this_str, value),
-            _ => format!(
-                "Helpers.serialize{}({}{}, serializer);",
-                common::mangle_type(format).to_camel_case(),
-                this_str,
-                value
+    /// JSDoc tag marking the field `name` of the definition `path` as deprecated, if it is.
+    fn quote_field_deprecation(&self, path: &[String], field: &Named<Format>) -> String {
+        let mut field_path = path.to_vec();
+        field_path.push(field.name.clone());
+        match self.generator.config.deprecations.get(&field_path) {
+            Some(message) => format!("/** @deprecated {} */ ", message.trim()),
+            None => String::new(),
+        }
+    }
+
+    fn quote_zod_integer(&self, low: i128, high: u128) -> String {
+        let schema = format!("z.number().int().min({}).max({})", low, high);
+        match self.generator.config.json_integer_policy {
+            JsonIntegerPolicy::Unchecked => "z.number()".to_string(),
+            JsonIntegerPolicy::Strict => schema,
+            JsonIntegerPolicy::Lenient => format!(
+                "z.union([z.number(), z.string().regex(/^-?[0-9]+$/).transform(Number)]).pipe({})",
+                schema
             ),
         }
     }
 
-    fn quote_deserialize(&self, format: &Format) -> String {
+    fn quote_zod_big_integer(&self, low: i128, high: u128) -> String {
+        let schema = format!(
+            "z.coerce.bigint().min(BigInt(\"{}\")).max(BigInt(\"{}\"))",
+            low, high
+        );
+        if self.generator.config.json_integers_as_strings {
+            format!("z.string().regex(/^-?[0-9]+$/).pipe({})", schema)
+        } else {
+            schema
+        }
+    }
+
+    fn quote_zod_schema(&self, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(name) => format!(
-                "{}.deserialize(deserializer)",
-                self.quote_qualified_name(name)
+            // Network addresses use their text format in JSON.
+            TypeName(x) if self.addresses.contains_key(x) => "z.string()".into(),
+            TypeName(x) => format!("{}Schema", self.quote_qualified_name(x)),
+            Unit => "z.null()".into(),
+            Bool => "z.boolean()".into(),
+            I8 => self.quote_zod_integer(i8::MIN.into(), i8::MAX as u128),
+            I16 => self.quote_zod_integer(i16::MIN.into(), i16::MAX as u128),
+            I32 => self.quote_zod_integer(i32::MIN.into(), i32::MAX as u128),
+            I64 => self.quote_zod_big_integer(i64::MIN.into(), i64::MAX as u128),
+            I128 => self.quote_zod_big_integer(i128::MIN, i128::MAX as u128),
+            U8 => self.quote_zod_integer(0, u8::MAX.into()),
+            U16 => self.quote_zod_integer(0, u16::MAX.into()),
+            U32 => self.quote_zod_integer(0, u32::MAX.into()),
+            U64 => self.quote_zod_big_integer(0, u64::MAX.into()),
+            U128 => self.quote_zod_big_integer(0, u128::MAX),
+            F32 | F64 => "z.number()".into(),
+            Char => "z.string().refine((s) => Array.from(s).length === 1, \"Expected a single character\")".into(),
+            Str => "z.string()".into(),
+            Bytes => format!(
+                "z.array({}).transform((v) => new Uint8Array(v))",
+                self.quote_zod_integer(0, u8::MAX.into())
             ),
-            Unit => "deserializer.deserializeUnit()".to_string(),
-            Bool => "deserializer.deserializeBool()".to_string(),
-            I8 => "deserializer.deserializeI8()".to_string(),
-            I16 => "deserializer.deserializeI16()".to_string(),
-            I32 => "deserializer.deserializeI32()".to_string(),
-            I64 => "deserializer.deserializeI64()".to_string(),
-            I128 => "deserializer.deserializeI128()".to_string(),
-            U8 => "deserializer.deserializeU8()".to_string(),
-            U16 => "deserializer.deserializeU16()".to_string(),
-            U32 => "deserializer.deserializeU32()".to_string(),
-            U64 => "deserializer.deserializeU64()".to_string(),
-            U128 => "deserializer.deserializeU128()".to_string(),
-            F32 => "deserializer.deserializeF32()".to_string(),
-            F64 => "deserializer.deserializeF64()".to_string(),
-            Char => "deserializer.deserializeChar()".to_string(),
-            Str => "deserializer.deserializeStr()".to_string(),
-            Bytes => "deserializer.deserializeBytes()".to_string(),
-            _ => format!(
-                "Helpers.deserialize{}(deserializer)",
-                common::mangle_type(format).to_camel_case(),
+            Option(format) => format!("{}.nullable()", self.quote_zod_schema(format)),
+            Seq(format) => format!("z.array({})", self.quote_zod_schema(format)),
+            Set(format) => format!(
+                "z.array({}).transform((v) => new Set(v))",
+                self.quote_zod_schema(format)
             ),
+            Map { key, value } => format!(
+                "z.record(z.string(), {}).transform((v) => new Map(Object.entries(v).map(([k, x]): [{}, {}] => [{}, x])))",
+                self.quote_zod_schema(value),
+                self.quote_type(key),
+                self.quote_type(value),
+                self.quote_zod_key(key)
+            ),
+            Tuple(formats) => format!("z.tuple([{}])", self.quote_zod_schemas(formats)),
+            TupleArray { content, size } => format!(
+                "z.array({}).length({}).transform((v) => v.map((x): [{}] => [x]))",
+                self.quote_zod_schema(content),
+                size,
+                self.quote_type(content)
+            ),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => "z.object({ secs: z.number(), nanos: z.number() }).transform((v) => v.secs * 1000 + v.nanos / 1000000)".to_string(),
+                Some(common::TimeFormat::SystemTime) => "z.object({ secs_since_epoch: z.number(), nanos_since_epoch: z.number() }).transform((v) => new Date(v.secs_since_epoch * 1000 + v.nanos_since_epoch / 1000000))".to_string(),
+                None => match common::nonzero_integer(format) {
+                    Some(integer) => format!(
+                        "{}.refine((v) => {}, \"Expected a nonzero integer\")",
+                        self.quote_zod_schema(integer),
+                        Self::quote_integer_comparison("v", integer, "!=", 0)
+                    ),
+                    None => self.quote_zod_schema(layout),
+                },
+            },
+            Decimal { mantissa, .. } => self.quote_zod_schema(mantissa),
+            Variable(_) | Skipped(_) => unreachable!("formats are checked by common::check_formats"),
         }
     }
 
-    fn output_serialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
-        use Format::*;
+    fn quote_zod_schemas(&self, formats: &[Format]) -> String {
+        formats
+            .iter()
+            .map(|f| self.quote_zod_schema(f))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 
-        write!(
-            self.out,
-            "static serialize{}(value: {}, serializer: Serializer): void {{",
-            name.to_camel_case(),
-            self.quote_type(format0)
-        )?;
-        self.out.indent();
-        match format0 {
-            Option(format) => {
-                write!(
-                    self.out,
-                    r#"
-if (value) {{
-    serializer.serializeOptionTag(true);
-    {}
-}} else {{
-    serializer.serializeOptionTag(false);
-}}
-"#,
-                    self.quote_serialize_value("value", format, false)
-                )?;
+    /// Parse the key `k` of a JSON object. Serde writes non-string keys as strings.
+    fn quote_zod_key(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            Str => "k".into(),
+            TypeName(x) if self.addresses.contains_key(x) => "k".into(),
+            TypeName(x) if self.unit_enums.contains(x) => {
+                format!("{}.parse(k)", self.quote_zod_schema(format))
+            }
+            Custom { layout, .. } => self.quote_zod_key(layout),
+            Decimal { mantissa, .. } => self.quote_zod_key(mantissa),
+            Char | I64 | I128 | U64 | U128 => format!("{}.parse(k)", self.quote_zod_schema(format)),
+            I8 | I16 | I32 | U8 | U16 | U32 | F32 | F64 => {
+                format!("{}.parse(Number(k))", self.quote_zod_schema(format))
             }
+            _ => format!("{}.parse(JSON.parse(k))", self.quote_zod_schema(format)),
+        }
+    }
 
-            Seq(format) => {
-                write!(
-                    self.out,
-                    r#"
-serializer.serializeLen(value.length);
-value.forEach((item: {}) => {{
-    {}
-}});
-"#,
-                    self.quote_type(format),
-                    self.quote_serialize_value("item", format, false)
-                )?;
+    /// Schemas of the entries `name: schema` of a JSON object holding the fields of the
+    /// definition `path`, including the bounds of integer fields.
+    fn quote_zod_field_schemas(&self, path: &[String], fields: &[Named<Format>]) -> Vec<String> {
+        let mut bounds = BTreeMap::new();
+        for (field, format, constraint) in
+            common::integer_constraints(self.generator.config, path, fields)
+        {
+            // Nonzero integers are checked by the schemas of their formats.
+            let constraint = common::IntegerConstraint {
+                nonzero: false,
+                ..constraint
+            };
+            if constraint != common::IntegerConstraint::default() {
+                bounds.insert(field.name.as_str(), (format, constraint));
             }
+        }
+        fields
+            .iter()
+            .map(|f| {
+                let mut schema = self.quote_zod_schema(&f.value);
+                if let Some((format, constraint)) = bounds.get(f.name.as_str()) {
+                    let condition = constraint
+                        .comparisons()
+                        .iter()
+                        .map(|(operator, bound)| {
+                            Self::quote_integer_comparison("v", format, operator, *bound)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" && ");
+                    schema = format!(
+                        "{}.refine((v) => {}, \"Expected an integer {}\")",
+                        schema,
+                        condition,
+                        constraint.description()
+                    );
+                }
+                // Fields with a known default value are filled in by the constructor.
+                if self.quote_field_default(path, f).is_some() {
+                    schema = format!("{}.optional()", schema);
+                }
+                format!("{}: {}", f.name, schema)
+            })
+            .collect()
+    }
 
-            Map { key, value } => {
-                write!(
-                    self.out,
-                    r#"
-serializer.serializeLen(value.size);
-const offsets: number[] = [];
-for (const [k, v] of value.entries()) {{
+    /// Schema of the values `v` of a JSON object holding the fields of the definition `path`,
+    /// followed by the arguments of the constructor.
+    fn quote_zod_fields(
+        &self,
+        value: &str,
+        path: &[String],
+        fields: &[Named<Format>],
+    ) -> (String, String) {
+        let schema = if fields.is_empty() {
+            "z.object({})".to_string()
+        } else {
+            format!(
+                "z.object({{ {} }})",
+                self.quote_zod_field_schemas(path, fields).join(", ")
+            )
+        };
+        let arguments = fields
+            .iter()
+            .map(|f| format!("{}.{}", value, f.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        (schema, arguments)
+    }
+
+    fn quote_zod_tuple(value: &str, formats: &[Format]) -> String {
+        (0..formats.len())
+            .map(|i| format!("{}[{}]", value, i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn quote_zod_variant(&self, base: &str, variant: &Named<VariantFormat>) -> String {
+        use VariantFormat::*;
+        let class = self.quote_variant_class(base, &variant.name);
+        let value = format!("v.{}", variant.name);
+        let (schema, arguments) = match &variant.value {
+            // Unit variants are written as strings.
+            Unit => {
+                return format!(
+                    "z.literal({:?}).transform(() => new {}())",
+                    variant.name, class
+                )
+            }
+            NewType(format) => (self.quote_zod_schema(format), value),
+            Tuple(formats) => (
+                format!("z.tuple([{}])", self.quote_zod_schemas(formats)),
+                Self::quote_zod_tuple(&value, formats),
+            ),
+            Struct(fields) => {
+                let path = [
+                    self.generator.config.module_name.clone(),
+                    base.to_string(),
+                    variant.name.clone(),
+                ];
+                self.quote_zod_fields(&value, &path, fields)
+            }
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
+        };
+        format!(
+            "z.object({{ {}: {} }}).strict().transform((v) => new {}({}))",
+            variant.name, schema, class, arguments
+        )
+    }
+
+    /// Schema of a variant of an internally tagged enum, i.e. an object holding the name of the
+    /// variant under `tag` together with the fields of the variant.
+    fn quote_zod_tagged_variant(
+        &self,
+        base: &str,
+        tag: &str,
+        variant: &Named<VariantFormat>,
+    ) -> crate::Result<String> {
+        use VariantFormat::*;
+        let class = self.quote_variant_class(base, &variant.name);
+        let tag_schema = format!("{:?}: z.literal({:?})", tag, variant.name);
+        let schema = match &variant.value {
+            Unit => format!(
+                "z.object({{ {} }}).transform(() => new {}())",
+                tag_schema, class
+            ),
+            // The fields of the inner struct are next to the tag.
+            NewType(format) => format!(
+                "z.object({{ {} }}).passthrough().transform((v) => new {}({}.parse(v)))",
+                tag_schema,
+                class,
+                self.quote_zod_schema(format)
+            ),
+            Struct(fields) => {
+                let path = [
+                    self.generator.config.module_name.clone(),
+                    base.to_string(),
+                    variant.name.clone(),
+                ];
+                let (_, arguments) = self.quote_zod_fields("v", &path, fields);
+                format!(
+                    "z.object({{ {} }}).transform((v) => new {}({}))",
+                    std::iter::once(tag_schema)
+                        .chain(self.quote_zod_field_schemas(&path, fields))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    class,
+                    arguments
+                )
+            }
+            Tuple(_) | Variable(_) => {
+                return Err(crate::Error::UnsupportedFormat {
+                    language: "TypeScript",
+                    container: base.to_string(),
+                    field: Some(variant.name.clone()),
+                    format: format!("{:?}", variant.value),
+                })
+            }
+        };
+        Ok(schema)
+    }
+
+    fn output_zod_schema(&mut self, name: &str, format: &ContainerFormat) -> crate::Result<()> {
+        use ContainerFormat::*;
+        let schema = match format {
+            UnitStruct => format!("z.null().transform(() => new {}())", name),
+            NewTypeStruct(format) => format!(
+                "{}.transform((v) => new {}(v))",
+                self.quote_zod_schema(format),
+                name
+            ),
+            TupleStruct(formats) => format!(
+                "z.tuple([{}]).transform((v) => new {}({}))",
+                self.quote_zod_schemas(formats),
+                name,
+                Self::quote_zod_tuple("v", formats)
+            ),
+            Struct(fields) => {
+                let path = [self.generator.config.module_name.clone(), name.to_string()];
+                let (schema, arguments) = self.quote_zod_fields("v", &path, fields);
+                format!("{}.transform((v) => new {}({}))", schema, name, arguments)
+            }
+            Enum(variants) if self.unit_enums.contains(name) => format!(
+                "z.enum([{}])",
+                variants
+                    .values()
+                    .map(|v| format!("{:?}", v.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Enum(variants) => {
+                let schemas: Vec<_> = variants
+                    .values()
+                    .map(|variant| self.quote_zod_variant(name, variant))
+                    .collect();
+                match schemas.len() {
+                    0 => "z.never()".to_string(),
+                    1 => schemas[0].clone(),
+                    _ => format!("z.union([\n  {},\n])", schemas.join(",\n  ")),
+                }
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                let schemas = variants
+                    .values()
+                    .map(|variant| self.quote_zod_tagged_variant(name, tag, variant))
+                    .collect::<crate::Result<Vec<_>>>()?;
+                match schemas.len() {
+                    0 => "z.never()".to_string(),
+                    1 => schemas[0].clone(),
+                    _ => format!("z.union([\n  {},\n])", schemas.join(",\n  ")),
+                }
+            }
+        };
+        // Schemas may refer to each other, hence the use of `z.lazy`.
+        writeln!(
+            self.out,
+            "export const {0}Schema: z.ZodType<{0}, z.ZodTypeDef, unknown> = z.lazy(() =>\n  {1}\n);\n",
+            name,
+            schema.replace('\n', "\n  ")
+        )?;
+        Ok(())
+    }
+
+    fn output_helpers(&mut self, registry: &Registry) -> crate::Result<()> {
+        let config = self.generator.config;
+        let subtypes = common::helper_formats(config, registry, true, |format| {
+            Self::needs_helper(format) && !common::inlines_helper(config, format)
+        })?;
+
+        writeln!(self.out, "export class Helpers {{")?;
+        self.out.indent();
+        for (mangled_name, subtype) in &subtypes {
+            self.output_serialization_helper(mangled_name, subtype)?;
+            self.output_deserialization_helper(mangled_name, subtype)?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+
+    /// Output functions taking and returning `ArrayBuffer`s (which can be transferred to and
+    /// from Web Workers without copies) together with a table of decoders indexed by type name.
+    fn output_transferable_codecs(&mut self, registry: &Registry) -> Result<()> {
+        for encoding in &self.generator.config.encodings {
+            let encoding_name = encoding.name().to_camel_case();
+            for name in registry.keys() {
+                if self.addresses.contains_key(name) {
+                    continue;
+                }
+                writeln!(
+                    self.out,
+                    r#"export function {0}{1}Decode(buffer: ArrayBuffer): {2} {{
+  const input = {3};
+  const deserializer = new {1}Deserializer(input);
+  const value = {2}.deserialize(deserializer);
+  if (deserializer.getBufferOffset() < input.byteLength) {{
+    throw new Error("Some input bytes were not read");
+  }}
+  return value;
+}}
+
+export function {0}{1}Encode(value: {2}): ArrayBuffer {{
+  const serializer = new {1}Serializer();
+  {5}
+  const bytes = {4};
+  return bytes.buffer.slice(bytes.byteOffset, bytes.byteOffset + bytes.byteLength);
+}}
+"#,
+                    name.to_mixed_case(),
+                    encoding_name,
+                    name,
+                    self.quote_unwrap_envelope("new Uint8Array(buffer)"),
+                    self.quote_wrap_envelope("serializer.getBytes()"),
+                    self.quote_serialize_value("value", &Format::TypeName(name.clone()), false),
+                )?;
+                if self.generator.config.checksums {
+                    writeln!(
+                        self.out,
+                        r#"export function {0}{1}DecodeChecked(buffer: ArrayBuffer): {2} {{
+  return {0}{1}Decode(verifyChecksum(new Uint8Array(buffer)).slice().buffer);
+}}
+
+export function {0}{1}EncodeChecked(value: {2}): ArrayBuffer {{
+  return appendChecksum(new Uint8Array({0}{1}Encode(value))).buffer;
+}}
+"#,
+                        name.to_mixed_case(),
+                        encoding_name,
+                        name,
+                    )?;
+                }
+            }
+            writeln!(
+                self.out,
+                "export const {}Decoders: {{ [typeName: string]: (buffer: ArrayBuffer) => unknown }} = {{",
+                encoding_name
+            )?;
+            self.out.indent();
+            for name in registry.keys() {
+                writeln!(
+                    self.out,
+                    "{}: {}{}Decode,",
+                    name,
+                    name.to_mixed_case(),
+                    encoding_name
+                )?;
+            }
+            self.out.unindent();
+            writeln!(self.out, "}};\n")?;
+        }
+        Ok(())
+    }
+
+    fn has_record_logs(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization && !config.encodings.is_empty() && !config.record_log_types.is_empty()
+    }
+
+    fn output_record_log(&mut self, name: &str, fingerprint: u64) -> Result<()> {
+        let encoding_name = self
+            .generator
+            .config
+            .single_encoding()
+            .name()
+            .to_camel_case();
+        writeln!(
+            self.out,
+            r#"export const {1}_SCHEMA_FINGERPRINT = BigInt("{2:#018x}");
+
+export function {3}LogWriter(): RecordLogWriter<{0}> {{
+  return new RecordLogWriter<{0}>({1}_SCHEMA_FINGERPRINT, RecordLogEncoding.{4}, (value) => {{
+    const serializer = new {4}Serializer();
+    {7}
+    return {5};
+  }});
+}}
+
+export function {3}LogReader(bytes: Uint8Array): RecordLogReader<{0}> {{
+  return new RecordLogReader<{0}>(bytes, {1}_SCHEMA_FINGERPRINT, RecordLogEncoding.{4}, (record) => {{
+    const input = {6};
+    const deserializer = new {4}Deserializer(input);
+    const value = {0}.deserialize(deserializer);
+    if (deserializer.getBufferOffset() < input.length) {{
+      throw new Error("Some input bytes were not read");
+    }}
+    return value;
+  }});
+}}
+"#,
+            name,
+            name.to_shouty_snake_case(),
+            fingerprint,
+            name.to_mixed_case(),
+            encoding_name,
+            self.quote_wrap_envelope("serializer.getBytes()"),
+            self.quote_unwrap_envelope("record"),
+            self.quote_serialize_value("value", &Format::TypeName(name.to_string()), false),
+        )
+    }
+
+    fn has_root_formats(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization && !config.encodings.is_empty() && !config.root_formats.is_empty()
+    }
+
+    fn output_root_format(&mut self, name: &str, format: &Format) -> Result<()> {
+        for encoding in &self.generator.config.encodings {
+            writeln!(
+                self.out,
+                r#"export function {0}{1}Decode(buffer: ArrayBuffer): {2} {{
+  const input = {3};
+  const deserializer = new {1}Deserializer(input);
+  const value = {4};
+  if (deserializer.getBufferOffset() < input.byteLength) {{
+    throw new Error("Some input bytes were not read");
+  }}
+  return value;
+}}
+
+export function {0}{1}Encode(value: {2}): ArrayBuffer {{
+  const serializer = new {1}Serializer();
+  {5}
+  const bytes = {6};
+  return bytes.buffer.slice(bytes.byteOffset, bytes.byteOffset + bytes.byteLength);
+}}
+"#,
+                name.to_mixed_case(),
+                encoding.name().to_camel_case(),
+                self.quote_type(format),
+                self.quote_unwrap_envelope("new Uint8Array(buffer)"),
+                self.quote_deserialize(format),
+                self.quote_serialize_value("value", format, false),
+                self.quote_wrap_envelope("serializer.getBytes()"),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn has_checksums(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization && !config.encodings.is_empty() && config.checksums
+    }
+
+    fn has_envelope_hooks(&self) -> bool {
+        let config = &self.generator.config;
+        config.serialization && !config.encodings.is_empty() && config.envelope_hooks
+    }
+
+    fn quote_wrap_envelope(&self, bytes: &str) -> String {
+        if self.generator.config.envelope_hooks {
+            format!("wrapEnvelope({})", bytes)
+        } else {
+            bytes.to_string()
+        }
+    }
+
+    fn quote_unwrap_envelope(&self, bytes: &str) -> String {
+        if self.generator.config.envelope_hooks {
+            format!("unwrapEnvelope({})", bytes)
+        } else {
+            bytes.to_string()
+        }
+    }
+
+    fn needs_helper(format: &Format) -> bool {
+        use Format::*;
+        matches!(
+            format,
+            Option(_) | Seq(_) | Set(_) | Map { .. } | Tuple(_) | TupleArray { .. }
+        )
+    }
+
+    fn quote_serialize_value(&self, value: &str, format0: &Format, use_this: bool) -> String {
+        use Format::*;
+        let this_str = if use_this { "this." } else { "" };
+
+        match format0 {
+            TypeName(name) => match self.addresses.get(name) {
+                Some(address) => format!(
+                    "serialize{}({}{}, serializer);",
+                    address.helper_suffix().to_camel_case(),
+                    this_str,
+                    value
+                ),
+                None if self.unit_enums.contains(name) => format!(
+                    "{}.serialize({}{}, serializer);",
+                    self.quote_qualified_name(name),
+                    this_str,
+                    value
+                ),
+                None => format!("{}{}.serialize(serializer);", this_str, value),
+            },
+            Unit => format!("serializer.serializeUnit({}{});", this_str, value),
+            Bool => format!("serializer.serializeBool({}{});", this_str, value),
+            I8 => format!("serializer.serializeI8({}{});", this_str, value),
+            I16 => format!("serializer.serializeI16({}{});", this_str, value),
+            I32 => format!("serializer.serializeI32({}{});", this_str, value),
+            I64 => format!("serializer.serializeI64({}{});", this_str, value),
+            I128 => format!("serializer.serializeI128({}{});", this_str, value),
+            U8 => format!("serializer.serializeU8({}{});", this_str, value),
+            U16 => format!("serializer.serializeU16({}{});", this_str, value),
+            U32 => format!("serializer.serializeU32({}{});", this_str, value),
+            U64 => format!("serializer.serializeU64({}{});", this_str, value),
+            U128 => format!("serializer.serializeU128({}{});", this_str, value),
+            F32 => format!("serializer.serializeF32({}{});", this_str, value),
+            F64 => format!("serializer.serializeF64({}{});", this_str, value),
+            Char => format!("serializer.serializeChar({}{});", this_str, value),
+            Str => format!("serializer.serializeStr({}{});", this_str, value),
+            Bytes => format!("serializer.serializeBytes({}{});", this_str, value),
+            Custom { layout, .. } => match common::TimeFormat::of(format0) {
+                Some(common::TimeFormat::Duration) => format!(
+                    "serializer.serializeU64(BigInt(Math.floor({0}{1} / 1000))); serializer.serializeU32(Math.floor(({0}{1} % 1000) * 1000000));",
+                    this_str, value
+                ),
+                Some(common::TimeFormat::SystemTime) => format!(
+                    "serializer.serializeU64(BigInt(Math.floor({0}{1}.getTime() / 1000))); serializer.serializeU32(({0}{1}.getTime() % 1000) * 1000000);",
+                    this_str, value
+                ),
+                None => self.quote_serialize_value(value, layout, use_this),
+            },
+            Decimal { mantissa, .. } => self.quote_serialize_value(value, mantissa, use_this),
+            Option(format) if common::inlines_helper(self.generator.config, format0) => format!(
+                "if ({0}{1}) {{ serializer.serializeOptionTag(true); {2} }} else {{ serializer.serializeOptionTag(false); }}",
+                this_str,
+                value,
+                self.quote_serialize_value(&format!("{}{}", this_str, value), format, false)
+            ),
+            Seq(format) if common::inlines_helper(self.generator.config, format0) => format!(
+                "serializer.serializeLen({0}{1}.length); {0}{1}.forEach((item) => {{ {2} }});",
+                this_str,
+                value,
+                self.quote_serialize_value("item", format, false)
+            ),
+            _ => format!(
+                "Helpers.serialize{}({}{}, serializer);",
+                common::helper_name(self.generator.config, format0).to_camel_case(),
+                this_str,
+                value
+            ),
+        }
+    }
+
+    fn quote_deserialize(&self, format0: &Format) -> String {
+        use Format::*;
+        match format0 {
+            TypeName(name) => match self.addresses.get(name) {
+                Some(address) => format!(
+                    "deserialize{}(deserializer)",
+                    address.helper_suffix().to_camel_case()
+                ),
+                None => format!(
+                    "{}.deserialize(deserializer)",
+                    self.quote_qualified_name(name)
+                ),
+            },
+            Unit => "deserializer.deserializeUnit()".to_string(),
+            Bool => "deserializer.deserializeBool()".to_string(),
+            I8 => "deserializer.deserializeI8()".to_string(),
+            I16 => "deserializer.deserializeI16()".to_string(),
+            I32 => "deserializer.deserializeI32()".to_string(),
+            I64 => "deserializer.deserializeI64()".to_string(),
+            I128 => "deserializer.deserializeI128()".to_string(),
+            U8 => "deserializer.deserializeU8()".to_string(),
+            U16 => "deserializer.deserializeU16()".to_string(),
+            U32 => "deserializer.deserializeU32()".to_string(),
+            U64 => "deserializer.deserializeU64()".to_string(),
+            U128 => "deserializer.deserializeU128()".to_string(),
+            F32 => "deserializer.deserializeF32()".to_string(),
+            F64 => "deserializer.deserializeF64()".to_string(),
+            Char => "deserializer.deserializeChar()".to_string(),
+            Str => "deserializer.deserializeStr()".to_string(),
+            Bytes => "deserializer.deserializeBytes()".to_string(),
+            Custom { layout, .. } => match common::TimeFormat::of(format0) {
+                Some(common::TimeFormat::Duration) => "(Number(deserializer.deserializeU64()) * 1000 + deserializer.deserializeU32() / 1000000)".to_string(),
+                Some(common::TimeFormat::SystemTime) => "new Date(Number(deserializer.deserializeU64()) * 1000 + deserializer.deserializeU32() / 1000000)".to_string(),
+                None => self.quote_deserialize(layout),
+            },
+            Decimal { mantissa, .. } => self.quote_deserialize(mantissa),
+            Option(format) if common::inlines_helper(self.generator.config, format0) => format!(
+                "(deserializer.deserializeOptionTag() ? {} : null)",
+                self.quote_deserialize(format)
+            ),
+            Seq(format) if common::inlines_helper(self.generator.config, format0) => {
+                format!(
+                    "Array.from({{ length: deserializer.deserializeLen() }}, () => {})",
+                    self.quote_deserialize(format)
+                )
+            }
+            _ => format!(
+                "Helpers.deserialize{}(deserializer)",
+                common::helper_name(self.generator.config, format0).to_camel_case(),
+            ),
+        }
+    }
+
+    fn output_serialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+
+        write!(
+            self.out,
+            "static serialize{}(value: {}, serializer: Serializer): void {{",
+            name.to_camel_case(),
+            self.quote_type(format0)
+        )?;
+        self.out.indent();
+        match format0 {
+            Option(format) => {
+                write!(
+                    self.out,
+                    r#"
+if (value) {{
+    serializer.serializeOptionTag(true);
+    {}
+}} else {{
+    serializer.serializeOptionTag(false);
+}}
+"#,
+                    self.quote_serialize_value("value", format, false)
+                )?;
+            }
+
+            Seq(format) => {
+                write!(
+                    self.out,
+                    r#"
+serializer.serializeLen(value.length);
+value.forEach((item: {}) => {{
+    {}
+}});
+"#,
+                    self.quote_type(format),
+                    self.quote_serialize_value("item", format, false)
+                )?;
+            }
+
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+serializer.serializeLen(value.size);
+const offsets: number[] = [];
+for (const item of value) {{
+  offsets.push(serializer.getBufferOffset());
+  {}
+}}
+serializer.sortMapEntries(offsets);
+"#,
+                    self.quote_serialize_value("item", format, false)
+                )?;
+            }
+
+            Map { key, value } => {
+                write!(
+                    self.out,
+                    r#"
+serializer.serializeLen(value.size);
+const offsets: number[] = [];
+for (const [k, v] of value.entries()) {{
   offsets.push(serializer.getBufferOffset());
   {}
   {}
@@ -387,17 +1471,48 @@ if (!tag) {{
                     self.out,
                     r#"
 const length = deserializer.deserializeLen();
-const list: {} = [];
+const list: Seq<{}> = [];
 for (let i = 0; i < length; i++) {{
     list.push({});
 }}
 return list;
 "#,
-                    self.quote_type(format0),
+                    self.quote_type(format),
                     self.quote_deserialize(format)
                 )?;
             }
 
+            Set(format) => {
+                write!(
+                    self.out,
+                    r#"
+const length = deserializer.deserializeLen();
+const obj = new Set<{0}>();
+let previousItemStart = 0;
+let previousItemEnd = 0;
+for (let i = 0; i < length; i++) {{
+    const itemStart = deserializer.getBufferOffset();
+    const item = {1};
+    const itemEnd = deserializer.getBufferOffset();
+    if (i > 0) {{
+        deserializer.checkThatKeySlicesAreIncreasing(
+            [previousItemStart, previousItemEnd],
+            [itemStart, itemEnd]);
+    }}
+    previousItemStart = itemStart;
+    previousItemEnd = itemEnd;
+    obj.add(item);
+}}
+if (obj.size !== length) {{
+    throw new Error("Duplicate elements in set");
+}}
+return obj;
+"#,
+                    self.quote_type(format),
+                    self.quote_deserialize(format),
+                )?;
+            }
+
             Map { key, value } => {
                 write!(
                     self.out,
@@ -448,13 +1563,13 @@ return [{}
                 write!(
                     self.out,
                     r#"
-const list: {} = [];
+const list: ListTuple<[{}]> = [];
 for (let i = 0; i < {}; i++) {{
     list.push([{}]);
 }}
 return list;
 "#,
-                    self.quote_type(format0),
+                    self.quote_type(content),
                     size,
                     self.quote_deserialize(content)
                 )?;
@@ -469,38 +1584,44 @@ return list;
     fn output_variant(
         &mut self,
         base: &str,
-        index: u32,
+        tag: VariantTag,
         name: &str,
         variant: &VariantFormat,
     ) -> Result<()> {
+        let fields = Self::variant_fields(variant);
+        self.output_struct_or_variant_container(Some(base), Some(tag), name, &fields)
+    }
+
+    /// Fields of the class of a variant.
+    fn variant_fields(variant: &VariantFormat) -> Vec<Named<Format>> {
         use VariantFormat::*;
-        let fields = match variant {
+        match variant {
             Unit => Vec::new(),
-            NewType(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            Tuple(formats) => formats
-                .iter()
-                .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
-                .collect(),
+            NewType(format) => vec![Named::new("value", format.as_ref().clone())],
+            Tuple(formats) => Self::tuple_fields(formats),
             Struct(fields) => fields.clone(),
-            Variable(_) => panic!("incorrect value"),
-        };
-        self.output_struct_or_variant_container(Some(base), Some(index), name, &fields)
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
+        }
+    }
+
+    /// Fields `field0`, `field1`, etc of the class of a tuple struct or variant.
+    fn tuple_fields(formats: &[Format]) -> Vec<Named<Format>> {
+        formats
+            .iter()
+            .enumerate()
+            .map(|(i, f)| Named::new(format!("field{}", i), f.clone()))
+            .collect()
     }
 
     fn output_variants(
         &mut self,
         base: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
         for (index, variant) in variants {
-            self.output_variant(base, *index, &variant.name, &variant.value)?;
+            let tag = VariantTag::new(tag, *index, &variant.name);
+            self.output_variant(base, tag, &variant.name, &variant.value)?;
         }
         Ok(())
     }
@@ -508,24 +1629,29 @@ return list;
     fn output_struct_or_variant_container(
         &mut self,
         variant_base: Option<&str>,
-        variant_index: Option<u32>,
+        variant_tag: Option<VariantTag>,
         name: &str,
         fields: &[Named<Format>],
     ) -> Result<()> {
-        let mut variant_base_name = format!("");
+        // Nested variant classes are declared under their own name in the namespace of the enum.
+        let class_name = match variant_base {
+            Some(base) if !self.generator.config.nested_variant_classes => {
+                self.quote_variant_class(base, name)
+            }
+            _ => name.to_string(),
+        };
+
+        let mut path = vec![self.generator.config.module_name.clone()];
+        path.extend(variant_base.map(String::from));
+        path.push(name.to_string());
 
         // Beginning of class
         if let Some(base) = variant_base {
             writeln!(self.out)?;
-            self.output_comment(name)?;
-            writeln!(
-                self.out,
-                "export class {0}Variant{1} extends {0} {{",
-                base, name
-            )?;
-            variant_base_name = format!("{0}Variant", base);
+            self.output_comment(&path)?;
+            writeln!(self.out, "export class {} extends {} {{", class_name, base)?;
         } else {
-            self.output_comment(name)?;
+            self.output_comment_with_note(&path, Some(THREAD_SAFETY_NOTE))?;
             writeln!(self.out, "export class {} {{", name)?;
         }
         if !fields.is_empty() {
@@ -537,16 +1663,50 @@ return list;
             "constructor ({}) {{",
             fields
                 .iter()
-                .map(|f| { format!("public {}: {}", &f.name, self.quote_type(&f.value)) })
+                .map(|f| {
+                    let parameter = format!(
+                        "{}public {}: {}",
+                        self.quote_field_deprecation(&path, f),
+                        &f.name,
+                        self.quote_type(&f.value)
+                    );
+                    match self.quote_field_default(&path, f) {
+                        Some(default) => format!("{} = {}", parameter, default),
+                        None => parameter,
+                    }
+                })
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
+        self.out.indent();
         if let Some(_base) = variant_base {
-            self.out.indent();
             writeln!(self.out, "super();")?;
-            self.out.unindent();
         }
+        for (field, format, constraint) in
+            common::integer_constraints(self.generator.config, &path, fields)
+        {
+            let value = format!("this.{}", field.name);
+            let condition = constraint
+                .comparisons()
+                .iter()
+                .map(|(operator, bound)| {
+                    Self::quote_integer_comparison(&value, format, operator, *bound)
+                })
+                .collect::<Vec<_>>()
+                .join(" && ");
+            writeln!(
+                self.out,
+                "if (!({})) {{ throw new Error(\"{} must be {}\"); }}",
+                condition,
+                field.name,
+                constraint.description()
+            )?;
+        }
+        self.out.unindent();
         writeln!(self.out, "}}\n")?;
+        if self.generator.config.field_descriptors {
+            self.output_field_descriptors(fields)?;
+        }
         // Serialize
         if self.generator.config.serialization {
             writeln!(
@@ -554,8 +1714,14 @@ return list;
                 "public serialize(serializer: Serializer): void {{",
             )?;
             self.out.indent();
-            if let Some(index) = variant_index {
-                writeln!(self.out, "serializer.serializeVariantIndex({});", index)?;
+            match variant_tag {
+                Some(VariantTag::Index(index)) => {
+                    writeln!(self.out, "serializer.serializeVariantIndex({});", index)?
+                }
+                Some(VariantTag::Name { name, .. }) => {
+                    writeln!(self.out, "serializer.serializeStr({:?});", name)?
+                }
+                None => (),
             }
             for field in fields {
                 writeln!(
@@ -569,7 +1735,7 @@ return list;
         }
         // Deserialize (struct) or Load (variant)
         if self.generator.config.serialization {
-            if variant_index.is_none() {
+            if variant_tag.is_none() {
                 writeln!(
                     self.out,
                     "static deserialize(deserializer: Deserializer): {} {{",
@@ -578,42 +1744,128 @@ return list;
             } else {
                 writeln!(
                     self.out,
-                    "static load(deserializer: Deserializer): {}{} {{",
-                    variant_base_name, name,
+                    "static load(deserializer: Deserializer): {} {{",
+                    class_name,
                 )?;
             }
             self.out.indent();
             for field in fields {
                 writeln!(
                     self.out,
-                    "const {} = {};",
-                    field.name,
-                    self.quote_deserialize(&field.value)
+                    "const {} = {};",
+                    field.name,
+                    self.quote_deserialize(&field.value)
+                )?;
+            }
+            writeln!(
+                self.out,
+                r#"return new {0}({1});"#,
+                class_name,
+                fields
+                    .iter()
+                    .map(|f| f.name.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )?;
+            self.out.unindent();
+            writeln!(self.out, "}}\n")?;
+        }
+        if variant_tag.is_none() {
+            self.output_flags(name, fields)?;
+        }
+        writeln!(self.out, "}}")
+    }
+
+    fn output_field_descriptors(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        writeln!(
+            self.out,
+            "static fields(): {{ name: string, kind: string, optional: boolean }}[] {{"
+        )?;
+        self.out.indent();
+        writeln!(self.out, "return [")?;
+        self.out.indent();
+        for field in fields {
+            let (kind, optional) = common::field_kind(&field.value);
+            writeln!(
+                self.out,
+                "{{ name: {:?}, kind: {:?}, optional: {} }},",
+                field.name, kind, optional
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "];")?;
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
+    fn output_flags(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let path = vec![self.generator.config.module_name.clone(), name.to_string()];
+        for (field, flags) in common::flag_sets(self.generator.config, &path, fields) {
+            for (flag, value) in flags {
+                writeln!(
+                    self.out,
+                    "static readonly {}_{} = {:#x};",
+                    field.name.to_shouty_snake_case(),
+                    flag.to_shouty_snake_case(),
+                    value
                 )?;
             }
+            // Use `>>> 0` to keep results in the range of unsigned 32-bit integers.
             writeln!(
                 self.out,
-                r#"return new {0}{1}({2});"#,
-                variant_base_name,
-                name,
-                fields
-                    .iter()
-                    .map(|f| f.name.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",")
+                r#"
+public has{0}(mask: number): boolean {{
+  return ((this.{1} & mask) >>> 0) === mask;
+}}
+
+public set{0}(mask: number): void {{
+  this.{1} = (this.{1} | mask) >>> 0;
+}}
+
+public clear{0}(mask: number): void {{
+  this.{1} = (this.{1} & ~mask) >>> 0;
+}}
+"#,
+                field.name.to_camel_case(),
+                field.name
             )?;
-            self.out.unindent();
-            writeln!(self.out, "}}\n")?;
         }
-        writeln!(self.out, "}}")
+        Ok(())
+    }
+
+    /// Open a switch on the variant `key` and output the cases loading the given variants. The
+    /// caller outputs the default case and closes the switch.
+    fn output_variant_switch(
+        &mut self,
+        name: &str,
+        key: &str,
+        tag: Option<&str>,
+        variants: &[(&u32, &Named<VariantFormat>)],
+    ) -> Result<()> {
+        writeln!(self.out, "switch ({}) {{", key)?;
+        self.out.indent();
+        for (index, variant) in variants {
+            writeln!(
+                self.out,
+                "case {}: return {}.load(deserializer);",
+                match tag {
+                    Some(_) => format!("{:?}", variant.name),
+                    None => index.to_string(),
+                },
+                self.quote_variant_class(name, &variant.name),
+            )?;
+        }
+        Ok(())
     }
 
     fn output_enum_container(
         &mut self,
         name: &str,
+        tag: Option<&str>,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
-        self.output_comment(name)?;
+        let path = [self.generator.config.module_name.clone(), name.to_string()];
+        self.output_comment_with_note(&path, Some(THREAD_SAFETY_NOTE))?;
         writeln!(self.out, "export abstract class {} {{", name)?;
         if self.generator.config.serialization {
             writeln!(
@@ -626,54 +1878,183 @@ return list;
                 name
             )?;
             self.out.indent();
-            writeln!(
-                self.out,
-                r#"
-const index = deserializer.deserializeVariantIndex();
-switch (index) {{"#,
-            )?;
-            self.out.indent();
-            for (index, variant) in variants {
+            let (key_type, key, kind) = match tag {
+                Some(_) => ("string", "tag", "name"),
+                None => ("number", "index", "index"),
+            };
+            if tag.is_some() {
+                writeln!(self.out, "\nconst tag = deserializer.deserializeStr();")?;
+            } else {
                 writeln!(
                     self.out,
-                    "case {}: return {}Variant{}.load(deserializer);",
-                    index, name, variant.name,
+                    "\nconst index = deserializer.deserializeVariantIndex();"
+                )?;
+            }
+            let chunks = common::variant_chunks(self.generator.config, variants);
+            if chunks.len() == 1 {
+                self.output_variant_switch(name, key, tag, &chunks[0])?;
+                writeln!(
+                    self.out,
+                    "default: throw new Error(\"Unknown variant {} for {}: \" + {});",
+                    kind, name, key,
+                )?;
+                self.out.unindent();
+                writeln!(self.out, "}}")?;
+            } else {
+                writeln!(self.out, "let value: {} | undefined;", name)?;
+                for index in 0..chunks.len() {
+                    writeln!(
+                        self.out,
+                        "if ((value = {}.deserializeVariants{}({}, deserializer)) !== undefined) return value;",
+                        name, index, key,
+                    )?;
+                }
+                writeln!(
+                    self.out,
+                    "throw new Error(\"Unknown variant {} for {}: \" + {});",
+                    kind, name, key,
                 )?;
             }
-            writeln!(
-                self.out,
-                "default: throw new Error(\"Unknown variant index for {}: \" + index);",
-                name,
-            )?;
-            self.out.unindent();
-            writeln!(self.out, "}}")?;
             self.out.unindent();
             writeln!(self.out, "}}")?;
+            if chunks.len() > 1 {
+                for (index, chunk) in chunks.iter().enumerate() {
+                    write!(
+                        self.out,
+                        "\nprivate static deserializeVariants{}({}: {}, deserializer: Deserializer): {} | undefined {{\n",
+                        index, key, key_type, name,
+                    )?;
+                    self.out.indent();
+                    self.output_variant_switch(name, key, tag, chunk)?;
+                    writeln!(self.out, "default: return undefined;")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                    self.out.unindent();
+                    writeln!(self.out, "}}")?;
+                }
+            }
+        }
+        if self.generator.config.variant_names {
+            self.output_variant_names(variants)?;
         }
         writeln!(self.out, "}}\n")?;
-        self.output_variants(name, variants)?;
+        if self.generator.config.nested_variant_classes {
+            writeln!(self.out, "export namespace {} {{", name)?;
+            self.out.indent();
+            self.output_variants(name, tag, variants)?;
+            self.out.unindent();
+            writeln!(self.out, "}}\n")?;
+        } else {
+            self.output_variants(name, tag, variants)?;
+        }
         Ok(())
     }
 
+    /// Output an enum without variant data as a union of string literals, together with tables
+    /// mapping variant indices to names and back, used to (de)serialize values.
+    fn output_unit_enum(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        let path = [self.generator.config.module_name.clone(), name.to_string()];
+        self.output_comment(&path)?;
+        writeln!(
+            self.out,
+            "export type {} = {};\n",
+            name,
+            variants
+                .values()
+                .map(|v| format!("{:?}", v.name))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        )?;
+        writeln!(
+            self.out,
+            "export const {0}Names: {{ readonly [index: number]: {0} | undefined }} = {{ {1} }};\n",
+            name,
+            variants
+                .iter()
+                .map(|(index, v)| format!("{}: {:?}", index, v.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        writeln!(
+            self.out,
+            "export const {0}Indices: {{ readonly [name in {0}]: number }} = {{ {1} }};\n",
+            name,
+            variants
+                .iter()
+                .map(|(index, v)| format!("{:?}: {}", v.name, index))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        if !self.generator.config.serialization {
+            return Ok(());
+        }
+        writeln!(
+            self.out,
+            r#"export const {0} = {{
+  serialize(value: {0}, serializer: Serializer): void {{
+    serializer.serializeVariantIndex({0}Indices[value]);
+  }},
+
+  deserialize(deserializer: Deserializer): {0} {{
+    const index = deserializer.deserializeVariantIndex();
+    const value = {0}Names[index];
+    if (value === undefined) {{
+      throw new Error("Unknown variant index for {0}: " + index);
+    }}
+    return value;
+  }},
+}};
+"#,
+            name
+        )
+    }
+
+    fn output_variant_names(
+        &mut self,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"
+static readonly variantNames: readonly string[] = [{}];
+
+static variantName(index: number): string | undefined {{
+  return this.variantNames[index];
+}}
+
+static variantIndex(name: string): number | undefined {{
+  const index = this.variantNames.indexOf(name);
+  return index >= 0 ? index : undefined;
+}}"#,
+            variants
+                .values()
+                .map(|v| format!("{:?}", v.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
         let fields = match format {
             UnitStruct => Vec::new(),
-            NewTypeStruct(format) => vec![Named {
-                name: "value".to_string(),
-                value: format.as_ref().clone(),
-            }],
-            TupleStruct(formats) => formats
-                .iter()
-                .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
-                .collect::<Vec<_>>(),
+            NewTypeStruct(format) => vec![Named::new("value", format.as_ref().clone())],
+            TupleStruct(formats) => Self::tuple_fields(formats),
             Struct(fields) => fields.clone(),
+            Enum(variants) if self.unit_enums.contains(name) => {
+                self.output_unit_enum(name, variants)?;
+                return Ok(());
+            }
             Enum(variants) => {
-                self.output_enum_container(name, variants)?;
+                self.output_enum_container(name, None, variants)?;
+                return Ok(());
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                self.output_enum_container(name, Some(tag), variants)?;
                 return Ok(());
             }
         };
@@ -681,33 +2062,371 @@ switch (index) {{"#,
     }
 }
 
+impl<'a, T> TypeScriptEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_fixtures_preamble(&mut self, containers: &[(&str, &ContainerFormat)]) -> Result<()> {
+        let module_system = self.generator.module_system;
+        let mut names = Vec::new();
+        for (name, format) in containers {
+            names.push(name.to_string());
+            match format {
+                ContainerFormat::Enum(variants)
+                | ContainerFormat::InternallyTaggedEnum { variants, .. }
+                    if !self.unit_enums.contains(*name)
+                        && !self.generator.config.nested_variant_classes =>
+                {
+                    if let Some(variant) = variants.values().next() {
+                        names.push(self.quote_variant_class(name, &variant.name));
+                    }
+                }
+                _ => (),
+            }
+        }
+        writeln!(
+            self.out,
+            "\nimport {{ {} }} from './index{}';",
+            names.join(", "),
+            module_system.import_suffix(),
+        )?;
+        for namespace in self.generator.namespaces_to_import.iter() {
+            writeln!(
+                self.out,
+                "import * as {}Fixtures from '../{}/fixtures{}';",
+                namespace.to_camel_case(),
+                namespace,
+                module_system.import_suffix(),
+            )?;
+        }
+        writeln!(self.out)
+    }
+
+    /// Expression building a valid value of the given format, for test fixtures.
+    fn quote_sample(&self, format: &Format) -> String {
+        use Format::*;
+        if let Some(integer) = common::nonzero_integer(format) {
+            return Self::quote_integer_sample(integer, 1);
+        }
+        match format {
+            TypeName(x) => match self.addresses.get(x) {
+                Some(AddressFormat::IpAddr) => "\"0.0.0.0\"".into(),
+                Some(AddressFormat::SocketAddr) => "\"0.0.0.0:0\"".into(),
+                None => match self.generator.external_qualified_names.get(x) {
+                    Some(qualified_name) => format!(
+                        "{}Fixtures.sample()",
+                        qualified_name.replacen('.', "Fixtures.", 1)
+                    ),
+                    None => format!("{}Fixtures.sample()", x),
+                },
+            },
+            Unit | Option(_) => "null".into(),
+            Bool => "false".into(),
+            I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128 => {
+                Self::quote_integer_sample(format, 0)
+            }
+            F32 | F64 => "0".into(),
+            Char => "\"a\"".into(),
+            Str => "\"\"".into(),
+            Bytes => "new Uint8Array()".into(),
+            Seq(_) => "[]".into(),
+            Set(_) => "new Set()".into(),
+            Map { .. } => "new Map()".into(),
+            Tuple(formats) => format!(
+                "[{}]",
+                formats
+                    .iter()
+                    .map(|f| self.quote_sample(f))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TupleArray { content, size } => format!(
+                "[{}]",
+                vec![format!("[{}]", self.quote_sample(content)); *size].join(", ")
+            ),
+            Custom { layout, .. } => match common::TimeFormat::of(format) {
+                Some(common::TimeFormat::Duration) => "0".into(),
+                Some(common::TimeFormat::SystemTime) => "new Date(0)".into(),
+                None => self.quote_sample(layout),
+            },
+            Decimal { mantissa, .. } => self.quote_sample(mantissa),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
+        }
+    }
+
+    fn quote_integer_sample(format: &Format, value: i128) -> String {
+        use Format::*;
+        match format {
+            I64 | I128 | U64 | U128 => format!("BigInt(\"{}\")", value),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Sample of the field `name` of the definition `path`, following its default value and
+    /// the constraints on its values, if any.
+    fn quote_field_sample(&self, path: &[String], field: &Named<Format>) -> String {
+        if let Some(default) = self.quote_field_default(path, field) {
+            return default;
+        }
+        let fields = std::slice::from_ref(field);
+        match common::integer_constraints(self.generator.config, path, fields).pop() {
+            Some((_, format, constraint)) => {
+                Self::quote_integer_sample(format, constraint.sample())
+            }
+            None => self.quote_sample(&field.value),
+        }
+    }
+
+    fn quote_field_samples(&self, path: &[String], fields: &[Named<Format>]) -> Vec<String> {
+        fields
+            .iter()
+            .map(|field| self.quote_field_sample(path, field))
+            .collect()
+    }
+
+    fn output_fixture(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        let module_name = self.generator.config.module_name.clone();
+        let fields = match format {
+            UnitStruct => Vec::new(),
+            NewTypeStruct(format) => vec![Named::new("value", format.as_ref().clone())],
+            TupleStruct(formats) => Self::tuple_fields(formats),
+            Struct(fields) => fields.clone(),
+            Enum(variants) | InternallyTaggedEnum { variants, .. } => {
+                return self.output_enum_fixture(name, variants);
+            }
+        };
+        writeln!(self.out, "export class {}Fixtures {{", name)?;
+        self.out.indent();
+        if fields.is_empty() {
+            writeln!(self.out, "static sample(): {} {{", name)?;
+            writeln!(self.out, "  return new {}();", name)?;
+        } else {
+            let path = vec![module_name, name.to_string()];
+            writeln!(
+                self.out,
+                "static sample(overrides: Partial<{0}> = {{}}): {0} {{",
+                name
+            )?;
+            self.out.indent();
+            writeln!(
+                self.out,
+                "const fields = {{ {}, ...overrides }};",
+                fields
+                    .iter()
+                    .zip(self.quote_field_samples(&path, &fields))
+                    .map(|(field, sample)| format!("{}: {}", field.name, sample))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+            writeln!(
+                self.out,
+                "return new {}({});",
+                name,
+                fields
+                    .iter()
+                    .map(|field| format!("fields.{}", field.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+            self.out.unindent();
+        }
+        writeln!(self.out, "}}")?;
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
+    fn output_enum_fixture(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(self.out, "export class {}Fixtures {{", name)?;
+        self.out.indent();
+        writeln!(self.out, "static sample(): {} {{", name)?;
+        self.out.indent();
+        // The first variant is a base case of recursive definitions.
+        match variants.values().next() {
+            None => writeln!(self.out, "throw new Error(\"{} has no variants\");", name)?,
+            Some(variant) if self.unit_enums.contains(name) => {
+                writeln!(self.out, "return {:?};", variant.name)?
+            }
+            Some(variant) => {
+                let fields = Self::variant_fields(&variant.value);
+                let path = vec![
+                    self.generator.config.module_name.clone(),
+                    name.to_string(),
+                    variant.name.clone(),
+                ];
+                writeln!(
+                    self.out,
+                    "return new {}({});",
+                    self.quote_variant_class(name, &variant.name),
+                    self.quote_field_samples(&path, &fields).join(", ")
+                )?;
+            }
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+}
+
 /// Installer for generated source files in TypeScript.
+#[derive(Clone)]
 pub struct Installer {
     install_dir: PathBuf,
+    module_system: ModuleSystem,
+    zod: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            module_system: ModuleSystem::default(),
+            zod: false,
+        }
     }
 
-    fn install_runtime(
-        &self,
-        source_dir: include_dir::Dir,
-        path: &str,
-    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// Module system targeted by the generated code and the installed runtimes.
+    pub fn with_module_system(mut self, module_system: ModuleSystem) -> Self {
+        self.module_system = module_system;
+        self
+    }
+
+    /// Whether to generate zod schemas (requires the `zod` package, version 3.20 or later).
+    pub fn with_zod(mut self, zod: bool) -> Self {
+        self.zod = zod;
+        self
+    }
+
+    fn install_runtime(&self, source_dir: include_dir::Dir, path: &str) -> crate::Result<()> {
         let dir_path = self.install_dir.join(path);
         std::fs::create_dir_all(&dir_path)?;
         for entry in source_dir.files() {
             let mut file = std::fs::File::create(dir_path.join(entry.path()))?;
-            file.write_all(entry.contents())?;
+            let content =
+                std::str::from_utf8(entry.contents()).expect("runtime sources should be UTF-8");
+            file.write_all(self.module_system.quote_runtime_source(content).as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl Installer {
+    /// Write the module `content` marked with part boundaries into `index.ts`. If the module is
+    /// longer than `max_lines`, containers are moved to modules `index_part<i>.ts` importing the
+    /// names of the other modules and re-exported by `index.ts`.
+    fn write_parts(
+        &self,
+        config: &CodeGeneratorConfig,
+        dir_path: &Path,
+        content: &str,
+        max_lines: usize,
+    ) -> Result<()> {
+        // Chunks: preamble, type aliases, containers, then helpers and other trailing definitions.
+        let mut chunks = common::split_chunks(content);
+        let trailer = chunks.pop().unwrap_or_default();
+        let aliases = chunks.remove(1);
+        let preamble = chunks.remove(0);
+        if content.lines().count() <= max_lines {
+            let content = [preamble, aliases, chunks.concat(), trailer].concat();
+            let mut file = common::create_source_file(config, dir_path.join("index.ts"))?;
+            return file.write_all(content.as_bytes());
+        }
+        let budget = max_lines.saturating_sub(preamble.lines().count());
+        let mut modules = vec![format!("{}{}", aliases, trailer)];
+        modules.extend(common::group_chunks(&chunks, budget));
+        let names = modules
+            .iter()
+            .map(|module| Self::exported_names(module))
+            .collect::<Vec<_>>();
+        let suffix = self.module_system.import_suffix();
+        let module_path = |index: usize| match index {
+            0 => format!("./index{}", suffix),
+            _ => format!("./index_part{}{}", index, suffix),
+        };
+        for (index, module) in modules.iter().enumerate() {
+            let mut out = preamble.clone();
+            for (other, other_names) in names.iter().enumerate() {
+                let other_names = other_names
+                    .iter()
+                    .filter(|name| !names[index].contains(name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if other != index && !other_names.is_empty() {
+                    out += &format!(
+                        "import {{ {} }} from '{}';\n",
+                        other_names.join(", "),
+                        module_path(other)
+                    );
+                }
+            }
+            out += module;
+            let file_name = match index {
+                0 => {
+                    for part in 1..modules.len() {
+                        out += &format!("export * from '{}';\n", module_path(part));
+                    }
+                    "index.ts".to_string()
+                }
+                _ => format!("index_part{}.ts", index),
+            };
+            let mut file = common::create_source_file(config, dir_path.join(file_name))?;
+            file.write_all(out.as_bytes())?;
         }
         Ok(())
     }
+
+    /// Names declared by the top-level `export` statements of `module`.
+    fn exported_names(module: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        for line in module.lines() {
+            let mut words = match line.strip_prefix("export ") {
+                Some(declaration) => declaration.split(|c: char| !c.is_alphanumeric() && c != '_'),
+                None => continue,
+            };
+            let name = words.find(|word| {
+                !word.is_empty()
+                    && !matches!(
+                        *word,
+                        "abstract"
+                            | "class"
+                            | "const"
+                            | "enum"
+                            | "function"
+                            | "interface"
+                            | "namespace"
+                            | "type"
+                    )
+            });
+            if let Some(name) = name {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
 }
 
 impl crate::SourceInstaller for Installer {
-    type Error = Box<dyn std::error::Error>;
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
 
     fn install_module(
         &self,
@@ -716,11 +2435,27 @@ impl crate::SourceInstaller for Installer {
     ) -> std::result::Result<(), Self::Error> {
         let dir_path = self.install_dir.join(&config.module_name);
         std::fs::create_dir_all(&dir_path)?;
-        let source_path = dir_path.join("index.ts");
-        let mut file = std::fs::File::create(source_path)?;
-
-        let generator = CodeGenerator::new(config);
-        generator.output(&mut file, registry)?;
+        let generator = CodeGenerator::new(config)
+            .with_module_system(self.module_system)
+            .with_zod(self.zod)
+            .with_part_boundaries(config.max_file_lines.is_some());
+        match config.max_file_lines {
+            None => {
+                let source_path = dir_path.join("index.ts");
+                let mut file = common::create_source_file(config, source_path)?;
+                generator.output(&mut file, registry)?;
+            }
+            Some(max_lines) => {
+                let mut content = Vec::new();
+                generator.output(&mut content, registry)?;
+                let content = String::from_utf8(content).expect("generated code should be UTF-8");
+                self.write_parts(config, &dir_path, &content, max_lines)?;
+            }
+        }
+        if config.fixtures {
+            let mut file = common::create_source_file(config, dir_path.join("fixtures.ts"))?;
+            generator.output_fixtures(&mut file, registry)?;
+        }
         Ok(())
 
         // let generator = CodeGenerator::new(config, true);
@@ -732,11 +2467,11 @@ impl crate::SourceInstaller for Installer {
         self.install_runtime(include_directory!("runtime/typescript/serde"), "serde")
     }
 
-    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.install_runtime(include_directory!("runtime/typescript/bincode"), "bincode")
-    }
-
-    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
-        self.install_runtime(include_directory!("runtime/typescript/bcs"), "bcs")
+    fn install_encoding_runtime(&self, encoding: Encoding) -> std::result::Result<(), Self::Error> {
+        let source_dir = match encoding {
+            Encoding::Bincode => include_directory!("runtime/typescript/bincode"),
+            Encoding::Bcs => include_directory!("runtime/typescript/bcs"),
+        };
+        self.install_runtime(source_dir, encoding.name())
     }
 }
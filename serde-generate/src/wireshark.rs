@@ -0,0 +1,431 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    common,
+    indent::{IndentConfig, IndentedWriter},
+    transcode::Framing,
+    CodeGeneratorConfig, Encoding,
+};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
+
+/// Main configuration object for the generation of Wireshark dissectors in Lua.
+pub struct CodeGenerator<'a> {
+    /// Language-independent configuration.
+    config: &'a CodeGeneratorConfig,
+    /// Container name of the messages carried by the protocol.
+    root_type: Option<String>,
+    /// How messages are delimited on the wire.
+    framing: Framing,
+    /// TCP port registered for the protocol (otherwise, use "Decode As...").
+    port: Option<u16>,
+}
+
+/// Shared state for the code generation of a Lua dissector.
+struct LuaEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    /// Generator.
+    generator: &'a CodeGenerator<'a>,
+}
+
+impl<'a> CodeGenerator<'a> {
+    /// Create a Wireshark dissector generator for the given config.
+    /// Messages are decoded with BCS, unless Bincode is the only encoding of the config.
+    pub fn new(config: &'a CodeGeneratorConfig) -> Self {
+        Self {
+            config,
+            root_type: None,
+            framing: Framing::U32Le,
+            port: None,
+        }
+    }
+
+    /// Container name of the messages carried by the protocol.
+    pub fn with_root_type(mut self, root_type: Option<String>) -> Self {
+        self.root_type = root_type;
+        self
+    }
+
+    /// How messages are delimited on the wire.
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// TCP port registered for the protocol.
+    pub fn with_port(mut self, port: Option<u16>) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Write a Lua dissector for the container definitions.
+    pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> crate::Result<()> {
+        let registry = &*common::without_skipped_fields(registry)?;
+        common::check_formats("Wireshark", registry)?;
+        let mut emitter = LuaEmitter {
+            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            generator: self,
+        };
+        emitter.output_preamble()?;
+        for (name, format) in registry {
+            common::check_cancellation(self.config)?;
+            emitter.output_container(name, format)?;
+        }
+        if let Some(root_type) = &self.root_type {
+            emitter.output_dissector(root_type)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> LuaEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_preamble(&mut self) -> Result<()> {
+        let module_name = &self.generator.config.module_name;
+        let encoding = self.generator.config.single_encoding();
+        writeln!(
+            self.out,
+            r#"-- Wireshark dissector for the Serde formats of `{0}` ({1} encoding).
+-- Install this file in the Wireshark plugin directory.
+
+local proto = Proto("{2}", "{0} messages")
+"#,
+            module_name,
+            encoding.name(),
+            module_name.replace('.', "_").to_lowercase(),
+        )?;
+        match encoding {
+            Encoding::Bcs => writeln!(
+                self.out,
+                r#"local function read_uleb128(buffer, offset)
+    local value, multiplier = 0, 1
+    repeat
+        local byte = buffer(offset, 1):uint()
+        value = value + (byte % 128) * multiplier
+        multiplier = multiplier * 128
+        offset = offset + 1
+    until byte < 128
+    return value, offset
+end
+
+local read_len = read_uleb128
+local read_variant_index = read_uleb128
+"#
+            )?,
+            Encoding::Bincode => writeln!(
+                self.out,
+                r#"local function read_len(buffer, offset)
+    return buffer(offset, 8):le_uint64():tonumber(), offset + 8
+end
+
+local function read_variant_index(buffer, offset)
+    return buffer(offset, 4):le_uint(), offset + 4
+end
+"#
+            )?,
+        }
+        write!(
+            self.out,
+            "{}",
+            include_str!("../runtime/wireshark/serde.lua")
+        )
+    }
+
+    fn output_comment(&mut self, name: &str) -> Result<()> {
+        let mut path: Vec<_> = self
+            .generator
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect();
+        path.push(name.to_string());
+        if let Some(doc) = self.generator.config.comment(&path) {
+            write!(self.out, "{}", textwrap::indent(&doc, "-- "))?;
+        }
+        Ok(())
+    }
+
+    fn quote_type(format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(x) => format!("named({:?})", x),
+            Unit => "parse_unit".into(),
+            Bool => "parse_bool".into(),
+            I8 => "parse_i8".into(),
+            I16 => "parse_i16".into(),
+            I32 => "parse_i32".into(),
+            I64 => "parse_i64".into(),
+            I128 => "parse_i128".into(),
+            U8 => "parse_u8".into(),
+            U16 => "parse_u16".into(),
+            U32 => "parse_u32".into(),
+            U64 => "parse_u64".into(),
+            U128 => "parse_u128".into(),
+            F32 => "parse_f32".into(),
+            F64 => "parse_f64".into(),
+            Char => "parse_char".into(),
+            Str => "parse_str".into(),
+            Bytes => "parse_bytes".into(),
+            Option(format) => format!("parse_option({})", Self::quote_type(format)),
+            Seq(format) | Set(format) => format!("parse_seq({})", Self::quote_type(format)),
+            Map { key, value } => format!(
+                "parse_map({}, {})",
+                Self::quote_type(key),
+                Self::quote_type(value)
+            ),
+            Tuple(formats) => format!("parse_tuple({})", Self::quote_types(formats)),
+            TupleArray { content, size } => {
+                format!("parse_array({}, {})", Self::quote_type(content), size)
+            }
+            Custom { layout, .. } => Self::quote_type(layout),
+            Decimal { mantissa, .. } => Self::quote_type(mantissa),
+            Variable(_) | Skipped(_) => {
+                unreachable!("formats are checked by common::check_formats")
+            }
+        }
+    }
+
+    fn quote_types(formats: &[Format]) -> String {
+        format!(
+            "{{{}}}",
+            formats
+                .iter()
+                .map(Self::quote_type)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn quote_fields(fields: &[Named<Format>]) -> String {
+        format!(
+            "{{{}}}",
+            fields
+                .iter()
+                .map(|field| format!("{{{:?}, {}}}", field.name, Self::quote_type(&field.value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn quote_variant(format: &VariantFormat) -> String {
+        use VariantFormat::*;
+        match format {
+            Unit => "parse_unit".into(),
+            NewType(format) => Self::quote_type(format),
+            Tuple(formats) => format!("parse_tuple({})", Self::quote_types(formats)),
+            Struct(fields) => format!("parse_struct({})", Self::quote_fields(fields)),
+            Variable(_) => unreachable!("formats are checked by common::check_formats"),
+        }
+    }
+
+    fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        let parser = match format {
+            UnitStruct => "parse_unit".to_string(),
+            NewTypeStruct(format) => Self::quote_type(format),
+            TupleStruct(formats) => format!("parse_tuple({})", Self::quote_types(formats)),
+            Struct(fields) => format!("parse_struct({})", Self::quote_fields(fields)),
+            Enum(variants) => {
+                writeln!(self.out, "types[{:?}] = parse_enum({{", name)?;
+                self.out.indent();
+                for (index, variant) in variants {
+                    writeln!(
+                        self.out,
+                        "[{}] = {{{:?}, {}}},",
+                        index,
+                        variant.name,
+                        Self::quote_variant(&variant.value)
+                    )?;
+                }
+                self.out.unindent();
+                return writeln!(self.out, "}})");
+            }
+            InternallyTaggedEnum { variants, .. } => {
+                writeln!(self.out, "types[{:?}] = parse_tagged_enum({{", name)?;
+                self.out.indent();
+                for variant in variants.values() {
+                    writeln!(
+                        self.out,
+                        "[{:?}] = {},",
+                        variant.name,
+                        Self::quote_variant(&variant.value)
+                    )?;
+                }
+                self.out.unindent();
+                return writeln!(self.out, "}})");
+            }
+        };
+        writeln!(self.out, "types[{:?}] = {}", name, parser)
+    }
+
+    fn output_frame_header(&mut self) -> Result<()> {
+        let read_header = match self.generator.framing {
+            Framing::U32Le => "return 4, buffer(offset, 4):le_uint()",
+            Framing::U32Be => "return 4, buffer(offset, 4):uint()",
+            Framing::Uleb128 => {
+                return writeln!(
+                    self.out,
+                    r#"
+local function read_frame_header(buffer, offset)
+    local len, multiplier, size = 0, 1, 0
+    repeat
+        if offset + size >= buffer:len() then
+            return nil
+        end
+        local byte = buffer(offset + size, 1):uint()
+        len = len + (byte % 128) * multiplier
+        multiplier = multiplier * 128
+        size = size + 1
+    until byte < 128
+    return size, len
+end"#
+                );
+            }
+        };
+        writeln!(
+            self.out,
+            r#"
+local function read_frame_header(buffer, offset)
+    if buffer:len() - offset < 4 then
+        return nil
+    end
+    {}
+end"#,
+            read_header
+        )
+    }
+
+    fn output_dissector(&mut self, root_type: &str) -> Result<()> {
+        self.output_frame_header()?;
+        writeln!(
+            self.out,
+            r#"
+function proto.dissector(buffer, pinfo, tree)
+    pinfo.cols.protocol = proto.name
+    local offset = 0
+    while offset < buffer:len() do
+        local header, len = read_frame_header(buffer, offset)
+        if header == nil or buffer:len() - offset < header + len then
+            pinfo.desegment_offset = offset
+            pinfo.desegment_len = DESEGMENT_ONE_MORE_SEGMENT
+            return
+        end
+        local item = tree:add(proto, buffer(offset, header + len), {0:?})
+        if len > 0 then
+            local message = buffer(offset + header, len):tvb()
+            local ok, err = pcall(types[{0:?}], message, 0, item, {0:?})
+            if not ok then
+                item:add_expert_info(PI_MALFORMED, PI_ERROR, tostring(err))
+            end
+        end
+        offset = offset + header + len
+    end
+end
+"#,
+            root_type
+        )?;
+        match self.generator.port {
+            Some(port) => writeln!(
+                self.out,
+                "DissectorTable.get(\"tcp.port\"):add({}, proto)",
+                port
+            ),
+            None => writeln!(
+                self.out,
+                "DissectorTable.get(\"tcp.port\"):add_for_decode_as(proto)"
+            ),
+        }
+    }
+}
+
+/// Installer for generated Wireshark dissectors.
+#[derive(Clone)]
+pub struct Installer {
+    install_dir: PathBuf,
+    root_type: Option<String>,
+    framing: Framing,
+    port: Option<u16>,
+}
+
+impl Installer {
+    pub fn new(install_dir: PathBuf) -> Self {
+        Installer {
+            install_dir,
+            root_type: None,
+            framing: Framing::U32Le,
+            port: None,
+        }
+    }
+
+    /// Container name of the messages carried by the protocol.
+    pub fn with_root_type(mut self, root_type: Option<String>) -> Self {
+        self.root_type = root_type;
+        self
+    }
+
+    /// How messages are delimited on the wire.
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// TCP port registered for the protocol.
+    pub fn with_port(mut self, port: Option<u16>) -> Self {
+        self.port = port;
+        self
+    }
+}
+
+impl crate::SourceInstaller for Installer {
+    type Error = crate::Error;
+
+    fn install_dir(&self) -> Option<&Path> {
+        Some(&self.install_dir)
+    }
+
+    fn relocated(&self, install_dir: &Path) -> Option<Self> {
+        Some(Self {
+            install_dir: install_dir.to_path_buf(),
+            ..self.clone()
+        })
+    }
+
+    fn install_module(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &Registry,
+    ) -> std::result::Result<(), Self::Error> {
+        std::fs::create_dir_all(&self.install_dir)?;
+        let path = self
+            .install_dir
+            .join(format!("{}.lua", config.module_name.replace('.', "_")));
+        let mut file = common::create_source_file(config, path)?;
+        let generator = CodeGenerator::new(config)
+            .with_root_type(self.root_type.clone())
+            .with_framing(self.framing)
+            .with_port(self.port);
+        generator.output(&mut file, registry)?;
+        Ok(())
+    }
+
+    // Dissectors are self-contained: the runtime parsers are included in each module.
+
+    fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn install_encoding_runtime(
+        &self,
+        _encoding: Encoding,
+    ) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
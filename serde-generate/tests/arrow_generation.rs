@@ -0,0 +1,34 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{arrow, test_utils, CodeGeneratorConfig};
+
+#[test]
+fn test_that_arrow_schemas_are_generated() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    arrow::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+
+    assert!(content.contains(
+        r#"pub fn test_fields() -> Fields {
+    Fields::from(vec![Field::new("a", DataType::List(Arc::new(Field::new("item", DataType::UInt32, false))), false), Field::new("b", DataType::Struct(Fields::from(vec![Field::new("0", DataType::Int64, false), Field::new("1", DataType::UInt64, false)])), false), Field::new("c", choice_data_type(), false)])
+}"#
+    ));
+    assert!(content.contains("Schema::new(test_fields())"));
+    assert!(content.contains(
+        r#"DataType::Union(UnionFields::new(vec![0, 1, 2], vec![Field::new("A", DataType::Null, true), Field::new("B", DataType::UInt64, false), Field::new("C", DataType::Struct(Fields::from(vec![Field::new("x", DataType::UInt8, false)])), false)]), UnionMode::Dense)"#
+    ));
+}
+
+#[test]
+fn test_that_recursive_containers_are_rejected() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    assert!(arrow::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .is_err());
+}
@@ -225,3 +225,163 @@ fn test_that_installed_java_code_compiles() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_describe_and_completions() {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate")
+        .arg("--")
+        .arg("describe")
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // JSON is valid YAML.
+    let value: serde_yaml::Value = serde_yaml::from_slice(&output.stdout).unwrap();
+    let languages = value["languages"].as_sequence().unwrap();
+    assert!(languages.contains(&serde_yaml::Value::from("Python3")));
+    let options = value["options"].as_sequence().unwrap();
+    let pydantic = options
+        .iter()
+        .find(|option| option["name"] == "--pydantic")
+        .unwrap();
+    assert_eq!(
+        pydantic["languages"],
+        serde_yaml::Value::Sequence(vec!["Python3".into()])
+    );
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate")
+        .arg("--")
+        .arg("completions")
+        .arg("bash")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("serdegen"));
+}
+
+#[test]
+fn test_progress_notes() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate")
+        .arg("--")
+        .arg("--language")
+        .arg("java")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--module-name")
+        .arg("testing")
+        .arg("--progress")
+        .arg("--message-format")
+        .arg("json")
+        .arg(yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    // Skip the messages of cargo.
+    let notes = stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_yaml::from_str::<serde_yaml::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(notes.iter().all(|note| note["severity"] == "note"));
+    assert!(notes.iter().all(|note| note["code"] == "generated-file"));
+    let files = notes
+        .iter()
+        .map(|note| note["file"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    let path = dir.path().join("testing/SerdeData.java");
+    assert!(files.contains(&path.to_str().unwrap().to_string()));
+    assert_eq!(files.len(), registry.len() + 1);
+}
+
+#[test]
+fn test_json_diagnostics() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), "Foo:\n  STRUCT: [\n").unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate")
+        .arg("--")
+        .arg("--message-format")
+        .arg("json")
+        .arg(yaml_path.clone())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    // Skip the messages of cargo.
+    let line = stderr.lines().last().unwrap();
+    let diagnostic: serde_yaml::Value = serde_yaml::from_str(line).unwrap();
+    assert_eq!(diagnostic["severity"], serde_yaml::Value::from("error"));
+    assert_eq!(
+        diagnostic["code"],
+        serde_yaml::Value::from("invalid-formats")
+    );
+    assert_eq!(
+        diagnostic["file"],
+        serde_yaml::Value::from(yaml_path.to_str().unwrap())
+    );
+    assert!(diagnostic["line"].is_u64());
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate")
+        .arg("--")
+        .arg("--no-such-option")
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(2));
+}
+
+#[test]
+fn test_that_runtime_packages_are_rejected_outside_of_dart_and_csharp() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate")
+        .arg("--")
+        .arg("--language")
+        .arg("typescript")
+        .arg("--runtime-package")
+        .arg("serde_runtimes:0.1.0")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--message-format")
+        .arg("json")
+        .arg(yaml_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let line = stderr.lines().last().unwrap();
+    let diagnostic: serde_yaml::Value = serde_yaml::from_str(line).unwrap();
+    assert_eq!(
+        diagnostic["code"],
+        serde_yaml::Value::from("unsupported-runtime-package")
+    );
+}
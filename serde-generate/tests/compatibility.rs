@@ -0,0 +1,203 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{
+    compatibility::{
+        check_compatibility, check_compatibility_with_config, Compatibility, SchemaChange,
+    },
+    CodeGeneratorConfig, Deprecations, Encoding, Error,
+};
+use serde_reflection::{ContainerChange, ContainerFormat, Named, Registry, VariantFormat};
+
+const OLD: &str = r#"
+Account:
+  STRUCT:
+    - id: U64
+    - name: STR
+    - data: {SEQ: U8}
+Audit:
+  NEWTYPESTRUCT: U64
+Event:
+  ENUM:
+    0:
+      Opened:
+        NEWTYPE: {TYPENAME: Account}
+    1:
+      Closed:
+        STRUCT:
+          - id: U64
+    2:
+      Frozen: UNIT
+Ledger:
+  NEWTYPESTRUCT: {SEQ: {TUPLE: [STR, U64]}}
+Legacy: UNITSTRUCT
+"#;
+
+const NEW: &str = r#"
+Account:
+  STRUCT:
+    - identifier: U64
+    - name: STR
+    - data: BYTES
+    - cache: {SKIPPED: STR}
+Audit:
+  STRUCT:
+    - height: U64
+Event:
+  ENUM:
+    0:
+      Opened:
+        NEWTYPE: {TYPENAME: Account}
+    1:
+      Terminated:
+        TUPLE: [U64]
+    2:
+      Frozen:
+        NEWTYPE: STR
+    3:
+      Renamed:
+        NEWTYPE: STR
+Ledger:
+  NEWTYPESTRUCT: {MAP: {KEY: STR, VALUE: U64}}
+Notice:
+  NEWTYPESTRUCT: STR
+"#;
+
+fn registry(yaml: &str) -> Registry {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+#[test]
+fn test_unchanged_registry_is_compatible() {
+    let registry = registry(OLD);
+    let report = check_compatibility(&registry, &registry, Encoding::Bcs).unwrap();
+    assert!(report.changes.is_empty());
+    assert!(report.is_compatible());
+}
+
+#[test]
+fn test_changes_preserving_the_wire_layout_are_compatible() {
+    let report = check_compatibility(&registry(OLD), &registry(NEW), Encoding::Bincode).unwrap();
+    let compatible = report
+        .changes
+        .iter()
+        .filter(|change| change.compatibility == Compatibility::Compatible)
+        .map(|change| change.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        compatible,
+        vec![
+            "Notice: container was added",
+            "Account: field `id` was removed",
+            "Account: field `data` changed from `Seq(U8)` to `Bytes`",
+            "Account: field `identifier` was added with format `U64`",
+            "Account: field `cache` was added with format `Skipped(Str)`",
+            "Audit: container changed from `NewTypeStruct(U64)` to `Struct([Named { name: \"height\", value: U64, source_name: None }])`",
+            "Event: variant #1 was renamed from `Closed` to `Terminated`",
+            "Event: variant `Terminated` changed from `Struct([Named { name: \"id\", value: U64, source_name: None }])` to `Tuple([U64])`",
+            "Event: variant #3 `Renamed` was added",
+            "Ledger: field `0` changed from `Seq(Tuple([Str, U64]))` to `Map { key: Str, value: U64 }`",
+        ]
+    );
+    assert_eq!(
+        report.to_string(),
+        r#"Legacy: container was removed (breaking for bincode)
+Event: variant `Frozen` changed from `Unit` to `NewType(Str)` (breaking for bincode)
+"#
+    );
+}
+
+#[test]
+fn test_bcs_rejects_sequences_read_as_maps() {
+    let old = registry(OLD);
+    let new = registry(NEW);
+    let report = check_compatibility(&old, &new, Encoding::Bcs).unwrap();
+    assert_eq!(
+        report
+            .violations()
+            .iter()
+            .map(|change| change.container.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Legacy", "Event", "Ledger"]
+    );
+
+    // Sorted maps are valid sequences of pairs.
+    let report = check_compatibility(&new, &old, Encoding::Bcs).unwrap();
+    assert!(report
+        .changes
+        .iter()
+        .any(|change| change.container == "Ledger"
+            && change.compatibility == Compatibility::Compatible));
+}
+
+#[test]
+fn test_field_changes_are_breaking() {
+    let old = registry("Point:\n  STRUCT:\n    - x: U32\n    - y: STR\n    - z: U8\n");
+    let new = registry("Point:\n  STRUCT:\n    - y: STR\n    - x: U64\n");
+    for encoding in [Encoding::Bincode, Encoding::Bcs].iter().copied() {
+        let report = check_compatibility(&old, &new, encoding).unwrap();
+        assert!(!report.is_compatible());
+        assert_eq!(report.violations().len(), report.changes.len());
+        assert!(report.changes.iter().any(|change| matches!(
+            &change.change,
+            SchemaChange::ContainerChanged(ContainerChange::FieldRemoved { name, .. }) if name == "z"
+        )));
+    }
+}
+
+#[test]
+fn test_registries_with_unknown_formats_are_rejected() {
+    let old = registry("Point:\n  STRUCT:\n    - x: U32\n");
+    let mut new = Registry::new();
+    new.insert(
+        "Point".to_string(),
+        ContainerFormat::Enum(
+            vec![(0, Named::new("Origin", VariantFormat::unknown()))]
+                .into_iter()
+                .collect(),
+        ),
+    );
+    let error = check_compatibility(&old, &new, Encoding::Bcs).unwrap_err();
+    assert!(matches!(
+        error,
+        Error::Registry(serde_reflection::Error::UnknownFormatInContainer(name)) if name == "Point"
+    ));
+}
+
+#[test]
+fn test_removing_deprecated_definitions_is_not_a_violation() {
+    let old = registry(
+        "Point:\n  STRUCT:\n    - x: U32\n    - z: U8\nLegacy: UNITSTRUCT\nShape:\n  ENUM:\n    0:\n      Circle: UNIT\n    1:\n      Square: UNIT\n",
+    );
+    let new =
+        registry("Point:\n  STRUCT:\n    - x: U32\nShape:\n  ENUM:\n    0:\n      Circle: UNIT\n");
+    let deprecations: Deprecations = vec![
+        vec!["a", "b", "Legacy"],
+        vec!["a", "b", "Point", "z"],
+        vec!["a", "b", "Shape", "Square"],
+    ]
+    .into_iter()
+    .map(|path| {
+        let path = path.into_iter().map(String::from).collect();
+        (path, "Unused.".to_string())
+    })
+    .collect();
+    let config =
+        CodeGeneratorConfig::new("a.b".to_string()).with_deprecations(deprecations.clone());
+
+    let report = check_compatibility(&old, &new, Encoding::Bcs).unwrap();
+    assert_eq!(report.violations().len(), 3);
+
+    let report = check_compatibility_with_config(&old, &new, Encoding::Bcs, &config).unwrap();
+    assert!(report.is_compatible());
+    assert_eq!(report.changes.len(), 3);
+    assert!(report
+        .changes
+        .iter()
+        .all(|change| change.compatibility == Compatibility::DeprecatedRemoval));
+
+    // Other modules are not affected.
+    let config = CodeGeneratorConfig::new("a".to_string()).with_deprecations(deprecations);
+    let report = check_compatibility_with_config(&old, &new, Encoding::Bcs, &config).unwrap();
+    assert_eq!(report.violations().len(), 3);
+}
@@ -1,7 +1,7 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_generate::{cpp, test_utils, CodeGeneratorConfig, Encoding};
+use serde_generate::{cpp, test_utils, CodeGeneratorConfig, Encoding, Error};
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Write;
@@ -51,6 +51,38 @@ fn test_that_cpp_code_compiles() {
     test_that_cpp_code_compiles_with_config(&config);
 }
 
+#[test]
+fn test_that_cpp_code_compiles_as_single_header() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let header_path = dir.path().join("test.hpp");
+    let mut header = File::create(&header_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bincode, Encoding::Bcs]);
+    let generator = cpp::CodeGenerator::new(&config).with_single_header(true);
+    generator.output(&mut header, &registry).unwrap();
+
+    let content = std::fs::read_to_string(&header_path).unwrap();
+    assert!(!content.contains("#include \""));
+    assert_eq!(content.matches("#pragma once").count(), 1);
+
+    let source_path = dir.path().join("test.cpp");
+    let mut source = File::create(&source_path).unwrap();
+    writeln!(source, "#include \"test.hpp\"").unwrap();
+
+    // The runtime headers are not needed.
+    let status = Command::new("clang++")
+        .arg("--std=c++17")
+        .arg("-c")
+        .arg("-o")
+        .arg(dir.path().join("test.o"))
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_that_cpp_code_compiles_with_bcs() {
     let config =
@@ -62,7 +94,24 @@ fn test_that_cpp_code_compiles_with_bcs() {
 fn test_that_cpp_code_compiles_with_bincode() {
     let config =
         CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bincode]);
-    test_that_cpp_code_compiles_with_config(&config);
+    let (_dir, header_path) = test_that_cpp_code_compiles_with_config(&config);
+
+    // Platform requirements are checked at compile time.
+    let content = std::fs::read_to_string(&header_path).unwrap();
+    assert!(content.contains("static_assert(CHAR_BIT == 8,"));
+    assert!(content.contains("static_assert(sizeof(size_t) >= sizeof(uint32_t),"));
+}
+
+#[test]
+fn test_that_cpp_code_compiles_with_file_decoding() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_file_decoding(true);
+    let (_dir, header_path) = test_that_cpp_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(&header_path).unwrap();
+    assert!(content.contains("#include \"mmap.hpp\""));
+    assert!(content.contains("static SerdeData bcsDeserializeFile(const std::string &path);"));
 }
 
 #[test]
@@ -232,3 +281,114 @@ int main() {{
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_that_cpp_code_rejects_c_style_enums() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_c_style_enums(true);
+    let mut source = Vec::new();
+    let error = cpp::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
+
+#[test]
+fn test_that_cpp_code_compiles_with_free_functions() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let header_path = dir.path().join("test.hpp");
+    let mut header = File::create(&header_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bincode, Encoding::Bcs]);
+    cpp::CodeGenerator::new(&config)
+        .with_free_functions(true)
+        .output(&mut header, &registry)
+        .unwrap();
+
+    let content = std::fs::read_to_string(&header_path).unwrap();
+    assert!(
+        content.contains("void serialize(const testing::SerdeData &obj, Serializer &serializer)")
+    );
+    assert!(content.contains("bcsDeserializeSerdeData"));
+    assert!(!content.contains("struct Serializable<testing::SerdeData>"));
+
+    let source_path = dir.path().join("test.cpp");
+    let mut source = File::create(&source_path).unwrap();
+    writeln!(
+        source,
+        r#"
+#include "bincode.hpp"
+#include "bcs.hpp"
+#include "test.hpp"
+"#
+    )
+    .unwrap();
+
+    let status = Command::new("clang++")
+        .arg("--std=c++17")
+        .arg("-c")
+        .arg("-o")
+        .arg(dir.path().join("test.o"))
+        .arg("-I")
+        .arg("runtime/cpp")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_cpp_free_functions_reject_checksums() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_checksums(true);
+    let mut source = Vec::new();
+    let error = cpp::CodeGenerator::new(&config)
+        .with_free_functions(true)
+        .output(&mut source, &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
+
+#[test]
+fn test_that_cpp_code_marks_deprecated_definitions() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Config:\n  STRUCT:\n    - legacy: BOOL\nChoice:\n  ENUM:\n    0:\n      Old: UNIT\n    1:\n      New: UNIT\n",
+    )
+    .unwrap();
+    let deprecations = vec![
+        (
+            vec!["testing".to_string(), "Config".to_string()],
+            "Use Settings instead.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Config".to_string(),
+                "legacy".to_string(),
+            ],
+            "No longer read.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Choice".to_string(),
+                "Old".to_string(),
+            ],
+            "Use New.".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_deprecations(deprecations);
+
+    let mut source = Vec::new();
+    cpp::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("struct [[deprecated(\"Use Settings instead.\")]] Config {"));
+    assert!(content.contains("[[deprecated(\"No longer read.\")]] bool legacy;"));
+    assert!(content.contains("struct [[deprecated(\"Use New.\")]] Old {"));
+}
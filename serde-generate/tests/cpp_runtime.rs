@@ -4,7 +4,7 @@
 use serde_generate::{
     cpp, test_utils,
     test_utils::{Choice, Runtime, Test},
-    CodeGeneratorConfig,
+    AccessorConvention, CodeGeneratorConfig,
 };
 use std::fs::File;
 use std::io::Write;
@@ -96,6 +96,223 @@ int main() {{
     assert!(status.success());
 }
 
+#[test]
+fn test_cpp_bcs_runtime_on_hand_written_types() {
+    test_cpp_runtime_on_hand_written_types(Runtime::Bcs);
+}
+
+#[test]
+fn test_cpp_bincode_runtime_on_hand_written_types() {
+    test_cpp_runtime_on_hand_written_types(Runtime::Bincode);
+}
+
+fn test_cpp_runtime_on_hand_written_types(runtime: Runtime) {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let header_path = dir.path().join("test.hpp");
+    let mut header = File::create(&header_path).unwrap();
+
+    // "Choice" is maintained by hand in the namespace "pkg".
+    let definitions = vec![("pkg".to_string(), vec!["Choice".to_string()])]
+        .into_iter()
+        .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_external_definitions(definitions);
+    let generator = cpp::CodeGenerator::new(&config).with_free_functions(true);
+    generator.output(&mut header, &registry).unwrap();
+
+    let content = std::fs::read_to_string(&header_path).unwrap();
+    assert!(!content.contains("struct Choice"));
+    assert!(!content.contains("template <>"));
+
+    let reference = runtime.serialize(&Test {
+        a: vec![4, 6],
+        b: (-3, 5),
+        c: Choice::C { x: 7 },
+    });
+
+    let source_path = dir.path().join("test.cpp");
+    let mut source = File::create(&source_path).unwrap();
+    writeln!(
+        source,
+        r#"
+#include <cassert>
+#include <cstdint>
+#include <variant>
+
+namespace pkg {{
+
+struct Choice {{
+    struct A {{}};
+    struct B {{ uint64_t value; }};
+    struct C {{ uint8_t x; }};
+    std::variant<A, B, C> value;
+}};
+
+inline bool operator==(const Choice &lhs, const Choice &rhs) {{
+    return lhs.value.index() == rhs.value.index()
+        && (lhs.value.index() != 2 || std::get<2>(lhs.value).x == std::get<2>(rhs.value).x);
+}}
+
+}} // end of namespace pkg
+
+#include "test.hpp"
+
+using namespace testing;
+
+int main() {{
+    std::vector<uint8_t> input = {{{0}}};
+    auto value = {1}DeserializeTest(input);
+
+    auto a = std::vector<uint32_t> {{4, 6}};
+    auto b = std::tuple<int64_t, uint64_t> {{-3, 5}};
+    auto c = pkg::Choice {{ pkg::Choice::C {{ 7 }} }};
+    auto value2 = Test {{a, b, c}};
+
+    assert(value == value2);
+    assert({1}SerializeTest(value2) == input);
+    assert({1}DeserializeChoice({1}SerializeChoice(c)) == c);
+    return 0;
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("0x{:02x}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        runtime.name(),
+    )
+    .unwrap();
+
+    let status = Command::new("clang++")
+        .arg("--std=c++17")
+        .arg("-o")
+        .arg(dir.path().join("test"))
+        .arg("-I")
+        .arg("runtime/cpp")
+        .arg(source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new(dir.path().join("test")).status().unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_cpp_bcs_runtime_on_hand_written_types_with_accessors() {
+    test_cpp_runtime_on_hand_written_types_with_accessors(Runtime::Bcs);
+}
+
+#[test]
+fn test_cpp_bincode_runtime_on_hand_written_types_with_accessors() {
+    test_cpp_runtime_on_hand_written_types_with_accessors(Runtime::Bincode);
+}
+
+fn test_cpp_runtime_on_hand_written_types_with_accessors(runtime: Runtime) {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let header_path = dir.path().join("test.hpp");
+    let mut header = File::create(&header_path).unwrap();
+
+    // "Test" stands for an existing class with private fields.
+    let hand_written_types = vec![("Test".to_string(), "app::Record".to_string())]
+        .into_iter()
+        .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_hand_written_types(hand_written_types)
+        .with_accessor_convention(AccessorConvention::Methods);
+    let generator = cpp::CodeGenerator::new(&config);
+    generator.output(&mut header, &registry).unwrap();
+
+    let content = std::fs::read_to_string(&header_path).unwrap();
+    assert!(!content.contains("struct Test"));
+    assert!(content.contains("struct Choice"));
+    assert!(content.contains("obj.set_a("));
+
+    let reference = runtime.serialize(&Test {
+        a: vec![4, 6],
+        b: (-3, 5),
+        c: Choice::C { x: 7 },
+    });
+
+    let source_path = dir.path().join("test.cpp");
+    let mut source = File::create(&source_path).unwrap();
+    writeln!(
+        source,
+        r#"
+#include <cassert>
+#include <cstdint>
+#include <memory>
+#include <tuple>
+#include <vector>
+
+namespace testing {{ struct Choice; }}
+
+namespace app {{
+
+class Record {{
+  public:
+    const std::vector<uint32_t> &a() const {{ return a_; }}
+    void set_a(std::vector<uint32_t> a) {{ a_ = std::move(a); }}
+    const std::tuple<int64_t, uint64_t> &b() const {{ return b_; }}
+    void set_b(std::tuple<int64_t, uint64_t> b) {{ b_ = std::move(b); }}
+    const testing::Choice &c() const {{ return *c_; }}
+    void set_c(testing::Choice c);
+
+  private:
+    std::vector<uint32_t> a_;
+    std::tuple<int64_t, uint64_t> b_;
+    std::shared_ptr<testing::Choice> c_;
+}};
+
+}} // end of namespace app
+
+#include "test.hpp"
+
+void app::Record::set_c(testing::Choice c) {{
+    c_ = std::make_shared<testing::Choice>(std::move(c));
+}}
+
+using namespace testing;
+
+int main() {{
+    std::vector<uint8_t> input = {{{0}}};
+    auto value = {1}DeserializeTest(input);
+
+    assert(value.a() == (std::vector<uint32_t> {{4, 6}}));
+    assert(value.b() == (std::tuple<int64_t, uint64_t> {{-3, 5}}));
+    assert(value.c() == (Choice {{ Choice::C {{ 7 }} }}));
+    assert({1}SerializeTest(value) == input);
+    return 0;
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("0x{:02x}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        runtime.name(),
+    )
+    .unwrap();
+
+    let status = Command::new("clang++")
+        .arg("--std=c++17")
+        .arg("-o")
+        .arg(dir.path().join("test"))
+        .arg("-I")
+        .arg("runtime/cpp")
+        .arg(source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new(dir.path().join("test")).status().unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_cpp_bcs_runtime_on_supported_types() {
     test_cpp_runtime_on_supported_types(Runtime::Bcs);
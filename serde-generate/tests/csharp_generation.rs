@@ -24,8 +24,10 @@ fn test_that_csharp_code_compiles_with_config(
     let installer = csharp::Installer::new(dir_path.clone());
     installer.install_module(&config, &registry).unwrap();
     installer.install_serde_runtime().unwrap();
-    installer.install_bincode_runtime().unwrap();
-    installer.install_bcs_runtime().unwrap();
+    installer
+        .install_encoding_runtime(Encoding::Bincode)
+        .unwrap();
+    installer.install_encoding_runtime(Encoding::Bcs).unwrap();
 
     let proj_path = dir_path.join(config.module_name().replace(".", "/"));
     {
@@ -115,3 +117,20 @@ fn test_csharp_code_with_external_definitions() {
     let content = std::fs::read_to_string(dir.path().join("Generated/SerdeData.cs")).unwrap();
     assert!(content.contains("foo.TraitHelpers."));
 }
+
+#[test]
+fn test_that_csharp_code_uses_native_sets() {
+    let registry: serde_reflection::Registry =
+        serde_yaml::from_str("Tags:\n  STRUCT:\n    - names:\n        SET: STR\n").unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("Generated".to_string());
+    csharp::CodeGenerator::new(&config)
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let content = std::fs::read_to_string(dir.path().join("Generated/Tags.cs")).unwrap();
+    assert!(content.contains("public Serde.ValueSet<string> names;"));
+    let content = std::fs::read_to_string(dir.path().join("Generated/TraitHelpers.cs")).unwrap();
+    assert!(content.contains("serializer.sort_map_entries(offsets);"));
+    assert!(content.contains("return new Serde.ValueSet<string>(obj);"));
+}
@@ -135,10 +135,7 @@ fn test_csharp_runtime_on_simple_data(dir: PathBuf, runtime: Runtime) {
 
     let installer = csharp::Installer::new(dir);
     installer.install_serde_runtime().unwrap();
-    match runtime {
-        Runtime::Bincode => installer.install_bincode_runtime().unwrap(),
-        Runtime::Bcs => installer.install_bcs_runtime().unwrap(),
-    }
+    installer.install_encoding_runtime(runtime.into()).unwrap();
     installer.install_module(&config, &registry).unwrap();
 
     let reference = runtime.serialize(&Test {
@@ -228,10 +225,7 @@ fn test_csharp_runtime_on_supported_types(dir: PathBuf, runtime: Runtime) {
 
     let installer = csharp::Installer::new(dir);
     installer.install_serde_runtime().unwrap();
-    match runtime {
-        Runtime::Bincode => installer.install_bincode_runtime().unwrap(),
-        Runtime::Bcs => installer.install_bcs_runtime().unwrap(),
-    }
+    installer.install_encoding_runtime(runtime.into()).unwrap();
     installer.install_module(&config, &registry).unwrap();
 
     let positive_encodings = runtime
@@ -0,0 +1,115 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{
+    arrow, cpp, csharp, dart, golang, java, kaitai, python3, rust, sql, typescript, wireshark,
+    CodeGeneratorConfig, Error, SourceInstaller,
+};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tempfile::tempdir;
+
+/// Registries that no generator supports, with the container and field at fault.
+fn unsupported_registries() -> Vec<(Registry, &'static str, &'static str)> {
+    let known_variable = Format::unknown();
+    if let Format::Variable(variable) = &known_variable {
+        *variable.borrow_mut() = Some(Format::U8);
+    }
+    let fields = vec![
+        Format::unknown(),
+        known_variable,
+        Format::Option(Box::new(Format::Skipped(Box::new(Format::Bytes)))),
+        Format::Decimal {
+            mantissa: Box::new(Format::U64),
+            scale: 2,
+        },
+    ];
+    let mut registries: Vec<_> = fields
+        .into_iter()
+        .map(|format| {
+            let mut registry = Registry::new();
+            registry.insert(
+                "Session".to_string(),
+                ContainerFormat::Struct(vec![
+                    Named::new("user", Format::Str),
+                    Named::new("value", format),
+                ]),
+            );
+            (registry, "Session", "value")
+        })
+        .collect();
+
+    let mut registry = Registry::new();
+    let mut variants = BTreeMap::new();
+    variants.insert(0, Named::new("Ping", VariantFormat::Unit));
+    variants.insert(1, Named::new("Data", VariantFormat::unknown()));
+    registry.insert("Message".to_string(), ContainerFormat::Enum(variants));
+    registries.push((registry, "Message", "Data"));
+    registries
+}
+
+fn test_that_installed_modules_reject_unsupported_formats<I>(
+    language: &str,
+    installer: impl Fn(&Path) -> I,
+) where
+    I: SourceInstaller<Error = Error>,
+{
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    for (registry, expected_container, expected_field) in unsupported_registries() {
+        let dir = tempdir().unwrap();
+        match installer(dir.path()).install_module(&config, &registry) {
+            Err(Error::UnsupportedFormat {
+                language: actual_language,
+                container,
+                field,
+                ..
+            }) => {
+                assert_eq!(actual_language, language);
+                assert_eq!(container, expected_container);
+                assert_eq!(field.as_deref(), Some(expected_field));
+            }
+            result => panic!("{}: unexpected result {:?}", language, result),
+        }
+    }
+}
+
+#[test]
+fn test_that_all_languages_reject_unsupported_formats() {
+    test_that_installed_modules_reject_unsupported_formats("Arrow", |dir| {
+        arrow::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("C++", |dir| {
+        cpp::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("C#", |dir| {
+        csharp::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("Dart", |dir| {
+        dart::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("Go", |dir| {
+        golang::Installer::new(dir.to_path_buf(), None)
+    });
+    test_that_installed_modules_reject_unsupported_formats("Java", |dir| {
+        java::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("Kaitai", |dir| {
+        kaitai::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("Python 3", |dir| {
+        python3::Installer::new(dir.to_path_buf(), None)
+    });
+    test_that_installed_modules_reject_unsupported_formats("Rust", |dir| {
+        rust::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("SQL", |dir| {
+        sql::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("TypeScript", |dir| {
+        typescript::Installer::new(dir.to_path_buf())
+    });
+    test_that_installed_modules_reject_unsupported_formats("Wireshark", |dir| {
+        wireshark::Installer::new(dir.to_path_buf())
+    });
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use serde::{Deserialize, Serialize};
-use serde_generate::{golang, test_utils, CodeGeneratorConfig, Encoding};
+use serde_generate::{golang, test_utils, CodeGeneratorConfig, Encoding, Error};
 use serde_reflection::{Registry, Result, Samples, Tracer, TracerConfig};
 use std::collections::BTreeMap;
 use std::fs::File;
@@ -188,3 +188,71 @@ fn test_that_golang_code_compiles_with_custom_code() {
     assert!(content.contains("// custom1"));
     assert!(content.contains("// custom2"));
 }
+
+#[test]
+fn test_that_golang_code_marks_deprecated_definitions() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Config:\n  STRUCT:\n    - legacy: BOOL\nChoice:\n  ENUM:\n    0:\n      Old: UNIT\n    1:\n      New: UNIT\n",
+    )
+    .unwrap();
+    let deprecations = vec![
+        (
+            vec!["testing".to_string(), "Config".to_string()],
+            "Use Settings instead.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Config".to_string(),
+                "legacy".to_string(),
+            ],
+            "No longer read.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Choice".to_string(),
+                "Old".to_string(),
+            ],
+            "Use New.".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_deprecations(deprecations);
+
+    let mut source = Vec::new();
+    golang::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("// Deprecated: Use Settings instead.\ntype Config struct {"));
+    assert!(content.contains("\t// Deprecated: No longer read.\n\tLegacy bool\n"));
+    assert!(content.contains("// Deprecated: Use New.\ntype Choice__Old struct {"));
+}
+
+#[test]
+fn test_that_golang_code_rejects_canonical_equality() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_canonical_equality(true);
+    let error = golang::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
+
+#[test]
+fn test_that_golang_code_rejects_hand_written_types() {
+    let registry = test_utils::get_registry().unwrap();
+    let hand_written_types = vec![("Struct".to_string(), "bank.Struct".to_string())]
+        .into_iter()
+        .collect();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_hand_written_types(hand_written_types);
+    let error = golang::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
@@ -0,0 +1,315 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{
+    csharp, dart,
+    install::{read_manifest, FileAction, OverwritePolicy, PlannedFile},
+    java, python3, rust, test_utils, CodeGeneratorConfig, Encoding, Error, RuntimeSource,
+    SourceInstaller,
+};
+use serde_reflection::Registry;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn install_module<'a>(
+    module_name: &str,
+    registry: &'a Registry,
+) -> impl Fn(&rust::Installer) -> serde_generate::Result<()> + 'a {
+    let config = CodeGeneratorConfig::new(module_name.to_string());
+    move |installer| installer.install_module(&config, registry)
+}
+
+fn actions(files: &[PlannedFile]) -> Vec<(String, FileAction)> {
+    files
+        .iter()
+        .map(|file| (file.path.to_string_lossy().replace('\\', "/"), file.action))
+        .collect()
+}
+
+#[test]
+fn test_plan_and_install() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let installer = rust::Installer::new(dir.path().to_path_buf());
+
+    // Planning does not write anything.
+    let plan = installer
+        .plan(install_module("testing", &registry))
+        .unwrap();
+    assert_eq!(
+        actions(&plan),
+        vec![
+            ("testing/Cargo.toml".to_string(), FileAction::Create),
+            ("testing/src/lib.rs".to_string(), FileAction::Create),
+        ]
+    );
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    let installed = installer
+        .install(install_module("testing", &registry), OverwritePolicy::Fail)
+        .unwrap();
+    assert_eq!(installed, plan);
+    assert!(dir.path().join("testing/src/lib.rs").is_file());
+    assert_eq!(
+        read_manifest(dir.path()).unwrap(),
+        vec![
+            PathBuf::from("testing/Cargo.toml"),
+            PathBuf::from("testing/src/lib.rs")
+        ]
+        .into_iter()
+        .collect()
+    );
+
+    // Unchanged files are left alone.
+    let plan = installer
+        .plan(install_module("testing", &registry))
+        .unwrap();
+    assert!(plan.iter().all(|file| file.action == FileAction::Unchanged));
+}
+
+#[test]
+fn test_overwrite_policies() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let installer = rust::Installer::new(dir.path().to_path_buf());
+    installer
+        .install(install_module("testing", &registry), OverwritePolicy::Fail)
+        .unwrap();
+    let lib_path = dir.path().join("testing/src/lib.rs");
+    std::fs::write(&lib_path, "// edited").unwrap();
+
+    let error = installer
+        .install(install_module("testing", &registry), OverwritePolicy::Fail)
+        .unwrap_err();
+    assert!(matches!(error, Error::Io(error) if error.kind() == std::io::ErrorKind::AlreadyExists));
+    assert_eq!(std::fs::read_to_string(&lib_path).unwrap(), "// edited");
+
+    let installed = installer
+        .install(
+            install_module("testing", &registry),
+            OverwritePolicy::KeepExisting,
+        )
+        .unwrap();
+    assert_eq!(
+        actions(&installed),
+        vec![
+            ("testing/Cargo.toml".to_string(), FileAction::Unchanged),
+            ("testing/src/lib.rs".to_string(), FileAction::Keep),
+        ]
+    );
+    assert_eq!(std::fs::read_to_string(&lib_path).unwrap(), "// edited");
+
+    let installed = installer
+        .install(
+            install_module("testing", &registry),
+            OverwritePolicy::Overwrite,
+        )
+        .unwrap();
+    assert_eq!(installed[1].action, FileAction::Update);
+    assert_ne!(std::fs::read_to_string(&lib_path).unwrap(), "// edited");
+}
+
+#[test]
+fn test_stale_files_and_uninstall() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let installer = rust::Installer::new(dir.path().to_path_buf());
+    installer
+        .install(install_module("first", &registry), OverwritePolicy::Fail)
+        .unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "not generated").unwrap();
+
+    // Files of the previous installation that are no longer generated are removed.
+    let installed = installer
+        .install(install_module("second", &registry), OverwritePolicy::Fail)
+        .unwrap();
+    assert_eq!(
+        actions(&installed),
+        vec![
+            ("second/Cargo.toml".to_string(), FileAction::Create),
+            ("second/src/lib.rs".to_string(), FileAction::Create),
+            ("first/Cargo.toml".to_string(), FileAction::Remove),
+            ("first/src/lib.rs".to_string(), FileAction::Remove),
+        ]
+    );
+    assert!(!dir.path().join("first").exists());
+
+    let removed = installer.uninstall().unwrap();
+    assert_eq!(
+        removed,
+        vec![
+            PathBuf::from("second/Cargo.toml"),
+            PathBuf::from("second/src/lib.rs")
+        ]
+    );
+    let remaining = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(remaining, vec!["notes.txt"]);
+}
+
+fn runtime_package() -> RuntimeSource {
+    RuntimeSource::Package {
+        name: "serde_runtimes".to_string(),
+        version: "0.1.0".to_string(),
+    }
+}
+
+#[test]
+fn test_dart_runtime_package() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let installer =
+        dart::Installer::new(dir.path().to_path_buf()).with_runtime_source(runtime_package());
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+    installer.install_encoding_runtimes(&config).unwrap();
+
+    let pubspec = std::fs::read_to_string(dir.path().join("pubspec.yaml")).unwrap();
+    assert!(pubspec.contains("\n  serde_runtimes: '0.1.0'\n"));
+    let library = std::fs::read_to_string(dir.path().join("lib/testing/testing.dart")).unwrap();
+    assert!(library.contains("import 'package:serde_runtimes/serde/serde.dart';"));
+    let extensions =
+        std::fs::read_to_string(dir.path().join("lib/testing/testing_bcs.dart")).unwrap();
+    assert!(extensions.contains("import 'package:serde_runtimes/bcs/bcs.dart';"));
+    let tests = std::fs::read_to_string(dir.path().join("test/all_test.dart")).unwrap();
+    assert!(tests.contains("import 'package:serde_runtimes/bcs/bcs.dart';"));
+    assert!(!dir.path().join("lib/serde").exists());
+    assert!(!dir.path().join("lib/bcs").exists());
+}
+
+#[test]
+fn test_dart_web_support() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let installer = dart::Installer::new(dir.path().to_path_buf()).with_web_support(true);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+
+    let primitives =
+        std::fs::read_to_string(dir.path().join("lib/testing/PrimitiveTypes.dart")).unwrap();
+    assert!(primitives.contains("  BigInt f_u64;"));
+    assert!(primitives.contains("serializer.serialize_u64_bigint(f_u64);"));
+    assert!(primitives.contains("deserializer.deserialize_i64_bigint()"));
+    assert!(primitives.contains("TraitHelpers.from_json_u64(json['f_u64'])"));
+    let runtime = std::fs::read_to_string(dir.path().join("lib/serde/serde.dart")).unwrap();
+    assert!(runtime.contains("if (dart.library.html) 'int64_web.dart'"));
+    assert!(dir.path().join("lib/serde/int64_web.dart").is_file());
+}
+
+#[test]
+fn test_dart_comments() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let comments = vec![
+        (vec!["testing", "SerdeData"], "Some\n\ncomments"),
+        (vec!["testing", "SerdeData", "PrimitiveTypes"], "A variant."),
+        (vec!["testing", "PrimitiveTypes", "f_u64"], "A field."),
+        (
+            vec!["testing", "SerdeData", "StructVariant", "f0"],
+            "A variant field.",
+        ),
+    ]
+    .into_iter()
+    .map(|(path, comment)| {
+        let path = path.into_iter().map(String::from).collect();
+        (path, comment.to_string())
+    })
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_comments(comments);
+    let installer = dart::Installer::new(dir.path().to_path_buf());
+    installer.install_module(&config, &registry).unwrap();
+
+    let serde_data =
+        std::fs::read_to_string(dir.path().join("lib/testing/SerdeData.dart")).unwrap();
+    assert!(serde_data.contains("/// Some\n///\n/// comments\nabstract class SerdeData {"));
+    assert!(serde_data
+        .contains("/// A variant.\nclass SerdeDataPrimitiveTypesItem extends SerdeData {"));
+    let primitives =
+        std::fs::read_to_string(dir.path().join("lib/testing/PrimitiveTypes.dart")).unwrap();
+    assert!(primitives.contains("  /// A field.\n  int f_u64;"));
+    assert!(serde_data.contains("  /// A variant field.\n  UnitStruct f0;"));
+}
+
+#[test]
+fn test_csharp_runtime_package() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config =
+        CodeGeneratorConfig::new("Testing".to_string()).with_encodings(vec![Encoding::Bincode]);
+    let installer =
+        csharp::Installer::new(dir.path().to_path_buf()).with_runtime_source(runtime_package());
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+    installer.install_encoding_runtimes(&config).unwrap();
+
+    let project = std::fs::read_to_string(dir.path().join("Testing/Testing.csproj")).unwrap();
+    assert!(project.contains(r#"<PackageReference Include="serde_runtimes" Version="0.1.0" />"#));
+    assert!(!project.contains("ProjectReference"));
+    assert!(!dir.path().join("Serde").exists());
+    assert!(!dir.path().join("Bincode").exists());
+}
+
+#[test]
+fn test_runtime_tests_are_installed_on_demand() {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+
+    let dir = tempdir().unwrap();
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None);
+    installer.install_serde_runtime().unwrap();
+    installer.install_encoding_runtimes(&config).unwrap();
+    assert!(!dir.path().join("bcs/test_bcs.py").exists());
+    installer.install_runtime_tests(&config).unwrap();
+    assert!(dir.path().join("serde_types/test_serde_types.py").is_file());
+    assert!(dir.path().join("bcs/test_bcs.py").is_file());
+    assert!(!dir.path().join("bincode").exists());
+
+    let dir = tempdir().unwrap();
+    let installer = java::Installer::new(dir.path().to_path_buf());
+    installer.install_encoding_runtimes(&config).unwrap();
+    assert!(dir.path().join("com/novi/bcs/BcsSerializer.java").is_file());
+    assert!(!dir.path().join("com/novi/bcs/BcsTest.java").exists());
+    installer.install_runtime_tests(&config).unwrap();
+    assert!(dir.path().join("com/novi/bcs/BcsTest.java").is_file());
+
+    let dir = tempdir().unwrap();
+    let installer = csharp::Installer::new(dir.path().to_path_buf());
+    installer.install_runtime_tests(&config).unwrap();
+    assert!(dir.path().join("Serde.Tests/TestBcs.cs").is_file());
+    let project =
+        std::fs::read_to_string(dir.path().join("Serde.Tests/Serde.Tests.csproj")).unwrap();
+    assert!(project.contains(r#"<ProjectReference Include="..\Bcs\Bcs.csproj" />"#));
+    assert!(!project.contains("Bincode"));
+}
+
+#[test]
+fn test_corpus_runner_is_installed_on_demand() {
+    let config =
+        CodeGeneratorConfig::new("serde_corpus".to_string()).with_encodings(vec![Encoding::Bcs]);
+
+    let dir = tempdir().unwrap();
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None);
+    installer.install_corpus_runner(&config).unwrap();
+    assert!(dir.path().join("serde_corpus/__init__.py").is_file());
+    assert!(dir.path().join("test_serde_corpus.py").is_file());
+    let vectors = std::fs::read_to_string(dir.path().join("serde_corpus.tsv")).unwrap();
+    assert!(vectors.contains("Lengths\tbcs\t"));
+    assert!(!vectors.contains("\tbincode\t"));
+
+    let dir = tempdir().unwrap();
+    let installer = java::Installer::new(dir.path().to_path_buf());
+    installer.install_corpus_runner(&config).unwrap();
+    assert!(dir.path().join("serde_corpus/Lengths.java").is_file());
+    assert!(dir.path().join("serde_corpus/corpus.tsv").is_file());
+    let runner = std::fs::read_to_string(dir.path().join("serde_corpus/CorpusTest.java")).unwrap();
+    assert!(runner.contains("case \"bcs:Table\""));
+    assert!(!runner.contains("bincodeDeserialize"));
+}
@@ -10,11 +10,18 @@ fn test_that_java_code_compiles_with_config(
     config: &CodeGeneratorConfig,
 ) -> (TempDir, std::path::PathBuf) {
     let registry = test_utils::get_registry().unwrap();
+    test_that_java_code_compiles_with_registry(config, &registry)
+}
+
+fn test_that_java_code_compiles_with_registry(
+    config: &CodeGeneratorConfig,
+    registry: &serde_reflection::Registry,
+) -> (TempDir, std::path::PathBuf) {
     let dir = tempdir().unwrap();
 
     let generator = java::CodeGenerator::new(&config);
     generator
-        .write_source_files(dir.path().to_path_buf(), &registry)
+        .write_source_files(dir.path().to_path_buf(), registry)
         .unwrap();
 
     let paths = std::iter::empty()
@@ -86,6 +93,34 @@ fn test_that_java_code_compiles_with_comments() {
     ));
 }
 
+#[test]
+fn test_that_java_code_compiles_with_thread_safety_notes() {
+    let comments = vec![(
+        vec!["testing".to_string(), "SerdeData".to_string()],
+        "Some comments".to_string(),
+    )]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_comments(comments)
+        .with_thread_safety_notes(true);
+
+    let (_dir, path) = test_that_java_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
+    assert!(content.contains(
+        r#"
+/**
+ * Some comments
+ *
+ * Thread safety: instances are immutable"#
+    ));
+    // Variant classes are documented by their enum.
+    assert_eq!(content.matches("Thread safety").count(), 1);
+    let content = std::fs::read_to_string(path.join("Struct.java")).unwrap();
+    assert!(content.contains("/**\n * Thread safety: instances are immutable"));
+}
+
 #[test]
 fn test_java_code_with_external_definitions() {
     let registry = test_utils::get_registry().unwrap();
@@ -107,6 +142,31 @@ fn test_java_code_with_external_definitions() {
     assert!(content.contains("foo.TraitHelpers."));
 }
 
+#[test]
+fn test_java_code_with_jackson_annotations() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = java::CodeGenerator::new(&config).with_jackson(true);
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let content = std::fs::read_to_string(dir.path().join("testing/SerdeData.java")).unwrap();
+    assert!(content
+        .contains("include = com.fasterxml.jackson.annotation.JsonTypeInfo.As.WRAPPER_OBJECT"));
+    assert!(content.contains(
+        "@com.fasterxml.jackson.annotation.JsonSubTypes.Type(value = SerdeData.PrimitiveTypes.class, name = \"PrimitiveTypes\")"
+    ));
+    let content = std::fs::read_to_string(dir.path().join("testing/Struct.java")).unwrap();
+    assert!(
+        content.contains("@com.fasterxml.jackson.annotation.JsonProperty(\"x\")\n    public final")
+    );
+    let content = std::fs::read_to_string(dir.path().join("testing/NewTypeStruct.java")).unwrap();
+    assert!(content.contains("@com.fasterxml.jackson.annotation.JsonValue"));
+}
+
 #[test]
 fn test_that_java_code_compiles_with_custom_code() {
     let comments = vec![(
@@ -123,3 +183,124 @@ fn test_that_java_code_compiles_with_custom_code() {
     let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
     assert!(content.contains("me()"));
 }
+
+#[test]
+fn test_that_java_code_compiles_with_immutable_collections() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_immutable_collections(true);
+    let (_dir, path) = test_that_java_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
+    assert!(content.contains("java.util.Collections.unmodifiableList("));
+    assert!(content.contains("java.util.Collections.unmodifiableMap("));
+}
+
+#[test]
+fn test_that_java_code_compiles_with_canonical_equality() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_canonical_equality(true);
+    let (_dir, path) = test_that_java_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(path.join("Struct.java")).unwrap();
+    assert!(content.contains("java.util.Arrays.equals(canonicalBytes(), other.canonicalBytes())"));
+    assert!(!content.contains("java.util.Objects.equals("));
+}
+
+#[test]
+fn test_that_java_code_compiles_with_integer_constraints() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Account:
+  STRUCT:
+    - id: {CUSTOM: {NAME: NonZeroU64, LAYOUT: U64}}
+    - delta: {CUSTOM: {NAME: NonZeroI128, LAYOUT: I128}}
+    - percent: U8
+"#,
+    )
+    .unwrap();
+    let mut bounds = BTreeMap::new();
+    bounds.insert(
+        vec![
+            "testing".to_string(),
+            "Account".to_string(),
+            "percent".to_string(),
+        ],
+        (Some(1), Some(100)),
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_integer_bounds(bounds);
+    let (_dir, path) = test_that_java_code_compiles_with_registry(&config, &registry);
+
+    let content = std::fs::read_to_string(path.join("Account.java")).unwrap();
+    assert!(content.contains(
+        "if (!(Long.compareUnsigned(id, 0L) != 0)) { throw new IllegalArgumentException(\"id must be nonzero\"); }"
+    ));
+    assert!(content.contains("delta.compareTo(new java.math.BigInteger(\"0\")) != 0"));
+    assert!(content.contains(
+        "if (!(Byte.toUnsignedInt(percent) >= 1 && Byte.toUnsignedInt(percent) <= 100)) { throw new IllegalArgumentException(\"percent must be between 1 and 100\"); }"
+    ));
+    assert!(content.contains(" * Range: 1..=100\n"));
+}
+
+#[test]
+fn test_that_java_code_compiles_with_decimal_formats() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Payment:
+  STRUCT:
+    - amount: {DECIMAL: {MANTISSA: I64, SCALE: 2}}
+    - total: {DECIMAL: {MANTISSA: I128, SCALE: 4}}
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, path) = test_that_java_code_compiles_with_registry(&config, &registry);
+
+    let content = std::fs::read_to_string(path.join("Payment.java")).unwrap();
+    assert!(content.contains("public final java.math.BigDecimal amount;"));
+    assert!(content.contains(
+        "serializer.serialize_i64(amount.setScale(2).unscaledValue().longValueExact());"
+    ));
+    assert!(content.contains(
+        "new java.math.BigDecimal(java.math.BigInteger.valueOf(deserializer.deserialize_i64()), 2)"
+    ));
+    assert!(content.contains("new java.math.BigDecimal(deserializer.deserialize_i128(), 4)"));
+}
+
+#[test]
+fn test_that_java_code_compiles_with_decimal_formats_next_to_their_mantissas() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Invoice:
+  STRUCT:
+    - discount: {OPTION: {DECIMAL: {MANTISSA: I64, SCALE: 2}}}
+    - quantity: {OPTION: I64}
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, path) = test_that_java_code_compiles_with_registry(&config, &registry);
+
+    let content = std::fs::read_to_string(path.join("TraitHelpers.java")).unwrap();
+    assert!(content.contains("serialize_option_decimal2_i64("));
+    assert!(content.contains("serialize_option_i64("));
+}
+
+#[test]
+fn test_that_java_code_compiles_with_native_sets() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Tags:
+  STRUCT:
+    - names: {SET: STR}
+    - ids: {OPTION: {SET: U64}}
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, path) = test_that_java_code_compiles_with_registry(&config, &registry);
+
+    let content = std::fs::read_to_string(path.join("Tags.java")).unwrap();
+    assert!(content.contains("public final java.util.Set<String> names;"));
+    let content = std::fs::read_to_string(path.join("TraitHelpers.java")).unwrap();
+    assert!(content.contains("static void serialize_set_str(java.util.Set<String> value,"));
+    assert!(content.contains("serializer.sort_map_entries(offsets);"));
+    assert!(content.contains("new java.util.LinkedHashSet<String>()"));
+}
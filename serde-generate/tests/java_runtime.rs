@@ -4,12 +4,12 @@
 use serde_generate::{
     java, test_utils,
     test_utils::{Choice, Runtime, Test},
-    CodeGeneratorConfig,
+    CodeGeneratorConfig, Encoding,
 };
 use std::fs::File;
 use std::io::Write;
 use std::process::Command;
-use tempfile::tempdir;
+use tempfile::{tempdir, TempDir};
 
 #[test]
 fn test_java_bcs_runtime_on_simple_data() {
@@ -143,12 +143,31 @@ fn quote_bytes(bytes: &[u8]) -> String {
 }
 
 fn test_java_runtime_on_supported_types(runtime: Runtime) {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    test_java_runtime_on_supported_types_with_config(runtime, &config);
+}
+
+#[test]
+fn test_java_bcs_runtime_on_supported_types_with_chunked_dispatch() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_max_switch_cases(Some(4));
+    let dir = test_java_runtime_on_supported_types_with_config(Runtime::Bcs, &config);
+
+    let content = std::fs::read_to_string(dir.path().join("testing/SerdeData.java")).unwrap();
+    assert!(content.contains("if ((value = deserializeVariants1(index, deserializer)) != null)"));
+    assert!(content.contains("private static SerdeData deserializeVariants1(int index,"));
+}
+
+fn test_java_runtime_on_supported_types_with_config(
+    runtime: Runtime,
+    config: &CodeGeneratorConfig,
+) -> TempDir {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
 
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = java::CodeGenerator::new(&config);
+    let generator = java::CodeGenerator::new(config);
     generator
         .write_source_files(dir.path().to_path_buf(), &registry)
         .unwrap();
@@ -259,6 +278,7 @@ public class Main {{
         .status()
         .unwrap();
     assert!(status.success());
+    dir
 }
 
 #[test]
@@ -286,3 +306,137 @@ fn test_java_bcs_runtime_autotest() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_java_bcs_runtime_on_network_addresses() {
+    use serde::{Deserialize, Serialize};
+    use serde_reflection::{Samples, Tracer, TracerConfig};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    #[derive(Serialize, Deserialize)]
+    struct Peer {
+        ip: IpAddr,
+        socket: SocketAddr,
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<Peer>(&Samples::new()).unwrap();
+    let registry = tracer.registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Runtime::Bcs.into()]);
+    let generator = java::CodeGenerator::new(&config);
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+    // Network addresses are not generated.
+    assert!(!dir.path().join("testing/IpAddr.java").exists());
+
+    let reference = Runtime::Bcs.serialize(&Peer {
+        ip: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+        socket: SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 8080),
+    });
+
+    let mut source = File::create(dir.path().join("Main.java")).unwrap();
+    writeln!(
+        source,
+        r#"
+import java.net.InetAddress;
+import java.net.InetSocketAddress;
+import testing.Peer;
+
+public class Main {{
+    public static void main(String[] args) throws java.lang.Exception {{
+        byte[] input = new byte[] {0};
+
+        Peer value = Peer.bcsDeserialize(input);
+        assert value.ip.equals(InetAddress.getByName("192.168.0.1"));
+        assert value.socket.equals(new InetSocketAddress(InetAddress.getByName("::1"), 8080));
+
+        byte[] output = value.bcsSerialize();
+        assert java.util.Arrays.equals(input, output);
+    }}
+}}
+"#,
+        quote_bytes(&reference),
+    )
+    .unwrap();
+
+    let paths = std::iter::empty()
+        .chain(std::fs::read_dir("runtime/java/com/novi/serde").unwrap())
+        .chain(std::fs::read_dir("runtime/java/com/novi/bcs").unwrap())
+        .chain(std::fs::read_dir(dir.path().join("testing")).unwrap())
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .arg(dir.path().join("Main.java"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-enableassertions")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("Main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_java_runtime_on_corpus() {
+    use serde_generate::SourceInstaller;
+
+    fn java_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                java_files(&path, files);
+            } else if path.extension() == Some("java".as_ref()) {
+                files.push(path);
+            }
+        }
+    }
+
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("serde_corpus".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode]);
+    let installer = java::Installer::new(dir.path().to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_encoding_runtimes(&config).unwrap();
+    installer.install_corpus_runner(&config).unwrap();
+
+    let mut paths = Vec::new();
+    java_files(dir.path(), &mut paths);
+    let status = Command::new("javac")
+        .arg("-d")
+        .arg(dir.path().join("classes"))
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-cp")
+        .arg(dir.path().join("classes"))
+        .arg("serde_corpus.CorpusTest")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -0,0 +1,62 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{kaitai, test_utils, CodeGeneratorConfig, Encoding, SourceInstaller};
+use tempfile::tempdir;
+
+#[test]
+fn test_that_kaitai_definitions_follow_bcs() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    kaitai::CodeGenerator::new(&config)
+        .with_root_type(Some("Test".to_string()))
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+
+    assert!(content.contains("meta:\n  id: testing_bcs\n"));
+    assert!(content.contains("seq:\n  - id: value\n    type: test\n"));
+    assert!(content.contains(
+        r#"
+  choice:
+    seq:
+      - id: variant_index
+        type: uleb128
+      - id: value
+        type:
+          switch-on: variant_index.value
+          cases:
+            1: choice__b
+            2: choice__c
+"#
+    ));
+    assert!(content.contains(
+        r#"
+  vector_u32:
+    seq:
+      - id: len
+        type: uleb128
+      - id: items
+        type: u4
+        repeat: expr
+        repeat-expr: len.value
+"#
+    ));
+    assert!(serde_yaml::from_str::<serde_yaml::Value>(&content).is_ok());
+}
+
+#[test]
+fn test_that_kaitai_definitions_are_installed_per_encoding() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bincode, Encoding::Bcs]);
+    let dir = tempdir().unwrap();
+    let installer = kaitai::Installer::new(dir.path().to_path_buf());
+    installer.install_module(&config, &registry).unwrap();
+
+    let content = std::fs::read_to_string(dir.path().join("testing_bincode.ksy")).unwrap();
+    assert!(content.contains("switch-on: variant_index\n"));
+    assert!(!content.contains("uleb128"));
+    assert!(dir.path().join("testing_bcs.ksy").exists());
+}
@@ -0,0 +1,49 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{lock, test_utils};
+use serde_reflection::{ContainerFormat, Format, Named, VariantFormat};
+
+#[test]
+fn test_unchanged_layout_is_accepted() {
+    let registry = test_utils::get_registry().unwrap();
+    assert!(lock::check_wire_layout(&registry, &registry).is_empty());
+}
+
+#[test]
+fn test_additions_are_accepted() {
+    let locked = test_utils::get_registry().unwrap();
+    let mut current = locked.clone();
+    current.insert("NewStruct".to_string(), ContainerFormat::UnitStruct);
+    if let Some(ContainerFormat::Enum(variants)) = current.get_mut("CStyleEnum") {
+        let index = variants.len() as u32;
+        variants.insert(index, Named::new("NewVariant", VariantFormat::Unit));
+    } else {
+        panic!("CStyleEnum should be an enum");
+    }
+    assert!(lock::check_wire_layout(&locked, &current).is_empty());
+}
+
+#[test]
+fn test_wire_changes_are_reported() {
+    let locked = test_utils::get_registry().unwrap();
+    let mut current = locked.clone();
+    current.remove("UnitStruct");
+    current.insert(
+        "NewTypeStruct".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::U32)),
+    );
+    if let Some(ContainerFormat::Struct(fields)) = current.get_mut("Struct") {
+        fields.reverse();
+    } else {
+        panic!("Struct should be a struct");
+    }
+
+    let changes = lock::check_wire_layout(&locked, &current)
+        .into_iter()
+        .map(|change| change.to_string())
+        .collect::<Vec<_>>();
+    assert!(changes.contains(&"UnitStruct: container was removed".to_string()));
+    assert!(changes.contains(&"NewTypeStruct: format changed from `U64` to `U32`".to_string()));
+    assert!(changes.contains(&"Struct: field #0 changed from `x` to `y`".to_string()));
+}
@@ -0,0 +1,167 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "lsp")]
+
+use serde_generate::lsp::{self, LanguageServer};
+use serde_yaml::Value;
+
+const REGISTRY: &str = r#"Foo:
+  STRUCT:
+    - x:
+        TYPENAME: Bar
+    - y:
+        TYPENAME: Baz
+Bar:
+  NEWTYPESTRUCT: U64
+"#;
+
+fn request(id: u64, method: &str, params: &str) -> String {
+    message(&format!(
+        r#"{{"jsonrpc": "2.0", "id": {}, "method": "{}", "params": {}}}"#,
+        id, method, params
+    ))
+}
+
+fn notification(method: &str, params: &str) -> String {
+    message(&format!(
+        r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}}}"#,
+        method, params
+    ))
+}
+
+fn message(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+#[test]
+fn test_language_server_session() {
+    let uri = r#"{"uri": "file:///test.yaml"}"#;
+    let position = |line, character| {
+        format!(
+            r#"{{"textDocument": {}, "position": {{"line": {}, "character": {}}}}}"#,
+            uri, line, character
+        )
+    };
+    let input = [
+        request(1, "initialize", "{}"),
+        notification("initialized", "{}"),
+        notification(
+            "textDocument/didOpen",
+            &format!(
+                r#"{{"textDocument": {{"uri": "file:///test.yaml", "text": {:?}}}}}"#,
+                REGISTRY
+            ),
+        ),
+        request(2, "textDocument/hover", &position(3, 20)),
+        request(3, "textDocument/definition", &position(3, 20)),
+        request(4, "textDocument/completion", &position(0, 0)),
+        request(5, "shutdown", "null"),
+        notification("exit", "null"),
+    ]
+    .concat();
+
+    let mut output = Vec::new();
+    LanguageServer::new()
+        .serve(input.as_bytes(), &mut output)
+        .unwrap();
+    let mut reader = output.as_slice();
+    let mut messages = Vec::new();
+    while let Some(message) = lsp::read_message(&mut reader).unwrap() {
+        messages.push(message);
+    }
+    assert_eq!(messages.len(), 6);
+
+    assert_eq!(
+        messages[0]["result"]["capabilities"]["hoverProvider"],
+        Value::from(true)
+    );
+
+    let diagnostics = messages[1]["params"]["diagnostics"].as_sequence().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0]["message"],
+        Value::from("Undefined container Baz")
+    );
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], Value::from(5u64));
+
+    let hover = messages[2]["result"]["contents"]["value"].as_str().unwrap();
+    assert!(hover.contains("NEWTYPESTRUCT: U64"));
+
+    assert_eq!(
+        messages[3]["result"]["range"]["start"]["line"],
+        Value::from(6u64)
+    );
+
+    let labels: Vec<_> = messages[4]["result"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(labels, vec!["Foo", "Bar"]);
+}
+
+#[test]
+fn test_yaml_errors_are_located() {
+    let diagnostics = lsp::diagnostics("Foo:\n  STRUCT: [\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].0 <= 2);
+}
+
+#[test]
+fn test_unsupported_formats_are_reported() {
+    let diagnostics = lsp::diagnostics("Foo:\n  NEWTYPESTRUCT:\n    SKIPPED: U64\nBar:\n  STRUCT:\n    - x:\n        SKIPPED: U64\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].0, 0);
+    assert!(diagnostics[0].2.contains("container Foo"));
+}
+
+#[test]
+fn test_references_match_whole_names() {
+    let diagnostics = lsp::diagnostics(
+        "Foo:\n  STRUCT:\n    - x:\n        TYPENAME: BarBaz\n    - y:\n        TYPENAME: Bar\nBarBaz:\n  UNITSTRUCT\n",
+    );
+    assert_eq!(
+        diagnostics,
+        vec![(5, 18, "Undefined container Bar".to_string())]
+    );
+}
+
+#[test]
+fn test_positions_are_counted_in_utf16_code_units() {
+    // Each emoji takes two UTF-16 code units.
+    let text = "Foo:\n  STRUCT:\n    - \u{1F600}\u{1F600}\u{1F600}\u{1F600}: {TUPLE: [{TYPENAME: Bar}, {TYPENAME: Baz}]}\nBar:\n  UNITSTRUCT\n";
+    let diagnostics = lsp::diagnostics(text);
+    assert_eq!(
+        diagnostics,
+        vec![(2, 53, "Undefined container Baz".to_string())]
+    );
+
+    let input = [
+        notification(
+            "textDocument/didOpen",
+            &format!(
+                r#"{{"textDocument": {{"uri": "file:///test.yaml", "text": {:?}}}}}"#,
+                text
+            ),
+        ),
+        request(
+            1,
+            "textDocument/definition",
+            r#"{"textDocument": {"uri": "file:///test.yaml"}, "position": {"line": 2, "character": 36}}"#,
+        ),
+    ]
+    .concat();
+    let mut output = Vec::new();
+    LanguageServer::new()
+        .serve(input.as_bytes(), &mut output)
+        .unwrap();
+    let mut reader = output.as_slice();
+    lsp::read_message(&mut reader).unwrap().unwrap();
+    let response = lsp::read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(
+        response["result"]["range"]["start"]["line"],
+        Value::from(3u64)
+    );
+}
@@ -1,7 +1,10 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_generate::{python3, test_utils, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_generate::{
+    python3, test_utils, CodeGeneratorConfig, ContainerOrder, Encoding, Error, OutputLayout,
+    SourceInstaller, VariantNaming,
+};
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::process::Command;
@@ -120,6 +123,51 @@ fn test_python_code_with_external_definitions() {
     assert!(!content.contains("value: Tree"));
 }
 
+#[test]
+fn test_python_code_with_pydantic() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let generator = python3::CodeGenerator::new(&config).with_pydantic(true);
+    generator.output(&mut source, &registry).unwrap();
+
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains("from pydantic.dataclasses import dataclass"));
+    assert!(content.contains("@dataclass(frozen=True, config=_CONFIG)\nclass Struct:"));
+    assert!(content.contains("f_i8: typing.Annotated[st.int8, _integer(st.int8, -128, 127)]"));
+    assert!(content.contains(
+        "f_char: typing.Optional[typing.Annotated[st.char, pydantic.BeforeValidator(_char)]]"
+    ));
+    // Binary serialization is still available.
+    assert!(content.contains("def bcs_serialize(self) -> bytes:"));
+}
+
+#[test]
+fn test_that_python_code_parses_with_field_descriptors() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_field_descriptors(true);
+    let (_dir, source_path) = test_that_python_code_parses_with_config(&config);
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains(r#"{"name": "f_option", "kind": "container", "optional": True},"#));
+    assert!(content.contains(r#"{"name": "f_seq", "kind": "sequence", "optional": False},"#));
+}
+
+#[test]
+fn test_that_python_code_parses_with_variant_naming() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_variant_naming(VariantNaming::Suffix("Item".to_string()));
+    let (_dir, source_path) = test_that_python_code_parses_with_config(&config);
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains("class SerdeDataUnitVariantItem(SerdeData):"));
+    assert!(content.contains("    SerdeDataUnitVariantItem,\n"));
+    assert!(!content.contains("SerdeData__UnitVariant"));
+}
+
 #[test]
 fn test_that_python_code_parses_with_custom_code() {
     let custom_code = vec![
@@ -158,25 +206,18 @@ fn test_that_installed_python_code_passes_pyre_check() {
     let installer = python3::Installer::new(dir.path().join("src"), /* serde package */ None);
     installer.install_module(&config, &registry).unwrap();
     installer.install_serde_runtime().unwrap();
-    installer.install_bincode_runtime().unwrap();
-    installer.install_bcs_runtime().unwrap();
-
-    // Copy test files manually to type-check them as well.
-    // This should go away when python runtimes are properly packaged.
-    let status = Command::new("cp")
-        .arg("-r")
-        .arg("runtime/python/bcs/test_bcs.py")
-        .arg(dir.path().join("src/bcs"))
-        .status()
+    installer
+        .install_encoding_runtime(Encoding::Bincode)
         .unwrap();
-    assert!(status.success());
-    let status = Command::new("cp")
-        .arg("-r")
-        .arg("runtime/python/bincode/test_bincode.py")
-        .arg(dir.path().join("src/bincode"))
-        .status()
+    installer.install_encoding_runtime(Encoding::Bcs).unwrap();
+    // Type-check the runtime tests as well.
+    installer
+        .install_runtime_tests(
+            &config
+                .clone()
+                .with_encodings(vec![Encoding::Bincode, Encoding::Bcs]),
+        )
         .unwrap();
-    assert!(status.success());
 
     let site_packages = Command::new("python3")
         .arg("-c")
@@ -208,6 +249,53 @@ fn test_that_installed_python_code_passes_pyre_check() {
     assert!(status.success());
 }
 
+#[test]
+fn test_that_installed_python_code_parses_as_single_file() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_output_layout(OutputLayout::SingleFile);
+    let installer =
+        python3::Installer::new(dir.path().to_path_buf(), /* serde package */ None);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+    installer.install_encoding_runtimes(&config).unwrap();
+    assert!(dir.path().join("testing.py").exists());
+    assert!(!dir.path().join("testing").exists());
+
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("import testing")
+        .env("PYTHONPATH", dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_python_code_runs_with_embedded_runtime() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bincode, Encoding::Bcs]);
+    let generator = python3::CodeGenerator::new(&config).with_embedded_runtime(true);
+    generator.output(&mut source, &registry).unwrap();
+
+    // No runtime is installed next to the module.
+    let status = Command::new("python3")
+        .arg(&source_path)
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_python_autotest() {
     let status = Command::new("python3")
@@ -220,3 +308,384 @@ fn test_python_autotest() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_python_code_with_dependency_order() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_container_order(ContainerOrder::Dependencies);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    let position = |name: &str| content.find(&format!("\nclass {}:", name)).unwrap();
+    assert!(position("UnitStruct") < position("SerdeData"));
+}
+
+#[test]
+fn test_python_code_with_seq_u8_as_bytes() {
+    let registry: serde_reflection::Registry =
+        serde_yaml::from_str("Blob:\n  STRUCT:\n    - data:\n        SEQ: U8\n").unwrap();
+    let mut source = Vec::new();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("typing.Sequence[st.uint8]"));
+
+    let mut source = Vec::new();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_seq_u8_as_bytes(true);
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("data: bytes"));
+}
+
+#[test]
+fn test_python_code_with_native_sets() {
+    let registry: serde_reflection::Registry =
+        serde_yaml::from_str("Tags:\n  STRUCT:\n    - names:\n        SET: STR\n").unwrap();
+    let mut source = Vec::new();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("names: typing.FrozenSet[str]"));
+}
+
+#[test]
+fn test_python_code_with_time_formats() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Event:\n  STRUCT:\n    - time:\n        CUSTOM:\n          NAME: SystemTime\n          LAYOUT:\n            TUPLE: [U64, U32]\n    - timeout:\n        CUSTOM:\n          NAME: Duration\n          LAYOUT:\n            TUPLE: [U64, U32]\n",
+    )
+    .unwrap();
+    let mut source = Vec::new();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_type_aliases(true);
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("import datetime\n"));
+    assert!(content.contains("time: datetime.datetime"));
+    assert!(content.contains("timeout: datetime.timedelta"));
+    assert!(!content.contains("Duration ="));
+}
+
+#[test]
+fn test_python_code_with_network_addresses() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Peer:
+  STRUCT:
+    - ip: {TYPENAME: IpAddr}
+    - socket: {TYPENAME: SocketAddr}
+IpAddr:
+  ENUM:
+    0: {V4: {NEWTYPE: {TUPLEARRAY: {CONTENT: U8, SIZE: 4}}}}
+    1: {V6: {NEWTYPE: {TUPLEARRAY: {CONTENT: U8, SIZE: 16}}}}
+SocketAddr:
+  ENUM:
+    0: {V4: {NEWTYPE: {TUPLE: [{TUPLEARRAY: {CONTENT: U8, SIZE: 4}}, U16]}}}
+    1: {V6: {NEWTYPE: {TUPLE: [{TUPLEARRAY: {CONTENT: U8, SIZE: 16}}, U16]}}}
+"#,
+    )
+    .unwrap();
+    let mut source = Vec::new();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("ip: st.ip_addr"));
+    assert!(content.contains("socket: st.socket_addr"));
+    assert!(!content.contains("class IpAddr"));
+}
+
+#[test]
+fn test_python_code_with_integer_constraints() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Account:\n  STRUCT:\n    - id:\n        CUSTOM:\n          NAME: NonZeroU64\n          LAYOUT: U64\n    - percent: U8\n",
+    )
+    .unwrap();
+    let mut bounds = BTreeMap::new();
+    bounds.insert(
+        vec![
+            "testing".to_string(),
+            "Account".to_string(),
+            "percent".to_string(),
+        ],
+        (None, Some(100)),
+    );
+    let mut source = Vec::new();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_integer_bounds(bounds);
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(
+        r#"    def __post_init__(self) -> None:
+        if not (self.id != 0):
+            raise ValueError("id must be nonzero")
+        if not (self.percent <= 100):
+            raise ValueError("percent must be at most 100")
+"#
+    ));
+}
+
+#[test]
+fn test_python_code_with_decimal_formats() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Payment:\n  STRUCT:\n    - amount:\n        DECIMAL:\n          MANTISSA: I64\n          SCALE: 2\n",
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("\nimport decimal\n"));
+    assert!(content.contains("    amount: st.fixed_point(st.int64, 2)\n"));
+
+    let mut source = Vec::new();
+    python3::CodeGenerator::new(&config)
+        .with_pydantic(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(
+        "    amount: typing.Annotated[st.fixed_point(st.int64, 2), pydantic.PlainValidator(decimal.Decimal)]\n"
+    ));
+}
+
+#[test]
+fn test_python_fixtures() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Node:
+  ENUM:
+    0:
+      Leaf: UNIT
+    1:
+      Branch:
+        TUPLE:
+          - TYPENAME: Node
+          - TYPENAME: Node
+Pair:
+  TUPLESTRUCT:
+    - U64
+    - CHAR
+Server:
+  STRUCT:
+    - host: STR
+    - port: U16
+    - id:
+        CUSTOM:
+          NAME: NonZeroU32
+          LAYOUT: U32
+    - tree:
+        TYPENAME: Node
+    - tags:
+        SET: STR
+    - proxy:
+        OPTION: STR
+    - pair:
+        TYPENAME: Pair
+"#,
+    )
+    .unwrap();
+    let mut bounds = BTreeMap::new();
+    bounds.insert(
+        vec![
+            "testing".to_string(),
+            "Server".to_string(),
+            "port".to_string(),
+        ],
+        (Some(1024), None),
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_integer_bounds(bounds)
+        .with_fixtures(true);
+    let mut source = Vec::new();
+    python3::CodeGenerator::new(&config)
+        .output_fixtures(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("from testing import Node, Node__Leaf, Pair, Server\n"));
+    assert!(content.contains(
+        r#"class NodeFixtures:
+    @staticmethod
+    def sample() -> Node:
+        return Node__Leaf()
+"#
+    ));
+    assert!(content.contains(r#"fields = {"value": (st.uint64(0), st.char("a"))}"#));
+    assert!(content.contains(
+        r#"fields = {"host": "", "port": st.uint16(1024), "id": st.uint32(1), "tree": NodeFixtures.sample(), "tags": frozenset(), "proxy": None, "pair": PairFixtures.sample()}"#
+    ));
+    assert!(content.contains("        fields.update(overrides)\n        return Server(**fields)\n"));
+
+    let dir = tempdir().unwrap();
+    python3::Installer::new(dir.path().to_path_buf(), None)
+        .install_module(&config, &registry)
+        .unwrap();
+    assert!(dir.path().join("testing/fixtures.py").exists());
+}
+
+#[test]
+fn test_python_code_with_deprecations() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Config:\n  STRUCT:\n    - legacy: BOOL\nChoice:\n  ENUM:\n    0:\n      Old: UNIT\n    1:\n      New: UNIT\n",
+    )
+    .unwrap();
+    let deprecations = vec![
+        (
+            vec!["testing".to_string(), "Config".to_string()],
+            "Use Settings instead.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Config".to_string(),
+                "legacy".to_string(),
+            ],
+            "No longer read.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Choice".to_string(),
+                "Old".to_string(),
+            ],
+            "Use New.".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_deprecations(deprecations);
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains("from typing_extensions import deprecated\n"));
+    assert!(content.contains(
+        "@deprecated(\"Use Settings instead.\")\n@dataclass(frozen=True)\nclass Config:"
+    ));
+    assert!(content.contains(
+        "@deprecated(\"Use New.\")\n@dataclass(frozen=True)\nclass Choice__Old(Choice):"
+    ));
+    assert!(content.contains("    # Deprecated: No longer read.\n    legacy: bool\n"));
+
+    // Deprecated definitions are only reported when they are used.
+    let python_path = format!(
+        "{}:runtime/python",
+        std::env::var("PYTHONPATH").unwrap_or_default()
+    );
+    let status = Command::new("python3")
+        .arg("-W")
+        .arg("error::DeprecationWarning")
+        .arg(&source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_python_code_rejects_json_integers_as_strings() {
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_json_integers_as_strings(true);
+    let error = python3::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
+
+#[test]
+fn test_that_python_code_rejects_default_values() {
+    let registry = test_utils::get_registry().unwrap();
+    let default_values = vec![(
+        vec!["testing".to_string(), "Struct".to_string(), "x".to_string()],
+        Some(serde_reflection::Value::U32(1)),
+    )]
+    .into_iter()
+    .collect();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_default_values(default_values);
+    let error = python3::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
+
+#[test]
+fn test_python_code_with_immutable_collections() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Book:\n  STRUCT:\n    - pages: {SEQ: STR}\n    - tags: {SET: STR}\n    - index: {MAP: {KEY: STR, VALUE: U32}}\n",
+    )
+    .unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("testing.py");
+    let mut source = File::create(&source_path).unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_immutable_collections(true);
+    python3::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains("index: typing.Mapping[str, st.uint32]"));
+    assert!(content.contains(
+        r#"    def __post_init__(self) -> None:
+        object.__setattr__(self, "pages", tuple(self.pages))
+        object.__setattr__(self, "tags", frozenset(self.tags))
+        object.__setattr__(self, "index", types.MappingProxyType(dict(self.index)))
+"#
+    ));
+
+    let script_path = dir.path().join("main.py");
+    std::fs::write(
+        &script_path,
+        r#"
+import types
+import serde_types as st
+from testing import Book
+
+pages = ["a", "b"]
+book = Book(pages, {"x"}, {"b": st.uint32(2), "a": st.uint32(1)})
+pages.append("c")
+assert book.pages == ("a", "b")
+assert isinstance(book.tags, frozenset)
+assert isinstance(book.index, types.MappingProxyType)
+copy = Book.bcs_deserialize(book.bcs_serialize())
+assert copy == book
+assert isinstance(copy.pages, tuple)
+assert isinstance(copy.index, types.MappingProxyType)
+"#,
+    )
+    .unwrap();
+    let python_path = format!(
+        "{}:runtime/python",
+        std::env::var("PYTHONPATH").unwrap_or_default()
+    );
+    let status = Command::new("python3")
+        .arg(&script_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
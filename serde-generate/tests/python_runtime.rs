@@ -4,8 +4,10 @@
 use serde_generate::{
     python3, test_utils,
     test_utils::{Choice, Runtime, Test},
-    CodeGeneratorConfig,
+    test_vectors::TestVector,
+    CodeGeneratorConfig, Encoding, RootFormats,
 };
+use serde_reflection::{ContainerFormat, Format, Named, Registry};
 use std::fs::File;
 use std::io::Write;
 use std::process::Command;
@@ -76,6 +78,385 @@ assert seen_error
     assert!(status.success());
 }
 
+#[test]
+fn test_python_record_log_on_simple_data() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_record_log_types(vec!["Test".to_string()]);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+
+    writeln!(
+        source,
+        r#"
+import io
+
+values = [Test([4, 6], (3, 5), Choice__C(7)), Test([], (0, 1), Choice__A())]
+
+output = io.BytesIO()
+writer = test_log_writer(output)
+for value in values:
+    writer.write(value)
+writer.finish()
+content = output.getvalue()
+
+reader = test_log_reader(content)
+assert list(reader) == values
+offsets = reader.index()
+assert offsets is not None and len(offsets) == 2
+assert reader.record_at(offsets[1]) == values[1]
+
+output = io.BytesIO()
+writer = test_log_writer(output)
+writer.write(values[0])
+writer.finish(index=False)
+assert list(test_log_reader(output.getvalue())) == values[:1]
+assert test_log_reader(output.getvalue()).index() is None
+
+seen_error = False
+try:
+    serde_record_log.RecordLogReader(
+        content, TEST_SCHEMA_FINGERPRINT + 1, serde_record_log.BCS, Test.bcs_deserialize
+    )
+except st.DeserializationError:
+    seen_error = True
+assert seen_error
+"#
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_envelope_hooks_on_simple_data() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_envelope_hooks(true);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = Runtime::Bcs.serialize(&Test {
+        a: vec![4, 6],
+        b: (3, 5),
+        c: Choice::C { x: 7 },
+    });
+    writeln!(
+        source,
+        r#"
+import zlib
+
+input = bytes({:?})
+value = Test([4, 6], (3, 5), Choice__C(7))
+assert value.bcs_serialize() == input
+
+st.set_envelope_hooks(zlib.compress, zlib.decompress)
+s = value.bcs_serialize()
+assert s == zlib.compress(input)
+assert Test.bcs_deserialize(s) == value
+
+seen_error = False
+try:
+    Test.bcs_deserialize(input)
+except zlib.error:
+    seen_error = True
+assert seen_error
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_checksums_on_simple_data() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bincode.into()])
+        .with_checksums(true);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = Runtime::Bincode.serialize(&Test {
+        a: vec![4, 6],
+        b: (3, 5),
+        c: Choice::C { x: 7 },
+    });
+    writeln!(
+        source,
+        r#"
+import zlib
+
+input = bytes({:?})
+value = Test([4, 6], (3, 5), Choice__C(7))
+
+s = value.bincode_serialize_checked()
+assert s == input + zlib.crc32(input).to_bytes(4, "little")
+assert Test.bincode_deserialize_checked(s) == value
+
+for corrupted in [s[:-1], bytes([s[0] ^ 1]) + s[1:], input]:
+    seen_error = False
+    try:
+        Test.bincode_deserialize_checked(corrupted)
+    except st.DeserializationError:
+        seen_error = True
+    assert seen_error
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_root_formats_on_simple_data() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let mut root_formats = RootFormats::new();
+    root_formats.insert(
+        "TestList".to_string(),
+        Format::Seq(Box::new(Format::TypeName("Test".to_string()))),
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_root_formats(root_formats);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+
+    let values = vec![
+        Test {
+            a: vec![4, 6],
+            b: (3, 5),
+            c: Choice::C { x: 7 },
+        },
+        Test {
+            a: vec![],
+            b: (0, 1),
+            c: Choice::A,
+        },
+    ];
+    let reference = Runtime::Bcs.serialize(&values);
+    writeln!(
+        source,
+        r#"
+input = bytes({:?})
+values = [Test([4, 6], (3, 5), Choice__C(7)), Test([], (0, 1), Choice__A())]
+
+assert bcs_serialize_test_list(values) == input
+assert bcs_deserialize_test_list(input) == values
+
+seen_error = False
+try:
+    bcs_deserialize_test_list(input + bytes([0]))
+except st.DeserializationError:
+    seen_error = True
+assert seen_error
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_test_vectors_on_simple_data() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let vectors_path = dir.path().join("test.vectors");
+    let value =
+        serde_yaml::from_str(r#"{"a": [4, 6], "b": [3, 5], "c": {"C": {"x": 7}}}"#).unwrap();
+    let vectors = TestVector::generate(
+        &registry,
+        "Test",
+        &value,
+        &[Encoding::Bcs, Encoding::Bincode],
+    )
+    .unwrap();
+    let mut file = File::create(&vectors_path).unwrap();
+    for vector in vectors {
+        writeln!(file, "{}", vector).unwrap();
+    }
+
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode]);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+    writeln!(
+        source,
+        r#"
+import json
+
+vectors = st.load_test_vectors({:?})
+assert [v.encoding for v in vectors] == ["bcs", "bincode"]
+expected = Test([4, 6], (3, 5), Choice__C(7))
+for vector in vectors:
+    assert vector.type_name == "Test"
+    assert json.loads(vector.json)["c"] == {{"C": {{"x": 7}}}}
+    if vector.encoding == "bcs":
+        assert Test.bcs_deserialize(vector.bytes) == expected
+    else:
+        assert Test.bincode_deserialize(vector.bytes) == expected
+"#,
+        vectors_path,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_runtime_on_corpus() {
+    use serde_generate::SourceInstaller;
+
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("serde_corpus".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode]);
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None);
+    installer.install_serde_runtime().unwrap();
+    installer.install_encoding_runtimes(&config).unwrap();
+    installer.install_corpus_runner(&config).unwrap();
+
+    let status = Command::new("python3")
+        .arg("-m")
+        .arg("unittest")
+        .arg("test_serde_corpus")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_embedded_schema() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_embedded_schema(true);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+    writeln!(
+        source,
+        "\nassert schema() == {:?}",
+        serde_yaml::to_string(&registry).unwrap()
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_type_aliases() {
+    let height = Format::Custom {
+        name: "BlockHeight".to_string(),
+        layout: Box::new(Format::U64),
+    };
+    let mut registry = Registry::new();
+    registry.insert(
+        "Block".to_string(),
+        ContainerFormat::Struct(vec![
+            Named::new("height", height.clone()),
+            Named::new("parents", Format::Seq(Box::new(height))),
+        ]),
+    );
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_type_aliases(true);
+    let generator = python3::CodeGenerator::new(&config);
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = Runtime::Bcs.serialize(&(5u64, vec![3u64, 4u64]));
+    writeln!(
+        source,
+        r#"
+import typing
+
+assert BlockHeight is st.uint64
+assert typing.get_type_hints(Block)["parents"] == typing.Sequence[BlockHeight]
+
+input = bytes({:?})
+value = Block(BlockHeight(5), [BlockHeight(3), BlockHeight(4)])
+assert value.bcs_serialize() == input
+assert Block.bcs_deserialize(input) == value
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_python_bcs_runtime_on_supported_types() {
     test_python_runtime_on_supported_types(Runtime::Bcs);
@@ -1,7 +1,7 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_generate::{rust, test_utils, CodeGeneratorConfig};
+use serde_generate::{rust, test_utils, CodeGeneratorConfig, Encoding, Error};
 use std::fs::File;
 use std::io::Write;
 use std::process::Command;
@@ -168,3 +168,356 @@ fn test_that_rust_code_compiles_with_custom_code() {
     let content = std::fs::read_to_string(&source_path).unwrap();
     assert!(content.contains("// custom code\n"));
 }
+
+#[test]
+fn test_that_rust_code_uses_layout_of_custom_formats() {
+    use serde_reflection::{ContainerFormat, Format, Named, Registry};
+
+    let mut registry = Registry::new();
+    registry.insert(
+        "Packet".to_string(),
+        ContainerFormat::Struct(vec![Named::new(
+            "length",
+            Format::Custom {
+                name: "U24".to_string(),
+                layout: Box::new(Format::TupleArray {
+                    content: Box::new(Format::U8),
+                    size: 3,
+                }),
+            },
+        )]),
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("pub length: [u8; 3],"));
+}
+
+#[test]
+fn test_that_rust_code_uses_native_sets() {
+    let registry: serde_reflection::Registry =
+        serde_yaml::from_str("Tags:\n  NEWTYPESTRUCT:\n    SET: STR\n").unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("use std::collections::BTreeSet as Set;"));
+    assert!(content.contains("pub struct Tags(pub Set<String>);"));
+}
+
+#[test]
+fn test_that_rust_code_tags_internally_tagged_enums() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Shape:\n  INTERNALLYTAGGEDENUM:\n    TAG: kind\n    VARIANTS:\n      0:\n        Empty: UNIT\n      1:\n        Circle:\n          STRUCT:\n            - radius: U32\n",
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("#[serde(tag = \"kind\")]\npub enum Shape {"));
+    assert!(content.contains("    Circle {\n        radius: u32,\n    },"));
+}
+
+#[test]
+fn test_that_rust_code_imports_network_addresses() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Peer:
+  STRUCT:
+    - ip: {TYPENAME: IpAddr}
+    - socket: {TYPENAME: SocketAddr}
+IpAddr:
+  ENUM:
+    0: {V4: {NEWTYPE: {TUPLEARRAY: {CONTENT: U8, SIZE: 4}}}}
+    1: {V6: {NEWTYPE: {TUPLEARRAY: {CONTENT: U8, SIZE: 16}}}}
+SocketAddr:
+  ENUM:
+    0: {V4: {NEWTYPE: {TUPLE: [{TUPLEARRAY: {CONTENT: U8, SIZE: 4}}, U16]}}}
+    1: {V6: {NEWTYPE: {TUPLE: [{TUPLEARRAY: {CONTENT: U8, SIZE: 16}}, U16]}}}
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("use std::net::{IpAddr, SocketAddr};"));
+    assert!(!content.contains("pub enum IpAddr"));
+    assert!(content.contains("    pub ip: IpAddr,\n    pub socket: SocketAddr,"));
+}
+
+#[test]
+fn test_that_rust_code_renames_fields_to_source_names() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Person:
+  STRUCT:
+    - firstName: STR
+      SOURCE_NAME: first_name
+    - age: U8
+Event:
+  ENUM:
+    0:
+      STARTED: UNIT
+      SOURCE_NAME: Started
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(
+        "    #[serde(rename = \"firstName\")]\n    pub first_name: String,\n    pub age: u8,"
+    ));
+    assert!(content.contains("    #[serde(rename = \"STARTED\")]\n    Started,"));
+}
+
+#[test]
+fn test_that_rust_code_uses_nonzero_integers() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Account:\n  STRUCT:\n    - id: {CUSTOM: {NAME: NonZeroU64, LAYOUT: U64}}\n    - delta: {OPTION: {CUSTOM: {NAME: NonZeroI8, LAYOUT: I8}}}\n",
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(
+        "    pub id: std::num::NonZeroU64,\n    pub delta: Option<std::num::NonZeroI8>,"
+    ));
+}
+
+#[test]
+fn test_that_rust_code_omits_skipped_fields() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Session:\n  STRUCT:\n    - user: STR\n    - cache: {SKIPPED: BYTES}\n    - expiry: U64\n",
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("    pub user: String,\n    pub expiry: u64,"));
+    assert!(!content.contains("cache"));
+}
+
+#[test]
+fn test_that_rust_code_rejects_unsupported_formats() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Session:\n  STRUCT:\n    - user: STR\n    - cache: {OPTION: {SKIPPED: BYTES}}\n",
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    let error = rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap_err();
+    match error {
+        Error::UnsupportedFormat {
+            language,
+            container,
+            field,
+            ..
+        } => {
+            assert_eq!(language, "Rust");
+            assert_eq!(container, "Session");
+            assert_eq!(field.as_deref(), Some("cache"));
+        }
+        _ => panic!("unexpected error: {}", error),
+    }
+}
+
+#[test]
+fn test_that_rust_code_includes_flag_helpers() {
+    use serde_reflection::{ContainerFormat, Format, Named, Registry};
+
+    let mut registry = Registry::new();
+    registry.insert(
+        "Permissions".to_string(),
+        ContainerFormat::Struct(vec![Named::new("mode", Format::U8)]),
+    );
+    let flags = vec![(
+        vec![
+            "testing".to_string(),
+            "Permissions".to_string(),
+            "mode".to_string(),
+        ],
+        vec![("read".to_string(), 0), ("write".to_string(), 1)]
+            .into_iter()
+            .collect(),
+    )]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_flags(flags);
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("pub const MODE_READ: u8 = 0x1;"));
+    assert!(content.contains("pub const MODE_WRITE: u8 = 0x2;"));
+    assert!(content.contains("pub fn has_mode(&self, mask: u8) -> bool {"));
+    assert!(content.contains("pub fn clear_mode(&mut self, mask: u8) {"));
+}
+
+#[test]
+fn test_that_rust_code_uses_semantic_tags() {
+    use serde_reflection::{ContainerFormat, Format, Named, Registry};
+
+    let mut registry = Registry::new();
+    registry.insert(
+        "Timeout".to_string(),
+        ContainerFormat::Struct(vec![Named::new("duration", Format::U64)]),
+    );
+    let tags = vec![(
+        vec![
+            "testing".to_string(),
+            "Timeout".to_string(),
+            "duration".to_string(),
+        ],
+        "microseconds".to_string(),
+    )]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_semantic_tags(tags);
+
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("/// Unit: microseconds\n    pub duration: u64,"));
+
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .with_semantic_newtypes(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("pub struct Microseconds(pub u64);"));
+    assert!(content.contains("pub duration: Microseconds,"));
+}
+
+#[test]
+fn test_that_rust_code_marks_deprecated_definitions() {
+    use serde_reflection::{ContainerFormat, Format, Named, Registry};
+
+    let mut registry = Registry::new();
+    registry.insert(
+        "Config".to_string(),
+        ContainerFormat::Struct(vec![Named::new("legacy", Format::Bool)]),
+    );
+    let deprecations = vec![
+        (
+            vec!["testing".to_string(), "Config".to_string()],
+            "Use Settings instead.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Config".to_string(),
+                "legacy".to_string(),
+            ],
+            "No longer read.".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_deprecations(deprecations);
+
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("#[deprecated(note = \"Use Settings instead.\")]\n"));
+    assert!(
+        content.contains("    #[deprecated(note = \"No longer read.\")]\n    pub legacy: bool,")
+    );
+}
+
+#[test]
+fn test_that_rust_code_compiles_with_variant_names() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_serialization(false)
+        .with_variant_names(true);
+    let (_dir, source_path) = test_that_rust_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains(
+        "pub const VARIANT_NAMES: &'static [&'static str] = &[\"A\", \"B\", \"C\", \"D\", \"E\"];"
+    ));
+}
+
+#[test]
+fn test_that_rust_code_decodes_memory_mapped_files() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_file_decoding(true);
+
+    let mut source = Vec::new();
+    rust::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("fn map_file<P: AsRef<std::path::Path>>(path: P)"));
+    assert!(content.contains(
+        "impl SerdeData {\n    /// Decode a value from the BCS bytes of a memory-mapped file.\n    pub fn bcs_from_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {"
+    ));
+    assert!(!content.contains("bincode_from_file"));
+}
+
+#[test]
+fn test_that_rust_code_rejects_root_formats() {
+    let registry = test_utils::get_registry().unwrap();
+    let root_formats = vec![(
+        "TransactionList".to_string(),
+        serde_reflection::Format::Seq(Box::new(serde_reflection::Format::U64)),
+    )]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_root_formats(root_formats);
+    let error = rust::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
+
+#[test]
+fn test_that_rust_code_rejects_type_aliases() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_type_aliases(true);
+    let error = rust::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
+
+#[test]
+fn test_that_rust_code_rejects_fixtures() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_fixtures(true);
+    let error = rust::CodeGenerator::new(&config)
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+}
@@ -0,0 +1,116 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "schema-registry")]
+
+use serde_generate::{
+    schema_registry::{SchemaRegistryClient, SchemaRegistryServer},
+    test_utils, Encoding,
+};
+use serde_reflection::ContainerFormat;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_schemas_are_published_and_resolved() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    std::thread::spawn(move || SchemaRegistryServer::new().serve(listener));
+
+    let registry = test_utils::get_simple_registry().unwrap();
+    let publisher = SchemaRegistryClient::new(address.clone());
+    let fingerprint = publisher.publish(&registry).unwrap();
+    assert_eq!(fingerprint, serde_reflection::fingerprint(&registry));
+
+    let client = SchemaRegistryClient::new(address);
+    assert_eq!(*client.resolve(fingerprint).unwrap(), registry);
+    let value = client
+        .decode(fingerprint, "Choice", &[2, 7], Encoding::Bcs)
+        .unwrap();
+    assert_eq!(
+        value,
+        serde_yaml::from_str::<serde_yaml::Value>(r#"{"C": {"x": 7}}"#).unwrap()
+    );
+
+    let mut unknown = fingerprint;
+    unknown[0] ^= 1;
+    let error = client.resolve(unknown).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+    // Publishing the same registry again is fine, but source names are not part of the
+    // fingerprint, hence cannot be changed.
+    assert_eq!(publisher.publish(&registry).unwrap(), fingerprint);
+    let mut renamed = registry;
+    match renamed.get_mut("Test") {
+        Some(ContainerFormat::Struct(fields)) => fields[0].source_name = Some("a_".into()),
+        _ => panic!("Test should be a struct"),
+    }
+    let error = publisher.publish(&renamed).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+}
+
+#[test]
+fn test_large_requests_are_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        SchemaRegistryServer::new()
+            .with_max_body_size(1024)
+            .serve(listener)
+    });
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "POST /schemas HTTP/1.1\r\nContent-Length: 1099511627776\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 413"));
+}
+
+#[test]
+fn test_long_headers_are_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || SchemaRegistryServer::new().serve(listener));
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "GET /schemas/0 HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+        "a".repeat(10_000)
+    )
+    .unwrap();
+    // The connection is closed without a response.
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok();
+    assert!(response.is_empty());
+}
+
+#[test]
+fn test_idle_connections_time_out_and_connections_are_bounded() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let timeout = Duration::from_millis(200);
+    std::thread::spawn(move || {
+        SchemaRegistryServer::new()
+            .with_max_connections(1)
+            .with_timeout(timeout)
+            .serve(listener)
+    });
+
+    let start = Instant::now();
+    let mut idle = TcpStream::connect(address).unwrap();
+    // The second connection is only handled once the first one times out.
+    let registry = test_utils::get_simple_registry().unwrap();
+    let client = SchemaRegistryClient::new(address.to_string());
+    client.publish(&registry).unwrap();
+    assert!(start.elapsed() >= timeout);
+
+    let mut response = String::new();
+    idle.read_to_string(&mut response).ok();
+    assert!(response.is_empty());
+}
@@ -0,0 +1,46 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{sql, test_utils, CodeGeneratorConfig};
+
+fn generate(dialect: sql::Dialect) -> String {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    sql::CodeGenerator::new(&config)
+        .with_dialect(dialect)
+        .output(&mut source, &registry)
+        .unwrap();
+    String::from_utf8(source).unwrap()
+}
+
+#[test]
+fn test_that_postgres_tables_are_generated() {
+    let content = generate(sql::Dialect::Postgres);
+    assert!(content.contains(
+        r#"
+CREATE TABLE "struct" (
+    "x" BIGINT NOT NULL,
+    "y" NUMERIC(20, 0) NOT NULL
+);
+"#
+    ));
+    assert!(content.contains(r#""f_string" TEXT NOT NULL,"#));
+    assert!(content.contains(r#""f_option" JSONB,"#));
+    assert!(!content.contains(r#"CREATE TABLE "serde_data""#));
+}
+
+#[test]
+fn test_that_clickhouse_tables_are_generated() {
+    let content = generate(sql::Dialect::ClickHouse);
+    assert!(content.contains(
+        r#"
+CREATE TABLE `struct` (
+    `x` UInt32,
+    `y` UInt64
+)
+ENGINE = MergeTree()
+ORDER BY tuple();
+"#
+    ));
+}
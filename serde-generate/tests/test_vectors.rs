@@ -0,0 +1,96 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{
+    test_utils,
+    test_utils::{Choice, Test},
+    test_vectors,
+    test_vectors::TestVector,
+    Encoding,
+};
+
+#[test]
+fn test_vectors_round_trip() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let value =
+        serde_yaml::from_str(r#"{"a": [4, 6], "b": [3, 5], "c": {"C": {"x": 7}}}"#).unwrap();
+    let vectors = TestVector::generate(
+        &registry,
+        "Test",
+        &value,
+        &[Encoding::Bcs, Encoding::Bincode],
+    )
+    .unwrap();
+    let reference = bcs::to_bytes(&Test {
+        a: vec![4, 6],
+        b: (3, 5),
+        c: Choice::C { x: 7 },
+    })
+    .unwrap();
+    assert_eq!(vectors.len(), 2);
+    assert_eq!(vectors[0].bytes, reference);
+    assert_eq!(
+        vectors[0].to_string(),
+        concat!(
+            "Test\tbcs\t020400000006000000030000000000000005000000000000000207\t",
+            r#"{"a": [4, 6], "b": [3, 5], "c": {"C": {"x": 7}}}"#
+        )
+    );
+
+    let content = format!("# Test vectors\n\n{}\n{}\n", vectors[0], vectors[1]);
+    let parsed = test_vectors::parse(&content).unwrap();
+    assert_eq!(parsed, vectors);
+    for vector in &parsed {
+        vector.check(&registry).unwrap();
+    }
+}
+
+#[test]
+fn test_invalid_test_vectors_are_rejected() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    assert!(test_vectors::parse("Test\tbcs\t0\tnull\n").is_err());
+    assert!(test_vectors::parse("Test\tjson\t00\tnull\n").is_err());
+    assert!(test_vectors::parse("Test\tbcs\t00\n").is_err());
+
+    let vectors = test_vectors::parse("Choice\tbcs\t00\t\"B\"\n").unwrap();
+    assert!(vectors[0].check(&registry).is_err());
+}
+
+#[test]
+fn test_corpus_is_consistent() {
+    #[derive(serde::Serialize)]
+    struct Lengths {
+        #[serde(with = "serde_bytes")]
+        blob: Vec<u8>,
+        text: String,
+        items: Vec<u16>,
+    }
+
+    let registry = test_vectors::corpus_registry();
+    let vectors = test_vectors::corpus();
+    for vector in &vectors {
+        vector.check(&registry).unwrap();
+    }
+    for name in registry.keys() {
+        for encoding in [Encoding::Bcs, Encoding::Bincode].iter() {
+            assert!(vectors
+                .iter()
+                .any(|vector| &vector.type_name == name && vector.encoding == *encoding));
+        }
+    }
+
+    let value = Lengths {
+        blob: (0..128).collect(),
+        text: "y".repeat(128),
+        items: vec![8; 128],
+    };
+    let lengths = |encoding| {
+        vectors
+            .iter()
+            .filter(move |vector| vector.type_name == "Lengths" && vector.encoding == encoding)
+            .map(|vector| vector.bytes.clone())
+            .collect::<Vec<_>>()
+    };
+    assert!(lengths(Encoding::Bcs).contains(&bcs::to_bytes(&value).unwrap()));
+    assert!(lengths(Encoding::Bincode).contains(&bincode::serialize(&value).unwrap()));
+}
@@ -0,0 +1,290 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{test_utils, transcode, Encoding};
+use serde_reflection::Format;
+
+#[test]
+fn test_bincode_values_round_trip() {
+    let registry = test_utils::get_registry().unwrap();
+    let format = Format::TypeName("SerdeData".to_string());
+    for value in test_utils::get_sample_values(false, true) {
+        let bytes = bincode::serialize(&value).unwrap();
+        let decoded = transcode::decode(&registry, &format, &bytes, Encoding::Bincode).unwrap();
+        let encoded = transcode::encode(&registry, &format, &decoded, Encoding::Bincode).unwrap();
+        assert_eq!(bytes, encoded);
+    }
+}
+
+#[test]
+fn test_bcs_values_round_trip() {
+    let registry = test_utils::get_registry().unwrap();
+    let format = Format::TypeName("SerdeData".to_string());
+    for value in test_utils::get_sample_values(true, false) {
+        let bytes = bcs::to_bytes(&value).unwrap();
+        let decoded = transcode::decode(&registry, &format, &bytes, Encoding::Bcs).unwrap();
+        let encoded = transcode::encode(&registry, &format, &decoded, Encoding::Bcs).unwrap();
+        assert_eq!(bytes, encoded);
+    }
+}
+
+#[test]
+fn test_json_values_are_encoded() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let format = Format::TypeName("Choice".to_string());
+    let value = serde_yaml::from_str(r#"{"C": {"x": 7}}"#).unwrap();
+    let bytes = transcode::encode(&registry, &format, &value, Encoding::Bcs).unwrap();
+    assert_eq!(bytes, vec![2, 7]);
+    assert_eq!(
+        transcode::decode(&registry, &format, &bytes, Encoding::Bcs).unwrap(),
+        value
+    );
+
+    let value = serde_yaml::from_str(r#""A""#).unwrap();
+    let bytes = transcode::encode(&registry, &format, &value, Encoding::Bincode).unwrap();
+    assert_eq!(bytes, vec![0, 0, 0, 0]);
+}
+
+#[test]
+fn test_skipped_fields_are_not_encoded() {
+    let registry: serde_reflection::Registry =
+        serde_yaml::from_str("Session:\n  STRUCT:\n    - cache: {SKIPPED: STR}\n    - id: U8\n")
+            .unwrap();
+    let format = Format::TypeName("Session".to_string());
+    let value = serde_yaml::from_str(r#"{"id": 7}"#).unwrap();
+    let bytes = transcode::encode(&registry, &format, &value, Encoding::Bcs).unwrap();
+    assert_eq!(bytes, vec![7]);
+    assert_eq!(
+        transcode::decode(&registry, &format, &bytes, Encoding::Bcs).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn test_framed_messages_are_split() {
+    use transcode::Framing;
+
+    let mut buffer = vec![2, 0, 0, 0, 7, 8, 1, 0, 0];
+    assert_eq!(
+        Framing::U32Le.split_messages(&mut buffer).unwrap(),
+        vec![vec![7, 8]]
+    );
+    assert_eq!(buffer, vec![1, 0, 0]);
+    buffer.extend(&[0, 9]);
+    assert_eq!(
+        Framing::U32Le.split_messages(&mut buffer).unwrap(),
+        vec![vec![9]]
+    );
+    assert!(buffer.is_empty());
+
+    let mut buffer = vec![0, 0, 0, 1, 5, 0, 0];
+    assert_eq!(
+        Framing::U32Be.split_messages(&mut buffer).unwrap(),
+        vec![vec![5]]
+    );
+    assert_eq!(buffer, vec![0, 0]);
+
+    let mut buffer = vec![0x81];
+    assert_eq!(Framing::Uleb128.parse_header(&buffer).unwrap(), None);
+    buffer.push(0x01);
+    assert_eq!(
+        Framing::Uleb128.parse_header(&buffer).unwrap(),
+        Some((2, 129))
+    );
+    assert!(Framing::Uleb128
+        .split_messages(&mut buffer)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_decoded_values_are_compared() {
+    use serde_reflection::{ContainerFormat, Named, VariantFormat};
+
+    let old = test_utils::get_simple_registry().unwrap();
+    let mut new = old.clone();
+    if let Some(ContainerFormat::Struct(fields)) = new.get_mut("Test") {
+        fields[1].value = Format::U128;
+    } else {
+        panic!("Test should be a struct");
+    }
+    if let Some(ContainerFormat::Enum(variants)) = new.get_mut("Choice") {
+        variants.insert(
+            2,
+            Named::new(
+                "D",
+                VariantFormat::Struct(vec![Named::new("y", Format::U8)]),
+            ),
+        );
+    } else {
+        panic!("Choice should be an enum");
+    }
+
+    let format = Format::TypeName("Test".to_string());
+    let bytes = bcs::to_bytes(&test_utils::Test {
+        a: vec![4, 6],
+        b: (3, 5),
+        c: test_utils::Choice::C { x: 7 },
+    })
+    .unwrap();
+    let old_value = transcode::decode(&old, &format, &bytes, Encoding::Bcs).unwrap();
+    let new_value = transcode::decode(&new, &format, &bytes, Encoding::Bcs).unwrap();
+    assert!(transcode::diff(&old_value, &old_value).is_empty());
+    assert_eq!(
+        transcode::diff(&old_value, &new_value),
+        vec![
+            r#"$.b: [3, 5] vs "92233720368547758083""#,
+            r#"$.c.C: only in old schema: {"x": 7}"#,
+            r#"$.c.D: only in new schema: {"y": 7}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_values_are_printed_as_json() {
+    let yaml = r#"{name: "a \"b\"\u0001\n\u00e9", 1: [.inf, -2, 0.5]}"#;
+    let value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    let json = transcode::to_json(&value);
+    assert_eq!(
+        json,
+        r#"{"name": "a \"b\"\u0001\né", "1": [null, -2, 0.5]}"#
+    );
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed["name"], "a \"b\"\u{1}\né");
+}
+
+#[test]
+fn test_set_elements_are_sorted_and_unique() {
+    let registry = serde_reflection::Registry::new();
+    let format = Format::Set(Box::new(Format::U16));
+    let value = serde_yaml::from_str("[513, 3]").unwrap();
+
+    let bytes = transcode::encode(&registry, &format, &value, Encoding::Bcs).unwrap();
+    assert_eq!(bytes, vec![2, 1, 2, 3, 0]);
+    assert_eq!(
+        transcode::decode(&registry, &format, &bytes, Encoding::Bcs).unwrap(),
+        serde_yaml::from_str::<serde_yaml::Value>("[513, 3]").unwrap()
+    );
+    assert!(transcode::decode(&registry, &format, &[2, 3, 0, 1, 2], Encoding::Bcs).is_err());
+
+    let bytes = transcode::encode(&registry, &format, &value, Encoding::Bincode).unwrap();
+    assert_eq!(bytes, vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 0]);
+
+    let value = serde_yaml::from_str("[3, 3]").unwrap();
+    assert!(transcode::encode(&registry, &format, &value, Encoding::Bcs).is_err());
+    assert!(transcode::encode(&registry, &format, &value, Encoding::Bincode).is_err());
+    let bytes = vec![2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 3, 0];
+    assert!(transcode::decode(&registry, &format, &bytes, Encoding::Bincode).is_err());
+}
+
+#[test]
+fn test_internally_tagged_enums_use_variant_names() {
+    #[derive(serde::Serialize)]
+    struct Point {
+        x: u8,
+    }
+
+    #[derive(serde::Serialize)]
+    #[serde(tag = "kind")]
+    enum Shape {
+        Empty,
+        Circle { radius: u16 },
+        At(Point),
+    }
+
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Point:
+  STRUCT:
+    - x: U8
+Shape:
+  INTERNALLYTAGGEDENUM:
+    TAG: kind
+    VARIANTS:
+      0:
+        Empty: UNIT
+      1:
+        Circle:
+          STRUCT:
+            - radius: U16
+      2:
+        At:
+          NEWTYPE:
+            TYPENAME: Point
+"#,
+    )
+    .unwrap();
+    let format = Format::TypeName("Shape".to_string());
+    let samples = vec![
+        (Shape::Empty, r#"{"kind": "Empty"}"#),
+        (
+            Shape::Circle { radius: 513 },
+            r#"{"kind": "Circle", "radius": 513}"#,
+        ),
+        (Shape::At(Point { x: 7 }), r#"{"kind": "At", "x": 7}"#),
+    ];
+    for (shape, json) in samples {
+        let value: serde_yaml::Value = serde_yaml::from_str(json).unwrap();
+        let bytes = transcode::encode(&registry, &format, &value, Encoding::Bcs).unwrap();
+        assert_eq!(bytes, bcs::to_bytes(&shape).unwrap());
+        assert_eq!(
+            transcode::decode(&registry, &format, &bytes, Encoding::Bcs).unwrap(),
+            value
+        );
+        let bytes = transcode::encode(&registry, &format, &value, Encoding::Bincode).unwrap();
+        assert_eq!(bytes, bincode::serialize(&shape).unwrap());
+    }
+
+    let value = serde_yaml::from_str(r#"{"kind": "Square"}"#).unwrap();
+    assert!(transcode::encode(&registry, &format, &value, Encoding::Bcs).is_err());
+}
+
+#[test]
+fn test_nested_options_are_distinguished() {
+    let registry = serde_reflection::Registry::new();
+    let format = Format::Option(Box::new(Format::Option(Box::new(Format::U8))));
+    for (json, bytes) in [
+        ("null", vec![0]),
+        ("[null]", vec![1, 0]),
+        ("[3]", vec![1, 1, 3]),
+    ] {
+        let value = serde_yaml::from_str(json).unwrap();
+        assert_eq!(
+            transcode::encode(&registry, &format, &value, Encoding::Bcs).unwrap(),
+            bytes
+        );
+        assert_eq!(
+            transcode::decode(&registry, &format, &bytes, Encoding::Bcs).unwrap(),
+            value
+        );
+    }
+}
+
+#[test]
+fn test_deeply_nested_values_are_rejected() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "List:\n  STRUCT:\n    - next:\n        OPTION:\n          TYPENAME: List\n",
+    )
+    .unwrap();
+    let format = Format::TypeName("List".to_string());
+    // Decoding the maximal depth needs more than the default stack of test threads in debug builds.
+    std::thread::Builder::new()
+        .stack_size(64 << 20)
+        .spawn(move || {
+            let mut bytes = vec![1; bcs::MAX_CONTAINER_DEPTH - 1];
+            bytes.push(0);
+            assert!(transcode::decode(&registry, &format, &bytes, Encoding::Bcs).is_ok());
+
+            let mut bytes = vec![1; 50_000];
+            bytes.push(0);
+            for encoding in &[Encoding::Bcs, Encoding::Bincode] {
+                let message = transcode::decode(&registry, &format, &bytes, *encoding)
+                    .unwrap_err()
+                    .to_string();
+                assert!(message.contains("maximum container depth"), "{}", message);
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
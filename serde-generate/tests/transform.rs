@@ -0,0 +1,211 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::transform::{
+    self, ExternalTypes, InlineNewTypes, RegistryTransform, RemoveSkippedFields, RenameContainers,
+    ReplaceFormat, SeqU8AsBytes, StripNamespaces,
+};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+
+fn named(name: &str, value: Format) -> Named<Format> {
+    Named::new(name.to_string(), value)
+}
+
+fn get_registry() -> Registry {
+    let mut registry = Registry::new();
+    registry.insert(
+        "foo::Account".to_string(),
+        ContainerFormat::Struct(vec![
+            named("id", Format::TypeName("foo::Id".to_string())),
+            named(
+                "tree",
+                Format::Option(Box::new(Format::TypeName("Tree".to_string()))),
+            ),
+        ]),
+    );
+    registry.insert(
+        "foo::Id".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::TypeName("Hash".to_string()))),
+    );
+    registry.insert(
+        "Hash".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::Bytes)),
+    );
+    registry.insert(
+        "Tree".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::Seq(Box::new(Format::TypeName(
+            "Tree".to_string(),
+        ))))),
+    );
+    registry
+}
+
+#[test]
+fn test_rename_containers() {
+    let transform = RenameContainers(
+        vec![("Hash".to_string(), "Digest".to_string())]
+            .into_iter()
+            .collect(),
+    );
+    let registry = transform.apply(get_registry()).unwrap();
+    assert!(!registry.contains_key("Hash"));
+    assert_eq!(
+        registry["foo::Id"],
+        ContainerFormat::NewTypeStruct(Box::new(Format::TypeName("Digest".to_string())))
+    );
+
+    let transform = RenameContainers(
+        vec![("Tree".to_string(), "Hash".to_string())]
+            .into_iter()
+            .collect(),
+    );
+    assert!(transform.apply(get_registry()).is_err());
+}
+
+#[test]
+fn test_strip_namespaces() {
+    let registry = StripNamespaces.apply(get_registry()).unwrap();
+    assert_eq!(
+        registry.keys().collect::<Vec<_>>(),
+        vec!["Account", "Hash", "Id", "Tree"]
+    );
+    match &registry["Account"] {
+        ContainerFormat::Struct(fields) => {
+            assert_eq!(fields[0].value, Format::TypeName("Id".to_string()))
+        }
+        _ => panic!("Account should be a struct"),
+    }
+}
+
+#[test]
+fn test_inline_newtypes() {
+    let registry = InlineNewTypes.apply(get_registry()).unwrap();
+    // Recursive newtypes are kept.
+    assert_eq!(
+        registry.keys().collect::<Vec<_>>(),
+        vec!["Tree", "foo::Account"]
+    );
+    assert_eq!(
+        registry["foo::Account"],
+        ContainerFormat::Struct(vec![
+            named("id", Format::Bytes),
+            named(
+                "tree",
+                Format::Option(Box::new(Format::TypeName("Tree".to_string()))),
+            ),
+        ])
+    );
+}
+
+#[test]
+fn test_transform_pipeline() {
+    let transforms: Vec<Box<dyn RegistryTransform>> = vec![
+        Box::new(StripNamespaces),
+        Box::new(ReplaceFormat {
+            from: Format::Bytes,
+            to: Format::TupleArray {
+                content: Box::new(Format::U8),
+                size: 32,
+            },
+        }),
+    ];
+    let registry = transform::apply_all(get_registry(), &transforms).unwrap();
+    assert_eq!(
+        registry["Hash"],
+        ContainerFormat::NewTypeStruct(Box::new(Format::TupleArray {
+            content: Box::new(Format::U8),
+            size: 32,
+        }))
+    );
+}
+
+#[test]
+fn test_seq_u8_as_bytes() {
+    let mut registry = Registry::new();
+    registry.insert(
+        "Blob".to_string(),
+        ContainerFormat::Struct(vec![
+            named("data", Format::Seq(Box::new(Format::U8))),
+            named("values", Format::Seq(Box::new(Format::U16))),
+        ]),
+    );
+    let registry = SeqU8AsBytes.apply(registry).unwrap();
+    assert_eq!(
+        registry["Blob"],
+        ContainerFormat::Struct(vec![
+            named("data", Format::Bytes),
+            named("values", Format::Seq(Box::new(Format::U16))),
+        ])
+    );
+}
+
+#[test]
+fn test_remove_skipped_fields() {
+    let mut registry = Registry::new();
+    registry.insert(
+        "Session".to_string(),
+        ContainerFormat::Struct(vec![
+            named("user", Format::Str),
+            named("cache", Format::Skipped(Box::new(Format::Bytes))),
+        ]),
+    );
+    registry.insert(
+        "Message".to_string(),
+        ContainerFormat::Enum(
+            vec![(
+                0,
+                Named::new(
+                    "Text",
+                    VariantFormat::Struct(vec![
+                        named("draft", Format::Skipped(Box::new(Format::Str))),
+                        named("body", Format::Str),
+                    ]),
+                ),
+            )]
+            .into_iter()
+            .collect(),
+        ),
+    );
+    let registry = RemoveSkippedFields.apply(registry).unwrap();
+    assert_eq!(
+        registry["Session"],
+        ContainerFormat::Struct(vec![named("user", Format::Str)])
+    );
+    match &registry["Message"] {
+        ContainerFormat::Enum(variants) => assert_eq!(
+            variants[&0].value,
+            VariantFormat::Struct(vec![named("body", Format::Str)])
+        ),
+        _ => panic!("Message should be an enum"),
+    }
+}
+
+#[test]
+fn test_external_types() {
+    let custom = |name: &str| Format::Custom {
+        name: name.to_string(),
+        layout: Box::new(Format::Bytes),
+    };
+    let mut registry = Registry::new();
+    registry.insert(
+        "Record".to_string(),
+        ContainerFormat::Struct(vec![
+            named("id", custom("Uuid")),
+            named("history", Format::Seq(Box::new(custom("Uuid")))),
+            named("digest", custom("Digest")),
+        ]),
+    );
+    let transform = ExternalTypes(vec!["Uuid".to_string()].into_iter().collect());
+    let registry = transform.apply(registry).unwrap();
+    assert_eq!(
+        registry["Record"],
+        ContainerFormat::Struct(vec![
+            named("id", Format::TypeName("Uuid".to_string())),
+            named(
+                "history",
+                Format::Seq(Box::new(Format::TypeName("Uuid".to_string())))
+            ),
+            named("digest", custom("Digest")),
+        ])
+    );
+}
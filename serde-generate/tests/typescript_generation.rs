@@ -1,7 +1,11 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_generate::{test_utils, typescript, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_generate::{
+    test_utils, typescript, CancellationToken, CodeGeneratorConfig, Encoding, Error,
+    JsonIntegerPolicy, SourceInstaller, VariantNaming,
+};
+use serde_reflection::Format;
 use std::fs::File;
 use std::io::{Result, Write};
 use std::process::Command;
@@ -72,8 +76,10 @@ fn test_that_ts_code_compiles_with_config(
 
     let installer = typescript::Installer::new(dir.path().to_path_buf());
     installer.install_serde_runtime().unwrap();
-    installer.install_bincode_runtime().unwrap();
-    installer.install_bcs_runtime().unwrap();
+    installer
+        .install_encoding_runtime(Encoding::Bincode)
+        .unwrap();
+    installer.install_encoding_runtime(Encoding::Bcs).unwrap();
 
     let npm_status = Command::new("npm")
         .arg("install")
@@ -111,6 +117,403 @@ fn test_that_ts_code_compiles_with_bcs() {
     test_that_ts_code_compiles_with_config(&config);
 }
 
+#[test]
+fn test_that_ts_code_compiles_with_transferable_codecs() {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bincode]);
+    let (_dir, path) = test_that_ts_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    assert!(content
+        .contains("export function serdeDataBincodeDecode(buffer: ArrayBuffer): SerdeData {"));
+    assert!(content.contains("SerdeData: serdeDataBincodeDecode,"));
+}
+
+#[test]
+fn test_that_ts_imports_follow_module_system() {
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let dir = tempdir().unwrap();
+
+    let installer = typescript::Installer::new(dir.path().to_path_buf())
+        .with_module_system(typescript::ModuleSystem::EsModule);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+    installer.install_encoding_runtimes(&config).unwrap();
+    // Only the runtimes of the configured encodings are installed.
+    assert!(dir.path().join("bcs").is_dir());
+    assert!(!dir.path().join("bincode").exists());
+
+    let content = std::fs::read_to_string(dir.path().join("testing/index.ts")).unwrap();
+    assert!(content.contains("import { Serializer } from '../serde/serializer.js';"));
+    assert!(content.contains("import { BcsSerializer } from '../bcs/bcsSerializer.js';"));
+
+    let content = std::fs::read_to_string(dir.path().join("bcs/bcsSerializer.ts")).unwrap();
+    assert!(content.contains("from '../serde/binarySerializer.js';"));
+    // Only import declarations are rewritten, not examples in comments.
+    let content = std::fs::read_to_string(dir.path().join("serde/workerPool.ts")).unwrap();
+    assert!(content.contains(" *     import { BcsDecoders } from '../my_module';"));
+
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_module_system(typescript::ModuleSystem::Deno)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("import { Deserializer } from '../serde/deserializer.ts';"));
+}
+
+#[test]
+fn test_that_ts_modules_are_split_into_parts() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_max_file_lines(Some(200));
+    let dir = tempdir().unwrap();
+
+    let installer = typescript::Installer::new(dir.path().to_path_buf());
+    installer.install_module(&config, &registry).unwrap();
+
+    let content = std::fs::read_to_string(dir.path().join("testing/index.ts")).unwrap();
+    assert!(content.contains("export class Helpers {"));
+    assert!(content.contains("export * from './index_part1';"));
+    assert!(!content.contains("export abstract class SerdeData {"));
+
+    let part = std::fs::read_to_string(dir.path().join("testing/index_part1.ts")).unwrap();
+    assert!(part.contains("import { Serializer } from '../serde/serializer';"));
+    assert!(part.contains("import { Helpers, "));
+    assert!(part.contains(" } from './index';"));
+
+    let parts = (1..)
+        .map(|index| dir.path().join(format!("testing/index_part{}.ts", index)))
+        .take_while(|path| path.exists())
+        .map(|path| std::fs::read_to_string(path).unwrap())
+        .collect::<Vec<_>>();
+    assert!(parts.len() > 1);
+    assert!(parts
+        .iter()
+        .any(|part| part.contains("export abstract class SerdeData {")));
+}
+
+#[test]
+fn test_that_ts_generation_can_be_cancelled() {
+    let registry = test_utils::get_registry().unwrap();
+    let token = CancellationToken::new();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_cancellation_token(Some(token.clone()));
+
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+
+    token.cancel();
+    let mut source = Vec::new();
+    let error = typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::Cancelled));
+    assert!(!String::from_utf8(source).unwrap().contains("export class"));
+
+    let dir = tempdir().unwrap();
+    let installer = typescript::Installer::new(dir.path().to_path_buf());
+    assert!(installer.install_module(&config, &registry).is_err());
+}
+
+#[test]
+fn test_that_ts_code_declares_zod_schemas() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_zod(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("import { z } from 'zod';"));
+    assert!(content.contains(
+        "export const SerdeDataSchema: z.ZodType<SerdeData, z.ZodTypeDef, unknown> = z.lazy(() =>"
+    ));
+    assert!(content
+        .contains("z.literal(\"UnitVariant\").transform(() => new SerdeDataVariantUnitVariant())"));
+    assert!(content.contains("z.null().transform(() => new UnitStruct())"));
+}
+
+#[test]
+fn test_that_ts_zod_schemas_parse_big_integers_from_strings() {
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_json_integers_as_strings(true);
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_zod(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(
+        "z.string().regex(/^-?[0-9]+$/).pipe(z.coerce.bigint().min(BigInt(\"0\")).max(BigInt(\"18446744073709551615\")))"
+    ));
+    assert_eq!(
+        content.matches("z.coerce.bigint()").count(),
+        content.matches(".pipe(z.coerce.bigint()").count()
+    );
+}
+
+#[test]
+fn test_that_ts_zod_schemas_follow_json_integer_policy() {
+    let registry: serde_reflection::Registry =
+        serde_yaml::from_str("Point:\n  STRUCT:\n    - x: U8\n    - y: I64\n").unwrap();
+    let schemas = |policy| {
+        let config =
+            CodeGeneratorConfig::new("testing".to_string()).with_json_integer_policy(policy);
+        let mut source = Vec::new();
+        typescript::CodeGenerator::new(&config)
+            .with_zod(true)
+            .output(&mut source, &registry)
+            .unwrap();
+        String::from_utf8(source).unwrap()
+    };
+    assert!(schemas(JsonIntegerPolicy::Strict)
+        .contains("z.object({ x: z.number().int().min(0).max(255), y: z.coerce.bigint()"));
+    assert!(schemas(JsonIntegerPolicy::Unchecked)
+        .contains("z.object({ x: z.number(), y: z.coerce.bigint()"));
+    assert!(schemas(JsonIntegerPolicy::Lenient).contains(
+        "x: z.union([z.number(), z.string().regex(/^-?[0-9]+$/).transform(Number)]).pipe(z.number().int().min(0).max(255))"
+    ));
+}
+
+#[test]
+fn test_that_ts_zod_schemas_reject_unsupported_tagged_variants() {
+    for variant in &["TUPLE: [U8, U8]", "NEWTYPE: U32"] {
+        let registry: serde_reflection::Registry = serde_yaml::from_str(&format!(
+            "Shape:\n  INTERNALLYTAGGEDENUM:\n    TAG: kind\n    VARIANTS:\n      0:\n        Pair:\n          {}\n",
+            variant
+        ))
+        .unwrap();
+        let config = CodeGeneratorConfig::new("testing".to_string());
+        let mut source = Vec::new();
+        let error = typescript::CodeGenerator::new(&config)
+            .with_zod(true)
+            .output(&mut source, &registry)
+            .unwrap_err();
+        assert!(matches!(error, Error::UnsupportedFormat { .. }));
+        assert!(error.to_string().contains("field Pair of Shape"));
+    }
+}
+
+#[test]
+fn test_that_ts_code_uses_native_time_types() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Event:\n  STRUCT:\n    - time:\n        CUSTOM:\n          NAME: SystemTime\n          LAYOUT:\n            TUPLE: [U64, U32]\n    - timeout:\n        CUSTOM:\n          NAME: Duration\n          LAYOUT:\n            TUPLE: [U64, U32]\n",
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_zod(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("public time: Date"));
+    assert!(content.contains("public timeout: number"));
+    assert!(content.contains("new Date(Number(deserializer.deserializeU64()) * 1000"));
+    assert!(content.contains("z.object({ secs: z.number(), nanos: z.number() })"));
+}
+
+#[test]
+fn test_that_ts_code_uses_text_network_addresses() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Peer:
+  STRUCT:
+    - ip: {TYPENAME: IpAddr}
+    - socket: {TYPENAME: SocketAddr}
+IpAddr:
+  ENUM:
+    0: {V4: {NEWTYPE: {TUPLEARRAY: {CONTENT: U8, SIZE: 4}}}}
+    1: {V6: {NEWTYPE: {TUPLEARRAY: {CONTENT: U8, SIZE: 16}}}}
+SocketAddr:
+  ENUM:
+    0: {V4: {NEWTYPE: {TUPLE: [{TUPLEARRAY: {CONTENT: U8, SIZE: 4}}, U16]}}}
+    1: {V6: {NEWTYPE: {TUPLE: [{TUPLEARRAY: {CONTENT: U8, SIZE: 16}}, U16]}}}
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_zod(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("from '../serde/netAddress';"));
+    assert!(content.contains("public ip: string"));
+    assert!(content.contains("serializeSocketAddr(this.socket, serializer);"));
+    assert!(content.contains("deserializeIpAddr(deserializer)"));
+    assert!(!content.contains("class IpAddr"));
+    assert!(content.contains("ip: z.string()"));
+}
+
+#[test]
+fn test_that_ts_helper_names_are_shortened() {
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_max_helper_name_length(Some(24));
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    let names = content
+        .split("static serialize")
+        .skip(1)
+        .map(|rest| rest.split('(').next().unwrap())
+        .collect::<Vec<_>>();
+    assert!(!names.is_empty());
+    assert!(names.iter().all(|name| name.len() <= 24));
+    assert!(!content.contains("ArrayToUnit"));
+}
+
+#[test]
+fn test_that_ts_helper_name_collisions_are_rejected() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_name_mangler(|format| format!("{:?}", format).chars().take(3).collect());
+    let mut source = Vec::new();
+    let error = typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+    assert!(error.to_string().contains("is used by both"));
+}
+
+#[test]
+fn test_that_ts_helper_names_distinguish_custom_formats() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Block:
+  STRUCT:
+    - height: {OPTION: {CUSTOM: {NAME: BlockHeight, LAYOUT: U64}}}
+    - count: {OPTION: U64}
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("static serializeOptionCustomBlockHeight("));
+    assert!(content.contains("static serializeOptionU64("));
+
+    // A name mangler that only looks at the wire layout of custom formats.
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_name_mangler(|format| match format {
+            Format::Option(format) => match format.as_ref() {
+                Format::Custom { layout, .. } => format!("option_{:?}", layout),
+                format => format!("option_{:?}", format),
+            },
+            format => format!("{:?}", format),
+        });
+    let mut source = Vec::new();
+    let error = typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidConfig(_)));
+    assert!(error.to_string().contains("is used by both"));
+}
+
+#[test]
+fn test_that_ts_code_compiles_with_inlined_helpers() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_inline_simple_helpers(true);
+    let (_dir, path) = test_that_ts_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    assert!(!content.contains("static serializeOptionF32("));
+    assert!(!content.contains("static serializeVectorU32("));
+    assert!(content
+        .contains("(deserializer.deserializeOptionTag() ? deserializer.deserializeF32() : null)"));
+    assert!(content.contains("static serializeMapStrToU32("));
+}
+
+#[test]
+fn test_that_ts_code_compiles_with_variant_naming() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_variant_naming(VariantNaming::Suffix("Item".to_string()));
+    let (_dir, path) = test_that_ts_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    assert!(content.contains("export class SerdeDataUnitVariantItem extends SerdeData {"));
+    assert!(content.contains("case 2: return SerdeDataUnitVariantItem.load(deserializer);"));
+    assert!(!content.contains("SerdeDataVariantUnitVariant"));
+}
+
+#[test]
+fn test_that_ts_variant_classes_are_nested() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_nested_variant_classes(true);
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("export namespace Choice {\n"));
+    assert!(content.contains("  export class B extends Choice {"));
+    assert!(content.contains("case 1: return Choice.B.load(deserializer);"));
+    assert!(!content.contains("ChoiceVariantB"));
+}
+
+#[test]
+fn test_that_ts_nested_variant_classes_do_not_shadow_containers() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_nested_variant_classes(true);
+    let mut source = Vec::new();
+    let error = typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap_err();
+    assert!(matches!(error, Error::Registry(_)));
+    assert!(error.to_string().contains("would shadow the definition"));
+}
+
+#[test]
+fn test_that_ts_code_compiles_with_c_style_enums() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_c_style_enums(true);
+    test_that_ts_code_compiles_with_config(&config);
+}
+
+#[test]
+fn test_that_ts_unit_enums_are_string_unions() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_c_style_enums(true);
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_zod(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("export type CStyleEnum = \"A\" | \"B\" | \"C\" | \"D\" | \"E\";"));
+    assert!(content.contains(
+        "export const CStyleEnumNames: { readonly [index: number]: CStyleEnum | undefined } = { 0: \"A\", 1: \"B\", 2: \"C\", 3: \"D\", 4: \"E\" };"
+    ));
+    assert!(content.contains("serializer.serializeVariantIndex(CStyleEnumIndices[value]);"));
+    assert!(content.contains("CStyleEnum.serialize(this.value, serializer);"));
+    assert!(content.contains("z.enum([\"A\", \"B\", \"C\", \"D\", \"E\"])"));
+    assert!(!content.contains("class CStyleEnum"));
+    // Enums with variant data are still classes.
+    assert!(content.contains("export abstract class SerdeData {"));
+}
+
 #[test]
 fn test_that_ts_code_compiles_with_comments() {
     let comments = vec![(vec!["SerdeData".to_string()], "Some\ncomments".to_string())]
@@ -131,3 +534,244 @@ fn test_that_ts_code_compiles_with_comments() {
 "#
     ));
 }
+
+#[test]
+fn test_that_ts_code_checks_integer_constraints() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Account:
+  STRUCT:
+    - id: {CUSTOM: {NAME: NonZeroU64, LAYOUT: U64}}
+    - percent: U8
+"#,
+    )
+    .unwrap();
+    let mut bounds = std::collections::BTreeMap::new();
+    bounds.insert(
+        vec![
+            "testing".to_string(),
+            "Account".to_string(),
+            "percent".to_string(),
+        ],
+        (Some(1), Some(100)),
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_integer_bounds(bounds);
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_zod(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(
+        "  if (!((this.id as bigint) !== BigInt(\"0\"))) { throw new Error(\"id must be nonzero\"); }\n"
+    ));
+    assert!(content.contains(
+        "  if (!(this.percent >= 1 && this.percent <= 100)) { throw new Error(\"percent must be between 1 and 100\"); }\n"
+    ));
+    assert!(content.contains(
+        ".refine((v) => (v as bigint) !== BigInt(\"0\"), \"Expected a nonzero integer\")"
+    ));
+    assert!(content.contains(
+        "percent: z.number().int().min(0).max(255).refine((v) => v >= 1 && v <= 100, \"Expected an integer between 1 and 100\")"
+    ));
+}
+
+#[test]
+fn test_that_ts_code_uses_default_values() {
+    use serde_reflection::Value;
+
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Server:
+  STRUCT:
+    - host: STR
+    - port: U16
+    - retries: U64
+    - tags:
+        SEQ: STR
+    - proxy:
+        OPTION: STR
+"#,
+    )
+    .unwrap();
+    let path = |name: &str| {
+        vec![
+            "testing".to_string(),
+            "Server".to_string(),
+            name.to_string(),
+        ]
+    };
+    let default_values = vec![
+        (path("port"), Some(Value::U16(8080))),
+        (path("retries"), Some(Value::U64(3))),
+        (path("tags"), Some(Value::Seq(vec![]))),
+        (path("proxy"), None),
+    ]
+    .into_iter()
+    .collect();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_default_values(default_values);
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .with_zod(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(
+        "constructor (public host: str, public port: uint16 = 8080, public retries: uint64 = BigInt(\"3\"), public tags: Seq<str> = [], public proxy: Optional<str>) {"
+    ));
+    assert!(content.contains("port: z.number().int().min(0).max(65535).optional()"));
+    assert!(content.contains("tags: z.array(z.string()).optional()"));
+    // Unknown default values are required.
+    assert!(content.contains("proxy: z.string().nullable() })"));
+}
+
+#[test]
+fn test_that_ts_fixtures_build_valid_samples() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"
+Color:
+  ENUM:
+    0:
+      Red: UNIT
+    1:
+      Green: UNIT
+Node:
+  ENUM:
+    0:
+      Leaf: UNIT
+    1:
+      Branch:
+        TUPLE:
+          - TYPENAME: Node
+          - TYPENAME: Node
+Pair:
+  TUPLESTRUCT:
+    - U64
+    - CHAR
+Server:
+  STRUCT:
+    - host: STR
+    - port: U16
+    - id:
+        CUSTOM:
+          NAME: NonZeroU32
+          LAYOUT: U32
+    - color:
+        TYPENAME: Color
+    - tree:
+        TYPENAME: Node
+    - tags:
+        SEQ: STR
+    - proxy:
+        OPTION: STR
+    - pair:
+        TYPENAME: Pair
+"#,
+    )
+    .unwrap();
+    let integer_bounds = vec![(
+        vec![
+            "testing".to_string(),
+            "Server".to_string(),
+            "port".to_string(),
+        ],
+        (Some(1024), None),
+    )]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_c_style_enums(true)
+        .with_integer_bounds(integer_bounds)
+        .with_fixtures(true);
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .output_fixtures(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(
+        content.contains("import { Color, Node, NodeVariantLeaf, Pair, Server } from './index';")
+    );
+    // Enums use their first variant, which terminates recursive definitions.
+    assert!(content.contains("static sample(): Color {\n    return \"Red\";"));
+    assert!(content.contains("return new NodeVariantLeaf();"));
+    assert!(
+        content.contains("const fields = { field0: BigInt(\"0\"), field1: \"a\", ...overrides };")
+    );
+    // Integer samples satisfy bounds and nonzero constraints.
+    assert!(content.contains(
+        "const fields = { host: \"\", port: 1024, id: 1, color: ColorFixtures.sample(), tree: NodeFixtures.sample(), tags: [], proxy: null, pair: PairFixtures.sample(), ...overrides };"
+    ));
+    assert!(content.contains("static sample(overrides: Partial<Server> = {}): Server {"));
+    assert!(content.contains("return new Server(fields.host, fields.port, fields.id, fields.color, fields.tree, fields.tags, fields.proxy, fields.pair);"));
+
+    // Fixtures are installed next to the module.
+    let dir = tempdir().unwrap();
+    typescript::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .unwrap();
+    assert!(dir.path().join("testing/fixtures.ts").exists());
+}
+
+#[test]
+fn test_that_ts_code_uses_native_sets() {
+    let registry: serde_reflection::Registry =
+        serde_yaml::from_str("Tags:\n  STRUCT:\n    - names:\n        SET: STR\n").unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains("constructor (public names: Set<str>) {"));
+    assert!(
+        content.contains("static serializeSetStr(value: Set<str>, serializer: Serializer): void {")
+    );
+    assert!(content.contains("serializer.sortMapEntries(offsets);"));
+    assert!(content.contains("const obj = new Set<str>();"));
+}
+
+#[test]
+fn test_that_ts_code_marks_deprecated_definitions() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        "Config:\n  STRUCT:\n    - legacy: BOOL\nChoice:\n  ENUM:\n    0:\n      Old: UNIT\n    1:\n      New: UNIT\n",
+    )
+    .unwrap();
+    let deprecations = vec![
+        (
+            vec!["testing".to_string(), "Config".to_string()],
+            "Use Settings instead.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Config".to_string(),
+                "legacy".to_string(),
+            ],
+            "No longer read.".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "Choice".to_string(),
+                "Old".to_string(),
+            ],
+            "Use New.".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_deprecations(deprecations);
+
+    let mut source = Vec::new();
+    typescript::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+    assert!(content.contains(" * @deprecated Use Settings instead.\n */\nexport class Config {"));
+    assert!(
+        content.contains("constructor (/** @deprecated No longer read. */ public legacy: bool)")
+    );
+    assert!(content
+        .contains(" * @deprecated Use New.\n */\nexport class ChoiceVariantOld extends Choice {"));
+}
@@ -0,0 +1,48 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{test_utils, transcode::Framing, wireshark, CodeGeneratorConfig};
+
+#[test]
+fn test_that_wireshark_dissector_parses_containers() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut source = Vec::new();
+    wireshark::CodeGenerator::new(&config)
+        .with_root_type(Some("Test".to_string()))
+        .with_framing(Framing::Uleb128)
+        .with_port(Some(7000))
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+
+    assert!(content.contains(r#"local proto = Proto("testing", "testing messages")"#));
+    assert!(content.contains("local read_len = read_uleb128"));
+    assert!(content.contains(
+        r#"types["Test"] = parse_struct({{"a", parse_seq(parse_u32)}, {"b", parse_tuple({parse_i64, parse_u64})}, {"c", named("Choice")}})"#
+    ));
+    assert!(content.contains(
+        r#"types["Choice"] = parse_enum({
+    [0] = {"A", parse_unit},
+    [1] = {"B", parse_u64},
+    [2] = {"C", parse_struct({{"x", parse_u8}})},
+})"#
+    ));
+    assert!(content.contains(r#"local ok, err = pcall(types["Test"], message, 0, item, "Test")"#));
+    assert!(content.contains(r#"DissectorTable.get("tcp.port"):add(7000, proto)"#));
+}
+
+#[test]
+fn test_that_wireshark_dissector_follows_bincode() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![serde_generate::Encoding::Bincode]);
+    let mut source = Vec::new();
+    wireshark::CodeGenerator::new(&config)
+        .output(&mut source, &registry)
+        .unwrap();
+    let content = String::from_utf8(source).unwrap();
+
+    assert!(content.contains("return buffer(offset, 8):le_uint64():tonumber(), offset + 8"));
+    assert!(!content.contains("proto.dissector"));
+}
@@ -0,0 +1,899 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! This crate provides `#[derive(SerdeReflectDoc)]`, which implements the trait
+//! `serde_reflection::Documented` by recording the doc comments of a type, of its fields and
+//! variants, and of the fields of its variants.
+//!
+//! It also provides `#[derive(SerdeReflectNames)]`, which implements the trait
+//! `serde_reflection::SourceNamed` by recording the Rust identifiers of the fields and variants
+//! whose Serde names differ.
+//!
+//! `#[derive(SerdeReflectSkipped)]` implements the trait `serde_reflection::Skipping` by
+//! recording the named fields skipped by Serde, together with their formats as inferred from
+//! their Rust types: primitive types, strings, options, standard collections, tuples, arrays,
+//! and smart pointers are recognized, while other types are given by name.
+//!
+//! Finally, `#[derive(SerdeReflectDefaults)]` implements the trait `serde_reflection::Defaulted`
+//! by recording the named fields with a Serde default, i.e. `#[serde(default)]` or
+//! `#[serde(default = "..")]` on the field or on the struct. Default values are computed when
+//! the trait is called, except for generic types and for fields with custom serializers.
+//!
+//! `#[derive(Reflect)]` implements the trait `serde_reflection::Reflect`, which computes the
+//! formats of a type and of the containers it uses without tracing. Type parameters and the
+//! types of fields must implement `Reflect` as well. Internally tagged enums are supported, while
+//! the attributes `#[serde(untagged)]`, `#[serde(content = "..")]`, `#[serde(flatten)]`, and
+//! custom (de)serializers are rejected.
+//!
+//! Definitions are indexed by their Serde names, taking the attributes `#[serde(rename = "..")]`,
+//! `#[serde(rename_all = "..")]`, and `#[serde(rename_all_fields = "..")]` into account. Other
+//! forms of renaming (e.g. distinct names for serialization and deserialization) are not
+//! supported.
+//!
+//! Use the feature `derive` of `serde-reflection` rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprPath, Field, Fields,
+    GenericArgument, Ident, Lit, LitStr, Meta, NestedMeta, PathArguments, Type, Variant,
+};
+
+#[proc_macro_derive(SerdeReflectDoc)]
+pub fn derive_serde_reflect_doc(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    if let Err(error) = check_rename_rules(&input) {
+        return error.to_compile_error().into();
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = serde_name(&input.attrs).unwrap_or_else(|| ident.to_string());
+    let mut comments = Vec::new();
+    record_comment(&mut comments, vec![name.clone()], &input.attrs);
+    match &input.data {
+        Data::Struct(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            record_field_comments(&mut comments, &[name], &data.fields, rule)
+        }
+        Data::Enum(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            let fields_rule = rename_rule(&input.attrs, "rename_all_fields");
+            for variant in &data.variants {
+                let variant_name = variant_name(variant, rule);
+                let path = vec![name.clone(), variant_name];
+                record_comment(&mut comments, path.clone(), &variant.attrs);
+                let rule = rename_rule(&variant.attrs, "rename_all").or(fields_rule);
+                record_field_comments(&mut comments, &path, &variant.fields, rule);
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(ident, "SerdeReflectDoc does not support unions")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let entries = comments.iter().map(|(path, comment)| {
+        quote! { (vec![#(#path.to_string()),*], #comment.to_string()) }
+    });
+    let expanded = quote! {
+        impl #impl_generics ::serde_reflection::Documented for #ident #ty_generics #where_clause {
+            fn doc_comments() -> ::serde_reflection::DocComments {
+                vec![#(#entries),*].into_iter().collect()
+            }
+        }
+    };
+    expanded.into()
+}
+
+#[proc_macro_derive(SerdeReflectNames)]
+pub fn derive_serde_reflect_names(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    if let Err(error) = check_rename_rules(&input) {
+        return error.to_compile_error().into();
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = serde_name(&input.attrs).unwrap_or_else(|| ident.to_string());
+    let mut names = Vec::new();
+    match &input.data {
+        Data::Struct(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            record_field_names(&mut names, &[name], &data.fields, rule)
+        }
+        Data::Enum(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            let fields_rule = rename_rule(&input.attrs, "rename_all_fields");
+            for variant in &data.variants {
+                let variant_name = variant_name(variant, rule);
+                let source_name = unraw(&variant.ident);
+                let path = vec![name.clone(), variant_name.clone()];
+                if variant_name != source_name {
+                    names.push((path.clone(), source_name));
+                }
+                let rule = rename_rule(&variant.attrs, "rename_all").or(fields_rule);
+                record_field_names(&mut names, &path, &variant.fields, rule);
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(ident, "SerdeReflectNames does not support unions")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let entries = names.iter().map(|(path, source_name)| {
+        quote! { (vec![#(#path.to_string()),*], #source_name.to_string()) }
+    });
+    let expanded = quote! {
+        impl #impl_generics ::serde_reflection::SourceNamed for #ident #ty_generics #where_clause {
+            fn source_names() -> ::serde_reflection::SourceNames {
+                vec![#(#entries),*].into_iter().collect()
+            }
+        }
+    };
+    expanded.into()
+}
+
+#[proc_macro_derive(SerdeReflectSkipped)]
+pub fn derive_serde_reflect_skipped(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    if let Err(error) = check_rename_rules(&input) {
+        return error.to_compile_error().into();
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = serde_name(&input.attrs).unwrap_or_else(|| ident.to_string());
+    let mut skipped = Vec::new();
+    match &input.data {
+        Data::Struct(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            record_skipped_fields(&mut skipped, &[name], &data.fields, rule)
+        }
+        Data::Enum(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            let fields_rule = rename_rule(&input.attrs, "rename_all_fields");
+            for variant in &data.variants {
+                let path = vec![name.clone(), variant_name(variant, rule)];
+                let rule = rename_rule(&variant.attrs, "rename_all").or(fields_rule);
+                record_skipped_fields(&mut skipped, &path, &variant.fields, rule);
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(ident, "SerdeReflectSkipped does not support unions")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let entries = skipped.iter().map(|(path, position, format)| {
+        quote! { (vec![#(#path.to_string()),*], (#position, #format)) }
+    });
+    let expanded = quote! {
+        impl #impl_generics ::serde_reflection::Skipping for #ident #ty_generics #where_clause {
+            fn skipped_fields() -> ::serde_reflection::SkippedFields {
+                vec![#(#entries),*].into_iter().collect()
+            }
+        }
+    };
+    expanded.into()
+}
+
+#[proc_macro_derive(SerdeReflectDefaults)]
+pub fn derive_serde_reflect_defaults(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    if let Err(error) = check_rename_rules(&input) {
+        return error.to_compile_error().into();
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Values are only computed for non-generic types, for lack of trait bounds on the fields.
+    let context = DefaultContext {
+        container_default: serde_default(&input.attrs),
+        computed: input.generics.params.is_empty(),
+    };
+    let name = serde_name(&input.attrs).unwrap_or_else(|| ident.to_string());
+    let mut defaults = Vec::new();
+    match &input.data {
+        Data::Struct(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            record_default_values(&mut defaults, &[name], &data.fields, rule, &context)
+        }
+        Data::Enum(data) => {
+            // The default of the container does not apply to the fields of variants.
+            let context = DefaultContext {
+                container_default: None,
+                ..context
+            };
+            let rule = rename_rule(&input.attrs, "rename_all");
+            let fields_rule = rename_rule(&input.attrs, "rename_all_fields");
+            for variant in &data.variants {
+                let path = vec![name.clone(), variant_name(variant, rule)];
+                let rule = rename_rule(&variant.attrs, "rename_all").or(fields_rule);
+                record_default_values(&mut defaults, &path, &variant.fields, rule, &context);
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(ident, "SerdeReflectDefaults does not support unions")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let entries = defaults.iter().map(|(path, value)| {
+        quote! { (vec![#(#path.to_string()),*], #value) }
+    });
+    let expanded = quote! {
+        impl #impl_generics ::serde_reflection::Defaulted for #ident #ty_generics #where_clause {
+            fn default_values() -> ::serde_reflection::DefaultValues {
+                vec![#(#entries),*].into_iter().collect()
+            }
+        }
+    };
+    expanded.into()
+}
+
+#[proc_macro_derive(Reflect)]
+pub fn derive_reflect(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+    if let Err(error) = check_rename_rules(&input).and(check_reflect_attributes(&input)) {
+        return error.to_compile_error().into();
+    }
+    for param in input.generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(::serde_reflection::Reflect));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = serde_name(&input.attrs).unwrap_or_else(|| ident.to_string());
+    let container = quote! { ::serde_reflection::ContainerFormat };
+    let mut dependencies = Vec::new();
+    let value = match &input.data {
+        Data::Struct(data) if serde_flag(&input.attrs, "transparent") => {
+            let ty = match data.fields.iter().find(|field| is_reflected(field)) {
+                Some(field) => &field.ty,
+                None => {
+                    return syn::Error::new_spanned(ident, "transparent structs need a field")
+                        .to_compile_error()
+                        .into();
+                }
+            };
+            let expanded = quote! {
+                impl #impl_generics ::serde_reflection::Reflect for #ident #ty_generics #where_clause {
+                    fn format() -> ::serde_reflection::Format {
+                        <#ty as ::serde_reflection::Reflect>::format()
+                    }
+
+                    fn register(registry: &mut ::serde_reflection::Registry) {
+                        <#ty as ::serde_reflection::Reflect>::register(registry)
+                    }
+                }
+            };
+            return expanded.into();
+        }
+        Data::Struct(data) => {
+            let rule = rename_rule(&input.attrs, "rename_all");
+            dependencies.extend(reflected_types(&data.fields));
+            match &data.fields {
+                Fields::Unit => quote! { #container::UnitStruct },
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    let formats = quote_field_formats(&data.fields);
+                    quote! { #container::NewTypeStruct(::std::boxed::Box::new(#(#formats)*)) }
+                }
+                Fields::Unnamed(_) => {
+                    let formats = quote_field_formats(&data.fields);
+                    quote! { #container::TupleStruct(vec![#(#formats),*]) }
+                }
+                Fields::Named(_) => {
+                    let fields = quote_named_formats(&data.fields, rule);
+                    quote! { #container::Struct(vec![#(#fields),*]) }
+                }
+            }
+        }
+        Data::Enum(data) => {
+            let variant_format = quote! { ::serde_reflection::VariantFormat };
+            let rule = rename_rule(&input.attrs, "rename_all");
+            let fields_rule = rename_rule(&input.attrs, "rename_all_fields");
+            // Skipped variants are not numbered by Serde when deserializing.
+            let variants = data
+                .variants
+                .iter()
+                .filter(|variant| {
+                    !["skip", "skip_deserializing"]
+                        .iter()
+                        .any(|key| serde_flag(&variant.attrs, key))
+                })
+                .enumerate()
+                .map(|(index, variant)| {
+                    let index = index as u32;
+                    let variant_name = variant_name(variant, rule);
+                    let rule = rename_rule(&variant.attrs, "rename_all").or(fields_rule);
+                    dependencies.extend(reflected_types(&variant.fields));
+                    let value = match &variant.fields {
+                        Fields::Unit => quote! { #variant_format::Unit },
+                        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                            let formats = quote_field_formats(&variant.fields);
+                            quote! { #variant_format::NewType(::std::boxed::Box::new(#(#formats)*)) }
+                        }
+                        Fields::Unnamed(_) => {
+                            let formats = quote_field_formats(&variant.fields);
+                            quote! { #variant_format::Tuple(vec![#(#formats),*]) }
+                        }
+                        Fields::Named(_) => {
+                            let fields = quote_named_formats(&variant.fields, rule);
+                            quote! { #variant_format::Struct(vec![#(#fields),*]) }
+                        }
+                    };
+                    quote! {
+                        (#index, ::serde_reflection::Named::new(#variant_name.to_string(), #value))
+                    }
+                })
+                .collect::<Vec<_>>();
+            let variants = quote! { vec![#(#variants),*].into_iter().collect() };
+            match serde_attribute(&input.attrs, "tag") {
+                Some(tag) => quote! {
+                    #container::InternallyTaggedEnum {
+                        tag: #tag.to_string(),
+                        variants: #variants,
+                    }
+                },
+                None => quote! { #container::Enum(#variants) },
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(ident, "Reflect does not support unions")
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let expanded = quote! {
+        impl #impl_generics ::serde_reflection::Reflect for #ident #ty_generics #where_clause {
+            fn format() -> ::serde_reflection::Format {
+                ::serde_reflection::Format::TypeName(#name.to_string())
+            }
+
+            fn register(registry: &mut ::serde_reflection::Registry) {
+                // Registering the container first terminates recursive definitions.
+                if registry.contains_key(#name) {
+                    return;
+                }
+                registry.insert(#name.to_string(), #value);
+                #(<#dependencies as ::serde_reflection::Reflect>::register(registry);)*
+            }
+        }
+    };
+    expanded.into()
+}
+
+/// Reject the Serde attributes whose formats cannot be computed by `#[derive(Reflect)]`.
+fn check_reflect_attributes(input: &DeriveInput) -> syn::Result<()> {
+    let mut attrs = vec![(&input.ident, &input.attrs, CONTAINER_UNSUPPORTED)];
+    let mut fields = Vec::new();
+    match &input.data {
+        Data::Struct(data) => fields.extend(&data.fields),
+        Data::Enum(data) => {
+            for variant in &data.variants {
+                attrs.push((&variant.ident, &variant.attrs, FIELD_UNSUPPORTED));
+                fields.extend(&variant.fields);
+            }
+        }
+        Data::Union(_) => (),
+    }
+    for (ident, attrs, keys) in attrs {
+        check_unsupported(ident, attrs, keys)?;
+    }
+    for field in fields {
+        check_unsupported(&field.ty, &field.attrs, FIELD_UNSUPPORTED)?;
+    }
+    Ok(())
+}
+
+const CONTAINER_UNSUPPORTED: &[&str] =
+    &["untagged", "content", "from", "try_from", "into", "remote"];
+
+const FIELD_UNSUPPORTED: &[&str] = &["flatten", "with", "deserialize_with", "serialize_with"];
+
+fn check_unsupported<T: quote::ToTokens>(
+    tokens: T,
+    attrs: &[Attribute],
+    keys: &[&str],
+) -> syn::Result<()> {
+    for key in keys {
+        if serde_flag(attrs, key) || serde_attribute(attrs, key).is_some() {
+            let message = format!("Reflect does not support #[serde({})]", key);
+            return Err(syn::Error::new_spanned(tokens, message));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a field is visible to Serde when deserializing.
+fn is_reflected(field: &Field) -> bool {
+    !["skip", "skip_deserializing"]
+        .iter()
+        .any(|key| serde_flag(&field.attrs, key))
+}
+
+/// The types of the fields of a struct or a variant that are visible to Serde.
+fn reflected_types(fields: &Fields) -> Vec<Type> {
+    fields
+        .iter()
+        .filter(|field| is_reflected(field))
+        .map(|field| field.ty.clone())
+        .collect()
+}
+
+/// Expressions building the formats of the fields of a struct or a variant.
+fn quote_field_formats(fields: &Fields) -> Vec<proc_macro2::TokenStream> {
+    reflected_types(fields)
+        .iter()
+        .map(|ty| quote! { <#ty as ::serde_reflection::Reflect>::format() })
+        .collect()
+}
+
+/// Expressions building the named formats of the named fields of a struct or a variant.
+fn quote_named_formats(fields: &Fields, rule: Option<RenameRule>) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter(|field| is_reflected(field))
+        .map(|field| {
+            let name = field_name(field, rule);
+            let ty = &field.ty;
+            quote! {
+                ::serde_reflection::Named::new(#name.to_string(), <#ty as ::serde_reflection::Reflect>::format())
+            }
+        })
+        .collect()
+}
+
+/// Record the doc comments of the named fields of a struct or a variant.
+fn record_field_comments(
+    comments: &mut Vec<(Vec<String>, String)>,
+    prefix: &[String],
+    fields: &Fields,
+    rule: Option<RenameRule>,
+) {
+    if let Fields::Named(fields) = fields {
+        for field in &fields.named {
+            let mut path = prefix.to_vec();
+            path.push(field_name(field, rule));
+            record_comment(comments, path, &field.attrs);
+        }
+    }
+}
+
+/// Record the identifiers of the named fields of a struct or a variant that are renamed.
+fn record_field_names(
+    names: &mut Vec<(Vec<String>, String)>,
+    prefix: &[String],
+    fields: &Fields,
+    rule: Option<RenameRule>,
+) {
+    if let Fields::Named(fields) = fields {
+        for field in &fields.named {
+            let field_name = field_name(field, rule);
+            let source_name = unraw(field.ident.as_ref().expect("named fields have identifiers"));
+            if field_name != source_name {
+                let mut path = prefix.to_vec();
+                path.push(field_name);
+                names.push((path, source_name));
+            }
+        }
+    }
+}
+
+/// Record the position and the format of the named fields of a struct or a variant that are
+/// skipped by Serde.
+fn record_skipped_fields(
+    skipped: &mut Vec<(Vec<String>, usize, proc_macro2::TokenStream)>,
+    prefix: &[String],
+    fields: &Fields,
+    rule: Option<RenameRule>,
+) {
+    if let Fields::Named(fields) = fields {
+        for (position, field) in fields.named.iter().enumerate() {
+            let is_skipped = ["skip", "skip_serializing", "skip_deserializing"]
+                .iter()
+                .any(|key| serde_flag(&field.attrs, key));
+            if is_skipped {
+                let mut path = prefix.to_vec();
+                path.push(field_name(field, rule));
+                skipped.push((path, position, quote_format(&field.ty)));
+            }
+        }
+    }
+}
+
+/// The default of a container, applying to its fields when computing default values.
+struct DefaultContext {
+    container_default: Option<Option<ExprPath>>,
+    computed: bool,
+}
+
+/// Record the named fields of a struct or a variant that have a Serde default, together with
+/// an expression computing their default values.
+fn record_default_values(
+    defaults: &mut Vec<(Vec<String>, proc_macro2::TokenStream)>,
+    prefix: &[String],
+    fields: &Fields,
+    rule: Option<RenameRule>,
+    context: &DefaultContext,
+) {
+    if let Fields::Named(fields) = fields {
+        for field in &fields.named {
+            let is_skipped = ["skip", "skip_deserializing"]
+                .iter()
+                .any(|key| serde_flag(&field.attrs, key));
+            if is_skipped {
+                continue;
+            }
+            let field_ident = field.ident.as_ref().expect("named fields have identifiers");
+            let ty = &field.ty;
+            let value = match (serde_default(&field.attrs), &context.container_default) {
+                (None, None) => continue,
+                // Custom serializers may not agree with the `Serialize` implementation.
+                _ if !context.computed
+                    || ["with", "serialize_with"]
+                        .iter()
+                        .any(|key| serde_attribute(&field.attrs, key).is_some()) =>
+                {
+                    None
+                }
+                (Some(None), _) => Some(quote! { <#ty as ::std::default::Default>::default() }),
+                (Some(Some(function)), _) => Some(quote! { #function() }),
+                (None, Some(None)) => {
+                    Some(quote! { <Self as ::std::default::Default>::default().#field_ident })
+                }
+                (None, Some(Some(function))) => Some(quote! { #function().#field_ident }),
+            };
+            let value = match value {
+                Some(value) => quote! { ::serde_reflection::default_value(&#value) },
+                None => quote! { None },
+            };
+            let mut path = prefix.to_vec();
+            path.push(field_name(field, rule));
+            defaults.push((path, value));
+        }
+    }
+}
+
+/// An expression building the `serde_reflection::Format` of the Rust type `ty`, following
+/// the Serde data model where possible, e.g. sets are sequences.
+fn quote_format(ty: &Type) -> proc_macro2::TokenStream {
+    let format = quote! { ::serde_reflection::Format };
+    match ty {
+        Type::Paren(ty) => quote_format(&ty.elem),
+        Type::Group(ty) => quote_format(&ty.elem),
+        Type::Reference(ty) => quote_format(&ty.elem),
+        Type::Tuple(ty) if ty.elems.is_empty() => quote! { #format::Unit },
+        Type::Tuple(ty) => {
+            let formats = ty.elems.iter().map(quote_format);
+            quote! { #format::Tuple(vec![#(#formats),*]) }
+        }
+        Type::Array(ty) => {
+            let content = quote_format(&ty.elem);
+            match &ty.len {
+                Expr::Lit(len) => {
+                    let size = &len.lit;
+                    quote! {
+                        #format::TupleArray {
+                            content: ::std::boxed::Box::new(#content),
+                            size: #size,
+                        }
+                    }
+                }
+                _ => quote! { #format::Seq(::std::boxed::Box::new(#content)) },
+            }
+        }
+        Type::Slice(ty) => {
+            let content = quote_format(&ty.elem);
+            quote! { #format::Seq(::std::boxed::Box::new(#content)) }
+        }
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last().expect("paths are not empty");
+            let arguments = match &segment.arguments {
+                PathArguments::AngleBracketed(arguments) => arguments
+                    .args
+                    .iter()
+                    .filter_map(|argument| match argument {
+                        GenericArgument::Type(ty) => Some(quote_format(ty)),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let name = segment.ident.to_string();
+            match (name.as_str(), arguments.as_slice()) {
+                ("bool", []) => quote! { #format::Bool },
+                ("i8", []) => quote! { #format::I8 },
+                ("i16", []) => quote! { #format::I16 },
+                ("i32", []) => quote! { #format::I32 },
+                ("i64", []) | ("isize", []) => quote! { #format::I64 },
+                ("i128", []) => quote! { #format::I128 },
+                ("u8", []) => quote! { #format::U8 },
+                ("u16", []) => quote! { #format::U16 },
+                ("u32", []) => quote! { #format::U32 },
+                ("u64", []) | ("usize", []) => quote! { #format::U64 },
+                ("u128", []) => quote! { #format::U128 },
+                ("f32", []) => quote! { #format::F32 },
+                ("f64", []) => quote! { #format::F64 },
+                ("char", []) => quote! { #format::Char },
+                ("String", []) | ("str", []) => quote! { #format::Str },
+                ("Option", [content]) => {
+                    quote! { #format::Option(::std::boxed::Box::new(#content)) }
+                }
+                ("Vec", [content]) | ("VecDeque", [content]) | ("LinkedList", [content]) => {
+                    quote! { #format::Seq(::std::boxed::Box::new(#content)) }
+                }
+                ("BTreeSet", [content]) | ("HashSet", [content]) => {
+                    quote! { #format::Set(::std::boxed::Box::new(#content)) }
+                }
+                ("BTreeMap", [key, value]) | ("HashMap", [key, value]) => quote! {
+                    #format::Map {
+                        key: ::std::boxed::Box::new(#key),
+                        value: ::std::boxed::Box::new(#value),
+                    }
+                },
+                ("Box", [content]) | ("Rc", [content]) | ("Arc", [content]) => content.clone(),
+                _ => quote! { #format::TypeName(#name.to_string()) },
+            }
+        }
+        _ => {
+            let name = quote!(#ty).to_string();
+            quote! { #format::TypeName(#name.to_string()) }
+        }
+    }
+}
+
+/// The Serde name of a named field.
+fn field_name(field: &Field, rule: Option<RenameRule>) -> String {
+    serde_name(&field.attrs).unwrap_or_else(|| {
+        let name = unraw(field.ident.as_ref().expect("named fields have identifiers"));
+        match rule {
+            Some(rule) => rule.apply_to_field(&name),
+            None => name,
+        }
+    })
+}
+
+/// The Serde name of a variant.
+fn variant_name(variant: &Variant, rule: Option<RenameRule>) -> String {
+    serde_name(&variant.attrs).unwrap_or_else(|| {
+        let name = unraw(&variant.ident);
+        match rule {
+            Some(rule) => rule.apply_to_variant(&name),
+            None => name,
+        }
+    })
+}
+
+/// The name of an identifier without the prefix `r#` of raw identifiers, as used by Serde.
+fn unraw(ident: &Ident) -> String {
+    let name = ident.to_string();
+    name.strip_prefix("r#").map(str::to_string).unwrap_or(name)
+}
+
+/// Record the doc comment found in `attrs`, if any.
+fn record_comment(
+    comments: &mut Vec<(Vec<String>, String)>,
+    path: Vec<String>,
+    attrs: &[Attribute],
+) {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(line) => Some(line.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(str::to_string).unwrap_or(line))
+        .collect::<Vec<_>>();
+    let comment = lines.join("\n").trim().to_string();
+    if !comment.is_empty() {
+        comments.push((path, comment));
+    }
+}
+
+/// The name given by `#[serde(rename = "..")]` in `attrs`, if any.
+fn serde_name(attrs: &[Attribute]) -> Option<String> {
+    serde_attribute(attrs, "rename")
+}
+
+/// Whether the flag `#[serde(<key>)]` (e.g. `skip`) is present in `attrs`.
+fn serde_flag(attrs: &[Attribute], key: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident(key)))
+}
+
+/// The default given by `#[serde(default)]` (i.e. `Some(None)`) or by
+/// `#[serde(default = "path")]` (i.e. `Some(Some(path))`) in `attrs`, if any.
+fn serde_default(attrs: &[Attribute]) -> Option<Option<ExprPath>> {
+    if serde_flag(attrs, "default") {
+        return Some(None);
+    }
+    let function = serde_attribute(attrs, "default")?;
+    syn::parse_str(&function).ok().map(Some)
+}
+
+/// The rule given by `#[serde(<key> = "..")]` in `attrs` (e.g. `rename_all`), if any.
+fn rename_rule(attrs: &[Attribute], key: &str) -> Option<RenameRule> {
+    serde_attribute(attrs, key).and_then(|rule| RenameRule::new(&rule))
+}
+
+/// Reject the renaming rules of a container and of its variants that are unknown to Serde.
+fn check_rename_rules(input: &DeriveInput) -> syn::Result<()> {
+    let mut rules = vec![
+        serde_attribute_lit(&input.attrs, "rename_all"),
+        serde_attribute_lit(&input.attrs, "rename_all_fields"),
+    ];
+    if let Data::Enum(data) = &input.data {
+        for variant in &data.variants {
+            rules.push(serde_attribute_lit(&variant.attrs, "rename_all"));
+        }
+    }
+    for rule in rules.into_iter().flatten() {
+        if RenameRule::new(&rule.value()).is_none() {
+            let message = format!("unknown rename rule `{}`", rule.value());
+            return Err(syn::Error::new_spanned(rule, message));
+        }
+    }
+    Ok(())
+}
+
+/// The string value of `#[serde(<key> = "..")]` in `attrs`, if any.
+fn serde_attribute(attrs: &[Attribute], key: &str) -> Option<String> {
+    serde_attribute_lit(attrs, key).map(|value| value.value())
+}
+
+/// The string literal of `#[serde(<key> = "..")]` in `attrs`, if any.
+fn serde_attribute_lit(attrs: &[Attribute], key: &str) -> Option<LitStr> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|nested| match nested {
+            NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident(key) => match meta.lit {
+                Lit::Str(value) => Some(value),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+/// The renaming rules of `#[serde(rename_all = "..")]`.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn new(rule: &str) -> Option<Self> {
+        use RenameRule::*;
+        let rule = match rule {
+            "lowercase" => Lower,
+            "UPPERCASE" => Upper,
+            "PascalCase" => Pascal,
+            "camelCase" => Camel,
+            "snake_case" => Snake,
+            "SCREAMING_SNAKE_CASE" => ScreamingSnake,
+            "kebab-case" => Kebab,
+            "SCREAMING-KEBAB-CASE" => ScreamingKebab,
+            _ => return None,
+        };
+        Some(rule)
+    }
+
+    /// Rename a variant, assuming that Rust variants are written in Pascal.
+    fn apply_to_variant(self, variant: &str) -> String {
+        use RenameRule::*;
+        match self {
+            Pascal => variant.to_string(),
+            Lower => variant.to_ascii_lowercase(),
+            Upper => variant.to_ascii_uppercase(),
+            Camel => lowercase_first(variant),
+            Snake => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            ScreamingSnake => Snake.apply_to_variant(variant).to_ascii_uppercase(),
+            Kebab => Snake.apply_to_variant(variant).replace('_', "-"),
+            ScreamingKebab => ScreamingSnake.apply_to_variant(variant).replace('_', "-"),
+        }
+    }
+
+    /// Rename a field, assuming that Rust fields are written in snake_case.
+    fn apply_to_field(self, field: &str) -> String {
+        use RenameRule::*;
+        match self {
+            Lower | Snake => field.to_string(),
+            Upper | ScreamingSnake => field.to_ascii_uppercase(),
+            Pascal => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            Camel => lowercase_first(&Pascal.apply_to_field(field)),
+            Kebab => field.replace('_', "-"),
+            ScreamingKebab => ScreamingSnake.apply_to_field(field).replace('_', "-"),
+        }
+    }
+}
+
+/// Lowercase the first character of `name`, if any (e.g. an empty name for the field `__`).
+fn lowercase_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn camel_case_of_underscores() {
+        assert_eq!(RenameRule::Camel.apply_to_field("__"), "");
+        assert_eq!(RenameRule::Camel.apply_to_field("_x"), "x");
+        assert_eq!(RenameRule::Camel.apply_to_variant(""), "");
+    }
+
+    #[test]
+    fn unknown_rules() {
+        assert!(RenameRule::new("PascalCase").is_some());
+        assert!(RenameRule::new("Pascal").is_none());
+        let input: DeriveInput = syn::parse_quote! {
+            #[serde(rename_all = "Pascal")]
+            struct Point { x_coordinate: u8 }
+        };
+        let error = check_rename_rules(&input).unwrap_err();
+        assert_eq!(error.to_string(), "unknown rename rule `Pascal`");
+    }
+}
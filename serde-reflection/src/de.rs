@@ -3,9 +3,9 @@
 
 use crate::{
     error::{Error, Result},
-    format::{ContainerFormat, ContainerFormatEntry, Format, FormatHolder, Named, VariantFormat},
-    trace::{Samples, Tracer},
-    value::IntoSeqDeserializer,
+    format::{ContainerFormat, Format, FormatHolder, Named, Unify, VariantFormat},
+    trace::{arbitrary_precision_number, check_layout, nonzero_integer_name, Samples, Tracer},
+    value::visit_arbitrary_precision_number,
 };
 use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
 use std::collections::BTreeMap;
@@ -19,6 +19,8 @@ pub(crate) struct Deserializer<'de, 'a> {
     tracer: &'a mut Tracer,
     samples: &'de Samples,
     format: &'a mut Format,
+    /// Name of the deserialized type, when known, as given by `std::any::type_name`.
+    type_name: Option<&'static str>,
 }
 
 impl<'de, 'a> Deserializer<'de, 'a> {
@@ -31,6 +33,179 @@ impl<'de, 'a> Deserializer<'de, 'a> {
             tracer,
             samples,
             format,
+            type_name: None,
+        }
+    }
+
+    /// Set the name of the deserialized type, so that type hints apply to it.
+    pub(crate) fn with_type_name(mut self, type_name: Option<&'static str>) -> Self {
+        self.type_name = type_name;
+        self
+    }
+
+    /// Deserialize a value with `seed`, tracing it as a custom format if its type was declared
+    /// with `TracerConfig::map_external_type`. Samples of such types are used when available.
+    pub(crate) fn deserialize_seed<S>(self, seed: S) -> Result<S::Value>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let type_name = match self.type_name {
+            Some(type_name) => type_name,
+            None => return seed.deserialize(self),
+        };
+        let custom_format = match self.tracer.external_format(type_name) {
+            Some(custom_format) => custom_format,
+            None => return seed.deserialize(self),
+        };
+        self.format.unify(custom_format.clone())?;
+        if let Some(sample) = self.samples.deserializable_value(type_name) {
+            return seed.deserialize(sample.into_deserializer());
+        }
+        let mut format = Format::unknown();
+        let inner = Deserializer::new(self.tracer, self.samples, &mut format)
+            .with_type_name(Some(type_name));
+        let value = seed.deserialize(inner)?;
+        if let Format::Custom { layout, .. } = &custom_format {
+            check_layout(format, layout)?;
+        }
+        Ok(value)
+    }
+
+    /// Visit the default value of a format given by a type hint, i.e. zero, `false`, an empty
+    /// string, `None`, or an empty sequence or map.
+    fn visit_hinted_format<V>(self, format: &Format, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match format {
+            Format::Unit => visitor.visit_unit(),
+            Format::Bool => visitor.visit_bool(false),
+            Format::I8 => visitor.visit_i8(0),
+            Format::I16 => visitor.visit_i16(0),
+            Format::I32 => visitor.visit_i32(0),
+            Format::I64 => visitor.visit_i64(0),
+            Format::I128 => visitor.visit_i128(0),
+            Format::U8 => visitor.visit_u8(0),
+            Format::U16 => visitor.visit_u16(0),
+            Format::U32 => visitor.visit_u32(0),
+            Format::U64 => visitor.visit_u64(0),
+            Format::U128 => visitor.visit_u128(0),
+            Format::F32 => visitor.visit_f32(0.0),
+            Format::F64 => visitor.visit_f64(0.0),
+            Format::Char => visitor.visit_char('\0'),
+            Format::Str => visitor.visit_str(""),
+            Format::Bytes => visitor.visit_bytes(&[]),
+            Format::Option(_) => visitor.visit_none(),
+            Format::Seq(_) => visitor.visit_seq(SeqDeserializer::new(
+                self.tracer,
+                self.samples,
+                std::iter::empty(),
+            )),
+            Format::Map { .. } => visitor.visit_map(SeqDeserializer::new(
+                self.tracer,
+                self.samples,
+                std::iter::empty(),
+            )),
+            Format::Custom { .. } if *format == arbitrary_precision_number() => {
+                visit_arbitrary_precision_number("0", visitor)
+            }
+            _ => Err(Error::NotSupported(
+                "type hints other than primitive formats, options, sequences, and maps",
+            )),
+        }
+    }
+
+    /// Deserialize a newtype struct declared as a custom format in the tracer configuration.
+    fn deserialize_custom_format<V>(
+        self,
+        name: &'static str,
+        custom_format: Format,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.format.unify(custom_format)?;
+        if self.tracer.config.record_samples_for_newtype_structs {
+            // If a value was recorded during serialization, use it.
+            if let Some(sample) = self.samples.value(name) {
+                return visitor
+                    .visit_newtype_struct(sample.into_deserializer())
+                    .map_err(|err| match err {
+                        Error::DeserializationError(msg) => {
+                            let layout = self.tracer.config.custom_formats[name].clone();
+                            let format = ContainerFormat::NewTypeStruct(Box::new(layout));
+                            Error::UnexpectedDeserializationFormat(name, format, msg)
+                        }
+                        _ => err,
+                    });
+            }
+            self.tracer.request_sample(name)?;
+        }
+        let mut format = Format::unknown();
+        let inner = Deserializer::new(self.tracer, self.samples, &mut format);
+        let value = visitor.visit_newtype_struct(inner)?;
+        self.tracer.check_custom_layout(name, format)?;
+        Ok(value)
+    }
+
+    /// Deserialize a newtype struct declared as a type alias in the tracer configuration.
+    fn deserialize_type_alias<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut format = Format::unknown();
+        self.format.unify(Format::Custom {
+            name: name.into(),
+            layout: Box::new(format.clone()),
+        })?;
+        if self.tracer.config.record_samples_for_newtype_structs {
+            // If a value was recorded during serialization, use it.
+            if let Some(sample) = self.samples.value(name) {
+                return visitor.visit_newtype_struct(sample.into_deserializer());
+            }
+        }
+        let inner = Deserializer::new(self.tracer, self.samples, &mut format);
+        visitor.visit_newtype_struct(inner)
+    }
+
+    fn deserialize_decimal<V>(self, name: &'static str, scale: u32, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut mantissa = Format::unknown();
+        self.format.unify(Format::Decimal {
+            mantissa: Box::new(mantissa.clone()),
+            scale,
+        })?;
+        if self.tracer.config.record_samples_for_newtype_structs {
+            // If a value was recorded during serialization, use it.
+            if let Some(sample) = self.samples.value(name) {
+                return visitor.visit_newtype_struct(sample.into_deserializer());
+            }
+        }
+        let inner = Deserializer::new(self.tracer, self.samples, &mut mantissa);
+        let value = visitor.visit_newtype_struct(inner)?;
+        self.tracer.check_decimal_mantissa(&mantissa)?;
+        Ok(value)
+    }
+
+    /// Unify the current format with the integer `format`, or with the custom format of the
+    /// corresponding nonzero integer (e.g. `NonZeroU64`) if the deserialized type is one and
+    /// `TracerConfig::nonzero_integers` is set. Returns whether the sample value must be nonzero.
+    fn unify_integer(&mut self, format: Format) -> Result<bool> {
+        match self.type_name.and_then(nonzero_integer_name) {
+            Some(name) if self.tracer.config.nonzero_integers => {
+                self.format.unify(Format::Custom {
+                    name: name.into(),
+                    layout: Box::new(format),
+                })?;
+                Ok(true)
+            }
+            nonzero => {
+                self.format.unify(format)?;
+                Ok(nonzero.is_some())
+            }
         }
     }
 }
@@ -38,11 +213,22 @@ impl<'de, 'a> Deserializer<'de, 'a> {
 impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::NotSupported("deserialize_any"))
+        let hint = self
+            .type_name
+            .and_then(|type_name| self.tracer.config.type_hints.get(type_name));
+        if let Some(format) = hint {
+            let format = format.clone();
+            self.format.unify(format.clone())?;
+            return self.visit_hinted_format(&format, visitor);
+        }
+        match self.type_name {
+            Some(type_name) => Err(Error::DeserializeAny(type_name)),
+            None => Err(Error::NotSupported("deserialize_any")),
+        }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
@@ -53,84 +239,84 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         visitor.visit_bool(false)
     }
 
-    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_i8<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::I8)?;
-        visitor.visit_i8(0)
+        let nonzero = self.unify_integer(Format::I8)?;
+        visitor.visit_i8(nonzero.into())
     }
 
-    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_i16<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::I16)?;
-        visitor.visit_i16(0)
+        let nonzero = self.unify_integer(Format::I16)?;
+        visitor.visit_i16(nonzero.into())
     }
 
-    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_i32<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::I32)?;
-        visitor.visit_i32(0)
+        let nonzero = self.unify_integer(Format::I32)?;
+        visitor.visit_i32(nonzero.into())
     }
 
-    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_i64<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::I64)?;
-        visitor.visit_i64(0)
+        let nonzero = self.unify_integer(Format::I64)?;
+        visitor.visit_i64(nonzero.into())
     }
 
-    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_i128<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::I128)?;
-        visitor.visit_i128(0)
+        let nonzero = self.unify_integer(Format::I128)?;
+        visitor.visit_i128(nonzero.into())
     }
 
-    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_u8<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::U8)?;
-        visitor.visit_u8(0)
+        let nonzero = self.unify_integer(Format::U8)?;
+        visitor.visit_u8(nonzero.into())
     }
 
-    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_u16<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::U16)?;
-        visitor.visit_u16(0)
+        let nonzero = self.unify_integer(Format::U16)?;
+        visitor.visit_u16(nonzero.into())
     }
 
-    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_u32<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::U32)?;
-        visitor.visit_u32(0)
+        let nonzero = self.unify_integer(Format::U32)?;
+        visitor.visit_u32(nonzero.into())
     }
 
-    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_u64<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::U64)?;
-        visitor.visit_u64(0)
+        let nonzero = self.unify_integer(Format::U64)?;
+        visitor.visit_u64(nonzero.into())
     }
 
-    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_u128<V>(mut self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::U128)?;
-        visitor.visit_u128(0)
+        let nonzero = self.unify_integer(Format::U128)?;
+        visitor.visit_u128(nonzero.into())
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
@@ -177,7 +363,9 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::Bytes)?;
+        // Borrowed byte slices may have been serialized as sequences of bytes.
+        self.format
+            .unify_with(Format::Bytes, self.tracer.config.borrowed_bytes)?;
         visitor.visit_borrowed_bytes(b"")
     }
 
@@ -185,7 +373,8 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     where
         V: Visitor<'de>,
     {
-        self.format.unify(Format::Bytes)?;
+        self.format
+            .unify_with(Format::Bytes, self.tracer.config.borrowed_bytes)?;
         visitor.visit_byte_buf(Vec::new())
     }
 
@@ -197,8 +386,13 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         self.format
             .unify(Format::Option(Box::new(format.clone())))?;
         if format.is_unknown() {
-            let inner = Deserializer::new(self.tracer, self.samples, &mut format);
-            visitor.visit_some(inner)
+            // Type hints for `T` also apply to the content of `Option<T>`.
+            let type_name = self
+                .type_name
+                .and_then(|name| self.tracer.option_content(name));
+            let inner =
+                Deserializer::new(self.tracer, self.samples, &mut format).with_type_name(type_name);
+            inner.deserialize_seed(SomeSeed(visitor))
         } else {
             // Cut exploration.
             visitor.visit_none()
@@ -217,11 +411,13 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     where
         V: Visitor<'de>,
     {
+        if let Some(format) = self.tracer.std_format(name, &[]) {
+            self.format.unify(format)?;
+            return visitor.visit_unit();
+        }
         self.format.unify(Format::TypeName(name.into()))?;
         self.tracer
-            .registry
-            .entry(name.to_string())
-            .unify(ContainerFormat::UnitStruct)?;
+            .unify_container(name.to_string(), ContainerFormat::UnitStruct)?;
         visitor.visit_unit()
     }
 
@@ -229,10 +425,22 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     where
         V: Visitor<'de>,
     {
+        if let Some(custom_format) = self.tracer.custom_format(name) {
+            return self.deserialize_custom_format(name, custom_format, visitor);
+        }
+        if self.tracer.config.type_aliases.contains(name) {
+            return self.deserialize_type_alias(name, visitor);
+        }
+        if let Some(&scale) = self.tracer.config.decimal_formats.get(name) {
+            return self.deserialize_decimal(name, scale, visitor);
+        }
         self.format.unify(Format::TypeName(name.into()))?;
-        if self.tracer.config.record_samples_for_newtype_structs {
+        if self.tracer.config.record_samples_for_newtype_structs || self.samples.is_named(name) {
             // If a value was recorded during serialization, use it.
-            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name) {
+            let sample = self.tracer.get_sample(self.samples, name, |format| {
+                Some(ContainerFormat::NewTypeStruct(Box::new(format)))
+            })?;
+            if let Some((format, sample)) = sample {
                 return visitor
                     .visit_newtype_struct(sample.into_deserializer())
                     .map_err(|err| match err {
@@ -244,16 +452,20 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
                         _ => err,
                     });
             }
+            self.tracer.request_sample(name)?;
         }
         // Pre-update the registry.
         let mut format = Format::unknown();
-        self.tracer
-            .registry
-            .entry(name.to_string())
-            .unify(ContainerFormat::NewTypeStruct(Box::new(format.clone())))?;
+        self.tracer.unify_container(
+            name.to_string(),
+            ContainerFormat::NewTypeStruct(Box::new(format.clone())),
+        )?;
         // Compute the format.
-        let inner = Deserializer::new(self.tracer, self.samples, &mut format);
-        visitor.visit_newtype_struct(inner)
+        self.tracer.enter_container(name)?;
+        let inner = Deserializer::new(&mut *self.tracer, self.samples, &mut format);
+        let value = visitor.visit_newtype_struct(inner)?;
+        self.tracer.exit_container();
+        Ok(value)
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
@@ -261,7 +473,15 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         V: Visitor<'de>,
     {
         let mut format = Format::unknown();
-        self.format.unify(Format::Seq(Box::new(format.clone())))?;
+        let is_set = matches!(
+            self.type_name,
+            Some(type_name) if self.tracer.config.set_types.contains(type_name)
+        );
+        if is_set {
+            self.format.unify(Format::Set(Box::new(format.clone())))?;
+        } else {
+            self.format.unify(Format::Seq(Box::new(format.clone())))?;
+        }
         if format.is_unknown() {
             // Simulate vector of size 1.
             let inner =
@@ -294,11 +514,24 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         V: Visitor<'de>,
     {
         self.format.unify(Format::TypeName(name.into()))?;
-        if self.tracer.config.record_samples_for_tuple_structs {
+        if self.tracer.config.record_samples_for_tuple_structs || self.samples.is_named(name) {
             // If a value was recorded during serialization, use it.
-            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name) {
-                let result = || visitor.visit_seq(sample.seq_values()?.into_seq_deserializer());
-                return result().map_err(|err| match err {
+            let sample = self
+                .tracer
+                .get_sample(self.samples, name, |format| match format {
+                    Format::Tuple(formats) if formats.len() == len => {
+                        Some(ContainerFormat::TupleStruct(formats))
+                    }
+                    _ => None,
+                })?;
+            if let Some((format, sample)) = sample {
+                let result = de::Deserializer::deserialize_tuple_struct(
+                    sample.into_deserializer(),
+                    name,
+                    len,
+                    visitor,
+                );
+                return result.map_err(|err| match err {
                     Error::DeserializationError(msg) => {
                         let mut format = format.clone();
                         format.reduce();
@@ -307,16 +540,20 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
                     _ => err,
                 });
             }
+            self.tracer.request_sample(name)?;
         }
         // Pre-update the registry.
         let mut formats: Vec<_> = std::iter::repeat_with(Format::unknown).take(len).collect();
-        self.tracer
-            .registry
-            .entry(name.to_string())
-            .unify(ContainerFormat::TupleStruct(formats.clone()))?;
+        self.tracer.unify_container(
+            name.to_string(),
+            ContainerFormat::TupleStruct(formats.clone()),
+        )?;
         // Compute the formats.
-        let inner = SeqDeserializer::new(self.tracer, self.samples, formats.iter_mut());
-        visitor.visit_seq(inner)
+        self.tracer.enter_container(name)?;
+        let inner = SeqDeserializer::new(&mut *self.tracer, self.samples, formats.iter_mut());
+        let value = visitor.visit_seq(inner)?;
+        self.tracer.exit_container();
+        Ok(value)
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
@@ -353,12 +590,36 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     where
         V: Visitor<'de>,
     {
+        if let Some(format) = self.tracer.std_format(name, fields) {
+            self.format.unify(format)?;
+            let values = vec![0u64, 0u64].into_iter();
+            return visitor.visit_seq(de::value::SeqDeserializer::<_, Error>::new(values));
+        }
         self.format.unify(Format::TypeName(name.into()))?;
-        if self.tracer.config.record_samples_for_structs {
+        if self.tracer.config.record_samples_for_structs || self.samples.is_named(name) {
             // If a value was recorded during serialization, use it.
-            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name) {
-                let result = || visitor.visit_seq(sample.seq_values()?.into_seq_deserializer());
-                return result().map_err(|err| match err {
+            let sample = self
+                .tracer
+                .get_sample(self.samples, name, |format| match format {
+                    Format::Tuple(formats) if formats.len() == fields.len() => {
+                        Some(ContainerFormat::Struct(
+                            fields
+                                .iter()
+                                .zip(formats)
+                                .map(|(&name, value)| Named::new(name, value))
+                                .collect(),
+                        ))
+                    }
+                    _ => None,
+                })?;
+            if let Some((format, sample)) = sample {
+                let result = de::Deserializer::deserialize_struct(
+                    sample.into_deserializer(),
+                    name,
+                    fields,
+                    visitor,
+                );
+                return result.map_err(|err| match err {
                     Error::DeserializationError(msg) => {
                         let mut format = format.clone();
                         format.reduce();
@@ -367,26 +628,25 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
                     _ => err,
                 });
             }
+            self.tracer.request_sample(name)?;
         }
         // Pre-update the registry.
         let mut formats: Vec<_> = fields
             .iter()
-            .map(|&name| Named {
-                name: name.into(),
-                value: Format::unknown(),
-            })
+            .map(|&name| Named::new(name, Format::unknown()))
             .collect();
         self.tracer
-            .registry
-            .entry(name.to_string())
-            .unify(ContainerFormat::Struct(formats.clone()))?;
+            .unify_container(name.to_string(), ContainerFormat::Struct(formats.clone()))?;
         // Compute the formats.
+        self.tracer.enter_container(name)?;
         let inner = SeqDeserializer::new(
-            self.tracer,
+            &mut *self.tracer,
             self.samples,
             formats.iter_mut().map(|named| &mut named.value),
         );
-        visitor.visit_seq(inner)
+        let value = visitor.visit_seq(inner)?;
+        self.tracer.exit_container();
+        Ok(value)
     }
 
     // Assumption: The first variant(s) should be "base cases", i.e. not cause infinite recursion
@@ -401,11 +661,13 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         V: Visitor<'de>,
     {
         self.format.unify(Format::TypeName(name.into()))?;
+        self.tracer.declared_variants.insert(name, variants);
+        if let Some(format) = self.tracer.address_format(name, variants) {
+            self.tracer.unify_container(name.to_string(), format)?;
+        }
         // Pre-update the registry.
         self.tracer
-            .registry
-            .entry(name.to_string())
-            .unify(ContainerFormat::Enum(BTreeMap::new()))?;
+            .unify_container(name.to_string(), ContainerFormat::Enum(BTreeMap::new()))?;
         let known_variants = match self.tracer.registry.get_mut(name) {
             Some(ContainerFormat::Enum(x)) => x,
             _ => unreachable!(),
@@ -425,12 +687,14 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
             }
             index
         };
-        let variant = known_variants.entry(index).or_insert_with(|| Named {
-            name: (*variants
-                .get(index as usize)
-                .expect("variant indexes must be a non-empty range 0..variants.len()"))
-            .to_string(),
-            value: VariantFormat::unknown(),
+        let variant = known_variants.entry(index).or_insert_with(|| {
+            Named::new(
+                (*variants
+                    .get(index as usize)
+                    .expect("variant indexes must be a non-empty range 0..variants.len()"))
+                .to_string(),
+                VariantFormat::unknown(),
+            )
         });
         let mut value = variant.value.clone();
         // Mark the enum as incomplete if this was not the last variant to explore.
@@ -438,8 +702,11 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
             self.tracer.incomplete_enums.insert(name.into());
         }
         // Compute the format for this variant.
-        let inner = EnumDeserializer::new(self.tracer, self.samples, index, &mut value);
-        visitor.visit_enum(inner)
+        self.tracer.enter_container(name)?;
+        let inner = EnumDeserializer::new(&mut *self.tracer, self.samples, index, &mut value);
+        let value = visitor.visit_enum(inner)?;
+        self.tracer.exit_container();
+        Ok(value)
     }
 
     fn deserialize_identifier<V>(self, _visitor: V) -> Result<V::Value>
@@ -491,8 +758,9 @@ where
             Some(x) => x,
             None => return Ok(None),
         };
-        let inner = Deserializer::new(self.tracer, self.samples, format);
-        seed.deserialize(inner).map(Some)
+        let inner = Deserializer::new(self.tracer, self.samples, format)
+            .with_type_name(Some(std::any::type_name::<T::Value>()));
+        inner.deserialize_seed(seed).map(Some)
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -515,8 +783,9 @@ where
             Some(x) => x,
             None => return Ok(None),
         };
-        let inner = Deserializer::new(self.tracer, self.samples, format);
-        seed.deserialize(inner).map(Some)
+        let inner = Deserializer::new(self.tracer, self.samples, format)
+            .with_type_name(Some(std::any::type_name::<K::Value>()));
+        inner.deserialize_seed(seed).map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
@@ -527,8 +796,9 @@ where
             Some(x) => x,
             None => unreachable!(),
         };
-        let inner = Deserializer::new(self.tracer, self.samples, format);
-        seed.deserialize(inner)
+        let inner = Deserializer::new(self.tracer, self.samples, format)
+            .with_type_name(Some(std::any::type_name::<V::Value>()));
+        inner.deserialize_seed(seed)
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -536,6 +806,23 @@ where
     }
 }
 
+/// Seed visiting the content of an option with `visitor`.
+struct SomeSeed<V>(V);
+
+impl<'de, V> DeserializeSeed<'de> for SomeSeed<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.0.visit_some(deserializer)
+    }
+}
+
 struct EnumDeserializer<'de, 'a> {
     tracer: &'a mut Tracer,
     samples: &'de Samples,
@@ -587,8 +874,9 @@ impl<'de, 'a> de::VariantAccess<'de> for EnumDeserializer<'de, 'a> {
         let mut format = Format::unknown();
         self.format
             .unify(VariantFormat::NewType(Box::new(format.clone())))?;
-        let inner = Deserializer::new(self.tracer, self.samples, &mut format);
-        seed.deserialize(inner)
+        let inner = Deserializer::new(self.tracer, self.samples, &mut format)
+            .with_type_name(Some(std::any::type_name::<T::Value>()));
+        inner.deserialize_seed(seed)
     }
 
     fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
@@ -607,10 +895,7 @@ impl<'de, 'a> de::VariantAccess<'de> for EnumDeserializer<'de, 'a> {
     {
         let mut formats: Vec<_> = fields
             .iter()
-            .map(|&name| Named {
-                name: name.into(),
-                value: Format::unknown(),
-            })
+            .map(|&name| Named::new(name, Format::unknown()))
             .collect();
         self.format.unify(VariantFormat::Struct(formats.clone()))?;
 
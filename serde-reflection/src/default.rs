@@ -0,0 +1,46 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    trace::{Samples, Tracer, TracerConfig},
+    value::Value,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Fields with a Serde default (e.g. `#[serde(default)]`) indexed by their qualified Serde names,
+/// e.g. `["MyStruct", "my_field"]`, together with their default values when they are known.
+pub type DefaultValues = BTreeMap<Vec<String>, Option<Value>>;
+
+/// Rust definitions exposing the default values of their fields, usually implemented with
+/// `#[derive(SerdeReflectDefaults)]` (feature `derive`).
+pub trait Defaulted {
+    /// Fields of the type, and of its variants, that Serde fills in when they are missing.
+    fn default_values() -> DefaultValues;
+
+    /// Same as `default_values` with names prefixed by `module_name`, as expected by
+    /// `CodeGeneratorConfig::with_default_values` in `serde-generate`.
+    fn qualified_default_values(module_name: &str) -> DefaultValues {
+        Self::default_values()
+            .into_iter()
+            .map(|(path, value)| {
+                let mut qualified_path = vec![module_name.to_string()];
+                qualified_path.extend(path);
+                (qualified_path, value)
+            })
+            .collect()
+    }
+}
+
+/// The Serde value of `value`, e.g. the default value of a field, or `None` if it cannot be
+/// traced.
+pub fn default_value<T>(value: &T) -> Option<Value>
+where
+    T: ?Sized + Serialize,
+{
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer
+        .trace_value(&mut Samples::new(), value)
+        .ok()
+        .map(|(_, value)| value)
+}
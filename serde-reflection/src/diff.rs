@@ -0,0 +1,417 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    format::{ContainerFormat, Format, Named, VariantFormat},
+    trace::Registry,
+};
+use std::{collections::BTreeMap, fmt};
+
+/// Structural differences between two registries, as computed by `diff`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegistryDiff {
+    /// Containers present in the new registry only.
+    pub added_containers: Vec<String>,
+    /// Containers present in the old registry only.
+    pub removed_containers: Vec<String>,
+    /// Changes of the containers present in both registries, indexed by container name.
+    pub changed_containers: BTreeMap<String, Vec<ContainerChange>>,
+}
+
+/// A change of a container present in both registries.
+///
+/// Fields are identified by name in structs and struct variants, and by position (e.g. `"0"`)
+/// in tuple structs, newtype structs, and their variants. Variants are identified by index in
+/// enums and by name in internally tagged enums, following their representations on the wire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContainerChange {
+    /// The container changed kind, e.g. from a struct to an enum.
+    ContainerRetyped {
+        old: ContainerFormat,
+        new: ContainerFormat,
+    },
+    /// The tag of an internally tagged enum changed.
+    TagChanged { old: String, new: String },
+    /// A field was added, to the container or to one of its variants.
+    FieldAdded {
+        variant: Option<String>,
+        name: String,
+        format: Format,
+    },
+    /// A field was removed, from the container or from one of its variants.
+    FieldRemoved {
+        variant: Option<String>,
+        name: String,
+        format: Format,
+    },
+    /// The format of a field changed.
+    FieldRetyped {
+        variant: Option<String>,
+        name: String,
+        old: Format,
+        new: Format,
+    },
+    /// Fields present on both sides appear in a different order.
+    FieldsReordered {
+        variant: Option<String>,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+    /// A variant was added.
+    VariantAdded { index: u32, name: String },
+    /// A variant was removed.
+    VariantRemoved { index: u32, name: String },
+    /// The variant at a given index was renamed.
+    VariantRenamed {
+        index: u32,
+        old: String,
+        new: String,
+    },
+    /// A variant changed kind, e.g. from a unit variant to a struct variant.
+    VariantRetyped {
+        name: String,
+        old: VariantFormat,
+        new: VariantFormat,
+    },
+}
+
+impl RegistryDiff {
+    /// Whether the registries have the same containers, with the same formats.
+    pub fn is_empty(&self) -> bool {
+        self.added_containers.is_empty()
+            && self.removed_containers.is_empty()
+            && self.changed_containers.is_empty()
+    }
+}
+
+impl fmt::Display for RegistryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.added_containers {
+            writeln!(f, "{}: container was added", name)?;
+        }
+        for name in &self.removed_containers {
+            writeln!(f, "{}: container was removed", name)?;
+        }
+        for (name, changes) in &self.changed_containers {
+            for change in changes {
+                writeln!(f, "{}: {}", name, change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn field_description(variant: &Option<String>, name: &str) -> String {
+    match variant {
+        None => format!("field `{}`", name),
+        Some(variant) => format!("field `{}` of variant `{}`", name, variant),
+    }
+}
+
+impl fmt::Display for ContainerChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ContainerChange::*;
+        match self {
+            ContainerRetyped { old, new } => {
+                write!(f, "container changed from `{:?}` to `{:?}`", old, new)
+            }
+            TagChanged { old, new } => write!(f, "tag changed from `{}` to `{}`", old, new),
+            FieldAdded {
+                variant,
+                name,
+                format,
+            } => write!(
+                f,
+                "{} was added with format `{:?}`",
+                field_description(variant, name),
+                format
+            ),
+            FieldRemoved { variant, name, .. } => {
+                write!(f, "{} was removed", field_description(variant, name))
+            }
+            FieldRetyped {
+                variant,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "{} changed from `{:?}` to `{:?}`",
+                field_description(variant, name),
+                old,
+                new
+            ),
+            FieldsReordered { variant, old, new } => {
+                match variant {
+                    None => write!(f, "fields")?,
+                    Some(variant) => write!(f, "fields of variant `{}`", variant)?,
+                }
+                write!(
+                    f,
+                    " were reordered from `{}` to `{}`",
+                    old.join(", "),
+                    new.join(", ")
+                )
+            }
+            VariantAdded { index, name } => write!(f, "variant #{} `{}` was added", index, name),
+            VariantRemoved { index, name } => {
+                write!(f, "variant #{} `{}` was removed", index, name)
+            }
+            VariantRenamed { index, old, new } => write!(
+                f,
+                "variant #{} was renamed from `{}` to `{}`",
+                index, old, new
+            ),
+            VariantRetyped { name, old, new } => write!(
+                f,
+                "variant `{}` changed from `{:?}` to `{:?}`",
+                name, old, new
+            ),
+        }
+    }
+}
+
+/// Compute the structural differences between the registries `old` and `new`, e.g. to check
+/// the evolution of a schema in CI.
+pub fn diff(old: &Registry, new: &Registry) -> RegistryDiff {
+    let mut diff = RegistryDiff::default();
+    for (name, old_format) in old {
+        match new.get(name) {
+            None => diff.removed_containers.push(name.clone()),
+            Some(new_format) => {
+                let mut changes = Vec::new();
+                diff_containers(&mut changes, old_format, new_format);
+                if !changes.is_empty() {
+                    diff.changed_containers.insert(name.clone(), changes);
+                }
+            }
+        }
+    }
+    diff.added_containers = new
+        .keys()
+        .filter(|name| !old.contains_key(*name))
+        .cloned()
+        .collect();
+    diff
+}
+
+fn diff_containers(
+    changes: &mut Vec<ContainerChange>,
+    old: &ContainerFormat,
+    new: &ContainerFormat,
+) {
+    use ContainerFormat::*;
+    match (old, new) {
+        (UnitStruct, UnitStruct) => (),
+        (NewTypeStruct(old), NewTypeStruct(new)) => diff_positional_fields(
+            changes,
+            None,
+            std::slice::from_ref(old),
+            std::slice::from_ref(new),
+        ),
+        (TupleStruct(old), TupleStruct(new)) => diff_positional_fields(changes, None, old, new),
+        (Struct(old), Struct(new)) => diff_named_fields(changes, None, old, new),
+        (Enum(old), Enum(new)) => {
+            for (index, old_variant) in old {
+                match new.get(index) {
+                    None => changes.push(ContainerChange::VariantRemoved {
+                        index: *index,
+                        name: old_variant.name.clone(),
+                    }),
+                    Some(new_variant) => {
+                        if old_variant.name != new_variant.name {
+                            changes.push(ContainerChange::VariantRenamed {
+                                index: *index,
+                                old: old_variant.name.clone(),
+                                new: new_variant.name.clone(),
+                            });
+                        }
+                        diff_variants(
+                            changes,
+                            &new_variant.name,
+                            &old_variant.value,
+                            &new_variant.value,
+                        );
+                    }
+                }
+            }
+            for (index, new_variant) in new {
+                if !old.contains_key(index) {
+                    changes.push(ContainerChange::VariantAdded {
+                        index: *index,
+                        name: new_variant.name.clone(),
+                    });
+                }
+            }
+        }
+        (
+            InternallyTaggedEnum {
+                tag: old_tag,
+                variants: old,
+            },
+            InternallyTaggedEnum {
+                tag: new_tag,
+                variants: new,
+            },
+        ) => {
+            if old_tag != new_tag {
+                changes.push(ContainerChange::TagChanged {
+                    old: old_tag.clone(),
+                    new: new_tag.clone(),
+                });
+            }
+            // Variants are identified by name on the wire, hence indices do not matter.
+            for (index, old_variant) in old {
+                match new
+                    .values()
+                    .find(|variant| variant.name == old_variant.name)
+                {
+                    None => changes.push(ContainerChange::VariantRemoved {
+                        index: *index,
+                        name: old_variant.name.clone(),
+                    }),
+                    Some(new_variant) => diff_variants(
+                        changes,
+                        &new_variant.name,
+                        &old_variant.value,
+                        &new_variant.value,
+                    ),
+                }
+            }
+            for (index, new_variant) in new {
+                if !old.values().any(|variant| variant.name == new_variant.name) {
+                    changes.push(ContainerChange::VariantAdded {
+                        index: *index,
+                        name: new_variant.name.clone(),
+                    });
+                }
+            }
+        }
+        _ => changes.push(ContainerChange::ContainerRetyped {
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+fn diff_variants(
+    changes: &mut Vec<ContainerChange>,
+    name: &str,
+    old: &VariantFormat,
+    new: &VariantFormat,
+) {
+    use VariantFormat::*;
+    let variant = Some(name.to_string());
+    match (old, new) {
+        (Unit, Unit) => (),
+        (NewType(old), NewType(new)) => diff_positional_fields(
+            changes,
+            variant,
+            std::slice::from_ref(old),
+            std::slice::from_ref(new),
+        ),
+        (Tuple(old), Tuple(new)) => diff_positional_fields(changes, variant, old, new),
+        (Struct(old), Struct(new)) => diff_named_fields(changes, variant, old, new),
+        _ => changes.push(ContainerChange::VariantRetyped {
+            name: name.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+fn diff_positional_fields<F>(
+    changes: &mut Vec<ContainerChange>,
+    variant: Option<String>,
+    old: &[F],
+    new: &[F],
+) where
+    F: std::borrow::Borrow<Format>,
+{
+    let name = |position: usize| position.to_string();
+    for (position, old_format) in old.iter().enumerate() {
+        match new.get(position) {
+            None => changes.push(ContainerChange::FieldRemoved {
+                variant: variant.clone(),
+                name: name(position),
+                format: old_format.borrow().clone(),
+            }),
+            Some(new_format) => {
+                if old_format.borrow() != new_format.borrow() {
+                    changes.push(ContainerChange::FieldRetyped {
+                        variant: variant.clone(),
+                        name: name(position),
+                        old: old_format.borrow().clone(),
+                        new: new_format.borrow().clone(),
+                    });
+                }
+            }
+        }
+    }
+    for (position, new_format) in new.iter().enumerate().skip(old.len()) {
+        changes.push(ContainerChange::FieldAdded {
+            variant: variant.clone(),
+            name: name(position),
+            format: new_format.borrow().clone(),
+        });
+    }
+}
+
+fn diff_named_fields(
+    changes: &mut Vec<ContainerChange>,
+    variant: Option<String>,
+    old: &[Named<Format>],
+    new: &[Named<Format>],
+) {
+    let find = |fields: &[Named<Format>], name: &str| {
+        fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| field.value.clone())
+    };
+    for old_field in old {
+        match find(new, &old_field.name) {
+            None => changes.push(ContainerChange::FieldRemoved {
+                variant: variant.clone(),
+                name: old_field.name.clone(),
+                format: old_field.value.clone(),
+            }),
+            Some(new_format) => {
+                if old_field.value != new_format {
+                    changes.push(ContainerChange::FieldRetyped {
+                        variant: variant.clone(),
+                        name: old_field.name.clone(),
+                        old: old_field.value.clone(),
+                        new: new_format,
+                    });
+                }
+            }
+        }
+    }
+    for new_field in new {
+        if find(old, &new_field.name).is_none() {
+            changes.push(ContainerChange::FieldAdded {
+                variant: variant.clone(),
+                name: new_field.name.clone(),
+                format: new_field.value.clone(),
+            });
+        }
+    }
+    // Binary encodings depend on the order of fields.
+    let common_names = |fields: &[Named<Format>], others: &[Named<Format>]| {
+        fields
+            .iter()
+            .filter(|field| find(others, &field.name).is_some())
+            .map(|field| field.name.clone())
+            .collect::<Vec<_>>()
+    };
+    let old_order = common_names(old, new);
+    let new_order = common_names(new, old);
+    if old_order != new_order {
+        changes.push(ContainerChange::FieldsReordered {
+            variant,
+            old: old_order,
+            new: new_order,
+        });
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::collections::BTreeMap;
+
+/// Doc comments indexed by qualified name, e.g. `["MyEnum", "MyVariant", "my_field"]`.
+pub type DocComments = BTreeMap<Vec<String>, String>;
+
+/// Rust definitions exposing their doc comments, usually implemented with
+/// `#[derive(SerdeReflectDoc)]` (feature `derive`).
+pub trait Documented {
+    /// Doc comments of the type, of its fields and variants, and of the fields of its variants,
+    /// indexed by their Serde names.
+    fn doc_comments() -> DocComments;
+
+    /// Same as `doc_comments` with names prefixed by `module_name`, as expected by
+    /// `CodeGeneratorConfig::with_comments` in `serde-generate`.
+    fn qualified_doc_comments(module_name: &str) -> DocComments {
+        Self::doc_comments()
+            .into_iter()
+            .map(|(path, comment)| {
+                let mut qualified_path = vec![module_name.to_string()];
+                qualified_path.extend(path);
+                (qualified_path, comment)
+            })
+            .collect()
+    }
+}
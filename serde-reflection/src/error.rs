@@ -15,6 +15,8 @@ pub enum Error {
     Custom(String),
     #[error("Not supported: {0}")]
     NotSupported(&'static str),
+    #[error("Type {0} calls `deserialize_any`, which requires a type hint")]
+    DeserializeAny(&'static str),
     #[error("Failed to deserialize {0}")]
     DeserializationError(&'static str),
     #[error("In container {0}, recorded value for serialization format {1:?} failed to deserialize into {2}")]
@@ -25,8 +27,14 @@ pub enum Error {
     UnknownFormat,
     #[error("Incomplete tracing detected inside container: {0}")]
     UnknownFormatInContainer(String),
+    #[error("Missing sample for container: {0}")]
+    MissingSample(&'static str),
+    #[error("Recursion limit reached while tracing containers: {}", .0.join(" -> "))]
+    RecursionLimit(Vec<String>),
     #[error("Missing variants detected for specific enums: {0:?}")]
     MissingVariants(Vec<String>),
+    #[error("Conflicting formats detected for containers: {}", .0.iter().map(|conflict| conflict.name.as_str()).collect::<Vec<_>>().join(", "))]
+    ConflictingContainers(Vec<crate::ContainerConflict>),
 }
 
 impl ser::Error for Error {
@@ -54,7 +62,8 @@ user-provided code 'impl<'de> Deserialize<'de> for Foo { .. }' rejects a candida
 provided by serde-reflection.
 
 To fix this, add a call `tracer.trace_value(foo, &mut samples)` so that a correct value `foo` is
-recorded *before* `tracer.trace_type` is called.
+recorded *before* `tracer.trace_type` is called, or use `tracer.trace_type_with_provider` with a
+`SampleProvider` that records such values on demand.
 "#.to_string()
             }
             NotSupported(_) => {
@@ -68,6 +77,17 @@ To fix this, avoid unsupported Serde attributes or use custom (de)serialize impl
 behaviors depending on the Serde callback `(De)Serializer::is_human_readable()`.
 "#.to_string()
             }
+            DeserializeAny(name) => {
+                format!(r#"
+The `Deserialize` implementation of the type {} calls `deserialize_any`, which lets the input decide
+what to deserialize. This happens with dynamic values (e.g. `serde_json::Value`) and types accepting
+several representations. Tracing cannot tell which format such types have on the wire.
+
+To fix this, declare the format of the type in the tracer configuration, e.g.
+`TracerConfig::default().record_type_as::<T>(Format::Str)`.
+"#,
+                name)
+            }
             DeserializationError(_) => {
                 r#"
 This internal error should not be surfaced during tracing.
@@ -109,6 +129,23 @@ least one value `foo` such that `foo.x` is not empty.
 "#,
                 name)
             }
+            MissingSample(_) => {
+                r#"
+This internal error should not be surfaced during tracing.
+"#.to_string()
+            }
+            RecursionLimit(path) => {
+                format!(r#"
+Tracing deserialization explored too many nested containers, ending with {}. This happens when
+the exploration of a recursive definition does not reach a base case, e.g. if the first variant of a
+recursive enum contains the enum itself, or if a struct always contains itself (e.g. `Box<Self>`).
+
+To fix this, make sure that the first variant of recursive enums is a base case and that recursive
+fields are optional (e.g. `Option<Box<Self>>`, `Vec<Self>`). For deep definitions that are not
+recursive, use `TracerConfig::max_recursion_depth` to raise the limit.
+"#,
+                path.join(" -> "))
+            }
             MissingVariants(names) => {
                 format!(r#"
 A registry was requested with `tracer.registry()` but some variants have not been analyzed yet
@@ -119,6 +156,17 @@ corpus of definitions. You may also use `tracer.registry_unchecked()` for debugg
 "#,
                 names)
             }
+            ConflictingContainers(conflicts) => {
+                format!(r#"
+Registries were merged with `merge_registries` but the containers {:?} are defined with different
+formats on each side. This happens when distinct types share the same (non-qualified) name, or when
+registries were traced from different versions of the same type.
+
+To fix this, rename one of the types (e.g. with `#[serde(rename = "..")]`) or make sure that both
+registries were traced from the same definitions.
+"#,
+                conflicts.iter().map(|conflict| &conflict.name).collect::<Vec<_>>())
+            }
         }
     }
 }
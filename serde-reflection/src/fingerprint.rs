@@ -0,0 +1,276 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    format::{ContainerFormat, Format, Named, VariantFormat},
+    trace::Registry,
+};
+use std::collections::BTreeMap;
+
+/// Version of the canonical encoding hashed by `fingerprint`. Changing the encoding requires a
+/// new version, so that fingerprints computed by different versions of this crate never collide.
+const ENCODING_VERSION: &[u8] = b"serde-reflection/fingerprint/v1";
+
+/// Fingerprint of a registry, i.e. the SHA-256 hash of a canonical binary encoding of its
+/// formats.
+///
+/// The fingerprint only depends on the names and wire formats of containers, fields, and
+/// variants: source names (see `TracerConfig::source_names`) and skipped fields are ignored.
+/// It does not depend on the version of this crate, so that it can be embedded in Rust programs
+/// and generated clients to detect schema drift at runtime.
+///
+/// # Panics
+///
+/// Panics if the registry contains unknown formats, i.e. if it was not normalized.
+pub fn fingerprint(registry: &Registry) -> [u8; 32] {
+    let mut encoder = Encoder(Vec::new());
+    encoder.bytes(ENCODING_VERSION);
+    encoder.len(registry.len());
+    for (name, format) in registry {
+        encoder.str(name);
+        encoder.container(format);
+    }
+    sha256(&encoder.0)
+}
+
+/// Canonical encoding of formats: every node starts with a fixed tag byte, and strings and
+/// sequences start with their length as a little-endian `u64`.
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn tag(&mut self, tag: u8) {
+        self.0.push(tag);
+    }
+
+    fn len(&mut self, len: usize) {
+        self.0.extend_from_slice(&(len as u64).to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.len(bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    fn container(&mut self, format: &ContainerFormat) {
+        use ContainerFormat::*;
+        match format {
+            UnitStruct => self.tag(0),
+            NewTypeStruct(format) => {
+                self.tag(1);
+                self.format(format);
+            }
+            TupleStruct(formats) => {
+                self.tag(2);
+                self.formats(formats);
+            }
+            Struct(fields) => {
+                self.tag(3);
+                self.fields(fields);
+            }
+            Enum(variants) => {
+                self.tag(4);
+                self.variants(variants);
+            }
+            InternallyTaggedEnum { tag, variants } => {
+                self.tag(5);
+                self.str(tag);
+                self.variants(variants);
+            }
+        }
+    }
+
+    fn variants(&mut self, variants: &BTreeMap<u32, Named<VariantFormat>>) {
+        self.len(variants.len());
+        for (index, variant) in variants {
+            self.0.extend_from_slice(&index.to_le_bytes());
+            self.str(&variant.name);
+            self.variant(&variant.value);
+        }
+    }
+
+    fn variant(&mut self, format: &VariantFormat) {
+        use VariantFormat::*;
+        match format {
+            Variable(_) => panic!("registries should not contain unknown formats"),
+            Unit => self.tag(0),
+            NewType(format) => {
+                self.tag(1);
+                self.format(format);
+            }
+            Tuple(formats) => {
+                self.tag(2);
+                self.formats(formats);
+            }
+            Struct(fields) => {
+                self.tag(3);
+                self.fields(fields);
+            }
+        }
+    }
+
+    fn fields(&mut self, fields: &[Named<Format>]) {
+        let fields = fields
+            .iter()
+            .filter(|field| !matches!(field.value, Format::Skipped(_)))
+            .collect::<Vec<_>>();
+        self.len(fields.len());
+        for field in fields {
+            self.str(&field.name);
+            self.format(&field.value);
+        }
+    }
+
+    fn formats(&mut self, formats: &[Format]) {
+        self.len(formats.len());
+        for format in formats {
+            self.format(format);
+        }
+    }
+
+    fn format(&mut self, format: &Format) {
+        use Format::*;
+        match format {
+            Variable(_) => panic!("registries should not contain unknown formats"),
+            TypeName(name) => {
+                self.tag(0);
+                self.str(name);
+            }
+            Unit => self.tag(1),
+            Bool => self.tag(2),
+            I8 => self.tag(3),
+            I16 => self.tag(4),
+            I32 => self.tag(5),
+            I64 => self.tag(6),
+            I128 => self.tag(7),
+            U8 => self.tag(8),
+            U16 => self.tag(9),
+            U32 => self.tag(10),
+            U64 => self.tag(11),
+            U128 => self.tag(12),
+            F32 => self.tag(13),
+            F64 => self.tag(14),
+            Char => self.tag(15),
+            Str => self.tag(16),
+            Bytes => self.tag(17),
+            Option(format) => {
+                self.tag(18);
+                self.format(format);
+            }
+            Seq(format) => {
+                self.tag(19);
+                self.format(format);
+            }
+            Set(format) => {
+                self.tag(20);
+                self.format(format);
+            }
+            Map { key, value } => {
+                self.tag(21);
+                self.format(key);
+                self.format(value);
+            }
+            Tuple(formats) => {
+                self.tag(22);
+                self.formats(formats);
+            }
+            TupleArray { content, size } => {
+                self.tag(23);
+                self.format(content);
+                self.len(*size);
+            }
+            Custom { name, layout } => {
+                self.tag(24);
+                self.str(name);
+                self.format(layout);
+            }
+            Decimal { mantissa, scale } => {
+                self.tag(25);
+                self.format(mantissa);
+                self.0.extend_from_slice(&scale.to_le_bytes());
+            }
+            Skipped(format) => {
+                self.tag(26);
+                self.format(format);
+            }
+        }
+    }
+}
+
+const SHA256_ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 hash of `data`, as specified by FIPS 180-4.
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut schedule = [0u32; 64];
+        for (word, bytes) in schedule.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = schedule[i - 15].rotate_right(7)
+                ^ schedule[i - 15].rotate_right(18)
+                ^ (schedule[i - 15] >> 3);
+            let s1 = schedule[i - 2].rotate_right(17)
+                ^ schedule[i - 2].rotate_right(19)
+                ^ (schedule[i - 2] >> 10);
+            schedule[i] = schedule[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(schedule[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for (constant, word) in SHA256_ROUND_CONSTANTS.iter().zip(schedule.iter()) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(*constant)
+                .wrapping_add(*word);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(majority);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        for (value, update) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+            *value = value.wrapping_add(*update);
+        }
+    }
+
+    let mut hash = [0u8; 32];
+    for (bytes, value) in hash.chunks_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    hash
+}
@@ -18,12 +18,14 @@ use serde::{
     ser::{SerializeMap, SerializeStruct},
     Deserialize, Serialize,
 };
-use std::cell::{Ref, RefCell, RefMut};
 use std::collections::{btree_map::Entry, BTreeMap};
 use std::ops::DerefMut;
-use std::rc::Rc;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 /// Serde-based serialization format for anonymous "value" types.
+///
+/// Subformats are owned (`Box<Format>`, `Vec<Format>`) so that users may build and match on them
+/// directly. Repeated subformats may be shared with a `FormatInterner`.
 #[derive(Serialize, Deserialize, Debug, Eq, Clone, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Format {
@@ -55,6 +57,11 @@ pub enum Format {
     Option(Box<Format>),
     /// A sequence, e.g. the format of `Vec<Foo>`.
     Seq(Box<Format>),
+    /// A set, e.g. `BTreeSet<Foo>`, with the same wire layout as a sequence. Serde serializes
+    /// sets as sequences, hence tracing only produces this format for the types declared with
+    /// `TracerConfig::record_set`, while `Reflect` and the derived formats of skipped fields
+    /// use it for `BTreeSet` and `HashSet`. Code generators use native sets.
+    Set(Box<Format>),
     /// A map, e.g. the format of `BTreeMap<K, V>`.
     #[serde(rename_all = "UPPERCASE")]
     Map {
@@ -71,6 +78,32 @@ pub enum Format {
         content: Box<Format>,
         size: usize,
     },
+    /// A format declared by the user with `TracerConfig::custom_format`, e.g. a 24-bit integer,
+    /// with `TracerConfig::type_alias`, e.g. `BlockHeight` for `u64`, or with
+    /// `TracerConfig::map_external_type`, e.g. `Uuid` for `uuid::Uuid`.
+    /// The `layout` describes the encoding of values on the wire, e.g. `[u8; 3]`.
+    /// Tracing the deserialization of nonzero integers, e.g. `NonZeroU64`, with
+    /// `TracerConfig::nonzero_integers` also produces custom formats named after the Rust type
+    /// and laid out as the corresponding integer.
+    /// Plain integers previously traced by serialization are refined accordingly, except
+    /// where deserialization stops exploring, e.g. in options whose content is known.
+    #[serde(rename_all = "UPPERCASE")]
+    Custom {
+        name: String,
+        layout: Box<Format>,
+    },
+    /// A fixed-point decimal number declared with `TracerConfig::decimal_format`, e.g. an
+    /// amount of money. Values are encoded on the wire as the signed integer `mantissa`
+    /// holding the number multiplied by `10^scale`, e.g. `12345` for `123.45` with a scale of 2.
+    #[serde(rename_all = "UPPERCASE")]
+    Decimal {
+        mantissa: Box<Format>,
+        scale: u32,
+    },
+    /// A field skipped by Serde, e.g. with `#[serde(skip)]`, as recorded by
+    /// `TracerConfig::skipped_fields`. Skipped fields are absent from the wire: the format
+    /// only describes the Rust type of the field.
+    Skipped(Box<Format>),
 }
 
 /// Serde-based serialization format for named "container" types.
@@ -89,19 +122,48 @@ pub enum ContainerFormat {
     /// An enum, that is, an enumeration of variants.
     /// Each variant has a unique name and index within the enum.
     Enum(BTreeMap<u32, Named<VariantFormat>>),
+    /// An internally tagged enum, e.g. `#[serde(tag = "type")] enum A { .. }`.
+    /// Values are serialized as a struct starting with a string field `tag` holding the name
+    /// of the variant, followed by the fields of the variant. Only unit variants, struct
+    /// variants, and newtype variants holding a struct are supported. Tracing never produces
+    /// this format: it must be declared in the registry, e.g. with a registry transform.
+    #[serde(rename_all = "UPPERCASE")]
+    InternallyTaggedEnum {
+        tag: String,
+        variants: BTreeMap<u32, Named<VariantFormat>>,
+    },
 }
 
 #[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[non_exhaustive]
 /// A named value.
-/// Used for named parameters or variants.
+/// Used for named parameters or variants. Named values are created with `Named::new`.
 pub struct Named<T> {
+    /// The name used by Serde, e.g. in JSON objects.
     pub name: String,
     pub value: T,
+    /// The identifier of the Rust definition, when it is known and differs from `name`
+    /// because of `#[serde(rename = "..")]` or `#[serde(rename_all = "..")]`.
+    /// Source names are only serialized in human-readable formats, and only when present:
+    /// binary encodings of `Named` values are the same with or without them.
+    pub source_name: Option<String>,
 }
 
-#[derive(Debug, Clone, Default, Eq, PartialEq)]
-/// A mutable holder for an initially unknown value.
-pub struct Variable<T>(Rc<RefCell<Option<T>>>);
+impl<T> Named<T> {
+    /// A named value without source name.
+    pub fn new(name: impl Into<String>, value: T) -> Self {
+        Self {
+            name: name.into(),
+            value,
+            source_name: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A mutable holder for an initially unknown value. Variables are shared between threads, so
+/// that formats, and therefore tracers, may be sent to other threads.
+pub struct Variable<T>(Arc<RwLock<Option<T>>>);
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -119,6 +181,17 @@ pub enum VariantFormat {
     Struct(Vec<Named<Format>>),
 }
 
+/// How to proceed after visiting a format with `FormatHolder::visit_mut_with`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VisitFlow {
+    /// Visit the subformats of the current format, then the remaining formats.
+    Continue,
+    /// Do not visit the subformats of the current format (e.g. after replacing it).
+    SkipChildren,
+    /// Stop the visit.
+    Break,
+}
+
 /// Common methods for nodes in the AST of formats.
 pub trait FormatHolder {
     /// Visit all the formats in `self` in a depth-first way.
@@ -127,10 +200,38 @@ pub trait FormatHolder {
 
     /// Mutably visit all the formats in `self` in a depth-first way.
     /// * Replace variables (if any) with their known values then apply the
-    /// visiting function `f`.
+    ///   visiting function `f`.
     /// * Return an error if any variable has still an unknown value (thus cannot be removed).
     fn visit_mut(&mut self, f: &mut dyn FnMut(&mut Format) -> Result<()>) -> Result<()>;
 
+    /// Mutably visit the formats in `self` in a depth-first way, applying `f` to each format
+    /// before its subformats so that `f` may replace it.
+    /// * Variables (if any) are replaced with their known values.
+    /// * The visit stops early as soon as `f` returns `VisitFlow::Break`, in which case
+    ///   `VisitFlow::Break` is returned. Otherwise, `VisitFlow::Continue` is returned.
+    /// * Return an error if `f` fails or if any visited variable has an unknown value.
+    ///
+    /// The default implementation returns `Error::NotSupported`. It is overridden by all the
+    /// formats of this crate.
+    fn visit_mut_with(
+        &mut self,
+        _f: &mut dyn FnMut(&mut Format) -> Result<VisitFlow>,
+    ) -> Result<VisitFlow> {
+        Err(Error::NotSupported("Cannot visit this holder of formats"))
+    }
+
+    /// Rewrite the formats in `self` from the bottom up: the subformats of a format are
+    /// rewritten first, then `f` maps the format to its replacement, e.g. to replace every
+    /// `TypeName("AccountAddress")` by `[u8; 32]`. Replacements are not visited again.
+    /// * Variables (if any) are replaced with their known values.
+    /// * Return an error if `f` fails or if any variable has an unknown value.
+    fn map_formats(&mut self, f: &mut dyn FnMut(Format) -> Result<Format>) -> Result<()> {
+        self.visit_mut(&mut |format: &mut Format| {
+            *format = f(std::mem::take(format))?;
+            Ok(())
+        })
+    }
+
     /// Update variables and add missing enum variants so that the terms match.
     /// This is a special case of [term unification](https://en.wikipedia.org/wiki/Unification_(computer_science)):
     /// * Variables occurring in `other` must be "fresh" and distinct
@@ -182,6 +283,22 @@ pub trait FormatHolder {
     fn is_unknown(&self) -> bool;
 }
 
+fn visit_all_mut_with<'a, T, I>(
+    holders: I,
+    f: &mut dyn FnMut(&mut Format) -> Result<VisitFlow>,
+) -> Result<VisitFlow>
+where
+    T: FormatHolder + 'a,
+    I: IntoIterator<Item = &'a mut T>,
+{
+    for holder in holders {
+        if holder.visit_mut_with(f)? == VisitFlow::Break {
+            return Ok(VisitFlow::Break);
+        }
+    }
+    Ok(VisitFlow::Continue)
+}
+
 fn unification_error<T>(v1: T, v2: T) -> Error
 where
     T: std::fmt::Debug,
@@ -189,6 +306,12 @@ where
     Error::Incompatible(format!("{:?}", v1), format!("{:?}", v2))
 }
 
+/// Unification used by the tracer, where sequences of bytes may also be unified with bytes if
+/// `TracerConfig::borrowed_bytes` is set. `FormatHolder::unify` never unifies them.
+pub(crate) trait Unify: Sized {
+    fn unify_with(&mut self, other: Self, borrowed_bytes: bool) -> Result<()>;
+}
+
 impl FormatHolder for VariantFormat {
     fn visit<'a>(&'a self, f: &mut dyn FnMut(&'a Format) -> Result<()>) -> Result<()> {
         match self {
@@ -237,7 +360,39 @@ impl FormatHolder for VariantFormat {
         Ok(())
     }
 
-    fn unify(&mut self, mut format: VariantFormat) -> Result<()> {
+    fn visit_mut_with(
+        &mut self,
+        f: &mut dyn FnMut(&mut Format) -> Result<VisitFlow>,
+    ) -> Result<VisitFlow> {
+        match self {
+            Self::Variable(variable) => {
+                let flow = variable.visit_mut_with(f)?;
+                *self = std::mem::take(variable)
+                    .into_inner()
+                    .expect("variable is known");
+                Ok(flow)
+            }
+            Self::Unit => Ok(VisitFlow::Continue),
+            Self::NewType(format) => format.visit_mut_with(f),
+            Self::Tuple(formats) => visit_all_mut_with(formats, f),
+            Self::Struct(named_formats) => visit_all_mut_with(named_formats, f),
+        }
+    }
+
+    fn unify(&mut self, format: VariantFormat) -> Result<()> {
+        self.unify_with(format, false)
+    }
+
+    fn is_unknown(&self) -> bool {
+        if let Self::Variable(v) = self {
+            return v.is_unknown();
+        }
+        false
+    }
+}
+
+impl Unify for VariantFormat {
+    fn unify_with(&mut self, mut format: VariantFormat, borrowed_bytes: bool) -> Result<()> {
         // Matching `&mut format` instead of `format` because of
         //   "error[E0009]: cannot bind by-move and by-ref in the same pattern"
         // See also https://github.com/rust-lang/rust/issues/68354
@@ -255,7 +410,7 @@ impl FormatHolder for VariantFormat {
                         None
                     }
                     Some(format1) => {
-                        format1.unify(format2)?;
+                        format1.unify_with(format2, borrowed_bytes)?;
                         match format1 {
                             Self::Variable(variable) => Some(variable.clone()),
                             _ => None,
@@ -272,7 +427,7 @@ impl FormatHolder for VariantFormat {
 
             (Self::NewType(format1), Self::NewType(format2)) => {
                 let format2 = std::mem::take(format2.as_mut());
-                format1.as_mut().unify(format2)?;
+                format1.as_mut().unify_with(format2, borrowed_bytes)?;
             }
 
             (Self::Tuple(formats1), Self::Tuple(formats2)) => {
@@ -282,7 +437,7 @@ impl FormatHolder for VariantFormat {
                 let mut formats2 = formats2.iter_mut();
                 for format1 in formats1 {
                     let format2 = std::mem::take(formats2.next().unwrap());
-                    format1.unify(format2)?;
+                    format1.unify_with(format2, borrowed_bytes)?;
                 }
             }
 
@@ -293,7 +448,7 @@ impl FormatHolder for VariantFormat {
                 let mut formats2 = formats2.iter_mut();
                 for format1 in formats1 {
                     let format2 = std::mem::take(formats2.next().unwrap());
-                    format1.unify(format2)?;
+                    format1.unify_with(format2, borrowed_bytes)?;
                 }
             }
 
@@ -303,13 +458,6 @@ impl FormatHolder for VariantFormat {
         }
         Ok(())
     }
-
-    fn is_unknown(&self) -> bool {
-        if let Self::Variable(v) = self {
-            return v.is_unknown();
-        }
-        false
-    }
 }
 
 impl<T> FormatHolder for Named<T>
@@ -324,40 +472,89 @@ where
         self.value.visit_mut(f)
     }
 
+    fn visit_mut_with(
+        &mut self,
+        f: &mut dyn FnMut(&mut Format) -> Result<VisitFlow>,
+    ) -> Result<VisitFlow> {
+        self.value.visit_mut_with(f)
+    }
+
     fn unify(&mut self, other: Named<T>) -> Result<()> {
+        let value = self.unify_names(other)?;
+        self.value.unify(value)
+    }
+
+    fn is_unknown(&self) -> bool {
+        false
+    }
+}
+
+impl<T> Named<T>
+where
+    T: std::fmt::Debug,
+{
+    /// Unify the name and source name of `other` into `self`, and return the value of `other`.
+    fn unify_names(&mut self, other: Named<T>) -> Result<T> {
         if self.name != other.name {
             return Err(unification_error(&*self, &other));
         }
-        self.value.unify(other.value)
+        match (&self.source_name, other.source_name) {
+            (Some(name1), Some(name2)) if *name1 != name2 => {
+                return Err(Error::Incompatible(name1.clone(), name2));
+            }
+            (None, source_name) => self.source_name = source_name,
+            _ => (),
+        }
+        Ok(other.value)
     }
+}
 
-    fn is_unknown(&self) -> bool {
-        false
+impl<T> Unify for Named<T>
+where
+    T: Unify + std::fmt::Debug,
+{
+    fn unify_with(&mut self, other: Named<T>, borrowed_bytes: bool) -> Result<()> {
+        let value = self.unify_names(other)?;
+        self.value.unify_with(value, borrowed_bytes)
     }
 }
 
 impl<T> Variable<T> {
     pub(crate) fn new(content: Option<T>) -> Self {
-        Self(Rc::new(RefCell::new(content)))
+        Self(Arc::new(RwLock::new(content)))
+    }
+
+    /// Read the content of the variable. Variables are shared between threads, hence this
+    /// returns a read guard.
+    pub fn borrow(&self) -> RwLockReadGuard<'_, Option<T>> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
     }
 
-    pub fn borrow(&self) -> Ref<Option<T>> {
-        self.0.as_ref().borrow()
+    /// Modify the content of the variable through a write guard.
+    pub fn borrow_mut(&self) -> RwLockWriteGuard<'_, Option<T>> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
     }
+}
 
-    pub fn borrow_mut(&self) -> RefMut<Option<T>> {
-        self.0.as_ref().borrow_mut()
+impl<T> PartialEq for Variable<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || *self.borrow() == *other.borrow()
     }
 }
 
+impl<T> Eq for Variable<T> where T: Eq {}
+
 impl<T> Variable<T>
 where
     T: Clone,
 {
     fn into_inner(self) -> Option<T> {
-        match Rc::try_unwrap(self.0) {
-            Ok(cell) => cell.into_inner(),
-            Err(rc) => rc.borrow().clone(),
+        match Arc::try_unwrap(self.0) {
+            Ok(lock) => lock.into_inner().unwrap_or_else(PoisonError::into_inner),
+            Err(arc) => arc.read().unwrap_or_else(PoisonError::into_inner).clone(),
         }
     }
 }
@@ -397,6 +594,16 @@ where
         }
     }
 
+    fn visit_mut_with(
+        &mut self,
+        f: &mut dyn FnMut(&mut Format) -> Result<VisitFlow>,
+    ) -> Result<VisitFlow> {
+        match self.borrow_mut().deref_mut() {
+            None => Err(Error::UnknownFormat),
+            Some(value) => value.visit_mut_with(f),
+        }
+    }
+
     fn unify(&mut self, _other: Variable<T>) -> Result<()> {
         // Omitting this method because a correct implementation would require
         // additional assumptions on T (in order to create new variables of type `T`).
@@ -426,7 +633,7 @@ impl FormatHolder for ContainerFormat {
                     format.visit(f)?;
                 }
             }
-            Self::Enum(variants) => {
+            Self::Enum(variants) | Self::InternallyTaggedEnum { variants, .. } => {
                 for variant in variants {
                     variant.1.visit(f)?;
                 }
@@ -449,7 +656,7 @@ impl FormatHolder for ContainerFormat {
                     format.visit_mut(f)?;
                 }
             }
-            Self::Enum(variants) => {
+            Self::Enum(variants) | Self::InternallyTaggedEnum { variants, .. } => {
                 for variant in variants {
                     variant.1.visit_mut(f)?;
                 }
@@ -458,7 +665,32 @@ impl FormatHolder for ContainerFormat {
         Ok(())
     }
 
-    fn unify(&mut self, mut format: ContainerFormat) -> Result<()> {
+    fn visit_mut_with(
+        &mut self,
+        f: &mut dyn FnMut(&mut Format) -> Result<VisitFlow>,
+    ) -> Result<VisitFlow> {
+        match self {
+            Self::UnitStruct => Ok(VisitFlow::Continue),
+            Self::NewTypeStruct(format) => format.visit_mut_with(f),
+            Self::TupleStruct(formats) => visit_all_mut_with(formats, f),
+            Self::Struct(named_formats) => visit_all_mut_with(named_formats, f),
+            Self::Enum(variants) | Self::InternallyTaggedEnum { variants, .. } => {
+                visit_all_mut_with(variants.values_mut(), f)
+            }
+        }
+    }
+
+    fn unify(&mut self, format: ContainerFormat) -> Result<()> {
+        self.unify_with(format, false)
+    }
+
+    fn is_unknown(&self) -> bool {
+        false
+    }
+}
+
+impl Unify for ContainerFormat {
+    fn unify_with(&mut self, mut format: ContainerFormat, borrowed_bytes: bool) -> Result<()> {
         // Matching `&mut format` instead of `format` because of
         // "error[E0009]: cannot bind by-move and by-ref in the same pattern"
         match (&mut *self, &mut format) {
@@ -466,7 +698,7 @@ impl FormatHolder for ContainerFormat {
 
             (Self::NewTypeStruct(format1), Self::NewTypeStruct(format2)) => {
                 let format2 = std::mem::take(format2.as_mut());
-                format1.as_mut().unify(format2)?;
+                format1.as_mut().unify_with(format2, borrowed_bytes)?;
             }
 
             (Self::TupleStruct(formats1), Self::TupleStruct(formats2)) => {
@@ -476,7 +708,7 @@ impl FormatHolder for ContainerFormat {
                 let mut formats2 = formats2.iter_mut();
                 for format1 in formats1 {
                     let format2 = std::mem::take(formats2.next().unwrap());
-                    format1.unify(format2)?;
+                    format1.unify_with(format2, borrowed_bytes)?;
                 }
             }
 
@@ -487,23 +719,28 @@ impl FormatHolder for ContainerFormat {
                 let mut named_formats2 = named_formats2.iter_mut();
                 for format1 in named_formats1 {
                     let format2 = std::mem::take(named_formats2.next().unwrap());
-                    format1.unify(format2)?;
+                    format1.unify_with(format2, borrowed_bytes)?;
                 }
             }
 
             (Self::Enum(variants1), Self::Enum(variants2)) => {
-                for (index2, variant2) in variants2.iter_mut() {
-                    let variant2 = std::mem::take(variant2);
-                    match variants1.entry(*index2) {
-                        Entry::Vacant(e) => {
-                            // Note that we do not check for name collisions.
-                            e.insert(variant2);
-                        }
-                        Entry::Occupied(mut e) => {
-                            e.get_mut().unify(variant2)?;
-                        }
-                    }
+                unify_variants(variants1, variants2, borrowed_bytes)?;
+            }
+
+            (
+                Self::InternallyTaggedEnum {
+                    tag: tag1,
+                    variants: variants1,
+                },
+                Self::InternallyTaggedEnum {
+                    tag: tag2,
+                    variants: variants2,
+                },
+            ) => {
+                if tag1 != tag2 {
+                    return Err(unification_error(self, &mut format));
                 }
+                unify_variants(variants1, variants2, borrowed_bytes)?;
             }
 
             _ => {
@@ -512,10 +749,26 @@ impl FormatHolder for ContainerFormat {
         }
         Ok(())
     }
+}
 
-    fn is_unknown(&self) -> bool {
-        false
+fn unify_variants(
+    variants1: &mut BTreeMap<u32, Named<VariantFormat>>,
+    variants2: &mut BTreeMap<u32, Named<VariantFormat>>,
+    borrowed_bytes: bool,
+) -> Result<()> {
+    for (index2, variant2) in variants2.iter_mut() {
+        let variant2 = std::mem::take(variant2);
+        match variants1.entry(*index2) {
+            Entry::Vacant(e) => {
+                // Note that we do not check for name collisions.
+                e.insert(variant2);
+            }
+            Entry::Occupied(mut e) => {
+                e.get_mut().unify_with(variant2, borrowed_bytes)?;
+            }
+        }
     }
+    Ok(())
 }
 
 impl FormatHolder for Format {
@@ -543,9 +796,15 @@ impl FormatHolder for Format {
 
             Self::Option(format)
             | Self::Seq(format)
+            | Self::Set(format)
             | Self::TupleArray {
                 content: format, ..
-            } => {
+            }
+            | Self::Custom { layout: format, .. }
+            | Self::Decimal {
+                mantissa: format, ..
+            }
+            | Self::Skipped(format) => {
                 format.visit(f)?;
             }
 
@@ -594,9 +853,15 @@ impl FormatHolder for Format {
 
             Self::Option(format)
             | Self::Seq(format)
+            | Self::Set(format)
             | Self::TupleArray {
                 content: format, ..
-            } => {
+            }
+            | Self::Custom { layout: format, .. }
+            | Self::Decimal {
+                mantissa: format, ..
+            }
+            | Self::Skipped(format) => {
                 format.visit_mut(f)?;
             }
 
@@ -614,15 +879,82 @@ impl FormatHolder for Format {
         f(self)
     }
 
+    fn visit_mut_with(
+        &mut self,
+        f: &mut dyn FnMut(&mut Format) -> Result<VisitFlow>,
+    ) -> Result<VisitFlow> {
+        if let Self::Variable(variable) = self {
+            let flow = variable.visit_mut_with(f)?;
+            *self = std::mem::take(variable)
+                .into_inner()
+                .expect("variable is known");
+            return Ok(flow);
+        }
+        match f(self)? {
+            VisitFlow::Continue => (),
+            VisitFlow::SkipChildren => return Ok(VisitFlow::Continue),
+            VisitFlow::Break => return Ok(VisitFlow::Break),
+        }
+        match self {
+            Self::Option(format)
+            | Self::Seq(format)
+            | Self::Set(format)
+            | Self::TupleArray {
+                content: format, ..
+            }
+            | Self::Custom { layout: format, .. }
+            | Self::Decimal {
+                mantissa: format, ..
+            }
+            | Self::Skipped(format) => format.visit_mut_with(f),
+
+            Self::Map { key, value } => {
+                if key.visit_mut_with(f)? == VisitFlow::Break {
+                    return Ok(VisitFlow::Break);
+                }
+                value.visit_mut_with(f)
+            }
+
+            Self::Tuple(formats) => visit_all_mut_with(formats, f),
+
+            _ => Ok(VisitFlow::Continue),
+        }
+    }
+
     /// Unify the newly "traced" value `format` into the current format.
     /// Note that there should be no `TupleArray`s at this point.
-    fn unify(&mut self, mut format: Format) -> Result<()> {
+    fn unify(&mut self, format: Format) -> Result<()> {
+        self.unify_with(format, false)
+    }
+
+    fn is_unknown(&self) -> bool {
+        if let Self::Variable(v) = self {
+            return v.is_unknown();
+        }
+        false
+    }
+}
+
+impl Unify for Format {
+    fn unify_with(&mut self, mut format: Format, borrowed_bytes: bool) -> Result<()> {
         // Matching `&mut format` instead of `format` because of
         // "error[E0009]: cannot bind by-move and by-ref in the same pattern"
         match (&mut *self, &mut format) {
             (_, Self::Variable(variable2)) => {
-                assert!(variable2.borrow().is_none());
-                *variable2.borrow_mut() = Some(self.clone());
+                // Formats traced during serialization, e.g. the content of sequences, may
+                // already be known.
+                let known_format = variable2.borrow().clone();
+                if let Some(format2) = known_format {
+                    return self.unify_with(format2, borrowed_bytes);
+                }
+                if let Self::Variable(_) = self {
+                    *variable2.borrow_mut() = Some(self.clone());
+                } else {
+                    // Share the current format with the new variable, so that later
+                    // refinements, e.g. to nonzero integers (see below), apply to both.
+                    *variable2.borrow_mut() = Some(std::mem::take(self));
+                    *self = Self::Variable(variable2.clone());
+                }
             }
             (Self::Variable(variable1), _) => {
                 let format2 = std::mem::take(&mut format);
@@ -632,7 +964,7 @@ impl FormatHolder for Format {
                         None
                     }
                     Some(format1) => {
-                        format1.unify(format2)?;
+                        format1.unify_with(format2, borrowed_bytes)?;
                         match format1 {
                             Self::Variable(variable) => Some(variable.clone()),
                             _ => None,
@@ -669,10 +1001,69 @@ impl FormatHolder for Format {
                 }
             }
 
+            (
+                Self::Custom {
+                    name: name1,
+                    layout: layout1,
+                },
+                Self::Custom {
+                    name: name2,
+                    layout: layout2,
+                },
+            ) => {
+                if name1 != name2 {
+                    return Err(unification_error(self, &mut format));
+                }
+                // Layouts of type aliases are traced incrementally.
+                if layout1 != layout2 {
+                    let layout2 = std::mem::take(layout2.as_mut());
+                    layout1.as_mut().unify_with(layout2, borrowed_bytes)?;
+                }
+            }
+
+            // Serializing a nonzero integer, e.g. `NonZeroU64`, is indistinguishable from
+            // serializing the corresponding integer.
+            (Self::Custom { name, layout }, format2)
+                if is_nonzero_integer(name) && **layout == *format2 => {}
+            (format1, Self::Custom { name, layout })
+                if is_nonzero_integer(name) && *format1 == **layout =>
+            {
+                *self = format;
+            }
+
+            // Borrowed byte slices, e.g. `&[u8]`, are serialized as sequences of bytes but
+            // deserialized as bytes. Both have the same layout in binary encodings.
+            (Self::Bytes, Self::Seq(format2)) if borrowed_bytes => {
+                format2.as_mut().unify_with(Self::U8, borrowed_bytes)?;
+            }
+            (Self::Seq(format1), Self::Bytes) if borrowed_bytes => {
+                format1.as_mut().unify_with(Self::U8, borrowed_bytes)?;
+                *self = Self::Bytes;
+            }
+
+            (
+                Self::Decimal {
+                    mantissa: mantissa1,
+                    scale: scale1,
+                },
+                Self::Decimal {
+                    mantissa: mantissa2,
+                    scale: scale2,
+                },
+            ) => {
+                if scale1 != scale2 {
+                    return Err(unification_error(self, &mut format));
+                }
+                let mantissa2 = std::mem::take(mantissa2.as_mut());
+                mantissa1.as_mut().unify_with(mantissa2, borrowed_bytes)?;
+            }
+
             (Self::Option(format1), Self::Option(format2))
-            | (Self::Seq(format1), Self::Seq(format2)) => {
+            | (Self::Seq(format1), Self::Seq(format2))
+            | (Self::Set(format1), Self::Set(format2))
+            | (Self::Skipped(format1), Self::Skipped(format2)) => {
                 let format2 = std::mem::take(format2.as_mut());
-                format1.as_mut().unify(format2)?;
+                format1.as_mut().unify_with(format2, borrowed_bytes)?;
             }
 
             (Self::Tuple(formats1), Self::Tuple(formats2)) => {
@@ -682,7 +1073,7 @@ impl FormatHolder for Format {
                 let mut formats2 = formats2.iter_mut();
                 for format1 in formats1 {
                     let format2 = std::mem::take(formats2.next().unwrap());
-                    format1.unify(format2)?;
+                    format1.unify_with(format2, borrowed_bytes)?;
                 }
             }
 
@@ -698,8 +1089,8 @@ impl FormatHolder for Format {
             ) => {
                 let key2 = std::mem::take(key2.as_mut());
                 let value2 = std::mem::take(value2.as_mut());
-                key1.as_mut().unify(key2)?;
-                value1.as_mut().unify(value2)?;
+                key1.as_mut().unify_with(key2, borrowed_bytes)?;
+                value1.as_mut().unify_with(value2, borrowed_bytes)?;
             }
 
             _ => {
@@ -708,31 +1099,43 @@ impl FormatHolder for Format {
         }
         Ok(())
     }
+}
 
-    fn is_unknown(&self) -> bool {
-        if let Self::Variable(v) = self {
-            return v.is_unknown();
-        }
-        false
-    }
+/// Whether the custom format `name` was traced from a nonzero integer, e.g. `NonZeroU64`.
+fn is_nonzero_integer(name: &str) -> bool {
+    matches!(
+        name,
+        "NonZeroU8"
+            | "NonZeroU16"
+            | "NonZeroU32"
+            | "NonZeroU64"
+            | "NonZeroU128"
+            | "NonZeroUsize"
+            | "NonZeroI8"
+            | "NonZeroI16"
+            | "NonZeroI32"
+            | "NonZeroI64"
+            | "NonZeroI128"
+            | "NonZeroIsize"
+    )
 }
 
 /// Helper trait to update formats in maps.
 pub(crate) trait ContainerFormatEntry {
-    fn unify(self, format: ContainerFormat) -> Result<()>;
+    fn unify_with(self, format: ContainerFormat, borrowed_bytes: bool) -> Result<()>;
 }
 
 impl<'a, K> ContainerFormatEntry for Entry<'a, K, ContainerFormat>
 where
     K: std::cmp::Ord,
 {
-    fn unify(self, format: ContainerFormat) -> Result<()> {
+    fn unify_with(self, format: ContainerFormat, borrowed_bytes: bool) -> Result<()> {
         match self {
             Entry::Vacant(e) => {
                 e.insert(format);
                 Ok(())
             }
-            Entry::Occupied(e) => e.into_mut().unify(format),
+            Entry::Occupied(e) => e.into_mut().unify_with(format, borrowed_bytes),
         }
     }
 }
@@ -764,7 +1167,8 @@ impl Default for VariantFormat {
 }
 
 // For better rendering in human readable formats, we wish to serialize
-// `Named { key: x, value: y }` as a map `{ x: y }`.
+// `Named { key: x, value: y }` as a map `{ x: y }`. A source name `z`, if any, is written as a
+// second entry `{ x: y, SOURCE_NAME: z }`.
 impl<T> Serialize for Named<T>
 where
     T: Serialize,
@@ -774,8 +1178,12 @@ where
         S: ser::Serializer,
     {
         if serializer.is_human_readable() {
-            let mut map = serializer.serialize_map(Some(1))?;
+            let len = if self.source_name.is_some() { 2 } else { 1 };
+            let mut map = serializer.serialize_map(Some(len))?;
             map.serialize_entry(&self.name, &self.value)?;
+            if let Some(source_name) = &self.source_name {
+                map.serialize_entry(SOURCE_NAME_KEY, source_name)?;
+            }
             map.end()
         } else {
             let mut inner = serializer.serialize_struct("Named", 2)?;
@@ -786,6 +1194,9 @@ where
     }
 }
 
+/// Key of the source name of a `Named` value in human readable formats.
+const SOURCE_NAME_KEY: &str = "SOURCE_NAME";
+
 struct NamedVisitor<T> {
     marker: std::marker::PhantomData<T>,
 }
@@ -812,13 +1223,22 @@ where
     where
         M: de::MapAccess<'de>,
     {
-        let named_value = match access.next_entry::<String, T>()? {
-            Some((name, value)) => Named { name, value },
+        let mut named_value = match access.next_entry::<String, T>()? {
+            Some((name, value)) => Named::new(name, value),
             _ => {
                 return Err(de::Error::custom("Missing entry"));
             }
         };
-        if access.next_entry::<String, T>()?.is_some() {
+        match access.next_key::<String>()? {
+            Some(key) if key == SOURCE_NAME_KEY => {
+                named_value.source_name = Some(access.next_value()?);
+            }
+            Some(key) => {
+                return Err(de::Error::custom(format!("Unexpected entry {}", key)));
+            }
+            None => return Ok(named_value),
+        }
+        if access.next_key::<String>()?.is_some() {
             return Err(de::Error::custom("Too many entries"));
         }
         Ok(named_value)
@@ -845,7 +1265,7 @@ where
             deserializer.deserialize_map(NamedVisitor::new())
         } else {
             let NamedInternal { name, value } = NamedInternal::deserialize(deserializer)?;
-            Ok(Self { name, value })
+            Ok(Self::new(name, value))
         }
     }
 }
@@ -0,0 +1,223 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    error::{Error, Result},
+    format::{ContainerFormat, Format, FormatHolder, VariantFormat},
+    trace::Registry,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// Structural sharing of formats: structurally equal formats are stored once and handed out
+/// as clones of the same `Arc`, so that they are cloned in constant time.
+///
+/// Large registries often repeat the same nested formats (e.g. `Option(Seq(TypeName("Coin")))`)
+/// in many fields. Code generators may intern the formats of a registry once, then clone and
+/// visit its distinct formats only, see `FormatInterner::formats`.
+///
+/// Formats are hash-consed: each format is identified by its tag, its own data (names and
+/// sizes), and the identifiers of its subformats, so that interning a format only costs a
+/// traversal of the format itself.
+#[derive(Debug, Default)]
+pub struct FormatInterner {
+    ids: HashMap<Vec<u8>, usize>,
+    formats: Vec<Arc<Format>>,
+}
+
+impl FormatInterner {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared copy of `format`. Interning structurally equal formats returns clones of the
+    /// same `Arc`. Known variables are replaced by their content, while unknown variables are
+    /// rejected with `Error::UnknownFormat`.
+    pub fn intern(&mut self, format: &Format) -> Result<Arc<Format>> {
+        let id = self.intern_id(format)?;
+        Ok(self.formats[id].clone())
+    }
+
+    /// Intern the formats of all the fields and variants of `registry`, including nested formats.
+    pub fn intern_registry(&mut self, registry: &Registry) -> Result<()> {
+        for format in registry.values() {
+            self.intern_container(format)?;
+        }
+        Ok(())
+    }
+
+    /// The distinct formats interned so far, nested formats included, in the order of their first
+    /// occurrence, where subformats come before the formats containing them (as in
+    /// `FormatHolder::visit`).
+    pub fn formats(&self) -> impl Iterator<Item = &Arc<Format>> {
+        self.formats.iter()
+    }
+
+    /// The number of distinct formats interned so far.
+    pub fn len(&self) -> usize {
+        self.formats.len()
+    }
+
+    /// Whether no format was interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.formats.is_empty()
+    }
+
+    fn intern_container(&mut self, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        match format {
+            UnitStruct => (),
+            NewTypeStruct(format) => {
+                self.intern_id(format)?;
+            }
+            TupleStruct(formats) => {
+                for format in formats {
+                    self.intern_id(format)?;
+                }
+            }
+            Struct(fields) => {
+                for field in fields {
+                    self.intern_id(&field.value)?;
+                }
+            }
+            Enum(variants) | InternallyTaggedEnum { variants, .. } => {
+                for variant in variants.values() {
+                    self.intern_variant(&variant.value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn intern_variant(&mut self, format: &VariantFormat) -> Result<()> {
+        use VariantFormat::*;
+        match format {
+            Variable(variable) => match variable.borrow().as_ref() {
+                Some(format) => self.intern_variant(format)?,
+                None => return Err(Error::UnknownFormat),
+            },
+            Unit => (),
+            NewType(format) => {
+                self.intern_id(format)?;
+            }
+            Tuple(formats) => {
+                for format in formats {
+                    self.intern_id(format)?;
+                }
+            }
+            Struct(fields) => {
+                for field in fields {
+                    self.intern_id(&field.value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Identifier of the shared copy of `format`, interning its subformats first.
+    fn intern_id(&mut self, format: &Format) -> Result<usize> {
+        use Format::*;
+        let mut key = Key::default();
+        match format {
+            Variable(variable) => {
+                return match variable.borrow().as_ref() {
+                    Some(format) => self.intern_id(format),
+                    None => Err(Error::UnknownFormat),
+                };
+            }
+            TypeName(name) => {
+                key.tag(0);
+                key.str(name);
+            }
+            Unit => key.tag(1),
+            Bool => key.tag(2),
+            I8 => key.tag(3),
+            I16 => key.tag(4),
+            I32 => key.tag(5),
+            I64 => key.tag(6),
+            I128 => key.tag(7),
+            U8 => key.tag(8),
+            U16 => key.tag(9),
+            U32 => key.tag(10),
+            U64 => key.tag(11),
+            U128 => key.tag(12),
+            F32 => key.tag(13),
+            F64 => key.tag(14),
+            Char => key.tag(15),
+            Str => key.tag(16),
+            Bytes => key.tag(17),
+            Option(format) => {
+                key.tag(18);
+                key.id(self.intern_id(format)?);
+            }
+            Seq(format) => {
+                key.tag(19);
+                key.id(self.intern_id(format)?);
+            }
+            Set(format) => {
+                key.tag(20);
+                key.id(self.intern_id(format)?);
+            }
+            Map { key: k, value } => {
+                key.tag(21);
+                key.id(self.intern_id(k)?);
+                key.id(self.intern_id(value)?);
+            }
+            Tuple(formats) => {
+                key.tag(22);
+                key.id(formats.len());
+                for format in formats {
+                    key.id(self.intern_id(format)?);
+                }
+            }
+            TupleArray { content, size } => {
+                key.tag(23);
+                key.id(self.intern_id(content)?);
+                key.id(*size);
+            }
+            Custom { name, layout } => {
+                key.tag(24);
+                key.str(name);
+                key.id(self.intern_id(layout)?);
+            }
+            Decimal { mantissa, scale } => {
+                key.tag(25);
+                key.id(self.intern_id(mantissa)?);
+                key.id(*scale as usize);
+            }
+            Skipped(format) => {
+                key.tag(26);
+                key.id(self.intern_id(format)?);
+            }
+        }
+        if let Some(id) = self.ids.get(&key.0) {
+            return Ok(*id);
+        }
+        let mut shared = format.clone();
+        // Subformats were interned above, hence all variables are known.
+        shared.reduce();
+        let id = self.formats.len();
+        self.formats.push(Arc::new(shared));
+        self.ids.insert(key.0, id);
+        Ok(id)
+    }
+}
+
+/// Shallow encoding of a format, where subformats are given by their identifiers.
+#[derive(Default)]
+struct Key(Vec<u8>);
+
+impl Key {
+    fn tag(&mut self, tag: u8) {
+        self.0.push(tag);
+    }
+
+    fn id(&mut self, id: usize) {
+        self.0.extend_from_slice(&(id as u64).to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.id(value.len());
+        self.0.extend_from_slice(value.as_bytes());
+    }
+}
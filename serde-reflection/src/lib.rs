@@ -69,6 +69,241 @@
 //! The error type used in this crate provides a method `error.explanation()` to help with
 //! troubleshooting during format tracing.
 //!
+//! # Sample providers
+//!
+//! Types whose `Deserialize` implementation enforces invariants (checksums, ranges, etc) need
+//! valid samples. Instead of recording them all with `trace_value` beforehand, a `SampleProvider`
+//! can generate them programmatically: `tracer.trace_type_with_provider::<T, _>(&mut samples, &provider)`
+//! asks the provider for a sample of each container that records samples and has none yet.
+//! Closures `Fn(&'static str, &mut Tracer, &mut Samples) -> Result<bool>` implement the trait.
+//!
+//! Containers whose Rust type cannot be named or serialized, such as the
+//! `#[serde(remote = "..")]` definitions of third-party types, can be given samples by name:
+//! `tracer.trace_sample_by_name(&mut samples, "SpanDef", &(start, end))` records a payload
+//! serialized like the content of the container (a tuple of the fields of a struct, or the value
+//! of a newtype struct). The sample is used whenever the container is deserialized, and the
+//! format of the container is derived from the payload if needed.
+//!
+//! # Variant coverage
+//!
+//! `tracer.coverage()` reports, for each enum found so far, the variants that were visited and
+//! those that remain unexplored, so that tests can assert that tracing covered all the variants,
+//! e.g. `assert!(tracer.coverage().values().all(VariantCoverage::is_complete))`. The variants
+//! declared by an enum are only known once its deserialization has been traced.
+//!
+//! # Standard library types
+//!
+//! Serde implements some types of the standard library as containers with internal layouts, e.g.
+//! `Duration` as a struct `{secs: u64, nanos: u32}`. `TracerConfig::default().std_formats(true)`
+//! traces them with canonical formats instead (`Duration` and `SystemTime` as custom formats laid
+//! out as `(u64, u32)`, `PhantomData` as a unit), and `std_format(name, format)` overrides the
+//! format of a particular type, e.g. `Format::Tuple(vec![Format::U64, Format::U32])` for `Duration`.
+//!
+//! # External types
+//!
+//! Types of third-party crates, such as `uuid::Uuid`, `chrono::DateTime`, or `rust_decimal::Decimal`,
+//! are traced with their internal representation by default. `map_external_type(path, layout)`
+//! traces them as custom formats named after their type instead, e.g.
+//! `TracerConfig::default().map_external_type("uuid::Uuid", Format::Bytes)` records `Uuid` fields as
+//! `Format::Custom { name: "Uuid", layout: Bytes }`. Values passed to `trace_value` beforehand are
+//! used as samples during deserialization. In `serde-generate`, the transformation
+//! `transform::ExternalTypes` then replaces these formats by references to external definitions.
+//!
+//! Arbitrary-precision numbers, i.e. `serde_json::Number` with the feature `arbitrary_precision` of
+//! `serde_json`, are traced as `Format::Custom { name: "Number", layout: Str }` when this format is
+//! given as a hint: `record_type_as::<serde_json::Number>(format)`. Their values are then recorded as
+//! `Value::Number` with their exact decimal representation, e.g. for amounts of money that do not
+//! fit in 128-bit integers. Without this feature, `serde_json::Number` may be given any other
+//! format, such as `Format::F64`.
+//!
+//! Sets, e.g. `BTreeSet<Foo>`, are serialized by Serde as sequences, hence traced as `Format::Seq` by
+//! default. `TracerConfig::default().record_set::<BTreeSet<Foo>>()` traces them as `Format::Set`,
+//! which the `Reflect` implementations of `BTreeSet` and `HashSet` use as well.
+//!
+//! # Parallel tracing
+//!
+//! Large sets of types can be traced on several threads: `tracer.trace_types_parallel(&samples, &jobs, threads)`
+//! runs each job, e.g. `Box::new(|tracer, samples| tracer.trace_type::<Foo>(samples).map(|_| ()))`,
+//! with a tracer of its own, then merges the formats found into `tracer`. The result does not depend
+//! on the number of threads. Tracers are `Send`, and `tracer.merge(other)` can also combine the
+//! results of tracers driven by other means.
+//!
+//! # Merging registries
+//!
+//! Registries traced separately, e.g. for several crates, are combined with
+//! `merge_registries(&mut registry, other)`. Containers defined by both registries must agree:
+//! otherwise, `Error::ConflictingContainers` lists each name collision with the two formats, and
+//! `registry` is left unchanged.
+//!
+//! # Registry diffs
+//!
+//! `diff(&old, &new)` compares two registries, e.g. the formats of a released version and the current ones, and
+//! returns a `RegistryDiff` listing the added and removed containers, and for other containers the added, removed,
+//! retyped, or reordered fields and the added, removed, renamed, or retyped variants. Its `Display` implementation
+//! prints one change per line, for use in CI pipelines checking the evolution of schemas.
+//!
+//! # Fingerprints
+//!
+//! `fingerprint(&registry)` returns a SHA-256 hash of a canonical encoding of the registry, which only depends on the
+//! names and wire formats of containers, fields, and variants (source names and skipped fields are ignored). Fingerprints
+//! are stable across versions of this crate, so that Rust programs and generated clients may embed them to detect schema
+//! drift at runtime.
+//!
+//! # Sharing formats
+//!
+//! Large registries often repeat the same nested formats in many fields. A `FormatInterner` stores structurally equal
+//! formats once: `interner.intern(&format)` returns clones of the same `Arc<Format>`, and after
+//! `interner.intern_registry(&registry)`, `interner.formats()` lists the distinct formats of the registry, nested formats
+//! included, so that tools may clone and visit each of them once.
+//!
+//! # Rewriting formats
+//!
+//! `FormatHolder::map_formats` rewrites the formats of a container from the bottom up with a function mapping each format
+//! to its replacement, e.g. to replace every `TypeName("AccountAddress")` by `[u8; 32]` or to replace references to newtype
+//! structs by their content before code generation, without writing the recursion over every variant of `Format`.
+//! `FormatHolder::visit_mut_with` visits formats from the top down instead, and may skip subformats or stop early.
+//!
+//! # Round-trip checks
+//!
+//! In test suites, `assert_roundtrip(&registry, &samples)` checks that serializing sample values
+//! of a type into the `Value` model, checking them against the traced formats, then
+//! deserializing them back is lossless. This catches custom implementations of `Serialize` and
+//! `Deserialize` that disagree with each other or with the formats.
+//!
+//! # Doc comments
+//!
+//! With the feature `derive`, `#[derive(SerdeReflectDoc)]` records the doc comments of a type,
+//! of its fields, and of its variants. They may then be passed to the code generators of
+//! `serde-generate` so that generated definitions carry the same documentation.
+//!
+//! ```rust,ignore
+//! use serde_reflection::{Documented, SerdeReflectDoc};
+//!
+//! /// A choice.
+//! #[derive(SerdeReflectDoc)]
+//! enum Choice {
+//!     /// The first option.
+//!     A,
+//!     B {
+//!         /// Some payload.
+//!         value: u64,
+//!     },
+//! }
+//!
+//! let comments = Choice::qualified_doc_comments("my_module");
+//! assert_eq!(comments[&vec!["my_module".to_string(), "Choice".to_string()]], "A choice.");
+//! // let config = CodeGeneratorConfig::new("my_module".to_string()).with_comments(comments);
+//! ```
+//!
+//! Alternatively, doc comments may be passed to the tracer, which then only reports those of the
+//! containers found in the registry. This is convenient when tracing many types at once.
+//!
+//! ```rust,ignore
+//! let config = TracerConfig::default().doc_comments(Choice::doc_comments());
+//! let mut tracer = Tracer::new(config);
+//! tracer.trace_type::<Choice>(&Samples::new()).unwrap();
+//! let comments = tracer.qualified_doc_comments("my_module");
+//! let registry = tracer.registry().unwrap();
+//! ```
+//!
+//! # Source names
+//!
+//! Tracing only observes the Serde names of fields and variants, i.e. after renaming by
+//! `#[serde(rename = "..")]` or `#[serde(rename_all = "..")]`. With the feature `derive`,
+//! `#[derive(SerdeReflectNames)]` records the original Rust identifiers. Once passed to the
+//! tracer, they are reported in the `source_name` of the corresponding `Named` values.
+//! Source names are serialized in human-readable formats such as YAML, but not in binary formats,
+//! so that binary encodings of `Named` values do not depend on them.
+//!
+//! ```rust,ignore
+//! use serde_reflection::{SerdeReflectNames, SourceNamed};
+//!
+//! #[derive(Serialize, Deserialize, SerdeReflectNames)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Person {
+//!     first_name: String,
+//! }
+//!
+//! let config = TracerConfig::default().source_names(Person::source_names());
+//! let mut tracer = Tracer::new(config);
+//! tracer.trace_type::<Person>(&Samples::new()).unwrap();
+//! // The field `firstName` has the source name `first_name`.
+//! ```
+//!
+//! # Skipped fields
+//!
+//! Fields marked with `#[serde(skip)]` or `#[serde(skip_deserializing)]` are invisible to the
+//! tracer. With the feature `derive`, `#[derive(SerdeReflectSkipped)]` records them together with
+//! their formats, as far as they can be inferred from their Rust types. Once passed to the tracer,
+//! they appear in the final registry as `Format::Skipped` fields, at their position in the Rust
+//! definition. Skipped fields are never serialized: code generators and round-trip checks ignore
+//! them.
+//!
+//! ```rust,ignore
+//! use serde_reflection::{SerdeReflectSkipped, Skipping};
+//!
+//! #[derive(Serialize, Deserialize, SerdeReflectSkipped)]
+//! struct Session {
+//!     user: String,
+//!     #[serde(skip)]
+//!     cache: Option<Vec<u8>>,
+//! }
+//!
+//! let config = TracerConfig::default().skipped_fields(Session::skipped_fields());
+//! let mut tracer = Tracer::new(config);
+//! tracer.trace_type::<Session>(&Samples::new()).unwrap();
+//! // The field `cache` has the format `Skipped(Option(Seq(U8)))`.
+//! ```
+//!
+//! # Default values
+//!
+//! Fields with a Serde default, i.e. `#[serde(default)]` or `#[serde(default = "..")]` on the
+//! field or on the struct, may be missing from the input of deserializers. With the feature
+//! `derive`, `#[derive(SerdeReflectDefaults)]` records them together with their default values,
+//! traced as `Value`s. Default values are unknown (`None`) for generic types and for fields with
+//! custom serializers. Once passed to `CodeGeneratorConfig::with_default_values` in
+//! `serde-generate`, they become optional parameters of generated TypeScript constructors.
+//!
+//! ```rust,ignore
+//! use serde_reflection::{Defaulted, SerdeReflectDefaults};
+//!
+//! fn default_port() -> u16 {
+//!     8080
+//! }
+//!
+//! #[derive(Serialize, Deserialize, SerdeReflectDefaults)]
+//! struct Server {
+//!     host: String,
+//!     #[serde(default = "default_port")]
+//!     port: u16,
+//! }
+//!
+//! let values = Server::qualified_default_values("config");
+//! // The field `["config", "Server", "port"]` has the default value `Some(Value::U16(8080))`.
+//! ```
+//!
+//! # Formats without tracing
+//!
+//! With the feature `derive`, `#[derive(Reflect)]` computes the formats of a type and of the
+//! containers it uses at compile time, without constructing values or providing samples. Fields
+//! must have types implementing the trait `Reflect`, which covers primitive types, strings,
+//! options, standard collections, tuples, arrays, and smart pointers. Custom (de)serializers,
+//! flattened fields, and untagged or adjacently tagged enums are not supported: such types should
+//! implement `Reflect` manually or be traced.
+//!
+//! ```rust,ignore
+//! use serde_reflection::{reflect_registry, Reflect};
+//!
+//! #[derive(Serialize, Deserialize, Reflect)]
+//! enum Tree {
+//!     Leaf(u64),
+//!     Node(Vec<Tree>),
+//! }
+//!
+//! let registry = reflect_registry::<Tree>();
+//! // Several types may share a registry: `Tree::register(&mut registry)`.
+//! ```
+//!
 //! # Overview
 //!
 //! In the following, more complete example, we extract the Serde formats of two containers
@@ -260,12 +495,18 @@
 //! (1) The first variants of mutually recursive enums must be a "base case". That is,
 //! defaulting to the first variant for every enum type (along with `None` for option values
 //! and `[]` for sequences) must guarantee termination of depth-first traversals of the graph of type
-//! declarations.
+//! declarations. Otherwise, tracing fails with `Error::RecursionLimit`, naming the cycle of containers,
+//! once `TracerConfig::max_recursion_depth` nested containers are being explored.
 //!
 //! (2) If a type runs custom validation checks during deserialization, sample values must have been provided
-//! previously by calling `trace_value`. Besides, the corresponding registered formats
+//! previously by calling `trace_value`, or be supplied on demand by a `SampleProvider`. Besides, the
+//! corresponding registered formats
 //! must not contain unknown parts.
 //!
+//! (3) Types whose `Deserialize` implementation calls `deserialize_any` (e.g. `serde_json::Value`) must
+//! be given a format with `TracerConfig::record_type_as::<T>(format)`. Otherwise, tracing fails with
+//! `Error::DeserializeAny`.
+//!
 //! ## Design Considerations
 //!
 //! Whenever we traverse the graph of type declarations using deserialization callbacks, the type
@@ -293,13 +534,42 @@
 //! traced separately.
 
 mod de;
+mod default;
+mod diff;
+mod doc;
 mod error;
+mod fingerprint;
 mod format;
+mod intern;
+mod merge;
+mod reflect;
+mod roundtrip;
 mod ser;
+mod skip;
+mod source;
 mod trace;
 mod value;
 
+pub use default::{default_value, DefaultValues, Defaulted};
+pub use diff::{diff, ContainerChange, RegistryDiff};
+pub use doc::{DocComments, Documented};
 pub use error::{Error, Result};
-pub use format::{ContainerFormat, Format, FormatHolder, Named, Variable, VariantFormat};
-pub use trace::{Registry, Samples, Tracer, TracerConfig};
+pub use fingerprint::fingerprint;
+pub use format::{
+    ContainerFormat, Format, FormatHolder, Named, Variable, VariantFormat, VisitFlow,
+};
+pub use intern::FormatInterner;
+pub use merge::{merge_registries, ContainerConflict};
+pub use reflect::{reflect_registry, Reflect};
+pub use roundtrip::{assert_roundtrip, check_roundtrip};
+pub use skip::{SkippedFields, Skipping};
+pub use source::{SourceNamed, SourceNames};
+pub use trace::{
+    Registry, SampleProvider, Samples, TraceJob, Tracer, TracerConfig, VariantCoverage,
+};
 pub use value::Value;
+
+#[cfg(feature = "derive")]
+pub use serde_reflection_derive::{
+    Reflect, SerdeReflectDefaults, SerdeReflectDoc, SerdeReflectNames, SerdeReflectSkipped,
+};
@@ -0,0 +1,44 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    error::{Error, Result},
+    format::ContainerFormat,
+    trace::Registry,
+};
+
+/// A container defined with different formats by two registries, as reported by
+/// `merge_registries`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContainerConflict {
+    /// Name of the container.
+    pub name: String,
+    /// Format of the container in the registry being extended.
+    pub left: ContainerFormat,
+    /// Format of the container in the registry being merged.
+    pub right: ContainerFormat,
+}
+
+/// Add the containers of `other` to `registry`, e.g. to combine the registries of several crates
+/// traced separately. Containers defined by both registries must have the same format.
+///
+/// If some containers conflict, `Error::ConflictingContainers` lists all of them, in the order of
+/// their names, and `registry` is left unchanged.
+pub fn merge_registries(registry: &mut Registry, other: Registry) -> Result<()> {
+    let conflicts = other
+        .iter()
+        .filter_map(|(name, right)| match registry.get(name) {
+            Some(left) if left != right => Some(ContainerConflict {
+                name: name.clone(),
+                left: left.clone(),
+                right: right.clone(),
+            }),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !conflicts.is_empty() {
+        return Err(Error::ConflictingContainers(conflicts));
+    }
+    registry.extend(other);
+    Ok(())
+}
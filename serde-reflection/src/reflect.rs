@@ -0,0 +1,238 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{format::Format, trace::Registry};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+    rc::Rc,
+    sync::Arc,
+};
+
+/// Rust types exposing their Serde formats without tracing, usually implemented with
+/// `#[derive(Reflect)]` (feature `derive`).
+pub trait Reflect {
+    /// Format of the type where it is used, e.g. `Format::TypeName("MyStruct")` for a
+    /// container.
+    fn format() -> Format;
+
+    /// Record the formats of the containers used by the type, including the type itself, in
+    /// `registry`. Containers already present in `registry` are left unchanged.
+    fn register(_registry: &mut Registry) {}
+}
+
+/// The registry of the containers used by the type `T`, as computed by `Reflect`.
+///
+/// Several types may be collected in the same registry with `Reflect::register`.
+pub fn reflect_registry<T>() -> Registry
+where
+    T: ?Sized + Reflect,
+{
+    let mut registry = Registry::new();
+    T::register(&mut registry);
+    registry
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(
+            impl Reflect for $ty {
+                fn format() -> Format {
+                    Format::$format
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive! {
+    () => Unit,
+    bool => Bool,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    isize => I64,
+    i128 => I128,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    usize => U64,
+    u128 => U128,
+    f32 => F32,
+    f64 => F64,
+    char => Char,
+    str => Str,
+    String => Str,
+}
+
+/// Smart pointers and references are transparent to Serde.
+macro_rules! impl_pointer {
+    ($($ty:ty),*) => {
+        $(
+            impl<T> Reflect for $ty
+            where
+                T: ?Sized + Reflect,
+            {
+                fn format() -> Format {
+                    T::format()
+                }
+
+                fn register(registry: &mut Registry) {
+                    T::register(registry)
+                }
+            }
+        )*
+    };
+}
+
+impl_pointer!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+impl<T> Reflect for Option<T>
+where
+    T: Reflect,
+{
+    fn format() -> Format {
+        Format::Option(Box::new(T::format()))
+    }
+
+    fn register(registry: &mut Registry) {
+        T::register(registry)
+    }
+}
+
+macro_rules! impl_seq {
+    ($($ty:ty),*) => {
+        $(
+            impl<T> Reflect for $ty
+            where
+                T: Reflect,
+            {
+                fn format() -> Format {
+                    Format::Seq(Box::new(T::format()))
+                }
+
+                fn register(registry: &mut Registry) {
+                    T::register(registry)
+                }
+            }
+        )*
+    };
+}
+
+impl_seq!([T], Vec<T>, VecDeque<T>, LinkedList<T>);
+
+/// Sets have the wire layout of sequences.
+macro_rules! impl_set {
+    ($($ty:ty),*) => {
+        $(
+            impl<T> Reflect for $ty
+            where
+                T: Reflect,
+            {
+                fn format() -> Format {
+                    Format::Set(Box::new(T::format()))
+                }
+
+                fn register(registry: &mut Registry) {
+                    T::register(registry)
+                }
+            }
+        )*
+    };
+}
+
+impl_set!(BTreeSet<T>, HashSet<T>);
+
+macro_rules! impl_map {
+    ($($ty:ty),*) => {
+        $(
+            impl<K, V> Reflect for $ty
+            where
+                K: Reflect,
+                V: Reflect,
+            {
+                fn format() -> Format {
+                    Format::Map {
+                        key: Box::new(K::format()),
+                        value: Box::new(V::format()),
+                    }
+                }
+
+                fn register(registry: &mut Registry) {
+                    K::register(registry);
+                    V::register(registry);
+                }
+            }
+        )*
+    };
+}
+
+impl_map!(BTreeMap<K, V>, HashMap<K, V>);
+
+macro_rules! impl_tuple {
+    ($(($($name:ident),+))*) => {
+        $(
+            impl<$($name),+> Reflect for ($($name,)+)
+            where
+                $($name: Reflect),+
+            {
+                fn format() -> Format {
+                    Format::Tuple(vec![$($name::format()),+])
+                }
+
+                fn register(registry: &mut Registry) {
+                    $($name::register(registry);)+
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple! {
+    (T0)
+    (T0, T1)
+    (T0, T1, T2)
+    (T0, T1, T2, T3)
+    (T0, T1, T2, T3, T4)
+    (T0, T1, T2, T3, T4, T5)
+    (T0, T1, T2, T3, T4, T5, T6)
+    (T0, T1, T2, T3, T4, T5, T6, T7)
+}
+
+/// Arrays up to 32 elements, as supported by Serde.
+macro_rules! impl_array {
+    ($($size:expr),*) => {
+        $(
+            impl<T> Reflect for [T; $size]
+            where
+                T: Reflect,
+            {
+                fn format() -> Format {
+                    Format::TupleArray {
+                        content: Box::new(T::format()),
+                        size: $size,
+                    }
+                }
+
+                fn register(registry: &mut Registry) {
+                    T::register(registry)
+                }
+            }
+        )*
+    };
+}
+
+/// Empty arrays are traced as empty tuples, and Serde does not require `T` to be serializable.
+impl<T> Reflect for [T; 0] {
+    fn format() -> Format {
+        Format::Tuple(Vec::new())
+    }
+
+    fn register(_registry: &mut Registry) {}
+}
+
+impl_array!(
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
+    27, 28, 29, 30, 31, 32
+);
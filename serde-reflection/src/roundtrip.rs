@@ -0,0 +1,255 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    error::{Error, Result},
+    format::*,
+    trace::{Registry, Samples, Tracer, TracerConfig},
+    value::Value,
+};
+use serde::{de::DeserializeOwned, de::IntoDeserializer, Serialize};
+
+/// Check that the sample values of type `T` are preserved by a round-trip through the format
+/// model: each sample is serialized into a `Value`, the value is checked against the formats of
+/// `registry`, then deserialized back and serialized again to obtain the same value.
+///
+/// This catches bugs of the tracer as well as custom implementations of `Serialize` and
+/// `Deserialize` that disagree with each other or with the traced formats.
+pub fn check_roundtrip<T>(registry: &Registry, samples: &[T]) -> Result<()>
+where
+    T: Serialize + DeserializeOwned,
+{
+    for (index, sample) in samples.iter().enumerate() {
+        let context = |error: Error| Error::Custom(format!("Sample #{}: {}", index, error));
+        let (format, value) = trace_value(sample).map_err(context)?;
+        check_value(registry, &format, &value, &mut vec!["$".to_string()]).map_err(context)?;
+        let copy = T::deserialize((&value).into_deserializer()).map_err(context)?;
+        let (_, new_value) = trace_value(&copy).map_err(context)?;
+        if new_value != value {
+            return Err(context(Error::Custom(format!(
+                "Value changed after a round-trip: {:?} became {:?}",
+                value, new_value
+            ))));
+        }
+    }
+    Ok(())
+}
+
+/// Same as `check_roundtrip` but panics with an explanation of the problem, for use in test
+/// suites.
+pub fn assert_roundtrip<T>(registry: &Registry, samples: &[T])
+where
+    T: Serialize + DeserializeOwned,
+{
+    if let Err(error) = check_roundtrip(registry, samples) {
+        panic!("Round-trip check failed: {}", error);
+    }
+}
+
+fn trace_value<T>(value: &T) -> Result<(Format, Value)>
+where
+    T: Serialize,
+{
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_value(&mut Samples::new(), value)
+}
+
+fn mismatch(path: &[String], format: &dyn std::fmt::Debug, value: &Value) -> Error {
+    Error::Custom(format!(
+        "At {}, value {:?} does not match format {:?}",
+        path.join(""),
+        value,
+        format
+    ))
+}
+
+/// Check that `value` follows `format`, where container names are resolved in `registry`.
+fn check_value(
+    registry: &Registry,
+    format: &Format,
+    value: &Value,
+    path: &mut Vec<String>,
+) -> Result<()> {
+    use Format::*;
+
+    match (format, value) {
+        (Variable(variable), _) => match &*variable.borrow() {
+            Some(format) => check_value(registry, format, value, path),
+            // Unknown formats only arise from empty sequences and missing optional values.
+            None => Ok(()),
+        },
+        (TypeName(name), _) => {
+            let container = registry.get(name).ok_or_else(|| {
+                Error::Custom(format!("At {}, unknown container {}", path.join(""), name))
+            })?;
+            check_container(registry, name, container, value, path)
+        }
+        (Custom { layout, .. }, _) => check_value(registry, layout, value, path),
+        (Decimal { mantissa, .. }, _) => check_value(registry, mantissa, value, path),
+        (Unit, Value::Unit)
+        | (Bool, Value::Bool(_))
+        | (I8, Value::I8(_))
+        | (I16, Value::I16(_))
+        | (I32, Value::I32(_))
+        | (I64, Value::I64(_))
+        | (I128, Value::I128(_))
+        | (U8, Value::U8(_))
+        | (U16, Value::U16(_))
+        | (U32, Value::U32(_))
+        | (U64, Value::U64(_))
+        | (U128, Value::U128(_))
+        | (F32, Value::F32(_))
+        | (F64, Value::F64(_))
+        | (Char, Value::Char(_))
+        | (Str, Value::Str(_))
+        | (Str, Value::Number(_))
+        | (Bytes, Value::Bytes(_))
+        | (Option(_), Value::Option(None)) => Ok(()),
+        (Option(format), Value::Option(Some(value))) => check_value(registry, format, value, path),
+        (Seq(format), Value::Seq(values)) | (Set(format), Value::Seq(values)) => {
+            for (index, value) in values.iter().enumerate() {
+                path.push(format!("[{}]", index));
+                check_value(registry, format, value, path)?;
+                path.pop();
+            }
+            Ok(())
+        }
+        (Map { key, value: format }, Value::Seq(values)) if values.len() % 2 == 0 => {
+            for (index, entry) in values.chunks(2).enumerate() {
+                path.push(format!("[{}]", index));
+                check_value(registry, key, &entry[0], path)?;
+                check_value(registry, format, &entry[1], path)?;
+                path.pop();
+            }
+            Ok(())
+        }
+        (Tuple(formats), Value::Seq(values)) => check_values(registry, formats, values, path),
+        (TupleArray { content, size }, Value::Seq(values)) if values.len() == *size => {
+            let formats = vec![content.as_ref().clone(); *size];
+            check_values(registry, &formats, values, path)
+        }
+        _ => Err(mismatch(path, format, value)),
+    }
+}
+
+fn check_values(
+    registry: &Registry,
+    formats: &[Format],
+    values: &[Value],
+    path: &mut Vec<String>,
+) -> Result<()> {
+    if formats.len() != values.len() {
+        return Err(Error::Custom(format!(
+            "At {}, expected {} values, found {}",
+            path.join(""),
+            formats.len(),
+            values.len()
+        )));
+    }
+    for (index, (format, value)) in formats.iter().zip(values).enumerate() {
+        path.push(format!(".{}", index));
+        check_value(registry, format, value, path)?;
+        path.pop();
+    }
+    Ok(())
+}
+
+fn check_fields(
+    registry: &Registry,
+    fields: &[Named<Format>],
+    values: &[Value],
+    path: &mut Vec<String>,
+) -> Result<()> {
+    // Skipped fields are absent from values.
+    let fields = fields
+        .iter()
+        .filter(|field| !matches!(field.value, Format::Skipped(_)))
+        .collect::<Vec<_>>();
+    if fields.len() != values.len() {
+        return Err(Error::Custom(format!(
+            "At {}, expected {} fields, found {}",
+            path.join(""),
+            fields.len(),
+            values.len()
+        )));
+    }
+    for (field, value) in fields.into_iter().zip(values) {
+        path.push(format!(".{}", field.name));
+        check_value(registry, &field.value, value, path)?;
+        path.pop();
+    }
+    Ok(())
+}
+
+fn check_container(
+    registry: &Registry,
+    name: &str,
+    container: &ContainerFormat,
+    value: &Value,
+    path: &mut Vec<String>,
+) -> Result<()> {
+    use ContainerFormat::*;
+
+    match (container, value) {
+        (UnitStruct, Value::Unit) => Ok(()),
+        (NewTypeStruct(format), _) => check_value(registry, format, value, path),
+        (TupleStruct(formats), Value::Seq(values)) => check_values(registry, formats, values, path),
+        (Struct(fields), Value::Seq(values)) => check_fields(registry, fields, values, path),
+        (Enum(variants), Value::Variant(index, value)) => {
+            let variant = variants.get(index).ok_or_else(|| {
+                Error::Custom(format!(
+                    "At {}, unknown variant #{} of enum {}",
+                    path.join(""),
+                    index,
+                    name
+                ))
+            })?;
+            path.push(format!("::{}", variant.name));
+            match (&variant.value, value.as_ref()) {
+                (VariantFormat::Unit, Value::Unit) => (),
+                (VariantFormat::NewType(format), value) => {
+                    check_value(registry, format, value, path)?
+                }
+                (VariantFormat::Tuple(formats), Value::Seq(values)) => {
+                    check_values(registry, formats, values, path)?
+                }
+                (VariantFormat::Struct(fields), Value::Seq(values)) => {
+                    check_fields(registry, fields, values, path)?
+                }
+                (format, value) => return Err(mismatch(path, format, value)),
+            }
+            path.pop();
+            Ok(())
+        }
+        (InternallyTaggedEnum { variants, .. }, Value::Seq(values)) => {
+            let (variant_name, values) = match values.split_first() {
+                Some((Value::Str(variant_name), values)) => (variant_name, values),
+                _ => return Err(mismatch(path, container, value)),
+            };
+            let variant = variants
+                .values()
+                .find(|variant| &variant.name == variant_name)
+                .ok_or_else(|| {
+                    Error::Custom(format!(
+                        "At {}, unknown variant {} of enum {}",
+                        path.join(""),
+                        variant_name,
+                        name
+                    ))
+                })?;
+            path.push(format!("::{}", variant.name));
+            match &variant.value {
+                VariantFormat::Unit => check_fields(registry, &[], values, path)?,
+                VariantFormat::Struct(fields) => check_fields(registry, fields, values, path)?,
+                // The fields of the inner struct follow the tag.
+                VariantFormat::NewType(format) => {
+                    check_value(registry, format, &Value::Seq(values.to_vec()), path)?
+                }
+                format => return Err(mismatch(path, format, value)),
+            }
+            path.pop();
+            Ok(())
+        }
+        _ => Err(mismatch(path, container, value)),
+    }
+}
@@ -4,7 +4,7 @@
 use crate::{
     error::{Error, Result},
     format::*,
-    trace::{Samples, Tracer},
+    trace::{arbitrary_precision_number, check_layout, Samples, Tracer, NUMBER_TOKEN},
     value::Value,
 };
 use serde::{ser, Serialize};
@@ -21,6 +21,33 @@ impl<'a> Serializer<'a> {
     pub(crate) fn new(tracer: &'a mut Tracer, samples: &'a mut Samples) -> Self {
         Self { tracer, samples }
     }
+
+    /// Serialize `value`, tracing it as a custom format if its type was declared with
+    /// `TracerConfig::map_external_type`. Values of such types are recorded as samples.
+    pub(crate) fn trace<T>(self, value: &T) -> Result<(Format, Value)>
+    where
+        T: ?Sized + Serialize,
+    {
+        let type_name = std::any::type_name::<T>();
+        let custom_format = self.tracer.external_format(type_name);
+        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
+        let format = match format {
+            Format::Seq(content) if self.tracer.config.set_types.contains(type_name) => {
+                Format::Set(content)
+            }
+            format => format,
+        };
+        match custom_format {
+            Some(custom_format) => {
+                if let Format::Custom { layout, .. } = &custom_format {
+                    check_layout(format, layout)?;
+                }
+                self.samples.insert(type_name, value.clone());
+                Ok((custom_format, value))
+            }
+            None => Ok((format, value)),
+        }
+    }
 }
 
 impl<'a> ser::Serializer for Serializer<'a> {
@@ -106,7 +133,7 @@ impl<'a> ser::Serializer for Serializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = v.serialize(self)?;
+        let (format, value) = self.trace(v)?;
         Ok((
             Format::Option(Box::new(format)),
             Value::Option(Some(Box::new(value))),
@@ -118,6 +145,9 @@ impl<'a> ser::Serializer for Serializer<'a> {
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<(Format, Value)> {
+        if let Some(format) = self.tracer.std_format(name, &[]) {
+            return Ok((format, Value::Unit));
+        }
         self.tracer.record_container(
             self.samples,
             name,
@@ -148,6 +178,34 @@ impl<'a> ser::Serializer for Serializer<'a> {
         T: ?Sized + Serialize,
     {
         let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
+        if let Some(custom_format) = self.tracer.custom_format(name) {
+            self.tracer.check_custom_layout(name, format)?;
+            if self.tracer.config.record_samples_for_newtype_structs {
+                self.samples.insert(name, value.clone());
+            }
+            return Ok((custom_format, value));
+        }
+        if let Some(scale) = self.tracer.config.decimal_formats.get(name) {
+            self.tracer.check_decimal_mantissa(&format)?;
+            if self.tracer.config.record_samples_for_newtype_structs {
+                self.samples.insert(name, value.clone());
+            }
+            let decimal = Format::Decimal {
+                mantissa: Box::new(format),
+                scale: *scale,
+            };
+            return Ok((decimal, value));
+        }
+        if self.tracer.config.type_aliases.contains(name) {
+            if self.tracer.config.record_samples_for_newtype_structs {
+                self.samples.insert(name, value.clone());
+            }
+            let alias = Format::Custom {
+                name: name.into(),
+                layout: Box::new(format),
+            };
+            return Ok((alias, value));
+        }
         self.tracer.record_container(
             self.samples,
             name,
@@ -167,7 +225,7 @@ impl<'a> ser::Serializer for Serializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(value)?;
         self.tracer.record_variant(
             self.samples,
             name,
@@ -287,7 +345,7 @@ impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(value)?;
         self.format.unify(format)?;
         self.values.push(value);
         Ok(())
@@ -314,7 +372,7 @@ impl<'a> ser::SerializeTuple for TupleSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(value)?;
         self.formats.push(format);
         self.values.push(value);
         Ok(())
@@ -342,7 +400,7 @@ impl<'a> ser::SerializeTupleStruct for TupleStructSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(value)?;
         self.formats.push(format);
         self.values.push(value);
         Ok(())
@@ -380,7 +438,7 @@ impl<'a> ser::SerializeTupleVariant for TupleVariantSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = v.serialize(Serializer::new(self.tracer, self.samples))?;
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(v)?;
         self.formats.push(format);
         self.values.push(value);
         Ok(())
@@ -417,7 +475,7 @@ impl<'a> ser::SerializeMap for MapSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = key.serialize(Serializer::new(self.tracer, self.samples))?;
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(key)?;
         self.key_format.unify(format)?;
         self.values.push(value);
         Ok(())
@@ -427,7 +485,7 @@ impl<'a> ser::SerializeMap for MapSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(value)?;
         self.value_format.unify(format)?;
         self.values.push(value);
         Ok(())
@@ -460,16 +518,27 @@ impl<'a> ser::SerializeStruct for StructSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
-        self.fields.push(Named {
-            name: name.into(),
-            value: format,
-        });
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(value)?;
+        self.fields.push(Named::new(name, format));
         self.values.push(value);
         Ok(())
     }
 
     fn end(self) -> Result<(Format, Value)> {
+        let names = self
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        if self.name == NUMBER_TOKEN {
+            if let [Value::Str(digits)] = self.values.as_slice() {
+                let value = Value::Number(digits.clone());
+                return Ok((arbitrary_precision_number(), value));
+            }
+        }
+        if let Some(format) = self.tracer.std_format(self.name, &names) {
+            return Ok((format, Value::Seq(self.values)));
+        }
         let format = ContainerFormat::Struct(self.fields);
         let value = Value::Seq(self.values);
         self.tracer.record_container(
@@ -500,11 +569,8 @@ impl<'a> ser::SerializeStructVariant for StructVariantSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        let (format, value) = value.serialize(Serializer::new(self.tracer, self.samples))?;
-        self.fields.push(Named {
-            name: name.into(),
-            value: format,
-        });
+        let (format, value) = Serializer::new(self.tracer, self.samples).trace(value)?;
+        self.fields.push(Named::new(name, format));
         self.values.push(value);
         Ok(())
     }
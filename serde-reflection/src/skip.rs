@@ -0,0 +1,18 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::format::Format;
+use std::collections::BTreeMap;
+
+/// Fields skipped by Serde (e.g. with `#[serde(skip)]`) indexed by their qualified Serde names,
+/// e.g. `["MyStruct", "cache"]`. Each field comes with its position among the fields of the
+/// Rust definition and its format, as far as it can be inferred from the Rust type.
+pub type SkippedFields = BTreeMap<Vec<String>, (usize, Format)>;
+
+/// Rust definitions exposing the fields that Serde skips, usually implemented with
+/// `#[derive(SerdeReflectSkipped)]` (feature `derive`).
+pub trait Skipping {
+    /// Fields of the type, and of its variants, marked with `#[serde(skip)]`,
+    /// `#[serde(skip_serializing)]`, or `#[serde(skip_deserializing)]`.
+    fn skipped_fields() -> SkippedFields;
+}
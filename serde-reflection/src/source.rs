@@ -0,0 +1,16 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::collections::BTreeMap;
+
+/// Rust identifiers of fields and variants indexed by their qualified Serde names, e.g.
+/// `["MyStruct", "myField"]` for a field `my_field` renamed by `#[serde(rename_all = "camelCase")]`.
+pub type SourceNames = BTreeMap<Vec<String>, String>;
+
+/// Rust definitions exposing the identifiers of their renamed fields and variants, usually
+/// implemented with `#[derive(SerdeReflectNames)]` (feature `derive`).
+pub trait SourceNamed {
+    /// Identifiers of the fields and variants of the type, and of the fields of its variants,
+    /// whose Serde names differ from them.
+    fn source_names() -> SourceNames;
+}
@@ -3,17 +3,30 @@
 
 use crate::{
     de::Deserializer,
+    doc::DocComments,
     error::{Error, Result},
     format::*,
     ser::Serializer,
+    skip::SkippedFields,
+    source::SourceNames,
     value::Value,
 };
-use serde::{de::DeserializeSeed, Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed},
+    Deserialize, Serialize,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    marker::PhantomData,
+};
 
 /// A map of container formats.
 pub type Registry = BTreeMap<String, ContainerFormat>;
 
+/// A unit of work for `Tracer::trace_types_parallel`, typically tracing the deserialization of
+/// one type with `tracer.trace_type::<T>(samples)`.
+pub type TraceJob<'a> = Box<dyn Fn(&mut Tracer, &Samples) -> Result<()> + Sync + 'a>;
+
 /// Structure to drive the tracing of Serde serialization and deserialization.
 /// This typically aims at computing a `Registry`.
 #[derive(Debug)]
@@ -28,6 +41,33 @@ pub struct Tracer {
     /// Enums that have detected to be yet incomplete (i.e. missing variants)
     /// while tracing deserialization.
     pub(crate) incomplete_enums: BTreeSet<String>,
+
+    /// Containers for which a sample was requested from a `SampleProvider` so far, while
+    /// tracing deserialization with `Tracer::trace_type_with_provider`.
+    pub(crate) requested_samples: Option<BTreeSet<&'static str>>,
+
+    /// Containers being explored while tracing deserialization, from the outermost one.
+    pub(crate) explored_containers: Vec<&'static str>,
+
+    /// Names of the variants declared by the enums whose deserialization was traced.
+    pub(crate) declared_variants: BTreeMap<&'static str, &'static [&'static str]>,
+}
+
+/// Variants of an enum found while tracing, as reported by `Tracer::coverage`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VariantCoverage {
+    /// Names of the variants found so far, indexed by variant index.
+    pub visited: BTreeMap<u32, String>,
+    /// Names of the variants not found yet, indexed by variant index, or `None` if the list of
+    /// variants is unknown because only the serialization of the enum was traced.
+    pub unexplored: Option<BTreeMap<u32, String>>,
+}
+
+impl VariantCoverage {
+    /// Whether all the variants of the enum are known to have been found.
+    pub fn is_complete(&self) -> bool {
+        matches!(&self.unexplored, Some(unexplored) if unexplored.is_empty())
+    }
 }
 
 /// User inputs, aka "samples", recorded during serialization.
@@ -35,6 +75,11 @@ pub struct Tracer {
 #[derive(Debug, Default)]
 pub struct Samples {
     pub(crate) values: BTreeMap<&'static str, Value>,
+    /// Copies of the values holding sequences of bytes, where these sequences are written as
+    /// bytes, so that borrowed byte slices (e.g. `&[u8]`) can be deserialized from them.
+    borrowable_values: BTreeMap<&'static str, Value>,
+    /// Formats of the payloads of the samples recorded with `Tracer::trace_sample_by_name`.
+    payload_formats: BTreeMap<&'static str, Format>,
 }
 
 impl Samples {
@@ -47,15 +92,90 @@ impl Samples {
     pub fn value(&self, name: &'static str) -> Option<&Value> {
         self.values.get(name)
     }
+
+    pub(crate) fn insert(&mut self, name: &'static str, value: Value) {
+        match value.with_byte_sequences_as_bytes() {
+            Some(borrowable_value) => self.borrowable_values.insert(name, borrowable_value),
+            None => self.borrowable_values.remove(name),
+        };
+        self.values.insert(name, value);
+    }
+
+    /// Whether the sample of the container `name` was recorded with
+    /// `Tracer::trace_sample_by_name`, in which case it is used regardless of `TracerConfig`.
+    pub(crate) fn is_named(&self, name: &'static str) -> bool {
+        self.payload_formats.contains_key(name)
+    }
+
+    /// The sample to deserialize for the container `name`, if any.
+    pub(crate) fn deserializable_value(&self, name: &'static str) -> Option<&Value> {
+        self.borrowable_values
+            .get(name)
+            .or_else(|| self.values.get(name))
+    }
+}
+
+/// Source of samples generated on demand, e.g. for types whose `Deserialize` implementation
+/// enforces invariants (checksums, ranges, etc) that candidate values built by the tracer would
+/// not pass.
+///
+/// During `Tracer::trace_type_with_provider`, the provider is consulted once for each container
+/// that records samples (see `TracerConfig`) and has no sample yet.
+pub trait SampleProvider {
+    /// Record a sample of the container `name`, typically by calling
+    /// `tracer.trace_value(samples, &value)` on a valid value. Returns `false` if no sample is
+    /// available for this container.
+    fn provide(
+        &self,
+        name: &'static str,
+        tracer: &mut Tracer,
+        samples: &mut Samples,
+    ) -> Result<bool>;
+}
+
+impl<F> SampleProvider for F
+where
+    F: Fn(&'static str, &mut Tracer, &mut Samples) -> Result<bool>,
+{
+    fn provide(
+        &self,
+        name: &'static str,
+        tracer: &mut Tracer,
+        samples: &mut Samples,
+    ) -> Result<bool> {
+        self(name, tracer, samples)
+    }
 }
 
 /// Configuration object to create a tracer.
-#[derive(Debug)]
+///
+/// Byte buffers that (de)serialize as bytes, such as `serde_bytes::ByteBuf`, fields with
+/// `#[serde(with = "serde_bytes")]`, and `bytes::Bytes`, are always traced as `Format::Bytes`.
+/// Plain `Vec<u8>` values are traced as `Format::Seq(U8)`.
+///
+/// Borrowed types such as `&str`, `&[u8]`, and `Cow<str>` can be traced directly: values
+/// returned by `Tracer::trace_type` borrow from the samples. Byte slices are serialized as
+/// sequences but deserialized as bytes; see `borrowed_bytes` to trace them.
+#[derive(Debug, Clone)]
 pub struct TracerConfig {
     pub(crate) is_human_readable: bool,
     pub(crate) record_samples_for_newtype_structs: bool,
     pub(crate) record_samples_for_tuple_structs: bool,
     pub(crate) record_samples_for_structs: bool,
+    pub(crate) borrowed_bytes: bool,
+    pub(crate) nonzero_integers: bool,
+    pub(crate) custom_formats: BTreeMap<&'static str, Format>,
+    pub(crate) type_aliases: BTreeSet<&'static str>,
+    pub(crate) decimal_formats: BTreeMap<&'static str, u32>,
+    pub(crate) std_formats: BTreeMap<&'static str, Format>,
+    pub(crate) external_types: BTreeMap<&'static str, Format>,
+    pub(crate) source_names: SourceNames,
+    pub(crate) skipped_fields: SkippedFields,
+    pub(crate) doc_comments: DocComments,
+    pub(crate) max_recursion_depth: usize,
+    pub(crate) type_hints: BTreeMap<&'static str, Format>,
+    pub(crate) option_contents: BTreeMap<&'static str, &'static str>,
+    pub(crate) set_types: BTreeSet<&'static str>,
 }
 
 impl Default for TracerConfig {
@@ -66,10 +186,32 @@ impl Default for TracerConfig {
             record_samples_for_newtype_structs: true,
             record_samples_for_tuple_structs: false,
             record_samples_for_structs: false,
+            borrowed_bytes: false,
+            nonzero_integers: false,
+            custom_formats: BTreeMap::new(),
+            type_aliases: BTreeSet::new(),
+            decimal_formats: BTreeMap::new(),
+            std_formats: BTreeMap::new(),
+            external_types: BTreeMap::new(),
+            source_names: SourceNames::new(),
+            skipped_fields: SkippedFields::new(),
+            doc_comments: DocComments::new(),
+            max_recursion_depth: 64,
+            type_hints: BTreeMap::new(),
+            option_contents: BTreeMap::new(),
+            set_types: BTreeSet::new(),
         }
     }
 }
 
+/// Containers of the standard library that may be traced with a canonical format, with the
+/// names of their fields.
+const STD_CONTAINERS: &[(&str, &[&str])] = &[
+    ("Duration", &["secs", "nanos"]),
+    ("PhantomData", &[]),
+    ("SystemTime", &["secs_since_epoch", "nanos_since_epoch"]),
+];
+
 impl TracerConfig {
     /// Whether to trace the human readable encoding of (de)serialization.
     #[allow(clippy::wrong_self_convention)]
@@ -95,6 +237,177 @@ impl TracerConfig {
         self.record_samples_for_structs = value;
         self
     }
+
+    /// Trace borrowed byte slices, e.g. `&[u8]`, as `Format::Bytes`. Byte slices are serialized
+    /// as sequences of bytes but deserialized as bytes. Both have the same layout in binary
+    /// encodings, but tracing both otherwise fails with `Error::Incompatible`.
+    pub fn borrowed_bytes(mut self, value: bool) -> Self {
+        self.borrowed_bytes = value;
+        self
+    }
+
+    /// Trace the nonzero integers of the standard library, e.g. `NonZeroU64`, as custom formats
+    /// named after the Rust type and laid out as the corresponding integer, so that code
+    /// generators may check that values are nonzero. They are traced as plain integers otherwise.
+    /// Like type hints, this does not apply to the contents of newtype structs.
+    pub fn nonzero_integers(mut self, value: bool) -> Self {
+        self.nonzero_integers = value;
+        self
+    }
+
+    /// Trace the newtype struct `name` as a `Format::Custom` with the given wire `layout`
+    /// instead of a named container. The layout must be normalized (e.g. use `TupleArray`
+    /// for Rust arrays) and match the content of the newtype struct.
+    pub fn custom_format(mut self, name: &'static str, layout: Format) -> Self {
+        self.custom_formats.insert(name, layout);
+        self
+    }
+
+    /// Trace the newtype struct `name` as a type alias, that is, a `Format::Custom` whose
+    /// layout is the traced content of the newtype struct (e.g. `type BlockHeight = u64`).
+    /// Code generators may then emit a native alias. Type aliases cannot be recursive.
+    pub fn type_alias(mut self, name: &'static str) -> Self {
+        self.type_aliases.insert(name);
+        self
+    }
+
+    /// Trace the newtype struct `name` as a fixed-point decimal number, that is, a
+    /// `Format::Decimal` with the given `scale`. The newtype struct must hold a signed integer
+    /// counting units of `10^-scale`, e.g. cents for a scale of 2.
+    pub fn decimal_format(mut self, name: &'static str, scale: u32) -> Self {
+        self.decimal_formats.insert(name, scale);
+        self
+    }
+
+    /// Trace `std::time::Duration` and `std::time::SystemTime` as the custom formats `Duration`
+    /// and `SystemTime` instead of named structs. Both are laid out on the wire as a number of
+    /// seconds followed by a number of nanoseconds, i.e. `(u64, u32)`, where `SystemTime` counts
+    /// from the UNIX epoch. Code generators may then use native types.
+    pub fn time_formats(mut self, value: bool) -> Self {
+        for name in ["Duration", "SystemTime"] {
+            self.set_std_format(name, value);
+        }
+        self
+    }
+
+    /// Trace the containers of the standard library with canonical formats instead of their
+    /// internal layouts: `Duration` and `SystemTime` as with `time_formats`, and
+    /// `std::marker::PhantomData` as `Format::Unit`. Other types of the standard library are
+    /// traced with canonical formats regardless, e.g. `PathBuf` as `Format::Str`, while
+    /// `IpAddr` and `SocketAddr` are complete enums. See `nonzero_integers` for `NonZeroU32`.
+    pub fn std_formats(mut self, value: bool) -> Self {
+        for (name, _) in STD_CONTAINERS {
+            self.set_std_format(name, value);
+        }
+        self
+    }
+
+    /// Trace the container `name` of the standard library (see `std_formats`) as the given
+    /// format, e.g. `Format::Tuple(vec![Format::U64, Format::U32])` for an anonymous `Duration`.
+    /// The format must have the same wire layout as the container. Other names are ignored.
+    pub fn std_format(mut self, name: &'static str, format: Format) -> Self {
+        self.std_formats.insert(name, format);
+        self
+    }
+
+    fn set_std_format(&mut self, name: &'static str, value: bool) {
+        if value {
+            let format = match name {
+                "PhantomData" => Format::Unit,
+                _ => Format::Custom {
+                    name: name.into(),
+                    layout: Box::new(Format::Tuple(vec![Format::U64, Format::U32])),
+                },
+            };
+            self.std_formats.insert(name, format);
+        } else {
+            self.std_formats.remove(name);
+        }
+    }
+
+    /// Trace the values of the third-party type `path`, e.g. `uuid::Uuid` or `chrono::DateTime`,
+    /// as a `Format::Custom` named after the last segment of the path (e.g. `Uuid`) with the
+    /// given wire `layout`, instead of their internal representation. Types match when they are
+    /// defined in the same crate with the same name, regardless of their module and generic
+    /// parameters (e.g. `chrono::datetime::DateTime<Utc>`). The layout must be normalized and
+    /// agree with the traced serialization. Values serialized with `trace_value` are recorded
+    /// as samples for deserialization, which is useful for types that validate their content.
+    /// Like type hints, mappings do not apply to the contents of newtype structs.
+    pub fn map_external_type(mut self, path: &'static str, layout: Format) -> Self {
+        self.external_types.insert(path, layout);
+        self
+    }
+
+    /// Record the Rust identifiers of renamed fields and variants, usually obtained from
+    /// `SourceNamed::source_names`, as the `source_name` of the corresponding `Named` values
+    /// in the final registry. May be called once for each type.
+    pub fn source_names(mut self, names: SourceNames) -> Self {
+        self.source_names.extend(names);
+        self
+    }
+
+    /// Record the fields skipped by Serde, usually obtained from `Skipping::skipped_fields`,
+    /// as `Format::Skipped` fields of their struct or variant in the final registry, where
+    /// Serde does not report them otherwise. May be called once for each type.
+    pub fn skipped_fields(mut self, fields: SkippedFields) -> Self {
+        self.skipped_fields.extend(fields);
+        self
+    }
+
+    /// Record the doc comments of a type, usually obtained from `Documented::doc_comments`,
+    /// so that `Tracer::qualified_doc_comments` reports them together with the traced formats.
+    /// May be called once for each type.
+    pub fn doc_comments(mut self, comments: DocComments) -> Self {
+        self.doc_comments.extend(comments);
+        self
+    }
+
+    /// Maximal number of nested containers explored while tracing deserialization (64 by
+    /// default). Deeper explorations fail with `Error::RecursionLimit`, which names the cycle
+    /// of containers responsible for them, e.g. when the first variant of a recursive enum
+    /// is not a base case.
+    pub fn max_recursion_depth(mut self, value: usize) -> Self {
+        self.max_recursion_depth = value;
+        self
+    }
+
+    /// Trace the values of type `T` as the given format when their `Deserialize` implementation
+    /// calls `deserialize_any`, which tracing does not support otherwise (e.g. a JSON value
+    /// recorded as `Format::Str`). Such values are deserialized from a default value of the
+    /// format: zero, `false`, an empty string, `None`, or an empty sequence or map. Hints apply
+    /// to traced types, fields, elements, map keys and values, variant contents, and the contents
+    /// of options, but not to the contents of newtype structs.
+    ///
+    /// Hints are matched by the name that `std::any::type_name` gives to `T`, as computed by the
+    /// same compiler for the hint and for the traced types, so that its exact (unstable) spelling
+    /// does not matter.
+    ///
+    /// The hint `Format::Custom { name: "Number", layout: Str }` for `serde_json::Number` with the
+    /// feature `arbitrary_precision` of `serde_json` records the exact decimal representation of
+    /// such numbers as `Value::Number`.
+    pub fn record_type_as<T>(mut self, format: Format) -> Self {
+        self.type_hints.insert(std::any::type_name::<T>(), format);
+        self.record_option_content::<T>();
+        self
+    }
+
+    /// Trace the values of type `T`, a set that Serde (de)serializes as a sequence, e.g.
+    /// `BTreeSet<Foo>`, as `Format::Set` instead of `Format::Seq`. Like type hints, sets are
+    /// matched by the name that `std::any::type_name` gives to `T`.
+    pub fn record_set<T>(mut self) -> Self {
+        self.set_types.insert(std::any::type_name::<T>());
+        self.record_option_content::<T>();
+        self
+    }
+
+    /// Remember that the content of `Option<T>` has type `T`, since Serde does not tell the
+    /// tracer which type an option holds.
+    fn record_option_content<T>(&mut self) {
+        self.option_contents.insert(
+            std::any::type_name::<Option<T>>(),
+            std::any::type_name::<T>(),
+        );
+    }
 }
 
 impl Tracer {
@@ -104,6 +417,9 @@ impl Tracer {
             config,
             registry: BTreeMap::new(),
             incomplete_enums: BTreeSet::new(),
+            requested_samples: None,
+            explored_containers: Vec::new(),
+            declared_variants: BTreeMap::new(),
         }
     }
 
@@ -116,12 +432,34 @@ impl Tracer {
     where
         T: ?Sized + Serialize,
     {
-        let serializer = Serializer::new(self, samples);
-        let (mut format, sample) = value.serialize(serializer)?;
+        let (mut format, sample) = Serializer::new(self, samples).trace(value)?;
         format.reduce();
         Ok((format, sample))
     }
 
+    /// Record a sample of the container `name` from a payload serialized like its content, i.e.
+    /// the value of a newtype struct, or a tuple of the fields of a struct or a tuple struct.
+    /// This is meant for containers whose Rust type cannot be named or does not implement
+    /// `Serialize`, such as the `#[serde(remote = "..")]` definitions of third-party types.
+    /// * The sample is used whenever the container `name` is deserialized, regardless of the
+    ///   `record_samples_for_*` options of the configuration.
+    /// * The format of the container is derived from the payload, in case the container is
+    ///   not traced otherwise.
+    pub fn trace_sample_by_name<T>(
+        &mut self,
+        samples: &mut Samples,
+        name: &'static str,
+        payload: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let (format, value) = self.trace_value(samples, payload)?;
+        samples.insert(name, value);
+        samples.payload_formats.insert(name, format);
+        Ok(())
+    }
+
     /// Trace a single deserialization of a particular type.
     /// * Nested containers will be added to the tracing registry, indexed by
     /// their (non-qualified) name.
@@ -135,8 +473,10 @@ impl Tracer {
         T: Deserialize<'de>,
     {
         let mut format = Format::unknown();
-        let deserializer = Deserializer::new(self, samples, &mut format);
-        let value = T::deserialize(deserializer)?;
+        self.explored_containers.clear();
+        let deserializer = Deserializer::new(self, samples, &mut format)
+            .with_type_name(Some(std::any::type_name::<T>()));
+        let value = deserializer.deserialize_seed(PhantomData::<T>)?;
         format.reduce();
         Ok((format, value))
     }
@@ -151,8 +491,10 @@ impl Tracer {
         S: DeserializeSeed<'de>,
     {
         let mut format = Format::unknown();
-        let deserializer = Deserializer::new(self, samples, &mut format);
-        let value = seed.deserialize(deserializer)?;
+        self.explored_containers.clear();
+        let deserializer = Deserializer::new(self, samples, &mut format)
+            .with_type_name(Some(std::any::type_name::<S::Value>()));
+        let value = deserializer.deserialize_seed(seed)?;
         format.reduce();
         Ok((format, value))
     }
@@ -203,6 +545,109 @@ impl Tracer {
         }
     }
 
+    /// Same as `trace_type` but the given provider is asked for a sample of each container that
+    /// records samples and has none yet. Samples supplied by the provider are added to `samples`.
+    pub fn trace_type_with_provider<T, P>(
+        &mut self,
+        samples: &mut Samples,
+        provider: &P,
+    ) -> Result<(Format, Vec<T>)>
+    where
+        T: DeserializeOwned,
+        P: SampleProvider + ?Sized,
+    {
+        let mut requested_samples = BTreeSet::new();
+        loop {
+            let registry = self.registry.clone();
+            let incomplete_enums = self.incomplete_enums.clone();
+            self.requested_samples = Some(requested_samples);
+            let result = self.trace_type::<T>(samples);
+            requested_samples = self.requested_samples.take().unwrap_or_default();
+            match result {
+                Err(Error::MissingSample(name)) => {
+                    // Discard the interrupted analysis (notably, the enum variant being
+                    // explored) then restart once the provider has recorded its sample.
+                    self.registry = registry;
+                    self.incomplete_enums = incomplete_enums;
+                    provider.provide(name, self, samples)?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run the given jobs on `threads` threads, each with its own tracer configured as this one,
+    /// then merge the formats found by these tracers into this one.
+    /// * Jobs are assigned to threads in a round-robin fashion and the tracers are merged in the
+    ///   order of their threads, so that the result only depends on the list of jobs.
+    /// * If some jobs fail, the error of the first failing job in the list is returned and
+    ///   nothing is merged.
+    pub fn trace_types_parallel(
+        &mut self,
+        samples: &Samples,
+        jobs: &[TraceJob],
+        threads: usize,
+    ) -> Result<()> {
+        let threads = threads.max(1);
+        let results = std::thread::scope(|scope| {
+            let handles = (0..threads)
+                .map(|thread| {
+                    let config = self.config.clone();
+                    scope.spawn(move || {
+                        let mut tracer = Tracer::new(config);
+                        for index in (thread..jobs.len()).step_by(threads) {
+                            jobs[index](&mut tracer, samples).map_err(|error| (index, error))?;
+                        }
+                        Ok(tracer)
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("tracing threads should not panic"))
+                .collect::<Vec<std::result::Result<Tracer, (usize, Error)>>>()
+        });
+        let mut tracers = Vec::new();
+        let mut failures = Vec::new();
+        for result in results {
+            match result {
+                Ok(tracer) => tracers.push(tracer),
+                Err(failure) => failures.push(failure),
+            }
+        }
+        if let Some((_, error)) = failures.into_iter().min_by_key(|(index, _)| *index) {
+            return Err(error);
+        }
+        for tracer in tracers {
+            self.merge(tracer)?;
+        }
+        Ok(())
+    }
+
+    /// Merge the formats found by another tracer into this one, e.g. after tracing disjoint
+    /// sets of types on different threads. The configuration of `other` is ignored.
+    pub fn merge(&mut self, other: Tracer) -> Result<()> {
+        for (name, mut format) in other.registry {
+            // Unification expects the formats of `other` to hold no known variables.
+            format.reduce();
+            self.unify_container(name, format)?;
+        }
+        self.declared_variants.extend(other.declared_variants);
+        self.incomplete_enums.extend(other.incomplete_enums);
+        // An enum found incomplete by one tracer may have been completed by the other one.
+        let registry = &self.registry;
+        let declared_variants = &self.declared_variants;
+        self.incomplete_enums.retain(|name| {
+            match (registry.get(name), declared_variants.get(name.as_str())) {
+                (Some(ContainerFormat::Enum(variants)), Some(declared)) => {
+                    variants.len() < declared.len()
+                }
+                _ => true,
+            }
+        });
+        Ok(())
+    }
+
     /// Finish tracing and recover a map of normalized formats.
     /// Returns an error if we detect incompletely traced types.
     /// This may happen in a few of cases:
@@ -216,6 +661,8 @@ impl Tracer {
                 .normalize()
                 .map_err(|_| Error::UnknownFormatInContainer(name.clone()))?;
         }
+        record_skipped_fields(&mut registry, &self.config.skipped_fields);
+        record_source_names(&mut registry, &self.config.source_names);
         if self.incomplete_enums.is_empty() {
             Ok(registry)
         } else {
@@ -225,6 +672,57 @@ impl Tracer {
         }
     }
 
+    /// Report, for each enum found so far, which variants were visited and which remain
+    /// unexplored. Tests may use this to assert that tracing covered all the variants of
+    /// their enums, e.g. after tracing values with `trace_value` or nested types with
+    /// `trace_type_once`.
+    pub fn coverage(&self) -> BTreeMap<String, VariantCoverage> {
+        let mut coverage = BTreeMap::new();
+        for (name, format) in &self.registry {
+            let variants = match format {
+                ContainerFormat::Enum(variants) => variants,
+                _ => continue,
+            };
+            let visited = variants
+                .iter()
+                .map(|(index, variant)| (*index, variant.name.clone()))
+                .collect::<BTreeMap<_, _>>();
+            let unexplored = self.declared_variants.get(name.as_str()).map(|declared| {
+                (0..)
+                    .zip(declared.iter())
+                    .filter(|(index, _)| !visited.contains_key(index))
+                    .map(|(index, name)| (index, name.to_string()))
+                    .collect()
+            });
+            coverage.insert(
+                name.clone(),
+                VariantCoverage {
+                    visited,
+                    unexplored,
+                },
+            );
+        }
+        coverage
+    }
+
+    /// Doc comments recorded with `TracerConfig::doc_comments` for the containers found so far,
+    /// with names prefixed by `module_name`, as expected by `CodeGeneratorConfig::with_comments`
+    /// in `serde-generate`. Call this before `registry`, which consumes the tracer.
+    pub fn qualified_doc_comments(&self, module_name: &str) -> DocComments {
+        self.config
+            .doc_comments
+            .iter()
+            .filter(
+                |(path, _)| matches!(path.first(), Some(name) if self.registry.contains_key(name)),
+            )
+            .map(|(path, comment)| {
+                let mut qualified_path = vec![module_name.to_string()];
+                qualified_path.extend(path.iter().cloned());
+                (qualified_path, comment.clone())
+            })
+            .collect()
+    }
+
     /// Same as registry but always return a value, even if we detected issues.
     /// This should only be use for debugging.
     pub fn registry_unchecked(self) -> Registry {
@@ -232,9 +730,18 @@ impl Tracer {
         for format in registry.values_mut() {
             format.normalize().unwrap_or(());
         }
+        record_skipped_fields(&mut registry, &self.config.skipped_fields);
+        record_source_names(&mut registry, &self.config.source_names);
         registry
     }
 
+    /// Unify `format` into the format of the container `name` in the registry.
+    pub(crate) fn unify_container(&mut self, name: String, format: ContainerFormat) -> Result<()> {
+        self.registry
+            .entry(name)
+            .unify_with(format, self.config.borrowed_bytes)
+    }
+
     pub(crate) fn record_container(
         &mut self,
         samples: &mut Samples,
@@ -243,13 +750,118 @@ impl Tracer {
         value: Value,
         record_value: bool,
     ) -> Result<(Format, Value)> {
-        self.registry.entry(name.to_string()).unify(format)?;
+        self.unify_container(name.to_string(), format)?;
         if record_value {
-            samples.values.insert(name, value.clone());
+            samples.insert(name, value.clone());
         }
         Ok((Format::TypeName(name.into()), value))
     }
 
+    pub(crate) fn custom_format(&self, name: &'static str) -> Option<Format> {
+        self.config
+            .custom_formats
+            .get(name)
+            .map(|layout| Format::Custom {
+                name: name.into(),
+                layout: Box::new(layout.clone()),
+            })
+    }
+
+    /// The custom format of the type `type_name`, as given by `std::any::type_name`, if it was
+    /// declared with `TracerConfig::map_external_type`.
+    pub(crate) fn external_format(&self, type_name: &str) -> Option<Format> {
+        let type_name = type_name.trim_start_matches('&');
+        let path = type_name.split('<').next().unwrap_or(type_name);
+        let (krate, name) = external_type_key(path);
+        self.config
+            .external_types
+            .iter()
+            .find(|(external, _)| external_type_key(external) == (krate, name))
+            .map(|(_, layout)| Format::Custom {
+                name: name.into(),
+                layout: Box::new(layout.clone()),
+            })
+    }
+
+    /// The name of the type `T` held by the option `type_name`, i.e. `Option<T>`, if type hints
+    /// apply to `T` or `T` is a nonzero integer. Options of external types, which are matched by
+    /// path, are recognized with the name that the compiler gives to `Option` itself.
+    pub(crate) fn option_content(&self, type_name: &'static str) -> Option<&'static str> {
+        if let Some(content) = self.config.option_contents.get(type_name) {
+            return Some(content);
+        }
+        if let Some((content, _, _)) = nonzero_integer_types()
+            .iter()
+            .find(|(_, option_name, _)| *option_name == type_name)
+        {
+            return Some(content);
+        }
+        let prefix = std::any::type_name::<Option<()>>().strip_suffix("()>")?;
+        let content = type_name.strip_prefix(prefix)?.strip_suffix('>')?;
+        self.external_format(content).map(|_| content)
+    }
+
+    /// Check that the content traced for a decimal format is a signed integer.
+    pub(crate) fn check_decimal_mantissa(&self, mantissa: &Format) -> Result<()> {
+        let mut mantissa = mantissa.clone();
+        mantissa.reduce();
+        match mantissa {
+            Format::I8 | Format::I16 | Format::I32 | Format::I64 | Format::I128 => Ok(()),
+            _ => Err(Error::Incompatible(
+                format!("{:?}", mantissa),
+                "a signed integer".to_string(),
+            )),
+        }
+    }
+
+    /// The canonical format of the struct `name` with the given fields, if it is one of the
+    /// containers of the standard library and a format was set with `TracerConfig::std_formats`
+    /// or a similar option.
+    pub(crate) fn std_format(&self, name: &'static str, fields: &[&str]) -> Option<Format> {
+        if !STD_CONTAINERS
+            .iter()
+            .any(|&(n, f)| n == name && f == fields)
+        {
+            return None;
+        }
+        self.config.std_formats.get(name).cloned()
+    }
+
+    /// The complete format of the enum `name` with the given variants, if it is one of the
+    /// network address types of the standard library, i.e. `std::net::IpAddr` or
+    /// `std::net::SocketAddr`. Their variants are known in advance, so that tracing the
+    /// deserialization of containers that use them does not require extra calls to `trace_type`.
+    pub(crate) fn address_format(
+        &self,
+        name: &'static str,
+        variants: &[&str],
+    ) -> Option<ContainerFormat> {
+        // Formats are not normalized yet during tracing.
+        let octets = |size| Format::Tuple(vec![Format::U8; size]);
+        let (v4, v6) = match name {
+            "IpAddr" => (octets(4), octets(16)),
+            "SocketAddr" => (
+                Format::Tuple(vec![octets(4), Format::U16]),
+                Format::Tuple(vec![octets(16), Format::U16]),
+            ),
+            _ => return None,
+        };
+        if variants != ["V4", "V6"] {
+            return None;
+        }
+        let variant = |name: &str, format| {
+            Named::new(name.to_string(), VariantFormat::NewType(Box::new(format)))
+        };
+        let variants = vec![(0, variant("V4", v4)), (1, variant("V6", v6))];
+        Some(ContainerFormat::Enum(variants.into_iter().collect()))
+    }
+
+    /// Check that the traced content of a custom format agrees with its declared layout.
+    /// The check is skipped if the content is not fully known yet.
+    pub(crate) fn check_custom_layout(&self, name: &'static str, format: Format) -> Result<()> {
+        check_layout(format, &self.config.custom_formats[name])
+    }
+
     pub(crate) fn record_variant(
         &mut self,
         samples: &mut Samples,
@@ -260,32 +872,226 @@ impl Tracer {
         variant_value: Value,
     ) -> Result<(Format, Value)> {
         let mut variants = BTreeMap::new();
-        variants.insert(
-            variant_index,
-            Named {
-                name: variant_name.into(),
-                value: variant,
-            },
-        );
+        variants.insert(variant_index, Named::new(variant_name, variant));
         let format = ContainerFormat::Enum(variants);
         let value = Value::Variant(variant_index, Box::new(variant_value));
         self.record_container(samples, name, format, value, false)
     }
 
-    pub(crate) fn get_sample<'de, 'a>(
-        &'a self,
+    /// Record that deserialization tracing enters the container `name`. Fails if the maximal
+    /// recursion depth is reached, reporting the last cycle of containers, if any.
+    pub(crate) fn enter_container(&mut self, name: &'static str) -> Result<()> {
+        if self.explored_containers.len() >= self.config.max_recursion_depth {
+            let start = self
+                .explored_containers
+                .iter()
+                .rposition(|explored| *explored == name)
+                .unwrap_or(0);
+            let mut path = self.explored_containers[start..]
+                .iter()
+                .map(|explored| explored.to_string())
+                .collect::<Vec<_>>();
+            path.push(name.to_string());
+            return Err(Error::RecursionLimit(path));
+        }
+        self.explored_containers.push(name);
+        Ok(())
+    }
+
+    pub(crate) fn exit_container(&mut self) {
+        self.explored_containers.pop();
+    }
+
+    /// When tracing with a `SampleProvider`, interrupt the analysis the first time that the
+    /// sample of a container is found missing.
+    pub(crate) fn request_sample(&mut self, name: &'static str) -> Result<()> {
+        if let Some(requested_samples) = &mut self.requested_samples {
+            if requested_samples.insert(name) {
+                return Err(Error::MissingSample(name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The sample of the container `name` and the format of the container, if any. For samples
+    /// recorded with `trace_sample_by_name`, the format of the container is first unified with
+    /// the one obtained by applying `container` to the format of the payload.
+    pub(crate) fn get_sample<'de, 'a, F>(
+        &'a mut self,
         samples: &'de Samples,
         name: &'static str,
-    ) -> Option<(&'a ContainerFormat, &'de Value)> {
-        match samples.value(name) {
-            Some(value) => {
-                let format = self
-                    .registry
-                    .get(name)
-                    .expect("recorded containers should have a format already");
-                Some((format, value))
+        container: F,
+    ) -> Result<Option<(&'a ContainerFormat, &'de Value)>>
+    where
+        F: FnOnce(Format) -> Option<ContainerFormat>,
+    {
+        let value = match samples.deserializable_value(name) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if let Some(payload_format) = samples.payload_formats.get(name) {
+            let format = container(payload_format.clone()).ok_or_else(|| {
+                Error::Incompatible(format!("{:?}", payload_format), name.to_string())
+            })?;
+            self.unify_container(name.to_string(), format)?;
+        }
+        let format = self
+            .registry
+            .get(name)
+            .expect("recorded containers should have a format already");
+        Ok(Some((format, value)))
+    }
+}
+
+/// Check that the traced content of a custom format agrees with the given `layout`. The check
+/// is skipped if the content is not fully known yet.
+pub(crate) fn check_layout(mut format: Format, layout: &Format) -> Result<()> {
+    if format.normalize().is_ok() && &format != layout {
+        return Err(Error::Incompatible(
+            format!("{:?}", format),
+            format!("{:?}", layout),
+        ));
+    }
+    Ok(())
+}
+
+/// The nonzero integer types of the standard library: the names given by `std::any::type_name`
+/// to each type and to its options, and the name of its custom format. Tracing deserialization
+/// uses them to sample nonzero values.
+fn nonzero_integer_types() -> [(&'static str, &'static str, &'static str); 12] {
+    use std::num::*;
+    macro_rules! types {
+        ($($ty:ident),*) => {
+            [$((
+                std::any::type_name::<$ty>(),
+                std::any::type_name::<Option<$ty>>(),
+                stringify!($ty),
+            )),*]
+        };
+    }
+    types!(
+        NonZeroU8,
+        NonZeroU16,
+        NonZeroU32,
+        NonZeroU64,
+        NonZeroU128,
+        NonZeroUsize,
+        NonZeroI8,
+        NonZeroI16,
+        NonZeroI32,
+        NonZeroI64,
+        NonZeroI128,
+        NonZeroIsize
+    )
+}
+
+/// The name of the custom format of `type_name`, as given by `std::any::type_name`, if it is a
+/// nonzero integer type of the standard library.
+pub(crate) fn nonzero_integer_name(type_name: &str) -> Option<&'static str> {
+    nonzero_integer_types()
+        .iter()
+        .find(|(name, _, _)| *name == type_name)
+        .map(|(_, _, name)| *name)
+}
+
+/// Name of the struct, and of its single field, under which `serde_json::Number` serializes
+/// arbitrary-precision numbers (feature `arbitrary_precision` of `serde_json`).
+pub(crate) const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// Format of arbitrary-precision numbers, laid out on the wire as their decimal representation.
+pub(crate) fn arbitrary_precision_number() -> Format {
+    Format::Custom {
+        name: "Number".into(),
+        layout: Box::new(Format::Str),
+    }
+}
+
+/// The crate and the name of a type path, e.g. `("chrono", "DateTime")` for
+/// `chrono::datetime::DateTime`.
+fn external_type_key(path: &str) -> (&str, &str) {
+    let krate = path.split("::").next().unwrap_or(path);
+    let name = path.rsplit("::").next().unwrap_or(path);
+    (krate, name)
+}
+
+/// Insert the fields of `skipped` into the structs and struct variants of `registry`, at their
+/// positions in the Rust definitions, unless tracing already found them.
+fn record_skipped_fields(registry: &mut Registry, skipped: &SkippedFields) {
+    if skipped.is_empty() {
+        return;
+    }
+    let record_fields = |path: &[&str], fields: &mut Vec<Named<Format>>| {
+        let mut entries = skipped
+            .iter()
+            .filter_map(|(field_path, (position, format))| {
+                let (name, prefix) = field_path.split_last()?;
+                if prefix.iter().eq(path) {
+                    Some((*position, name, format))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(position, _, _)| *position);
+        for (position, name, format) in entries {
+            if fields.iter().any(|field| &field.name == name) {
+                continue;
+            }
+            let field = Named::new(name.clone(), Format::Skipped(Box::new(format.clone())));
+            fields.insert(std::cmp::min(position, fields.len()), field);
+        }
+    };
+    for (name, format) in registry.iter_mut() {
+        match format {
+            ContainerFormat::Struct(fields) => record_fields(&[name], fields),
+            ContainerFormat::Enum(variants)
+            | ContainerFormat::InternallyTaggedEnum { variants, .. } => {
+                for variant in variants.values_mut() {
+                    if let VariantFormat::Struct(fields) = &mut variant.value {
+                        record_fields(&[name, &variant.name], fields);
+                    }
+                }
+            }
+            ContainerFormat::UnitStruct
+            | ContainerFormat::NewTypeStruct(_)
+            | ContainerFormat::TupleStruct(_) => (),
+        }
+    }
+}
+
+/// Set the source names of the fields and variants of `registry` found in `names`.
+fn record_source_names(registry: &mut Registry, names: &SourceNames) {
+    if names.is_empty() {
+        return;
+    }
+    let record = |path: &[&str], named_source_name: &mut Option<String>| {
+        let path = path.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        if let Some(source_name) = names.get(&path) {
+            *named_source_name = Some(source_name.clone());
+        }
+    };
+    let record_fields = |path: &[&str], fields: &mut Vec<Named<Format>>| {
+        for field in fields {
+            let mut path = path.to_vec();
+            path.push(&field.name);
+            record(&path, &mut field.source_name);
+        }
+    };
+    for (name, format) in registry.iter_mut() {
+        match format {
+            ContainerFormat::Struct(fields) => record_fields(&[name], fields),
+            ContainerFormat::Enum(variants)
+            | ContainerFormat::InternallyTaggedEnum { variants, .. } => {
+                for variant in variants.values_mut() {
+                    record(&[name, &variant.name], &mut variant.source_name);
+                    if let VariantFormat::Struct(fields) = &mut variant.value {
+                        record_fields(&[name, &variant.name], fields);
+                    }
+                }
             }
-            None => None,
+            ContainerFormat::UnitStruct
+            | ContainerFormat::NewTypeStruct(_)
+            | ContainerFormat::TupleStruct(_) => (),
         }
     }
 }
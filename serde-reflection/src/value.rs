@@ -1,7 +1,10 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    trace::NUMBER_TOKEN,
+};
 use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
 
 /// A structured Serde value.
@@ -29,6 +32,9 @@ pub enum Value {
     Char(char),
     Str(String),
     Bytes(Vec<u8>),
+    /// An arbitrary-precision number, i.e. a `serde_json::Number` with the feature
+    /// `arbitrary_precision` of `serde_json`, as its decimal representation.
+    Number(String),
 
     Option(Option<Box<Value>>),
     Variant(u32, Box<Value>),
@@ -55,14 +61,68 @@ impl<'de> IntoDeserializer<'de, Error> for &'de Value {
 }
 
 impl Value {
-    pub(crate) fn seq_values(&self) -> Result<&Vec<Value>> {
+    /// A copy of `self` where sequences of bytes, e.g. `Seq(vec![U8(1)])`, are replaced by
+    /// `Bytes`, or `None` if there are no such sequences.
+    pub(crate) fn with_byte_sequences_as_bytes(&self) -> Option<Value> {
         match self {
-            Value::Seq(x) => Ok(x),
-            _ => Err(Error::DeserializationError("seq_values")),
+            Value::Seq(values) => {
+                let bytes: Vec<_> = values
+                    .iter()
+                    .filter_map(|value| match value {
+                        Value::U8(x) => Some(*x),
+                        _ => None,
+                    })
+                    .collect();
+                if bytes.len() == values.len() {
+                    return Some(Value::Bytes(bytes));
+                }
+                let copies: Vec<_> = values
+                    .iter()
+                    .map(Value::with_byte_sequences_as_bytes)
+                    .collect();
+                if copies.iter().all(Option::is_none) {
+                    return None;
+                }
+                Some(Value::Seq(
+                    copies
+                        .into_iter()
+                        .zip(values)
+                        .map(|(copy, value)| copy.unwrap_or_else(|| value.clone()))
+                        .collect(),
+                ))
+            }
+            Value::Option(Some(value)) => value
+                .with_byte_sequences_as_bytes()
+                .map(|value| Value::Option(Some(Box::new(value)))),
+            Value::Variant(index, value) => value
+                .with_byte_sequences_as_bytes()
+                .map(|value| Value::Variant(*index, Box::new(value))),
+            _ => None,
         }
     }
 }
 
+/// Deserialize the elements of a sequence (or the fields of a struct) written as bytes.
+fn visit_bytes_as_seq<'de, V>(bytes: &'de [u8], visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    visitor.visit_seq(de::value::SeqDeserializer::<_, Error>::new(
+        bytes.iter().copied(),
+    ))
+}
+
+/// Deserialize an arbitrary-precision number from its decimal representation, as expected by
+/// `serde_json::Number`.
+pub(crate) fn visit_arbitrary_precision_number<'de, V>(digits: &str, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    visitor.visit_map(de::value::MapDeserializer::<_, Error>::new(
+        std::iter::once((NUMBER_TOKEN, digits)),
+    ))
+}
+
 macro_rules! declare_deserialize {
     ($method:ident, $token:ident, $visit:ident, $str:expr) => {
         fn $method<V>(self, visitor: V) -> Result<V::Value>
@@ -94,11 +154,14 @@ macro_rules! declare_deserialize_borrowed {
 impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::NotSupported("deserialize_any"))
+        match self.value {
+            Value::Number(digits) => visit_arbitrary_precision_number(digits, visitor),
+            _ => Err(Error::NotSupported("deserialize_any")),
+        }
     }
 
     declare_deserialize!(deserialize_bool, Bool, visit_bool, "bool");
@@ -168,6 +231,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     {
         match self.value {
             Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Bytes(x) => visit_bytes_as_seq(x, visitor),
             _ => Err(Error::DeserializationError("seq")),
         }
     }
@@ -178,6 +242,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     {
         match self.value {
             Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Bytes(x) => visit_bytes_as_seq(x, visitor),
             _ => Err(Error::DeserializationError("tuple")),
         }
     }
@@ -193,6 +258,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     {
         match self.value {
             Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Bytes(x) => visit_bytes_as_seq(x, visitor),
             _ => Err(Error::DeserializationError("tuple struct")),
         }
     }
@@ -202,7 +268,10 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.value {
-            Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Seq(x) => visitor.visit_map(x.into_seq_deserializer()),
+            Value::Bytes(x) => visitor.visit_map(de::value::MapDeserializer::<_, Error>::new(
+                x.chunks_exact(2).map(|entry| (entry[0], entry[1])),
+            )),
             _ => Err(Error::DeserializationError("map")),
         }
     }
@@ -218,6 +287,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     {
         match self.value {
             Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Bytes(x) => visit_bytes_as_seq(x, visitor),
             _ => Err(Error::DeserializationError("tuple struct")),
         }
     }
@@ -383,6 +453,7 @@ impl<'de> de::VariantAccess<'de> for EnumDeserializer<'de> {
     {
         match self.value {
             Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Bytes(x) => visit_bytes_as_seq(x, visitor),
             _ => Err(Error::DeserializationError("tuple variant")),
         }
     }
@@ -393,6 +464,7 @@ impl<'de> de::VariantAccess<'de> for EnumDeserializer<'de> {
     {
         match self.value {
             Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Bytes(x) => visit_bytes_as_seq(x, visitor),
             _ => Err(Error::DeserializationError("struct variant")),
         }
     }
@@ -0,0 +1,91 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{default_value, Defaulted, Value};
+use serde_reflection_derive::SerdeReflectDefaults;
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(Serialize, Deserialize, SerdeReflectDefaults)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct Server {
+    host_name: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default, skip)]
+    cache: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, SerdeReflectDefaults)]
+#[serde(default)]
+struct Limits {
+    retries: u8,
+    timeout: Option<u32>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            timeout: Some(30),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, SerdeReflectDefaults)]
+#[allow(dead_code)]
+enum Command<T> {
+    Stop,
+    Start {
+        #[serde(default)]
+        delay: u32,
+        payload: T,
+    },
+}
+
+fn path(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn test_field_default_values() {
+    let values = Server::default_values();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[&path(&["Server", "port"])], Some(Value::U16(8080)));
+    assert_eq!(values[&path(&["Server", "tags"])], Some(Value::Seq(vec![])));
+}
+
+#[test]
+fn test_container_default_values() {
+    let values = Limits::qualified_default_values("config");
+    assert_eq!(values.len(), 2);
+    assert_eq!(
+        values[&path(&["config", "Limits", "retries"])],
+        Some(Value::U8(3))
+    );
+    assert_eq!(
+        values[&path(&["config", "Limits", "timeout"])],
+        Some(Value::Option(Some(Box::new(Value::U32(30)))))
+    );
+}
+
+#[test]
+fn test_default_values_of_generic_types_are_unknown() {
+    let values = Command::<String>::default_values();
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[&path(&["Command", "Start", "delay"])], None);
+}
+
+#[test]
+fn test_default_value() {
+    assert_eq!(
+        default_value(&(1u8, "a")),
+        Some(Value::Seq(vec![Value::U8(1), Value::Str("a".to_string())]))
+    );
+}
@@ -0,0 +1,143 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{
+    diff, ContainerChange, Format, Registry, Samples, Tracer, TracerConfig, VariantFormat,
+};
+
+mod old {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Account {
+        pub id: u64,
+        pub name: String,
+        pub balance: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Opened(Account),
+        Closed { id: u64 },
+        Frozen,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Audit(pub u64);
+}
+
+mod new {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Account {
+        pub name: String,
+        pub id: u64,
+        pub balance: u64,
+        pub currency: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub enum Event {
+        Opened(Account),
+        Terminated { id: u64, reason: Option<String> },
+        Frozen(String),
+        Renamed(String),
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Notice(pub String);
+}
+
+fn trace<A, E, C>() -> Registry
+where
+    A: for<'de> Deserialize<'de>,
+    E: for<'de> Deserialize<'de>,
+    C: for<'de> Deserialize<'de>,
+{
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<A>(&samples).unwrap();
+    tracer.trace_type::<E>(&samples).unwrap();
+    tracer.trace_type::<C>(&samples).unwrap();
+    tracer.registry().unwrap()
+}
+
+#[test]
+fn test_diff_of_identical_registries() {
+    let registry = trace::<old::Account, old::Event, old::Audit>();
+    let diff = diff(&registry, &registry);
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn test_diff_of_evolved_registries() {
+    let old = trace::<old::Account, old::Event, old::Audit>();
+    let new = trace::<new::Account, new::Event, new::Notice>();
+    let diff = diff(&old, &new);
+    assert_eq!(diff.added_containers, vec!["Notice".to_string()]);
+    assert_eq!(diff.removed_containers, vec!["Audit".to_string()]);
+
+    assert_eq!(
+        diff.changed_containers["Account"],
+        vec![
+            ContainerChange::FieldRetyped {
+                variant: None,
+                name: "balance".into(),
+                old: Format::U32,
+                new: Format::U64,
+            },
+            ContainerChange::FieldAdded {
+                variant: None,
+                name: "currency".into(),
+                format: Format::Str,
+            },
+            ContainerChange::FieldsReordered {
+                variant: None,
+                old: vec!["id".into(), "name".into(), "balance".into()],
+                new: vec!["name".into(), "id".into(), "balance".into()],
+            },
+        ]
+    );
+
+    assert_eq!(
+        diff.changed_containers["Event"],
+        vec![
+            ContainerChange::VariantRenamed {
+                index: 1,
+                old: "Closed".into(),
+                new: "Terminated".into(),
+            },
+            ContainerChange::FieldAdded {
+                variant: Some("Terminated".into()),
+                name: "reason".into(),
+                format: Format::Option(Box::new(Format::Str)),
+            },
+            ContainerChange::VariantRetyped {
+                name: "Frozen".into(),
+                old: VariantFormat::Unit,
+                new: VariantFormat::NewType(Box::new(Format::Str)),
+            },
+            ContainerChange::VariantAdded {
+                index: 3,
+                name: "Renamed".into(),
+            },
+        ]
+    );
+
+    assert_eq!(
+        diff.to_string(),
+        r#"Notice: container was added
+Audit: container was removed
+Account: field `balance` changed from `U32` to `U64`
+Account: field `currency` was added with format `Str`
+Account: fields were reordered from `id, name, balance` to `name, id, balance`
+Event: variant #1 was renamed from `Closed` to `Terminated`
+Event: field `reason` of variant `Terminated` was added with format `Option(Str)`
+Event: variant `Frozen` changed from `Unit` to `NewType(Str)`
+Event: variant #3 `Renamed` was added
+"#
+    );
+}
@@ -0,0 +1,95 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{Documented, Samples, Tracer, TracerConfig};
+use serde_reflection_derive::SerdeReflectDoc;
+
+/// A point
+/// in space.
+#[derive(Serialize, Deserialize, SerdeReflectDoc)]
+#[allow(dead_code)]
+struct Point {
+    /// Horizontal coordinate.
+    x: u64,
+    #[serde(rename = "vertical")]
+    /// Vertical coordinate.
+    y: u64,
+    z: u64,
+}
+
+#[derive(Serialize, Deserialize, SerdeReflectDoc)]
+#[serde(rename = "Shape")]
+#[allow(dead_code)]
+enum RenamedShape<T> {
+    /// Nothing to draw.
+    Empty,
+    Circle {
+        /// Length of the radius.
+        radius: T,
+    },
+    Polygon(Vec<T>),
+}
+
+fn path(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn test_struct_doc_comments() {
+    let comments = Point::doc_comments();
+    assert_eq!(comments.len(), 3);
+    assert_eq!(comments[&path(&["Point"])], "A point\nin space.");
+    assert_eq!(comments[&path(&["Point", "x"])], "Horizontal coordinate.");
+    assert_eq!(
+        comments[&path(&["Point", "vertical"])],
+        "Vertical coordinate."
+    );
+}
+
+#[test]
+fn test_enum_doc_comments() {
+    let comments = RenamedShape::<u32>::qualified_doc_comments("shapes");
+    assert_eq!(comments.len(), 2);
+    assert_eq!(
+        comments[&path(&["shapes", "Shape", "Empty"])],
+        "Nothing to draw."
+    );
+    assert_eq!(
+        comments[&path(&["shapes", "Shape", "Circle", "radius"])],
+        "Length of the radius."
+    );
+}
+
+#[derive(Serialize, Deserialize, SerdeReflectDoc)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct Account {
+    /// Identifier of the owner.
+    owner_id: u64,
+}
+
+#[test]
+fn test_doc_comments_of_fields_renamed_by_rule() {
+    let comments = Account::doc_comments();
+    assert_eq!(
+        comments[&path(&["Account", "ownerId"])],
+        "Identifier of the owner."
+    );
+}
+
+#[test]
+fn test_tracer_reports_doc_comments_of_traced_containers() {
+    let config = TracerConfig::default()
+        .doc_comments(Point::doc_comments())
+        .doc_comments(Account::doc_comments());
+    let mut tracer = Tracer::new(config);
+    tracer.trace_type::<Point>(&Samples::new()).unwrap();
+    let comments = tracer.qualified_doc_comments("geometry");
+    assert_eq!(comments.len(), 3);
+    assert_eq!(
+        comments[&path(&["geometry", "Point", "x"])],
+        "Horizontal coordinate."
+    );
+    assert!(!comments.contains_key(&path(&["geometry", "Account", "ownerId"])));
+}
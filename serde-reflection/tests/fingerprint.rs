@@ -0,0 +1,65 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{
+    fingerprint, ContainerFormat, Format, Named, Registry, Samples, Tracer, TracerConfig,
+};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn field(name: &str, value: Format) -> Named<Format> {
+    Named::new(name, value)
+}
+
+#[test]
+fn test_fingerprints_are_stable() {
+    // These values must never change: fingerprints are embedded in programs and clients.
+    assert_eq!(
+        hex(&fingerprint(&Registry::new())),
+        "7d933da7f0a2451ecd1baa0e80ff72e78c2a7eebe52fbef95ebb265708cbdf05"
+    );
+    let mut registry = Registry::new();
+    registry.insert(
+        "Point".into(),
+        ContainerFormat::Struct(vec![field("x", Format::U32), field("y", Format::U32)]),
+    );
+    assert_eq!(
+        hex(&fingerprint(&registry)),
+        "c584efc9ad6f2a689ae9ff3e748cd46d526206440787ba47b225284fc0538b1c"
+    );
+}
+
+#[test]
+fn test_fingerprints_ignore_source_names_and_skipped_fields() {
+    #[derive(Serialize, Deserialize)]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<Point>(&Samples::new()).unwrap();
+    let registry = tracer.registry().unwrap();
+
+    let mut other = registry.clone();
+    let mut x = field("x", Format::U32);
+    x.source_name = Some("x_coordinate".into());
+    other.insert(
+        "Point".into(),
+        ContainerFormat::Struct(vec![
+            x,
+            field("cache", Format::Skipped(Box::new(Format::Bytes))),
+            field("y", Format::U32),
+        ]),
+    );
+    assert_eq!(fingerprint(&registry), fingerprint(&other));
+
+    other.insert(
+        "Point".into(),
+        ContainerFormat::Struct(vec![field("y", Format::U32), field("x", Format::U32)]),
+    );
+    assert_ne!(fingerprint(&registry), fingerprint(&other));
+}
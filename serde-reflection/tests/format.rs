@@ -1,7 +1,9 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_reflection::{ContainerFormat, Error, Format, FormatHolder, Named, VariantFormat};
+use serde_reflection::{
+    ContainerFormat, Error, Format, FormatHolder, Named, VariantFormat, VisitFlow,
+};
 use std::collections::HashSet;
 use std::ops::Deref;
 
@@ -12,14 +14,14 @@ fn test_format_visiting() {
     let format = ContainerFormat::Enum(
         vec![(
             0,
-            Named {
-                name: "foo".into(),
-                value: VariantFormat::Tuple(vec![
+            Named::new(
+                "foo",
+                VariantFormat::Tuple(vec![
                     TypeName("foo".into()),
                     TypeName("bar".into()),
                     Seq(Box::new(TypeName("foo".into()))),
                 ]),
-            },
+            ),
         )]
         .into_iter()
         .collect(),
@@ -40,6 +42,176 @@ fn test_format_visiting() {
     assert!(Format::unknown().visit(&mut |_| Ok(())).is_err());
 }
 
+#[test]
+fn test_format_rewriting() {
+    use Format::*;
+
+    let mut registry = std::collections::BTreeMap::new();
+    registry.insert(
+        "Foo".to_string(),
+        ContainerFormat::Struct(vec![
+            Named::new("a", Bytes),
+            Named::new("b", Seq(Box::new(Option(Box::new(Bytes))))),
+        ]),
+    );
+    registry.insert(
+        "Bar".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Map {
+            key: Box::new(Str),
+            value: Box::new(Bytes),
+        })),
+    );
+    let hash = TupleArray {
+        content: Box::new(U8),
+        size: 32,
+    };
+    for format in registry.values_mut() {
+        let flow = format
+            .visit_mut_with(&mut |f| {
+                if *f == Bytes {
+                    *f = hash.clone();
+                    return Ok(VisitFlow::SkipChildren);
+                }
+                Ok(VisitFlow::Continue)
+            })
+            .unwrap();
+        assert_eq!(flow, VisitFlow::Continue);
+    }
+    assert_eq!(
+        registry["Foo"],
+        ContainerFormat::Struct(vec![
+            Named::new("a", hash.clone()),
+            Named::new("b", Seq(Box::new(Option(Box::new(hash.clone()))))),
+        ])
+    );
+    assert_eq!(
+        registry["Bar"],
+        ContainerFormat::NewTypeStruct(Box::new(Map {
+            key: Box::new(Str),
+            value: Box::new(hash),
+        }))
+    );
+
+    // Formats are visited before their subformats and the visit may stop early.
+    let mut format = Seq(Box::new(Tuple(vec![U8, Option(Box::new(U16)), U32])));
+    let mut visited = Vec::new();
+    let flow = format
+        .visit_mut_with(&mut |f| {
+            visited.push(f.clone());
+            if let Option(_) = f {
+                return Ok(VisitFlow::Break);
+            }
+            Ok(VisitFlow::Continue)
+        })
+        .unwrap();
+    assert_eq!(flow, VisitFlow::Break);
+    assert_eq!(visited.len(), 4);
+    assert_eq!(visited[3], Option(Box::new(U16)));
+
+    assert!(Format::unknown()
+        .visit_mut_with(&mut |_| Ok(VisitFlow::Continue))
+        .is_err());
+}
+
+#[test]
+fn test_format_holders_outside_the_crate() {
+    /// A holder of formats that does not implement `visit_mut_with`.
+    struct Holder(Format);
+
+    impl FormatHolder for Holder {
+        fn visit<'a>(
+            &'a self,
+            f: &mut dyn FnMut(&'a Format) -> serde_reflection::Result<()>,
+        ) -> serde_reflection::Result<()> {
+            self.0.visit(f)
+        }
+
+        fn visit_mut(
+            &mut self,
+            f: &mut dyn FnMut(&mut Format) -> serde_reflection::Result<()>,
+        ) -> serde_reflection::Result<()> {
+            self.0.visit_mut(f)
+        }
+
+        fn unify(&mut self, other: Self) -> serde_reflection::Result<()> {
+            self.0.unify(other.0)
+        }
+
+        fn is_unknown(&self) -> bool {
+            self.0.is_unknown()
+        }
+    }
+
+    let mut holder = Holder(Format::U8);
+    assert!(matches!(
+        holder.visit_mut_with(&mut |_| Ok(VisitFlow::Continue)),
+        Err(Error::NotSupported(_))
+    ));
+}
+
+#[test]
+fn test_format_mapping() {
+    use Format::*;
+
+    let mut registry = std::collections::BTreeMap::new();
+    registry.insert(
+        "Account".to_string(),
+        ContainerFormat::Struct(vec![
+            Named::new("owner", TypeName("AccountAddress".into())),
+            Named::new(
+                "history",
+                Seq(Box::new(Option(Box::new(TypeName("Amount".into()))))),
+            ),
+        ]),
+    );
+    registry.insert(
+        "Amount".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(U64)),
+    );
+
+    // Erase newtypes and replace addresses by byte arrays.
+    let newtypes = registry
+        .iter()
+        .filter_map(|(name, format)| match format {
+            ContainerFormat::NewTypeStruct(format) => Some((name.clone(), *format.clone())),
+            _ => None,
+        })
+        .collect::<std::collections::BTreeMap<_, _>>();
+    let address = TupleArray {
+        content: Box::new(U8),
+        size: 32,
+    };
+    registry.retain(|name, _| !newtypes.contains_key(name));
+    for format in registry.values_mut() {
+        format
+            .map_formats(&mut |format| match format {
+                TypeName(name) if name == "AccountAddress" => Ok(address.clone()),
+                TypeName(name) if newtypes.contains_key(&name) => Ok(newtypes[&name].clone()),
+                format => Ok(format),
+            })
+            .unwrap();
+    }
+    assert_eq!(registry.len(), 1);
+    assert_eq!(
+        registry["Account"],
+        ContainerFormat::Struct(vec![
+            Named::new("owner", address),
+            Named::new("history", Seq(Box::new(Option(Box::new(U64))))),
+        ])
+    );
+
+    // Errors are propagated.
+    let mut format = Seq(Box::new(U8));
+    let error = format
+        .map_formats(&mut |format| match format {
+            U8 => Err(Error::Custom("no bytes".into())),
+            format => Ok(format),
+        })
+        .unwrap_err();
+    assert_eq!(error, Error::Custom("no bytes".into()));
+    assert!(Format::unknown().map_formats(&mut Ok).is_err());
+}
+
 // Note: this does not test pointer equality, only referenced content.
 fn assert_variable_contains_value(format: &Format, value: &Format) {
     match format {
@@ -147,6 +319,7 @@ fn test_format_unification() {
         TypeName("foo".into()),
         Option(Box::new(Unit)),
         Seq(Box::new(Unit)),
+        Set(Box::new(Unit)),
         Map {
             key: Box::new(Unit),
             value: Box::new(Unit),
@@ -158,10 +331,35 @@ fn test_format_unification() {
         assert!(x.unify(TypeName("bar".into())).is_err());
         assert!(x.unify(Option(Box::new(U32))).is_err());
         assert!(x.unify(Seq(Box::new(U32))).is_err());
+        assert!(x.unify(Set(Box::new(U32))).is_err());
         assert!(x.unify(Tuple(vec![])).is_err());
     }
 }
 
+#[test]
+fn test_nonzero_integer_unification() {
+    use Format::*;
+
+    let custom = |name: &str| Custom {
+        name: name.into(),
+        layout: Box::new(U64),
+    };
+
+    // Nonzero integers of the standard library refine the corresponding integers.
+    let mut x = U64;
+    x.unify(custom("NonZeroU64")).unwrap();
+    assert_eq!(x, custom("NonZeroU64"));
+    let mut x = custom("NonZeroU64");
+    x.unify(U64).unwrap();
+    assert_eq!(x, custom("NonZeroU64"));
+
+    // Other custom formats do not, even with a similar name.
+    let mut x = U64;
+    assert!(x.unify(custom("NonZeroAmount")).is_err());
+    let mut x = custom("NonZeroAmount");
+    assert!(x.unify(U64).is_err());
+}
+
 #[test]
 fn test_container_format_unification() {
     use ContainerFormat::*;
@@ -175,59 +373,56 @@ fn test_container_format_unification() {
     let mut x = Enum(
         vec![(
             0,
-            Named {
-                name: "foo".into(),
-                value: VariantFormat::Tuple(vec![Format::unknown()]),
-            },
+            Named::new("foo", VariantFormat::Tuple(vec![Format::unknown()])),
         )]
         .into_iter()
         .collect(),
     );
     assert!(x
         .unify(Enum(
-            vec![(
-                0,
-                Named {
-                    name: "foo".into(),
-                    value: VariantFormat::Unit,
-                }
-            )]
-            .into_iter()
-            .collect()
+            vec![(0, Named::new("foo", VariantFormat::Unit))]
+                .into_iter()
+                .collect()
         ))
         .is_err());
     assert!(x
         .unify(Enum(
-            vec![(
-                0,
-                Named {
-                    name: "foo".into(),
-                    value: VariantFormat::Tuple(vec![U8]),
-                }
-            )]
-            .into_iter()
-            .collect()
+            vec![(0, Named::new("foo", VariantFormat::Tuple(vec![U8])))]
+                .into_iter()
+                .collect()
         ))
         .is_ok());
 
+    let variants = vec![(0, Named::new("foo", VariantFormat::Unit))]
+        .into_iter()
+        .collect::<std::collections::BTreeMap<_, _>>();
+    let mut x = InternallyTaggedEnum {
+        tag: "type".into(),
+        variants: variants.clone(),
+    };
+    assert!(x
+        .unify(InternallyTaggedEnum {
+            tag: "kind".into(),
+            variants: variants.clone(),
+        })
+        .is_err());
+    assert!(x.unify(Enum(variants.clone())).is_err());
+    assert!(x
+        .unify(InternallyTaggedEnum {
+            tag: "type".into(),
+            variants,
+        })
+        .is_ok());
+
     for x in vec![
         UnitStruct,
         NewTypeStruct(Box::new(Unit)),
         TupleStruct(vec![Format::unknown()]),
-        Struct(vec![Named {
-            name: "foo".into(),
-            value: Format::unknown(),
-        }]),
+        Struct(vec![Named::new("foo", Format::unknown())]),
         Enum(
-            vec![(
-                0,
-                Named {
-                    name: "foo".into(),
-                    value: VariantFormat::unknown(),
-                },
-            )]
-            .into_iter()
-            .collect(),
+            vec![(0, Named::new("foo", VariantFormat::unknown()))]
+                .into_iter()
+                .collect(),
         ),
     ]
     .iter_mut()
@@ -235,22 +430,13 @@ fn test_container_format_unification() {
         assert!(x.unify(NewTypeStruct(Box::new(U8))).is_err());
         assert!(x.unify(TupleStruct(vec![])).is_err());
         assert!(x
-            .unify(Struct(vec![Named {
-                name: "bar".into(),
-                value: Format::unknown()
-            }]))
+            .unify(Struct(vec![Named::new("bar", Format::unknown())]))
             .is_err());
         assert!(x
             .unify(Enum(
-                vec![(
-                    0,
-                    Named {
-                        name: "bar".into(),
-                        value: VariantFormat::unknown()
-                    }
-                )]
-                .into_iter()
-                .collect()
+                vec![(0, Named::new("bar", VariantFormat::unknown()))]
+                    .into_iter()
+                    .collect()
             ))
             .is_err());
     }
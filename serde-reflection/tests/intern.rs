@@ -0,0 +1,78 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use serde_reflection::{Format, FormatInterner, Samples, Tracer, TracerConfig};
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Coin(u64);
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Wallet {
+    main: Option<Vec<Coin>>,
+    savings: Option<Vec<Coin>>,
+    history: Vec<Option<Vec<Coin>>>,
+}
+
+#[test]
+fn test_structurally_equal_formats_are_shared() {
+    let coins = || {
+        Format::Option(Box::new(Format::Seq(Box::new(Format::TypeName(
+            "Coin".into(),
+        )))))
+    };
+    let mut interner = FormatInterner::new();
+    let first = interner.intern(&coins()).unwrap();
+    let second = interner.intern(&coins()).unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(*first, coins());
+    // `TypeName("Coin")`, `Seq(..)`, and `Option(..)`.
+    assert_eq!(interner.len(), 3);
+
+    let other = interner
+        .intern(&Format::Option(Box::new(Format::Seq(Box::new(
+            Format::U64,
+        )))))
+        .unwrap();
+    assert!(!Arc::ptr_eq(&first, &other));
+    assert_eq!(interner.len(), 6);
+}
+
+#[test]
+fn test_distinct_formats_of_a_registry() {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<Wallet>(&Samples::new()).unwrap();
+    let registry = tracer.registry().unwrap();
+
+    let mut interner = FormatInterner::new();
+    interner.intern_registry(&registry).unwrap();
+    let coins = Format::Option(Box::new(Format::Seq(Box::new(Format::TypeName(
+        "Coin".into(),
+    )))));
+    assert_eq!(
+        interner
+            .formats()
+            .map(|f| (**f).clone())
+            .collect::<Vec<_>>(),
+        vec![
+            // Containers are visited in the order of their names.
+            Format::U64,
+            Format::TypeName("Coin".into()),
+            Format::Seq(Box::new(Format::TypeName("Coin".into()))),
+            coins.clone(),
+            Format::Seq(Box::new(coins)),
+        ]
+    );
+}
+
+#[test]
+fn test_unknown_formats_are_rejected() {
+    let mut interner = FormatInterner::new();
+    assert!(interner
+        .intern(&Format::Seq(Box::new(Format::unknown())))
+        .is_err());
+    assert!(interner.is_empty());
+}
@@ -0,0 +1,92 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{
+    merge_registries, ContainerConflict, Error, Registry, Samples, Tracer, TracerConfig,
+};
+
+mod first {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Line(pub Point, pub Point);
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Label(pub String);
+}
+
+mod second {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Circle {
+        pub center: Point,
+        pub radius: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Label(pub u64);
+}
+
+fn trace<T>() -> Registry
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<T>(&Samples::new()).unwrap();
+    tracer.registry().unwrap()
+}
+
+#[test]
+fn test_merge_registries() {
+    let mut registry = trace::<first::Line>();
+    let other = trace::<second::Circle>();
+    merge_registries(&mut registry, other.clone()).unwrap();
+    assert_eq!(
+        registry.keys().collect::<Vec<_>>(),
+        vec!["Circle", "Line", "Point"]
+    );
+    assert_eq!(registry["Circle"], other["Circle"]);
+
+    // Merging is idempotent.
+    let expected = registry.clone();
+    merge_registries(&mut registry, other).unwrap();
+    assert_eq!(registry, expected);
+}
+
+#[test]
+fn test_merge_registries_with_conflicts() {
+    let mut registry = trace::<first::Line>();
+    registry.extend(trace::<first::Label>());
+    let mut other = trace::<second::Circle>();
+    other.extend(trace::<second::Label>());
+    let expected = registry.clone();
+
+    let error = merge_registries(&mut registry, other.clone()).unwrap_err();
+    assert_eq!(
+        error,
+        Error::ConflictingContainers(vec![ContainerConflict {
+            name: "Label".into(),
+            left: expected["Label"].clone(),
+            right: other["Label"].clone(),
+        }])
+    );
+    assert_eq!(
+        error.to_string(),
+        "Conflicting formats detected for containers: Label"
+    );
+    assert_eq!(registry, expected);
+}
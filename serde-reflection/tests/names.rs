@@ -0,0 +1,128 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{
+    ContainerFormat, Format, Named, Registry, Samples, SourceNamed, Tracer, TracerConfig,
+    VariantFormat,
+};
+use serde_reflection_derive::SerdeReflectNames;
+
+#[derive(Serialize, Deserialize, SerdeReflectNames)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct Person {
+    first_name: String,
+    #[serde(rename = "surname")]
+    last_name: String,
+    age: u8,
+}
+
+#[derive(Serialize, Deserialize, SerdeReflectNames)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[allow(dead_code)]
+enum Event {
+    LoggedIn,
+    #[serde(rename_all = "kebab-case")]
+    PageViewed {
+        page_url: String,
+        r#type: u8,
+    },
+}
+
+fn clear_source_names(fields: &mut [Named<Format>]) {
+    for field in fields {
+        field.source_name = None;
+    }
+}
+
+fn path(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn test_struct_source_names() {
+    let names = Person::source_names();
+    assert_eq!(names.len(), 2);
+    assert_eq!(names[&path(&["Person", "firstName"])], "first_name");
+    assert_eq!(names[&path(&["Person", "surname"])], "last_name");
+}
+
+#[test]
+fn test_enum_source_names() {
+    let names = Event::source_names();
+    assert_eq!(names.len(), 3);
+    assert_eq!(names[&path(&["Event", "LOGGED_IN"])], "LoggedIn");
+    assert_eq!(names[&path(&["Event", "PAGE_VIEWED"])], "PageViewed");
+    assert_eq!(
+        names[&path(&["Event", "PAGE_VIEWED", "page-url"])],
+        "page_url"
+    );
+}
+
+#[test]
+fn test_source_names_are_recorded_by_the_tracer() {
+    let config = TracerConfig::default()
+        .source_names(Person::source_names())
+        .source_names(Event::source_names());
+    let mut tracer = Tracer::new(config);
+    let samples = Samples::new();
+    tracer.trace_type::<Person>(&samples).unwrap();
+    tracer.trace_type::<Event>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+
+    let field = |name: &str, value, source_name: Option<&str>| {
+        let mut field = Named::new(name, value);
+        field.source_name = source_name.map(String::from);
+        field
+    };
+    assert_eq!(
+        registry["Person"],
+        ContainerFormat::Struct(vec![
+            field("firstName", Format::Str, Some("first_name")),
+            field("surname", Format::Str, Some("last_name")),
+            field("age", Format::U8, None),
+        ])
+    );
+    let variants = match &registry["Event"] {
+        ContainerFormat::Enum(variants) => variants,
+        _ => panic!("unexpected format"),
+    };
+    assert_eq!(variants[&0].source_name.as_deref(), Some("LoggedIn"));
+    assert_eq!(
+        variants[&1].value,
+        VariantFormat::Struct(vec![
+            field("page-url", Format::Str, Some("page_url")),
+            field("type", Format::U8, None),
+        ])
+    );
+
+    // Source names are preserved in YAML.
+    let content = serde_yaml::to_string(&registry).unwrap();
+    assert!(content.contains("- firstName: STR\n      SOURCE_NAME: first_name\n"));
+    let registry2 = serde_yaml::from_str(&content).unwrap();
+    assert_eq!(registry, registry2);
+    // Binary formats keep the same layout as without source names.
+    let mut registry3 = registry.clone();
+    for format in registry3.values_mut() {
+        match format {
+            ContainerFormat::Struct(fields) => clear_source_names(fields),
+            ContainerFormat::Enum(variants) => {
+                for variant in variants.values_mut() {
+                    variant.source_name = None;
+                    if let VariantFormat::Struct(fields) = &mut variant.value {
+                        clear_source_names(fields);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    assert_eq!(
+        bincode::serialize(&registry).unwrap(),
+        bincode::serialize(&registry3).unwrap()
+    );
+    let registry4: Registry =
+        bincode::deserialize(&bincode::serialize(&registry).unwrap()).unwrap();
+    assert_eq!(registry3, registry4);
+}
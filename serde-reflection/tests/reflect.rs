@@ -0,0 +1,177 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(feature = "derive")]
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{
+    reflect_registry, ContainerFormat, Format, Named, Reflect, Registry, Samples, Tracer,
+    TracerConfig, VariantFormat,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[allow(dead_code)]
+struct UnitStruct;
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[allow(dead_code)]
+struct NewTypeStruct(u64);
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[allow(dead_code)]
+struct TupleStruct(u32, #[serde(skip)] u64, Option<String>);
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct Account {
+    owner_id: u64,
+    #[serde(rename = "tags")]
+    labels: BTreeSet<String>,
+    #[serde(skip)]
+    cache: Vec<u8>,
+    balances: BTreeMap<String, (i64, [u8; 4])>,
+    parent: Option<Box<Account>>,
+}
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[serde(rename = "Tree")]
+#[allow(dead_code)]
+enum RenamedTree<T> {
+    Leaf,
+    Node(T, Vec<RenamedTree<T>>),
+    #[serde(rename_all = "UPPERCASE")]
+    Labeled {
+        label: String,
+        tree: Box<RenamedTree<T>>,
+    },
+    Wrapped(Wrapper),
+}
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[serde(transparent)]
+#[allow(dead_code)]
+struct Wrapper {
+    inner: NewTypeStruct,
+}
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[allow(dead_code)]
+struct Root {
+    unit: UnitStruct,
+    tuple: TupleStruct,
+    account: Account,
+    tree: RenamedTree<u16>,
+}
+
+fn trace() -> Registry {
+    let samples = Samples::new();
+    // Sets are traced as sequences unless declared.
+    let config = TracerConfig::default().record_set::<BTreeSet<String>>();
+    let mut tracer = Tracer::new(config);
+    tracer.trace_type::<Root>(&samples).unwrap();
+    tracer.trace_type::<RenamedTree<u16>>(&samples).unwrap();
+    tracer.registry().unwrap()
+}
+
+#[test]
+fn test_reflected_registry_matches_tracing() {
+    let registry = reflect_registry::<Root>();
+    assert_eq!(registry, trace());
+    assert_eq!(
+        registry.keys().collect::<Vec<_>>(),
+        vec![
+            "Account",
+            "NewTypeStruct",
+            "Root",
+            "Tree",
+            "TupleStruct",
+            "UnitStruct"
+        ]
+    );
+}
+
+#[test]
+fn test_reflected_formats() {
+    assert_eq!(Root::format(), Format::TypeName("Root".into()));
+    assert_eq!(Wrapper::format(), Format::TypeName("NewTypeStruct".into()));
+    assert_eq!(
+        <Vec<(u8, [RenamedTree<u8>; 2])>>::format(),
+        Format::Seq(Box::new(Format::Tuple(vec![
+            Format::U8,
+            Format::TupleArray {
+                content: Box::new(Format::TypeName("Tree".into())),
+                size: 2,
+            },
+        ])))
+    );
+    assert_eq!(<[String; 0]>::format(), Format::Tuple(vec![]));
+    assert_eq!(
+        <std::collections::BTreeSet<u8>>::format(),
+        Format::Set(Box::new(Format::U8))
+    );
+}
+
+#[derive(Serialize, Deserialize, Reflect)]
+#[serde(tag = "type")]
+#[allow(dead_code)]
+enum Message {
+    Ping,
+    Request { id: u64 },
+}
+
+#[test]
+fn test_reflected_internally_tagged_enum() {
+    let mut registry = Registry::new();
+    Message::register(&mut registry);
+    UnitStruct::register(&mut registry);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(
+        registry["Message"],
+        ContainerFormat::InternallyTaggedEnum {
+            tag: "type".into(),
+            variants: vec![
+                (0, Named::new("Ping", VariantFormat::Unit)),
+                (
+                    1,
+                    Named::new(
+                        "Request",
+                        VariantFormat::Struct(vec![Named::new("id", Format::U64)])
+                    )
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    );
+}
+
+macro_rules! test_rename_rule {
+    ($test:ident, $rule:tt) => {
+        #[test]
+        fn $test() {
+            #[derive(Serialize, Deserialize, Reflect)]
+            #[serde(rename_all = $rule, rename_all_fields = $rule)]
+            #[allow(dead_code)]
+            enum Renamed {
+                FirstVariant { first_field: u8, _second_field: u8 },
+                Other,
+            }
+
+            let samples = Samples::new();
+            let mut tracer = Tracer::new(TracerConfig::default());
+            tracer.trace_type::<Renamed>(&samples).unwrap();
+            assert_eq!(reflect_registry::<Renamed>(), tracer.registry().unwrap());
+        }
+    };
+}
+
+test_rename_rule!(test_lowercase_rule, "lowercase");
+test_rename_rule!(test_uppercase_rule, "UPPERCASE");
+test_rename_rule!(test_pascal_case_rule, "PascalCase");
+test_rename_rule!(test_camel_case_rule, "camelCase");
+test_rename_rule!(test_snake_case_rule, "snake_case");
+test_rename_rule!(test_screaming_snake_case_rule, "SCREAMING_SNAKE_CASE");
+test_rename_rule!(test_kebab_case_rule, "kebab-case");
+test_rename_rule!(test_screaming_kebab_case_rule, "SCREAMING-KEBAB-CASE");
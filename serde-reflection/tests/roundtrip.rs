@@ -0,0 +1,88 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_reflection::{
+    assert_roundtrip, check_roundtrip, ContainerFormat, Format, Samples, Tracer, TracerConfig,
+};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize)]
+enum Shape {
+    Point,
+    Circle { radius: u32 },
+    Polygon(Vec<(i64, i64)>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Drawing {
+    name: Option<String>,
+    shapes: BTreeMap<String, Shape>,
+    color: [u8; 3],
+}
+
+#[test]
+fn test_roundtrip_of_derived_types() {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    tracer.trace_type::<Drawing>(&samples).unwrap();
+    tracer.trace_type::<Shape>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+
+    let mut shapes = BTreeMap::new();
+    shapes.insert("a".to_string(), Shape::Point);
+    shapes.insert("b".to_string(), Shape::Circle { radius: 3 });
+    shapes.insert("c".to_string(), Shape::Polygon(vec![(0, 0), (1, -1)]));
+    assert_roundtrip(
+        &registry,
+        &[
+            Drawing {
+                name: None,
+                shapes: BTreeMap::new(),
+                color: [0, 0, 0],
+            },
+            Drawing {
+                name: Some("test".to_string()),
+                shapes,
+                color: [255, 128, 0],
+            },
+        ],
+    );
+}
+
+/// Serializes as a `u32` but deserializes from a `u64`.
+struct Counter(u64);
+
+impl Serialize for Counter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("Counter", &(self.0 as u32))
+    }
+}
+
+impl<'de> Deserialize<'de> for Counter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Counter")]
+        struct Inner(u64);
+        Ok(Counter(Inner::deserialize(deserializer)?.0))
+    }
+}
+
+#[test]
+fn test_roundtrip_detects_mismatches() {
+    let mut registry = BTreeMap::new();
+    registry.insert(
+        "Counter".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::U64)),
+    );
+    // The serialized value does not match the declared format.
+    let error = check_roundtrip(&registry, &[Counter(1)]).unwrap_err();
+    assert!(error.to_string().contains("does not match format U64"));
+
+    // The serialized value matches the format but fails to deserialize.
+    registry.insert(
+        "Counter".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::U32)),
+    );
+    assert!(check_roundtrip(&registry, &[Counter(1)]).is_err());
+}
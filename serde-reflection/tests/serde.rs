@@ -3,10 +3,10 @@
 
 use serde::{de::IntoDeserializer, Deserialize, Serialize};
 use serde_reflection::{
-    ContainerFormat, Error, Format, FormatHolder, Named, Samples, Tracer, TracerConfig, Value,
-    VariantFormat,
+    ContainerFormat, Error, Format, FormatHolder, Named, Result, Samples, TraceJob, Tracer,
+    TracerConfig, Value, VariantCoverage, VariantFormat,
 };
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap};
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 enum E {
@@ -99,10 +99,7 @@ fn test_tracers() {
     );
     assert_eq!(
         variants.get(&3).unwrap().value,
-        VariantFormat::Struct(vec![Named {
-            name: "a".into(),
-            value: Format::U32
-        }])
+        VariantFormat::Struct(vec![Named::new("a", Format::U32)])
     );
     assert_eq!(
         variants.get(&4).unwrap().value,
@@ -220,26 +217,20 @@ fn test_trace_deserialization_with_custom_invariants() {
     let mut variants: BTreeMap<_, _> = BTreeMap::new();
     variants.insert(
         0,
-        Named {
-            name: "NickName".into(),
-            value: VariantFormat::NewType(Box::new(Format::TypeName("Name".into()))),
-        },
+        Named::new(
+            "NickName",
+            VariantFormat::NewType(Box::new(Format::TypeName("Name".into()))),
+        ),
     );
     variants.insert(
         1,
-        Named {
-            name: "FullName".into(),
-            value: VariantFormat::Struct(vec![
-                Named {
-                    name: "first".into(),
-                    value: Format::TypeName("Name".into()),
-                },
-                Named {
-                    name: "last".into(),
-                    value: Format::TypeName("Name".into()),
-                },
+        Named::new(
+            "FullName",
+            VariantFormat::Struct(vec![
+                Named::new("first", Format::TypeName("Name".into())),
+                Named::new("last", Format::TypeName("Name".into())),
             ]),
-        },
+        ),
     );
     assert_eq!(
         registry.get("Person").unwrap(),
@@ -247,6 +238,129 @@ fn test_trace_deserialization_with_custom_invariants() {
     );
 }
 
+#[test]
+fn test_trace_deserialization_with_sample_provider() {
+    let provider =
+        |name: &'static str, tracer: &mut Tracer, samples: &mut Samples| -> Result<bool> {
+            match name {
+                "Name" => {
+                    tracer.trace_value(samples, &Name("Alice".into()))?;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        };
+    let mut samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let (format, values) = tracer
+        .trace_type_with_provider::<Person, _>(&mut samples, &provider)
+        .unwrap();
+    assert_eq!(format, Format::TypeName("Person".into()));
+    let alice = Name("Alice".into());
+    assert_eq!(
+        values,
+        vec![
+            Person::NickName(alice.clone()),
+            Person::FullName {
+                first: alice.clone(),
+                last: alice,
+            }
+        ]
+    );
+    assert_eq!(samples.value("Name"), Some(&Value::Str("Alice".into())));
+
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.get("Name").unwrap(),
+        &ContainerFormat::NewTypeStruct(Box::new(Format::Str))
+    );
+    match registry.get("Person").unwrap() {
+        ContainerFormat::Enum(variants) => assert_eq!(variants.len(), 2),
+        _ => panic!("Person should be an enum"),
+    }
+
+    // Without a sample, the provider cannot help.
+    let mut samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let no_provider = |_: &'static str, _: &mut Tracer, _: &mut Samples| Ok(false);
+    assert_eq!(
+        tracer
+            .trace_type_with_provider::<Person, _>(&mut samples, &no_provider)
+            .unwrap_err(),
+        Error::Custom(format!(
+            "Failed to deserialize value: \"Invalid name {}\"",
+            ""
+        )),
+    );
+}
+
+mod third_party {
+    pub struct Span {
+        pub start: u32,
+        pub end: u32,
+    }
+}
+
+fn non_empty_end<'de, D>(deserializer: D) -> std::result::Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let end = u32::deserialize(deserializer)?;
+    if end == 0 {
+        return Err(serde::de::Error::custom("empty span"));
+    }
+    Ok(end)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "third_party::Span")]
+struct SpanDef {
+    start: u32,
+    #[serde(deserialize_with = "non_empty_end")]
+    end: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Token {
+    #[serde(with = "SpanDef")]
+    span: third_party::Span,
+}
+
+#[test]
+fn test_trace_deserialization_with_samples_by_name() {
+    let mut samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    assert!(tracer.trace_type_once::<Token>(&samples).is_err());
+
+    // `third_party::Span` does not implement `Serialize`: record a payload for `SpanDef`.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer
+        .trace_sample_by_name(&mut samples, "SpanDef", &(1u32, 2u32))
+        .unwrap();
+    let (_, token) = tracer.trace_type_once::<Token>(&samples).unwrap();
+    assert_eq!((token.span.start, token.span.end), (1, 2));
+
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.get("SpanDef").unwrap(),
+        &ContainerFormat::Struct(vec![
+            Named::new("start", Format::U32),
+            Named::new("end", Format::U32),
+        ])
+    );
+
+    // Payloads must match the shape of the container.
+    let mut samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer
+        .trace_sample_by_name(&mut samples, "SpanDef", &(1u32, 2u32, 3u32))
+        .unwrap();
+    assert!(matches!(
+        tracer.trace_type_once::<Token>(&samples),
+        Err(Error::Incompatible(..))
+    ));
+}
+
 mod foo {
     #[derive(super::Serialize)]
     pub struct A;
@@ -282,14 +396,69 @@ fn test_borrowed_slice() {
     // Slice was traced and serialized as a sequence.
     assert_eq!(value, Value::Seq(vec![Value::U8(1); 4]));
 
-    // Unfortunately, borrowed slices can only de-serialize as bytes.
+    // Borrowed slices de-serialize as bytes, which the recorded sample provides.
+    let (format, samples) = tracer.trace_type::<Borrowed>(&samples).unwrap();
+    assert_eq!(format, Format::TypeName("Borrowed".into()));
+    assert_eq!(samples, vec![Borrowed(&bytes)]);
+
+    let registry = tracer.registry().unwrap();
     assert_eq!(
-        tracer.trace_type::<Borrowed>(&samples),
-        Err(Error::UnexpectedDeserializationFormat(
-            "Borrowed",
-            ContainerFormat::NewTypeStruct(Box::new(Format::Seq(Box::new(Format::U8)))),
-            "bytes"
-        )),
+        registry.get("Borrowed").unwrap(),
+        &ContainerFormat::NewTypeStruct(Box::new(Format::Seq(Box::new(Format::U8))))
+    );
+}
+
+#[test]
+fn test_borrowed_types() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        data: &'a [u8],
+        #[serde(borrow)]
+        label: Cow<'a, str>,
+        #[serde(borrow)]
+        payload: Cow<'a, [u8]>,
+        digests: Vec<&'a [u8]>,
+    }
+
+    let config = || TracerConfig::default().record_samples_for_structs(true);
+    let value = Borrowed {
+        name: "a",
+        data: &[1, 2],
+        label: Cow::Borrowed("b"),
+        payload: Cow::Borrowed(&[3]),
+        digests: vec![&[4, 5]],
+    };
+
+    // By default, serializing slices as sequences conflicts with the bytes traced so far.
+    let mut tracer = Tracer::new(config());
+    tracer.trace_type::<Borrowed>(&Samples::new()).unwrap();
+    assert!(matches!(
+        tracer.trace_value(&mut Samples::new(), &value),
+        Err(Error::Incompatible(..))
+    ));
+
+    let mut tracer = Tracer::new(config().borrowed_bytes(true));
+    let samples = Samples::new();
+    tracer.trace_type::<Borrowed>(&samples).unwrap();
+
+    // Serializing slices as sequences is compatible with the formats traced so far.
+    let mut samples = Samples::new();
+    tracer.trace_value(&mut samples, &value).unwrap();
+    let (_, values) = tracer.trace_type::<Borrowed>(&samples).unwrap();
+    assert_eq!(values, vec![value]);
+
+    let registry = tracer.registry().unwrap();
+    let named = |name: &str, value| Named::new(name, value);
+    assert_eq!(
+        registry.get("Borrowed").unwrap(),
+        &ContainerFormat::Struct(vec![
+            named("name", Format::Str),
+            named("data", Format::Bytes),
+            named("label", Format::Str),
+            named("payload", Format::Bytes),
+            named("digests", Format::Seq(Box::new(Format::Bytes))),
+        ])
     );
 }
 
@@ -318,6 +487,41 @@ fn test_borrowed_bytes() {
     );
 }
 
+#[test]
+fn test_byte_buffers() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Buffers {
+        #[serde(with = "serde_bytes")]
+        a: Vec<u8>,
+        b: serde_bytes::ByteBuf,
+        c: bytes::Bytes,
+        d: Vec<u8>,
+    }
+
+    let mut samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let value = Buffers {
+        a: vec![1],
+        b: serde_bytes::ByteBuf::from(vec![2]),
+        c: bytes::Bytes::from_static(&[3]),
+        d: vec![4],
+    };
+    tracer.trace_value(&mut samples, &value).unwrap();
+    tracer.trace_type::<Buffers>(&samples).unwrap();
+
+    let registry = tracer.registry().unwrap();
+    let named = |name: &str, value| Named::new(name, value);
+    assert_eq!(
+        registry.get("Buffers").unwrap(),
+        &ContainerFormat::Struct(vec![
+            named("a", Format::Bytes),
+            named("b", Format::Bytes),
+            named("c", Format::Bytes),
+            named("d", Format::Seq(Box::new(Format::U8))),
+        ])
+    );
+}
+
 #[test]
 fn test_trace_deserialization_with_recursive_types() {
     #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -342,6 +546,60 @@ fn test_trace_deserialization_with_recursive_types() {
     assert_eq!(variants.get(&1).unwrap().name, "Cons");
 }
 
+#[test]
+fn test_trace_deserialization_with_unbounded_recursion() {
+    // The first variant is not a base case.
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    enum List {
+        Cons(u32, Box<List>),
+        Empty,
+    }
+
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    assert_eq!(
+        tracer.trace_type::<List>(&samples).unwrap_err(),
+        Error::RecursionLimit(vec!["List".into(), "List".into()]),
+    );
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Ping {
+        pong: Box<Pong>,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Pong {
+        ping: Box<Ping>,
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default().max_recursion_depth(10));
+    let error = tracer.trace_type::<Ping>(&samples).unwrap_err();
+    assert_eq!(
+        error,
+        Error::RecursionLimit(vec!["Ping".into(), "Pong".into(), "Ping".into()]),
+    );
+    assert_eq!(
+        error.to_string(),
+        "Recursion limit reached while tracing containers: Ping -> Pong -> Ping"
+    );
+
+    // Deep definitions that are not recursive need a higher limit.
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct A(B);
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct B(C);
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct C(u8);
+
+    let mut tracer = Tracer::new(TracerConfig::default().max_recursion_depth(2));
+    assert_eq!(
+        tracer.trace_type::<A>(&samples).unwrap_err(),
+        Error::RecursionLimit(vec!["A".into(), "B".into(), "C".into()]),
+    );
+    let mut tracer = Tracer::new(TracerConfig::default().max_recursion_depth(3));
+    tracer.trace_type::<A>(&samples).unwrap();
+}
+
 #[test]
 fn test_tracing_deserialization_for_lists() {
     #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -406,6 +664,111 @@ fn test_mixed_tracing_for_multiple_enums() {
     assert_eq!(variants.len(), 2);
 }
 
+#[test]
+fn test_parallel_tracing() {
+    #[derive(Serialize, Deserialize)]
+    enum Foo {
+        A,
+        B(Bar),
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum Bar {
+        C,
+        D(u32),
+        E { name: String },
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Baz {
+        bars: Vec<Bar>,
+        foo: Option<Foo>,
+    }
+
+    fn assert_send<T: Send>() {}
+    assert_send::<Tracer>();
+
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<Foo>(&samples).unwrap();
+    tracer.trace_type::<Bar>(&samples).unwrap();
+    tracer.trace_type::<Baz>(&samples).unwrap();
+    let expected = tracer.registry().unwrap();
+
+    let jobs: Vec<TraceJob> = vec![
+        Box::new(|tracer, samples| tracer.trace_type::<Foo>(samples).map(|_| ())),
+        Box::new(|tracer, samples| tracer.trace_type::<Bar>(samples).map(|_| ())),
+        Box::new(|tracer, samples| tracer.trace_type::<Baz>(samples).map(|_| ())),
+    ];
+    for threads in 1..=4 {
+        // Tracing `Foo` alone leaves `Bar` incomplete, until merged with the other tracers.
+        let mut tracer = Tracer::new(TracerConfig::default());
+        tracer
+            .trace_types_parallel(&samples, &jobs, threads)
+            .unwrap();
+        assert_eq!(tracer.registry().unwrap(), expected);
+    }
+
+    // The error of the first failing job is reported.
+    let jobs: Vec<TraceJob> = vec![
+        Box::new(|tracer, samples| tracer.trace_type::<Foo>(samples).map(|_| ())),
+        Box::new(|_, _| Err(Error::Custom("first".into()))),
+        Box::new(|_, _| Err(Error::Custom("second".into()))),
+    ];
+    let mut tracer = Tracer::new(TracerConfig::default());
+    assert_eq!(
+        tracer.trace_types_parallel(&samples, &jobs, 3),
+        Err(Error::Custom("first".into()))
+    );
+    assert!(tracer.registry().unwrap().is_empty());
+}
+
+#[test]
+fn test_variant_coverage() {
+    #[derive(Serialize, Deserialize)]
+    enum Foo {
+        A,
+        B(Bar),
+        C,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum Bar {
+        D,
+        E,
+    }
+
+    let mut samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+
+    // Serialization only reveals the variants of the values.
+    tracer.trace_value(&mut samples, &Foo::B(Bar::E)).unwrap();
+    let coverage = tracer.coverage();
+    assert_eq!(
+        coverage["Foo"].visited,
+        vec![(1, "B".to_string())].into_iter().collect()
+    );
+    assert_eq!(coverage["Foo"].unexplored, None);
+    assert!(!coverage["Foo"].is_complete());
+
+    // Deserialization reveals the list of variants.
+    tracer.trace_type_once::<Foo>(&samples).unwrap();
+    let coverage = tracer.coverage();
+    assert_eq!(coverage["Foo"].visited.len(), 2);
+    assert_eq!(
+        coverage["Foo"].unexplored,
+        Some(vec![(2, "C".to_string())].into_iter().collect())
+    );
+    assert!(!coverage["Foo"].is_complete());
+    assert_eq!(coverage["Bar"].unexplored, None);
+
+    tracer.trace_type::<Foo>(&samples).unwrap();
+    tracer.trace_type::<Bar>(&samples).unwrap();
+    let coverage = tracer.coverage();
+    assert_eq!(coverage.len(), 2);
+    assert!(coverage.values().all(VariantCoverage::is_complete));
+}
+
 #[test]
 fn test_value_recording_for_structs() {
     #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -437,3 +800,642 @@ fn test_value_recording_for_structs() {
     assert_eq!(tracer.trace_type_once::<S>(&samples).unwrap().1, S { a: 2 });
     assert_eq!(tracer.trace_type_once::<T>(&samples).unwrap().1, T(3, 4));
 }
+
+#[test]
+fn test_custom_formats() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct U24([u8; 3]);
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Packet {
+        length: U24,
+        payload: Vec<u8>,
+    }
+
+    let layout = Format::TupleArray {
+        content: Box::new(Format::U8),
+        size: 3,
+    };
+    let custom = Format::Custom {
+        name: "U24".into(),
+        layout: Box::new(layout.clone()),
+    };
+
+    let mut tracer = Tracer::new(TracerConfig::default().custom_format("U24", layout));
+    let samples = Samples::new();
+    tracer.trace_type::<Packet>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert!(!registry.contains_key("U24"));
+    assert_eq!(
+        registry.get("Packet").unwrap(),
+        &ContainerFormat::Struct(vec![
+            Named::new("length", custom.clone()),
+            Named::new("payload", Format::Seq(Box::new(Format::U8))),
+        ])
+    );
+
+    let mut tracer = Tracer::new(TracerConfig::default().custom_format("U24", custom.clone()));
+    let mut samples = Samples::new();
+    assert!(tracer.trace_value(&mut samples, &U24([1, 2, 3])).is_err());
+
+    let mut tracer = Tracer::new(TracerConfig::default().custom_format("U24", Format::U32));
+    let samples = Samples::new();
+    assert!(tracer.trace_type::<Packet>(&samples).is_err());
+}
+
+#[test]
+fn test_type_aliases() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct BlockHeight(u64);
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Block {
+        height: BlockHeight,
+        parents: Vec<BlockHeight>,
+    }
+
+    let alias = Format::Custom {
+        name: "BlockHeight".into(),
+        layout: Box::new(Format::U64),
+    };
+
+    let mut tracer = Tracer::new(TracerConfig::default().type_alias("BlockHeight"));
+    let samples = Samples::new();
+    tracer.trace_type::<Block>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert!(!registry.contains_key("BlockHeight"));
+    assert_eq!(
+        registry.get("Block").unwrap(),
+        &ContainerFormat::Struct(vec![
+            Named::new("height", alias.clone()),
+            Named::new("parents", Format::Seq(Box::new(alias.clone()))),
+        ])
+    );
+
+    let mut tracer = Tracer::new(TracerConfig::default().type_alias("BlockHeight"));
+    let mut samples = Samples::new();
+    let block = Block {
+        height: BlockHeight(3),
+        parents: vec![BlockHeight(2)],
+    };
+    tracer.trace_value(&mut samples, &block).unwrap();
+    tracer.trace_type::<Block>(&samples).unwrap();
+    assert_eq!(tracer.registry().unwrap(), registry);
+}
+
+#[test]
+fn test_time_formats() {
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Event {
+        time: SystemTime,
+        timeout: Option<Duration>,
+    }
+
+    let custom = |name: &str| Format::Custom {
+        name: name.into(),
+        layout: Box::new(Format::Tuple(vec![Format::U64, Format::U32])),
+    };
+    let expected = ContainerFormat::Struct(vec![
+        Named::new("time", custom("SystemTime")),
+        Named::new("timeout", Format::Option(Box::new(custom("Duration")))),
+    ]);
+
+    let mut tracer = Tracer::new(TracerConfig::default().time_formats(true));
+    let samples = Samples::new();
+    tracer.trace_type::<Event>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert_eq!(registry.len(), 1);
+    assert_eq!(registry.get("Event").unwrap(), &expected);
+
+    let mut tracer = Tracer::new(TracerConfig::default().time_formats(true));
+    let mut samples = Samples::new();
+    let event = Event {
+        time: SystemTime::UNIX_EPOCH + Duration::new(3, 4),
+        timeout: Some(Duration::from_millis(5)),
+    };
+    let (_, value) = tracer.trace_value(&mut samples, &event).unwrap();
+    assert_eq!(
+        value,
+        Value::Seq(vec![
+            Value::Seq(vec![Value::U64(3), Value::U32(4)]),
+            Value::Option(Some(Box::new(Value::Seq(vec![
+                Value::U64(0),
+                Value::U32(5_000_000)
+            ])))),
+        ])
+    );
+    assert_eq!(tracer.registry().unwrap(), registry);
+
+    // Without the option, time types are traced as regular structs.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    tracer.trace_type::<Event>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert!(registry.contains_key("Duration"));
+    assert!(registry.contains_key("SystemTime"));
+}
+
+#[test]
+fn test_std_formats() {
+    use std::marker::PhantomData;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Job {
+        path: PathBuf,
+        started: SystemTime,
+        timeout: Duration,
+        marker: PhantomData<u8>,
+    }
+
+    let config = TracerConfig::default()
+        .std_formats(true)
+        .std_format("Duration", Format::Tuple(vec![Format::U64, Format::U32]));
+    let mut tracer = Tracer::new(config);
+    let samples = Samples::new();
+    let (format, _) = tracer.trace_type_once::<Job>(&samples).unwrap();
+    assert_eq!(format, Format::TypeName("Job".into()));
+    let registry = tracer.registry().unwrap();
+    assert_eq!(registry.len(), 1);
+    let fields = match registry.get("Job").unwrap() {
+        ContainerFormat::Struct(fields) => fields
+            .iter()
+            .map(|field| field.value.clone())
+            .collect::<Vec<_>>(),
+        _ => panic!("Job should be a struct"),
+    };
+    assert_eq!(
+        fields,
+        vec![
+            Format::Str,
+            Format::Custom {
+                name: "SystemTime".into(),
+                layout: Box::new(Format::Tuple(vec![Format::U64, Format::U32])),
+            },
+            Format::Tuple(vec![Format::U64, Format::U32]),
+            Format::Unit,
+        ]
+    );
+
+    // Serialization agrees with deserialization.
+    let config = TracerConfig::default()
+        .std_formats(true)
+        .std_format("Duration", Format::Tuple(vec![Format::U64, Format::U32]));
+    let mut tracer = Tracer::new(config);
+    let mut samples = Samples::new();
+    let job = Job {
+        path: PathBuf::from("/tmp"),
+        started: SystemTime::UNIX_EPOCH,
+        timeout: Duration::from_secs(1),
+        marker: PhantomData,
+    };
+    tracer.trace_value(&mut samples, &job).unwrap();
+    assert_eq!(tracer.registry().unwrap(), registry);
+
+    // Options may be turned off again.
+    let config = TracerConfig::default()
+        .std_formats(true)
+        .time_formats(false);
+    let mut tracer = Tracer::new(config);
+    tracer.trace_type::<Job>(&Samples::new()).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert!(registry.contains_key("Duration"));
+    assert!(!registry.contains_key("PhantomData"));
+}
+
+/// A third-party type that validates its content, as `uuid::Uuid` would.
+mod keys {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use std::convert::TryInto;
+
+    #[derive(PartialEq, Eq, Debug, Clone)]
+    pub struct Key(pub [u8; 4]);
+
+    impl Serialize for Key {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Key {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct KeyVisitor;
+
+            impl<'de> de::Visitor<'de> for KeyVisitor {
+                type Value = Key;
+
+                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    formatter.write_str("4 bytes")
+                }
+
+                fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Key, E> {
+                    let bytes = bytes
+                        .try_into()
+                        .map_err(|_| E::invalid_length(bytes.len(), &self))?;
+                    Ok(Key(bytes))
+                }
+            }
+
+            deserializer.deserialize_bytes(KeyVisitor)
+        }
+    }
+}
+
+#[test]
+fn test_external_types() {
+    use keys::Key;
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Record {
+        key: Key,
+        backup: Option<Key>,
+        history: Vec<Key>,
+    }
+
+    let key_format = Format::Custom {
+        name: "Key".into(),
+        layout: Box::new(Format::Bytes),
+    };
+    let expected = ContainerFormat::Struct(vec![
+        Named::new("key", key_format.clone()),
+        Named::new("backup", Format::Option(Box::new(key_format.clone()))),
+        Named::new("history", Format::Seq(Box::new(key_format.clone()))),
+    ]);
+
+    // Types match by crate and name, regardless of their module.
+    let config = TracerConfig::default().map_external_type("serde::Key", Format::Bytes);
+    let mut tracer = Tracer::new(config);
+    let mut samples = Samples::new();
+    let record = Record {
+        key: Key([1, 2, 3, 4]),
+        backup: Some(Key([5, 6, 7, 8])),
+        history: vec![Key([0; 4])],
+    };
+    tracer.trace_value(&mut samples, &record).unwrap();
+    assert_eq!(tracer.registry().unwrap().get("Record").unwrap(), &expected);
+
+    // Serialized values are used as samples during deserialization.
+    let config = TracerConfig::default().map_external_type("serde::Key", Format::Bytes);
+    let mut tracer = Tracer::new(config);
+    let mut samples = Samples::new();
+    tracer.trace_value(&mut samples, &Key([0; 4])).unwrap();
+    let (format, values) = tracer.trace_type::<Record>(&samples).unwrap();
+    assert_eq!(format, Format::TypeName("Record".into()));
+    assert_eq!(values[0].key, Key([0; 4]));
+    assert_eq!(tracer.registry().unwrap().get("Record").unwrap(), &expected);
+
+    // Layouts must agree with the traced serialization.
+    let config = TracerConfig::default().map_external_type("serde::Key", Format::Str);
+    let mut tracer = Tracer::new(config);
+    let mut samples = Samples::new();
+    assert!(matches!(
+        tracer.trace_value(&mut samples, &record),
+        Err(Error::Incompatible(..))
+    ));
+}
+
+#[test]
+fn test_network_addresses() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Peer {
+        ip: IpAddr,
+        socket: SocketAddr,
+        v6: Ipv6Addr,
+    }
+
+    let octets = |size| Format::TupleArray {
+        content: Box::new(Format::U8),
+        size,
+    };
+    let variants = |v4: Format, v6: Format| {
+        let mut variants = BTreeMap::new();
+        variants.insert(0, Named::new("V4", VariantFormat::NewType(Box::new(v4))));
+        variants.insert(1, Named::new("V6", VariantFormat::NewType(Box::new(v6))));
+        ContainerFormat::Enum(variants)
+    };
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    tracer.trace_type::<Peer>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert_eq!(registry.len(), 3);
+    assert_eq!(
+        registry.get("IpAddr").unwrap(),
+        &variants(octets(4), octets(16))
+    );
+    assert_eq!(
+        registry.get("SocketAddr").unwrap(),
+        &variants(
+            Format::Tuple(vec![octets(4), Format::U16]),
+            Format::Tuple(vec![octets(16), Format::U16])
+        )
+    );
+    // Addresses of a known version are plain octets.
+    assert_eq!(
+        registry.get("Peer").unwrap(),
+        &ContainerFormat::Struct(vec![
+            Named::new("ip", Format::TypeName("IpAddr".into())),
+            Named::new("socket", Format::TypeName("SocketAddr".into())),
+            Named::new("v6", octets(16)),
+        ])
+    );
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let mut samples = Samples::new();
+    let peer = Peer {
+        ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+        socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 8080),
+        v6: Ipv6Addr::LOCALHOST,
+    };
+    let (_, value) = tracer.trace_value(&mut samples, &peer).unwrap();
+    let bytes = |octets: &[u8]| Value::Seq(octets.iter().map(|x| Value::U8(*x)).collect());
+    assert_eq!(
+        value,
+        Value::Seq(vec![
+            Value::Variant(0, Box::new(bytes(&[127, 0, 0, 1]))),
+            Value::Variant(
+                0,
+                Box::new(Value::Seq(vec![bytes(&[10, 0, 0, 2]), Value::U16(8080)]))
+            ),
+            bytes(&Ipv6Addr::LOCALHOST.octets()),
+        ])
+    );
+}
+
+#[test]
+fn test_type_hints_for_deserialize_any() {
+    #[allow(dead_code)]
+    #[derive(Deserialize, Debug)]
+    struct Event {
+        payload: serde_json::Value,
+        extra: Option<serde_json::Value>,
+        tags: BTreeMap<String, serde_json::Value>,
+    }
+
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    assert_eq!(
+        tracer.trace_type::<Event>(&samples).unwrap_err(),
+        Error::DeserializeAny(std::any::type_name::<serde_json::Value>())
+    );
+
+    let config = TracerConfig::default().record_type_as::<serde_json::Value>(Format::Str);
+    let mut tracer = Tracer::new(config);
+    let (_, values) = tracer.trace_type::<Event>(&samples).unwrap();
+    assert_eq!(values[0].payload, serde_json::Value::String(String::new()));
+    let registry = tracer.registry().unwrap();
+    let field = |name: &str, value| Named::new(name, value);
+    assert_eq!(
+        registry["Event"],
+        ContainerFormat::Struct(vec![
+            field("payload", Format::Str),
+            field("extra", Format::Option(Box::new(Format::Str))),
+            field(
+                "tags",
+                Format::Map {
+                    key: Box::new(Format::Str),
+                    value: Box::new(Format::Str),
+                }
+            ),
+        ])
+    );
+}
+
+#[test]
+fn test_record_set() {
+    use std::collections::{BTreeSet, HashSet};
+
+    #[derive(Serialize, Deserialize)]
+    struct Tags {
+        names: BTreeSet<String>,
+        ids: Option<HashSet<u32>>,
+        history: Vec<u32>,
+    }
+
+    let tags = Tags {
+        names: vec!["a".to_string()].into_iter().collect(),
+        ids: Some(vec![1].into_iter().collect()),
+        history: vec![1],
+    };
+    let expected = ContainerFormat::Struct(vec![
+        Named::new("names", Format::Set(Box::new(Format::Str))),
+        Named::new(
+            "ids",
+            Format::Option(Box::new(Format::Set(Box::new(Format::U32)))),
+        ),
+        Named::new("history", Format::Seq(Box::new(Format::U32))),
+    ]);
+    let config = || {
+        TracerConfig::default()
+            .record_set::<BTreeSet<String>>()
+            .record_set::<HashSet<u32>>()
+    };
+
+    // Serialization.
+    let mut tracer = Tracer::new(config());
+    tracer.trace_value(&mut Samples::new(), &tags).unwrap();
+    assert_eq!(tracer.registry().unwrap()["Tags"], expected);
+
+    // Deserialization.
+    let mut tracer = Tracer::new(config());
+    tracer.trace_type::<Tags>(&Samples::new()).unwrap();
+    assert_eq!(tracer.registry().unwrap()["Tags"], expected);
+
+    // Sets are sequences by default.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<Tags>(&Samples::new()).unwrap();
+    assert_eq!(
+        tracer.registry().unwrap()["Tags"],
+        ContainerFormat::Struct(vec![
+            Named::new("names", Format::Seq(Box::new(Format::Str))),
+            Named::new(
+                "ids",
+                Format::Option(Box::new(Format::Seq(Box::new(Format::U32)))),
+            ),
+            Named::new("history", Format::Seq(Box::new(Format::U32))),
+        ])
+    );
+}
+
+#[test]
+fn test_nonzero_integers() {
+    use std::num::{NonZeroI8, NonZeroU64};
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Account {
+        id: NonZeroU64,
+        delta: Option<NonZeroI8>,
+        count: u64,
+    }
+
+    let nonzero = |name: &str, layout| Format::Custom {
+        name: name.into(),
+        layout: Box::new(layout),
+    };
+    let expected = ContainerFormat::Struct(vec![
+        Named::new("id", nonzero("NonZeroU64", Format::U64)),
+        Named::new(
+            "delta",
+            Format::Option(Box::new(nonzero("NonZeroI8", Format::I8))),
+        ),
+        Named::new("count", Format::U64),
+    ]);
+
+    // Nonzero integers are traced as plain integers by default.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    let (_, values) = tracer.trace_type::<Account>(&samples).unwrap();
+    assert_eq!(values[0].id.get(), 1);
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.get("Account").unwrap(),
+        &ContainerFormat::Struct(vec![
+            Named::new("id", Format::U64),
+            Named::new("delta", Format::Option(Box::new(Format::I8))),
+            Named::new("count", Format::U64),
+        ])
+    );
+
+    let config = || TracerConfig::default().nonzero_integers(true);
+    let mut tracer = Tracer::new(config());
+    tracer.trace_type::<Account>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert_eq!(registry.get("Account").unwrap(), &expected);
+
+    // Serialization only sees plain integers. They are refined into nonzero integers when
+    // tracing deserialization next.
+    let account = Account {
+        id: NonZeroU64::new(3).unwrap(),
+        delta: None,
+        count: 0,
+    };
+    let mut tracer = Tracer::new(config());
+    let mut samples = Samples::new();
+    let (_, value) = tracer.trace_value(&mut samples, &account).unwrap();
+    assert_eq!(
+        value,
+        Value::Seq(vec![Value::U64(3), Value::Option(None), Value::U64(0)])
+    );
+    tracer.trace_type::<Account>(&samples).unwrap();
+    tracer.trace_value(&mut samples, &account).unwrap();
+    assert_eq!(tracer.registry().unwrap(), registry);
+}
+
+#[test]
+fn test_decimal_formats() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Amount(i64);
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Payment {
+        amount: Amount,
+        fee: Option<Amount>,
+    }
+
+    let decimal = Format::Decimal {
+        mantissa: Box::new(Format::I64),
+        scale: 2,
+    };
+    let expected = ContainerFormat::Struct(vec![
+        Named::new("amount", decimal.clone()),
+        Named::new("fee", Format::Option(Box::new(decimal))),
+    ]);
+    let config = || TracerConfig::default().decimal_format("Amount", 2);
+
+    let mut tracer = Tracer::new(config());
+    let mut samples = Samples::new();
+    let payment = Payment {
+        amount: Amount(12345),
+        fee: Some(Amount(5)),
+    };
+    let (_, value) = tracer.trace_value(&mut samples, &payment).unwrap();
+    assert_eq!(
+        value,
+        Value::Seq(vec![
+            Value::I64(12345),
+            Value::Option(Some(Box::new(Value::I64(5))))
+        ])
+    );
+    tracer.trace_type::<Payment>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert_eq!(registry.get("Payment").unwrap(), &expected);
+    assert!(!registry.contains_key("Amount"));
+
+    let mut tracer = Tracer::new(config());
+    tracer.trace_type::<Payment>(&Samples::new()).unwrap();
+    assert_eq!(tracer.registry().unwrap(), registry);
+
+    // The mantissa must be a signed integer.
+    #[derive(Serialize, Deserialize)]
+    struct Price(f64);
+
+    let mut tracer = Tracer::new(TracerConfig::default().decimal_format("Price", 2));
+    assert!(tracer.trace_type::<Price>(&Samples::new()).is_err());
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_trace_128_bit_and_arbitrary_precision_numbers() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Balance {
+        low: i128,
+        high: u128,
+        exact: serde_json::Number,
+        history: Vec<serde_json::Number>,
+    }
+
+    let exact: serde_json::Number =
+        serde_json::from_str("123456789012345678901234567890.125").unwrap();
+    let balance = Balance {
+        low: i128::MIN,
+        high: u128::MAX,
+        exact: exact.clone(),
+        history: vec![exact],
+    };
+    let digits = "123456789012345678901234567890.125".to_string();
+    let number_format = Format::Custom {
+        name: "Number".into(),
+        layout: Box::new(Format::Str),
+    };
+
+    let mut samples = Samples::new();
+    let config =
+        || TracerConfig::default().record_type_as::<serde_json::Number>(number_format.clone());
+    let mut tracer = Tracer::new(config().record_samples_for_structs(true));
+    let (_, value) = tracer.trace_value(&mut samples, &balance).unwrap();
+    assert_eq!(
+        value,
+        Value::Seq(vec![
+            Value::I128(i128::MIN),
+            Value::U128(u128::MAX),
+            Value::Number(digits.clone()),
+            Value::Seq(vec![Value::Number(digits)]),
+        ])
+    );
+    // Values are preserved without loss of precision.
+    assert_eq!(
+        balance,
+        Balance::deserialize(value.into_deserializer()).unwrap()
+    );
+
+    let (_, values) = tracer.trace_type::<Balance>(&samples).unwrap();
+    assert_eq!(values, vec![balance]);
+    let registry = tracer.registry().unwrap();
+    let expected = ContainerFormat::Struct(vec![
+        Named::new("low", Format::I128),
+        Named::new("high", Format::U128),
+        Named::new("exact", number_format.clone()),
+        Named::new("history", Format::Seq(Box::new(number_format.clone()))),
+    ]);
+    assert_eq!(registry.get("Balance").unwrap(), &expected);
+    assert_eq!(registry.len(), 1);
+    serde_reflection::check_roundtrip(&registry, &values).unwrap();
+
+    // Deserialization alone finds the same formats.
+    let mut tracer = Tracer::new(config());
+    tracer.trace_type::<Balance>(&Samples::new()).unwrap();
+    assert_eq!(tracer.registry().unwrap(), registry);
+}
@@ -0,0 +1,151 @@
+// Copyright (c) Facebook, Inc. and its affiliates
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use serde_reflection::{
+    assert_roundtrip, ContainerFormat, Format, Named, Samples, Skipping, Tracer, TracerConfig,
+    VariantFormat,
+};
+use serde_reflection_derive::SerdeReflectSkipped;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, SerdeReflectSkipped, Default, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    user_name: String,
+    #[serde(skip)]
+    cached_token: Option<Vec<u8>>,
+    expires_at: u64,
+    #[serde(skip)]
+    hits: BTreeMap<String, (u32, [u8; 4])>,
+}
+
+#[derive(Serialize, Deserialize, SerdeReflectSkipped)]
+#[allow(dead_code)]
+enum Message {
+    Ping,
+    Text {
+        #[serde(skip_deserializing)]
+        draft: Box<Session>,
+        body: String,
+    },
+}
+
+fn path(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn skipped(name: &str, format: Format) -> Named<Format> {
+    Named::new(name, Format::Skipped(Box::new(format)))
+}
+
+fn field(name: &str, format: Format) -> Named<Format> {
+    Named::new(name, format)
+}
+
+#[test]
+fn test_skipped_fields() {
+    let fields = Session::skipped_fields();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(
+        fields[&path(&["Session", "cachedToken"])],
+        (
+            1,
+            Format::Option(Box::new(Format::Seq(Box::new(Format::U8))))
+        )
+    );
+    assert_eq!(
+        fields[&path(&["Session", "hits"])],
+        (
+            3,
+            Format::Map {
+                key: Box::new(Format::Str),
+                value: Box::new(Format::Tuple(vec![
+                    Format::U32,
+                    Format::TupleArray {
+                        content: Box::new(Format::U8),
+                        size: 4,
+                    },
+                ])),
+            }
+        )
+    );
+
+    let fields = Message::skipped_fields();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(
+        fields[&path(&["Message", "Text", "draft"])],
+        (0, Format::TypeName("Session".into()))
+    );
+}
+
+#[test]
+fn test_skipped_fields_are_recorded_by_the_tracer() {
+    let config = TracerConfig::default()
+        .skipped_fields(Session::skipped_fields())
+        .skipped_fields(Message::skipped_fields());
+    let mut tracer = Tracer::new(config);
+    let samples = Samples::new();
+    tracer.trace_type::<Session>(&samples).unwrap();
+    tracer.trace_type::<Message>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+
+    assert_eq!(
+        registry["Session"],
+        ContainerFormat::Struct(vec![
+            field("userName", Format::Str),
+            skipped(
+                "cachedToken",
+                Format::Option(Box::new(Format::Seq(Box::new(Format::U8))))
+            ),
+            field("expiresAt", Format::U64),
+            skipped(
+                "hits",
+                Format::Map {
+                    key: Box::new(Format::Str),
+                    value: Box::new(Format::Tuple(vec![
+                        Format::U32,
+                        Format::TupleArray {
+                            content: Box::new(Format::U8),
+                            size: 4,
+                        },
+                    ])),
+                }
+            ),
+        ])
+    );
+    let variants = match &registry["Message"] {
+        ContainerFormat::Enum(variants) => variants,
+        _ => panic!("expected an enum"),
+    };
+    assert_eq!(
+        variants[&1].value,
+        VariantFormat::Struct(vec![
+            skipped("draft", Format::TypeName("Session".into())),
+            field("body", Format::Str),
+        ])
+    );
+
+    // Skipped fields do not take part in the wire format.
+    let session = Session {
+        user_name: "alice".into(),
+        cached_token: None,
+        expires_at: 3600,
+        hits: BTreeMap::new(),
+    };
+    assert_roundtrip(&registry, &[session]);
+}
+
+#[test]
+fn test_skipped_fields_are_not_recorded_by_default() {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type::<Session>(&Samples::new()).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry["Session"],
+        ContainerFormat::Struct(vec![
+            field("userName", Format::Str),
+            field("expiresAt", Format::U64),
+        ])
+    );
+}